@@ -0,0 +1,303 @@
+//! HTTP 管理接口
+//!
+//! 与 WebSocket 服务分开监听，默认只绑定本机回环地址，
+//! 所有请求需携带 `Authorization: Bearer <token>`：
+//! - `GET /rooms`: 房间列表（客户端数、最近活动时间、文档大小估算）
+//! - `GET /rooms/{id}/clients`: 房间内的连接与 presence 摘要
+//! - `POST /rooms/{id}/disconnect/{client}`: 强制断开某个连接
+//! - `POST /rooms/{id}/evict`: 断开所有连接并走房间下线流程（保存数据）
+//! - `GET /metrics`: Prometheus 文本格式的房间与连接指标
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use warp::http::StatusCode;
+use warp::{Filter, Rejection, Reply};
+use yrs::{ReadTxn, StateVector, Transact};
+
+use crate::connections::unix_secs;
+use crate::ws_server::{CollaborationServer, RoomNotFoundError};
+
+/// 管理接口配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminConfig {
+    /// 监听地址，默认为 `127.0.0.1`
+    #[serde(default = "default_bind")]
+    pub bind: IpAddr,
+    /// 监听端口，需与 WebSocket 端口不同
+    pub port: u16,
+    /// 访问令牌，不能为空
+    pub token: String,
+}
+
+impl AdminConfig {
+    /// 监听本机回环地址的管理接口配置
+    pub fn new(
+        port: u16,
+        token: impl Into<String>,
+    ) -> Self {
+        Self { bind: default_bind(), port, token: token.into() }
+    }
+}
+
+fn default_bind() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::LOCALHOST)
+}
+
+/// 按字节比较令牌，耗时与首个不同字节的位置无关
+fn token_matches(
+    given: &[u8],
+    expected: &[u8],
+) -> bool {
+    given.len() == expected.len()
+        && given.iter().zip(expected).fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// 房间概要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminRoom {
+    pub id: String,
+    pub client_count: usize,
+    /// 最近一次连接或断开的时间（Unix 秒），没有经过 WebSocket 连接的房间为空
+    pub last_activity: Option<u64>,
+    /// 编码后的文档状态大小（字节）
+    pub doc_size: usize,
+}
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+/// 管理接口路由
+///
+/// 令牌为空时拒绝所有请求
+pub fn routes(
+    server: CollaborationServer,
+    config: AdminConfig,
+) -> impl Filter<Extract = impl Reply, Error = std::convert::Infallible>
++ Clone
++ Send
++ Sync
++ 'static {
+    let token: Arc<str> = config.token.into();
+    let auth = warp::header::optional::<String>("authorization")
+        .and_then(move |header: Option<String>| {
+            let token = token.clone();
+            async move {
+                let bearer =
+                    header.as_deref().and_then(|h| h.strip_prefix("Bearer "));
+                if !token.is_empty()
+                    && bearer.is_some_and(|bearer| {
+                        token_matches(bearer.as_bytes(), token.as_bytes())
+                    })
+                {
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(Unauthorized))
+                }
+            }
+        })
+        .untuple_one();
+    let with_server = warp::any().map(move || server.clone());
+
+    let rooms = warp::path!("rooms")
+        .and(warp::get())
+        .and(with_server.clone())
+        .and_then(rooms_handler);
+    let clients = warp::path!("rooms" / String / "clients")
+        .and(warp::get())
+        .and(with_server.clone())
+        .and_then(clients_handler);
+    let disconnect = warp::path!("rooms" / String / "disconnect" / u64)
+        .and(warp::post())
+        .and(with_server.clone())
+        .and_then(disconnect_handler);
+    let evict = warp::path!("rooms" / String / "evict")
+        .and(warp::post())
+        .and(with_server.clone())
+        .and_then(evict_handler);
+    let metrics = warp::path!("metrics")
+        .and(warp::get())
+        .and(with_server)
+        .and_then(metrics_handler);
+
+    auth.and(rooms.or(clients).or(disconnect).or(evict).or(metrics))
+        .recover(handle_rejection)
+}
+
+async fn handle_rejection(
+    err: Rejection
+) -> Result<warp::reply::Response, std::convert::Infallible> {
+    if err.find::<Unauthorized>().is_some() {
+        let body = json!({
+            "error": "UNAUTHORIZED",
+            "message": "缺少或错误的访问令牌",
+            "code": 401
+        });
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&body),
+            StatusCode::UNAUTHORIZED,
+        )
+        .into_response());
+    }
+    CollaborationServer::handle_rejection(err)
+        .await
+        .map(|reply| reply.into_response())
+}
+
+async fn room_summary(
+    server: &CollaborationServer,
+    room_id: &str,
+) -> Option<AdminRoom> {
+    let awareness_ref =
+        server.sync_service().yrs_manager().get_awareness_ref(room_id)?;
+    let doc_size = {
+        let awareness = awareness_ref.read().await;
+        let txn = awareness.doc().transact();
+        txn.encode_state_as_update_v1(&StateVector::default()).len()
+    };
+    let connections = server.connections();
+    Some(AdminRoom {
+        id: room_id.to_string(),
+        client_count: connections.client_count(room_id),
+        last_activity: connections.last_activity(room_id).map(unix_secs),
+        doc_size,
+    })
+}
+
+async fn rooms_handler(
+    server: CollaborationServer
+) -> Result<impl Reply, Rejection> {
+    let mut room_ids = server.get_active_rooms();
+    room_ids.sort();
+    let mut rooms = Vec::new();
+    for room_id in room_ids {
+        if let Some(room) = room_summary(&server, &room_id).await {
+            rooms.push(room);
+        }
+    }
+    Ok(warp::reply::json(&rooms))
+}
+
+async fn clients_handler(
+    room_id: String,
+    server: CollaborationServer,
+) -> Result<impl Reply, Rejection> {
+    let Some(awareness_ref) =
+        server.sync_service().yrs_manager().get_awareness_ref(&room_id)
+    else {
+        return Err(warp::reject::custom(RoomNotFoundError::new(room_id)));
+    };
+    // presence 按 awareness 客户端 id 汇总，与连接 id 无关
+    let presence: Vec<serde_json::Value> = {
+        let awareness = awareness_ref.read().await;
+        let mut states: Vec<_> = awareness
+            .clients()
+            .iter()
+            .filter_map(|(id, state)| {
+                let value: serde_json::Value =
+                    serde_json::from_str(state).ok()?;
+                let presence = value.get("presence")?.clone();
+                Some((*id, presence))
+            })
+            .collect();
+        states.sort_by_key(|(id, _)| *id);
+        states
+            .into_iter()
+            .map(|(id, presence)| json!({ "client_id": id, "presence": presence }))
+            .collect()
+    };
+    Ok(warp::reply::json(&json!({
+        "room_id": room_id,
+        "clients": server.connections().clients(&room_id),
+        "presence": presence,
+    })))
+}
+
+async fn disconnect_handler(
+    room_id: String,
+    client_id: u64,
+    server: CollaborationServer,
+) -> Result<warp::reply::Response, Rejection> {
+    if server.connections().disconnect(&room_id, client_id) {
+        tracing::info!(
+            "🔌 管理接口断开房间 {} 的客户端 {}",
+            room_id,
+            client_id
+        );
+        let body = json!({ "room_id": room_id, "client_id": client_id });
+        return Ok(warp::reply::json(&body).into_response());
+    }
+    let body = json!({
+        "error": "CLIENT_NOT_FOUND",
+        "message": format!("房间 '{}' 中不存在客户端 {}", room_id, client_id),
+        "code": 404
+    });
+    Ok(warp::reply::with_status(
+        warp::reply::json(&body),
+        StatusCode::NOT_FOUND,
+    )
+    .into_response())
+}
+
+async fn evict_handler(
+    room_id: String,
+    server: CollaborationServer,
+) -> Result<warp::reply::Response, Rejection> {
+    if !server.sync_service().yrs_manager().room_exists(&room_id) {
+        return Err(warp::reject::custom(RoomNotFoundError::new(room_id)));
+    }
+    let disconnected = server.connections().disconnect_room(&room_id);
+    match server.offline_room(&room_id, true).await {
+        Ok(evicted) => {
+            let body = json!({
+                "room_id": room_id,
+                "evicted": evicted,
+                "disconnected": disconnected,
+            });
+            Ok(warp::reply::json(&body).into_response())
+        },
+        Err(e) => {
+            let body = json!({
+                "error": "EVICT_FAILED",
+                "message": e.to_string(),
+                "code": 500
+            });
+            Ok(warp::reply::with_status(
+                warp::reply::json(&body),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .into_response())
+        },
+    }
+}
+
+async fn metrics_handler(
+    server: CollaborationServer
+) -> Result<impl Reply, Rejection> {
+    let mut room_ids = server.get_active_rooms();
+    room_ids.sort();
+    let connections = server.connections();
+    let mut body = String::new();
+    body.push_str("# HELP mf_collab_rooms 活跃房间数\n");
+    body.push_str("# TYPE mf_collab_rooms gauge\n");
+    body.push_str(&format!("mf_collab_rooms {}\n", room_ids.len()));
+    body.push_str("# HELP mf_collab_clients 房间内的 WebSocket 连接数\n");
+    body.push_str("# TYPE mf_collab_clients gauge\n");
+    for room_id in &room_ids {
+        body.push_str(&format!(
+            "mf_collab_clients{{room=\"{}\"}} {}\n",
+            room_id.replace('\\', "\\\\").replace('"', "\\\""),
+            connections.client_count(room_id)
+        ));
+    }
+    Ok(warp::reply::with_header(
+        body,
+        "content-type",
+        "text/plain; version=0.0.4",
+    ))
+}
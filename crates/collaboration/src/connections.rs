@@ -0,0 +1,178 @@
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+/// 客户端连接信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientInfo {
+    /// 服务端分配的连接 id
+    pub client_id: u64,
+    /// 客户端标识（当前为远程地址）
+    pub identity: String,
+    /// 连接建立时间（Unix 秒）
+    pub connected_since: u64,
+}
+
+#[derive(Debug)]
+struct ClientEntry {
+    info: ClientInfo,
+    disconnect: Arc<Notify>,
+}
+
+#[derive(Debug)]
+struct RoomConnections {
+    clients: HashMap<u64, ClientEntry>,
+    last_activity: SystemTime,
+}
+
+/// WebSocket 连接注册表
+///
+/// 记录每个房间当前的客户端连接，供管理接口查询或强制断开
+#[derive(Debug, Default)]
+pub struct ConnectionRegistry {
+    rooms: DashMap<String, RoomConnections>,
+    next_id: AtomicU64,
+}
+
+impl ConnectionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记新连接，返回的守卫被丢弃时自动注销
+    pub fn register(
+        self: &Arc<Self>,
+        room_id: &str,
+        identity: impl Into<String>,
+    ) -> ConnectionGuard {
+        let client_id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let now = SystemTime::now();
+        let disconnect = Arc::new(Notify::new());
+        let entry = ClientEntry {
+            info: ClientInfo {
+                client_id,
+                identity: identity.into(),
+                connected_since: unix_secs(now),
+            },
+            disconnect: disconnect.clone(),
+        };
+        let mut room =
+            self.rooms.entry(room_id.to_string()).or_insert_with(|| {
+                RoomConnections { clients: HashMap::new(), last_activity: now }
+            });
+        room.clients.insert(client_id, entry);
+        room.last_activity = now;
+        ConnectionGuard {
+            registry: self.clone(),
+            room_id: room_id.to_string(),
+            client_id,
+            disconnect,
+        }
+    }
+
+    /// 房间内的客户端，按连接先后排序
+    pub fn clients(
+        &self,
+        room_id: &str,
+    ) -> Vec<ClientInfo> {
+        let Some(room) = self.rooms.get(room_id) else {
+            return Vec::new();
+        };
+        let mut clients: Vec<ClientInfo> =
+            room.clients.values().map(|entry| entry.info.clone()).collect();
+        clients.sort_by_key(|info| info.client_id);
+        clients
+    }
+
+    pub fn client_count(
+        &self,
+        room_id: &str,
+    ) -> usize {
+        self.rooms.get(room_id).map_or(0, |room| room.clients.len())
+    }
+
+    /// 房间最近一次连接或断开的时间
+    pub fn last_activity(
+        &self,
+        room_id: &str,
+    ) -> Option<SystemTime> {
+        self.rooms.get(room_id).map(|room| room.last_activity)
+    }
+
+    /// 通知指定客户端断开，客户端不存在时返回 false
+    pub fn disconnect(
+        &self,
+        room_id: &str,
+        client_id: u64,
+    ) -> bool {
+        let Some(room) = self.rooms.get(room_id) else {
+            return false;
+        };
+        match room.clients.get(&client_id) {
+            Some(entry) => {
+                entry.disconnect.notify_one();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// 通知房间内所有客户端断开，返回通知的数量
+    pub fn disconnect_room(
+        &self,
+        room_id: &str,
+    ) -> usize {
+        let Some(room) = self.rooms.get(room_id) else {
+            return 0;
+        };
+        for entry in room.clients.values() {
+            entry.disconnect.notify_one();
+        }
+        room.clients.len()
+    }
+
+    fn unregister(
+        &self,
+        room_id: &str,
+        client_id: u64,
+    ) {
+        if let Some(mut room) = self.rooms.get_mut(room_id) {
+            room.clients.remove(&client_id);
+            room.last_activity = SystemTime::now();
+        }
+    }
+}
+
+/// 已登记的连接，丢弃时从注册表注销
+#[derive(Debug)]
+pub struct ConnectionGuard {
+    registry: Arc<ConnectionRegistry>,
+    room_id: String,
+    client_id: u64,
+    disconnect: Arc<Notify>,
+}
+
+impl ConnectionGuard {
+    pub fn client_id(&self) -> u64 {
+        self.client_id
+    }
+
+    /// 等待管理接口要求断开该连接
+    pub async fn disconnected(&self) {
+        self.disconnect.notified().await
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.registry.unregister(&self.room_id, self.client_id);
+    }
+}
+
+pub(crate) fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
@@ -1,11 +1,15 @@
+pub mod admin;
+pub mod connections;
 pub mod error;
 pub mod sync_service;
 pub mod types;
 pub mod ws_server;
 pub mod yrs_manager;
 
-pub use yrs_manager::YrsManager;
+pub use yrs_manager::{HistoryConfig, YrsManager};
 pub use ws_server::CollaborationServer;
+pub use admin::AdminConfig;
+pub use connections::{ClientInfo, ConnectionRegistry};
 pub use sync_service::{SyncService, RoomStatus, RoomInfo};
 pub use types::*;
 pub use error::*;
@@ -1,12 +1,15 @@
 use std::sync::Arc;
 use crate::{YrsManager, SyncService};
+use crate::admin::AdminConfig;
+use crate::connections::ConnectionRegistry;
 use crate::sync_service::{RoomInfo, RoomStatus};
 use warp::ws::{WebSocket, Ws};
 use warp::{Filter, Rejection, Reply};
 use yrs_warp::broadcast::BroadcastGroup;
 use yrs_warp::ws::{WarpSink, WarpStream};
 use tokio::sync::Mutex;
-use futures_util::StreamExt;
+use tokio::task::JoinHandle;
+use futures_util::{SinkExt, StreamExt};
 use serde_json::json;
 
 /// 自定义错误类型用于房间不存在的情况
@@ -32,6 +35,9 @@ impl RoomNotFoundError {
 pub struct CollaborationServer {
     yrs_manager: Arc<YrsManager>,
     sync_service: Arc<SyncService>,
+    connections: Arc<ConnectionRegistry>,
+    admin: Option<AdminConfig>,
+    presence_pruner: Arc<std::sync::Mutex<Option<JoinHandle<()>>>>,
     port: u16,
 }
 
@@ -41,7 +47,7 @@ impl CollaborationServer {
         port: u16,
     ) -> Self {
         let sync_service = Arc::new(SyncService::new(yrs_manager.clone()));
-        Self { yrs_manager, sync_service, port }
+        Self::with_sync_service(yrs_manager, sync_service, port)
     }
 
     /// 使用现有的 SyncService 创建服务器
@@ -50,7 +56,29 @@ impl CollaborationServer {
         sync_service: Arc<SyncService>,
         port: u16,
     ) -> Self {
-        Self { yrs_manager, sync_service, port }
+        Self {
+            yrs_manager,
+            sync_service,
+            connections: Arc::new(ConnectionRegistry::new()),
+            admin: None,
+            presence_pruner: Arc::default(),
+            port,
+        }
+    }
+
+    /// 启用 HTTP 管理接口，在独立端口上与 WebSocket 服务一同启动
+    pub fn with_admin(
+        mut self,
+        config: AdminConfig,
+    ) -> Self {
+        self.admin = Some(config);
+        self
+    }
+
+    fn presence_pruner(
+        &self
+    ) -> std::sync::MutexGuard<'_, Option<JoinHandle<()>>> {
+        self.presence_pruner.lock().unwrap_or_else(|e| e.into_inner())
     }
 
     /// 自定义错误处理器
@@ -301,6 +329,9 @@ impl CollaborationServer {
         save_all_data: bool,
     ) -> crate::Result<()> {
         tracing::info!("🔴 开始服务器关闭流程");
+        if let Some(pruner) = self.presence_pruner().take() {
+            pruner.abort();
+        }
 
         let all_rooms = self.get_active_rooms();
         if all_rooms.is_empty() {
@@ -329,7 +360,78 @@ impl CollaborationServer {
     }
 
     /// 启动 WebSocket 服务器
-    pub async fn start(self) {
+    ///
+    /// 监听地址无法绑定，或启用了令牌为空的管理接口时返回错误
+    pub async fn start(self) -> crate::Result<()> {
+        if let Some(config) = self.admin.clone() {
+            if config.token.is_empty() {
+                return Err(
+                    anyhow::anyhow!("管理接口的访问令牌不能为空").into()
+                );
+            }
+            let admin_addr = (config.bind, config.port);
+            let admin_routes = crate::admin::routes(self.clone(), config);
+            let (admin_addr, admin_server) = warp::serve(admin_routes)
+                .try_bind_ephemeral(admin_addr)
+                .map_err(|e| {
+                    anyhow::anyhow!("管理接口监听 {:?} 失败: {e}", admin_addr)
+                })?;
+            tracing::info!("🛠️ 管理接口: http://{}", admin_addr);
+            tokio::spawn(admin_server);
+        }
+        let routes = self.routes();
+
+        let addr = ([0, 0, 0, 0], self.port);
+        tracing::info!(
+            "🌐 协作服务器启动于 http://{}:{}",
+            addr.0.iter().map(|&o| o.to_string()).collect::<Vec<_>>().join("."),
+            addr.1
+        );
+        tracing::info!(
+            "📡 WebSocket: ws://{}:{}/collaboration/{{room_id}}",
+            addr.0.iter().map(|&o| o.to_string()).collect::<Vec<_>>().join("."),
+            addr.1
+        );
+        tracing::info!(
+            "🔍 房间检查: http://{}:{}/collaboration/room-check/{{room_id}}",
+            addr.0.iter().map(|&o| o.to_string()).collect::<Vec<_>>().join("."),
+            addr.1
+        );
+        tracing::info!(
+            "💚 健康检查: http://{}:{}/health",
+            addr.0.iter().map(|&o| o.to_string()).collect::<Vec<_>>().join("."),
+            addr.1
+        );
+        tracing::info!(
+            "📊 房间状态: http://{}:{}/collaboration/rooms/{{room_id}}/status",
+            addr.0.iter().map(|&o| o.to_string()).collect::<Vec<_>>().join("."),
+            addr.1
+        );
+
+        let (_, server) =
+            warp::serve(routes).try_bind_ephemeral(addr).map_err(|e| {
+                anyhow::anyhow!("协作服务器监听端口 {} 失败: {e}", addr.1)
+            })?;
+        // 周期清理断线客户端遗留的 presence，关闭服务器时停止
+        let pruner = self.yrs_manager.spawn_presence_pruner(
+            std::time::Duration::from_secs(10),
+            crate::yrs_manager::DEFAULT_PRESENCE_TIMEOUT,
+        );
+        if let Some(previous) = self.presence_pruner().replace(pruner) {
+            previous.abort();
+        }
+        server.await;
+        Ok(())
+    }
+
+    /// WebSocket 与公开 HTTP 路由
+    pub fn routes(
+        &self
+    ) -> impl Filter<Extract = impl Reply, Error = Rejection>
+    + Clone
+    + Send
+    + Sync
+    + 'static {
         let server = self.clone(); // 克隆 self 以移动到过滤器
 
         // WebSocket 路由（带错误处理）
@@ -367,7 +469,7 @@ impl CollaborationServer {
             .and_then(Self::room_status_handler);
 
         // 合并所有路由并添加全局错误处理
-        let routes = ws_route
+        ws_route
             .or(room_check_route)
             .or(health_route)
             .or(room_status_route)
@@ -379,36 +481,7 @@ impl CollaborationServer {
                     .allow_methods(vec![
                         "GET", "POST", "PUT", "DELETE", "HEAD", "OPTIONS",
                     ]),
-            );
-
-        let addr = ([0, 0, 0, 0], self.port);
-        tracing::info!(
-            "🌐 协作服务器启动于 http://{}:{}",
-            addr.0.iter().map(|&o| o.to_string()).collect::<Vec<_>>().join("."),
-            addr.1
-        );
-        tracing::info!(
-            "📡 WebSocket: ws://{}:{}/collaboration/{{room_id}}",
-            addr.0.iter().map(|&o| o.to_string()).collect::<Vec<_>>().join("."),
-            addr.1
-        );
-        tracing::info!(
-            "🔍 房间检查: http://{}:{}/collaboration/room-check/{{room_id}}",
-            addr.0.iter().map(|&o| o.to_string()).collect::<Vec<_>>().join("."),
-            addr.1
-        );
-        tracing::info!(
-            "💚 健康检查: http://{}:{}/health",
-            addr.0.iter().map(|&o| o.to_string()).collect::<Vec<_>>().join("."),
-            addr.1
-        );
-        tracing::info!(
-            "📊 房间状态: http://{}:{}/collaboration/rooms/{{room_id}}/status",
-            addr.0.iter().map(|&o| o.to_string()).collect::<Vec<_>>().join("."),
-            addr.1
-        );
-
-        warp::serve(routes).run(addr).await;
+            )
     }
 
     /// WebSocket connection handler with room initialization.
@@ -421,6 +494,7 @@ impl CollaborationServer {
         let yrs_manager = server.yrs_manager.clone();
         // 获取已存在的 awareness（不创建新的）
         let awareness_ref = yrs_manager.get_or_create_awareness(&room_id);
+        let connections = server.connections.clone();
         Ok(ws.on_upgrade(move |socket| async move {
             tracing::info!("✅ 客户端成功连接到现有房间: {}", room_id);
             let client_addr = remote_addr
@@ -428,7 +502,14 @@ impl CollaborationServer {
                 .unwrap_or_else(|| "unknown".to_string());
             // The buffer capacity can be adjusted as needed. 128 is a reasonable default.
             let bcast = Arc::new(BroadcastGroup::new(awareness_ref, 128).await);
-            Self::peer(socket, bcast, room_id.clone(), client_addr).await;
+            Self::peer(
+                socket,
+                bcast,
+                connections,
+                room_id.clone(),
+                client_addr,
+            )
+            .await;
         }))
     }
 
@@ -436,6 +517,7 @@ impl CollaborationServer {
     async fn peer(
         ws: WebSocket,
         bcast: Arc<BroadcastGroup>,
+        connections: Arc<ConnectionRegistry>,
         room_id: String,
         client_addr: String,
     ) {
@@ -449,9 +531,24 @@ impl CollaborationServer {
             client_addr
         );
 
-        let sub = bcast.subscribe(sink, stream);
+        let connection = connections.register(&room_id, client_addr.clone());
+        let sub = bcast.subscribe(sink.clone(), stream);
+
+        let result = tokio::select! {
+            result = sub.completed() => result,
+            _ = connection.disconnected() => {
+                // 管理接口要求断开：关闭连接，订阅任务随连接关闭结束
+                tracing::info!(
+                    "🔌 管理接口断开客户端 - 房间: {} (地址: {})",
+                    room_id,
+                    client_addr
+                );
+                let _ = sink.lock().await.close().await;
+                return;
+            },
+        };
 
-        match sub.completed().await {
+        match result {
             Ok(_) => {
                 tracing::info!(
                     "✅ 客户端正常断开连接 - 房间: {} (地址: {})",
@@ -501,6 +598,11 @@ impl CollaborationServer {
         &self.sync_service
     }
 
+    /// 获取 WebSocket 连接注册表
+    pub fn connections(&self) -> &Arc<ConnectionRegistry> {
+        &self.connections
+    }
+
     /// HTTP 房间检查处理器
     async fn room_check_handler(
         room_id: String,
@@ -610,3 +712,37 @@ impl CollaborationServer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn shutdown_stops_presence_pruner() {
+        let server = CollaborationServer::new(Arc::new(YrsManager::new()), 0);
+        let running = tokio::spawn(server.clone().start());
+        let pruner =
+            tokio::time::timeout(std::time::Duration::from_secs(5), async {
+                loop {
+                    if let Some(pruner) = server.presence_pruner().as_ref() {
+                        return pruner.abort_handle();
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(10))
+                        .await;
+                }
+            })
+            .await
+            .expect("清理任务未启动");
+
+        server.shutdown(false).await.unwrap();
+        assert!(server.presence_pruner().is_none());
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while !pruner.is_finished() {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("清理任务未停止");
+        running.abort();
+    }
+}
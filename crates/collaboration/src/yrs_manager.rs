@@ -1,13 +1,122 @@
 use dashmap::DashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 use yrs::sync::Awareness;
-use yrs::Doc;
+use yrs::updates::decoder::Decode;
+use yrs::block::ClientID;
+use yrs::{Doc, Subscription, Transact, Update};
 use yrs_warp::AwarenessRef;
 
+/// 默认的 presence 过期时间
+pub const DEFAULT_PRESENCE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Unix 毫秒时间戳
+pub type Timestamp = i64;
+
+/// 房间更新历史的保留策略
+///
+/// 默认不记录历史，通过 [`YrsManager::with_history`] 开启。
+#[derive(Debug, Clone, Default)]
+pub struct HistoryConfig {
+    /// 最多保留的更新条数，为 0 时不记录历史
+    pub max_updates: usize,
+    /// 更新的最长保留时间，`None` 表示不按时间淘汰
+    pub max_age: Option<Duration>,
+}
+
+/// 房间的更新历史
+///
+/// 超出保留策略的更新从队首淘汰并合并到基线中，基线之后的时间点仍可重建。
+/// 合并失败时丢弃基线并标记为截断，此后不再重建任何时间点。
+#[derive(Default)]
+struct RoomHistory {
+    /// 已淘汰更新合并后的 v1 编码更新
+    base: Option<Vec<u8>>,
+    /// 基线包含的最后一条更新的时间
+    base_until: Timestamp,
+    updates: VecDeque<(Timestamp, Vec<u8>)>,
+    /// 淘汰的更新未能合并进基线
+    truncated: bool,
+}
+
+impl RoomHistory {
+    fn record(
+        &mut self,
+        update: Vec<u8>,
+        config: &HistoryConfig,
+    ) {
+        let now = now_millis();
+        self.updates.push_back((now, update));
+        let deadline =
+            config.max_age.map(|age| now - age.as_millis() as Timestamp);
+        let mut evicted = Vec::new();
+        while let Some((ts, _)) = self.updates.front() {
+            let expired = deadline.is_some_and(|d| *ts < d);
+            if self.updates.len() <= config.max_updates && !expired {
+                break;
+            }
+            let (ts, update) = self.updates.pop_front().unwrap();
+            self.base_until = ts;
+            evicted.push(update);
+        }
+        if evicted.is_empty() || self.truncated {
+            return;
+        }
+        let mut batch: Vec<&[u8]> = Vec::with_capacity(evicted.len() + 1);
+        if let Some(base) = &self.base {
+            batch.push(base);
+        }
+        batch.extend(evicted.iter().map(Vec::as_slice));
+        match yrs::merge_updates_v1(&batch) {
+            Ok(merged) => self.base = Some(merged),
+            Err(e) => {
+                tracing::error!("合并历史更新失败，历史已截断: {}", e);
+                self.base = None;
+                self.truncated = true;
+            },
+        }
+    }
+}
+
+/// 房间历史与产生历史的文档订阅
+struct RoomLog {
+    history: Arc<Mutex<RoomHistory>>,
+    _subscription: Subscription,
+}
+
+impl fmt::Debug for RoomLog {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str("RoomLog")
+    }
+}
+
+/// 服务端最近一次收到各客户端 awareness 更新的时间
+struct RoomPresence {
+    seen: Arc<Mutex<HashMap<ClientID, Instant>>>,
+    _subscription: Subscription,
+}
+
+impl fmt::Debug for RoomPresence {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str("RoomPresence")
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct YrsManager {
     awareness_refs: DashMap<String, AwarenessRef>,
+    history_config: HistoryConfig,
+    histories: DashMap<String, RoomLog>,
+    presence: DashMap<String, RoomPresence>,
 }
 
 impl YrsManager {
@@ -15,6 +124,11 @@ impl YrsManager {
         Self::default()
     }
 
+    /// 使用指定的历史保留策略创建
+    pub fn with_history(history_config: HistoryConfig) -> Self {
+        Self { history_config, ..Self::default() }
+    }
+
     /// 获取或创建房间的 Awareness 引用
     ///
     /// 如果房间的 awareness 对象不存在，则创建一个新的 Yrs `Doc`，
@@ -32,12 +146,131 @@ impl YrsManager {
         }
 
         let doc: Doc = Doc::new();
+        if self.history_config.max_updates > 0 {
+            self.observe_history(room_id, &doc);
+        }
         let awareness = Awareness::new(doc);
+        self.observe_presence(room_id, &awareness);
         let awareness_ref = Arc::new(RwLock::new(awareness));
         self.awareness_refs.insert(room_id.to_string(), awareness_ref.clone());
         awareness_ref
     }
 
+    fn observe_history(
+        &self,
+        room_id: &str,
+        doc: &Doc,
+    ) {
+        let history = Arc::new(Mutex::new(RoomHistory::default()));
+        let config = self.history_config.clone();
+        let recorder = history.clone();
+        let subscription = doc.observe_update_v1(move |_, event| {
+            if let Ok(mut history) = recorder.lock() {
+                history.record(event.update.clone(), &config);
+            }
+        });
+        match subscription {
+            Ok(subscription) => {
+                self.histories.insert(
+                    room_id.to_string(),
+                    RoomLog { history, _subscription: subscription },
+                );
+            },
+            Err(e) => {
+                tracing::error!("房间 '{}' 监听更新历史失败: {}", room_id, e);
+            },
+        }
+    }
+
+    fn observe_presence(
+        &self,
+        room_id: &str,
+        awareness: &Awareness,
+    ) {
+        let seen = Arc::new(Mutex::new(HashMap::new()));
+        let recorder = seen.clone();
+        let subscription = awareness.on_update(move |event| {
+            let now = Instant::now();
+            let mut seen = recorder.lock().unwrap_or_else(|e| e.into_inner());
+            for id in event.added().iter().chain(event.updated()) {
+                seen.insert(*id, now);
+            }
+            for id in event.removed() {
+                seen.remove(id);
+            }
+        });
+        self.presence.insert(
+            room_id.to_string(),
+            RoomPresence { seen, _subscription: subscription },
+        );
+    }
+
+    /// 房间保留的更新历史，按时间顺序排列，更新为 v1 编码
+    ///
+    /// 已被淘汰合并到基线的更新不包含在内。
+    pub fn update_log(
+        &self,
+        room_id: &str,
+    ) -> Vec<(Timestamp, Vec<u8>)> {
+        let Some(log) = self.histories.get(room_id) else {
+            return Vec::new();
+        };
+        let history = log.history.lock().unwrap_or_else(|e| e.into_inner());
+        history.updates.iter().cloned().collect()
+    }
+
+    /// 房间历史是否因淘汰的更新合并失败而截断
+    pub fn history_truncated(
+        &self,
+        room_id: &str,
+    ) -> bool {
+        self.histories.get(room_id).is_some_and(|log| {
+            log.history.lock().unwrap_or_else(|e| e.into_inner()).truncated
+        })
+    }
+
+    /// 重放历史，重建房间在 `timestamp` 时刻的文档
+    ///
+    /// 房间没有历史、历史已截断，或 `timestamp` 早于已淘汰的历史时返回 `None`。
+    pub fn snapshot_at(
+        &self,
+        room_id: &str,
+        timestamp: Timestamp,
+    ) -> Option<Doc> {
+        let log = self.histories.get(room_id)?;
+        let history = log.history.lock().unwrap_or_else(|e| e.into_inner());
+        if history.truncated
+            || (history.base.is_some() && timestamp < history.base_until)
+        {
+            return None;
+        }
+        let doc = Doc::new();
+        {
+            let mut txn = doc.transact_mut();
+            let updates = history.base.iter().chain(
+                history
+                    .updates
+                    .iter()
+                    .take_while(|(ts, _)| *ts <= timestamp)
+                    .map(|(_, update)| update),
+            );
+            for update in updates {
+                match Update::decode_v1(update) {
+                    Ok(update) => txn.apply_update(update),
+                    Err(e) => {
+                        tracing::error!(
+                            "房间 '{}' 历史更新解码失败: {}",
+                            room_id,
+                            e
+                        );
+                        return None;
+                    },
+                }
+            }
+        }
+        Some(doc)
+    }
+
     /// 获取给定房间的 awareness 引用，如果存在的话
     pub fn get_awareness_ref(
         &self,
@@ -59,6 +292,87 @@ impl YrsManager {
         self.awareness_refs.iter().map(|entry| entry.key().clone()).collect()
     }
 
+    /// 清理房间内过期的 presence 状态
+    ///
+    /// 按服务端收到客户端 awareness 更新的时间判断，客户端自报的
+    /// `presence.last_active` 不参与判断。超过 `timeout` 未收到更新即视为
+    /// 断线，移除其状态后由广播组通知其余客户端。没有 presence 字段的
+    /// 状态不受影响。返回被移除的客户端数量。
+    pub async fn prune_stale_presence(
+        &self,
+        room_id: &str,
+        timeout: Duration,
+    ) -> usize {
+        let Some(awareness_ref) = self.get_awareness_ref(room_id) else {
+            return 0;
+        };
+        let Some(seen) =
+            self.presence.get(room_id).map(|presence| presence.seen.clone())
+        else {
+            return 0;
+        };
+
+        let mut awareness = awareness_ref.write().await;
+        let local = awareness.client_id();
+        let stale: Vec<_> = {
+            let now = Instant::now();
+            let mut seen = seen.lock().unwrap_or_else(|e| e.into_inner());
+            awareness
+                .clients()
+                .iter()
+                .filter(|(id, _)| **id != local)
+                .filter(|(_, json)| {
+                    serde_json::from_str::<serde_json::Value>(json)
+                        .is_ok_and(|value| value.get("presence").is_some())
+                })
+                .filter_map(|(id, _)| {
+                    // 订阅之前写入的状态从现在开始计时
+                    let last = *seen.entry(*id).or_insert(now);
+                    (now.duration_since(last) > timeout).then_some(*id)
+                })
+                .collect()
+        };
+        for client_id in &stale {
+            awareness.remove_state(*client_id);
+        }
+        if !stale.is_empty() {
+            tracing::debug!(
+                "房间 '{}' 清理了 {} 个过期 presence",
+                room_id,
+                stale.len()
+            );
+        }
+        stale.len()
+    }
+
+    /// 清理所有房间内过期的 presence 状态
+    pub async fn prune_all_stale_presence(
+        &self,
+        timeout: Duration,
+    ) -> usize {
+        let mut removed = 0;
+        for room_id in self.get_active_rooms() {
+            removed += self.prune_stale_presence(&room_id, timeout).await;
+        }
+        removed
+    }
+
+    /// 启动后台任务，按 `interval` 周期清理过期 presence
+    pub fn spawn_presence_pruner(
+        self: &Arc<Self>,
+        interval: Duration,
+        timeout: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                manager.prune_all_stale_presence(timeout).await;
+            }
+        })
+    }
+
     /// 获取房间数量
     pub fn room_count(&self) -> usize {
         self.awareness_refs.len()
@@ -72,6 +386,8 @@ impl YrsManager {
     ) -> Option<AwarenessRef> {
         tracing::info!("🔄 移除房间: '{}'", room_id);
 
+        self.histories.remove(room_id);
+        self.presence.remove(room_id);
         if let Some((_, awareness_ref)) = self.awareness_refs.remove(room_id) {
             tracing::info!("🔄 房间 '{}' 成功 removed", room_id);
             Some(awareness_ref)
@@ -88,6 +404,8 @@ impl YrsManager {
         room_id: &str,
     ) -> bool {
         tracing::warn!("🔄 强制清理房间: '{}'", room_id);
+        self.histories.remove(room_id);
+        self.presence.remove(room_id);
 
         if let Some((_, awareness_ref)) = self.awareness_refs.remove(room_id) {
             // 尝试获取写锁并清理
@@ -144,3 +462,198 @@ impl YrsManager {
         tracing::info!("🔄 所有房间已关闭");
     }
 }
+
+fn now_millis() -> Timestamp {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as Timestamp)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn presence_json(last_active: i64) -> String {
+        serde_json::json!({
+            "presence": { "user_id": "u", "last_active": last_active }
+        })
+        .to_string()
+    }
+
+    /// 以远端客户端的身份写入 awareness 状态
+    async fn set_remote_state(
+        awareness_ref: &AwarenessRef,
+        remote: &mut Awareness,
+        state: String,
+    ) {
+        remote.set_local_state(state);
+        awareness_ref
+            .write()
+            .await
+            .apply_update(remote.update().unwrap())
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn prune_removes_only_stale_presence() {
+        let manager = YrsManager::new();
+        let awareness_ref = manager.get_or_create_awareness("room");
+        let timeout = Duration::from_secs(1);
+
+        // 客户端自报的时间不参与判断
+        let future = now_millis() + 3_600_000;
+        let mut active = Awareness::new(Doc::with_client_id(11));
+        let mut silent = Awareness::new(Doc::with_client_id(12));
+        let mut other = Awareness::new(Doc::with_client_id(13));
+        set_remote_state(&awareness_ref, &mut active, presence_json(0)).await;
+        set_remote_state(&awareness_ref, &mut silent, presence_json(future))
+            .await;
+        set_remote_state(&awareness_ref, &mut other, r#"{"other":1}"#.into())
+            .await;
+        assert_eq!(manager.prune_stale_presence("room", timeout).await, 0);
+
+        tokio::time::sleep(Duration::from_millis(700)).await;
+        set_remote_state(&awareness_ref, &mut active, presence_json(1)).await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        assert_eq!(manager.prune_stale_presence("room", timeout).await, 1);
+        let awareness = awareness_ref.read().await;
+        assert!(awareness.clients().contains_key(&11));
+        assert!(!awareness.clients().contains_key(&12));
+        assert!(awareness.clients().contains_key(&13));
+    }
+
+    /// 在房间文档中写入 key = value，返回写入后的时间
+    async fn write(
+        awareness_ref: &AwarenessRef,
+        key: &str,
+        value: i64,
+    ) -> Timestamp {
+        use yrs::Map;
+        {
+            let awareness = awareness_ref.write().await;
+            let doc = awareness.doc();
+            let map = doc.get_or_insert_map("m");
+            let mut txn = doc.transact_mut();
+            map.insert(&mut txn, key, value);
+        }
+        let ts = now_millis();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        ts
+    }
+
+    fn read(
+        doc: &Doc,
+        key: &str,
+    ) -> Option<i64> {
+        use yrs::Map;
+        let map = doc.get_or_insert_map("m");
+        let txn = doc.transact();
+        map.get(&txn, key).and_then(|v| v.cast::<i64>().ok())
+    }
+
+    #[tokio::test]
+    async fn snapshot_at_replays_history() {
+        let manager = YrsManager::with_history(HistoryConfig {
+            max_updates: 1000,
+            max_age: None,
+        });
+        let awareness_ref = manager.get_or_create_awareness("room");
+        let before = now_millis() - 1;
+        let t1 = write(&awareness_ref, "a", 1).await;
+        let t2 = write(&awareness_ref, "a", 2).await;
+        write(&awareness_ref, "b", 3).await;
+
+        let log = manager.update_log("room");
+        assert_eq!(log.len(), 3);
+        assert!(log.windows(2).all(|w| w[0].0 <= w[1].0));
+
+        let doc = manager.snapshot_at("room", before).unwrap();
+        assert_eq!(read(&doc, "a"), None);
+        let doc = manager.snapshot_at("room", t1).unwrap();
+        assert_eq!(read(&doc, "a"), Some(1));
+        let doc = manager.snapshot_at("room", t2).unwrap();
+        assert_eq!((read(&doc, "a"), read(&doc, "b")), (Some(2), None));
+        assert!(manager.snapshot_at("missing", t2).is_none());
+    }
+
+    #[tokio::test]
+    async fn history_is_bounded() {
+        let manager = YrsManager::with_history(HistoryConfig {
+            max_updates: 2,
+            max_age: None,
+        });
+        let awareness_ref = manager.get_or_create_awareness("room");
+        let before = now_millis() - 1;
+        write(&awareness_ref, "a", 1).await;
+        let t2 = write(&awareness_ref, "b", 2).await;
+        let t3 = write(&awareness_ref, "c", 3).await;
+        assert_eq!(manager.update_log("room").len(), 2);
+
+        // 被淘汰的更新合并到基线，之后的时间点仍可重建
+        let doc = manager.snapshot_at("room", t3).unwrap();
+        assert_eq!(
+            (read(&doc, "a"), read(&doc, "b"), read(&doc, "c")),
+            (Some(1), Some(2), Some(3))
+        );
+        let doc = manager.snapshot_at("room", t2).unwrap();
+        assert_eq!(read(&doc, "c"), None);
+        assert!(manager.snapshot_at("room", before).is_none());
+
+        manager.remove_room("room").await;
+        assert!(manager.update_log("room").is_empty());
+    }
+
+    #[tokio::test]
+    async fn history_is_off_by_default() {
+        let manager = YrsManager::new();
+        let awareness_ref = manager.get_or_create_awareness("room");
+        let t1 = write(&awareness_ref, "a", 1).await;
+        assert!(manager.update_log("room").is_empty());
+        assert!(manager.snapshot_at("room", t1).is_none());
+    }
+
+    #[test]
+    fn evicted_updates_merge_in_one_batch() {
+        fn update(key: &str) -> Vec<u8> {
+            use yrs::Map;
+            let doc = Doc::new();
+            let map = doc.get_or_insert_map("m");
+            let mut txn = doc.transact_mut();
+            map.insert(&mut txn, key, 1);
+            txn.encode_update_v1()
+        }
+        let keep_all = HistoryConfig { max_updates: 10, max_age: None };
+        let mut history = RoomHistory::default();
+        for key in ["a", "b", "c"] {
+            history.record(update(key), &keep_all);
+        }
+        // 一次淘汰三条，合并为一个基线
+        history.record(
+            update("d"),
+            &HistoryConfig { max_updates: 1, max_age: None },
+        );
+        assert_eq!(history.updates.len(), 1);
+        assert!(!history.truncated);
+        let doc = Doc::new();
+        doc.transact_mut().apply_update(
+            Update::decode_v1(history.base.as_ref().unwrap()).unwrap(),
+        );
+        let values: Vec<_> =
+            ["a", "b", "c", "d"].iter().map(|key| read(&doc, key)).collect();
+        assert_eq!(values, [Some(1), Some(1), Some(1), None]);
+    }
+
+    #[test]
+    fn failed_merge_truncates_history() {
+        let config = HistoryConfig { max_updates: 1, max_age: None };
+        let mut history = RoomHistory::default();
+        history.record(vec![0xFF, 0xFF], &config);
+        history.record(vec![0xFF, 0xFF], &config);
+        history.record(vec![0xFF, 0xFF], &config);
+        assert!(history.truncated);
+        assert!(history.base.is_none());
+        assert_eq!(history.updates.len(), 1);
+    }
+}
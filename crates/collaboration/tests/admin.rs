@@ -0,0 +1,192 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use mf_collab::{AdminConfig, CollaborationServer, YrsManager};
+use serde_json::Value;
+
+fn admin_config() -> AdminConfig {
+    AdminConfig::new(0, "secret")
+}
+
+async fn wait_for_clients(
+    server: &CollaborationServer,
+    room_id: &str,
+    count: usize,
+) {
+    for _ in 0..100 {
+        if server.connections().client_count(room_id) == count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!(
+        "房间 {} 的连接数未达到 {}，当前 {}",
+        room_id,
+        count,
+        server.connections().client_count(room_id)
+    );
+}
+
+fn body_json(body: &[u8]) -> Value {
+    serde_json::from_slice(body).expect("响应不是 JSON")
+}
+
+#[tokio::test]
+async fn test_admin_requires_token() {
+    let server = CollaborationServer::new(Arc::new(YrsManager::new()), 0);
+    let admin = mf_collab::admin::routes(server, admin_config());
+
+    let res = warp::test::request().path("/rooms").reply(&admin).await;
+    assert_eq!(res.status(), 401);
+
+    let res = warp::test::request()
+        .path("/rooms")
+        .header("authorization", "Bearer wrong")
+        .reply(&admin)
+        .await;
+    assert_eq!(res.status(), 401);
+
+    let res = warp::test::request()
+        .path("/rooms")
+        .header("authorization", "Bearer secret")
+        .reply(&admin)
+        .await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(body_json(res.body()), serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn test_admin_empty_token_rejects_all() {
+    let server = CollaborationServer::new(Arc::new(YrsManager::new()), 0);
+    let admin = mf_collab::admin::routes(server, AdminConfig::new(0, ""));
+
+    for header in ["Bearer ", "Bearer"] {
+        let res = warp::test::request()
+            .path("/rooms")
+            .header("authorization", header)
+            .reply(&admin)
+            .await;
+        assert_eq!(res.status(), 401);
+    }
+}
+
+#[test]
+fn test_admin_binds_loopback_by_default() {
+    let config: AdminConfig =
+        serde_json::from_str(r#"{"port": 9001, "token": "secret"}"#).unwrap();
+    assert!(config.bind.is_loopback());
+    assert!(admin_config().bind.is_loopback());
+}
+
+#[tokio::test]
+async fn test_start_reports_admin_errors() {
+    let server = CollaborationServer::new(Arc::new(YrsManager::new()), 0)
+        .with_admin(AdminConfig::new(0, ""));
+    assert!(server.start().await.is_err());
+
+    // 管理端口已被占用
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = taken.local_addr().unwrap().port();
+    let server = CollaborationServer::new(Arc::new(YrsManager::new()), 0)
+        .with_admin(AdminConfig::new(port, "secret"));
+    let result = tokio::time::timeout(Duration::from_secs(5), server.start())
+        .await
+        .expect("绑定失败时应立即返回");
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_admin_list_disconnect_and_evict() {
+    let server = CollaborationServer::new(Arc::new(YrsManager::new()), 0);
+    let admin = mf_collab::admin::routes(server.clone(), admin_config());
+    let routes = server.routes();
+
+    let mut first = warp::test::ws()
+        .path("/collaboration/room1")
+        .handshake(routes.clone())
+        .await
+        .expect("握手失败");
+    let mut second = warp::test::ws()
+        .path("/collaboration/room1")
+        .handshake(routes)
+        .await
+        .expect("握手失败");
+    wait_for_clients(&server, "room1", 2).await;
+
+    let res = warp::test::request()
+        .path("/rooms")
+        .header("authorization", "Bearer secret")
+        .reply(&admin)
+        .await;
+    let rooms = body_json(res.body());
+    assert_eq!(rooms[0]["id"], "room1");
+    assert_eq!(rooms[0]["client_count"], 2);
+    assert!(rooms[0]["last_activity"].is_u64());
+
+    let res = warp::test::request()
+        .path("/rooms/room1/clients")
+        .header("authorization", "Bearer secret")
+        .reply(&admin)
+        .await;
+    assert_eq!(res.status(), 200);
+    let clients = body_json(res.body());
+    let clients = clients["clients"].as_array().unwrap();
+    assert_eq!(clients.len(), 2);
+    let first_id = clients[0]["client_id"].as_u64().unwrap();
+
+    let res = warp::test::request()
+        .path("/rooms/missing/clients")
+        .header("authorization", "Bearer secret")
+        .reply(&admin)
+        .await;
+    assert_eq!(res.status(), 404);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/rooms/room1/disconnect/9999")
+        .header("authorization", "Bearer secret")
+        .reply(&admin)
+        .await;
+    assert_eq!(res.status(), 404);
+    assert_eq!(body_json(res.body())["error"], "CLIENT_NOT_FOUND");
+
+    let res = warp::test::request()
+        .method("POST")
+        .path(&format!("/rooms/room1/disconnect/{first_id}"))
+        .header("authorization", "Bearer secret")
+        .reply(&admin)
+        .await;
+    assert_eq!(res.status(), 200);
+    wait_for_clients(&server, "room1", 1).await;
+    tokio::time::timeout(Duration::from_secs(2), first.recv_closed())
+        .await
+        .expect("连接未被关闭")
+        .ok();
+
+    let res = warp::test::request()
+        .path("/metrics")
+        .header("authorization", "Bearer secret")
+        .reply(&admin)
+        .await;
+    assert_eq!(res.status(), 200);
+    let metrics = String::from_utf8(res.body().to_vec()).unwrap();
+    assert!(metrics.contains("mf_collab_rooms 1\n"));
+    assert!(metrics.contains("mf_collab_clients{room=\"room1\"} 1\n"));
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/rooms/room1/evict")
+        .header("authorization", "Bearer secret")
+        .reply(&admin)
+        .await;
+    assert_eq!(res.status(), 200);
+    let evicted = body_json(res.body());
+    assert_eq!(evicted["evicted"], true);
+    assert_eq!(evicted["disconnected"], 1);
+    wait_for_clients(&server, "room1", 0).await;
+    tokio::time::timeout(Duration::from_secs(2), second.recv_closed())
+        .await
+        .expect("连接未被关闭")
+        .ok();
+    assert!(server.get_active_rooms().is_empty());
+}
@@ -21,7 +21,7 @@ async fn test_collaboration() -> Result<()> {
     );
     // 5. 启动 WebSocket 服务器
     tokio::spawn(async move {
-        collaboration_server.start().await;
+        collaboration_server.start().await.expect("协作服务器启动失败");
     });
     // 等待用户中断
     tokio::select! {
@@ -28,6 +28,7 @@ pub struct Connection<Sink, Stream> {
     awareness: Arc<RwLock<Awareness>>,
     inbox: Arc<Mutex<Sink>>,
     sync_tracker: Arc<RwLock<SyncTracker>>, // 新增同步跟踪器
+    closed: Arc<AtomicBool>,
     _stream: PhantomData<Stream>,
 }
 
@@ -97,77 +98,91 @@ where
         let loop_sink = Arc::downgrade(&sink);
         let loop_awareness = Arc::downgrade(&awareness);
         let loop_sync_tracker = Arc::downgrade(&sync_tracker);
+        let closed = Arc::new(AtomicBool::new(false));
+        let loop_closed = closed.clone();
+
+        let processing_loop: JoinHandle<Result<(), Error>> = spawn(
+            async move {
+                let result = async {
+                    // 发送 SyncStep1
+                    let payload = {
+                        let awareness = loop_awareness.upgrade().unwrap();
+                        let mut encoder = EncoderV1::new();
+                        let awareness = awareness.read().await;
+                        protocol.start(&awareness, &mut encoder)?;
+                        encoder.to_vec()
+                    };
+
+                    if !payload.is_empty() {
+                        // 🔥 标记 Step1 已发送
+                        if let Some(tracker) = loop_sync_tracker.upgrade() {
+                            tracker.read().await.on_step1_sent();
+                        }
 
-        let processing_loop: JoinHandle<Result<(), Error>> =
-            spawn(async move {
-                // 发送 SyncStep1
-                let payload = {
-                    let awareness = loop_awareness.upgrade().unwrap();
-                    let mut encoder = EncoderV1::new();
-                    let awareness = awareness.read().await;
-                    protocol.start(&awareness, &mut encoder)?;
-                    encoder.to_vec()
-                };
-
-                if !payload.is_empty() {
-                    // 🔥 标记 Step1 已发送
-                    if let Some(tracker) = loop_sync_tracker.upgrade() {
-                        tracker.read().await.on_step1_sent();
-                    }
-
-                    if let Some(sink) = loop_sink.upgrade() {
-                        let mut s = sink.lock().await;
-                        if let Err(e) = s.send(payload).await {
-                            return Err(e.into());
+                        if let Some(sink) = loop_sink.upgrade() {
+                            let mut s = sink.lock().await;
+                            if let Err(e) = s.send(payload).await {
+                                return Err(e.into());
+                            }
+                        } else {
+                            return Ok(());
                         }
-                    } else {
-                        return Ok(());
                     }
-                }
 
-                // 消息处理循环
-                while let Some(input) = stream.next().await {
-                    match input {
-                        Ok(data) => {
-                            if let Some(mut sink) = loop_sink.upgrade() {
-                                if let Some(awareness) =
-                                    loop_awareness.upgrade()
-                                {
-                                    if let Some(sync_tracker) =
-                                        loop_sync_tracker.upgrade()
+                    // 消息处理循环
+                    while let Some(input) = stream.next().await {
+                        match input {
+                            Ok(data) => {
+                                if let Some(mut sink) = loop_sink.upgrade() {
+                                    if let Some(awareness) =
+                                        loop_awareness.upgrade()
                                     {
-                                        match Self::process_with_sync_detection(
-                                            &protocol,
-                                            &awareness,
-                                            &mut sink,
-                                            &sync_tracker,
-                                            data,
-                                        )
-                                        .await
+                                        if let Some(sync_tracker) =
+                                            loop_sync_tracker.upgrade()
                                         {
-                                            Ok(()) => { /* continue */ },
-                                            Err(e) => return Err(e),
+                                            match Self::process_with_sync_detection(
+                                                &protocol,
+                                                &awareness,
+                                                &mut sink,
+                                                &sync_tracker,
+                                                data,
+                                            )
+                                            .await
+                                            {
+                                                Ok(()) => { /* continue */ },
+                                                Err(e) => return Err(e),
+                                            }
                                         }
+                                    } else {
+                                        return Ok(());
                                     }
                                 } else {
                                     return Ok(());
                                 }
-                            } else {
-                                return Ok(());
-                            }
-                        },
-                        Err(e) => return Err(e.into()),
+                            },
+                            Err(e) => return Err(e.into()),
+                        }
                     }
+
+                    Ok(())
                 }
+                .await;
 
-                Ok(())
-            });
+                // 连接仍被持有时结束即为意外断开，通知上层
+                loop_closed.store(true, Ordering::Release);
+                if let Some(tracker) = loop_sync_tracker.upgrade() {
+                    tracker.read().await.on_connection_closed();
+                }
+                result
+            },
+        );
 
         Connection {
             processing_loop,
             awareness,
             inbox,
             sync_tracker,
+            closed,
             _stream: PhantomData,
         }
     }
@@ -221,6 +236,11 @@ where
         }
     }
 
+    /// 消息处理循环是否已结束
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
     /// 获取同步跟踪器
     pub fn sync_tracker(&self) -> &Arc<RwLock<SyncTracker>> {
         &self.sync_tracker
@@ -322,7 +342,10 @@ pub async fn handle_msg<P: Protocol>(
     }
 }
 
-use crate::types::{ConnectionError, ProtocolSyncState, SyncEvent, SyncEventSender};
+use crate::types::{
+    ConnectionError, ConnectionStatus, ProtocolSyncState, SyncEvent,
+    SyncEventSender,
+};
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
 /// 同步状态跟踪器
@@ -434,6 +457,14 @@ impl SyncTracker {
         self.step2_time = None;
     }
 
+    /// 连接意外断开
+    pub fn on_connection_closed(&self) {
+        tracing::warn!("🔌 连接已断开");
+        self.emit_event(SyncEvent::ConnectionChanged(
+            ConnectionStatus::Disconnected,
+        ));
+    }
+
     /// 标记连接失败
     pub fn on_connection_failed(
         &self,
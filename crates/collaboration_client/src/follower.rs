@@ -0,0 +1,257 @@
+//! 只读跟随者
+//!
+//! 连接协作房间，把远端更新经 yrs → NodePool 映射到本地只读的 [`State`]，
+//! 通过 `watch` 通道发布，适合只观察、不编辑的看板等场景。
+//!
+//! 跟随者不会向服务端发送任何文档更新，[`FollowerProvider::dispatch`]
+//! 总是返回 [`FollowerError::ReadOnly`]。awareness 参与可选：
+//! [`FollowerPresence::Invisible`] 不广播本地状态，
+//! [`FollowerPresence::Viewer`] 以 presence 对其他客户端可见。
+//!
+//! 连接意外断开时按 [`FollowerOptions::retry`] 自动重连，
+//! 重试次数用尽后保持断开，可调用 [`FollowerProvider::reconnect`] 重试。
+
+use std::sync::Arc;
+
+use mf_model::node_pool::NodePool;
+use mf_state::{State, Transaction};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{watch, Mutex, MutexGuard, Notify};
+use tokio::task::JoinHandle;
+use yrs::Subscription;
+
+use crate::presence::PresenceState;
+use crate::provider::WebsocketProvider;
+use crate::types::{
+    ConnectionRetryConfig, ConnectionStatus, SyncEvent, SyncEventReceiver,
+};
+use crate::utils::Utils;
+use crate::{AwarenessRef, ClientResult};
+
+/// 跟随者的错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FollowerError {
+    #[error("只读跟随者不能提交事务")]
+    ReadOnly,
+}
+
+/// 跟随者在 awareness 中的可见性
+#[derive(Debug, Clone, Default)]
+pub enum FollowerPresence {
+    /// 不广播本地 awareness，其他客户端看不到跟随者
+    #[default]
+    Invisible,
+    /// 以 presence 对其他客户端可见
+    Viewer(PresenceState),
+}
+
+/// 跟随者配置
+#[derive(Debug, Clone, Default)]
+pub struct FollowerOptions {
+    pub presence: FollowerPresence,
+    /// 连接与重连的退避配置
+    pub retry: ConnectionRetryConfig,
+}
+
+/// 只读跟随者
+pub struct FollowerProvider {
+    provider: Arc<Mutex<WebsocketProvider>>,
+    retry: ConnectionRetryConfig,
+    state: watch::Receiver<Arc<State>>,
+    refresh: JoinHandle<()>,
+    supervisor: JoinHandle<()>,
+    // 不随 provider 断开而释放，重连后继续刷新
+    _doc_subscription: Subscription,
+}
+
+impl FollowerProvider {
+    /// 连接房间并开始跟随
+    ///
+    /// `initial` 提供配置与插件状态，文档内容随远端更新替换；
+    /// 房间为空时通道中保持 `initial`。
+    pub async fn connect(
+        server_url: String,
+        room_name: String,
+        awareness: AwarenessRef,
+        initial: Arc<State>,
+        options: FollowerOptions,
+    ) -> ClientResult<Self> {
+        let mut provider =
+            WebsocketProvider::new(server_url, room_name, awareness.clone())
+                .await;
+        let share_awareness = match &options.presence {
+            FollowerPresence::Invisible => false,
+            FollowerPresence::Viewer(presence) => {
+                provider.set_presence(presence).await?;
+                true
+            },
+        };
+        provider.set_read_only(share_awareness);
+
+        let (sender, state) = watch::channel(initial);
+        let notify = Arc::new(Notify::new());
+        let doc_subscription = {
+            let notify = notify.clone();
+            let awareness = awareness.read().await;
+            awareness
+                .doc()
+                .observe_update_v1(move |_, _| notify.notify_one())
+                .map_err(|e| anyhow::anyhow!("监听文档更新失败: {e}"))?
+        };
+        let refresh = tokio::spawn(refresh_loop(awareness, notify, sender));
+
+        provider.connect_with_retry(Some(options.retry.clone())).await?;
+        provider.setup_update_listeners().await;
+
+        let provider = Arc::new(Mutex::new(provider));
+        let supervisor = spawn_supervisor(&provider, &options.retry).await;
+        Ok(Self {
+            provider,
+            retry: options.retry,
+            state,
+            refresh,
+            supervisor,
+            _doc_subscription: doc_subscription,
+        })
+    }
+
+    /// 订阅跟随的状态
+    pub fn state(&self) -> watch::Receiver<Arc<State>> {
+        self.state.clone()
+    }
+
+    /// 当前跟随的状态
+    pub fn current(&self) -> Arc<State> {
+        self.state.borrow().clone()
+    }
+
+    /// 跟随者只读，总是返回 [`FollowerError::ReadOnly`]
+    pub fn dispatch(
+        &self,
+        _tr: Transaction,
+    ) -> Result<(), FollowerError> {
+        Err(FollowerError::ReadOnly)
+    }
+
+    /// 断开后按退避配置重新连接，重连时与服务端重新同步
+    ///
+    /// 重连成功后恢复自动重连。
+    pub async fn reconnect(&mut self) -> ClientResult<()> {
+        self.supervisor.abort();
+        restore(&mut *self.provider.lock().await, &self.retry).await?;
+        self.supervisor = spawn_supervisor(&self.provider, &self.retry).await;
+        Ok(())
+    }
+
+    /// 断开连接并停止自动重连，已发布的状态保持不变
+    pub async fn disconnect(&mut self) {
+        self.supervisor.abort();
+        self.provider.lock().await.disconnect().await;
+    }
+
+    /// 底层连接，用于查询连接状态与远端 presence
+    ///
+    /// 自动重连期间会等待重连结束。
+    pub async fn provider(&self) -> MutexGuard<'_, WebsocketProvider> {
+        self.provider.lock().await
+    }
+}
+
+impl Drop for FollowerProvider {
+    fn drop(&mut self) {
+        self.refresh.abort();
+        self.supervisor.abort();
+    }
+}
+
+/// 重新建立连接并恢复监听
+async fn restore(
+    provider: &mut WebsocketProvider,
+    retry: &ConnectionRetryConfig,
+) -> ClientResult<()> {
+    provider.disconnect().await;
+    provider.connect_with_retry(Some(retry.clone())).await?;
+    provider.setup_update_listeners().await;
+    Ok(())
+}
+
+async fn spawn_supervisor(
+    provider: &Arc<Mutex<WebsocketProvider>>,
+    retry: &ConnectionRetryConfig,
+) -> JoinHandle<()> {
+    let events = provider.lock().await.sync_events();
+    tokio::spawn(supervise(provider.clone(), retry.clone(), events))
+}
+
+/// 连接意外断开时按退避配置自动重连
+///
+/// 只处理已连接状态下的断开：主动断开或重试用尽后不再重连。
+async fn supervise(
+    provider: Arc<Mutex<WebsocketProvider>>,
+    retry: ConnectionRetryConfig,
+    events: Option<SyncEventReceiver>,
+) {
+    let Some(mut events) = events else {
+        return;
+    };
+    loop {
+        match events.recv().await {
+            Ok(SyncEvent::ConnectionChanged(
+                ConnectionStatus::Disconnected,
+            ))
+            | Err(RecvError::Lagged(_)) => {},
+            Ok(_) => continue,
+            Err(RecvError::Closed) => return,
+        }
+        let mut provider = provider.lock().await;
+        if !provider.is_connection_lost() {
+            continue;
+        }
+        tracing::warn!("跟随者连接已断开，开始重连");
+        if let Err(e) = restore(&mut provider, &retry).await {
+            tracing::error!("跟随者重连失败: {}", e);
+        }
+    }
+}
+
+/// 文档更新后重建节点树并发布新状态
+///
+/// 一批更新只在读锁释放后重建一次，通道的所有接收者都释放后退出。
+async fn refresh_loop(
+    awareness: AwarenessRef,
+    notify: Arc<Notify>,
+    sender: watch::Sender<Arc<State>>,
+) {
+    loop {
+        tokio::select! {
+            _ = notify.notified() => {},
+            _ = sender.closed() => return,
+        }
+        let tree = {
+            let awareness = awareness.read().await;
+            Utils::apply_yrs_to_tree(awareness.doc())
+        };
+        let tree = match tree {
+            Ok(tree) => tree,
+            Err(e) => {
+                tracing::debug!("跳过无法映射的文档更新: {}", e);
+                continue;
+            },
+        };
+        let prev = sender.borrow().clone();
+        let mut next = match State::new_generic(
+            prev.config.clone(),
+            NodePool::new(Arc::new(tree)),
+        ) {
+            Ok(state) => state,
+            Err(e) => {
+                tracing::warn!("跟随状态创建失败: {}", e);
+                continue;
+            },
+        };
+        next.fields_instances = prev.fields_instances.clone();
+        if sender.send(Arc::new(next)).is_err() {
+            return;
+        }
+    }
+}
@@ -3,8 +3,10 @@ use tokio::sync::RwLock;
 
 pub mod client;
 pub mod conn;
+pub mod follower;
 pub mod mapping;
 pub mod mapping_v2;
+pub mod presence;
 pub mod provider;
 pub mod types;
 pub mod utils;
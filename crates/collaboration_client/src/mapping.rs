@@ -29,6 +29,7 @@ pub use crate::mapping_v2::{
     SimpleNodeAddConverter,
     SimpleNodeRemoveConverter,
     SimpleAttrConverter,
+    SimpleBulkAttrConverter,
     SimpleMarkAddConverter,
     SimpleMarkRemoveConverter,
 };
@@ -4,7 +4,7 @@ use yrs::{Array, ArrayPrelim, Map, MapPrelim, TransactionMut, WriteTxn};
 use mf_transform::{
     step::StepGeneric,
     node_step::{AddNodeStep, RemoveNodeStep},
-    attr_step::AttrStep,
+    attr_step::{AttrStep, BulkAttrStep},
     mark_step::{AddMarkStep, RemoveMarkStep},
 };
 use mf_model::node::Node;
@@ -259,6 +259,82 @@ impl TypedStepConverter<AttrStep> for SimpleAttrConverter {
     }
 }
 
+// ================================
+// 批量属性转换器
+// ================================
+
+#[derive(Debug, Default, Clone)]
+pub struct SimpleBulkAttrConverter;
+
+impl TypedStepConverter<BulkAttrStep> for SimpleBulkAttrConverter {
+    fn convert_typed(
+        &self,
+        step: &BulkAttrStep,
+        txn: &mut TransactionMut,
+        context: &ConversionContext,
+    ) -> ConversionResult<StepResult> {
+        self.validate_step(step, context)?;
+
+        // 所有节点的属性写入同一个 yrs 事务，只产生一次更新
+        let nodes_map = txn.get_or_insert_map("nodes");
+        for (id, values) in &step.updates {
+            let node_data_map =
+                Utils::get_or_create_node_data_map(&nodes_map, txn, id);
+            let attrs_map =
+                Utils::get_or_create_node_attrs_map(&node_data_map, txn);
+            for (key, value) in values {
+                attrs_map.insert(
+                    txn,
+                    key.clone(),
+                    Utils::json_value_to_yrs_any(value),
+                );
+            }
+        }
+
+        Ok(StepResult {
+            step_id: uuid::Uuid::new_v4().to_string(),
+            step_name: step.name().to_string(),
+            description: format!(
+                "批量更新 {} 个节点的属性",
+                step.updates.len()
+            ),
+            timestamp: context.timestamp,
+            client_id: context.client_id.clone(),
+        })
+    }
+
+    fn validate_step(
+        &self,
+        step: &BulkAttrStep,
+        _context: &ConversionContext,
+    ) -> ConversionResult<()> {
+        if step.updates.is_empty() {
+            return Err(ConversionError::validation_failed(
+                "BulkAttrStep",
+                "属性更新不能为空",
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn converter_name() -> &'static str {
+        "SimpleBulkAttrConverter"
+    }
+
+    fn step_type_name() -> &'static str {
+        "BulkAttrStep"
+    }
+
+    fn priority() -> u8 {
+        10
+    }
+
+    fn supports_concurrent_execution() -> bool {
+        true
+    }
+}
+
 // ================================
 // 标记添加转换器
 // ================================
@@ -496,6 +572,7 @@ fn register_simple_converters() {
     register_global_converter::<AddNodeStep, SimpleNodeAddConverter>();
     register_global_converter::<RemoveNodeStep, SimpleNodeRemoveConverter>();
     register_global_converter::<AttrStep, SimpleAttrConverter>();
+    register_global_converter::<BulkAttrStep, SimpleBulkAttrConverter>();
     register_global_converter::<AddMarkStep, SimpleMarkAddConverter>();
     register_global_converter::<RemoveMarkStep, SimpleMarkRemoveConverter>();
 
@@ -0,0 +1,271 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use yrs::sync::Awareness;
+use yrs::sync::awareness::Event;
+use yrs::block::ClientID;
+use yrs::Subscription;
+
+use crate::ClientResult;
+
+/// presence 在 awareness 状态对象中的字段名
+///
+/// 同一 awareness 状态中的其他字段保持不变，便于与其他协议共存。
+pub const PRESENCE_KEY: &str = "presence";
+
+/// 节点属性内的选区，`anchor`/`head` 为字符偏移
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresenceSelection {
+    /// 选区所在的属性名，`None` 表示整个节点
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attr: Option<String>,
+    pub anchor: u32,
+    pub head: u32,
+}
+
+/// 用户在线状态，光标绑定到节点 id 而非坐标
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresenceState {
+    pub user_id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub color: String,
+    /// 当前聚焦的节点 id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focused_node: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<PresenceSelection>,
+    /// 最近活跃时间（Unix 毫秒），服务端据此清理过期状态
+    #[serde(default)]
+    pub last_active: i64,
+    /// 未识别的字段，原样保留，兼容新旧版本客户端
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl PresenceState {
+    pub fn new(
+        user_id: impl Into<String>,
+        name: impl Into<String>,
+        color: impl Into<String>,
+    ) -> Self {
+        Self {
+            user_id: user_id.into(),
+            name: name.into(),
+            color: color.into(),
+            focused_node: None,
+            selection: None,
+            last_active: now_millis(),
+            extra: Map::new(),
+        }
+    }
+
+    /// 聚焦到节点，同时清除旧选区
+    pub fn focus(
+        mut self,
+        node_id: impl Into<String>,
+    ) -> Self {
+        self.focused_node = Some(node_id.into());
+        self.selection = None;
+        self
+    }
+
+    pub fn with_selection(
+        mut self,
+        selection: PresenceSelection,
+    ) -> Self {
+        self.selection = Some(selection);
+        self
+    }
+
+    /// 刷新活跃时间
+    pub fn touch(&mut self) {
+        self.last_active = now_millis();
+    }
+}
+
+/// 远端 presence 变化
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresenceEvent {
+    Joined { client_id: ClientID, presence: PresenceState },
+    Updated { client_id: ClientID, presence: PresenceState },
+    Left { client_id: ClientID },
+}
+
+pub fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// 从 awareness 的 JSON 状态中解析 presence，无 presence 字段时返回 `None`
+pub fn parse_presence(json: &str) -> Option<PresenceState> {
+    let value: Value = serde_json::from_str(json).ok()?;
+    let presence = value.get(PRESENCE_KEY)?.clone();
+    serde_json::from_value(presence).ok()
+}
+
+/// 设置本地 presence，保留 awareness 状态中的其他字段
+pub fn set_local_presence(
+    awareness: &mut Awareness,
+    presence: &PresenceState,
+) -> ClientResult<()> {
+    let mut state = awareness
+        .local_state()
+        .and_then(|json| serde_json::from_str::<Value>(json).ok())
+        .and_then(|value| match value {
+            Value::Object(map) => Some(map),
+            _ => None,
+        })
+        .unwrap_or_default();
+    state.insert(PRESENCE_KEY.to_string(), serde_json::to_value(presence)?);
+    awareness.set_local_state(serde_json::to_string(&Value::Object(state))?);
+    Ok(())
+}
+
+/// 读取本地 presence
+pub fn local_presence(awareness: &Awareness) -> Option<PresenceState> {
+    awareness.local_state().and_then(parse_presence)
+}
+
+/// 所有远端客户端的 presence（不含本地客户端）
+pub fn remote_presences(
+    awareness: &Awareness
+) -> Vec<(ClientID, PresenceState)> {
+    let local = awareness.client_id();
+    let mut presences: Vec<_> = awareness
+        .clients()
+        .iter()
+        .filter(|(id, _)| **id != local)
+        .filter_map(|(id, json)| parse_presence(json).map(|p| (*id, p)))
+        .collect();
+    presences.sort_by_key(|(id, _)| *id);
+    presences
+}
+
+/// 监听远端 presence 的加入、更新与离开
+///
+/// 只关心带 presence 字段的状态；状态仍在但 presence 被移除时视为离开。
+pub fn observe_presence<F>(
+    awareness: &Awareness,
+    f: F,
+) -> Subscription
+where
+    F: Fn(PresenceEvent) + 'static,
+{
+    let known: Mutex<HashSet<ClientID>> = Mutex::new(HashSet::new());
+    awareness.on_update(move |event: &Event| {
+        let local = event.doc().client_id();
+        let mut known = known.lock().unwrap_or_else(|e| e.into_inner());
+        for &client_id in event.added().iter().chain(event.updated()) {
+            if client_id == local {
+                continue;
+            }
+            let presence = event
+                .awareness_state()
+                .get_state(client_id)
+                .and_then(parse_presence);
+            match presence {
+                Some(presence) if known.insert(client_id) => {
+                    f(PresenceEvent::Joined { client_id, presence })
+                },
+                Some(presence) => {
+                    f(PresenceEvent::Updated { client_id, presence })
+                },
+                None if known.remove(&client_id) => {
+                    f(PresenceEvent::Left { client_id })
+                },
+                None => {},
+            }
+        }
+        for &client_id in event.removed() {
+            if client_id != local && known.remove(&client_id) {
+                f(PresenceEvent::Left { client_id });
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use yrs::Doc;
+
+    fn awareness(client_id: ClientID) -> Awareness {
+        Awareness::new(Doc::with_client_id(client_id))
+    }
+
+    /// 模拟网络：把 `from` 的本地状态（含移除）同步给 `to`
+    fn sync(
+        from: &Awareness,
+        to: &mut Awareness,
+    ) {
+        let update = from.update_with_clients([from.client_id()]).unwrap();
+        to.apply_update(update).unwrap();
+    }
+
+    #[test]
+    fn remote_cursor_visible_and_leaves() {
+        let mut alice = awareness(1);
+        let mut bob = awareness(2);
+        alice.set_local_state(r#"{"custom":true}"#);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let _sub =
+            observe_presence(&bob, move |e| sink.lock().unwrap().push(e));
+
+        let presence = PresenceState::new("u1", "Alice", "#f00")
+            .focus("node-1")
+            .with_selection(PresenceSelection {
+                attr: Some("title".into()),
+                anchor: 0,
+                head: 3,
+            });
+        set_local_presence(&mut alice, &presence).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(alice.local_state().unwrap())
+                .unwrap()["custom"],
+            Value::Bool(true)
+        );
+        sync(&alice, &mut bob);
+
+        let remote = remote_presences(&bob);
+        assert_eq!(remote.len(), 1);
+        assert_eq!(remote[0].0, 1);
+        assert_eq!(remote[0].1.focused_node.as_deref(), Some("node-1"));
+        assert!(local_presence(&bob).is_none());
+
+        set_local_presence(&mut alice, &presence.clone().focus("node-2"))
+            .unwrap();
+        sync(&alice, &mut bob);
+        alice.clean_local_state();
+        sync(&alice, &mut bob);
+
+        let events = events.lock().unwrap();
+        assert!(matches!(
+            events[0],
+            PresenceEvent::Joined { client_id: 1, .. }
+        ));
+        match &events[1] {
+            PresenceEvent::Updated { presence, .. } => {
+                assert_eq!(presence.focused_node.as_deref(), Some("node-2"))
+            },
+            other => panic!("unexpected event: {other:?}"),
+        }
+        assert_eq!(events[2], PresenceEvent::Left { client_id: 1 });
+        assert!(remote_presences(&bob).is_empty());
+    }
+
+    #[test]
+    fn unknown_fields_are_preserved() {
+        let json = r#"{"presence":{"user_id":"u1","mood":"busy"}}"#;
+        let presence = parse_presence(json).unwrap();
+        assert_eq!(presence.extra["mood"], Value::from("busy"));
+        assert!(presence.focused_node.is_none());
+        let value = serde_json::to_value(&presence).unwrap();
+        assert_eq!(value["mood"], Value::from("busy"));
+    }
+}
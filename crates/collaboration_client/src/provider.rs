@@ -8,6 +8,8 @@ use yrs::{Subscription};
 use url::Url;
 use crate::AwarenessRef;
 use crate::conn::Connection;
+use crate::presence::{self, PresenceEvent, PresenceState};
+use crate::ClientResult;
 use crate::types::*;
 use crate::client::{ClientSink, ClientStream};
 use futures_util::{SinkExt, StreamExt};
@@ -27,6 +29,10 @@ pub struct WebsocketProvider {
     pub ws_url: Option<Url>,
     pub client_id: u64,
     subscriptions: Vec<Subscription>,
+    // 只读模式下不向服务端发送本地文档更新
+    read_only: bool,
+    // 只读模式下是否广播本地 awareness
+    share_awareness: bool,
 }
 
 impl WebsocketProvider {
@@ -60,9 +66,26 @@ impl WebsocketProvider {
             max_backoff_time: 2500,
             ws_url,
             subscriptions: Vec::new(),
+            read_only: false,
+            share_awareness: true,
         }
     }
 
+    /// 切换为只读：连接后不再向服务端发送本地文档更新，
+    /// `share_awareness` 为 false 时也不广播本地 awareness
+    pub(crate) fn set_read_only(
+        &mut self,
+        share_awareness: bool,
+    ) {
+        self.read_only = true;
+        self.share_awareness = share_awareness;
+    }
+
+    /// 是否为只读连接
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
     pub fn subscription(
         &mut self,
         subscription: Subscription,
@@ -134,9 +157,7 @@ impl WebsocketProvider {
         Err(anyhow::anyhow!("连接失败，已达到最大重试次数"))
     }
     async fn try_connect(&mut self) -> anyhow::Result<()> {
-        if self.status == ConnectionStatus::Connected
-            || self.status == ConnectionStatus::Connecting
-        {
+        if self.is_connected() || self.status == ConnectionStatus::Connecting {
             return Ok(());
         }
 
@@ -268,7 +289,7 @@ impl WebsocketProvider {
 
     /// 设置统一的文档变更监听器
     /// 监听所有文档变更并发送事件通知
-    async fn setup_update_listeners(&mut self) {
+    pub(crate) async fn setup_update_listeners(&mut self) {
         // 延迟 100 毫秒，以避免与 yrs-warp 的初始事务发生竞争
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
@@ -281,8 +302,10 @@ impl WebsocketProvider {
             },
         };
 
-        // 1. 监听文档变更
-        let doc_subscription = {
+        // 1. 监听文档变更，只读连接不发送本地更新
+        let doc_subscription = if self.read_only {
+            None
+        } else {
             let sink = conn.sink();
             let client_id = self.client_id;
             let awareness_lock = self.awareness.read().await;
@@ -318,14 +341,18 @@ impl WebsocketProvider {
                     }
                 }
             })
+            .ok()
         };
 
         // 保存订阅
-        if let Ok(subscription) = doc_subscription {
+        if let Some(subscription) = doc_subscription {
             self.subscriptions.push(subscription);
         }
 
         // 2. 监听本地 awareness 变更
+        if self.read_only && !self.share_awareness {
+            return;
+        }
 
         {
             let awareness_lock = self.awareness.write().await;
@@ -388,6 +415,11 @@ impl WebsocketProvider {
         }
     }
 
+    /// 新建一个同步事件接收端，不影响 [`Self::subscribe_sync_events`]
+    pub(crate) fn sync_events(&self) -> Option<SyncEventReceiver> {
+        self.sync_event_sender.as_ref().map(|sender| sender.subscribe())
+    }
+
     /// 订阅同步事件
     pub fn subscribe_sync_events(&mut self) -> Option<SyncEventReceiver> {
         self.sync_event_receiver.take()
@@ -412,9 +444,63 @@ impl WebsocketProvider {
         tracing::info!("✅ WebSocket 连接已断开且监听器已清理");
     }
 
+    /// 设置本地 presence，经 awareness 广播给其他客户端
+    pub async fn set_presence(
+        &self,
+        presence: &PresenceState,
+    ) -> ClientResult<()> {
+        let mut awareness = self.awareness.write().await;
+        presence::set_local_presence(&mut awareness, presence)
+    }
+
+    /// 修改本地 presence 并刷新活跃时间；尚未设置 presence 时返回 `false`
+    ///
+    /// 服务端会清理超时未刷新的 presence，空闲时也应定期调用以保持在线。
+    pub async fn update_presence<F>(
+        &self,
+        f: F,
+    ) -> ClientResult<bool>
+    where
+        F: FnOnce(&mut PresenceState),
+    {
+        let mut awareness = self.awareness.write().await;
+        let Some(mut presence) = presence::local_presence(&awareness) else {
+            return Ok(false);
+        };
+        f(&mut presence);
+        presence.touch();
+        presence::set_local_presence(&mut awareness, &presence)?;
+        Ok(true)
+    }
+
+    /// 获取远端用户的 presence
+    pub async fn remote_presences(&self) -> Vec<(u64, PresenceState)> {
+        presence::remote_presences(&*self.awareness.read().await)
+    }
+
+    /// 监听远端 presence 变化，订阅随 provider 一起释放
+    pub async fn on_presence<F>(
+        &mut self,
+        f: F,
+    ) where
+        F: Fn(PresenceEvent) + 'static,
+    {
+        let subscription = {
+            let awareness = self.awareness.read().await;
+            presence::observe_presence(&awareness, f)
+        };
+        self.subscriptions.push(subscription);
+    }
+
     /// 检查连接状态
     pub fn is_connected(&self) -> bool {
-        self.status == ConnectionStatus::Connected && self.client_conn.is_some()
+        self.status == ConnectionStatus::Connected
+            && self.client_conn.as_ref().is_some_and(|conn| !conn.is_closed())
+    }
+
+    /// 连接是否意外断开：状态仍为已连接，但底层连接已结束
+    pub(crate) fn is_connection_lost(&self) -> bool {
+        self.status == ConnectionStatus::Connected && !self.is_connected()
     }
 
     /// 获取连接状态
@@ -106,10 +106,8 @@ impl Utils {
         if let Some(root_tree) = tree.all_children(&tree.root_id, None) {
             // 创建一个 AddNodeStep 来添加整个子树
             // 注意：root_tree 已经包含了根节点，不需要重复添加
-            let add_step = AddNodeStep {
-                parent_id: tree.root_id.clone(),
-                nodes: vec![root_tree],
-            };
+            let add_step =
+                AddNodeStep::new(tree.root_id.clone(), vec![root_tree]);
 
             // 使用新版本的转换器API
             let context = crate::mapping::create_context(
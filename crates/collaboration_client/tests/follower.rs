@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use mf_collab::{CollaborationServer, YrsManager};
+use mf_collab_client::follower::{FollowerError, FollowerOptions, FollowerProvider};
+use mf_collab_client::types::ConnectionRetryConfig;
+use mf_collab_client::provider::WebsocketProvider;
+use mf_collab_client::utils::Utils;
+use mf_collab_client::yrs::sync::Awareness;
+use mf_collab_client::yrs::Doc;
+use mf_collab_client::AwarenessRef;
+use mf_model::attrs::Attrs;
+use mf_model::node::Node;
+use mf_model::node_definition::NodeSpec;
+use mf_model::node_pool::NodePool;
+use mf_model::rpds::HashTrieMapSync;
+use mf_model::schema::{AttributeSpec, Schema, SchemaSpec};
+use mf_model::tree::Tree;
+use mf_state::{State, StateConfig};
+use serde_json::Value;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+fn schema() -> Arc<Schema> {
+    let title = AttributeSpec::new(Some(Value::from("")));
+    let mut nodes = HashMap::new();
+    nodes.insert(
+        "doc".to_string(),
+        NodeSpec {
+            content: Some("item*".to_string()),
+            attrs: Some(HashMap::from([("title".to_string(), title.clone())])),
+            ..Default::default()
+        },
+    );
+    nodes.insert(
+        "item".to_string(),
+        NodeSpec {
+            attrs: Some(HashMap::from([("title".to_string(), title)])),
+            ..Default::default()
+        },
+    );
+    let spec = SchemaSpec {
+        nodes,
+        marks: HashMap::new(),
+        top_node: Some("doc".to_string()),
+    };
+    Arc::new(Schema::compile(spec).expect("测试 Schema 编译失败"))
+}
+
+fn node(
+    id: &str,
+    r#type: &str,
+) -> Node {
+    let attrs = Attrs::from(
+        HashTrieMapSync::new_sync().insert("title".to_string(), "".into()),
+    );
+    Node::new(id, r#type.to_string(), attrs, vec![], vec![])
+}
+
+async fn create_state(doc: Option<Arc<NodePool>>) -> Arc<State> {
+    let state = State::create(StateConfig {
+        schema: Some(schema()),
+        doc,
+        stored_marks: None,
+        plugins: None,
+        resource_manager: None,
+    })
+    .await
+    .expect("测试状态创建失败");
+    Arc::new(state)
+}
+
+fn awareness() -> AwarenessRef {
+    Arc::new(RwLock::new(Awareness::new(Doc::new())))
+}
+
+fn titles(state: &State) -> Vec<(String, Value)> {
+    let doc = state.doc();
+    let mut titles: Vec<(String, Value)> = ["root", "a", "b"]
+        .into_iter()
+        .filter_map(|id| {
+            let node = doc.get_node(&id.into())?;
+            Some((id.to_string(), node.attrs.get_value::<Value>("title")?))
+        })
+        .collect();
+    titles.sort_by(|a, b| a.0.cmp(&b.0));
+    titles
+}
+
+/// 转发到服务端的 TCP 代理，用于模拟连接意外断开
+struct Proxy {
+    addr: SocketAddr,
+    links: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    accepted: Arc<AtomicUsize>,
+}
+
+impl Proxy {
+    async fn start(upstream: SocketAddr) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let links: Arc<Mutex<Vec<JoinHandle<()>>>> = Arc::default();
+        let accepted: Arc<AtomicUsize> = Arc::default();
+        let (open, count) = (links.clone(), accepted.clone());
+        tokio::spawn(async move {
+            while let Ok((mut inbound, _)) = listener.accept().await {
+                count.fetch_add(1, Ordering::Relaxed);
+                let link = tokio::spawn(async move {
+                    let mut outbound =
+                        TcpStream::connect(upstream).await.unwrap();
+                    let _ = tokio::io::copy_bidirectional(
+                        &mut inbound,
+                        &mut outbound,
+                    )
+                    .await;
+                });
+                open.lock().unwrap().push(link);
+            }
+        });
+        Self { addr, links, accepted }
+    }
+
+    /// 断开所有已建立的连接，之后仍接受新连接
+    fn cut(&self) {
+        for link in self.links.lock().unwrap().drain(..) {
+            link.abort();
+        }
+    }
+}
+
+fn set_title(
+    state: &State,
+    id: &str,
+    title: &str,
+) -> mf_state::Transaction {
+    let mut tr = state.tr();
+    tr.set_node_attribute(
+        id.into(),
+        HashTrieMapSync::new_sync()
+            .insert("title".to_string(), Value::from(title)),
+    )
+    .unwrap();
+    tr
+}
+
+#[tokio::test]
+async fn follower_reconnects_after_connection_drop() {
+    let server = CollaborationServer::new(Arc::new(YrsManager::new()), 0);
+    let (addr, serve) =
+        warp::serve(server.routes()).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(serve);
+    let proxy = Proxy::start(addr).await;
+
+    let mut tree = Tree::new(node("root", "doc"));
+    tree.add_node(&"root".into(), &vec![node("a", "item"), node("b", "item")])
+        .unwrap();
+    let mut writer_state =
+        create_state(Some(NodePool::new(Arc::new(tree.clone())))).await;
+    let writer_awareness = awareness();
+    let mut writer = WebsocketProvider::new(
+        format!("ws://{addr}/collaboration"),
+        "reconnect-room".to_string(),
+        writer_awareness.clone(),
+    )
+    .await;
+    writer.connect().await;
+    Utils::apply_tree_to_yrs(writer_awareness.clone(), &tree).await.unwrap();
+
+    let retry = ConnectionRetryConfig {
+        max_attempts: 10,
+        initial_delay_ms: 50,
+        max_delay_ms: 200,
+        backoff_multiplier: 2.0,
+    };
+    let follower = FollowerProvider::connect(
+        format!("ws://{}/collaboration", proxy.addr),
+        "reconnect-room".to_string(),
+        awareness(),
+        create_state(None).await,
+        FollowerOptions { retry, ..Default::default() },
+    )
+    .await
+    .expect("跟随者连接失败");
+    let mut rx = follower.state();
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        rx.wait_for(|state| titles(state) == titles(&writer_state)),
+    )
+    .await
+    .expect("跟随者初次同步未完成")
+    .unwrap();
+
+    // 断开跟随者的连接，断开期间的编辑在重连后同步
+    proxy.cut();
+    let tr = set_title(&writer_state, "a", "offline");
+    Utils::apply_transaction_to_yrs(writer_awareness.clone(), &tr)
+        .await
+        .unwrap();
+    writer_state = writer_state.apply(tr).await.unwrap().state;
+
+    let expected = titles(&writer_state);
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        rx.wait_for(|state| titles(state) == expected),
+    )
+    .await
+    .expect("跟随者未重连")
+    .unwrap();
+    assert_eq!(proxy.accepted.load(Ordering::Relaxed), 2);
+    assert!(follower.provider().await.is_connected());
+    writer.disconnect().await;
+}
+
+#[tokio::test]
+async fn follower_converges_and_rejects_dispatch() {
+    let server = CollaborationServer::new(Arc::new(YrsManager::new()), 0);
+    let (addr, serve) =
+        warp::serve(server.routes()).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(serve);
+    let server_url = format!("ws://{addr}/collaboration");
+
+    // 写入方初始化文档
+    let mut tree = Tree::new(node("root", "doc"));
+    tree.add_node(&"root".into(), &vec![node("a", "item"), node("b", "item")])
+        .unwrap();
+    let mut writer_state =
+        create_state(Some(NodePool::new(Arc::new(tree.clone())))).await;
+    let writer_awareness = awareness();
+    let mut writer = WebsocketProvider::new(
+        server_url.clone(),
+        "follower-room".to_string(),
+        writer_awareness.clone(),
+    )
+    .await;
+    writer.connect().await;
+    Utils::apply_tree_to_yrs(writer_awareness.clone(), &tree).await.unwrap();
+
+    let follower = FollowerProvider::connect(
+        server_url,
+        "follower-room".to_string(),
+        awareness(),
+        create_state(None).await,
+        FollowerOptions::default(),
+    )
+    .await
+    .expect("跟随者连接失败");
+    assert!(follower.provider().await.is_read_only());
+
+    // 连续编辑
+    for i in 0..20 {
+        let mut tr = writer_state.tr();
+        let id = if i % 2 == 0 { "a" } else { "b" };
+        tr.set_node_attribute(
+            id.into(),
+            HashTrieMapSync::new_sync()
+                .insert("title".to_string(), Value::from(format!("v{i}"))),
+        )
+        .unwrap();
+        Utils::apply_transaction_to_yrs(writer_awareness.clone(), &tr)
+            .await
+            .unwrap();
+        writer_state = writer_state.apply(tr).await.unwrap().state;
+    }
+    let expected = titles(&writer_state);
+    assert_eq!(expected[0], ("a".to_string(), Value::from("v18")));
+
+    let mut rx = follower.state();
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        rx.wait_for(|state| titles(state) == expected),
+    )
+    .await
+    .expect("跟随者状态未收敛")
+    .unwrap();
+
+    let tr = follower.current().tr();
+    assert_eq!(follower.dispatch(tr), Err(FollowerError::ReadOnly));
+    writer.disconnect().await;
+}
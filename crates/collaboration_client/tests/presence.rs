@@ -0,0 +1,104 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use mf_collab::{CollaborationServer, YrsManager};
+use mf_collab_client::presence::{PresenceEvent, PresenceState};
+use mf_collab_client::provider::WebsocketProvider;
+use mf_collab_client::yrs::sync::Awareness;
+use mf_collab_client::yrs::Doc;
+use tokio::sync::{mpsc, RwLock};
+
+/// 服务端清理 presence 的超时时间
+const TIMEOUT: Duration = Duration::from_millis(400);
+
+async fn connect(url: &str) -> WebsocketProvider {
+    let awareness = Arc::new(RwLock::new(Awareness::new(Doc::new())));
+    let mut provider = WebsocketProvider::new(
+        url.to_string(),
+        "presence-room".to_string(),
+        awareness,
+    )
+    .await;
+    provider.connect().await;
+    assert!(provider.wait_for_protocol_sync(5000).await.unwrap());
+    provider
+}
+
+async fn next_event(
+    rx: &mut mpsc::UnboundedReceiver<PresenceEvent>
+) -> PresenceEvent {
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("未收到 presence 事件")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn presence_visible_then_pruned_after_timeout() {
+    let manager = Arc::new(YrsManager::new());
+    let server = CollaborationServer::new(manager.clone(), 0);
+    let (addr, serve) =
+        warp::serve(server.routes()).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(serve);
+    let pruner =
+        manager.spawn_presence_pruner(Duration::from_millis(50), TIMEOUT);
+    let url = format!("ws://{addr}/collaboration");
+
+    let mut alice = connect(&url).await;
+    let mut bob = connect(&url).await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    bob.on_presence(move |event| {
+        let _ = tx.send(event);
+    })
+    .await;
+
+    // alice 的光标出现在 bob 一侧
+    alice
+        .set_presence(&PresenceState::new("alice", "Alice", "#f00").focus("a"))
+        .await
+        .unwrap();
+    let PresenceEvent::Joined { client_id, presence } =
+        next_event(&mut rx).await
+    else {
+        panic!("应先收到加入事件");
+    };
+    assert_eq!(presence.user_id, "alice");
+    assert_eq!(presence.focused_node.as_deref(), Some("a"));
+    assert_eq!(bob.remote_presences().await, vec![(client_id, presence)]);
+
+    // 持续刷新时超过超时时间也不会被清理
+    let started = Instant::now();
+    let mut last_refresh = started;
+    while started.elapsed() < TIMEOUT * 2 {
+        last_refresh = Instant::now();
+        alice
+            .update_presence(|p| p.focused_node = Some("b".into()))
+            .await
+            .unwrap();
+        tokio::time::sleep(TIMEOUT / 4).await;
+    }
+    while let Ok(event) = rx.try_recv() {
+        assert!(matches!(event, PresenceEvent::Updated { .. }), "{event:?}");
+    }
+    let visible = bob.remote_presences().await;
+    assert_eq!(visible.len(), 1);
+    assert_eq!(visible[0].1.focused_node.as_deref(), Some("b"));
+
+    // alice 停止刷新（连接仍在），超时后由服务端清理
+    loop {
+        match next_event(&mut rx).await {
+            PresenceEvent::Left { client_id: left } => {
+                assert_eq!(left, client_id);
+                break;
+            },
+            PresenceEvent::Updated { .. } => {},
+            event => panic!("意外的 presence 事件: {event:?}"),
+        }
+    }
+    assert!(last_refresh.elapsed() >= TIMEOUT);
+    assert!(bob.remote_presences().await.is_empty());
+
+    pruner.abort();
+    alice.disconnect().await;
+    bob.disconnect().await;
+}
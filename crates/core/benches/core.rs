@@ -81,6 +81,7 @@ fn bench_history_manager(c: &mut Criterion) {
                 max_entries: 1000,
                 enable_compression: true,
                 persistence_interval: Duration::from_secs(60),
+                ..Default::default()
             };
             let history_manager = HistoryManager::<String>::with_config(
                 "initial".to_string(),
@@ -6,8 +6,8 @@ use ractor::{Actor, ActorRef, ActorProcessingErr};
 use std::sync::Arc;
 
 use crate::{
-    debug::debug, error::ForgeResult, history_manager::HistoryManager,
-    types::HistoryEntryWithMeta,
+    debug::debug, error::ForgeResult, helpers::history_helper::HistoryHelper,
+    history_manager::HistoryManager, types::HistoryEntryWithMeta,
 };
 
 use mf_state::state::State;
@@ -149,23 +149,13 @@ impl Actor for StateActor {
                 }
 
                 // 记录事务到历史（不应用，因为已经在外部应用过了）
-                let entry = if transactions.len() == 1 {
-                    HistoryEntryWithMeta::new(
-                        transactions[0].clone(),
-                        new_state,
-                        description,
-                        meta,
-                    )
-                } else {
-                    HistoryEntryWithMeta::new_batch(
-                        transactions,
-                        new_state,
-                        description,
-                        meta,
-                    )
-                };
-
-                state.history_manager.insert(entry);
+                HistoryHelper::insert(
+                    &mut state.history_manager,
+                    new_state,
+                    transactions,
+                    description,
+                    meta,
+                );
                 state.version_counter += 1;
 
                 let _ = reply.send(Ok(()));
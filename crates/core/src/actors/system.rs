@@ -251,9 +251,9 @@ impl ForgeActorSystem {
             })?;
 
         // 创建全局资源管理器
-        let op_state = mf_state::ops::GlobalResourceManager::new();
+        let op_state = Arc::new(mf_state::ops::GlobalResourceManager::new());
         for op_fn in &op_fns {
-            op_fn(&op_state).map_err(|e| {
+            op_fn.run(&op_state).map_err(|e| {
                 ActorSystemError::ConfigurationError {
                     message: format!("执行操作函数失败: {e}"),
                 }
@@ -266,7 +266,7 @@ impl ForgeActorSystem {
             doc: None,
             stored_marks: None,
             plugins: Some(plugins),
-            resource_manager: Some(Arc::new(op_state)),
+            resource_manager: Some(op_state),
         };
 
         // 创建文档
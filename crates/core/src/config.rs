@@ -18,6 +18,9 @@
 //! // 使用预设环境配置
 //! let config = ForgeConfig::for_environment(Environment::Production);
 //!
+//! // 从 TOML/JSON 文件加载，未写出的字段取预设值
+//! let config = ForgeConfig::from_file("forge.toml")?;
+//!
 //! // 自定义配置
 //! let config = ForgeConfig::builder()
 //!     .processor_config(ProcessorConfig {
@@ -33,8 +36,14 @@
 //!     .build();
 //! ```
 
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
+use crate::debug::warn;
+
+/// 配置相关环境变量的前缀
+pub const ENV_PREFIX: &str = "FORGE_";
 
 /// 运行环境类型
 #[derive(
@@ -53,6 +62,16 @@ pub enum Environment {
 }
 
 /// 任务处理器配置
+///
+/// 运行中可通过 `reconfigure` 调整：
+/// - `max_queue_size`、`max_concurrent_tasks`：异步运行时立即生效，
+///   已排队的任务不会丢失；同步运行时没有工作池，这两项不起作用
+/// - `task_timeout`：对之后开始执行的任务生效（仅异步运行时）
+/// - `max_retries`、`retry_delay`：对之后开始执行的任务生效；同步运行时
+///   构造时使用固定的重试设置，只在调用 `reconfigure` 后读取这两项
+/// - `cleanup_timeout`：用于之后的关闭（仅异步运行时）
+///
+/// 其他配置项（如 `history`、`extension`、`snapshot`）在构造时确定
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessorConfig {
     /// 任务队列的最大容量
@@ -127,6 +146,9 @@ pub struct EventConfig {
     pub max_concurrent_handlers: usize,
     /// 事件处理器出错时是否抛出错误（false 则只记录错误日志）
     pub fail_on_handler_error: bool,
+    /// 回放缓冲区容量：保留最近分发的事件供后注册的处理器补齐，0 表示不启用
+    #[serde(default)]
+    pub replay_capacity: usize,
 }
 
 impl Default for EventConfig {
@@ -138,6 +160,7 @@ impl Default for EventConfig {
             batch_size: 100,
             max_concurrent_handlers: 5,
             fail_on_handler_error: false, // 默认不抛出错误，保持向后兼容
+            replay_capacity: 0,
         }
     }
 }
@@ -151,6 +174,9 @@ pub struct HistoryConfig {
     pub enable_compression: bool,
     /// 历史记录持久化间隔
     pub persistence_interval: Duration,
+    /// 插件或系统来源的事务如何计入历史记录
+    #[serde(default)]
+    pub non_user_transactions: NonUserHistoryMode,
 }
 
 impl Default for HistoryConfig {
@@ -159,10 +185,29 @@ impl Default for HistoryConfig {
             max_entries: 100,
             enable_compression: false,
             persistence_interval: Duration::from_secs(60),
+            non_user_transactions: NonUserHistoryMode::Record,
         }
     }
 }
 
+/// 插件或系统来源（见 `mf_state::TransactionOrigin`）的事务的历史记录方式
+///
+/// 只作用于单独提交的事务，同一次提交中插件追加的事务总是与根事务在同一条目中
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NonUserHistoryMode {
+    /// 与用户操作一样单独记录为一个条目
+    #[default]
+    Record,
+    /// 不单独记录，状态并入当前条目
+    Skip,
+    /// 并入触发它的条目（`caused_by` 指向当前条目中的事务），
+    /// 撤销该条目时一并撤销；找不到触发条目时单独记录
+    Merge,
+}
+
 /// 扩展系统配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtensionConfig {
@@ -220,6 +265,41 @@ impl Default for CacheConfig {
     }
 }
 
+/// 快照配置
+///
+/// 启用后由后台任务在满足触发条件且运行时空闲时写入快照，不会阻塞事务分发
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    /// 是否启用后台定时快照
+    pub enabled: bool,
+    /// 快照目录
+    pub directory: PathBuf,
+    /// 距上次快照超过该间隔后触发
+    pub interval: Option<Duration>,
+    /// 自上次快照累计该数量的事务后触发
+    pub every_transactions: Option<u64>,
+    /// 空闲判定：该时长内没有新事务才写入快照
+    pub idle_after: Duration,
+    /// 是否包含插件状态
+    pub include_plugin_states: bool,
+    /// 目录中保留的快照数量，为 0 时不清理
+    pub keep: usize,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: PathBuf::from("snapshots"),
+            interval: Some(Duration::from_secs(600)), // 10分钟
+            every_transactions: None,
+            idle_after: Duration::from_secs(5),
+            include_plugin_states: true,
+            keep: 3,
+        }
+    }
+}
+
 /// 运行时类型选择
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RuntimeType {
@@ -274,6 +354,13 @@ pub struct ForgeConfig {
     /// 缓存配置
     #[serde(default)]
     pub cache: CacheConfig,
+    /// 快照配置
+    #[serde(default)]
+    pub snapshot: SnapshotConfig,
+    /// 未识别的 `FORGE_` 前缀环境变量（变量名 -> 值），见
+    /// [`ForgeConfigBuilder::from_env`]
+    #[serde(default)]
+    pub extra: HashMap<String, String>,
 }
 
 impl ForgeConfig {
@@ -315,11 +402,13 @@ impl ForgeConfig {
                 batch_size: 50,
                 max_concurrent_handlers: 3,
                 fail_on_handler_error: false,
+                replay_capacity: 0,
             },
             history: HistoryConfig {
                 max_entries: 200,
                 enable_compression: false,
                 persistence_interval: Duration::from_secs(30),
+                non_user_transactions: NonUserHistoryMode::Record,
             },
             extension: ExtensionConfig {
                 load_timeout: Duration::from_secs(30),
@@ -336,6 +425,8 @@ impl ForgeConfig {
                 enable_lru: true,
                 cleanup_interval: Duration::from_secs(30),
             },
+            snapshot: SnapshotConfig::default(),
+            extra: HashMap::new(),
         }
     }
 
@@ -367,11 +458,13 @@ impl ForgeConfig {
                 batch_size: 20,
                 max_concurrent_handlers: 2,
                 fail_on_handler_error: false,
+                replay_capacity: 0,
             },
             history: HistoryConfig {
                 max_entries: 50,
                 enable_compression: false,
                 persistence_interval: Duration::from_secs(10),
+                non_user_transactions: NonUserHistoryMode::Record,
             },
             extension: ExtensionConfig {
                 load_timeout: Duration::from_secs(5),
@@ -388,6 +481,8 @@ impl ForgeConfig {
                 enable_lru: true,
                 cleanup_interval: Duration::from_secs(10),
             },
+            snapshot: SnapshotConfig::default(),
+            extra: HashMap::new(),
         }
     }
 
@@ -419,11 +514,13 @@ impl ForgeConfig {
                 batch_size: 500,
                 max_concurrent_handlers: 10,
                 fail_on_handler_error: false,
+                replay_capacity: 0,
             },
             history: HistoryConfig {
                 max_entries: 1000,
                 enable_compression: true,
                 persistence_interval: Duration::from_secs(300), // 5分钟
+                non_user_transactions: NonUserHistoryMode::Record,
             },
             extension: ExtensionConfig {
                 load_timeout: Duration::from_secs(10),
@@ -440,6 +537,8 @@ impl ForgeConfig {
                 enable_lru: true,
                 cleanup_interval: Duration::from_secs(300), // 5分钟
             },
+            snapshot: SnapshotConfig::default(),
+            extra: HashMap::new(),
         }
     }
 
@@ -519,6 +618,17 @@ impl ForgeConfig {
             });
         }
 
+        // 验证快照配置
+        if self.snapshot.enabled
+            && self.snapshot.interval.is_none()
+            && self.snapshot.every_transactions.is_none()
+        {
+            return Err(ConfigValidationError::MissingRequired {
+                field: "snapshot.interval 或 snapshot.every_transactions"
+                    .to_string(),
+            });
+        }
+
         Ok(())
     }
 
@@ -579,6 +689,10 @@ pub enum ConfigValidationError {
     Conflict { field1: String, field2: String, reason: String },
     /// 缺少必需的配置
     MissingRequired { field: String },
+    /// 未知的配置字段
+    UnknownField { field: String },
+    /// 配置文件无法读取或解析
+    InvalidFile { path: String, reason: String },
 }
 
 impl std::fmt::Display for ConfigValidationError {
@@ -596,6 +710,12 @@ impl std::fmt::Display for ConfigValidationError {
             ConfigValidationError::MissingRequired { field } => {
                 write!(f, "缺少必需的配置字段: {field}")
             },
+            ConfigValidationError::UnknownField { field } => {
+                write!(f, "未知的配置字段: {field}")
+            },
+            ConfigValidationError::InvalidFile { path, reason } => {
+                write!(f, "配置文件 '{path}' 无效: {reason}")
+            },
         }
     }
 }
@@ -682,6 +802,15 @@ impl ForgeConfigBuilder {
         self
     }
 
+    /// 设置快照配置
+    pub fn snapshot_config(
+        mut self,
+        config: SnapshotConfig,
+    ) -> Self {
+        self.config.snapshot = config;
+        self
+    }
+
     /// 设置任务队列大小
     pub fn max_queue_size(
         mut self,
@@ -746,6 +875,59 @@ impl ForgeConfigBuilder {
     pub fn build_unchecked(self) -> ForgeConfig {
         self.config
     }
+
+    /// 从环境变量构建并验证配置
+    ///
+    /// 以 `FORGE_ENVIRONMENT` 对应的预设为默认值，字段变量名为
+    /// `FORGE_<分组>_<字段>`，如 `FORGE_EVENT_BATCH_SIZE`；时长以毫秒为单位并带
+    /// `_MS` 后缀，如 `FORGE_PROCESSOR_TASK_TIMEOUT_MS`。另支持简写
+    /// `FORGE_MAX_HISTORY`（历史记录条数）与 `FORGE_PROCESSOR_THREADS`
+    /// （并发任务数）。布尔值接受 `true`/`false`/`1`/`0`，
+    /// 无法识别的 `FORGE_` 变量收集到 [`ForgeConfig::extra`]
+    pub fn from_env() -> Result<ForgeConfig, ConfigValidationError> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// 同 [`Self::from_env`]，从给定的变量集合读取
+    pub fn from_vars<I, K, V>(
+        vars: I
+    ) -> Result<ForgeConfig, ConfigValidationError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let vars = forge_vars(vars);
+        let environment = env_environment(&vars)?.unwrap_or_default();
+        let mut config = ForgeConfig::for_environment(environment);
+        config.environment = environment;
+        config.apply_env_vars(vars)?;
+        Self::from_config(config).build()
+    }
+
+    /// 从配置文件加载后再应用环境变量覆盖
+    ///
+    /// 文件格式见 [`ForgeConfig::from_file`]，变量规则同 [`Self::from_env`]，
+    /// `FORGE_ENVIRONMENT` 只覆盖 `environment` 字段而不会重新套用预设
+    pub fn from_file_with_env(
+        path: impl AsRef<Path>
+    ) -> Result<ForgeConfig, ConfigValidationError> {
+        Self::from_file_with_vars(path, std::env::vars())
+    }
+
+    /// 同 [`Self::from_file_with_env`]，从给定的变量集合读取
+    pub fn from_file_with_vars<I, K, V>(
+        path: impl AsRef<Path>,
+        vars: I,
+    ) -> Result<ForgeConfig, ConfigValidationError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let config = ForgeConfig::from_file(path)?.with_env_vars(vars)?;
+        Self::from_config(config).build()
+    }
 }
 
 impl Default for ForgeConfigBuilder {
@@ -766,59 +948,327 @@ impl ForgeConfig {
         serde_json::to_string_pretty(self)
     }
 
-    /// 从环境变量加载配置覆盖
+    /// 从 TOML 或 JSON 文件加载并验证配置
     ///
-    /// 支持的环境变量格式：
-    /// - FORGE_ENVIRONMENT: 运行环境
-    /// - FORGE_PROCESSOR_MAX_QUEUE_SIZE: 队列大小
-    /// - FORGE_PROCESSOR_MAX_CONCURRENT_TASKS: 并发任务数
-    /// - FORGE_PERFORMANCE_ENABLE_MONITORING: 启用监控
-    /// - 等等...
-    pub fn from_env_override(mut self) -> Self {
-        use std::env;
-
-        // 环境类型
-        if let Ok(env_str) = env::var("FORGE_ENVIRONMENT") {
-            match env_str.to_lowercase().as_str() {
-                "development" | "dev" => {
-                    self.environment = Environment::Development
-                },
-                "testing" | "test" => self.environment = Environment::Testing,
-                "production" | "prod" => {
-                    self.environment = Environment::Production
-                },
-                "custom" => self.environment = Environment::Custom,
-                _ => {},
-            }
-        }
+    /// 按扩展名（`.toml`/`.json`）识别格式。文件只需包含要覆盖的字段，
+    /// 其余字段取 `environment` 对应的预设；时长写作 `"1m 30s"`、`"500ms"` 等字符串，
+    /// 或 `{ secs = 30, nanos = 0 }`。
+    /// 未知字段、类型不符与超出范围的值都会带字段路径报告，
+    /// 如 `processor.max_queue_size`
+    pub fn from_file(
+        path: impl AsRef<Path>
+    ) -> Result<Self, ConfigValidationError> {
+        let path = path.as_ref();
+        let invalid_file =
+            |reason: String| ConfigValidationError::InvalidFile {
+                path: path.display().to_string(),
+                reason,
+            };
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| invalid_file(format!("读取失败: {e}")))?;
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        let value = match extension.map(str::to_ascii_lowercase).as_deref() {
+            Some("toml") => toml::from_str(&content)
+                .map_err(|e| invalid_file(format!("TOML 解析失败: {e}")))?,
+            Some("json") => serde_json::from_str(&content)
+                .map_err(|e| invalid_file(format!("JSON 解析失败: {e}")))?,
+            _ => {
+                return Err(invalid_file(
+                    "仅支持 .toml 与 .json 文件".to_string(),
+                ));
+            },
+        };
+        Self::from_value(value)
+    }
 
-        // 处理器配置
-        if let Ok(size) = env::var("FORGE_PROCESSOR_MAX_QUEUE_SIZE") {
-            if let Ok(size) = size.parse::<usize>() {
-                self.processor.max_queue_size = size;
-            }
-        }
+    /// 在预设之上应用配置值并验证，规则同 [`Self::from_file`]
+    pub fn from_value(
+        value: serde_json::Value
+    ) -> Result<Self, ConfigValidationError> {
+        let serde_json::Value::Object(mut overrides) = value else {
+            return Err(ConfigValidationError::InvalidValue {
+                field: "<root>".to_string(),
+                value: value.to_string(),
+                reason: "配置必须是对象".to_string(),
+            });
+        };
+        let environment = match overrides.remove("environment") {
+            Some(value) => value
+                .as_str()
+                .and_then(parse_environment)
+                .ok_or_else(|| ConfigValidationError::InvalidValue {
+                    field: "environment".to_string(),
+                    value: value.to_string(),
+                    reason: "应为 development、testing、production 或 custom"
+                        .to_string(),
+                })?,
+            None => Environment::default(),
+        };
+
+        let preset = Self::for_environment(environment);
+        let base = serde_json::to_value(&preset).unwrap_or_default();
+        let shape = preset.shape();
+        let mut merged = base.clone();
+        let mut leaves = Vec::new();
+        merge_config_value(
+            &mut merged,
+            &shape,
+            serde_json::Value::Object(overrides),
+            "",
+            &mut leaves,
+        )?;
+        let mut config: Self = serde_json::from_value(merged).map_err(|e| {
+            invalid_leaf(&base, &shape, leaves).unwrap_or_else(|| {
+                ConfigValidationError::InvalidValue {
+                    field: "<root>".to_string(),
+                    value: String::new(),
+                    reason: e.to_string(),
+                }
+            })
+        })?;
+        config.environment = environment;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 可选字段都填上值后的 JSON，用于检查配置值的类型
+    ///
+    /// 新增可选字段时需在此填充，否则该字段不做类型检查
+    fn shape(mut self) -> serde_json::Value {
+        self.snapshot.interval.get_or_insert(Duration::ZERO);
+        self.snapshot.every_transactions.get_or_insert(0);
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// 在当前配置上应用环境变量覆盖
+    ///
+    /// 变量规则同 [`ForgeConfigBuilder::from_env`]，`FORGE_ENVIRONMENT`
+    /// 只覆盖 `environment` 字段；无法解析的值记录警告后跳过，结果不做验证。
+    /// 需要在值无效时报错请使用 [`Self::with_env_vars`]
+    pub fn from_env_override(self) -> Self {
+        self.with_env_vars_lenient(std::env::vars())
+    }
 
-        if let Ok(tasks) = env::var("FORGE_PROCESSOR_MAX_CONCURRENT_TASKS") {
-            if let Ok(tasks) = tasks.parse::<usize>() {
-                self.processor.max_concurrent_tasks = tasks;
+    /// 同 [`Self::from_env_override`]，从给定的变量集合读取
+    fn with_env_vars_lenient<I, K, V>(
+        mut self,
+        vars: I,
+    ) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let vars = forge_vars(vars);
+        match env_environment(&vars) {
+            Ok(Some(environment)) => self.environment = environment,
+            Ok(None) => {},
+            Err(e) => warn!("忽略环境变量: {}", e),
+        }
+        for (key, value) in vars {
+            if key == "FORGE_ENVIRONMENT" {
+                continue;
+            }
+            match self.apply_env_var(&key, &value) {
+                Ok(true) => {},
+                Ok(false) => {
+                    self.extra.insert(key, value);
+                },
+                Err(e) => warn!("忽略环境变量: {}", e),
             }
         }
+        self
+    }
 
-        // 性能配置
-        if let Ok(enable) = env::var("FORGE_PERFORMANCE_ENABLE_MONITORING") {
-            self.performance.enable_monitoring =
-                enable.to_lowercase() == "true";
+    /// 在当前配置上应用给定的环境变量覆盖，无法解析的值返回错误
+    ///
+    /// 变量规则同 [`Self::from_env_override`]
+    pub fn with_env_vars<I, K, V>(
+        mut self,
+        vars: I,
+    ) -> Result<Self, ConfigValidationError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let vars = forge_vars(vars);
+        if let Some(environment) = env_environment(&vars)? {
+            self.environment = environment;
         }
+        self.apply_env_vars(vars)?;
+        Ok(self)
+    }
 
-        if let Ok(timeout) = env::var("FORGE_PERFORMANCE_MIDDLEWARE_TIMEOUT_MS")
-        {
-            if let Ok(timeout) = timeout.parse::<u64>() {
-                self.performance.middleware_timeout_ms = timeout;
+    /// 应用 `FORGE_` 变量，无法识别的变量收集到 `extra`
+    fn apply_env_vars(
+        &mut self,
+        vars: BTreeMap<String, String>,
+    ) -> Result<(), ConfigValidationError> {
+        for (key, value) in vars {
+            if key == "FORGE_ENVIRONMENT" {
+                continue;
+            }
+            if !self.apply_env_var(&key, &value)? {
+                self.extra.insert(key, value);
             }
         }
+        Ok(())
+    }
 
-        self
+    /// 按变量名设置对应字段，变量名无法识别时返回 `false`
+    fn apply_env_var(
+        &mut self,
+        key: &str,
+        value: &str,
+    ) -> Result<bool, ConfigValidationError> {
+        match key {
+            "FORGE_RUNTIME_TYPE" => {
+                self.runtime.runtime_type = parse_runtime_type(value)
+                    .ok_or_else(|| {
+                        invalid_env(
+                            key,
+                            value,
+                            "应为 auto、sync、async 或 actor",
+                        )
+                    })?
+            },
+            // 处理器配置
+            "FORGE_PROCESSOR_MAX_QUEUE_SIZE" => {
+                self.processor.max_queue_size = env_number(key, value)?
+            },
+            "FORGE_PROCESSOR_MAX_CONCURRENT_TASKS"
+            | "FORGE_PROCESSOR_THREADS" => {
+                self.processor.max_concurrent_tasks = env_number(key, value)?
+            },
+            "FORGE_PROCESSOR_TASK_TIMEOUT_MS" => {
+                self.processor.task_timeout = env_millis(key, value)?
+            },
+            "FORGE_PROCESSOR_MAX_RETRIES" => {
+                self.processor.max_retries = env_number(key, value)?
+            },
+            "FORGE_PROCESSOR_RETRY_DELAY_MS" => {
+                self.processor.retry_delay = env_millis(key, value)?
+            },
+            "FORGE_PROCESSOR_CLEANUP_TIMEOUT_MS" => {
+                self.processor.cleanup_timeout = env_millis(key, value)?
+            },
+            // 性能配置
+            "FORGE_PERFORMANCE_ENABLE_MONITORING" => {
+                self.performance.enable_monitoring = env_bool(key, value)?
+            },
+            "FORGE_PERFORMANCE_MIDDLEWARE_TIMEOUT_MS" => {
+                self.performance.middleware_timeout_ms = env_number(key, value)?
+            },
+            "FORGE_PERFORMANCE_LOG_THRESHOLD_MS" => {
+                self.performance.log_threshold_ms = env_number(key, value)?
+            },
+            "FORGE_PERFORMANCE_TASK_RECEIVE_TIMEOUT_MS" => {
+                self.performance.task_receive_timeout_ms =
+                    env_number(key, value)?
+            },
+            "FORGE_PERFORMANCE_ENABLE_DETAILED_LOGGING" => {
+                self.performance.enable_detailed_logging = env_bool(key, value)?
+            },
+            "FORGE_PERFORMANCE_METRICS_SAMPLING_RATE" => {
+                self.performance.metrics_sampling_rate = env_number(key, value)?
+            },
+            // 事件配置
+            "FORGE_EVENT_MAX_QUEUE_SIZE" => {
+                self.event.max_queue_size = env_number(key, value)?
+            },
+            "FORGE_EVENT_HANDLER_TIMEOUT_MS" => {
+                self.event.handler_timeout = env_millis(key, value)?
+            },
+            "FORGE_EVENT_ENABLE_PERSISTENCE" => {
+                self.event.enable_persistence = env_bool(key, value)?
+            },
+            "FORGE_EVENT_BATCH_SIZE" => {
+                self.event.batch_size = env_number(key, value)?
+            },
+            "FORGE_EVENT_MAX_CONCURRENT_HANDLERS" => {
+                self.event.max_concurrent_handlers = env_number(key, value)?
+            },
+            "FORGE_EVENT_FAIL_ON_HANDLER_ERROR" => {
+                self.event.fail_on_handler_error = env_bool(key, value)?
+            },
+            "FORGE_EVENT_REPLAY_CAPACITY" => {
+                self.event.replay_capacity = env_number(key, value)?
+            },
+            // 历史记录配置
+            "FORGE_HISTORY_MAX_ENTRIES" | "FORGE_MAX_HISTORY" => {
+                self.history.max_entries = env_number(key, value)?
+            },
+            "FORGE_HISTORY_ENABLE_COMPRESSION" => {
+                self.history.enable_compression = env_bool(key, value)?
+            },
+            "FORGE_HISTORY_PERSISTENCE_INTERVAL_MS" => {
+                self.history.persistence_interval = env_millis(key, value)?
+            },
+            // 扩展配置
+            "FORGE_EXTENSION_LOAD_TIMEOUT_MS" => {
+                self.extension.load_timeout = env_millis(key, value)?
+            },
+            "FORGE_EXTENSION_ENABLE_HOT_RELOAD" => {
+                self.extension.enable_hot_reload = env_bool(key, value)?
+            },
+            "FORGE_EXTENSION_MAX_MEMORY_MB" => {
+                self.extension.max_memory_mb = env_number(key, value)?
+            },
+            "FORGE_EXTENSION_ENABLE_SANDBOX" => {
+                self.extension.enable_sandbox = env_bool(key, value)?
+            },
+            "FORGE_EXTENSION_XML_SCHEMA_PATHS" => {
+                // 与 PATH 相同的分隔符（Unix 下为 `:`，Windows 下为 `;`）
+                self.extension.xml_schema_paths = std::env::split_paths(value)
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .filter(|path| !path.is_empty())
+                    .collect()
+            },
+            "FORGE_EXTENSION_ENABLE_XML_AUTO_RELOAD" => {
+                self.extension.enable_xml_auto_reload = env_bool(key, value)?
+            },
+            "FORGE_EXTENSION_XML_PARSE_TIMEOUT_MS" => {
+                self.extension.xml_parse_timeout = env_millis(key, value)?
+            },
+            // 缓存配置
+            "FORGE_CACHE_MAX_ENTRIES" => {
+                self.cache.max_entries = env_number(key, value)?
+            },
+            "FORGE_CACHE_ENTRY_TTL_MS" => {
+                self.cache.entry_ttl = env_millis(key, value)?
+            },
+            "FORGE_CACHE_ENABLE_LRU" => {
+                self.cache.enable_lru = env_bool(key, value)?
+            },
+            "FORGE_CACHE_CLEANUP_INTERVAL_MS" => {
+                self.cache.cleanup_interval = env_millis(key, value)?
+            },
+            // 快照配置
+            "FORGE_SNAPSHOT_ENABLED" => {
+                self.snapshot.enabled = env_bool(key, value)?
+            },
+            "FORGE_SNAPSHOT_DIRECTORY" => {
+                self.snapshot.directory = PathBuf::from(value)
+            },
+            "FORGE_SNAPSHOT_INTERVAL_MS" => {
+                self.snapshot.interval = Some(env_millis(key, value)?)
+            },
+            "FORGE_SNAPSHOT_EVERY_TRANSACTIONS" => {
+                self.snapshot.every_transactions = Some(env_number(key, value)?)
+            },
+            "FORGE_SNAPSHOT_IDLE_MS" => {
+                self.snapshot.idle_after = env_millis(key, value)?
+            },
+            "FORGE_SNAPSHOT_INCLUDE_PLUGIN_STATES" => {
+                self.snapshot.include_plugin_states = env_bool(key, value)?
+            },
+            "FORGE_SNAPSHOT_KEEP" => {
+                self.snapshot.keep = env_number(key, value)?
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
     }
 
     /// 合并另一个配置，优先使用 other 的非默认值
@@ -843,3 +1293,482 @@ impl ForgeConfig {
         self
     }
 }
+
+fn parse_environment(value: &str) -> Option<Environment> {
+    match value.to_lowercase().as_str() {
+        "development" | "dev" => Some(Environment::Development),
+        "testing" | "test" => Some(Environment::Testing),
+        "production" | "prod" => Some(Environment::Production),
+        "custom" => Some(Environment::Custom),
+        _ => None,
+    }
+}
+
+/// 只保留 `FORGE_` 前缀的变量
+fn forge_vars<I, K, V>(vars: I) -> BTreeMap<String, String>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<String>,
+{
+    vars.into_iter()
+        .map(|(key, value)| (key.into(), value.into()))
+        .filter(|(key, _)| key.starts_with(ENV_PREFIX))
+        .collect()
+}
+
+fn env_environment(
+    vars: &BTreeMap<String, String>
+) -> Result<Option<Environment>, ConfigValidationError> {
+    vars.get("FORGE_ENVIRONMENT")
+        .map(|value| {
+            parse_environment(value).ok_or_else(|| {
+                invalid_env(
+                    "FORGE_ENVIRONMENT",
+                    value,
+                    "应为 development、testing、production 或 custom",
+                )
+            })
+        })
+        .transpose()
+}
+
+/// 把配置文件中的值合并到预设上
+///
+/// 对象逐字段合并，预设中不存在的字段视为未知字段；叶子值的类型须与 `shape`
+/// 一致，预设为 `null` 的可选字段按 `shape` 中的值检查。时长字段另接受字符串。
+/// `extra` 整体替换。写入的叶子按 `(路径, 原始值)` 记录到 `leaves`
+fn merge_config_value(
+    base: &mut serde_json::Value,
+    shape: &serde_json::Value,
+    overlay: serde_json::Value,
+    path: &str,
+    leaves: &mut Vec<(String, serde_json::Value)>,
+) -> Result<(), ConfigValidationError> {
+    use serde_json::Value;
+
+    let mismatch =
+        |value: &Value, reason: &str| ConfigValidationError::InvalidValue {
+            field: path.to_string(),
+            value: value.to_string(),
+            reason: reason.to_string(),
+        };
+    if path == "extra" {
+        let is_string_map = overlay
+            .as_object()
+            .is_some_and(|map| map.values().all(Value::is_string));
+        if !is_string_map {
+            return Err(mismatch(&overlay, "应为字符串到字符串的映射"));
+        }
+        leaves.push((path.to_string(), overlay.clone()));
+        *base = overlay;
+        return Ok(());
+    }
+    if base.is_null() && !overlay.is_null() {
+        *base = shape.clone();
+    }
+    match overlay {
+        Value::String(text) if is_duration(shape) => {
+            let duration = parse_duration(&text).ok_or_else(|| {
+                mismatch(&Value::from(text.as_str()), "应为时长，如 \"1m 30s\"")
+            })?;
+            *base = serde_json::json!({
+                "secs": duration.as_secs(),
+                "nanos": duration.subsec_nanos(),
+            });
+            leaves.push((path.to_string(), Value::String(text)));
+        },
+        Value::Object(overlay) if shape.is_object() => {
+            for (key, value) in overlay {
+                let field = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match (base.get_mut(&key), shape.get(&key)) {
+                    (Some(slot), Some(shape)) => {
+                        merge_config_value(slot, shape, value, &field, leaves)?
+                    },
+                    _ => {
+                        return Err(ConfigValidationError::UnknownField {
+                            field,
+                        });
+                    },
+                }
+            }
+        },
+        overlay => {
+            let reason = match shape {
+                Value::Bool(_) if !overlay.is_boolean() => Some("应为布尔值"),
+                Value::Number(n) if n.is_u64() && !overlay.is_u64() => {
+                    Some("应为非负整数")
+                },
+                Value::Number(_) if !overlay.is_number() => Some("应为数值"),
+                Value::String(_) if !overlay.is_string() => Some("应为字符串"),
+                Value::Array(_) if !overlay.is_array() => Some("应为数组"),
+                Value::Object(_) if is_duration(shape) => {
+                    Some("应为时长，如 \"1m 30s\"")
+                },
+                Value::Object(_) => Some("应为对象"),
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                return Err(mismatch(&overlay, reason));
+            }
+            leaves.push((path.to_string(), overlay.clone()));
+            *base = overlay;
+        },
+    }
+    Ok(())
+}
+
+/// 合并后反序列化失败时，逐个叶子单独应用到预设上，找出无法反序列化的字段
+fn invalid_leaf(
+    base: &serde_json::Value,
+    shape: &serde_json::Value,
+    leaves: Vec<(String, serde_json::Value)>,
+) -> Option<ConfigValidationError> {
+    leaves.into_iter().find_map(|(path, value)| {
+        let overlay = path.rsplit('.').fold(
+            value.clone(),
+            |inner, key| serde_json::json!({ key: inner }),
+        );
+        let mut trial = base.clone();
+        merge_config_value(&mut trial, shape, overlay, "", &mut Vec::new())
+            .ok()?;
+        let err = serde_json::from_value::<ForgeConfig>(trial).err()?;
+        Some(ConfigValidationError::InvalidValue {
+            field: path,
+            value: value.to_string(),
+            reason: err.to_string(),
+        })
+    })
+}
+
+/// 预设中的时长序列化为 `{ secs, nanos }`
+fn is_duration(shape: &serde_json::Value) -> bool {
+    shape.as_object().is_some_and(|map| {
+        map.len() == 2 && map.contains_key("secs") && map.contains_key("nanos")
+    })
+}
+
+/// 解析 `"1h 30m"`、`"1500ms"` 形式的时长，各段为整数加单位，可用空格分隔
+fn parse_duration(text: &str) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let digits =
+            rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let number: u64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let part = match &rest[..unit_len] {
+            "ns" | "nsec" => Duration::from_nanos(number),
+            "us" | "µs" | "usec" => Duration::from_micros(number),
+            "ms" | "msec" => Duration::from_millis(number),
+            "s" | "sec" | "secs" | "second" | "seconds" => {
+                Duration::from_secs(number)
+            },
+            "m" | "min" | "mins" | "minute" | "minutes" => {
+                Duration::from_secs(number.checked_mul(60)?)
+            },
+            "h" | "hr" | "hrs" | "hour" | "hours" => {
+                Duration::from_secs(number.checked_mul(3600)?)
+            },
+            "d" | "day" | "days" => {
+                Duration::from_secs(number.checked_mul(86400)?)
+            },
+            _ => return None,
+        };
+        total = total.checked_add(part)?;
+        rest = rest[unit_len..].trim_start();
+    }
+    Some(total)
+}
+
+fn parse_runtime_type(value: &str) -> Option<RuntimeType> {
+    match value.to_lowercase().as_str() {
+        "auto" => Some(RuntimeType::Auto),
+        "sync" => Some(RuntimeType::Sync),
+        "async" => Some(RuntimeType::Async),
+        "actor" => Some(RuntimeType::Actor),
+        _ => None,
+    }
+}
+
+fn invalid_env(
+    key: &str,
+    value: &str,
+    reason: &str,
+) -> ConfigValidationError {
+    ConfigValidationError::InvalidValue {
+        field: key.to_string(),
+        value: value.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+fn env_number<T: std::str::FromStr>(
+    key: &str,
+    value: &str,
+) -> Result<T, ConfigValidationError> {
+    value.trim().parse().map_err(|_| invalid_env(key, value, "不是有效的数值"))
+}
+
+fn env_millis(
+    key: &str,
+    value: &str,
+) -> Result<Duration, ConfigValidationError> {
+    env_number(key, value).map(Duration::from_millis)
+}
+
+fn env_bool(
+    key: &str,
+    value: &str,
+) -> Result<bool, ConfigValidationError> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(invalid_env(key, value, "应为 true、false、1 或 0")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_vars() {
+        let config = ForgeConfigBuilder::from_vars([
+            ("FORGE_ENVIRONMENT", "production"),
+            ("FORGE_MAX_HISTORY", "20"),
+            ("FORGE_PROCESSOR_THREADS", "4"),
+            ("FORGE_PROCESSOR_TASK_TIMEOUT_MS", "1500"),
+            ("FORGE_EVENT_ENABLE_PERSISTENCE", "0"),
+            ("FORGE_CACHE_ENABLE_LRU", "FALSE"),
+            ("FORGE_RUNTIME_TYPE", "actor"),
+            ("FORGE_CACHE_SIZE_MB", "64"),
+            ("PATH", "/usr/bin"),
+        ])
+        .unwrap();
+
+        assert_eq!(config.environment, Environment::Production);
+        assert_eq!(config.history.max_entries, 20);
+        assert_eq!(config.processor.max_concurrent_tasks, 4);
+        assert_eq!(config.processor.task_timeout, Duration::from_millis(1500));
+        assert!(!config.event.enable_persistence);
+        assert!(!config.cache.enable_lru);
+        assert_eq!(config.runtime.runtime_type, RuntimeType::Actor);
+        // 未设置的字段沿用生产环境预设
+        assert_eq!(config.processor.max_queue_size, 10000);
+        assert_eq!(
+            config.extra,
+            HashMap::from([(
+                "FORGE_CACHE_SIZE_MB".to_string(),
+                "64".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn test_from_vars_errors() {
+        let err = ForgeConfigBuilder::from_vars([(
+            "FORGE_EVENT_ENABLE_PERSISTENCE",
+            "yes",
+        )])
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigValidationError::InvalidValue { ref field, .. }
+                if field == "FORGE_EVENT_ENABLE_PERSISTENCE"
+        ));
+        assert!(
+            ForgeConfigBuilder::from_vars([("FORGE_ENVIRONMENT", "staging")])
+                .is_err()
+        );
+        assert!(
+            ForgeConfigBuilder::from_vars([("FORGE_MAX_HISTORY", "-1")])
+                .is_err()
+        );
+        // 解析成功但未通过验证
+        assert!(
+            ForgeConfigBuilder::from_vars([("FORGE_MAX_HISTORY", "0")])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_with_env_vars() {
+        let config = ForgeConfig::for_environment(Environment::Testing)
+            .with_env_vars([
+                ("FORGE_ENVIRONMENT", "production"),
+                ("FORGE_PROCESSOR_THREADS", "6"),
+                ("FORGE_PERFORMANCE_ENABLE_MONITORING", "1"),
+                ("FORGE_CACHE_SIZE_MB", "64"),
+            ])
+            .unwrap();
+        // 只覆盖 environment 字段，其余沿用原配置
+        assert_eq!(config.environment, Environment::Production);
+        assert_eq!(
+            config.processor.max_queue_size,
+            ForgeConfig::for_environment(Environment::Testing)
+                .processor
+                .max_queue_size
+        );
+        assert_eq!(config.processor.max_concurrent_tasks, 6);
+        assert!(config.performance.enable_monitoring);
+        assert_eq!(config.extra["FORGE_CACHE_SIZE_MB"], "64");
+
+        let vars = [
+            ("FORGE_ENVIRONMENT", "staging"),
+            ("FORGE_PROCESSOR_MAX_QUEUE_SIZE", "many"),
+            ("FORGE_PROCESSOR_THREADS", "6"),
+        ];
+        // 宽松覆盖跳过无效值，其余变量照常生效
+        let lenient = ForgeConfig::default().with_env_vars_lenient(vars);
+        assert_eq!(lenient.environment, ForgeConfig::default().environment);
+        assert_eq!(
+            lenient.processor.max_queue_size,
+            ForgeConfig::default().processor.max_queue_size
+        );
+        assert_eq!(lenient.processor.max_concurrent_tasks, 6);
+
+        let err = ForgeConfig::default()
+            .with_env_vars([("FORGE_PROCESSOR_MAX_QUEUE_SIZE", "many")])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigValidationError::InvalidValue { ref field, .. }
+                if field == "FORGE_PROCESSOR_MAX_QUEUE_SIZE"
+        ));
+    }
+
+    const FIXTURE: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/forge_config.toml");
+
+    #[test]
+    fn test_from_file() {
+        let config = ForgeConfig::from_file(FIXTURE).unwrap();
+        assert_eq!(config.environment, Environment::Production);
+        assert_eq!(config.runtime.runtime_type, RuntimeType::Actor);
+        assert_eq!(config.processor.max_queue_size, 2000);
+        assert_eq!(config.processor.task_timeout, Duration::from_secs(15));
+        assert_eq!(config.performance.metrics_sampling_rate, 0.05);
+        assert_eq!(config.extension.xml_schema_paths.len(), 2);
+        assert!(config.snapshot.enabled);
+        assert_eq!(config.snapshot.every_transactions, Some(100));
+        assert_eq!(config.extra["FORGE_CACHE_SIZE_MB"], "64");
+        // 未写入文件的字段沿用生产环境预设
+        assert_eq!(config.processor.max_retries, 3);
+        assert_eq!(config.snapshot.interval, Some(Duration::from_secs(600)));
+
+        // 写出为 JSON 后重新加载，结果一致
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join("forge.json");
+        std::fs::write(&json_path, config.to_json().unwrap()).unwrap();
+        let reloaded = ForgeConfig::from_file(&json_path).unwrap();
+        assert_eq!(reloaded.to_json().unwrap(), config.to_json().unwrap());
+
+        let config = ForgeConfigBuilder::from_file_with_vars(
+            FIXTURE,
+            [("FORGE_PROCESSOR_MAX_QUEUE_SIZE", "3000")],
+        )
+        .unwrap();
+        assert_eq!(config.processor.max_queue_size, 3000);
+        assert_eq!(config.processor.max_concurrent_tasks, 8);
+    }
+
+    #[test]
+    fn test_from_value_durations() {
+        let config = ForgeConfig::from_value(serde_json::json!({
+            "processor": { "task_timeout": "1m 30s", "retry_delay": "250ms" },
+            "snapshot": { "interval": "2h", "every_transactions": 50 },
+            "cache": { "entry_ttl": { "secs": 7, "nanos": 0 } },
+        }))
+        .unwrap();
+        assert_eq!(config.processor.task_timeout, Duration::from_secs(90));
+        assert_eq!(config.processor.retry_delay, Duration::from_millis(250));
+        assert_eq!(config.snapshot.interval, Some(Duration::from_secs(7200)));
+        assert_eq!(config.snapshot.every_transactions, Some(50));
+        assert_eq!(config.cache.entry_ttl, Duration::from_secs(7));
+
+        assert_eq!(parse_duration("1d2h"), Some(Duration::from_secs(93600)));
+        assert_eq!(parse_duration("15"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
+    #[test]
+    fn test_from_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let load = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            ForgeConfig::from_file(path).unwrap_err()
+        };
+
+        let err = load("a.toml", "[processor]\nmax_queue = 10\n");
+        assert!(matches!(
+            err,
+            ConfigValidationError::UnknownField { ref field }
+                if field == "processor.max_queue"
+        ));
+        let err = load("b.toml", "[processor]\nmax_queue_size = 0\n");
+        assert!(matches!(
+            err,
+            ConfigValidationError::InvalidValue { ref field, .. }
+                if field == "processor.max_queue_size"
+        ));
+        let err = load("c.json", r#"{"processor": {"max_retries": -1}}"#);
+        assert!(matches!(
+            err,
+            ConfigValidationError::InvalidValue { ref field, .. }
+                if field == "processor.max_retries"
+        ));
+        let err = load("d.json", r#"{"event": {"enable_persistence": "yes"}}"#);
+        assert!(matches!(
+            err,
+            ConfigValidationError::InvalidValue { ref field, .. }
+                if field == "event.enable_persistence"
+        ));
+        let err = load("e.toml", "environment = \"staging\"\n");
+        assert!(matches!(
+            err,
+            ConfigValidationError::InvalidValue { ref field, .. }
+                if field == "environment"
+        ));
+        // 预设为 null 的可选字段同样检查类型
+        let err =
+            load("h.json", r#"{"snapshot": {"every_transactions": "many"}}"#);
+        assert!(matches!(
+            err,
+            ConfigValidationError::InvalidValue { ref field, .. }
+                if field == "snapshot.every_transactions"
+        ));
+        let err = load("i.toml", "[processor]\ntask_timeout = \"soon\"\n");
+        assert!(matches!(
+            err,
+            ConfigValidationError::InvalidValue { ref field, .. }
+                if field == "processor.task_timeout"
+        ));
+        // 类型正确但无法反序列化时报告具体字段
+        let err = load("j.toml", "[runtime]\nruntime_type = \"Fiber\"\n");
+        assert!(matches!(
+            err,
+            ConfigValidationError::InvalidValue { ref field, .. }
+                if field == "runtime.runtime_type"
+        ));
+        assert!(matches!(
+            load("f.yaml", "processor: {}"),
+            ConfigValidationError::InvalidFile { .. }
+        ));
+        assert!(matches!(
+            load("g.toml", "[processor"),
+            ConfigValidationError::InvalidFile { .. }
+        ));
+    }
+}
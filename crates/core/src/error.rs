@@ -1,3 +1,4 @@
+use mf_error_codes::{ErrorCode, ErrorWire, ToWire, find_code, source_chain};
 use thiserror::Error;
 
 /// 统一的 Forge 错误类型
@@ -94,6 +95,10 @@ pub enum ForgeError {
     #[error("操作超时: {operation} (超时时间: {timeout_ms}ms)")]
     Timeout { operation: String, timeout_ms: u64 },
 
+    /// 操作被取消
+    #[error("操作已取消: {operation}")]
+    Cancelled { operation: String },
+
     /// 资源不足错误
     #[error("资源不足: {resource_type}")]
     ResourceExhausted {
@@ -135,31 +140,39 @@ pub enum ForgeError {
 pub type ForgeResult<T> = Result<T, ForgeError>;
 
 impl ForgeError {
-    /// 获取错误代码，用于程序化处理
-    pub fn error_code(&self) -> &'static str {
+    /// 获取错误码
+    pub fn code(&self) -> ErrorCode {
         match self {
-            ForgeError::State { .. } => "STATE_ERROR",
-            ForgeError::Event { .. } => "EVENT_ERROR",
-            ForgeError::Middleware { .. } => "MIDDLEWARE_ERROR",
-            ForgeError::Extension { .. } => "EXTENSION_ERROR",
-            ForgeError::Transaction { .. } => "TRANSACTION_ERROR",
-            ForgeError::History { .. } => "HISTORY_ERROR",
-            ForgeError::Config { .. } => "CONFIG_ERROR",
-            ForgeError::Storage { .. } => "STORAGE_ERROR",
-            ForgeError::Cache { .. } => "CACHE_ERROR",
-            ForgeError::Engine { .. } => "ENGINE_ERROR",
-            ForgeError::Timeout { .. } => "TIMEOUT_ERROR",
-            ForgeError::ResourceExhausted { .. } => "RESOURCE_EXHAUSTED",
-            ForgeError::Concurrency { .. } => "CONCURRENCY_ERROR",
-            ForgeError::Validation { .. } => "VALIDATION_ERROR",
+            ForgeError::State { .. } => ErrorCode::State,
+            ForgeError::Event { .. } => ErrorCode::Event,
+            ForgeError::Middleware { .. } => ErrorCode::Middleware,
+            ForgeError::Extension { .. } => ErrorCode::Extension,
+            ForgeError::Transaction { .. } => ErrorCode::Transaction,
+            ForgeError::History { .. } => ErrorCode::History,
+            ForgeError::Config { .. } => ErrorCode::Config,
+            ForgeError::Storage { .. } => ErrorCode::Storage,
+            ForgeError::Cache { .. } => ErrorCode::Cache,
+            ForgeError::Engine { .. } => ErrorCode::Engine,
+            ForgeError::Timeout { .. } => ErrorCode::Timeout,
+            ForgeError::Cancelled { .. } => ErrorCode::Cancelled,
+            ForgeError::ResourceExhausted { .. } => {
+                ErrorCode::ResourceExhausted
+            },
+            ForgeError::Concurrency { .. } => ErrorCode::Concurrency,
+            ForgeError::Validation { .. } => ErrorCode::Validation,
             ForgeError::ExternalDependency { .. } => {
-                "EXTERNAL_DEPENDENCY_ERROR"
+                ErrorCode::ExternalDependency
             },
-            ForgeError::Internal { .. } => "INTERNAL_ERROR",
-            ForgeError::Other(_) => "OTHER_ERROR",
+            ForgeError::Internal { .. } => ErrorCode::Internal,
+            ForgeError::Other(_) => ErrorCode::Other,
         }
     }
 
+    /// 获取错误代码，用于程序化处理
+    pub fn error_code(&self) -> &'static str {
+        self.code().as_str()
+    }
+
     /// 检查错误是否可重试
     pub fn is_retryable(&self) -> bool {
         matches!(
@@ -182,6 +195,63 @@ impl ForgeError {
     }
 }
 
+/// 变体字段写入 `details`；`Other` 中若携带下层错误码则沿用
+impl ToWire for ForgeError {
+    fn to_wire(&self) -> ErrorWire {
+        if let ForgeError::Other(err) = self
+            && find_code(err).is_some()
+        {
+            return err.to_wire();
+        }
+        let wire = ErrorWire::new(self.code(), self.to_string());
+        let wire = match self {
+            ForgeError::Middleware { middleware_name, .. } => {
+                wire.with_detail("middleware_name", middleware_name.clone())
+            },
+            ForgeError::Extension { extension_name, .. } => {
+                wire.with_detail("extension_name", extension_name.clone())
+            },
+            ForgeError::Transaction { transaction_id, .. } => {
+                wire.with_detail("transaction_id", *transaction_id)
+            },
+            ForgeError::Config { config_key, .. } => {
+                wire.with_detail("config_key", config_key.clone())
+            },
+            ForgeError::Timeout { operation, timeout_ms } => wire
+                .with_detail("operation", operation.clone())
+                .with_detail("timeout_ms", *timeout_ms),
+            ForgeError::Cancelled { operation } => {
+                wire.with_detail("operation", operation.clone())
+            },
+            ForgeError::ResourceExhausted {
+                resource_type,
+                current_usage,
+                limit,
+            } => wire
+                .with_detail("resource_type", resource_type.clone())
+                .with_detail("current_usage", *current_usage)
+                .with_detail("limit", *limit),
+            ForgeError::Validation { field, .. } => {
+                wire.with_detail("field", field.clone())
+            },
+            ForgeError::ExternalDependency { dependency, .. } => {
+                wire.with_detail("dependency", dependency.clone())
+            },
+            ForgeError::Internal { location, .. } => {
+                wire.with_detail("location", location.clone())
+            },
+            _ => wire,
+        };
+        let sources = match self {
+            ForgeError::Other(err) => {
+                err.chain().skip(1).map(|e| e.to_string()).collect()
+            },
+            _ => source_chain(self),
+        };
+        wire.with_sources(sources)
+    }
+}
+
 /// 错误构造工具函数
 ///
 /// 这些函数提供了便捷的方式来创建各种类型的错误，
@@ -369,6 +439,11 @@ pub mod error_utils {
         ForgeError::Timeout { operation: operation.into(), timeout_ms }
     }
 
+    /// 创建取消错误
+    pub fn cancelled_error(operation: impl Into<String>) -> ForgeError {
+        ForgeError::Cancelled { operation: operation.into() }
+    }
+
     /// 创建运行时错误
     pub fn runtime_error(msg: impl Into<String>) -> ForgeError {
         ForgeError::Engine { message: msg.into(), source: None }
@@ -456,3 +531,33 @@ impl From<crate::config::ConfigValidationError> for ForgeError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mf_error_codes::coded;
+
+    #[test]
+    fn test_to_wire() {
+        let err = error_utils::timeout_error_with_duration("apply", 500);
+        assert_eq!(err.error_code(), "TIMEOUT_ERROR");
+        let wire = err.to_wire();
+        assert_eq!(wire.code, "TIMEOUT_ERROR");
+        assert_eq!(wire.details["kind"], "timeout");
+        assert_eq!(wire.details["timeout_ms"], 500);
+
+        let err = error_utils::state_error_with_source(
+            "保存失败",
+            std::io::Error::other("磁盘已满"),
+        );
+        assert_eq!(err.to_wire().details["sources"][0], "磁盘已满");
+
+        let err = ForgeError::from(coded(ErrorCode::StatePluginNotFound, "x"));
+        let wire = err.to_wire();
+        assert_eq!(wire.code, "STATE_PLUGIN_NOT_FOUND");
+        assert_eq!(wire.details["kind"], "not_found");
+
+        let wire = ForgeError::from(anyhow::anyhow!("未知")).to_wire();
+        assert_eq!(wire.error_code(), Some(ErrorCode::Other));
+    }
+}
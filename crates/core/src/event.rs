@@ -1,7 +1,8 @@
 use std::{
+    collections::VecDeque,
     fmt::Debug,
     sync::{
-        Arc,
+        Arc, Mutex,
         atomic::{AtomicU64, Ordering},
     },
 };
@@ -57,6 +58,63 @@ pub struct EventBus<T: Send + Sync + Clone + 'static> {
     config: EventConfig,
     /// 事件统计
     stats: EventBusStats,
+    /// 最近分发的事件，供后注册的处理器回放
+    replay: Arc<Mutex<ReplayBuffer<T>>>,
+}
+
+/// 有界回放缓冲区
+struct ReplayBuffer<T> {
+    events: VecDeque<T>,
+    capacity: usize,
+    /// 已分发的事件总数
+    dispatched: u64,
+}
+
+impl<T: Clone> ReplayBuffer<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+            dispatched: 0,
+        }
+    }
+
+    fn record(
+        &mut self,
+        event: &T,
+    ) {
+        self.dispatched += 1;
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event.clone());
+    }
+
+    /// 最近的 `n` 个事件与其中已被淘汰的数量
+    fn last(
+        &self,
+        n: usize,
+    ) -> (Vec<T>, u64) {
+        let requested = self.dispatched.min(n as u64);
+        let available = self.events.len().min(n);
+        let events =
+            self.events.iter().skip(self.events.len() - available).cloned();
+        (events.collect(), requested - available as u64)
+    }
+}
+
+/// 带回放的处理器注册结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplaySubscription {
+    /// 处理器 ID
+    pub handler_id: HandlerId,
+    /// 已回放的事件数
+    pub replayed: usize,
+    /// 请求回放但已超出缓冲区、无法补齐的事件数
+    pub missed: u64,
 }
 
 /// 事件总线统计信息
@@ -100,6 +158,7 @@ impl<T: Send + Sync + Clone + 'static> Clone for EventBus<T> {
             shutdown: (self.shutdown.0.clone(), self.shutdown.1.clone()),
             config: self.config.clone(),
             stats: self.stats.clone(),
+            replay: self.replay.clone(),
         }
     }
 }
@@ -124,6 +183,60 @@ impl<T: Send + Sync + Clone + 'static> EventBus<T> {
         Ok(handler_id)
     }
 
+    /// 添加事件处理器，并先向其回放最近分发的 `last_n` 个事件
+    ///
+    /// 需配置 `EventConfig.replay_capacity`，未启用回放时返回错误且不注册处理器。
+    /// 处理器先注册再回放，每个事件恰好送达一次，但回放期间分发的新事件可能与
+    /// 回放并发处理。
+    pub async fn add_event_handler_with_replay(
+        &self,
+        event_handler: Arc<dyn EventHandler<T> + Send + Sync>,
+        last_n: usize,
+    ) -> ForgeResult<ReplaySubscription> {
+        // 未启用回放时事件循环不记录分发的事件，无法得知错过了多少
+        if self.config.replay_capacity == 0 {
+            return Err(error_utils::event_error(
+                "未启用事件回放（EventConfig.replay_capacity 为 0）",
+            ));
+        }
+        // 注册与读取缓冲区在同一把锁内，与事件循环的记录互斥
+        let (handler_id, events, missed) = {
+            let replay = self.replay.lock().unwrap_or_else(|e| e.into_inner());
+            let handler_id = self.add_event_handler(event_handler.clone())?;
+            let (events, missed) = replay.last(last_n);
+            (handler_id, events, missed)
+        };
+        if missed > 0 {
+            debug!(
+                "回放缓冲区不足，处理器 {} 错过 {} 个事件",
+                handler_id, missed
+            );
+        }
+        for event in &events {
+            match tokio::time::timeout(
+                self.config.handler_timeout,
+                event_handler.handle(event),
+            )
+            .await
+            {
+                Ok(Ok(())) => {},
+                Ok(Err(e)) => {
+                    debug!("回放事件处理失败: {}", e);
+                    self.stats
+                        .processing_failures
+                        .fetch_add(1, Ordering::Relaxed);
+                },
+                Err(_) => {
+                    debug!("回放事件处理超时");
+                    self.stats
+                        .processing_timeouts
+                        .fetch_add(1, Ordering::Relaxed);
+                },
+            }
+        }
+        Ok(ReplaySubscription { handler_id, replayed: events.len(), missed })
+    }
+
     /// 批量添加事件处理器
     pub fn add_event_handlers(
         &self,
@@ -230,6 +343,7 @@ impl<T: Send + Sync + Clone + 'static> EventBus<T> {
         let shutdown_rt = self.shutdown.1.clone();
         let config = self.config.clone();
         let stats = self.stats.clone();
+        let replay = self.replay.clone();
         tokio::spawn(async move {
             let mut join_set = tokio::task::JoinSet::new();
 
@@ -269,8 +383,15 @@ impl<T: Send + Sync + Clone + 'static> EventBus<T> {
                                 }
                             }
 
-                            // 无锁读取事件处理器列表
-                            let handlers = event_handlers.load();
+                            // 无锁读取事件处理器列表；启用回放时与带回放的注册互斥，
+                            // 保证新处理器既不遗漏也不重复收到事件
+                            let handlers = if config.replay_capacity > 0 {
+                                let mut replay = replay.lock().unwrap_or_else(|e| e.into_inner());
+                                replay.record(&event);
+                                event_handlers.load_full()
+                            } else {
+                                event_handlers.load_full()
+                            };
                             let handler_timeout = config.handler_timeout;
                             let event_stats = stats.clone();
 
@@ -358,8 +479,11 @@ impl<T: Send + Sync + Clone + 'static> EventBus<T> {
             handler_registry: Arc::new(DashMap::new()),
             next_handler_id: Arc::new(AtomicU64::new(1)),
             shutdown: (shutdown_tx, shutdown_rt),
-            config,
             stats: EventBusStats::default(),
+            replay: Arc::new(Mutex::new(ReplayBuffer::new(
+                config.replay_capacity,
+            ))),
+            config,
         }
     }
 
@@ -402,6 +526,18 @@ impl<T: Send + Sync + Clone + 'static> EventBus<T> {
             .map_err(|e| error_utils::event_error(format!("广播事件失败: {e}")))
     }
 
+    /// 非阻塞广播事件，队列已满时直接返回错误
+    ///
+    /// 适用于进度通知等可丢弃的高频事件
+    pub fn try_broadcast(
+        &self,
+        event: T,
+    ) -> ForgeResult<()> {
+        self.tx
+            .try_send(event)
+            .map_err(|e| error_utils::event_error(format!("广播事件失败: {e}")))
+    }
+
     /// 获取事件配置
     pub fn get_config(&self) -> &EventConfig {
         &self.config
@@ -484,3 +620,75 @@ pub trait EventHandler<T>: Send + Sync + Debug {
         event: &T,
     ) -> ForgeResult<()>;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct Collect(Mutex<Vec<u32>>);
+
+    #[async_trait::async_trait]
+    impl EventHandler<u32> for Collect {
+        async fn handle(
+            &self,
+            event: &u32,
+        ) -> ForgeResult<()> {
+            self.0.lock().unwrap().push(*event);
+            Ok(())
+        }
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool) {
+        for _ in 0..100 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("等待事件处理超时");
+    }
+
+    #[tokio::test]
+    async fn test_late_handler_receives_replayed_events() {
+        let bus = EventBus::with_config(EventConfig {
+            replay_capacity: 3,
+            ..EventConfig::default()
+        });
+        bus.start_event_loop();
+        for event in 1..=5 {
+            bus.broadcast(event).await.unwrap();
+        }
+        let stats = bus.get_stats();
+        wait_until(|| stats.events_processed.load(Ordering::Relaxed) == 5)
+            .await;
+
+        let late = Arc::new(Collect::default());
+        let subscription =
+            bus.add_event_handler_with_replay(late.clone(), 4).await.unwrap();
+        assert_eq!(subscription.replayed, 3);
+        assert_eq!(subscription.missed, 1);
+        assert_eq!(*late.0.lock().unwrap(), vec![3, 4, 5]);
+
+        bus.broadcast(6).await.unwrap();
+        wait_until(|| late.0.lock().unwrap().len() == 4).await;
+        assert_eq!(*late.0.lock().unwrap(), vec![3, 4, 5, 6]);
+
+        let recent = Arc::new(Collect::default());
+        let subscription =
+            bus.add_event_handler_with_replay(recent.clone(), 2).await.unwrap();
+        assert_eq!((subscription.replayed, subscription.missed), (2, 0));
+        assert_eq!(*recent.0.lock().unwrap(), vec![5, 6]);
+        bus.destroy().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_requires_capacity() {
+        let bus: EventBus<u32> = EventBus::with_config(EventConfig::default());
+        let handler = Arc::new(Collect::default());
+        assert!(bus.add_event_handler_with_replay(handler, 1).await.is_err());
+        assert_eq!(bus.get_stats().active_handlers.load(Ordering::Relaxed), 0);
+    }
+}
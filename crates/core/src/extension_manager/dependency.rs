@@ -0,0 +1,164 @@
+//! 扩展依赖排序
+//!
+//! 扩展通过 [`Extension::set_name`](crate::extension::Extension::set_name)
+//! 命名，并用 `add_dependency` 声明依赖的扩展。构建 schema 前按依赖做拓扑排序，
+//! 被依赖的扩展排在前面；没有依赖关系的扩展保持原有的添加顺序。
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{error::error_utils, types::Extensions, ForgeResult};
+
+fn label(extension: &Extensions) -> &str {
+    match extension {
+        Extensions::E(extension) => extension.name().unwrap_or("<unnamed>"),
+        Extensions::N(node) => &node.name,
+        Extensions::M(mark) => &mark.name,
+    }
+}
+
+/// 按依赖关系排序扩展
+///
+/// 依赖未提供、扩展重名或存在循环依赖时返回错误，错误信息中列出相关的扩展名称
+pub(crate) fn sort_by_dependencies(
+    extensions: &[Extensions]
+) -> ForgeResult<Vec<Extensions>> {
+    let declared = extensions.iter().any(|extension| {
+        matches!(extension, Extensions::E(e) if !e.depends_on().is_empty())
+    });
+    if !declared {
+        return Ok(extensions.to_vec());
+    }
+
+    let mut by_name: HashMap<&str, usize> = HashMap::new();
+    for (index, extension) in extensions.iter().enumerate() {
+        if let Extensions::E(e) = extension
+            && let Some(name) = e.name()
+            && by_name.insert(name, index).is_some()
+        {
+            return Err(error_utils::extension_error_with_name(
+                format!("扩展名称重复: {name}"),
+                name,
+            ));
+        }
+    }
+
+    // 被依赖的扩展 -> 依赖它的扩展
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); extensions.len()];
+    let mut pending = vec![0usize; extensions.len()];
+    for (index, extension) in extensions.iter().enumerate() {
+        let Extensions::E(e) = extension else {
+            continue;
+        };
+        let mut missing = Vec::new();
+        for dependency in e.depends_on() {
+            match by_name.get(dependency) {
+                Some(&target) => {
+                    dependents[target].push(index);
+                    pending[index] += 1;
+                },
+                None => missing.push(dependency),
+            }
+        }
+        if !missing.is_empty() {
+            let name = label(extension);
+            return Err(error_utils::extension_error_with_name(
+                format!("扩展 {name} 依赖的扩展不存在: {}", missing.join(", ")),
+                name,
+            ));
+        }
+    }
+
+    // 每次取出原始位置最靠前的就绪扩展，保证排序稳定
+    let mut ready: BTreeSet<usize> =
+        (0..extensions.len()).filter(|&index| pending[index] == 0).collect();
+    let mut sorted = Vec::with_capacity(extensions.len());
+    while let Some(index) = ready.pop_first() {
+        sorted.push(extensions[index].clone());
+        for &dependent in &dependents[index] {
+            pending[dependent] -= 1;
+            if pending[dependent] == 0 {
+                ready.insert(dependent);
+            }
+        }
+    }
+
+    if sorted.len() < extensions.len() {
+        let cycle: Vec<&str> = (0..extensions.len())
+            .filter(|&index| pending[index] > 0)
+            .map(|index| label(&extensions[index]))
+            .collect();
+        return Err(error_utils::extension_error(format!(
+            "扩展存在循环依赖: {}",
+            cycle.join(", ")
+        )));
+    }
+    Ok(sorted)
+}
+
+#[cfg(test)]
+mod tests {
+    use mf_model::node_definition::NodeSpec;
+
+    use super::*;
+    use crate::{error::ForgeError, extension::Extension, node::Node};
+
+    fn extension(
+        name: &str,
+        dependencies: &[&str],
+    ) -> Extensions {
+        let mut extension = Extension::new();
+        extension.set_name(name);
+        for dependency in dependencies {
+            extension.add_dependency(*dependency);
+        }
+        Extensions::E(extension)
+    }
+
+    fn labels(extensions: &[Extensions]) -> Vec<&str> {
+        extensions.iter().map(label).collect()
+    }
+
+    fn message(err: ForgeError) -> String {
+        match err {
+            ForgeError::Extension { message, .. } => message,
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn test_dependencies_are_loaded_first() {
+        let extensions = vec![
+            extension("table", &["base", "style"]),
+            Extensions::N(Node::create("doc", NodeSpec::default())),
+            extension("style", &["base"]),
+            extension("base", &[]),
+            extension("other", &[]),
+        ];
+        let sorted = sort_by_dependencies(&extensions).unwrap();
+        assert_eq!(labels(&sorted), ["doc", "base", "style", "table", "other"]);
+
+        // 没有声明依赖时保持原有顺序
+        let plain = vec![extension("b", &[]), extension("a", &[])];
+        assert_eq!(labels(&sort_by_dependencies(&plain).unwrap()), ["b", "a"]);
+    }
+
+    #[test]
+    fn test_missing_and_cyclic_dependencies_are_rejected() {
+        let missing = vec![extension("table", &["base", "style"])];
+        let err = message(sort_by_dependencies(&missing).err().unwrap());
+        assert!(err.contains("table") && err.contains("base, style"), "{err}");
+
+        let cyclic = vec![
+            extension("a", &["c"]),
+            extension("b", &["a"]),
+            extension("c", &["b"]),
+            extension("free", &[]),
+        ];
+        let err = message(sort_by_dependencies(&cyclic).err().unwrap());
+        assert!(err.contains("a, b, c") && !err.contains("free"), "{err}");
+
+        let duplicated = vec![extension("a", &[]), extension("a", &["x"])];
+        let err = message(sort_by_dependencies(&duplicated).err().unwrap());
+        assert!(err.contains("重复"), "{err}");
+    }
+}
@@ -0,0 +1,416 @@
+//! 声明式扩展清单
+//!
+//! 使用 TOML 描述一个扩展：节点类型、标记类型、全局属性，以及按名称引用的插件。
+//! 插件需要先在 [`PluginRegistry`] 中以名称注册工厂函数，清单中只引用名称。
+//!
+//! 清单会先转换为 XML schema 的映射结构，再复用 XML 解析器的转换与校验逻辑，
+//! 因此两条路径生成的扩展完全一致。
+//!
+//! ```toml
+//! name = "customer-a"
+//! top_node = "doc"
+//! plugins = ["audit"]
+//!
+//! [[nodes]]
+//! name = "doc"
+//! content = "paragraph+"
+//! [nodes.attrs]
+//! title = "未命名"
+//!
+//! [[nodes]]
+//! name = "paragraph"
+//! marks = "strong"
+//!
+//! [[marks]]
+//! name = "strong"
+//! spanning = true
+//!
+//! [[global_attributes]]
+//! types = "*"
+//! [global_attributes.attrs]
+//! locked = false
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Debug};
+use std::path::Path;
+use std::sync::Arc;
+
+use mf_state::plugin::Plugin;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::{error_utils, ForgeResult},
+    schema_parser::{
+        types::{
+            XmlAttr, XmlAttrs, XmlGlobalAttribute, XmlGlobalAttributes,
+            XmlMark, XmlMarks, XmlNode, XmlNodes, XmlSchemaWithReferences,
+        },
+        XmlSchemaParser,
+    },
+    types::Extensions,
+};
+
+/// 插件工厂函数
+pub type PluginFactory = Arc<dyn Fn() -> Arc<Plugin> + Send + Sync>;
+
+/// 插件注册表
+///
+/// 在代码中按名称注册可用的插件工厂，供扩展清单引用
+#[derive(Clone, Default)]
+pub struct PluginRegistry {
+    factories: HashMap<String, PluginFactory>,
+}
+
+impl Debug for PluginRegistry {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("PluginRegistry")
+            .field("plugins", &self.names())
+            .finish()
+    }
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册插件工厂，同名注册会覆盖之前的工厂
+    pub fn register<F>(
+        &mut self,
+        name: impl Into<String>,
+        factory: F,
+    ) -> &mut Self
+    where
+        F: Fn() -> Arc<Plugin> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.into(), Arc::new(factory));
+        self
+    }
+
+    /// 是否注册了指定名称的插件
+    pub fn contains(
+        &self,
+        name: &str,
+    ) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// 所有已注册的插件名称（按名称排序）
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.factories.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 按名称创建插件实例
+    pub fn create(
+        &self,
+        name: &str,
+    ) -> ForgeResult<Arc<Plugin>> {
+        match self.factories.get(name) {
+            Some(factory) => Ok(factory()),
+            None => Err(self.unknown_plugins_error(&[name])),
+        }
+    }
+
+    fn unknown_plugins_error(
+        &self,
+        names: &[&str],
+    ) -> crate::error::ForgeError {
+        error_utils::config_error(format!(
+            "未注册的插件: {}，可用插件: [{}]",
+            names.join(", "),
+            self.names().join(", ")
+        ))
+    }
+}
+
+/// 扩展清单
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExtensionManifest {
+    /// 清单名称，仅用于错误信息
+    pub name: Option<String>,
+    pub top_node: Option<String>,
+    pub nodes: Vec<ManifestNode>,
+    pub marks: Vec<ManifestMark>,
+    pub global_attributes: Vec<ManifestGlobalAttribute>,
+    /// 引用的插件名称
+    pub plugins: Vec<String>,
+}
+
+/// 清单中的节点定义
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ManifestNode {
+    pub name: String,
+    pub group: Option<String>,
+    pub desc: Option<String>,
+    pub content: Option<String>,
+    pub marks: Option<String>,
+    /// 子节点排序规则，例如 "attrs.seq desc"
+    pub sort_by: Option<String>,
+    /// 属性名 -> 默认值
+    pub attrs: BTreeMap<String, Value>,
+}
+
+/// 清单中的标记定义
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ManifestMark {
+    pub name: String,
+    pub group: Option<String>,
+    pub desc: Option<String>,
+    pub excludes: Option<String>,
+    pub spanning: Option<bool>,
+    pub cascade: Option<bool>,
+    /// 属性名 -> 默认值
+    pub attrs: BTreeMap<String, Value>,
+}
+
+/// 清单中的全局属性定义
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ManifestGlobalAttribute {
+    /// 适用的节点类型，空格分隔，`*` 表示全部
+    pub types: String,
+    /// 适用的节点分组，空格分隔
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub groups: Option<String>,
+    /// 属性名 -> 默认值
+    pub attrs: BTreeMap<String, Value>,
+}
+
+impl ExtensionManifest {
+    /// 解析 TOML 格式的清单内容
+    pub fn parse(content: &str) -> ForgeResult<Self> {
+        toml::from_str(content).map_err(|e| {
+            error_utils::config_error(format!("解析扩展清单失败: {e}"))
+        })
+    }
+
+    /// 从文件读取清单
+    pub fn from_file(path: impl AsRef<Path>) -> ForgeResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            error_utils::config_error(format!(
+                "读取扩展清单 {} 失败: {e}",
+                path.display()
+            ))
+        })?;
+        Self::parse(&content)
+    }
+
+    /// 转换为扩展列表
+    ///
+    /// 引用了未注册的插件时返回错误，并列出所有可用的插件名称
+    pub fn to_extensions(
+        &self,
+        registry: &PluginRegistry,
+    ) -> ForgeResult<Vec<Extensions>> {
+        let unknown: Vec<&str> = self
+            .plugins
+            .iter()
+            .map(String::as_str)
+            .filter(|name| !registry.contains(name))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(registry.unknown_plugins_error(&unknown));
+        }
+
+        let mut extensions =
+            XmlSchemaParser::convert_xml_schema_with_refs_to_extensions(
+                self.to_xml_schema(),
+            )
+            .map_err(|e| {
+                error_utils::config_error(format!(
+                    "扩展清单 {} 无效: {e}",
+                    self.name.as_deref().unwrap_or("<unnamed>")
+                ))
+            })?;
+
+        for extension in extensions.iter_mut() {
+            if let Extensions::E(extension) = extension {
+                for name in &self.plugins {
+                    extension.add_plugin(registry.create(name)?);
+                }
+                break;
+            }
+        }
+        Ok(extensions)
+    }
+
+    /// 转换为 XML schema 的映射结构，以复用 XML 解析器的转换逻辑
+    fn to_xml_schema(&self) -> XmlSchemaWithReferences {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| XmlNode {
+                name: node.name.clone(),
+                group: node.group.clone(),
+                desc: node.desc.clone(),
+                content: node.content.clone(),
+                marks: node.marks.clone(),
+                sort_by: node.sort_by.clone(),
+                attrs: to_xml_attrs(&node.attrs),
+            })
+            .collect();
+        let marks = self
+            .marks
+            .iter()
+            .map(|mark| XmlMark {
+                name: mark.name.clone(),
+                group: mark.group.clone(),
+                desc: mark.desc.clone(),
+                excludes: mark.excludes.clone(),
+                spanning: mark.spanning,
+                cascade: mark.cascade,
+                attrs: to_xml_attrs(&mark.attrs),
+            })
+            .collect();
+        let global_attributes =
+            (!self.global_attributes.is_empty()).then(|| XmlGlobalAttributes {
+                global_attributes: self
+                    .global_attributes
+                    .iter()
+                    .map(|item| XmlGlobalAttribute {
+                        types: item.types.clone(),
+                        groups: item.groups.clone(),
+                        attrs: to_xml_attrs(&item.attrs)
+                            .map(|a| a.attrs)
+                            .unwrap_or_default(),
+                    })
+                    .collect(),
+            });
+
+        XmlSchemaWithReferences {
+            top_node: self.top_node.clone(),
+            imports: None,
+            includes: None,
+            include_directives: Vec::new(),
+            global_attributes,
+            nodes: Some(XmlNodes { nodes }),
+            marks: Some(XmlMarks { marks }),
+        }
+    }
+}
+
+fn to_xml_attrs(attrs: &BTreeMap<String, Value>) -> Option<XmlAttrs> {
+    if attrs.is_empty() {
+        return None;
+    }
+    Some(XmlAttrs {
+        attrs: attrs
+            .iter()
+            .map(|(name, default)| XmlAttr {
+                name: name.clone(),
+                default: Some(default.clone()),
+                computed: None,
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::ForgeConfig, extension_manager::ExtensionManagerBuilder,
+        types::RuntimeOptions, ForgeRuntime,
+    };
+    use mf_state::plugin::{
+        PluginMetadata, PluginSpec, PluginTrait, PluginTraitGeneric,
+    };
+    use mf_model::{node_pool::NodePool, schema::Schema};
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/fixtures/extension_manifest.toml"
+    );
+
+    #[derive(Debug)]
+    struct AuditPlugin;
+
+    impl PluginTraitGeneric<NodePool, Schema> for AuditPlugin {
+        fn metadata(&self) -> PluginMetadata {
+            PluginMetadata {
+                name: "audit".to_string(),
+                version: "1.0.0".to_string(),
+                description: "审计插件".to_string(),
+                author: String::new(),
+                dependencies: vec![],
+                conflicts: vec![],
+                state_fields: vec![],
+                tags: vec![],
+            }
+        }
+    }
+
+    impl PluginTrait for AuditPlugin {}
+
+    fn registry() -> PluginRegistry {
+        let mut registry = PluginRegistry::new();
+        registry.register("audit", || {
+            Arc::new(Plugin::new(PluginSpec {
+                state_field: None,
+                tr: Arc::new(AuditPlugin),
+            }))
+        });
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_manifest_builds_runtime() {
+        let builder =
+            ExtensionManagerBuilder::from_manifest(FIXTURE, &registry())
+                .unwrap();
+        let manager = builder.build().unwrap();
+        let schema = manager.get_schema();
+        let factory = schema.factory();
+        let (nodes, marks) = factory.definitions();
+        assert!(nodes.contains_key("paragraph"));
+        assert!(marks.contains_key("strong"));
+        assert_eq!(manager.get_plugins().len(), 1);
+
+        let extensions = ExtensionManifest::from_file(FIXTURE)
+            .unwrap()
+            .to_extensions(&registry())
+            .unwrap();
+        let runtime = ForgeRuntime::create_with_config(
+            RuntimeOptions::default().set_extensions(extensions),
+            ForgeConfig::default(),
+        )
+        .await
+        .unwrap();
+        let plugins = runtime.get_state().plugins().await;
+        assert!(plugins.iter().any(|p| p.key == "audit"));
+        assert_eq!(runtime.doc().root().unwrap().r#type, "doc");
+    }
+
+    #[test]
+    fn test_unknown_plugin_lists_available() {
+        let manifest =
+            ExtensionManifest::parse("plugins = [\"audit\", \"missing\"]")
+                .unwrap();
+        let Err(err) = manifest.to_extensions(&registry()) else {
+            panic!("引用未注册的插件应当失败");
+        };
+        let message = err.to_string();
+        assert!(message.contains("missing"));
+        assert!(message.contains("[audit]"));
+    }
+
+    #[test]
+    fn test_duplicate_node_rejected() {
+        let manifest = ExtensionManifest::parse(
+            "[[nodes]]\nname = \"doc\"\n[[nodes]]\nname = \"doc\"",
+        )
+        .unwrap();
+        assert!(manifest.to_extensions(&registry()).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -8,6 +9,12 @@ use crate::{
     helpers::get_schema_by_resolved_extensions::get_schema_by_resolved_extensions,
     metrics, types::Extensions, ForgeResult, XmlSchemaParser, extension::OpFn,
 };
+
+mod dependency;
+pub mod manifest;
+
+pub use manifest::{ExtensionManifest, PluginRegistry};
+
 /// 扩展管理器
 pub struct ExtensionManager {
     plugins: Vec<Arc<Plugin>>,
@@ -30,6 +37,32 @@ impl ExtensionManagerBuilder {
         Self::default()
     }
 
+    /// 从 TOML 扩展清单创建构建器
+    ///
+    /// 清单中引用的插件必须已在 `registry` 中注册，否则返回错误并列出可用的插件名称
+    ///
+    /// # 示例
+    /// ```rust,no_run
+    /// use mf_core::{ExtensionManagerBuilder, PluginRegistry};
+    ///
+    /// # fn main() -> mf_core::ForgeResult<()> {
+    /// let registry = PluginRegistry::new();
+    /// let manager = ExtensionManagerBuilder::from_manifest(
+    ///     "./extensions/customer.toml",
+    ///     &registry,
+    /// )?
+    /// .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_manifest<P: AsRef<Path>>(
+        path: P,
+        registry: &PluginRegistry,
+    ) -> ForgeResult<Self> {
+        let manifest = ExtensionManifest::from_file(path)?;
+        Ok(Self::new().add_extensions(manifest.to_extensions(registry)?))
+    }
+
     /// 添加代码定义的扩展
     ///
     /// # 参数
@@ -200,8 +233,10 @@ impl ExtensionManager {
         ExtensionManagerBuilder::new()
     }
 
-    pub fn new(extensions: &Vec<Extensions>) -> ForgeResult<Self> {
+    /// 创建扩展管理器，扩展先按声明的依赖排序
+    pub fn new(extensions: &[Extensions]) -> ForgeResult<Self> {
         let start_time = Instant::now();
+        let extensions = &dependency::sort_by_dependencies(extensions)?;
         let schema = Arc::new(get_schema_by_resolved_extensions(extensions)?);
         let mut plugins = vec![];
         let mut op_fns = vec![];
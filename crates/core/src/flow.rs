@@ -0,0 +1,824 @@
+//! 流程控制
+//!
+//! 以命名阶段组成的状态机描述业务流程（如 草稿 → 审核 → 批准）：
+//! - 阶段间的流转由命令或事件触发，可附带守卫条件
+//! - 阶段可设置进入/离开钩子，向流转事务追加步骤
+//! - 当前阶段保存在插件状态中，随事务、撤销/重做与快照一起保存和恢复
+//! - 每次流转都会在事件总线上广播 `Event::FlowStageChanged`
+//!
+//! ```rust,ignore
+//! let flow = Flow::builder("approval")
+//!     .stage(Stage::new("draft"))
+//!     .stage(Stage::new("review"))
+//!     .transition(Transition::on_command("draft", "review", "submit"))
+//!     .build()?;
+//! let options = RuntimeOptions::default()
+//!     .add_extension(Extensions::E(flow.extension()));
+//! // ... 创建运行时后
+//! flow.fire(&mut runtime, Trigger::command("submit")).await?;
+//! ```
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use async_trait::async_trait;
+use mf_model::{node_pool::NodePool, schema::Schema};
+use mf_state::{
+    plugin::{
+        Plugin, PluginMetadata, PluginSpec, PluginTrait, PluginTraitGeneric,
+        StateFieldGeneric,
+    },
+    resource::Resource,
+    state::StateGeneric,
+    transaction::TransactionGeneric,
+    State, StateConfig, Transaction,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{error_utils, ForgeResult},
+    event::Event,
+    extension::Extension,
+    runtime::runtime::ForgeRuntime,
+};
+
+/// 守卫条件：基于当前状态（文档与插件状态）判断是否允许流转
+pub type FlowGuard = Arc<dyn Fn(&State) -> bool + Send + Sync>;
+
+/// 阶段钩子：参数为流转前的状态与流转事务，可向事务追加步骤，
+/// 返回错误时流转取消
+pub type StageHook =
+    Arc<dyn Fn(&State, &mut Transaction) -> ForgeResult<()> + Send + Sync>;
+
+/// 流转触发方式
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    /// 由业务命令触发
+    Command(String),
+    /// 由事件总线上的事件触发，名称与 `Event::name()` 一致
+    Event(String),
+}
+
+impl Trigger {
+    pub fn command(name: impl Into<String>) -> Self {
+        Trigger::Command(name.into())
+    }
+
+    pub fn event(name: impl Into<String>) -> Self {
+        Trigger::Event(name.into())
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            Trigger::Command(name) => write!(f, "命令 {name}"),
+            Trigger::Event(name) => write!(f, "事件 {name}"),
+        }
+    }
+}
+
+/// 流程阶段
+#[derive(Clone)]
+pub struct Stage {
+    name: String,
+    on_enter: Option<StageHook>,
+    on_exit: Option<StageHook>,
+}
+
+impl Stage {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), on_enter: None, on_exit: None }
+    }
+
+    /// 进入本阶段时执行
+    pub fn on_enter<F>(
+        mut self,
+        hook: F,
+    ) -> Self
+    where
+        F: Fn(&State, &mut Transaction) -> ForgeResult<()>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.on_enter = Some(Arc::new(hook));
+        self
+    }
+
+    /// 离开本阶段时执行，先于目标阶段的进入钩子
+    pub fn on_exit<F>(
+        mut self,
+        hook: F,
+    ) -> Self
+    where
+        F: Fn(&State, &mut Transaction) -> ForgeResult<()>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.on_exit = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Debug for Stage {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("Stage")
+            .field("name", &self.name)
+            .field("on_enter", &self.on_enter.is_some())
+            .field("on_exit", &self.on_exit.is_some())
+            .finish()
+    }
+}
+
+/// 阶段间的流转
+#[derive(Clone)]
+pub struct Transition {
+    from: String,
+    to: String,
+    trigger: Trigger,
+    guard: Option<FlowGuard>,
+}
+
+impl Transition {
+    pub fn new(
+        from: impl Into<String>,
+        to: impl Into<String>,
+        trigger: Trigger,
+    ) -> Self {
+        Self { from: from.into(), to: to.into(), trigger, guard: None }
+    }
+
+    /// 由命令触发的流转
+    pub fn on_command(
+        from: impl Into<String>,
+        to: impl Into<String>,
+        command: impl Into<String>,
+    ) -> Self {
+        Self::new(from, to, Trigger::command(command))
+    }
+
+    /// 由事件触发的流转
+    pub fn on_event(
+        from: impl Into<String>,
+        to: impl Into<String>,
+        event: impl Into<String>,
+    ) -> Self {
+        Self::new(from, to, Trigger::event(event))
+    }
+
+    /// 设置守卫条件，返回 false 时拒绝流转
+    pub fn guard<F>(
+        mut self,
+        guard: F,
+    ) -> Self
+    where
+        F: Fn(&State) -> bool + Send + Sync + 'static,
+    {
+        self.guard = Some(Arc::new(guard));
+        self
+    }
+
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+
+    pub fn to(&self) -> &str {
+        &self.to
+    }
+
+    pub fn trigger(&self) -> &Trigger {
+        &self.trigger
+    }
+
+    fn allows(
+        &self,
+        state: &State,
+    ) -> bool {
+        self.guard.as_ref().is_none_or(|guard| guard(state))
+    }
+}
+
+impl fmt::Debug for Transition {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("Transition")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("trigger", &self.trigger)
+            .field("guard", &self.guard.is_some())
+            .finish()
+    }
+}
+
+/// 一次成功的阶段流转
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageChange {
+    pub flow: String,
+    pub from: String,
+    pub to: String,
+    pub trigger: Trigger,
+}
+
+/// 保存在插件状态中的流程进度
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowState {
+    pub stage: String,
+}
+
+impl Resource for FlowState {}
+
+/// 流程定义
+#[derive(Debug)]
+pub struct Flow {
+    name: String,
+    initial: String,
+    stages: HashMap<String, Stage>,
+    transitions: Vec<Transition>,
+}
+
+impl Flow {
+    pub fn builder(name: impl Into<String>) -> FlowBuilder {
+        FlowBuilder::new(name)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 初始阶段
+    pub fn initial(&self) -> &str {
+        &self.initial
+    }
+
+    pub fn stage(
+        &self,
+        name: &str,
+    ) -> Option<&Stage> {
+        self.stages.get(name)
+    }
+
+    pub fn transitions(&self) -> &[Transition] {
+        &self.transitions
+    }
+
+    /// 保存流程进度的插件 key，同时用作流转事务的元数据键
+    pub fn plugin_key(&self) -> String {
+        format!("flow.{}", self.name)
+    }
+
+    /// 保存流程进度的插件
+    pub fn plugin(&self) -> Arc<Plugin> {
+        let key = self.plugin_key();
+        Arc::new(Plugin::new(PluginSpec {
+            state_field: Some(Arc::new(FlowStateField {
+                key: key.clone(),
+                initial: self.initial.clone(),
+            })),
+            tr: Arc::new(FlowPlugin { key, flow: self.name.clone() }),
+        }))
+    }
+
+    /// 只包含流程插件的扩展，可直接加入 `RuntimeOptions`
+    pub fn extension(&self) -> Extension {
+        let mut extension = Extension::new();
+        extension.add_plugin(self.plugin());
+        extension
+    }
+
+    /// 当前阶段，运行时未注册流程插件时返回错误
+    pub fn current_stage(
+        &self,
+        state: &State,
+    ) -> ForgeResult<String> {
+        state
+            .get::<FlowState>(&self.plugin_key())
+            .map(|flow_state| flow_state.stage.clone())
+            .ok_or_else(|| {
+                error_utils::plugin_error(format!(
+                    "流程 {} 的插件未注册",
+                    self.name
+                ))
+            })
+    }
+
+    /// 当前阶段下守卫条件满足、可以触发的流转
+    pub fn available_transitions(
+        &self,
+        state: &State,
+    ) -> ForgeResult<Vec<&Transition>> {
+        let current = self.current_stage(state)?;
+        Ok(self
+            .transitions
+            .iter()
+            .filter(|t| t.from == current && t.allows(state))
+            .collect())
+    }
+
+    /// 触发流转
+    ///
+    /// 当前阶段没有对应的流转或守卫条件不满足时返回错误，状态保持不变；
+    /// 成功时离开/进入钩子追加的步骤与阶段变更在同一个事务中提交，
+    /// 随后广播 `Event::FlowStageChanged`
+    pub async fn fire(
+        &self,
+        runtime: &mut ForgeRuntime,
+        trigger: Trigger,
+    ) -> ForgeResult<StageChange> {
+        let state = runtime.get_state().clone();
+        let from = self.current_stage(&state)?;
+        let transition = self
+            .transitions
+            .iter()
+            .find(|t| t.from == from && t.trigger == trigger)
+            .ok_or_else(|| {
+                error_utils::validation_error(format!(
+                    "流程 {} 在阶段 {} 不接受{}",
+                    self.name, from, trigger
+                ))
+            })?;
+        if !transition.allows(&state) {
+            return Err(error_utils::validation_error(format!(
+                "流程 {} 从 {} 到 {} 的守卫条件不满足",
+                self.name, from, transition.to
+            )));
+        }
+
+        let mut tr = runtime.get_tr();
+        if let Some(hook) =
+            self.stages.get(&from).and_then(|stage| stage.on_exit.as_ref())
+        {
+            hook(&state, &mut tr)?;
+        }
+        if let Some(hook) = &self.stages[&transition.to].on_enter {
+            hook(&state, &mut tr)?;
+        }
+        tr.set_meta(self.plugin_key(), transition.to.clone());
+        runtime
+            .dispatch_with_meta(
+                tr,
+                format!("{}: {} -> {}", self.name, from, transition.to),
+                serde_json::Value::Null,
+            )
+            .await?;
+        if self.current_stage(runtime.get_state())? != transition.to {
+            return Err(error_utils::transaction_error(format!(
+                "流程 {} 的流转事务被拒绝",
+                self.name
+            )));
+        }
+
+        runtime
+            .emit_event(Event::FlowStageChanged {
+                flow: self.name.clone(),
+                from: from.clone(),
+                to: transition.to.clone(),
+            })
+            .await?;
+        Ok(StageChange {
+            flow: self.name.clone(),
+            from,
+            to: transition.to.clone(),
+            trigger,
+        })
+    }
+
+    /// 按事件触发流转
+    ///
+    /// 当前阶段没有匹配该事件的流转或守卫条件不满足时不做处理，返回 `None`
+    pub async fn handle_event(
+        &self,
+        runtime: &mut ForgeRuntime,
+        event: &Event,
+    ) -> ForgeResult<Option<StageChange>> {
+        let trigger = Trigger::event(event.name());
+        let state = runtime.get_state().clone();
+        let current = self.current_stage(&state)?;
+        let accepted = self.transitions.iter().any(|t| {
+            t.from == current && t.trigger == trigger && t.allows(&state)
+        });
+        if !accepted {
+            return Ok(None);
+        }
+        self.fire(runtime, trigger).await.map(Some)
+    }
+}
+
+/// 流程构建器
+///
+/// 未调用 [`FlowBuilder::initial`] 时以第一个阶段为初始阶段
+pub struct FlowBuilder {
+    name: String,
+    initial: Option<String>,
+    stages: Vec<Stage>,
+    transitions: Vec<Transition>,
+}
+
+impl FlowBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            initial: None,
+            stages: Vec::new(),
+            transitions: Vec::new(),
+        }
+    }
+
+    pub fn stage(
+        mut self,
+        stage: Stage,
+    ) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    pub fn initial(
+        mut self,
+        name: impl Into<String>,
+    ) -> Self {
+        self.initial = Some(name.into());
+        self
+    }
+
+    pub fn transition(
+        mut self,
+        transition: Transition,
+    ) -> Self {
+        self.transitions.push(transition);
+        self
+    }
+
+    /// 校验阶段与流转并生成流程定义
+    pub fn build(self) -> ForgeResult<Arc<Flow>> {
+        let invalid = |msg: String| {
+            error_utils::validation_error(format!("流程 {}: {msg}", self.name))
+        };
+        let Some(first) = self.stages.first() else {
+            return Err(invalid("至少需要一个阶段".to_string()));
+        };
+        let initial =
+            self.initial.clone().unwrap_or_else(|| first.name.clone());
+
+        let mut stages = HashMap::new();
+        for stage in &self.stages {
+            if stages.insert(stage.name.clone(), stage.clone()).is_some() {
+                return Err(invalid(format!("阶段 {} 重复", stage.name)));
+            }
+        }
+        if !stages.contains_key(&initial) {
+            return Err(invalid(format!("初始阶段 {initial} 不存在")));
+        }
+        for (i, transition) in self.transitions.iter().enumerate() {
+            for stage in [&transition.from, &transition.to] {
+                if !stages.contains_key(stage) {
+                    return Err(invalid(format!(
+                        "流转引用了不存在的阶段 {stage}"
+                    )));
+                }
+            }
+            if self.transitions[..i].iter().any(|t| {
+                t.from == transition.from && t.trigger == transition.trigger
+            }) {
+                return Err(invalid(format!(
+                    "阶段 {} 的{}对应多个流转",
+                    transition.from, transition.trigger
+                )));
+            }
+        }
+
+        Ok(Arc::new(Flow {
+            name: self.name,
+            initial,
+            stages,
+            transitions: self.transitions,
+        }))
+    }
+}
+
+/// 流程插件，只负责承载流程进度的状态字段
+#[derive(Debug)]
+struct FlowPlugin {
+    key: String,
+    flow: String,
+}
+
+#[async_trait]
+impl PluginTraitGeneric<NodePool, Schema> for FlowPlugin {
+    fn metadata(&self) -> PluginMetadata {
+        PluginMetadata {
+            name: self.key.clone(),
+            version: "1.0.0".to_string(),
+            description: format!("流程 {} 的进度", self.flow),
+            author: String::new(),
+            dependencies: vec![],
+            conflicts: vec![],
+            state_fields: vec![self.key.clone()],
+            tags: vec!["flow".to_string()],
+        }
+    }
+}
+
+impl PluginTrait for FlowPlugin {}
+
+/// 流程进度状态字段：读取流转事务元数据中的目标阶段
+#[derive(Debug)]
+struct FlowStateField {
+    key: String,
+    initial: String,
+}
+
+#[async_trait]
+impl StateFieldGeneric<NodePool, Schema> for FlowStateField {
+    type Value = FlowState;
+
+    async fn init(
+        &self,
+        _config: &StateConfig,
+        _instance: &State,
+    ) -> Arc<FlowState> {
+        Arc::new(FlowState { stage: self.initial.clone() })
+    }
+
+    async fn apply(
+        &self,
+        tr: &TransactionGeneric<NodePool, Schema>,
+        value: Arc<FlowState>,
+        _old_state: &StateGeneric<NodePool, Schema>,
+        _new_state: &StateGeneric<NodePool, Schema>,
+    ) -> Arc<FlowState> {
+        match tr.get_meta::<String>(&self.key) {
+            Some(stage) => Arc::new(FlowState { stage }),
+            None => value,
+        }
+    }
+
+    fn serialize(
+        &self,
+        value: &Arc<FlowState>,
+    ) -> Option<Vec<u8>> {
+        serde_json::to_vec(value.as_ref()).ok()
+    }
+
+    fn deserialize(
+        &self,
+        data: &[u8],
+    ) -> Option<Arc<FlowState>> {
+        serde_json::from_slice(data).ok().map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use mf_model::rpds::HashTrieMapSync;
+    use serde_json::Value;
+
+    use crate::{
+        config::ForgeConfig,
+        event::EventHandler,
+        extension_manager::ExtensionManager,
+        types::{Extensions, RuntimeOptions},
+    };
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<schema top_node="doc">
+  <nodes>
+    <node name="doc" desc="文档">
+      <attrs>
+        <attr name="status" default=""/>
+        <attr name="reviewer" default=""/>
+      </attrs>
+    </node>
+  </nodes>
+</schema>"#;
+
+    fn set_root_attr(
+        state: &State,
+        tr: &mut Transaction,
+        key: &str,
+        value: &str,
+    ) -> ForgeResult<()> {
+        let root = state.doc().root_id().clone();
+        tr.set_node_attribute(
+            root,
+            HashTrieMapSync::new_sync()
+                .insert(key.to_string(), Value::from(value)),
+        )
+        .map_err(|e| error_utils::transaction_error(e.to_string()))?;
+        Ok(())
+    }
+
+    fn root_attr(
+        state: &State,
+        key: &str,
+    ) -> Option<Value> {
+        state.doc().root().and_then(|root| root.attrs.get(key).cloned())
+    }
+
+    /// 草稿 → 审核 → 批准，审核可被驳回；批准要求已指定审核人
+    fn approval_flow() -> Arc<Flow> {
+        Flow::builder("approval")
+            .stage(Stage::new("draft"))
+            .stage(Stage::new("review").on_enter(|state, tr| {
+                set_root_attr(state, tr, "status", "审核中")
+            }))
+            .stage(Stage::new("approved").on_enter(|state, tr| {
+                set_root_attr(state, tr, "status", "已批准")
+            }))
+            .transition(Transition::on_command("draft", "review", "submit"))
+            .transition(
+                Transition::on_command("review", "approved", "approve").guard(
+                    |state| {
+                        root_attr(state, "reviewer").is_some_and(|v| v != "")
+                    },
+                ),
+            )
+            .transition(Transition::on_event(
+                "review",
+                "draft",
+                "HistoryCleared",
+            ))
+            .build()
+            .unwrap()
+    }
+
+    fn options(flow: &Flow) -> RuntimeOptions {
+        RuntimeOptions::from_extension_manager(
+            ExtensionManager::from_xml_string(XML).unwrap(),
+        )
+        .add_extension(Extensions::E(flow.extension()))
+    }
+
+    #[derive(Debug, Default)]
+    struct StageEvents(Mutex<Vec<(String, String)>>);
+
+    #[async_trait]
+    impl EventHandler<Event> for StageEvents {
+        async fn handle(
+            &self,
+            event: &Event,
+        ) -> ForgeResult<()> {
+            if let Event::FlowStageChanged { from, to, .. } = event {
+                self.0.lock().unwrap().push((from.clone(), to.clone()));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_approval_flow() {
+        let flow = approval_flow();
+        let mut runtime = ForgeRuntime::create_with_config(
+            options(&flow),
+            ForgeConfig::default(),
+        )
+        .await
+        .unwrap();
+        let events = Arc::new(StageEvents::default());
+        runtime.get_event_bus().add_event_handler(events.clone()).unwrap();
+        assert_eq!(flow.current_stage(runtime.get_state()).unwrap(), "draft");
+
+        // 草稿阶段不能直接批准
+        let err = flow
+            .fire(&mut runtime, Trigger::command("approve"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("draft"));
+        assert_eq!(flow.current_stage(runtime.get_state()).unwrap(), "draft");
+
+        let change =
+            flow.fire(&mut runtime, Trigger::command("submit")).await.unwrap();
+        assert_eq!(
+            (change.from.as_str(), change.to.as_str()),
+            ("draft", "review")
+        );
+        let state = runtime.get_state().clone();
+        assert_eq!(flow.current_stage(&state).unwrap(), "review");
+        assert_eq!(root_attr(&state, "status"), Some(Value::from("审核中")));
+
+        // 未指定审核人时守卫条件不满足
+        assert!(
+            flow.fire(&mut runtime, Trigger::command("approve")).await.is_err()
+        );
+        assert!(
+            flow.available_transitions(runtime.get_state())
+                .unwrap()
+                .iter()
+                .all(|t| t.to() != "approved")
+        );
+
+        let mut tr = runtime.get_tr();
+        set_root_attr(runtime.get_state(), &mut tr, "reviewer", "张三")
+            .unwrap();
+        runtime.dispatch(tr).await.unwrap();
+        flow.fire(&mut runtime, Trigger::command("approve")).await.unwrap();
+        let state = runtime.get_state().clone();
+        assert_eq!(flow.current_stage(&state).unwrap(), "approved");
+        assert_eq!(root_attr(&state, "status"), Some(Value::from("已批准")));
+
+        // 阶段随历史撤销
+        runtime.undo();
+        assert_eq!(flow.current_stage(runtime.get_state()).unwrap(), "review");
+
+        // 事件触发：审核阶段收到 HistoryCleared 时退回草稿，其他阶段忽略
+        let change = flow
+            .handle_event(&mut runtime, &Event::HistoryCleared)
+            .await
+            .unwrap();
+        assert_eq!(change.map(|c| c.to), Some("draft".to_string()));
+        assert!(
+            flow.handle_event(&mut runtime, &Event::HistoryCleared)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        for _ in 0..100 {
+            if events.0.lock().unwrap().len() >= 3 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let stage = |from: &str, to: &str| (from.to_string(), to.to_string());
+        assert_eq!(
+            *events.0.lock().unwrap(),
+            vec![
+                stage("draft", "review"),
+                stage("review", "approved"),
+                stage("review", "draft"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stage_survives_snapshot() {
+        let flow = approval_flow();
+        let mut runtime = ForgeRuntime::create_with_config(
+            options(&flow),
+            ForgeConfig::default(),
+        )
+        .await
+        .unwrap();
+        flow.fire(&mut runtime, Trigger::command("submit")).await.unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("flow.mfsnap");
+        runtime.create_snapshot(&path).await.unwrap();
+        let restored =
+            ForgeRuntime::from_snapshot(&path, Some(options(&flow)), None)
+                .await
+                .unwrap();
+        assert_eq!(flow.current_stage(restored.get_state()).unwrap(), "review");
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        assert!(Flow::builder("empty").build().is_err());
+        assert!(
+            Flow::builder("dup")
+                .stage(Stage::new("a"))
+                .stage(Stage::new("a"))
+                .build()
+                .is_err()
+        );
+        assert!(
+            Flow::builder("unknown")
+                .stage(Stage::new("a"))
+                .transition(Transition::on_command("a", "b", "go"))
+                .build()
+                .is_err()
+        );
+        assert!(
+            Flow::builder("ambiguous")
+                .stage(Stage::new("a"))
+                .stage(Stage::new("b"))
+                .stage(Stage::new("c"))
+                .transition(Transition::on_command("a", "b", "go"))
+                .transition(Transition::on_command("a", "c", "go"))
+                .build()
+                .is_err()
+        );
+        let flow = Flow::builder("ok")
+            .stage(Stage::new("a"))
+            .stage(Stage::new("b"))
+            .initial("b")
+            .build()
+            .unwrap();
+        assert_eq!(flow.initial(), "b");
+        assert_eq!(flow.plugin_key(), "flow.ok");
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! 定义了支持任意 DataContainer 和 SchemaDefinition 组合的事件系统。
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use mf_model::traits::{DataContainer, SchemaDefinition};
 use mf_state::{
@@ -64,10 +64,26 @@ where
         error: String,
     },
 
+    /// 命令执行进度
+    /// 由命令通过 `CommandContext::report` 上报，可直接转发给 UI 显示进度条
+    CommandProgress { command: String, done: u64, total: u64, message: String },
+
     /// 历史清空事件
     /// 当历史记录被清空时触发
     HistoryCleared,
 
+    /// 流程阶段变更，见 [`crate::flow::Flow::fire`]
+    FlowStageChanged { flow: String, from: String, to: String },
+
+    /// 开始写入快照
+    SnapshotStarted { path: PathBuf },
+
+    /// 快照写入成功，`size` 为文件字节数
+    SnapshotSucceeded { path: PathBuf, duration: Duration, size: u64 },
+
+    /// 快照写入失败
+    SnapshotFailed { path: PathBuf, duration: Duration, error: String },
+
     /// 销毁事件
     Destroy,
 
@@ -88,9 +104,30 @@ where
             EventGeneric::Redo { .. } => "Redo",
             EventGeneric::Jump { .. } => "Jump",
             EventGeneric::TrFailed { .. } => "TrFailed",
+            EventGeneric::CommandProgress { .. } => "CommandProgress",
             EventGeneric::HistoryCleared => "HistoryCleared",
+            EventGeneric::FlowStageChanged { .. } => "FlowStageChanged",
+            EventGeneric::SnapshotStarted { .. } => "SnapshotStarted",
+            EventGeneric::SnapshotSucceeded { .. } => "SnapshotSucceeded",
+            EventGeneric::SnapshotFailed { .. } => "SnapshotFailed",
             EventGeneric::Destroy => "Destroy",
             EventGeneric::Stop => "Stop",
         }
     }
+
+    /// 事件对应修改的发起者，取本次提交根事务的 actor
+    ///
+    /// 插件追加的事务沿用根事务的 actor，因此取第一个事务即可
+    pub fn actor(&self) -> Option<&str> {
+        match self {
+            EventGeneric::TrApply { transactions, .. }
+            | EventGeneric::Undo { transactions, .. }
+            | EventGeneric::Redo { transactions, .. }
+            | EventGeneric::Jump { transactions, .. } => {
+                transactions.first().and_then(|tr| tr.actor())
+            },
+            EventGeneric::TrFailed { transaction, .. } => transaction.actor(),
+            _ => None,
+        }
+    }
 }
@@ -5,11 +5,16 @@
 use std::sync::Arc;
 use std::marker::PhantomData;
 use std::ops::Deref;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
 
 use mf_model::traits::{DataContainer, SchemaDefinition};
-use mf_state::{ops::GlobalResourceManager, plugin::PluginGeneric};
+use mf_state::{
+    ops::{GlobalResourceManager, OpError},
+    plugin::PluginGeneric,
+};
 
-use crate::{types::GlobalAttributeItem, ForgeResult};
+use crate::{error::error_utils, types::GlobalAttributeItem, ForgeResult};
 
 /// 操作函数项的内部类型
 /// GlobalResourceManager 当前不是泛型的
@@ -24,6 +29,8 @@ where
     S: SchemaDefinition<Container = C> + 'static,
 {
     inner: OpFnItemInner,
+    /// 操作名称与超时时间，未设置时在当前线程直接执行
+    timeout: Option<(String, Duration)>,
     _phantom: PhantomData<(C, S)>,
 }
 
@@ -35,13 +42,89 @@ where
     pub fn new(f: OpFnItemInner) -> Self {
         Self {
             inner: f,
+            timeout: None,
             _phantom: PhantomData,
         }
     }
 
+    /// 为操作设置超时
+    ///
+    /// 设置后操作在独立线程中执行，超时或 panic 时返回携带 [`OpError`] 的状态错误；
+    /// 超时的线程不会被强制终止，只是不再等待其结果
+    pub fn with_timeout(
+        mut self,
+        name: impl Into<String>,
+        timeout: Duration,
+    ) -> Self {
+        self.timeout = Some((name.into(), timeout));
+        self
+    }
+
+    /// 操作名称（仅在设置超时时存在）
+    pub fn name(&self) -> Option<&str> {
+        self.timeout.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout.as_ref().map(|(_, timeout)| *timeout)
+    }
+
     pub fn call(&self, manager: &GlobalResourceManager) -> ForgeResult<()> {
         (self.inner)(manager)
     }
+
+    /// 执行操作，设置了超时的操作受超时与 panic 保护
+    pub fn run(&self, manager: &Arc<GlobalResourceManager>) -> ForgeResult<()> {
+        let Some((name, timeout)) = &self.timeout else {
+            return self.call(manager);
+        };
+        let (tx, rx) = std::sync::mpsc::channel();
+        let f = self.inner.clone();
+        let manager = manager.clone();
+        std::thread::Builder::new()
+            .name(format!("mf-op-{name}"))
+            .spawn(move || {
+                let result =
+                    std::panic::catch_unwind(AssertUnwindSafe(|| f(&manager)));
+                let _ = tx.send(result);
+            })
+            .map_err(|e| {
+                error_utils::state_error_with_source(
+                    format!("启动操作 {name} 失败"),
+                    e,
+                )
+            })?;
+        let error = match rx.recv_timeout(*timeout) {
+            Ok(Ok(result)) => return result,
+            Ok(Err(panic)) => OpError::Panicked {
+                op: name.clone(),
+                message: panic_message(panic.as_ref()),
+            },
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                OpError::Timeout { op: name.clone(), timeout: *timeout }
+            },
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                OpError::Panicked {
+                    op: name.clone(),
+                    message: "操作线程意外退出".to_string(),
+                }
+            },
+        };
+        Err(error_utils::state_error_with_source(
+            format!("执行操作函数失败: {name}"),
+            error,
+        ))
+    }
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = panic.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = panic.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "未知 panic".to_string()
+    }
 }
 
 /// 实现 Deref 以便可以像函数一样调用
@@ -125,6 +208,10 @@ where
     plugins: Vec<Arc<PluginGeneric<C, S>>>,
     op_fn: Option<OpFnGeneric<C, S>>,
     node_transform: Option<NodeTransformFnGeneric<C, S>>,
+    /// 扩展名称，供其他扩展声明依赖
+    name: Option<String>,
+    /// 依赖的扩展名称
+    dependencies: Vec<String>,
 }
 
 impl<C, S> Default for ExtensionGeneric<C, S>
@@ -148,9 +235,37 @@ where
             plugins: vec![],
             op_fn: Some(vec![]),
             node_transform: None,
+            name: None,
+            dependencies: vec![],
         }
     }
 
+    pub fn set_name(
+        &mut self,
+        name: impl Into<String>,
+    ) -> &mut Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// 声明依赖的扩展，构建扩展管理器时被依赖的扩展排在前面
+    pub fn add_dependency(
+        &mut self,
+        name: impl Into<String>,
+    ) -> &mut Self {
+        self.dependencies.push(name.into());
+        self
+    }
+
+    /// 依赖的扩展名称
+    pub fn depends_on(&self) -> Vec<&str> {
+        self.dependencies.iter().map(String::as_str).collect()
+    }
+
     pub fn add_node_transform(
         &mut self,
         node_fn: NodeTransformFnGeneric<C, S>,
@@ -199,3 +314,63 @@ where
         &self.plugins
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ForgeError, extension::OpFnItem};
+
+    fn op_error(err: ForgeError) -> OpError {
+        match err {
+            ForgeError::State { source: Some(source), .. } => {
+                source.downcast_ref::<OpError>().cloned().expect("OpError")
+            },
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn op_exceeding_timeout_is_mapped() {
+        let op = OpFnItem::new(Arc::new(|_: &GlobalResourceManager| {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(())
+        }))
+        .with_timeout("op_slow", Duration::from_millis(20));
+        let manager = Arc::new(GlobalResourceManager::new());
+
+        let err = op_error(op.run(&manager).unwrap_err());
+        assert_eq!(
+            err,
+            OpError::Timeout {
+                op: "op_slow".to_string(),
+                timeout: Duration::from_millis(20)
+            }
+        );
+    }
+
+    #[test]
+    fn op_panic_is_mapped() {
+        let op = OpFnItem::new(Arc::new(|_: &GlobalResourceManager| {
+            panic!("boom")
+        }))
+        .with_timeout("op_panic", Duration::from_secs(5));
+        let manager = Arc::new(GlobalResourceManager::new());
+
+        let err = op_error(op.run(&manager).unwrap_err());
+        assert_eq!(err.op(), "op_panic");
+        assert!(matches!(err, OpError::Panicked { message, .. } if message == "boom"));
+    }
+
+    #[test]
+    fn op_within_timeout_runs() {
+        let op = OpFnItem::new(Arc::new(|manager: &GlobalResourceManager| {
+            manager.put(7u32);
+            Ok(())
+        }))
+        .with_timeout("op_fast", Duration::from_secs(5));
+        let manager = Arc::new(GlobalResourceManager::new());
+
+        op.run(&manager).unwrap();
+        assert_eq!(manager.get::<u32>().as_deref(), Some(&7));
+    }
+}
@@ -185,7 +185,8 @@ where
         u64,
         Receiver<AsyncTaskResult<TaskParamsGeneric<C, S>, ProcessorResultGeneric<C, S>>>,
     )> {
-        self.processor.submit_task(params, 0).await
+        let (handle, rx) = self.processor.submit_task(params, 0).await?;
+        Ok((handle.task_id(), rx))
     }
 
     pub async fn submit_transactions(
@@ -88,7 +88,10 @@ where
 // ==================== Transaction Processor Messages ====================
 
 /// 事务处理 Actor 消息（泛型版本）
+///
+/// 消息只在投递时移动一次，`UpdateConfig` 携带完整配置无需装箱
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum TransactionMessageGeneric<C, S>
 where
     C: DataContainer + 'static,
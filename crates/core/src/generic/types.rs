@@ -55,6 +55,11 @@ where
         }
     }
 
+    /// 条目 id，取第一个事务的 id
+    pub fn id(&self) -> u64 {
+        self.transactions.first().map(|tr| tr.id).unwrap_or_default()
+    }
+
     /// 创建批量事务历史条目
     pub fn new_batch(
         transactions: Vec<Arc<TransactionGeneric<C, S>>>,
@@ -0,0 +1,75 @@
+//! 命令执行辅助模块
+//!
+//! 统一三种运行时的命令执行逻辑，包括：
+//! - 执行前后的取消检查
+//! - 进度上报转发为 `Event::CommandProgress`
+
+use crate::{
+    debug::debug,
+    error::{error_utils, ForgeResult},
+    event::{Event, EventBus},
+};
+use mf_model::{node_pool::NodePool, schema::Schema};
+use mf_state::transaction::{CommandContext, CommandGeneric, Transaction};
+use std::sync::Arc;
+
+/// 命令执行辅助器
+pub struct CommandHelper;
+
+impl CommandHelper {
+    /// 在上下文中执行命令
+    ///
+    /// 执行期间被取消时立即放弃命令；无论命令是否返回成功，只要已取消都返回
+    /// `ForgeError::Cancelled`，调用方不会再提交或应用该事务。
+    ///
+    /// # 参数
+    /// * `command` - 要执行的命令
+    /// * `tr` - 命令写入的事务
+    /// * `ctx` - 执行上下文
+    ///
+    /// # 返回值
+    /// * `ForgeResult<()>` - 成功或错误
+    pub async fn execute(
+        command: &Arc<dyn CommandGeneric<NodePool, Schema>>,
+        tr: &mut Transaction,
+        ctx: &CommandContext,
+    ) -> ForgeResult<()> {
+        let name = command.name();
+        let cancelled = || error_utils::cancelled_error(format!("命令 {name}"));
+        if ctx.is_cancelled() {
+            return Err(cancelled());
+        }
+
+        let result = tokio::select! {
+            biased;
+            _ = ctx.cancellation_token().cancelled() => None,
+            result = command.execute_with(ctx, tr) => Some(result),
+        };
+        match result {
+            Some(result) if !ctx.is_cancelled() => Ok(result?),
+            _ => {
+                debug!("命令 '{}' 已取消", name);
+                Err(cancelled())
+            },
+        }
+    }
+
+    /// 为上下文追加进度转发：每次上报都广播一个 `Event::CommandProgress`
+    ///
+    /// 使用非阻塞广播，事件队列已满时丢弃该次进度
+    pub fn forward_progress(
+        ctx: CommandContext,
+        event_bus: &EventBus<Event>,
+        command: String,
+    ) -> CommandContext {
+        let event_bus = event_bus.clone();
+        ctx.on_progress(move |progress| {
+            let _ = event_bus.try_broadcast(Event::CommandProgress {
+                command: command.clone(),
+                done: progress.done,
+                total: progress.total,
+                message: progress.message.clone(),
+            });
+        })
+    }
+}
@@ -1,6 +1,11 @@
 use std::collections::HashMap;
 
-use mf_model::schema::{AttributeSpec, Schema, SchemaSpec};
+use mf_model::{
+    dynamic_default::DynamicDefault,
+    node_definition::NodeSpec,
+    schema::{AttributeSpec, Schema, SchemaSpec},
+};
+use serde_json::Value;
 
 use crate::{
     types::{Extensions, GlobalAttributeItem},
@@ -20,7 +25,9 @@ use crate::{
 /// 2. 处理节点扩展，构建节点定义
 /// 3. 处理标记扩展，构建标记定义
 /// 4. 确定顶层节点名称
-/// 5. 编译生成最终的 Schema
+/// 5. 编译生成最终的 Schema，并注册全局属性的动态默认值
+///
+/// 节点类型自身声明的属性优先于全局属性
 pub fn get_schema_by_resolved_extensions(
     extensions: &Vec<Extensions>
 ) -> ForgeResult<Schema> {
@@ -42,6 +49,7 @@ pub fn get_schema_by_resolved_extensions(
     let mut nodes = HashMap::new();
     let mut marks = HashMap::new();
     let mut top_name = "doc".to_string();
+    let mut dynamic_defaults = vec![];
 
     // 处理每个扩展
     for extension in extensions {
@@ -61,7 +69,20 @@ pub fn get_schema_by_resolved_extensions(
                     top_name = node.name.clone();
                 }
                 // 获取节点的属性定义
-                let mut attrs = get_attr_dfn(name, &extension_attributes);
+                let mut attrs =
+                    get_attr_dfn(&name, &node.r#type, &extension_attributes);
+                for (key, default) in get_dynamic_defaults(
+                    &name,
+                    &node.r#type,
+                    &extension_attributes,
+                ) {
+                    // 动态默认值以 null 占位，创建节点时由回调计算
+                    attrs.insert(
+                        key.clone(),
+                        AttributeSpec::new(Some(Value::Null)),
+                    );
+                    dynamic_defaults.push((name.clone(), key, default));
+                }
 
                 // 合并节点类型中定义的属性
                 let attrs_def = match &node.r#type.attrs {
@@ -88,7 +109,10 @@ pub fn get_schema_by_resolved_extensions(
 
     // 创建 Schema 规范并编译
     let instance_spec = SchemaSpec { nodes, marks, top_node: Some(top_name) };
-    let schema = Schema::compile(instance_spec)?;
+    let mut schema = Schema::compile(instance_spec)?;
+    for (node_type, attr, default) in dynamic_defaults {
+        schema.set_dynamic_default(&node_type, &attr, default)?;
+    }
     Ok(schema)
 }
 
@@ -96,18 +120,20 @@ pub fn get_schema_by_resolved_extensions(
 ///
 /// # 参数
 /// * `name` - 节点名称
+/// * `spec` - 节点规范
 /// * `extension_attributes` - 全局属性列表
 ///
 /// # 返回值
 /// * `HashMap<String, AttributeSpec>` - 节点对应的属性定义映射
 fn get_attr_dfn(
-    name: String,
+    name: &str,
+    spec: &NodeSpec,
     extension_attributes: &Vec<&GlobalAttributeItem>,
 ) -> HashMap<String, AttributeSpec> {
     let mut attributes: HashMap<String, AttributeSpec> = HashMap::new();
     // 遍历全局属性，找出适用于当前节点的属性
     for attr in extension_attributes.iter() {
-        if attr.types.contains(&name) {
+        if attr.applies_to(name, spec) {
             attr.attributes.iter().for_each(|e| {
                 attributes.insert(e.0.clone(), e.1.clone());
             });
@@ -115,3 +141,137 @@ fn get_attr_dfn(
     }
     attributes
 }
+
+/// 获取适用于指定节点的动态默认值，节点自身声明的属性不参与
+fn get_dynamic_defaults(
+    name: &str,
+    spec: &NodeSpec,
+    extension_attributes: &Vec<&GlobalAttributeItem>,
+) -> HashMap<String, DynamicDefault> {
+    let own = spec.attrs.as_ref();
+    let mut defaults = HashMap::new();
+    for attr in extension_attributes.iter() {
+        if attr.applies_to(name, spec) {
+            for (key, default) in &attr.dynamic_defaults {
+                if !own.is_some_and(|own| own.contains_key(key)) {
+                    defaults.insert(key.clone(), default.clone());
+                }
+            }
+        }
+    }
+    defaults
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{extension::Extension, node::Node};
+
+    fn node(
+        name: &str,
+        group: Option<&str>,
+        content: Option<&str>,
+        attrs: &[(&str, Value)],
+    ) -> Extensions {
+        let attrs = attrs
+            .iter()
+            .map(|(key, default)| {
+                (key.to_string(), AttributeSpec::new(Some(default.clone())))
+            })
+            .collect::<HashMap<_, _>>();
+        let spec = NodeSpec {
+            group: group.map(str::to_string),
+            content: content.map(str::to_string),
+            attrs: (!attrs.is_empty()).then_some(attrs),
+            ..Default::default()
+        };
+        Extensions::N(Node::create(name, spec))
+    }
+
+    #[test]
+    fn test_global_attributes_by_group_and_filter() {
+        let user = Arc::new(Mutex::new("alice".to_string()));
+        let current = user.clone();
+        let mut extension = Extension::new();
+        extension.add_global_attribute(
+            GlobalAttributeItem::for_groups(["report"])
+                .with_attribute("status", json!("draft"))
+                .with_dynamic_default("created_by", move |_| {
+                    Value::from(current.lock().unwrap().clone())
+                }),
+        );
+        extension.add_global_attribute(
+            GlobalAttributeItem::for_filter(|_, spec| spec.content.is_none())
+                .with_attribute("leaf", json!(true)),
+        );
+        extension.add_global_attribute(
+            GlobalAttributeItem {
+                types: vec!["*".to_string()],
+                ..Default::default()
+            }
+            .with_attribute("locked", json!(false)),
+        );
+        let schema = get_schema_by_resolved_extensions(&vec![
+            node("doc", None, Some("report+"), &[]),
+            node("report", Some("block report"), None, &[]),
+            node(
+                "summary",
+                Some("report"),
+                None,
+                &[("created_by", json!("system"))],
+            ),
+            Extensions::E(extension),
+        ])
+        .unwrap();
+        let factory = schema.factory();
+
+        // 结构节点只得到通配的全局属性
+        let doc = factory.node_definition("doc").unwrap();
+        let mut doc_attrs: Vec<&str> =
+            doc.attrs.keys().map(String::as_str).collect();
+        doc_attrs.sort();
+        assert_eq!(doc_attrs, ["locked"]);
+
+        let report =
+            factory.create_node("report", None, None, vec![], None).unwrap();
+        assert_eq!(report.attrs["created_by"], json!("alice"));
+        assert_eq!(report.attrs["status"], json!("draft"));
+        assert_eq!(report.attrs["leaf"], json!(true));
+
+        // 动态默认值在每次创建时计算，显式提供的值优先
+        *user.lock().unwrap() = "bob".to_string();
+        let definition = factory.node_definition("report").unwrap();
+        let report = definition.create_with_defaults(None).unwrap();
+        assert_eq!(report.attrs["created_by"], json!("bob"));
+        let given = HashMap::from([("created_by".to_string(), json!("carol"))]);
+        let report = factory
+            .create_node("report", None, Some(&given), vec![], None)
+            .unwrap();
+        assert_eq!(report.attrs["created_by"], json!("carol"));
+
+        // 节点类型自身声明的属性优先于全局默认值
+        let summary =
+            factory.create_node("summary", None, None, vec![], None).unwrap();
+        assert_eq!(summary.attrs["created_by"], json!("system"));
+        assert_eq!(summary.attrs["status"], json!("draft"));
+
+        let effective: Vec<(&str, bool)> = definition
+            .effective_attrs()
+            .iter()
+            .map(|attr| (attr.name, attr.dynamic))
+            .collect();
+        assert_eq!(
+            effective,
+            [
+                ("created_by", true),
+                ("leaf", false),
+                ("locked", false),
+                ("status", false),
+            ]
+        );
+    }
+}
@@ -7,8 +7,11 @@
 //! - 性能指标记录
 //! - 事件触发，供其他组件（如搜索索引）响应
 
-use crate::{history_manager::HistoryManager, metrics, types::HistoryEntryWithMeta};
-use mf_state::{state::State, Transaction};
+use crate::{
+    config::NonUserHistoryMode, history_manager::HistoryManager, metrics,
+    types::HistoryEntryWithMeta,
+};
+use mf_state::{state::State, Transaction, TransactionOrigin};
 use std::sync::Arc;
 
 /// 历史操作结果
@@ -172,6 +175,34 @@ impl HistoryHelper {
             return;
         }
 
+        let origin = transactions[0].origin();
+        if matches!(
+            origin,
+            TransactionOrigin::Plugin(_) | TransactionOrigin::System(_)
+        ) {
+            match history_manager.get_config().non_user_transactions {
+                NonUserHistoryMode::Record => {},
+                NonUserHistoryMode::Skip => {
+                    let mut present = history_manager.get_present();
+                    present.state = state;
+                    history_manager.replace_present(present);
+                    return;
+                },
+                NonUserHistoryMode::Merge => {
+                    let mut present = history_manager.get_present();
+                    let trigger = transactions[0].caused_by();
+                    if trigger.is_some_and(|id| {
+                        present.transactions.iter().any(|tr| tr.id == id)
+                    }) {
+                        present.transactions.extend(transactions);
+                        present.state = state;
+                        history_manager.replace_present(present);
+                        return;
+                    }
+                },
+            }
+        }
+
         let entry = if transactions.len() == 1 {
             HistoryEntryWithMeta::new(
                 transactions[0].clone(),
@@ -4,6 +4,7 @@ pub mod runtime_common;
 
 // 新的辅助模块
 pub mod aggregation_helper;
+pub mod command_helper;
 pub mod event_helper;
 pub mod history_helper;
 pub mod middleware_helper;
@@ -18,7 +18,18 @@ impl<T: Clone> History<T> {
     }
 }
 
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use mf_model::{traits::DataContainer, types::NodeId};
+use mf_state::Transaction;
+use mf_transform::touched_nodes;
+
 use crate::config::HistoryConfig;
+use crate::error::ForgeError;
+use crate::types::HistoryEntryWithMeta;
 
 /// 历史管理器
 pub struct HistoryManager<T: Clone> {
@@ -99,6 +110,18 @@ impl<T: Clone> HistoryManager<T> {
         self.history.latest_unfiltered = state;
     }
 
+    /// 替换当前状态，不新增条目
+    ///
+    /// 与 `insert` 一样会清空可重做的未来状态
+    pub fn replace_present(
+        &mut self,
+        state: T,
+    ) {
+        self.history.present = state.clone();
+        self.history.latest_unfiltered = state;
+        self.history.future.clear();
+    }
+
     /// 跳转到未来状态
     ///
     /// # 边界检查
@@ -285,6 +308,190 @@ impl<T: Clone> HistoryManager<T> {
     }
 }
 
+/// 可选择性撤销的历史条目
+#[derive(Debug, Clone)]
+pub struct HistoryEntryInfo {
+    /// 条目 id（条目中第一个事务的 id）
+    pub entry_id: u64,
+    /// 操作描述（通常为命令名称）
+    pub description: String,
+    pub timestamp: SystemTime,
+    /// 修改到的节点数，包含无法分析的步骤时为 `None`
+    pub changed_nodes: Option<usize>,
+}
+
+/// 阻止选择性撤销的后续条目
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndoBlocker {
+    pub entry_id: u64,
+    pub description: String,
+    /// 与目标条目共同修改的节点，后续条目无法分析时为空
+    pub nodes: Vec<NodeId>,
+}
+
+/// 选择性撤销失败原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectiveUndoError {
+    /// 条目不存在、已被撤销，或是历史中最早的条目（缺少撤销前的状态）
+    NotFound(u64),
+    /// 目标条目包含无法分析或无法反转的步骤
+    Unsupported { entry_id: u64, message: String },
+    /// 后续条目修改了相同的节点
+    Blocked { entry_id: u64, blockers: Vec<UndoBlocker> },
+    /// 反向步骤应用到当前文档失败
+    Apply { entry_id: u64, message: String },
+}
+
+impl fmt::Display for SelectiveUndoError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            SelectiveUndoError::NotFound(id) => {
+                write!(f, "历史条目 {id} 不存在或不可撤销")
+            },
+            SelectiveUndoError::Unsupported { entry_id, message } => {
+                write!(f, "历史条目 {entry_id} 无法撤销: {message}")
+            },
+            SelectiveUndoError::Blocked { entry_id, blockers } => {
+                let ids: Vec<String> =
+                    blockers.iter().map(|b| b.entry_id.to_string()).collect();
+                write!(
+                    f,
+                    "历史条目 {entry_id} 被后续条目阻止撤销: {}",
+                    ids.join(", ")
+                )
+            },
+            SelectiveUndoError::Apply { entry_id, message } => {
+                write!(f, "历史条目 {entry_id} 的反向步骤应用失败: {message}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for SelectiveUndoError {}
+
+impl From<SelectiveUndoError> for ForgeError {
+    fn from(err: SelectiveUndoError) -> Self {
+        ForgeError::History {
+            message: err.to_string(),
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+/// 条目中所有事务修改到的节点
+fn entry_touched_nodes(
+    entry: &HistoryEntryWithMeta
+) -> Option<HashSet<NodeId>> {
+    let mut touched = HashSet::new();
+    for tr in &entry.transactions {
+        touched.extend(touched_nodes(tr)?);
+    }
+    Some(touched)
+}
+
+impl HistoryManager<HistoryEntryWithMeta> {
+    /// 已生效的条目（不含已撤销的条目与没有前置状态的最早条目），由旧到新
+    pub fn entries(&self) -> Vec<HistoryEntryInfo> {
+        self.applied_entries()
+            .skip(1)
+            .map(|entry| HistoryEntryInfo {
+                entry_id: entry.id(),
+                description: entry.description.clone(),
+                timestamp: entry.timestamp,
+                changed_nodes: entry_touched_nodes(entry).map(|n| n.len()),
+            })
+            .collect()
+    }
+
+    /// 选择性撤销某个历史条目
+    ///
+    /// 基于当前状态构建目标条目的反向事务，历史保持只追加，由调用方提交该事务。
+    /// 若任一后续条目修改了相同的节点则拒绝撤销，并在错误中列出这些条目。
+    pub fn undo_entry(
+        &self,
+        entry_id: u64,
+    ) -> Result<Transaction, SelectiveUndoError> {
+        let applied: Vec<&HistoryEntryWithMeta> =
+            self.applied_entries().collect();
+        let index = applied
+            .iter()
+            .skip(1)
+            .position(|entry| entry.id() == entry_id)
+            .map(|i| i + 1)
+            .ok_or(SelectiveUndoError::NotFound(entry_id))?;
+        let target = applied[index];
+
+        let touched = entry_touched_nodes(target).ok_or_else(|| {
+            SelectiveUndoError::Unsupported {
+                entry_id,
+                message: "包含无法分析的步骤".to_string(),
+            }
+        })?;
+        let blockers: Vec<UndoBlocker> = applied[index + 1..]
+            .iter()
+            .filter_map(|later| {
+                let nodes = match entry_touched_nodes(later) {
+                    Some(nodes) => {
+                        let mut shared: Vec<NodeId> =
+                            nodes.intersection(&touched).cloned().collect();
+                        if shared.is_empty() {
+                            return None;
+                        }
+                        shared.sort();
+                        shared
+                    },
+                    None => Vec::new(),
+                };
+                Some(UndoBlocker {
+                    entry_id: later.id(),
+                    description: later.description.clone(),
+                    nodes,
+                })
+            })
+            .collect();
+        if !blockers.is_empty() {
+            return Err(SelectiveUndoError::Blocked { entry_id, blockers });
+        }
+
+        // 在目标条目的前置状态上重放，逐步计算反向步骤
+        let mut replay = applied[index - 1].state.tr();
+        let mut inverted = Vec::new();
+        for step in target.transactions.iter().flat_map(|tr| tr.steps.iter()) {
+            let doc = replay.doc();
+            let inverse = step
+                .invert(&Arc::new(doc.inner().clone()))
+                .ok_or_else(|| SelectiveUndoError::Unsupported {
+                    entry_id,
+                    message: format!("步骤 {} 不可反转", step.name()),
+                })?;
+            inverted.push(inverse);
+            replay.step(step.clone()).map_err(|e| {
+                SelectiveUndoError::Unsupported {
+                    entry_id,
+                    message: e.to_string(),
+                }
+            })?;
+        }
+
+        let mut tr = self.history.present.state.tr();
+        for step in inverted.into_iter().rev() {
+            tr.step(step).map_err(|e| SelectiveUndoError::Apply {
+                entry_id,
+                message: e.to_string(),
+            })?;
+        }
+        tr.set_meta("undo_entry", entry_id);
+        Ok(tr)
+    }
+
+    fn applied_entries(&self) -> impl Iterator<Item = &HistoryEntryWithMeta> {
+        self.history.past.iter().chain(std::iter::once(&self.history.present))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -384,4 +591,165 @@ mod tests {
         manager.jump(1); // 跳转到 state2
         assert_eq!(manager.get_present(), "state2");
     }
+
+    mod selective_undo {
+        use super::*;
+        use mf_model::{
+            attrs::Attrs,
+            node::Node,
+            node_definition::NodeSpec,
+            node_pool::NodePool,
+            rpds::HashTrieMapSync,
+            schema::{AttributeSpec, Schema, SchemaSpec},
+            tree::Tree,
+        };
+        use mf_state::{State, StateConfig};
+        use mf_transform::attr_step::AttrStep;
+        use serde_json::{Value, json};
+        use std::collections::HashMap;
+
+        async fn create_state() -> Arc<State> {
+            let mut attrs = HashMap::new();
+            attrs.insert(
+                "price".to_string(),
+                AttributeSpec::new(Some(json!(0))),
+            );
+            let mut nodes = HashMap::new();
+            nodes.insert(
+                "doc".to_string(),
+                NodeSpec {
+                    content: Some("item*".to_string()),
+                    ..Default::default()
+                },
+            );
+            nodes.insert(
+                "item".to_string(),
+                NodeSpec { attrs: Some(attrs), ..Default::default() },
+            );
+            let schema = Schema::compile(SchemaSpec {
+                nodes,
+                marks: HashMap::new(),
+                top_node: Some("doc".to_string()),
+            })
+            .unwrap();
+
+            let item = |id: &str| {
+                let mut attrs = HashTrieMapSync::new_sync();
+                attrs.insert_mut("price".to_string(), json!(10));
+                Node::new(
+                    id,
+                    "item".to_string(),
+                    Attrs::from(attrs),
+                    vec![],
+                    vec![],
+                )
+            };
+            let root = Node::new(
+                "root",
+                "doc".to_string(),
+                Attrs::default(),
+                vec![],
+                vec![],
+            );
+            let mut tree = Tree::new(root);
+            tree.add_node(&"root".into(), &vec![item("a"), item("b")]).unwrap();
+
+            Arc::new(
+                State::create(StateConfig {
+                    schema: Some(Arc::new(schema)),
+                    doc: Some(NodePool::new(Arc::new(tree))),
+                    stored_marks: None,
+                    plugins: None,
+                    resource_manager: None,
+                })
+                .await
+                .unwrap(),
+            )
+        }
+
+        /// 设置节点价格并记录历史，返回条目 id
+        async fn set_price(
+            manager: &mut HistoryManager<HistoryEntryWithMeta>,
+            id: &str,
+            price: i64,
+        ) -> u64 {
+            let state = manager.get_present().state;
+            let mut values = HashTrieMapSync::new_sync();
+            values.insert_mut("price".to_string(), json!(price));
+            let mut tr = state.tr();
+            tr.step(Arc::new(AttrStep::new(id.into(), values))).unwrap();
+            let result = state.apply(tr).await.unwrap();
+            let entry = HistoryEntryWithMeta::new_batch(
+                result.transactions,
+                result.state,
+                format!("设置 {id} 价格"),
+                Value::Null,
+            );
+            let entry_id = entry.id();
+            manager.insert(entry);
+            entry_id
+        }
+
+        fn price(
+            state: &State,
+            id: &str,
+        ) -> Value {
+            state.doc().get_node(&id.into()).unwrap().attrs["price"].clone()
+        }
+
+        async fn create_manager() -> HistoryManager<HistoryEntryWithMeta> {
+            let state = create_state().await;
+            HistoryManager::new(
+                HistoryEntryWithMeta::new(
+                    Arc::new(state.tr()),
+                    state,
+                    "初始状态".to_string(),
+                    Value::Null,
+                ),
+                Some(10),
+            )
+        }
+
+        #[tokio::test]
+        async fn test_undo_entry_with_unrelated_edits() {
+            let mut manager = create_manager().await;
+            let target = set_price(&mut manager, "a", 0).await;
+            set_price(&mut manager, "b", 20).await;
+
+            let entries = manager.entries();
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[0].entry_id, target);
+            assert_eq!(entries[0].description, "设置 a 价格");
+            assert_eq!(entries[0].changed_nodes, Some(1));
+
+            let tr = manager.undo_entry(target).unwrap();
+            assert_eq!(tr.get_meta::<u64>("undo_entry"), Some(target));
+            let state =
+                manager.get_present().state.apply(tr).await.unwrap().state;
+            assert_eq!(price(&state, "a"), json!(10));
+            assert_eq!(price(&state, "b"), json!(20));
+        }
+
+        #[tokio::test]
+        async fn test_undo_entry_blocked_by_later_edit() {
+            let mut manager = create_manager().await;
+            let target = set_price(&mut manager, "a", 0).await;
+            set_price(&mut manager, "b", 20).await;
+            let blocker = set_price(&mut manager, "a", 5).await;
+
+            match manager.undo_entry(target) {
+                Err(SelectiveUndoError::Blocked { entry_id, blockers }) => {
+                    assert_eq!(entry_id, target);
+                    assert_eq!(blockers.len(), 1);
+                    assert_eq!(blockers[0].entry_id, blocker);
+                    assert_eq!(blockers[0].nodes, vec![NodeId::from("a")]);
+                },
+                other => panic!("应被后续条目阻止: {other:?}"),
+            }
+            assert!(matches!(
+                manager.undo_entry(0),
+                Err(SelectiveUndoError::NotFound(0))
+            ));
+        }
+    }
 }
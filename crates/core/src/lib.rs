@@ -30,10 +30,13 @@ pub mod error_helpers;
 pub mod event;
 pub mod extension;
 pub mod extension_manager;
+pub mod flow;
 pub mod helpers;
 pub mod history_manager;
 #[cfg(test)]
 pub mod test_helpers;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
 
 pub mod mark;
 pub mod metrics;
@@ -60,13 +63,25 @@ pub use error_helpers::{
 
 // 公共 API 导出
 pub use runtime::async_processor::{
-    AsyncProcessor, ProcessorError, TaskProcessor, TaskResult, TaskStatus,
+    AsyncProcessor, ProcessorError, TaskHandle, TaskProcessor, TaskResult,
+    TaskStatus,
 };
 pub use runtime::async_runtime::ForgeAsyncRuntime;
 // 新的Actor运行时
 pub use runtime::actor_runtime::ForgeActorRuntime;
 // 运行时统一接口
 pub use runtime::runtime_trait::{RuntimeTrait};
+// 运行时快照
+pub use runtime::snapshot::{
+    SnapshotHeader, SnapshotInfo, SNAPSHOT_EXTENSION, SNAPSHOT_FORMAT_VERSION,
+    list_snapshots, read_snapshot_header, schema_fingerprint,
+};
+// 文档级咨询锁
+pub use runtime::doc_lock::{
+    DispatchGuard, DocLock, DocLockInfo, DocLockManager, DocLockPolicy,
+    LockScope,
+};
+pub use runtime::runtime::RuntimeDebugReport;
 // 运行时构建器和自适应配置
 pub use runtime::builder::{ForgeRuntimeBuilder, AnyRuntime};
 pub use runtime::system_detector::{SystemResources, ResourceTier};
@@ -74,17 +89,27 @@ pub use runtime::adaptive::AdaptiveRuntimeSelector;
 pub use config::{
     ForgeConfig, ForgeConfigBuilder, Environment, ProcessorConfig,
     PerformanceConfig, EventConfig, HistoryConfig, ExtensionConfig,
-    CacheConfig, ConfigValidationError, RuntimeType, RuntimeConfig,
+    CacheConfig, SnapshotConfig, ConfigValidationError, RuntimeType,
+    RuntimeConfig, NonUserHistoryMode,
 };
 pub use error::ForgeError;
-pub use event::{Event, EventBus, EventHandler};
+pub use mf_error_codes::{ErrorCode, ErrorKind, ErrorWire, ToWire};
+pub use event::{Event, EventBus, EventHandler, ReplaySubscription};
 pub use extension::Extension;
-pub use extension_manager::{ExtensionManager, ExtensionManagerBuilder};
+pub use flow::{
+    Flow, FlowBuilder, FlowGuard, FlowState, Stage, StageChange, StageHook,
+    Transition, Trigger,
+};
+pub use extension_manager::{
+    ExtensionManager, ExtensionManagerBuilder, ExtensionManifest,
+    PluginRegistry,
+};
 pub use history_manager::{History, HistoryManager};
 
 pub use runtime::runtime::ForgeRuntime;
 pub use schema_parser::{
-    XmlSchemaParser, XmlSchemaSerializer, XmlSchemaError, XmlSchemaResult,
+    SchemaXsdExt, XmlSchemaParser, XmlSchemaSerializer, XmlSchemaError,
+    XmlSchemaResult,
 };
 pub use runtime::sync_processor::{
     SyncProcessor, TaskProcessor as SyncTaskProcessor,
@@ -41,11 +41,11 @@ impl Mark {
     ) -> &mut Self {
         match &mut self.r#type.attrs {
             Some(map) => {
-                map.insert(name.to_string(), AttributeSpec { default });
+                map.insert(name.to_string(), AttributeSpec::new(default));
             },
             None => {
                 let mut new_map = HashMap::new();
-                new_map.insert(name.to_string(), AttributeSpec { default });
+                new_map.insert(name.to_string(), AttributeSpec::new(default));
                 self.r#type.attrs = Some(new_map);
             },
         }
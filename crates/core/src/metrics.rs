@@ -1,5 +1,12 @@
 use metrics::{counter, gauge, histogram};
 
+// 插件执行耗时 指标（由 `State::apply` 记录）
+pub use mf_state::plugin::timing::{
+    plugin_timing, plugin_timings, reset_plugin_timings, PluginPhase,
+    PluginTimer, PluginTiming, PluginTimings, PLUGIN_EXECUTIONS_TOTAL,
+    PLUGIN_EXECUTION_DURATION_SECONDS,
+};
+
 /// 已提交任务总数
 pub const TASKS_SUBMITTED_TOTAL: &str = "core.tasks.submitted.total";
 /// 已处理任务总数
@@ -128,3 +135,87 @@ pub fn plugins_loaded(count: u64) {
 pub fn xml_parsing_duration(duration: std::time::Duration) {
     histogram!(XML_PARSING_DURATION_SECONDS).record(duration.as_secs_f64());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashMap, sync::Arc, time::Duration};
+
+    use async_trait::async_trait;
+    use mf_model::{
+        node_definition::NodeSpec,
+        node_pool::NodePool,
+        schema::{Schema, SchemaSpec},
+    };
+    use mf_state::{
+        plugin::{Plugin, PluginMetadata, PluginSpec, PluginTraitGeneric},
+        state::StateGeneric,
+        transaction::TransactionGeneric,
+        State, StateConfig,
+    };
+
+    const SLOW_PLUGIN: &str = "metrics_slow_plugin";
+
+    #[derive(Debug)]
+    struct SlowPlugin;
+
+    #[async_trait]
+    impl PluginTraitGeneric<NodePool, Schema> for SlowPlugin {
+        fn metadata(&self) -> PluginMetadata {
+            PluginMetadata {
+                name: SLOW_PLUGIN.to_string(),
+                version: "1.0.0".to_string(),
+                description: String::new(),
+                author: String::new(),
+                dependencies: vec![],
+                conflicts: vec![],
+                state_fields: vec![],
+                tags: vec![],
+            }
+        }
+
+        async fn filter_transaction(
+            &self,
+            _: &TransactionGeneric<NodePool, Schema>,
+            _: &StateGeneric<NodePool, Schema>,
+        ) -> bool {
+            // 只统计插件自身占用的时间，这里用阻塞模拟耗时的计算
+            std::thread::sleep(Duration::from_millis(20));
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_plugin_timing() {
+        let mut nodes = HashMap::new();
+        nodes.insert("doc".to_string(), NodeSpec::default());
+        let schema = Schema::compile(SchemaSpec {
+            nodes,
+            marks: HashMap::new(),
+            top_node: Some("doc".to_string()),
+        })
+        .unwrap();
+        let state = State::create(StateConfig {
+            schema: Some(Arc::new(schema)),
+            doc: None,
+            stored_marks: None,
+            plugins: Some(vec![Arc::new(Plugin::new(PluginSpec {
+                state_field: None,
+                tr: Arc::new(SlowPlugin),
+            }))]),
+            resource_manager: None,
+        })
+        .await
+        .unwrap();
+        let state = Arc::new(state);
+        state.apply(state.tr()).await.unwrap();
+
+        let timings = plugin_timing(SLOW_PLUGIN).unwrap();
+        let filter = timings.get(PluginPhase::FilterTransaction);
+        assert_eq!(filter.calls, 1);
+        assert!(filter.total >= Duration::from_millis(20));
+        assert_eq!(timings.get(PluginPhase::AppendTransaction).calls, 1);
+        assert!(timings.total().total >= filter.total);
+        assert!(plugin_timings().iter().any(|(key, _)| key == SLOW_PLUGIN));
+    }
+}
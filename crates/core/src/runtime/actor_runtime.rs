@@ -18,12 +18,16 @@ use crate::{
     debug::debug,
     error::{error_utils, ForgeResult},
     event::Event,
+    helpers::command_helper::CommandHelper,
     types::RuntimeOptions,
     metrics,
 };
 
 use mf_model::schema::Schema;
-use mf_state::{state::State, transaction::Transaction};
+use mf_state::{
+    state::State,
+    transaction::{CommandContext, Transaction},
+};
 
 /// Actor运行时 - 新的基于Actor的实现
 ///
@@ -185,7 +189,8 @@ impl ForgeActorRuntime {
         metrics::command_executed(command.name().as_str());
 
         let mut tr = self.get_tr().await?;
-        command.execute(&mut tr).await?;
+        CommandHelper::execute(&command, &mut tr, &CommandContext::new())
+            .await?;
         tr.commit()?;
         self.dispatch(tr).await
     }
@@ -208,7 +213,8 @@ impl ForgeActorRuntime {
         metrics::command_executed(command.name().as_str());
 
         let mut tr = self.get_tr().await?;
-        command.execute(&mut tr).await?;
+        CommandHelper::execute(&command, &mut tr, &CommandContext::new())
+            .await?;
         tr.commit()?;
         self.dispatch_with_meta(tr, description, meta).await
     }
@@ -356,6 +362,10 @@ impl ForgeActorRuntime {
             // 广播销毁事件
             let _ = self.emit_event(Event::Destroy).await;
 
+            if let Ok(state) = self.get_state().await {
+                state.destroy().await;
+            }
+
             // 关闭Actor系统
             if let Some(actor_system) = self.actor_system.take() {
                 ForgeActorSystem::shutdown(actor_system).await.map_err(
@@ -32,7 +32,8 @@ use std::time::Duration;
 
 use crate::config::{
     CacheConfig, EventConfig, ExtensionConfig, ForgeConfig, HistoryConfig,
-    PerformanceConfig, ProcessorConfig, RuntimeConfig, RuntimeType,
+    NonUserHistoryMode, PerformanceConfig, ProcessorConfig, RuntimeConfig,
+    RuntimeType,
 };
 
 use super::system_detector::{ResourceTier, SystemResources};
@@ -223,6 +224,9 @@ impl AdaptiveRuntimeSelector {
 
             // 错误处理：默认不抛出错误
             fail_on_handler_error: false,
+
+            // 回放缓冲区：默认不启用
+            replay_capacity: 0,
         }
     }
 
@@ -252,6 +256,8 @@ impl AdaptiveRuntimeSelector {
                 ResourceTier::Medium => 60,
                 ResourceTier::Low => 120,
             }),
+
+            non_user_transactions: NonUserHistoryMode::Record,
         }
     }
 
@@ -43,7 +43,11 @@ pub struct FlowEngine {
 
 impl FlowEngine {
     pub async fn new() -> ForgeResult<Self> {
-        let config = ProcessorConfig::default();
+        Self::with_config(ProcessorConfig::default()).await
+    }
+
+    /// 按指定配置创建并启动处理器
+    pub async fn with_config(config: ProcessorConfig) -> ForgeResult<Self> {
         let mut processor = AsyncProcessor::new(config, TransactionProcessor);
         processor.start().await.map_err(|e| {
             crate::error::error_utils::engine_error(format!(
@@ -61,7 +65,8 @@ impl FlowEngine {
         u64,
         tokio::sync::mpsc::Receiver<TaskResult<TaskParams, ProcessorResult>>,
     )> {
-        self.processor.submit_task(params, 0).await
+        let (handle, rx) = self.processor.submit_task(params, 0).await?;
+        Ok((handle.task_id(), rx))
     }
 
     pub async fn submit_transactions(
@@ -83,6 +88,23 @@ impl FlowEngine {
         Ok(results)
     }
 
+    /// 运行中调整处理器配置，已排队的事务不会丢失
+    pub fn reconfigure(
+        &self,
+        config: ProcessorConfig,
+    ) -> ForgeResult<()> {
+        self.processor.reconfigure(config).map_err(|e| {
+            crate::error::error_utils::config_error(format!(
+                "调整处理器配置失败: {e}"
+            ))
+        })
+    }
+
+    /// 当前生效的处理器配置
+    pub fn config(&self) -> ProcessorConfig {
+        self.processor.config()
+    }
+
     /// 关闭流引擎
     ///
     /// 注意：由于 processor 被包装在 Arc 中，这个方法只能发送关闭信号
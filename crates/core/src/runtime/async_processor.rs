@@ -1,18 +1,25 @@
 use std::{
     fmt::Display,
-    sync::Arc,
+    sync::{Arc, Mutex, Weak},
     time::{Duration, Instant},
 };
 use crate::{error::error_utils, config::ProcessorConfig, debug::debug};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, watch, OwnedSemaphorePermit, Semaphore};
 use async_trait::async_trait;
 use tokio::select;
+use tokio_util::sync::CancellationToken;
 
 use crate::{metrics, ForgeResult};
 
 /// Type alias for complex receiver type
 type QueueReceiver<T, O> =
-    Arc<tokio::sync::Mutex<Option<mpsc::Receiver<QueuedTask<T, O>>>>>;
+    Arc<tokio::sync::Mutex<Option<mpsc::UnboundedReceiver<QueuedTask<T, O>>>>>;
+
+/// 优先级老化间隔：任务每排队这么久，有效优先级提升 1
+///
+/// 持续提交高优先级任务时，低优先级任务的有效优先级随等待时间增长，
+/// 最终追上并被调度，从而避免饿死。
+pub const PRIORITY_AGING_INTERVAL: Duration = Duration::from_secs(1);
 
 /// 任务处理的结果状态
 /// - Pending: 任务等待处理
@@ -129,12 +136,92 @@ where
     pub processing_time: Option<Duration>,
 }
 
+/// 单个任务的取消令牌与当前状态，由处理器与 [`TaskHandle`] 共享
+struct TaskControl {
+    token: CancellationToken,
+    status: Mutex<TaskStatus>,
+}
+
+impl TaskControl {
+    fn new() -> Self {
+        Self {
+            token: CancellationToken::new(),
+            status: Mutex::new(TaskStatus::Pending),
+        }
+    }
+
+    fn status(&self) -> TaskStatus {
+        self.status.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn set_status(
+        &self,
+        status: TaskStatus,
+    ) {
+        *self.status.lock().unwrap_or_else(|e| e.into_inner()) = status;
+    }
+}
+
+/// 已提交任务的句柄，用于查询状态与取消任务
+///
+/// - 尚未开始的任务被直接移出队列，结果通道收到 `Cancelled`
+/// - 正在执行的任务通过取消令牌通知；处理器可在
+///   [`TaskProcessor::process_with_token`] 中轮询令牌，
+///   未响应时在下一个 await 点被中止
+pub struct TaskHandle<T, O>
+where
+    T: Send + Sync,
+    O: Send + Sync,
+{
+    task_id: u64,
+    control: Arc<TaskControl>,
+    queue: Weak<TaskQueue<T, O>>,
+}
+
+impl<T, O> Clone for TaskHandle<T, O>
+where
+    T: Send + Sync,
+    O: Send + Sync,
+{
+    fn clone(&self) -> Self {
+        Self {
+            task_id: self.task_id,
+            control: self.control.clone(),
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static, O: Clone + Send + Sync + 'static>
+    TaskHandle<T, O>
+{
+    pub fn task_id(&self) -> u64 {
+        self.task_id
+    }
+
+    /// 任务当前状态
+    pub fn status(&self) -> TaskStatus {
+        self.control.status()
+    }
+
+    /// 取消任务，已结束的任务不受影响
+    pub async fn cancel(&self) {
+        self.control.token.cancel();
+        if let Some(queue) = self.queue.upgrade() {
+            queue.remove_cancelled().await;
+        }
+    }
+}
+
 /// 队列中的任务结构
 /// - task: 实际任务数据
 /// - task_id: 任务唯一标识符
 /// - result_tx: 用于发送处理结果的通道发送端
-/// - priority: 任务优先级
+/// - priority: 任务优先级，数值越大越先执行
 /// - retry_count: 重试次数
+/// - enqueued_at: 入队时间，用于优先级老化
+/// - control: 取消令牌与状态
+/// - slot: 占用的队列容量，任务离开队列时释放
 struct QueuedTask<T, O>
 where
     T: Send + Sync,
@@ -145,11 +232,118 @@ where
     result_tx: mpsc::Sender<TaskResult<T, O>>,
     priority: u32,
     retry_count: u32,
+    enqueued_at: Instant,
+    control: Arc<TaskControl>,
+    slot: Option<QueueSlot>,
+}
+
+/// 队列容量：当前容量与缩容时尚未收回的许可数
+///
+/// 始终满足 `可用许可 + 已占用许可 - deficit == size`
+#[derive(Debug)]
+struct Capacity {
+    size: usize,
+    deficit: usize,
+}
+
+/// 排队任务占用的一个队列容量，任务离开队列时归还
+///
+/// 缩容尚未收回的许可在此抵扣，不再归还给信号量
+struct QueueSlot {
+    permit: Option<OwnedSemaphorePermit>,
+    capacity: Arc<Mutex<Capacity>>,
+}
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        let mut capacity =
+            self.capacity.lock().unwrap_or_else(|e| e.into_inner());
+        let permit = self.permit.take();
+        if capacity.deficit > 0 {
+            capacity.deficit -= 1;
+            if let Some(permit) = permit {
+                permit.forget();
+            }
+        }
+    }
+}
+
+impl<T, O> QueuedTask<T, O>
+where
+    T: Send + Sync,
+    O: Send + Sync,
+{
+    /// 计入老化后的有效优先级
+    fn effective_priority(
+        &self,
+        now: Instant,
+    ) -> u64 {
+        let waited = now.saturating_duration_since(self.enqueued_at);
+        let aged = waited.as_millis() / PRIORITY_AGING_INTERVAL.as_millis();
+        u64::from(self.priority).saturating_add(aged as u64)
+    }
+}
+
+/// 已从通道取出但尚未执行的任务
+///
+/// 容量限制与背压由 [`TaskQueue`] 的信号量负责，取出的任务按有效优先级调度，
+/// 相同优先级按提交顺序（FIFO）。只在同步代码中加锁，不跨越 await。
+struct ReadyQueue<T, O>
+where
+    T: Send + Sync,
+    O: Send + Sync,
+{
+    pending: Vec<QueuedTask<T, O>>,
+}
+
+impl<T, O> ReadyQueue<T, O>
+where
+    T: Send + Sync,
+    O: Send + Sync,
+{
+    /// 将通道中已到达的任务全部移入待调度列表
+    fn drain(
+        &mut self,
+        rx: &mut mpsc::UnboundedReceiver<QueuedTask<T, O>>,
+    ) {
+        while let Ok(queued) = rx.try_recv() {
+            self.pending.push(queued);
+        }
+    }
+
+    /// 取出所有已取消的任务
+    fn take_cancelled(&mut self) -> Vec<QueuedTask<T, O>> {
+        let (cancelled, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|queued| queued.control.token.is_cancelled());
+        self.pending = pending;
+        cancelled
+    }
+
+    /// 取出有效优先级最高的任务
+    fn pop_highest(&mut self) -> Option<QueuedTask<T, O>> {
+        let now = Instant::now();
+        let index = self
+            .pending
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, queued)| {
+                (
+                    queued.effective_priority(now),
+                    std::cmp::Reverse(queued.task_id),
+                )
+            })
+            .map(|(index, _)| index)?;
+        Some(self.pending.remove(index))
+    }
 }
 
 /// 任务队列结构
 /// - queue: 任务发送通道
-/// - queue_rx: 任务接收通道（包装在Arc<Mutex>中以支持共享访问）
+/// - queue_rx: 任务接收通道，调度循环等待新任务时持有
+/// - ready: 已取出待调度的任务
+/// - slots: 队列容量信号量，每个排队任务持有一个许可
+/// - capacity: 当前的队列容量与缩容时尚未收回的许可数
 /// - next_task_id: 下一个任务的ID（原子递增）
 /// - stats: 任务处理器统计信息
 pub struct TaskQueue<T, O>
@@ -157,8 +351,11 @@ where
     T: Send + Sync,
     O: Send + Sync,
 {
-    queue: mpsc::Sender<QueuedTask<T, O>>,
+    queue: mpsc::UnboundedSender<QueuedTask<T, O>>,
     queue_rx: QueueReceiver<T, O>,
+    ready: Mutex<ReadyQueue<T, O>>,
+    slots: Arc<Semaphore>,
+    capacity: Arc<Mutex<Capacity>>,
     next_task_id: Arc<tokio::sync::Mutex<u64>>,
     stats: Arc<tokio::sync::Mutex<ProcessorStats>>,
 }
@@ -167,10 +364,16 @@ impl<T: Clone + Send + Sync + 'static, O: Clone + Send + Sync + 'static>
     TaskQueue<T, O>
 {
     pub fn new(config: &ProcessorConfig) -> Self {
-        let (tx, rx) = mpsc::channel(config.max_queue_size);
+        let (tx, rx) = mpsc::unbounded_channel();
         Self {
             queue: tx,
             queue_rx: Arc::new(tokio::sync::Mutex::new(Some(rx))),
+            ready: Mutex::new(ReadyQueue { pending: Vec::new() }),
+            slots: Arc::new(Semaphore::new(config.max_queue_size)),
+            capacity: Arc::new(Mutex::new(Capacity {
+                size: config.max_queue_size,
+                deficit: 0,
+            })),
             next_task_id: Arc::new(tokio::sync::Mutex::new(0)),
             stats: Arc::new(tokio::sync::Mutex::new(ProcessorStats::default())),
         }
@@ -181,22 +384,43 @@ impl<T: Clone + Send + Sync + 'static, O: Clone + Send + Sync + 'static>
         task: T,
         priority: u32,
     ) -> ForgeResult<(u64, mpsc::Receiver<TaskResult<T, O>>)> {
+        let (task_id, _, result_rx) = self.enqueue(task, priority).await?;
+        Ok((task_id, result_rx))
+    }
+
+    async fn enqueue(
+        &self,
+        task: T,
+        priority: u32,
+    ) -> ForgeResult<(u64, Arc<TaskControl>, mpsc::Receiver<TaskResult<T, O>>)>
+    {
+        // 队列已满时在此等待，直到有任务离开队列
+        let slot =
+            self.slots.clone().acquire_owned().await.map_err(|_| {
+                error_utils::resource_exhausted_error("任务队列")
+            })?;
         let mut task_id = self.next_task_id.lock().await;
         *task_id += 1;
         let current_id = *task_id;
 
         let (result_tx, result_rx) = mpsc::channel(1);
+        let control = Arc::new(TaskControl::new());
         let queued_task = QueuedTask {
             task,
             task_id: current_id,
             result_tx,
             priority,
             retry_count: 0,
+            enqueued_at: Instant::now(),
+            control: control.clone(),
+            slot: Some(QueueSlot {
+                permit: Some(slot),
+                capacity: self.capacity.clone(),
+            }),
         };
 
         self.queue
             .send(queued_task)
-            .await
             .map_err(|_| error_utils::resource_exhausted_error("任务队列"))?;
 
         let mut stats = self.stats.lock().await;
@@ -206,37 +430,160 @@ impl<T: Clone + Send + Sync + 'static, O: Clone + Send + Sync + 'static>
         metrics::task_submitted();
         metrics::set_queue_size(stats.current_queue_size);
 
-        Ok((current_id, result_rx))
+        Ok((current_id, control, result_rx))
     }
 
+    /// 等待并取出有效优先级最高的任务
+    ///
+    /// 可在 `select!` 中安全取消：等待期间收到的任务先进入待调度列表，
+    /// 只有在不再等待后才会被取出。
     pub async fn get_next_ready(
         &self
     ) -> Option<(T, u64, mpsc::Sender<TaskResult<T, O>>, u32, u32)> {
-        let mut rx_guard = self.queue_rx.lock().await;
-        if let Some(rx) = rx_guard.as_mut() {
-            if let Some(queued) = rx.recv().await {
-                let mut stats: tokio::sync::MutexGuard<'_, ProcessorStats> =
-                    self.stats.lock().await;
-                stats.current_queue_size -= 1;
-                stats.current_processing_tasks += 1;
-                metrics::set_queue_size(stats.current_queue_size);
-                metrics::increment_processing_tasks();
-                return Some((
-                    queued.task,
-                    queued.task_id,
-                    queued.result_tx,
-                    queued.priority,
-                    queued.retry_count,
-                ));
+        let queued = self.next_ready().await?;
+        Some((
+            queued.task,
+            queued.task_id,
+            queued.result_tx,
+            queued.priority,
+            queued.retry_count,
+        ))
+    }
+
+    /// 取出下一个待执行任务，已取消的任务在此被丢弃
+    ///
+    /// 唯一的取消点是等待统计锁与等待新任务；此后移出任务、
+    /// 更新统计与发送取消结果都在同步代码中完成，不会因取消而丢失任务。
+    async fn next_ready(&self) -> Option<QueuedTask<T, O>> {
+        let mut rx = self.queue_rx.lock().await;
+        loop {
+            {
+                let mut stats = self.stats.lock().await;
+                let mut ready = self.ready();
+                ready.drain(rx.as_mut()?);
+                for queued in ready.take_cancelled() {
+                    Self::report_cancelled(&mut stats, queued, "任务被取消");
+                }
+                if let Some(mut queued) = ready.pop_highest() {
+                    stats.current_queue_size -= 1;
+                    stats.current_processing_tasks += 1;
+                    metrics::set_queue_size(stats.current_queue_size);
+                    metrics::increment_processing_tasks();
+                    queued.control.set_status(TaskStatus::Processing);
+                    queued.slot = None;
+                    return Some(queued);
+                }
             }
+            let queued = rx.as_mut()?.recv().await?;
+            self.ready().pending.push(queued);
         }
-        None
+    }
+
+    fn ready(&self) -> std::sync::MutexGuard<'_, ReadyQueue<T, O>> {
+        self.ready.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 移除队列中已取消的任务
+    ///
+    /// 调度循环持有接收端时，要么正在等待新任务（通道为空），
+    /// 要么会在取出下一个任务前自行清理通道中已取消的任务。
+    async fn remove_cancelled(&self) {
+        let mut stats = self.stats.lock().await;
+        let mut ready = self.ready();
+        if let Ok(mut rx) = self.queue_rx.try_lock()
+            && let Some(rx) = rx.as_mut()
+        {
+            ready.drain(rx);
+        }
+        for queued in ready.take_cancelled() {
+            Self::report_cancelled(&mut stats, queued, "任务被取消");
+        }
+    }
+
+    /// 结束未开始执行就被取消的任务
+    ///
+    /// 结果通道容量为 1 且尚未发送过结果，`try_send` 只在提交方
+    /// 已丢弃接收端时失败。
+    fn report_cancelled(
+        stats: &mut ProcessorStats,
+        queued: QueuedTask<T, O>,
+        reason: &str,
+    ) {
+        stats.current_queue_size -= 1;
+        stats.cancelled_tasks += 1;
+        metrics::set_queue_size(stats.current_queue_size);
+        metrics::task_processed((&TaskStatus::Cancelled).into());
+        queued.control.set_status(TaskStatus::Cancelled);
+        let _ = queued.result_tx.try_send(TaskResult {
+            task_id: queued.task_id,
+            status: TaskStatus::Cancelled,
+            task: Some(queued.task),
+            output: None,
+            error: Some(reason.to_string()),
+            processing_time: Some(Duration::from_millis(0)),
+        });
+    }
+
+    /// 取消所有尚未开始执行的任务，返回取消的数量
+    pub async fn cancel_pending(&self) -> usize {
+        let mut rx = self.queue_rx.lock().await;
+        let mut stats = self.stats.lock().await;
+        let mut ready = self.ready();
+        if let Some(rx) = rx.as_mut() {
+            ready.drain(rx);
+        }
+        let pending = std::mem::take(&mut ready.pending);
+        let count = pending.len();
+        for queued in pending {
+            Self::report_cancelled(&mut stats, queued, "处理器正在关闭");
+        }
+        count
     }
 
     pub async fn get_stats(&self) -> ProcessorStats {
         self.stats.lock().await.clone()
     }
 
+    /// 当前的队列容量
+    pub fn capacity(&self) -> usize {
+        self.capacity.lock().unwrap_or_else(|e| e.into_inner()).size
+    }
+
+    /// 调整队列容量
+    ///
+    /// 扩容立即生效；缩容时已排队的任务保留，
+    /// 超出新容量的部分在这些任务离开队列后收回，期间新提交的任务等待。
+    /// 尚未收回的部分先抵扣之后的扩容
+    pub fn set_capacity(
+        &self,
+        new_capacity: usize,
+    ) {
+        let mut capacity =
+            self.capacity.lock().unwrap_or_else(|e| e.into_inner());
+        if new_capacity > capacity.size {
+            let grow = new_capacity - capacity.size;
+            let absorbed = grow.min(capacity.deficit);
+            capacity.deficit -= absorbed;
+            self.slots.add_permits(grow - absorbed);
+        } else if new_capacity < capacity.size {
+            let shrink = capacity.size - new_capacity;
+            capacity.deficit += shrink - self.slots.forget_permits(shrink);
+        }
+        capacity.size = new_capacity;
+    }
+
+    /// 记录结果、更新任务状态并发送给提交方
+    async fn finish(
+        &self,
+        control: &TaskControl,
+        result_tx: &mpsc::Sender<TaskResult<T, O>>,
+        result: TaskResult<T, O>,
+    ) {
+        self.update_stats(&result).await;
+        control.set_status(result.status.clone());
+        let _ = result_tx.send(result).await;
+    }
+
     pub async fn update_stats(
         &self,
         result: &TaskResult<T, O>,
@@ -276,6 +623,17 @@ where
         &self,
         task: T,
     ) -> Result<O, ProcessorError>;
+
+    /// 带取消令牌的处理入口，长时间运行的处理器可覆盖此方法轮询令牌
+    ///
+    /// 默认忽略令牌；任务被取消时处理器会在下一个 await 点被中止。
+    async fn process_with_token(
+        &self,
+        task: T,
+        _token: &CancellationToken,
+    ) -> Result<O, ProcessorError> {
+        self.process(task).await
+    }
 }
 
 /// 处理器状态
@@ -303,7 +661,7 @@ where
     P: TaskProcessor<T, O>,
 {
     task_queue: Arc<TaskQueue<T, O>>,
-    config: ProcessorConfig,
+    config: watch::Sender<ProcessorConfig>,
     processor: Arc<P>,
     shutdown_tx: Option<oneshot::Sender<()>>,
     handle: Option<tokio::task::JoinHandle<()>>,
@@ -324,7 +682,7 @@ where
         let task_queue = Arc::new(TaskQueue::new(&config));
         Self {
             task_queue,
-            config,
+            config: watch::Sender::new(config),
             processor: Arc::new(processor),
             shutdown_tx: None,
             handle: None,
@@ -335,13 +693,20 @@ where
     }
 
     /// 提交新任务到处理器
-    /// 返回任务ID和用于接收处理结果的通道
+    /// 返回任务句柄和用于接收处理结果的通道
     pub async fn submit_task(
         &self,
         task: T,
         priority: u32,
-    ) -> ForgeResult<(u64, mpsc::Receiver<TaskResult<T, O>>)> {
-        self.task_queue.enqueue_task(task, priority).await
+    ) -> ForgeResult<(TaskHandle<T, O>, mpsc::Receiver<TaskResult<T, O>>)> {
+        let (task_id, control, result_rx) =
+            self.task_queue.enqueue(task, priority).await?;
+        let handle = TaskHandle {
+            task_id,
+            control,
+            queue: Arc::downgrade(&self.task_queue),
+        };
+        Ok((handle, result_rx))
     }
 
     /// 启动任务处理器
@@ -358,7 +723,7 @@ where
 
         let queue = self.task_queue.clone();
         let processor = self.processor.clone();
-        let config = self.config.clone();
+        let mut config_rx = self.config.subscribe();
         let state_ref = self.state.clone();
         let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
 
@@ -393,7 +758,11 @@ where
             }
 
             loop {
+                let config = config_rx.borrow_and_update().clone();
                 select! {
+                    // 配置更新后重新计算并发上限
+                    Ok(()) = config_rx.changed() => {}
+
                     // 处理关闭信号
                     _ = &mut shutdown_rx => {
                         debug!("收到关闭信号，开始优雅关闭");
@@ -404,7 +773,9 @@ where
                         }
 
                         // 清理所有正在运行的任务
-                        cleanup_tasks(&mut join_set, Duration::from_secs(30)).await;
+                        cleanup_tasks(&mut join_set, config.cleanup_timeout).await;
+                        // 尚未开始执行的任务直接取消
+                        queue.cancel_pending().await;
                         break;
                     }
 
@@ -417,8 +788,11 @@ where
                         }
                     }
 
-                    // 获取新任务并处理
-                    Some((task, task_id, result_tx, _priority, retry_count)) = queue.get_next_ready() => {
+                    // 有空闲并发槽位时获取优先级最高的任务并处理，
+                    // 槽位占满时任务留在队列中，后到的高优先级任务可插队；
+                    // 并发上限调低后，超出的任务执行完当前任务即不再补充
+                    Some(queued) = queue.next_ready(), if join_set.len() < config.max_concurrent_tasks => {
+                        let QueuedTask { task, task_id, result_tx, retry_count, control, .. } = queued;
                         // 检查是否正在关闭
                         {
                             let state = state_ref.lock().await;
@@ -432,77 +806,86 @@ where
                                     error: Some("处理器正在关闭".to_string()),
                                     processing_time: Some(Duration::from_millis(0)),
                                 };
-                                queue.update_stats(&task_result).await;
-                                let _ = result_tx.send(task_result).await;
+                                queue.finish(&control, &result_tx, task_result).await;
                                 continue;
                             }
                         }
 
-                        if join_set.len() < config.max_concurrent_tasks {
-                            let processor = processor.clone();
-                            let config = config.clone();
-                            let queue = queue.clone();
+                        let processor = processor.clone();
+                        let config = config.clone();
+                        let queue = queue.clone();
 
-                            join_set.spawn(async move {
-                                let start_time = Instant::now();
-                                let mut current_retry = retry_count;
+                        join_set.spawn(async move {
+                            let start_time = Instant::now();
+                            let mut current_retry = retry_count;
 
-                                loop {
-                                    let result = tokio::time::timeout(
+                            loop {
+                                let token = control.token.clone();
+                                let result = select! {
+                                    result = tokio::time::timeout(
                                         config.task_timeout,
-                                        processor.process(task.clone())
-                                    ).await;
-
-                                    match result {
-                                        Ok(Ok(output)) => {
-                                            let processing_time = start_time.elapsed();
-                                            let task_result = TaskResult {
-                                                task_id,
-                                                status: TaskStatus::Completed,
-                                                task: Some(task),
-                                                output: Some(output),
-                                                error: None,
-                                                processing_time: Some(processing_time),
-                                            };
-                                            queue.update_stats(&task_result).await;
-                                            let _ = result_tx.send(task_result).await;
-                                            break;
-                                        }
-                                        Ok(Err(e)) => {
-                                            if current_retry < config.max_retries {
-                                                current_retry += 1;
-                                                tokio::time::sleep(config.retry_delay).await;
-                                                continue;
-                                            }
-                                            let task_result = TaskResult {
-                                                task_id,
-                                                status: TaskStatus::Failed(e.to_string()),
-                                                task: Some(task),
-                                                output: None,
-                                                error: Some(e.to_string()),
-                                                processing_time: Some(start_time.elapsed()),
-                                            };
-                                            queue.update_stats(&task_result).await;
-                                            let _ = result_tx.send(task_result).await;
-                                            break;
-                                        }
-                                        Err(_) => {
-                                            let task_result = TaskResult {
-                                                task_id,
-                                                status: TaskStatus::Timeout,
-                                                task: Some(task),
-                                                output: None,
-                                                error: Some("任务执行超时".to_string()),
-                                                processing_time: Some(start_time.elapsed()),
-                                            };
-                                            queue.update_stats(&task_result).await;
-                                            let _ = result_tx.send(task_result).await;
-                                            break;
+                                        processor.process_with_token(task.clone(), &token)
+                                    ) => result,
+                                    _ = token.cancelled() => {
+                                        let task_result = TaskResult {
+                                            task_id,
+                                            status: TaskStatus::Cancelled,
+                                            task: Some(task),
+                                            output: None,
+                                            error: Some("任务被取消".to_string()),
+                                            processing_time: Some(start_time.elapsed()),
+                                        };
+                                        queue.finish(&control, &result_tx, task_result).await;
+                                        break;
+                                    }
+                                };
+
+                                match result {
+                                    Ok(Ok(output)) => {
+                                        let processing_time = start_time.elapsed();
+                                        let task_result = TaskResult {
+                                            task_id,
+                                            status: TaskStatus::Completed,
+                                            task: Some(task),
+                                            output: Some(output),
+                                            error: None,
+                                            processing_time: Some(processing_time),
+                                        };
+                                        queue.finish(&control, &result_tx, task_result).await;
+                                        break;
+                                    }
+                                    Ok(Err(e)) => {
+                                        if current_retry < config.max_retries {
+                                            current_retry += 1;
+                                            tokio::time::sleep(config.retry_delay).await;
+                                            continue;
                                         }
+                                        let task_result = TaskResult {
+                                            task_id,
+                                            status: TaskStatus::Failed(e.to_string()),
+                                            task: Some(task),
+                                            output: None,
+                                            error: Some(e.to_string()),
+                                            processing_time: Some(start_time.elapsed()),
+                                        };
+                                        queue.finish(&control, &result_tx, task_result).await;
+                                        break;
+                                    }
+                                    Err(_) => {
+                                        let task_result = TaskResult {
+                                            task_id,
+                                            status: TaskStatus::Timeout,
+                                            task: Some(task),
+                                            output: None,
+                                            error: Some("任务执行超时".to_string()),
+                                            processing_time: Some(start_time.elapsed()),
+                                        };
+                                        queue.finish(&control, &result_tx, task_result).await;
+                                        break;
                                     }
                                 }
-                            });
-                        }
+                            }
+                        });
                     }
                 }
             }
@@ -587,6 +970,32 @@ where
         *state == ProcessorState::Running
     }
 
+    /// 当前生效的配置
+    pub fn config(&self) -> ProcessorConfig {
+        self.config.borrow().clone()
+    }
+
+    /// 运行中调整配置，不会丢弃已排队的任务
+    ///
+    /// - `max_concurrent_tasks`：调高立即调度更多任务；调低时执行中的任务继续完成，
+    ///   直到并发数降到新上限以下才调度新任务
+    /// - `max_queue_size`：见 [`TaskQueue::set_capacity`]
+    /// - `task_timeout`、`max_retries`、`retry_delay`：对之后开始执行的任务生效
+    /// - `cleanup_timeout`：用于之后的关闭
+    pub fn reconfigure(
+        &self,
+        config: ProcessorConfig,
+    ) -> Result<(), ProcessorError> {
+        if config.max_concurrent_tasks == 0 || config.max_queue_size == 0 {
+            return Err(ProcessorError::InternalError(
+                "并发任务数与队列容量必须大于0".to_string(),
+            ));
+        }
+        self.task_queue.set_capacity(config.max_queue_size);
+        self.config.send_replace(config);
+        Ok(())
+    }
+
     pub async fn get_stats(&self) -> ProcessorStats {
         self.task_queue.get_stats().await
     }
@@ -620,6 +1029,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     struct TestProcessor;
 
@@ -727,4 +1137,312 @@ mod tests {
             ));
         }
     }
+
+    /// 记录执行顺序的串行处理器
+    struct RecordingProcessor {
+        order: Arc<std::sync::Mutex<Vec<i32>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TaskProcessor<i32, i32> for RecordingProcessor {
+        async fn process(
+            &self,
+            task: i32,
+        ) -> Result<i32, ProcessorError> {
+            self.order.lock().unwrap().push(task);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(task)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_jumps_queue() {
+        let config = ProcessorConfig {
+            max_queue_size: 100,
+            max_concurrent_tasks: 1,
+            task_timeout: Duration::from_secs(1),
+            max_retries: 0,
+            retry_delay: Duration::from_millis(10),
+            cleanup_timeout: Duration::from_secs(10),
+        };
+        let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut processor = AsyncProcessor::new(
+            config,
+            RecordingProcessor { order: order.clone() },
+        );
+        processor.start().await.unwrap();
+
+        // 第一个任务占住唯一的并发槽位，其余任务排队
+        let mut receivers = Vec::new();
+        receivers.push(processor.submit_task(1, 0).await.unwrap().1);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        for task in [2, 3, 4] {
+            receivers.push(processor.submit_task(task, 0).await.unwrap().1);
+        }
+        receivers.push(processor.submit_task(100, 10).await.unwrap().1);
+
+        for mut rx in receivers {
+            let result = rx.recv().await.unwrap();
+            assert_eq!(result.status, TaskStatus::Completed);
+        }
+        assert_eq!(*order.lock().unwrap(), vec![1, 100, 2, 3, 4]);
+
+        processor.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_priority_aging() {
+        let (result_tx, _rx) = mpsc::channel::<TaskResult<i32, i32>>(1);
+        let now = Instant::now();
+        let queued = QueuedTask {
+            task: 1,
+            task_id: 1,
+            result_tx,
+            priority: 2,
+            retry_count: 0,
+            enqueued_at: now,
+            control: Arc::new(TaskControl::new()),
+            slot: None,
+        };
+        assert_eq!(queued.effective_priority(now), 2);
+        assert_eq!(
+            queued.effective_priority(now + PRIORITY_AGING_INTERVAL * 3),
+            5
+        );
+    }
+
+    /// 轮询取消令牌的长任务处理器
+    struct CancellableProcessor;
+
+    #[async_trait::async_trait]
+    impl TaskProcessor<i32, i32> for CancellableProcessor {
+        async fn process(
+            &self,
+            task: i32,
+        ) -> Result<i32, ProcessorError> {
+            self.process_with_token(task, &CancellationToken::new()).await
+        }
+
+        async fn process_with_token(
+            &self,
+            task: i32,
+            token: &CancellationToken,
+        ) -> Result<i32, ProcessorError> {
+            for _ in 0..100 {
+                if token.is_cancelled() {
+                    return Err(ProcessorError::TaskCancelled);
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            Ok(task)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_queued_and_running_tasks() {
+        let config = ProcessorConfig {
+            max_queue_size: 100,
+            max_concurrent_tasks: 1,
+            task_timeout: Duration::from_secs(5),
+            max_retries: 0,
+            retry_delay: Duration::from_millis(10),
+            cleanup_timeout: Duration::from_secs(10),
+        };
+        let mut processor = AsyncProcessor::new(config, CancellableProcessor);
+        processor.start().await.unwrap();
+
+        let (running, mut running_rx) =
+            processor.submit_task(1, 0).await.unwrap();
+        let (queued, mut queued_rx) =
+            processor.submit_task(2, 0).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(running.status(), TaskStatus::Processing);
+        assert_eq!(queued.status(), TaskStatus::Pending);
+
+        // 未开始的任务直接移出队列
+        queued.cancel().await;
+        let result = queued_rx.recv().await.unwrap();
+        assert_eq!(result.status, TaskStatus::Cancelled);
+        assert_eq!(queued.status(), TaskStatus::Cancelled);
+        assert_eq!(running.status(), TaskStatus::Processing);
+
+        // 正在执行的任务通过令牌中止
+        running.cancel().await;
+        let result = running_rx.recv().await.unwrap();
+        assert_eq!(result.status, TaskStatus::Cancelled);
+        assert_eq!(running.status(), TaskStatus::Cancelled);
+
+        let stats = processor.get_stats().await;
+        assert_eq!(stats.cancelled_tasks, 2);
+        assert_eq!(stats.current_queue_size, 0);
+        assert_eq!(stats.current_processing_tasks, 0);
+
+        processor.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_next_ready_cancel_safe() {
+        let config =
+            ProcessorConfig { max_queue_size: 10, ..Default::default() };
+        let queue = TaskQueue::<i32, i32>::new(&config);
+        let (_, cancelled, mut cancelled_rx) =
+            queue.enqueue(1, 0).await.unwrap();
+        let (_, _, _rx) = queue.enqueue(2, 0).await.unwrap();
+        cancelled.token.cancel();
+
+        // 在等待统计锁时丢弃 next_ready，已取消的任务不能丢失
+        let stats = queue.stats.lock().await;
+        tokio::select! {
+            biased;
+            _ = queue.next_ready() => unreachable!("统计锁被占用"),
+            _ = tokio::task::yield_now() => {},
+        }
+        drop(stats);
+
+        let next = queue.next_ready().await.unwrap();
+        assert_eq!(next.task, 2);
+        let result = cancelled_rx.recv().await.unwrap();
+        assert_eq!(result.status, TaskStatus::Cancelled);
+        let stats = queue.get_stats().await;
+        assert_eq!(stats.cancelled_tasks, 1);
+        assert_eq!(stats.current_queue_size, 0);
+    }
+
+    #[tokio::test]
+    async fn test_set_capacity_nets_deficit() {
+        let config =
+            ProcessorConfig { max_queue_size: 4, ..Default::default() };
+        let queue = TaskQueue::<i32, i32>::new(&config);
+        let mut receivers = Vec::new();
+        for task in 0..4 {
+            receivers.push(queue.enqueue(task, 0).await.unwrap().2);
+        }
+        assert_eq!(queue.slots.available_permits(), 0);
+
+        // 缩容时许可都被占用，之后的扩容先抵扣未收回的部分
+        queue.set_capacity(1);
+        queue.set_capacity(3);
+        assert_eq!(queue.capacity(), 3);
+        assert_eq!(queue.slots.available_permits(), 0);
+
+        for _ in 0..4 {
+            queue.next_ready().await.unwrap();
+        }
+        assert_eq!(queue.slots.available_permits(), 3);
+    }
+
+    /// 执行到放行前一直阻塞的处理器，开始执行时报告当前并发数
+    struct GatedProcessor {
+        running: Arc<AtomicUsize>,
+        started: mpsc::UnboundedSender<usize>,
+        gate: Arc<Semaphore>,
+    }
+
+    #[async_trait::async_trait]
+    impl TaskProcessor<i32, i32> for GatedProcessor {
+        async fn process(
+            &self,
+            task: i32,
+        ) -> Result<i32, ProcessorError> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            let _ = self.started.send(running);
+            self.gate.acquire().await.unwrap().forget();
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(task)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconfigure_resizes_pool() {
+        let config = ProcessorConfig {
+            max_queue_size: 100,
+            max_concurrent_tasks: 1,
+            task_timeout: Duration::from_secs(5),
+            max_retries: 0,
+            retry_delay: Duration::from_millis(10),
+            cleanup_timeout: Duration::from_secs(10),
+        };
+        let running = Arc::new(AtomicUsize::new(0));
+        let gate = Arc::new(Semaphore::new(0));
+        let (started_tx, mut started) = mpsc::unbounded_channel();
+        let mut processor = AsyncProcessor::new(
+            config.clone(),
+            GatedProcessor {
+                running: running.clone(),
+                started: started_tx,
+                gate: gate.clone(),
+            },
+        );
+        processor.start().await.unwrap();
+
+        assert!(
+            processor
+                .reconfigure(ProcessorConfig {
+                    max_concurrent_tasks: 0,
+                    ..config.clone()
+                })
+                .is_err()
+        );
+
+        // 扩容：排队的任务立即被更多工作槽位取走
+        let mut receivers = Vec::new();
+        for task in 0..8 {
+            receivers.push(processor.submit_task(task, 0).await.unwrap().1);
+        }
+        assert_eq!(started.recv().await, Some(1));
+        processor
+            .reconfigure(ProcessorConfig {
+                max_concurrent_tasks: 4,
+                ..config.clone()
+            })
+            .unwrap();
+        let mut concurrency = Vec::new();
+        for _ in 0..3 {
+            concurrency.push(started.recv().await.unwrap());
+        }
+        concurrency.sort();
+        assert_eq!(concurrency, [2, 3, 4]);
+        gate.add_permits(8);
+        for mut rx in receivers.drain(..) {
+            let result = rx.recv().await.unwrap();
+            assert_eq!(result.status, TaskStatus::Completed);
+        }
+        let concurrency: Vec<usize> =
+            std::iter::from_fn(|| started.try_recv().ok()).collect();
+        assert_eq!(concurrency.len(), 4);
+        assert!(concurrency.iter().all(|n| *n <= 4));
+
+        // 缩容：执行中的任务先完成，排队的任务不丢失
+        for task in 0..8 {
+            receivers.push(processor.submit_task(task, 0).await.unwrap().1);
+        }
+        for _ in 0..4 {
+            started.recv().await.unwrap();
+        }
+        processor
+            .reconfigure(ProcessorConfig {
+                max_queue_size: 2,
+                max_concurrent_tasks: 1,
+                ..config.clone()
+            })
+            .unwrap();
+        gate.add_permits(4);
+        // 之后的任务逐个执行
+        for _ in 0..4 {
+            assert_eq!(started.recv().await, Some(1));
+            gate.add_permits(1);
+        }
+        for mut rx in receivers {
+            let result = rx.recv().await.unwrap();
+            assert_eq!(result.status, TaskStatus::Completed);
+        }
+        assert_eq!(processor.config().max_queue_size, 2);
+
+        let stats = processor.get_stats().await;
+        assert_eq!(stats.completed_tasks, 16);
+        assert_eq!(stats.current_queue_size, 0);
+        assert_eq!(processor.task_queue.slots.available_permits(), 2);
+
+        processor.shutdown().await.unwrap();
+    }
 }
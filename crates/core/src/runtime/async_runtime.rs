@@ -43,8 +43,9 @@ use std::{
 use async_trait::async_trait;
 use crate::runtime::runtime::ForgeRuntime;
 use crate::types::ProcessorResult;
+use crate::helpers::command_helper::CommandHelper;
 use crate::{
-    config::{ForgeConfig, PerformanceConfig},
+    config::{ForgeConfig, PerformanceConfig, ProcessorConfig},
     debug::debug,
     error::error_utils,
     event::Event,
@@ -55,7 +56,7 @@ use crate::{
 use mf_model::schema::Schema;
 use mf_state::{
     state::TransactionResult,
-    transaction::Transaction,
+    transaction::{CommandContext, Transaction},
     State,
 };
 
@@ -192,7 +193,7 @@ impl ForgeAsyncRuntime {
     ) -> ForgeResult<Self> {
         let base = ForgeRuntime::from_xml_content(xml_content, options, config)
             .await?;
-        Ok(ForgeAsyncRuntime { base, flow_engine: FlowEngine::new().await? })
+        Self::with_flow_engine(base).await
     }
 
     /// 使用指定配置创建异步编辑器实例
@@ -218,7 +219,28 @@ impl ForgeAsyncRuntime {
         config: ForgeConfig,
     ) -> ForgeResult<Self> {
         let base = ForgeRuntime::create_with_config(options, config).await?;
-        Ok(ForgeAsyncRuntime { base, flow_engine: FlowEngine::new().await? })
+        Self::with_flow_engine(base).await
+    }
+
+    /// 按基础运行时的处理器配置启动异步流引擎
+    async fn with_flow_engine(base: ForgeRuntime) -> ForgeResult<Self> {
+        let flow_engine =
+            FlowEngine::with_config(base.get_config().processor.clone())
+                .await?;
+        Ok(ForgeAsyncRuntime { base, flow_engine })
+    }
+
+    /// 运行中调整处理器配置
+    ///
+    /// 工作池大小与队列容量立即生效，已排队的事务不会丢失；
+    /// 并发数调低时，执行中的事务会先完成再释放槽位。
+    /// 各字段的生效方式见 [`ProcessorConfig`]
+    pub fn reconfigure(
+        &mut self,
+        processor: ProcessorConfig,
+    ) -> ForgeResult<()> {
+        self.base.reconfigure(processor.clone())?;
+        self.flow_engine.reconfigure(processor)
     }
 
     /// 设置性能监控配置
@@ -275,6 +297,26 @@ impl ForgeAsyncRuntime {
             .await
     }
 
+    /// 在指定上下文中执行命令
+    ///
+    /// 进度广播为 `Event::CommandProgress`；被取消时不会派发事务
+    #[cfg_attr(feature = "dev-tracing", tracing::instrument(skip(self, command, ctx), fields(
+        crate_name = "core",
+        command_name = %command.name(),
+        runtime_type = "async"
+    )))]
+    pub async fn command_with_context(
+        &mut self,
+        command: Arc<dyn mf_state::transaction::CommandGeneric<
+            mf_model::node_pool::NodePool,
+            mf_model::schema::Schema,
+        >>,
+        ctx: CommandContext,
+    ) -> ForgeResult<()> {
+        self.run_command(command, ctx, "".to_string(), serde_json::Value::Null)
+            .await
+    }
+
     /// 执行命令并生成相应的事务
     ///
     /// 此方法封装了命令到事务的转换过程，并使用高性能的`dispatch_flow`来处理生成的事务。
@@ -299,13 +341,32 @@ impl ForgeAsyncRuntime {
         >>,
         description: String,
         meta: serde_json::Value,
+    ) -> ForgeResult<()> {
+        self.run_command(command, CommandContext::new(), description, meta)
+            .await
+    }
+
+    async fn run_command(
+        &mut self,
+        command: Arc<dyn mf_state::transaction::CommandGeneric<
+            mf_model::node_pool::NodePool,
+            mf_model::schema::Schema,
+        >>,
+        ctx: CommandContext,
+        description: String,
+        meta: serde_json::Value,
     ) -> ForgeResult<()> {
         let cmd_name = command.name();
         debug!("正在执行命令: {}", cmd_name);
+        let ctx = CommandHelper::forward_progress(
+            ctx,
+            self.base.get_event_bus(),
+            cmd_name.clone(),
+        );
 
         // 创建事务并应用命令
         let mut tr = self.base.get_tr();
-        command.execute(&mut tr).await?;
+        CommandHelper::execute(&command, &mut tr, &ctx).await?;
         tr.commit()?;
         // 使用高性能处理引擎处理事务
         match self.dispatch_flow_with_meta(tr, description, meta).await {
@@ -319,6 +380,23 @@ impl ForgeAsyncRuntime {
             },
         }
     }
+    /// 应用外部构建的事务
+    ///
+    /// 与 `command` 相同，通过 `dispatch_flow` 走完整的中间件与插件流水线；
+    /// 事务必须基于当前文档构建
+    #[cfg_attr(feature = "dev-tracing", tracing::instrument(skip(self, transaction), fields(
+        crate_name = "core",
+        tr_id = %transaction.id,
+        runtime_type = "async"
+    )))]
+    pub async fn apply_transaction(
+        &mut self,
+        mut transaction: Transaction,
+    ) -> ForgeResult<()> {
+        self.base.ensure_current_base(&transaction)?;
+        transaction.commit()?;
+        self.dispatch_flow(transaction).await
+    }
     #[cfg_attr(feature = "dev-tracing", tracing::instrument(skip(self, transaction), fields(
         crate_name = "core",
         tr_id = %transaction.id,
@@ -367,6 +445,12 @@ impl ForgeAsyncRuntime {
         let middleware_start = std::time::Instant::now();
         self.run_before_middleware(&mut current_transaction).await?;
         self.log_performance("前置中间件处理", middleware_start.elapsed());
+        // 守卫保持到状态更新完成，期间新的文档锁会等待
+        let _dispatch = self
+            .base
+            .doc_locks()
+            .begin_transaction(&current_transaction, &self.base.doc())
+            .await?;
 
         // 使用 flow_engine 提交事务
         let (_id, mut rx) = self
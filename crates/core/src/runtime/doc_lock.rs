@@ -0,0 +1,753 @@
+//! 文档级咨询锁
+//!
+//! 用于跨越多个 await 点的"读取-计算-写入"临界区，例如重新编排连续编号：
+//! - 锁定范围为整个文档或以某节点为根的子树
+//! - 锁外派发的事务若修改到已锁定的范围，按 [`DocLockPolicy`] 等待或立即失败
+//! - 派发时的检查与登记在同一次加锁内完成，事务应用结束前，覆盖其修改范围的新锁会等待
+//! - 锁在守卫 Drop 时释放，持锁的 future 被取消时同样会释放
+//! - 持锁期间只允许获取已被当前锁覆盖的范围，获取更大或不相交的范围会被拒绝，以避免死锁
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use std::time::{Duration, Instant};
+
+use mf_model::{node_pool::NodePool, types::NodeId};
+use mf_state::transaction::Transaction;
+use mf_transform::touched_nodes;
+use tokio::sync::Notify;
+
+use crate::{
+    debug::debug,
+    error::{error_utils, ForgeResult},
+    error_helpers::lock_helpers,
+};
+
+static MANAGER_ID: AtomicU64 = AtomicU64::new(1);
+
+tokio::task_local! {
+    /// 当前任务持有的锁 (管理器ID, 范围)
+    static HELD_LOCKS: Vec<(u64, LockScope)>;
+}
+
+/// 锁定范围
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockScope {
+    /// 整个文档
+    Document,
+    /// 以指定节点为根的子树
+    Subtree(NodeId),
+}
+
+impl LockScope {
+    /// 当前范围是否完全覆盖 `other`
+    pub fn covers(
+        &self,
+        other: &LockScope,
+        doc: &NodePool,
+    ) -> bool {
+        match (self, other) {
+            (LockScope::Document, _) => true,
+            (LockScope::Subtree(_), LockScope::Document) => false,
+            (LockScope::Subtree(_), LockScope::Subtree(id)) => {
+                self.contains_node(id, doc)
+            },
+        }
+    }
+
+    /// 两个范围是否相交（树上的两棵子树要么不相交，要么互相包含）
+    pub fn overlaps(
+        &self,
+        other: &LockScope,
+        doc: &NodePool,
+    ) -> bool {
+        self.covers(other, doc) || other.covers(self, doc)
+    }
+
+    /// 范围内是否包含指定节点
+    pub fn contains_node(
+        &self,
+        id: &NodeId,
+        doc: &NodePool,
+    ) -> bool {
+        match self {
+            LockScope::Document => true,
+            LockScope::Subtree(root) => root == id || doc.is_ancestor(root, id),
+        }
+    }
+}
+
+/// 锁冲突时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DocLockPolicy {
+    /// 等待锁释放，超过指定时间后返回超时错误
+    ///
+    /// 派发方在等待期间仍持有运行时，持锁方若需要同一运行时派发，
+    /// 会一直等到超时；只在持锁方不经由该运行时派发时使用。
+    Wait(Duration),
+    /// 立即返回并发错误
+    #[default]
+    FailFast,
+}
+
+/// 活跃锁信息，用于调试输出
+#[derive(Debug, Clone)]
+pub struct DocLockInfo {
+    pub id: u64,
+    pub scope: LockScope,
+    pub held_for: Duration,
+}
+
+/// 传递给临界区闭包的锁句柄
+#[derive(Debug, Clone)]
+pub struct DocLock {
+    id: Option<u64>,
+    scope: LockScope,
+}
+
+impl DocLock {
+    /// 锁ID，重入获取（已被外层锁覆盖）时为 `None`
+    pub fn id(&self) -> Option<u64> {
+        self.id
+    }
+
+    pub fn scope(&self) -> &LockScope {
+        &self.scope
+    }
+}
+
+#[derive(Debug)]
+struct LockEntry {
+    id: u64,
+    scope: LockScope,
+    acquired_at: Instant,
+}
+
+/// 正在应用的事务，`touched` 为 `None` 表示视为修改整个文档
+#[derive(Debug)]
+struct DispatchEntry {
+    id: u64,
+    touched: Option<HashSet<NodeId>>,
+}
+
+#[derive(Debug, Default)]
+struct LockTable {
+    locks: Vec<LockEntry>,
+    dispatches: Vec<DispatchEntry>,
+}
+
+/// 阻塞当前操作的对象
+enum Blocker {
+    Lock(LockScope),
+    Dispatch,
+}
+
+/// 文档锁管理器
+///
+/// 由运行时持有并共享，可通过 `ForgeRuntime::doc_locks` 获取。
+pub struct DocLockManager {
+    id: u64,
+    entries: Mutex<LockTable>,
+    notify: Notify,
+    next_lock_id: AtomicU64,
+    policy: Mutex<DocLockPolicy>,
+}
+
+impl Default for DocLockManager {
+    fn default() -> Self {
+        Self::new(DocLockPolicy::default())
+    }
+}
+
+impl std::fmt::Debug for DocLockManager {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.debug_struct("DocLockManager")
+            .field("policy", &self.policy())
+            .field("locks", &self.active_locks())
+            .finish()
+    }
+}
+
+impl DocLockManager {
+    pub fn new(policy: DocLockPolicy) -> Self {
+        DocLockManager {
+            id: MANAGER_ID.fetch_add(1, Ordering::Relaxed),
+            entries: Mutex::new(LockTable::default()),
+            notify: Notify::new(),
+            next_lock_id: AtomicU64::new(1),
+            policy: Mutex::new(policy),
+        }
+    }
+
+    /// 当前的冲突处理策略
+    pub fn policy(&self) -> DocLockPolicy {
+        self.policy.lock().map(|p| *p).unwrap_or_default()
+    }
+
+    /// 设置冲突处理策略
+    pub fn set_policy(
+        &self,
+        policy: DocLockPolicy,
+    ) {
+        if let Ok(mut current) = self.policy.lock() {
+            *current = policy;
+        }
+    }
+
+    /// 当前所有活跃的锁
+    pub fn active_locks(&self) -> Vec<DocLockInfo> {
+        let entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        entries
+            .locks
+            .iter()
+            .map(|e| DocLockInfo {
+                id: e.id,
+                scope: e.scope.clone(),
+                held_for: e.acquired_at.elapsed(),
+            })
+            .collect()
+    }
+
+    /// 在持有 `scope` 锁的情况下执行 `f`
+    ///
+    /// `doc` 用于判断子树之间的包含关系。`f` 返回后（或 future 被取消时）释放锁。
+    pub async fn with_lock<F, Fut, R>(
+        self: Arc<Self>,
+        scope: LockScope,
+        doc: Arc<NodePool>,
+        f: F,
+    ) -> ForgeResult<R>
+    where
+        F: FnOnce(DocLock) -> Fut,
+        Fut: Future<Output = ForgeResult<R>>,
+    {
+        let held = self.held_scopes();
+        if !held.is_empty() {
+            if held.iter().any(|h| h.covers(&scope, &doc)) {
+                // 已被外层锁覆盖，直接重入
+                return f(DocLock { id: None, scope }).await;
+            }
+            return Err(error_utils::concurrency_error(format!(
+                "持有文档锁 {held:?} 时不允许获取未被覆盖的范围 {scope:?}"
+            )));
+        }
+
+        let guard = self.acquire(scope.clone(), &doc).await?;
+        let lock = DocLock { id: Some(guard.id), scope: scope.clone() };
+        let mut stack =
+            HELD_LOCKS.try_with(|held| held.clone()).unwrap_or_default();
+        stack.push((self.id, scope));
+        let result = HELD_LOCKS.scope(stack, f(lock)).await;
+        drop(guard);
+        result
+    }
+
+    /// 检查事务是否修改了其它任务锁定的范围，无冲突时登记为正在应用
+    ///
+    /// 冲突时按策略等待或返回错误。检查与登记在同一次加锁内完成，
+    /// 返回的守卫释放前，覆盖事务修改范围的锁都会等待其应用结束。
+    pub async fn begin_transaction(
+        self: &Arc<Self>,
+        tr: &Transaction,
+        doc: &NodePool,
+    ) -> ForgeResult<DispatchGuard> {
+        let id = self.next_lock_id.fetch_add(1, Ordering::Relaxed);
+        let held = self.held_scopes();
+        let mut touched = None;
+        self.wait_until(
+            |table| {
+                // 没有锁时不计算修改范围，登记为整个文档
+                if !table.locks.is_empty() && touched.is_none() {
+                    touched = Some(touched_nodes(tr));
+                }
+                let blocking = table.locks.iter().find(|e| {
+                    let own = held.iter().any(|h| h.covers(&e.scope, doc));
+                    let hit = match &touched {
+                        Some(Some(ids)) => {
+                            ids.iter().any(|id| e.scope.contains_node(id, doc))
+                        },
+                        _ => true,
+                    };
+                    !own && hit
+                });
+                if let Some(e) = blocking {
+                    return Some(Blocker::Lock(e.scope.clone()));
+                }
+                table.dispatches.push(DispatchEntry {
+                    id,
+                    touched: touched.clone().flatten(),
+                });
+                None
+            },
+            "派发事务",
+        )
+        .await?;
+        Ok(DispatchGuard { manager: self.clone(), id })
+    }
+
+    fn held_scopes(&self) -> Vec<LockScope> {
+        HELD_LOCKS
+            .try_with(|held| {
+                held.iter()
+                    .filter(|(manager, _)| *manager == self.id)
+                    .map(|(_, scope)| scope.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    async fn acquire(
+        self: &Arc<Self>,
+        scope: LockScope,
+        doc: &NodePool,
+    ) -> ForgeResult<DocLockGuard> {
+        let id = self.next_lock_id.fetch_add(1, Ordering::Relaxed);
+        self.wait_until(
+            |table| {
+                if let Some(e) =
+                    table.locks.iter().find(|e| e.scope.overlaps(&scope, doc))
+                {
+                    return Some(Blocker::Lock(e.scope.clone()));
+                }
+                let applying =
+                    table.dispatches.iter().any(|d| match &d.touched {
+                        Some(ids) => {
+                            ids.iter().any(|id| scope.contains_node(id, doc))
+                        },
+                        None => true,
+                    });
+                if applying {
+                    return Some(Blocker::Dispatch);
+                }
+                table.locks.push(LockEntry {
+                    id,
+                    scope: scope.clone(),
+                    acquired_at: Instant::now(),
+                });
+                None
+            },
+            "获取文档锁",
+        )
+        .await?;
+        debug!("已获取文档锁 {}: {:?}", id, scope);
+        Ok(DocLockGuard { manager: self.clone(), id })
+    }
+
+    /// 反复执行 `check` 直到其返回 `None`（无冲突），冲突时按策略等待
+    ///
+    /// 正在应用的事务很快结束，总是等待，不受 `FailFast` 影响。
+    async fn wait_until<C>(
+        &self,
+        mut check: C,
+        operation: &str,
+    ) -> ForgeResult<()>
+    where
+        C: FnMut(&mut LockTable) -> Option<Blocker>,
+    {
+        let policy = self.policy();
+        let started = Instant::now();
+        loop {
+            // 先注册通知再检查，避免错过检查与等待之间的释放
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let blocking = {
+                let mut entries =
+                    lock_helpers::mutex_lock(&self.entries, "文档锁")?;
+                check(&mut entries)
+            };
+            let blocking = match blocking {
+                None => return Ok(()),
+                Some(Blocker::Lock(scope)) => scope,
+                Some(Blocker::Dispatch) => {
+                    notified.await;
+                    continue;
+                },
+            };
+
+            match policy {
+                DocLockPolicy::FailFast => {
+                    return Err(error_utils::concurrency_error(format!(
+                        "{operation}失败: 范围 {blocking:?} 已被锁定"
+                    )));
+                },
+                DocLockPolicy::Wait(timeout) => {
+                    let remaining = timeout.saturating_sub(started.elapsed());
+                    if tokio::time::timeout(remaining, notified).await.is_err()
+                    {
+                        return Err(error_utils::timeout_error_with_duration(
+                            format!("{operation}: 等待范围 {blocking:?} 解锁"),
+                            timeout.as_millis() as u64,
+                        ));
+                    }
+                },
+            }
+        }
+    }
+
+    fn release(
+        &self,
+        id: u64,
+    ) {
+        let mut entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        entries.locks.retain(|e| e.id != id);
+        drop(entries);
+        debug!("已释放文档锁 {}", id);
+        self.notify.notify_waiters();
+    }
+
+    fn finish_dispatch(
+        &self,
+        id: u64,
+    ) {
+        let mut entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        entries.dispatches.retain(|d| d.id != id);
+        drop(entries);
+        self.notify.notify_waiters();
+    }
+}
+
+/// 派发守卫，事务应用结束（Drop）后允许获取覆盖其修改范围的锁
+pub struct DispatchGuard {
+    manager: Arc<DocLockManager>,
+    id: u64,
+}
+
+impl Drop for DispatchGuard {
+    fn drop(&mut self) {
+        self.manager.finish_dispatch(self.id);
+    }
+}
+
+/// 锁守卫，Drop 时释放锁
+struct DocLockGuard {
+    manager: Arc<DocLockManager>,
+    id: u64,
+}
+
+impl Drop for DocLockGuard {
+    fn drop(&mut self) {
+        self.manager.release(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::runtime::ForgeRuntime;
+    use mf_model::rpds::HashTrieMapSync;
+    use serde_json::Value;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::{Barrier, Mutex as AsyncMutex};
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<schema top_node="doc">
+  <nodes>
+    <node name="doc" desc="文档">
+      <attrs>
+        <attr name="counter" default="0"/>
+      </attrs>
+    </node>
+  </nodes>
+</schema>"#;
+
+    fn counter(doc: &NodePool) -> i64 {
+        let root = doc.root().expect("缺少根节点");
+        match root.attrs.get("counter") {
+            Some(Value::Number(n)) => n.as_i64().unwrap_or(0),
+            Some(Value::String(s)) => s.parse().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// 记录同时处于读改写区间的任务数
+    #[derive(Default)]
+    struct Probe {
+        /// 读取后等待另一个任务也完成读取，构造最坏的交错
+        barrier: Option<Barrier>,
+        active: AtomicUsize,
+        max_active: AtomicUsize,
+    }
+
+    /// 读取计数器，让出执行后写回 +1
+    async fn increment(
+        runtime: Arc<AsyncMutex<ForgeRuntime>>,
+        probe: Arc<Probe>,
+    ) -> ForgeResult<()> {
+        let active = probe.active.fetch_add(1, Ordering::SeqCst) + 1;
+        probe.max_active.fetch_max(active, Ordering::SeqCst);
+        let value = counter(&runtime.lock().await.doc());
+        match &probe.barrier {
+            Some(barrier) => {
+                barrier.wait().await;
+            },
+            None => tokio::task::yield_now().await,
+        }
+
+        let mut rt = runtime.lock().await;
+        let mut tr = rt.get_tr();
+        let root = rt.doc().root_id().clone();
+        tr.set_node_attribute(
+            root,
+            HashTrieMapSync::new_sync()
+                .insert("counter".to_string(), Value::from(value + 1)),
+        )?;
+        tr.commit()?;
+        let result = rt.dispatch(tr).await;
+        probe.active.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+
+    /// 两个任务各自递增 3 次，返回最终计数与最大并发数
+    async fn run_concurrently(
+        locked: bool,
+        probe: Arc<Probe>,
+    ) -> (i64, usize) {
+        let runtime = Arc::new(AsyncMutex::new(
+            ForgeRuntime::from_xml_content(XML, None, None).await.unwrap(),
+        ));
+        // 持锁方之间排队等待
+        runtime
+            .lock()
+            .await
+            .doc_locks()
+            .set_policy(DocLockPolicy::Wait(Duration::from_secs(5)));
+        let mut tasks = Vec::new();
+        for _ in 0..2 {
+            let runtime = runtime.clone();
+            let probe = probe.clone();
+            tasks.push(tokio::spawn(async move {
+                for _ in 0..3 {
+                    if locked {
+                        let rt = runtime.clone();
+                        let probe = probe.clone();
+                        let critical = runtime
+                            .lock()
+                            .await
+                            .with_doc_lock(LockScope::Document, |_| {
+                                increment(rt, probe)
+                            });
+                        critical.await.unwrap();
+                    } else {
+                        increment(runtime.clone(), probe.clone())
+                            .await
+                            .unwrap();
+                    }
+                }
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        let rt = runtime.lock().await;
+        (counter(&rt.doc()), probe.max_active.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_read_modify_write_with_lock() {
+        // 加锁时读改写区间互不重叠
+        let probe = Arc::new(Probe::default());
+        assert_eq!(run_concurrently(true, probe).await, (6, 1));
+
+        // 不加锁时每轮两个任务都读到相同的旧值，每轮丢失一次更新
+        let probe = Arc::new(Probe {
+            barrier: Some(Barrier::new(2)),
+            ..Default::default()
+        });
+        assert_eq!(run_concurrently(false, probe).await, (3, 2));
+    }
+
+    #[tokio::test]
+    async fn test_nested_and_fail_fast() {
+        let runtime =
+            ForgeRuntime::from_xml_content(XML, None, None).await.unwrap();
+        let locks = runtime.doc_locks();
+        let doc = runtime.doc();
+        let root = doc.root_id().clone();
+
+        let inner_locks = locks.clone();
+        let inner_doc = doc.clone();
+        let nested = locks
+            .clone()
+            .with_lock(LockScope::Subtree(root.clone()), doc.clone(), |_| {
+                async move {
+                    // 子树锁内获取整文档锁会被拒绝
+                    inner_locks
+                        .with_lock(LockScope::Document, inner_doc, |_| async {
+                            Ok(())
+                        })
+                        .await
+                }
+            })
+            .await;
+        assert!(nested.is_err());
+        assert!(locks.active_locks().is_empty());
+
+        // 持锁期间，锁外派发的事务按默认的 FailFast 策略立即失败
+        assert_eq!(locks.policy(), DocLockPolicy::FailFast);
+        let mut tr = runtime.get_tr();
+        tr.set_node_attribute(
+            root.clone(),
+            HashTrieMapSync::new_sync()
+                .insert("counter".to_string(), Value::from(1)),
+        )
+        .unwrap();
+        let checker = locks.clone();
+        let outside = tokio::spawn({
+            let doc = doc.clone();
+            async move {
+                let release = Arc::new(Notify::new());
+                let hold = release.clone();
+                let holder = tokio::spawn({
+                    let locks = checker.clone();
+                    let doc = doc.clone();
+                    async move {
+                        locks
+                            .with_lock(
+                                LockScope::Document,
+                                doc,
+                                |_| async move {
+                                    hold.notified().await;
+                                    Ok(())
+                                },
+                            )
+                            .await
+                    }
+                });
+                while checker.active_locks().is_empty() {
+                    tokio::task::yield_now().await;
+                }
+                let result = checker.begin_transaction(&tr, &doc).await;
+                release.notify_one();
+                holder.await.unwrap().unwrap();
+                result.map(drop)
+            }
+        });
+        assert!(outside.await.unwrap().is_err());
+
+        // 被取消的持锁 future 同样会释放锁
+        let pending =
+            locks.clone().with_lock(LockScope::Document, doc.clone(), |_| {
+                std::future::pending::<ForgeResult<()>>()
+            });
+        let _ = tokio::time::timeout(Duration::from_millis(10), pending).await;
+        assert!(locks.active_locks().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wait_policy_dispatch() {
+        let runtime = Arc::new(AsyncMutex::new(
+            ForgeRuntime::from_xml_content(XML, None, None).await.unwrap(),
+        ));
+        let locks = runtime.lock().await.doc_locks();
+        let policy = DocLockPolicy::Wait(Duration::from_secs(5));
+        locks.set_policy(policy);
+        let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        // 持锁方不经由运行时派发，只等待放行
+        let hold = |release: Arc<Notify>| {
+            let runtime = runtime.clone();
+            let order = order.clone();
+            tokio::spawn(async move {
+                let critical = runtime.lock().await.with_doc_lock(
+                    LockScope::Document,
+                    |_| async move {
+                        release.notified().await;
+                        order.lock().unwrap().push("holder");
+                        Ok(())
+                    },
+                );
+                critical.await
+            })
+        };
+        let dispatch = |value: i64| {
+            let runtime = runtime.clone();
+            let order = order.clone();
+            tokio::spawn(async move {
+                let mut rt = runtime.lock().await;
+                let mut tr = rt.get_tr();
+                let root = rt.doc().root_id().clone();
+                tr.set_node_attribute(
+                    root,
+                    HashTrieMapSync::new_sync()
+                        .insert("counter".to_string(), Value::from(value)),
+                )?;
+                tr.commit()?;
+                let result = rt.dispatch(tr).await;
+                order.lock().unwrap().push("dispatch");
+                result
+            })
+        };
+
+        let release = Arc::new(Notify::new());
+        let holder = hold(release.clone());
+        while locks.active_locks().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        // 调试报告列出活跃的锁
+        let report = runtime.lock().await.debug_report();
+        assert_eq!(report.lock_policy, policy);
+        assert_eq!(report.active_locks.len(), 1);
+        assert!(matches!(report.active_locks[0].scope, LockScope::Document));
+
+        // 锁外派发等待锁释放后再应用
+        let dispatcher = dispatch(1);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!dispatcher.is_finished());
+        release.notify_one();
+        holder.await.unwrap().unwrap();
+        dispatcher.await.unwrap().unwrap();
+        assert_eq!(*order.lock().unwrap(), ["holder", "dispatch"]);
+        assert_eq!(counter(&runtime.lock().await.doc()), 1);
+        assert!(runtime.lock().await.debug_report().active_locks.is_empty());
+
+        // 等待超时后派发失败，文档保持不变
+        locks.set_policy(DocLockPolicy::Wait(Duration::from_millis(30)));
+        let release = Arc::new(Notify::new());
+        let holder = hold(release.clone());
+        while locks.active_locks().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert!(dispatch(2).await.unwrap().is_err());
+        assert_eq!(counter(&runtime.lock().await.doc()), 1);
+        release.notify_one();
+        holder.await.unwrap().unwrap();
+
+        // 正在应用的事务结束前，新的锁即使是 FailFast 也会等待
+        locks.set_policy(DocLockPolicy::FailFast);
+        let (tr, doc) = {
+            let rt = runtime.lock().await;
+            (rt.get_tr(), rt.doc())
+        };
+        let applying = locks.begin_transaction(&tr, &doc).await.unwrap();
+        let waiter = tokio::spawn({
+            let locks = locks.clone();
+            async move {
+                locks
+                    .with_lock(LockScope::Document, doc, |_| async { Ok(()) })
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+        assert!(locks.active_locks().is_empty());
+        drop(applying);
+        waiter.await.unwrap().unwrap();
+    }
+}
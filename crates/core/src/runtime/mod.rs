@@ -2,9 +2,11 @@ pub mod async_flow;
 pub mod async_processor;
 pub mod async_runtime;
 pub mod async_utils;
+pub mod doc_lock;
 #[allow(clippy::module_inception)]
 pub mod runtime;
 pub mod runtime_trait;
+pub mod snapshot;
 pub mod sync_flow;
 pub mod sync_processor;
 
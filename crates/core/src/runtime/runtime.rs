@@ -1,31 +1,48 @@
+use std::future::Future;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Instant;
 
 use async_trait::async_trait;
 
 use crate::{
-    config::ForgeConfig,
+    config::{ForgeConfig, ProcessorConfig},
     debug::{debug, info},
     error::{error_utils, ForgeResult},
     event::{Event, EventBus},
     extension_manager::ExtensionManager,
     helpers::{
-        create_doc, event_helper::EventHelper, history_helper::HistoryHelper,
-        middleware_helper::MiddlewareHelper,
+        command_helper::CommandHelper, create_doc, event_helper::EventHelper,
+        history_helper::HistoryHelper, middleware_helper::MiddlewareHelper,
     },
     history_manager::HistoryManager,
     metrics,
-    runtime::sync_flow::FlowEngine,
+    runtime::{
+        doc_lock::{DocLock, DocLockInfo, DocLockManager, DocLockPolicy, LockScope},
+        snapshot::{self, SnapshotInfo, SnapshotScheduler},
+        sync_flow::FlowEngine,
+    },
     types::{HistoryEntryWithMeta, ProcessorResult, RuntimeOptions},
 };
 
 use mf_model::{node_pool::NodePool, schema::Schema};
 use mf_state::{
     ops::GlobalResourceManager,
-    state::{State, StateConfig},
-    transaction::Transaction,
+    state::{Configuration, State, StateConfig},
+    transaction::{CommandContext, Transaction},
 };
 
+/// 运行时调试报告，汇总状态、历史与文档锁
+#[derive(Debug, Clone)]
+pub struct RuntimeDebugReport {
+    pub state_version: u64,
+    pub node_count: usize,
+    pub undo_count: usize,
+    pub redo_count: usize,
+    pub lock_policy: DocLockPolicy,
+    pub active_locks: Vec<DocLockInfo>,
+}
+
 /// Editor 结构体代表编辑器的核心功能实现
 /// 负责管理文档状态、事件处理、插件系统和存储等核心功能
 pub struct ForgeRuntime {
@@ -36,6 +53,10 @@ pub struct ForgeRuntime {
     history_manager: HistoryManager<HistoryEntryWithMeta>,
     options: RuntimeOptions,
     config: ForgeConfig,
+    doc_locks: Arc<DocLockManager>,
+    snapshot_scheduler: Option<SnapshotScheduler>,
+    /// 最后一次提交的发起者，写入快照头部
+    last_actor: Option<String>,
 }
 impl ForgeRuntime {
     /// 创建新的编辑器实例
@@ -239,9 +260,9 @@ impl ForgeRuntime {
 
         debug!("已初始化扩展管理器");
 
-        let op_state = GlobalResourceManager::new();
+        let op_state = Arc::new(GlobalResourceManager::new());
         for op_fn in extension_manager.get_op_fns() {
-            op_fn(&op_state)?;
+            op_fn.run(&op_state)?;
         }
 
         let mut state_config = StateConfig {
@@ -249,15 +270,126 @@ impl ForgeRuntime {
             doc: None,
             stored_marks: None,
             plugins: Some(extension_manager.get_plugins().clone()),
-            resource_manager: Some(Arc::new(op_state)),
+            resource_manager: Some(op_state),
         };
         create_doc::create_doc(&options.get_content(), &mut state_config)
             .await?;
         let state: State = State::create(state_config).await?;
-
-        let state: Arc<State> = Arc::new(state);
         debug!("已创建编辑器状态");
 
+        Self::assemble(
+            Arc::new(state),
+            extension_manager,
+            options,
+            config,
+            start_time,
+        )
+        .await
+    }
+
+    /// 从快照文件恢复编辑器实例
+    ///
+    /// 按 `options` 与 `config` 构建 schema 与插件，快照中的 schema 指纹必须与之一致；
+    /// 快照中保存的插件状态会被恢复，未保存的插件状态使用初始值
+    ///
+    /// # 参数
+    /// * `path` - 快照文件路径
+    /// * `options` - 可选的RuntimeOptions配置
+    /// * `config` - 可选的ForgeConfig配置
+    ///
+    /// # 返回值
+    /// * `ForgeResult<Self>` - 编辑器实例或错误
+    pub async fn from_snapshot(
+        path: impl AsRef<Path>,
+        options: Option<RuntimeOptions>,
+        config: Option<ForgeConfig>,
+    ) -> ForgeResult<Self> {
+        let start_time = Instant::now();
+        let options = options.unwrap_or_default();
+        let config = config.unwrap_or_default();
+        let snapshot = snapshot::read_snapshot(path.as_ref()).await?;
+
+        let extension_manager =
+            Self::create_extension_manager(&options, &config)?;
+        let schema = extension_manager.get_schema();
+        let fingerprint = snapshot::schema_fingerprint(&schema);
+        if snapshot.header.schema_fingerprint != fingerprint {
+            return Err(error_utils::validation_error(format!(
+                "快照的 schema 指纹 {} 与当前 schema 指纹 {} 不一致",
+                snapshot.header.schema_fingerprint, fingerprint
+            )));
+        }
+
+        let op_state = Arc::new(GlobalResourceManager::new());
+        for op_fn in extension_manager.get_op_fns() {
+            op_fn.run(&op_state)?;
+        }
+        let configuration = Configuration::new(
+            schema,
+            Some(extension_manager.get_plugins().clone()),
+            None,
+            Some(op_state),
+        )
+        .await?;
+        let state = State::deserialize(&snapshot.state, &configuration).await?;
+        info!("已从快照 {} 恢复编辑器状态", path.as_ref().display());
+
+        Self::assemble(
+            Arc::new(state),
+            extension_manager,
+            options,
+            config,
+            start_time,
+        )
+        .await
+    }
+
+    /// 优先从快照恢复，失败时回退到常规创建
+    ///
+    /// `path` 为目录时按创建时间从新到旧依次尝试其中的快照，
+    /// 使用第一个能成功恢复的快照
+    pub async fn from_snapshot_or_fallback(
+        path: impl AsRef<Path>,
+        options: Option<RuntimeOptions>,
+        config: Option<ForgeConfig>,
+    ) -> ForgeResult<Self> {
+        let path = path.as_ref();
+        let options = options.unwrap_or_default();
+        let config = config.unwrap_or_default();
+        let candidates = if path.is_dir() {
+            snapshot::list_snapshots(path)
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect()
+        } else {
+            vec![path.to_path_buf()]
+        };
+        for candidate in candidates {
+            match Self::from_snapshot(
+                &candidate,
+                Some(options.clone()),
+                Some(config.clone()),
+            )
+            .await
+            {
+                Ok(runtime) => return Ok(runtime),
+                Err(e) => {
+                    debug!("快照 {} 不可用: {}", candidate.display(), e)
+                },
+            }
+        }
+        info!("没有可用的快照，使用常规方式创建编辑器实例");
+        Self::create_with_config(options, config).await
+    }
+
+    /// 由已构建的状态组装运行时：事件总线、历史记录与后台快照
+    async fn assemble(
+        state: Arc<State>,
+        extension_manager: ExtensionManager,
+        options: RuntimeOptions,
+        config: ForgeConfig,
+        start_time: Instant,
+    ) -> ForgeResult<Self> {
         // 使用 EventHelper 创建并初始化事件总线
         let event_bus = EventHelper::create_and_init_event_bus(
             &config,
@@ -270,7 +402,6 @@ impl ForgeRuntime {
         let initial_transaction = state.tr();
 
         let runtime = ForgeRuntime {
-            event_bus,
             state: state.clone(),
             flow_engine: Arc::new(FlowEngine::new()?),
             extension_manager,
@@ -283,8 +414,18 @@ impl ForgeRuntime {
                 ),
                 config.history.clone(),
             ),
+            snapshot_scheduler: config.snapshot.enabled.then(|| {
+                SnapshotScheduler::start(
+                    config.snapshot.clone(),
+                    state.clone(),
+                    event_bus.clone(),
+                )
+            }),
+            event_bus,
             options,
             config,
+            doc_locks: Arc::new(DocLockManager::default()),
+            last_actor: None,
         };
         info!("编辑器实例创建成功");
         metrics::editor_creation_duration(start_time.elapsed());
@@ -315,6 +456,10 @@ impl ForgeRuntime {
     )]
     pub async fn destroy(&mut self) -> ForgeResult<()> {
         debug!("正在销毁编辑器实例");
+        if let Some(scheduler) = &self.snapshot_scheduler {
+            scheduler.stop();
+        }
+        self.state.destroy().await;
         EventHelper::destroy_event_bus(&mut self.event_bus).await?;
         debug!("编辑器实例销毁成功");
         Ok(())
@@ -421,12 +566,27 @@ impl ForgeRuntime {
             mf_model::schema::Schema,
         >>,
     ) -> ForgeResult<()> {
-        debug!("正在执行命令: {}", command.name());
-        metrics::command_executed(command.name().as_str());
-        let mut tr = self.get_tr();
-        command.execute(&mut tr).await?;
-        tr.commit()?;
-        self.dispatch(tr).await
+        self.command_with_context(command, CommandContext::new()).await
+    }
+
+    /// 在指定上下文中执行命令
+    ///
+    /// 命令通过 `ctx` 上报的进度会广播为 `Event::CommandProgress`；
+    /// 执行期间被取消时状态保持不变，返回 `ForgeError::Cancelled`
+    #[cfg_attr(feature = "dev-tracing", tracing::instrument(skip(self, command, ctx), fields(
+        crate_name = "core",
+        command_name = %command.name()
+    )))]
+    pub async fn command_with_context(
+        &mut self,
+        command: Arc<dyn mf_state::transaction::CommandGeneric<
+            mf_model::node_pool::NodePool,
+            mf_model::schema::Schema,
+        >>,
+        ctx: CommandContext,
+    ) -> ForgeResult<()> {
+        self.run_command(command, ctx, "".to_string(), serde_json::Value::Null)
+            .await
     }
 
     #[cfg_attr(feature = "dev-tracing", tracing::instrument(skip(self, command, meta), fields(
@@ -442,15 +602,70 @@ impl ForgeRuntime {
         >>,
         description: String,
         meta: serde_json::Value,
+    ) -> ForgeResult<()> {
+        self.run_command(command, CommandContext::new(), description, meta)
+            .await
+    }
+
+    async fn run_command(
+        &mut self,
+        command: Arc<dyn mf_state::transaction::CommandGeneric<
+            mf_model::node_pool::NodePool,
+            mf_model::schema::Schema,
+        >>,
+        ctx: CommandContext,
+        description: String,
+        meta: serde_json::Value,
     ) -> ForgeResult<()> {
         debug!("正在执行命令: {}", command.name());
         metrics::command_executed(command.name().as_str());
+        let ctx = CommandHelper::forward_progress(
+            ctx,
+            &self.event_bus,
+            command.name(),
+        );
         let mut tr = self.get_tr();
-        command.execute(&mut tr).await?;
+        CommandHelper::execute(&command, &mut tr, &ctx).await?;
         tr.commit()?;
         self.dispatch_with_meta(tr, description, meta).await
     }
 
+    /// 应用外部构建的事务
+    ///
+    /// 与 `command` 走相同的中间件、文档锁与插件流水线，适用于重放持久化的步骤
+    /// 或应用已经是事务形式的远程协作编辑。事务必须基于当前文档构建，
+    /// 否则返回错误，调用方应先变基或三方合并。
+    #[cfg_attr(feature = "dev-tracing", tracing::instrument(skip(self, transaction), fields(
+        crate_name = "core",
+        tr_id = %transaction.id,
+        runtime_type = "sync"
+    )))]
+    pub async fn apply_transaction(
+        &mut self,
+        mut transaction: Transaction,
+    ) -> ForgeResult<()> {
+        self.ensure_current_base(&transaction)?;
+        transaction.commit()?;
+        self.dispatch(transaction).await
+    }
+
+    /// 校验事务基于当前文档构建
+    pub(crate) fn ensure_current_base(
+        &self,
+        transaction: &Transaction,
+    ) -> ForgeResult<()> {
+        let current = self.doc();
+        if Arc::ptr_eq(&transaction.base_doc, &current)
+            || *transaction.base_doc == *current
+        {
+            return Ok(());
+        }
+        Err(error_utils::transaction_error_with_id(
+            "事务不是基于当前文档构建的，请先变基或合并",
+            transaction.id,
+        ))
+    }
+
     /// 处理编辑器事务的核心方法
     ///
     /// # 参数
@@ -492,6 +707,11 @@ impl ForgeRuntime {
         // 保存当前事务的副本，用于中间件处理
         let mut current_transaction = transaction;
         self.run_before_middleware(&mut current_transaction).await?;
+        // 守卫保持到状态更新完成，期间新的文档锁会等待
+        let _dispatch = self
+            .doc_locks
+            .begin_transaction(&current_transaction, &self.doc())
+            .await?;
 
         // 应用事务到编辑器状态，获取新的状态和产生的事务列表
         let task_result = self
@@ -557,6 +777,7 @@ impl ForgeRuntime {
         meta: serde_json::Value,
     ) -> ForgeResult<()> {
         self.state = state.clone();
+        self.record_snapshot_activity(&transactions);
         HistoryHelper::insert(
             &mut self.history_manager,
             state,
@@ -616,6 +837,39 @@ impl ForgeRuntime {
         Ok(())
     }
 
+    /// 在文档锁保护下执行跨多个命令的临界区
+    ///
+    /// 返回的 future 不借用运行时，闭包内可以再次访问运行时派发事务。
+    /// 锁外派发的事务若修改到锁定范围，将按 `doc_locks().policy()` 等待或失败。
+    pub fn with_doc_lock<F, Fut, R>(
+        &self,
+        scope: LockScope,
+        f: F,
+    ) -> impl Future<Output = ForgeResult<R>> + use<F, Fut, R>
+    where
+        F: FnOnce(DocLock) -> Fut,
+        Fut: Future<Output = ForgeResult<R>>,
+    {
+        self.doc_locks.clone().with_lock(scope, self.doc(), f)
+    }
+
+    /// 获取文档锁管理器，可用于调整等待策略或查看活跃的锁
+    pub fn doc_locks(&self) -> Arc<DocLockManager> {
+        self.doc_locks.clone()
+    }
+
+    /// 生成调试报告，包含当前活跃的文档锁
+    pub fn debug_report(&self) -> RuntimeDebugReport {
+        RuntimeDebugReport {
+            state_version: self.state.version,
+            node_count: self.doc().size(),
+            undo_count: self.history_manager.past_count(),
+            redo_count: self.history_manager.future_count(),
+            lock_policy: self.doc_locks.policy(),
+            active_locks: self.doc_locks.active_locks(),
+        }
+    }
+
     /// 共享的基础实现方法
     pub fn doc(&self) -> Arc<NodePool> {
         self.state.doc()
@@ -638,6 +892,26 @@ impl ForgeRuntime {
         self.config = config;
     }
 
+    /// 运行中调整处理器配置
+    ///
+    /// 同步运行时没有工作池，只有重试次数与重试间隔生效，
+    /// 对之后提交的事务有效。构造时的同步处理器不读取 `config.processor`，
+    /// 使用固定的重试设置（3 次，间隔 1 秒），调用本方法后才改用传入的设置
+    pub fn reconfigure(
+        &mut self,
+        processor: ProcessorConfig,
+    ) -> ForgeResult<()> {
+        let mut config = self.config.clone();
+        config.processor = processor;
+        config.validate().map_err(|e| {
+            error_utils::config_error(format!("处理器配置无效: {e}"))
+        })?;
+        self.flow_engine =
+            Arc::new(FlowEngine::with_config(&config.processor)?);
+        self.config = config;
+        Ok(())
+    }
+
     pub fn get_state(&self) -> &Arc<State> {
         &self.state
     }
@@ -646,6 +920,47 @@ impl ForgeRuntime {
         self.extension_manager.get_schema()
     }
 
+    /// 导出当前 Schema 的定义，格式与 `SchemaSpec` 一致，
+    /// 可直接反序列化后重新编译
+    pub fn export_schema(&self) -> ForgeResult<serde_json::Value> {
+        serde_json::to_value(&self.get_schema().spec).map_err(|e| {
+            error_utils::internal_error(format!("Schema 导出失败: {e}"))
+        })
+    }
+
+    /// 把当前状态写入快照文件
+    ///
+    /// 包含文档、schema 指纹，以及按 `config.snapshot.include_plugin_states`
+    /// 决定是否包含的插件状态。先写临时文件再重命名，期间广播
+    /// `SnapshotStarted`、`SnapshotSucceeded` 或 `SnapshotFailed` 事件
+    pub async fn create_snapshot(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> ForgeResult<SnapshotInfo> {
+        snapshot::take_snapshot(
+            &self.event_bus,
+            path.as_ref().to_path_buf(),
+            &self.state,
+            self.last_actor.as_deref(),
+            self.config.snapshot.include_plugin_states,
+        )
+        .await
+    }
+
+    /// 记录本次提交的发起者，并通知后台快照调度器状态已更新
+    fn record_snapshot_activity(
+        &mut self,
+        transactions: &[Arc<Transaction>],
+    ) {
+        self.last_actor = transactions
+            .first()
+            .and_then(|tr| tr.actor())
+            .map(str::to_string);
+        if let Some(scheduler) = &self.snapshot_scheduler {
+            scheduler.record(&self.state, self.last_actor.as_deref());
+        }
+    }
+
     pub fn get_event_bus(&self) -> &EventBus<Event> {
         &self.event_bus
     }
@@ -659,6 +974,7 @@ impl ForgeRuntime {
             HistoryHelper::undo(&mut self.history_manager, self.state.clone())
         {
             self.state = result.new_state.clone();
+            self.record_snapshot_activity(&result.transactions);
 
             // 触发撤销事件，供其他组件（如搜索索引）使用
             let _ = self.event_bus.broadcast_blocking(Event::Undo {
@@ -674,6 +990,7 @@ impl ForgeRuntime {
             HistoryHelper::redo(&mut self.history_manager, self.state.clone())
         {
             self.state = result.new_state.clone();
+            self.record_snapshot_activity(&result.transactions);
 
             // 触发重做事件，供其他组件（如搜索索引）使用
             let _ = self.event_bus.broadcast_blocking(Event::Redo {
@@ -694,6 +1011,7 @@ impl ForgeRuntime {
             n,
         ) {
             self.state = result.new_state.clone();
+            self.record_snapshot_activity(&result.transactions);
 
             // 触发跳转事件，供其他组件（如搜索索引）使用
             let _ = self.event_bus.broadcast_blocking(Event::Jump {
@@ -704,6 +1022,27 @@ impl ForgeRuntime {
             });
         }
     }
+    /// 选择性撤销某个历史条目，反向事务作为新的历史条目提交
+    pub async fn undo_entry(
+        &mut self,
+        entry_id: u64,
+    ) -> ForgeResult<()> {
+        let tr = self.history_manager.undo_entry(entry_id)?;
+        let description = self
+            .history_manager
+            .entries()
+            .into_iter()
+            .find(|entry| entry.entry_id == entry_id)
+            .map(|entry| entry.description)
+            .unwrap_or_default();
+        self.dispatch_with_meta(
+            tr,
+            format!("撤销: {description}"),
+            serde_json::json!({ "undo_entry": entry_id }),
+        )
+        .await
+    }
+
     pub fn get_history_manager(&self) -> &HistoryManager<HistoryEntryWithMeta> {
         &self.history_manager
     }
@@ -809,3 +1148,478 @@ impl crate::runtime::runtime_trait::RuntimeTraitGeneric<
         self.destroy().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ForgeError, event::EventHandler};
+    use mf_model::{
+        node_pool::NodePool, rpds::HashTrieMapSync, schema::Schema,
+    };
+    use crate::config::NonUserHistoryMode;
+    use mf_state::transaction::CommandGeneric;
+    use mf_state::TransactionOrigin;
+    use mf_transform::TransformResult;
+    use serde_json::Value;
+    use std::sync::Mutex;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<schema top_node="doc">
+  <nodes>
+    <node name="doc" desc="文档">
+      <attrs>
+        <attr name="title" default=""/>
+      </attrs>
+    </node>
+  </nodes>
+</schema>"#;
+
+    fn set_title(
+        runtime: &ForgeRuntime,
+        title: &str,
+    ) -> Transaction {
+        let mut tr = runtime.get_tr();
+        let root = runtime.doc().root_id().clone();
+        tr.set_node_attribute(
+            root,
+            HashTrieMapSync::new_sync()
+                .insert("title".to_string(), Value::from(title)),
+        )
+        .unwrap();
+        tr
+    }
+
+    fn title(runtime: &ForgeRuntime) -> Option<Value> {
+        runtime.doc().root().unwrap().attrs.get("title").cloned()
+    }
+
+    #[tokio::test]
+    async fn test_apply_transaction() {
+        let mut runtime =
+            ForgeRuntime::from_xml_content(XML, None, None).await.unwrap();
+        let stale = set_title(&runtime, "stale");
+
+        let tr = set_title(&runtime, "外部事务");
+        runtime.apply_transaction(tr).await.unwrap();
+        assert_eq!(title(&runtime), Some(Value::from("外部事务")));
+
+        // 基于旧文档构建的事务会被拒绝，文档保持不变
+        assert!(runtime.apply_transaction(stale).await.is_err());
+        assert_eq!(title(&runtime), Some(Value::from("外部事务")));
+
+        // 与命令一样记录到历史中
+        runtime.undo();
+        assert_eq!(title(&runtime), Some(Value::from("")));
+    }
+
+    #[tokio::test]
+    async fn test_export_schema() {
+        let runtime =
+            ForgeRuntime::from_xml_content(XML, None, None).await.unwrap();
+        let exported = runtime.export_schema().unwrap();
+        assert_eq!(exported["top_node"], "doc");
+        assert_eq!(exported["nodes"]["doc"]["desc"], "文档");
+        assert_eq!(exported["nodes"]["doc"]["attrs"]["title"]["default"], "");
+
+        let spec: mf_model::schema::SchemaSpec =
+            serde_json::from_value(exported).unwrap();
+        assert_eq!(spec, runtime.get_schema().spec);
+        let schema = Schema::compile(spec).unwrap();
+        assert_eq!(schema.spec, runtime.get_schema().spec);
+    }
+
+    /// 模拟导入：分批写入并上报进度，`cancel_at` 批次时取消
+    #[derive(Debug)]
+    struct ImportCommand {
+        batches: u64,
+        cancel_at: Option<u64>,
+    }
+
+    #[async_trait::async_trait]
+    impl CommandGeneric<NodePool, Schema> for ImportCommand {
+        async fn execute(
+            &self,
+            tr: &mut Transaction,
+        ) -> TransformResult<()> {
+            self.execute_with(&CommandContext::new(), tr).await
+        }
+
+        async fn execute_with(
+            &self,
+            ctx: &CommandContext,
+            tr: &mut Transaction,
+        ) -> TransformResult<()> {
+            let root = tr.doc().root_id().clone();
+            for done in 1..=self.batches {
+                ctx.check_cancelled()?;
+                tr.set_node_attribute(
+                    root.clone(),
+                    HashTrieMapSync::new_sync()
+                        .insert("title".to_string(), Value::from("导入")),
+                )?;
+                ctx.report(done, self.batches, format!("第 {done} 批"));
+                if self.cancel_at == Some(done) {
+                    ctx.cancel();
+                }
+            }
+            Ok(())
+        }
+
+        fn name(&self) -> String {
+            "import".to_string()
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct ProgressCollector(Mutex<Vec<(u64, u64)>>);
+
+    #[async_trait::async_trait]
+    impl EventHandler<Event> for ProgressCollector {
+        async fn handle(
+            &self,
+            event: &Event,
+        ) -> ForgeResult<()> {
+            if let Event::CommandProgress { done, total, .. } = event {
+                self.0.lock().unwrap().push((*done, *total));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_with_context() {
+        let mut runtime =
+            ForgeRuntime::from_xml_content(XML, None, None).await.unwrap();
+        let collector = Arc::new(ProgressCollector::default());
+        runtime
+            .get_event_bus()
+            .add_event_handler(collector.clone())
+            .unwrap();
+
+        let import = Arc::new(ImportCommand { batches: 3, cancel_at: None });
+        runtime
+            .command_with_context(import, CommandContext::new())
+            .await
+            .unwrap();
+        assert_eq!(title(&runtime), Some(Value::from("导入")));
+        for _ in 0..100 {
+            if collector.0.lock().unwrap().len() >= 3 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            *collector.0.lock().unwrap(),
+            vec![(1, 3), (2, 3), (3, 3)]
+        );
+
+        // 执行中途取消：返回 Cancelled，文档与历史保持不变
+        let cancelled =
+            Arc::new(ImportCommand { batches: 3, cancel_at: Some(1) });
+        let err = runtime
+            .command_with_context(cancelled, CommandContext::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ForgeError::Cancelled { .. }));
+
+        // 执行前已取消的上下文不会运行命令
+        let ctx = CommandContext::new();
+        ctx.cancel();
+        let import = Arc::new(ImportCommand { batches: 3, cancel_at: None });
+        assert!(runtime.command_with_context(import, ctx).await.is_err());
+
+        runtime.undo();
+        assert_eq!(title(&runtime), Some(Value::from("")));
+    }
+
+    #[derive(Debug, Default)]
+    struct ActorEvents(Mutex<Vec<(&'static str, Option<String>)>>);
+
+    #[async_trait::async_trait]
+    impl EventHandler<Event> for ActorEvents {
+        async fn handle(
+            &self,
+            event: &Event,
+        ) -> ForgeResult<()> {
+            if matches!(event, Event::TrApply { .. } | Event::Undo { .. }) {
+                let actor = event.actor().map(str::to_string);
+                self.0.lock().unwrap().push((event.name(), actor));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_actor() {
+        let mut runtime =
+            ForgeRuntime::from_xml_content(XML, None, None).await.unwrap();
+        let events = Arc::new(ActorEvents::default());
+        runtime.get_event_bus().add_event_handler(events.clone()).unwrap();
+        let tr = set_title(&runtime, "a").with_actor("alice".to_string());
+        runtime.dispatch(tr).await.unwrap();
+        let tr = set_title(&runtime, "b");
+        runtime.dispatch(tr).await.unwrap();
+        runtime.undo();
+        runtime.undo();
+
+        for _ in 0..100 {
+            if events.0.lock().unwrap().len() >= 4 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            *events.0.lock().unwrap(),
+            vec![
+                ("TrApply", Some("alice".to_string())),
+                ("TrApply", None),
+                ("Undo", None),
+                ("Undo", Some("alice".to_string())),
+            ]
+        );
+    }
+
+    fn snapshot_options() -> RuntimeOptions {
+        RuntimeOptions::from_extension_manager(
+            ExtensionManager::from_xml_string(XML).unwrap(),
+        )
+    }
+
+    #[derive(Debug, Default)]
+    struct SnapshotEvents(Mutex<Vec<&'static str>>);
+
+    #[async_trait::async_trait]
+    impl EventHandler<Event> for SnapshotEvents {
+        async fn handle(
+            &self,
+            event: &Event,
+        ) -> ForgeResult<()> {
+            if event.name().starts_with("Snapshot") {
+                self.0.lock().unwrap().push(event.name());
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.mfsnap");
+        let mut runtime =
+            ForgeRuntime::from_xml_content(XML, None, None).await.unwrap();
+        let events = Arc::new(SnapshotEvents::default());
+        runtime.get_event_bus().add_event_handler(events.clone()).unwrap();
+        let tr = set_title(&runtime, "快照").with_actor("alice".to_string());
+        runtime.dispatch(tr).await.unwrap();
+
+        let info = runtime.create_snapshot(&path).await.unwrap();
+        assert_eq!(info.size, std::fs::metadata(&path).unwrap().len());
+        let header = crate::read_snapshot_header(&path).unwrap();
+        assert_eq!(header.state_version, runtime.get_state().version);
+        assert_eq!(header.actor.as_deref(), Some("alice"));
+        assert_eq!(
+            header.schema_fingerprint,
+            crate::schema_fingerprint(&runtime.get_schema())
+        );
+        for _ in 0..100 {
+            if events.0.lock().unwrap().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            *events.0.lock().unwrap(),
+            vec!["SnapshotStarted", "SnapshotSucceeded"]
+        );
+
+        let restored =
+            ForgeRuntime::from_snapshot(&path, Some(snapshot_options()), None)
+                .await
+                .unwrap();
+        assert_eq!(title(&restored), Some(Value::from("快照")));
+        assert_eq!(restored.doc().root_id(), runtime.doc().root_id());
+
+        // schema 不一致的快照被拒绝
+        let other = XML.replace(r#"default="""#, r#"default="x""#);
+        let options = RuntimeOptions::from_extension_manager(
+            ExtensionManager::from_xml_string(&other).unwrap(),
+        );
+        assert!(
+            ForgeRuntime::from_snapshot(&path, Some(options), None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_keeps_dynamic_defaults() {
+        use crate::{
+            extension::Extension,
+            node::Node,
+            types::{Extensions, GlobalAttributeItem},
+        };
+        use mf_model::node_definition::NodeSpec;
+
+        let user = Arc::new(Mutex::new("alice".to_string()));
+        let options = || {
+            let current = user.clone();
+            let mut extension = Extension::new();
+            extension.add_global_attribute(
+                GlobalAttributeItem::for_groups(["report"])
+                    .with_dynamic_default("created_by", move |_| {
+                        Value::from(current.lock().unwrap().clone())
+                    }),
+            );
+            let mut doc = Node::create(
+                "doc",
+                NodeSpec { group: Some("report".into()), ..Default::default() },
+            );
+            doc.set_top_node();
+            RuntimeOptions::default().set_extensions(vec![
+                Extensions::N(doc),
+                Extensions::E(extension),
+            ])
+        };
+        let created_by = |runtime: &ForgeRuntime| {
+            runtime.doc().root().unwrap().attrs.get("created_by").cloned()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.mfsnap");
+        let runtime = ForgeRuntime::create(options()).await.unwrap();
+        assert_eq!(created_by(&runtime), Some(Value::from("alice")));
+        runtime.create_snapshot(&path).await.unwrap();
+
+        // 恢复的节点保留快照中的值，之后新建的节点重新计算
+        *user.lock().unwrap() = "bob".to_string();
+        let restored = ForgeRuntime::from_snapshot(&path, Some(options()), None)
+            .await
+            .unwrap();
+        assert_eq!(created_by(&restored), Some(Value::from("alice")));
+        let node = restored
+            .get_schema()
+            .factory()
+            .create_node("doc", None, None, vec![], None)
+            .unwrap();
+        assert_eq!(node.attrs["created_by"], Value::from("bob"));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_fallback_prefers_newest_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut runtime =
+            ForgeRuntime::from_xml_content(XML, None, None).await.unwrap();
+        for (i, name) in ["旧", "新"].into_iter().enumerate() {
+            let tr = set_title(&runtime, name);
+            runtime.dispatch(tr).await.unwrap();
+            runtime
+                .create_snapshot(dir.path().join(format!("{i}.mfsnap")))
+                .await
+                .unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        std::fs::write(dir.path().join("9.mfsnap"), b"MFSNAP broken").unwrap();
+
+        let restored = ForgeRuntime::from_snapshot_or_fallback(
+            dir.path(),
+            Some(snapshot_options()),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(title(&restored), Some(Value::from("新")));
+
+        // 没有可用快照时回退到常规创建
+        let empty = tempfile::tempdir().unwrap();
+        let created = ForgeRuntime::from_snapshot_or_fallback(
+            empty.path(),
+            Some(snapshot_options()),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(title(&created), Some(Value::from("")));
+    }
+
+    #[tokio::test]
+    async fn test_background_snapshot_when_idle() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = ForgeConfig::default();
+        config.snapshot = crate::config::SnapshotConfig {
+            enabled: true,
+            directory: dir.path().to_path_buf(),
+            interval: None,
+            every_transactions: Some(2),
+            idle_after: std::time::Duration::from_millis(50),
+            include_plugin_states: true,
+            keep: 1,
+        };
+        let mut runtime =
+            ForgeRuntime::from_xml_content(XML, None, Some(config))
+                .await
+                .unwrap();
+
+        let tr = set_title(&runtime, "一");
+        runtime.dispatch(tr).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        // 事务数未达到阈值
+        assert!(crate::list_snapshots(dir.path()).is_empty());
+
+        for name in ["二", "三"] {
+            let tr = set_title(&runtime, name);
+            runtime.dispatch(tr).await.unwrap();
+        }
+        let mut snapshots = Vec::new();
+        for _ in 0..100 {
+            snapshots = crate::list_snapshots(dir.path());
+            if !snapshots.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].1.state_version, runtime.get_state().version);
+    }
+
+    /// 用户编辑后单独提交一个由它触发的插件事务
+    async fn edit_with_companion(mode: NonUserHistoryMode) -> ForgeRuntime {
+        let mut config = ForgeConfig::default();
+        config.history.non_user_transactions = mode;
+        let mut runtime =
+            ForgeRuntime::from_xml_content(XML, None, Some(config))
+                .await
+                .unwrap();
+        let tr = set_title(&runtime, "用户");
+        let user_id = tr.id;
+        runtime.dispatch(tr).await.unwrap();
+
+        let mut tr = set_title(&runtime, "用户-编号");
+        tr.set_origin(TransactionOrigin::Plugin("numbering".to_string()))
+            .set_caused_by(user_id);
+        runtime.dispatch(tr).await.unwrap();
+        assert_eq!(title(&runtime), Some(Value::from("用户-编号")));
+        runtime
+    }
+
+    #[tokio::test]
+    async fn test_undo_reverts_merged_plugin_companion() {
+        let mut runtime = edit_with_companion(NonUserHistoryMode::Merge).await;
+        assert_eq!(runtime.history_manager.get_present().transactions.len(), 2);
+
+        runtime.undo();
+        assert_eq!(title(&runtime), Some(Value::from("")));
+        runtime.redo();
+        assert_eq!(title(&runtime), Some(Value::from("用户-编号")));
+    }
+
+    #[tokio::test]
+    async fn test_plugin_companion_history_modes() {
+        let mut runtime = edit_with_companion(NonUserHistoryMode::Record).await;
+        runtime.undo();
+        assert_eq!(title(&runtime), Some(Value::from("用户")));
+
+        let mut runtime = edit_with_companion(NonUserHistoryMode::Skip).await;
+        assert_eq!(runtime.history_manager.get_present().transactions.len(), 1);
+        runtime.undo();
+        assert_eq!(title(&runtime), Some(Value::from("")));
+    }
+}
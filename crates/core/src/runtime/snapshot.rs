@@ -0,0 +1,516 @@
+//! 运行时快照
+//!
+//! 快照文件由 `MFSNAP` 魔数、4 字节小端头部长度、JSON 头部组成，
+//! 随后依次是文档字节与各插件状态字节。写入时先写临时文件再重命名，
+//! 中途失败不会留下不完整的快照。
+
+use std::{
+    ffi::OsString,
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+
+use mf_model::schema::Schema;
+use mf_state::state::{State, StateSerialize};
+
+use crate::{
+    config::SnapshotConfig,
+    debug::{debug, warn},
+    error::{error_utils, ForgeResult},
+    event::{Event, EventBus},
+};
+
+/// 快照文件魔数
+const MAGIC: &[u8; 6] = b"MFSNAP";
+/// 魔数与头部长度占用的字节数
+const PREFIX_LEN: usize = MAGIC.len() + 4;
+/// 头部长度上限，防止损坏的文件触发超大分配
+const MAX_HEADER_LEN: usize = 16 * 1024 * 1024;
+
+/// 当前快照格式版本
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+/// 快照文件扩展名
+pub const SNAPSHOT_EXTENSION: &str = "mfsnap";
+
+/// 快照头部
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotHeader {
+    /// 格式版本
+    pub version: u32,
+    /// 创建时间（Unix 毫秒）
+    pub created_at: u64,
+    /// 写入时的 schema 指纹，见 [`schema_fingerprint`]
+    pub schema_fingerprint: String,
+    /// 写入时的状态版本号
+    pub state_version: u64,
+    /// 文档字节数
+    pub doc_len: u64,
+    /// 插件状态（插件 key，字节数），按 key 排序
+    pub plugin_states: Vec<(String, u64)>,
+    /// 快照前最后一次提交的发起者，见 `Transaction::actor`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+}
+
+/// 一次快照写入的结果
+#[derive(Debug, Clone)]
+pub struct SnapshotInfo {
+    /// 快照文件路径
+    pub path: PathBuf,
+    /// 文件字节数
+    pub size: u64,
+    /// 序列化与写入耗时
+    pub duration: Duration,
+}
+
+/// 已解码的快照
+pub(crate) struct SnapshotData {
+    pub header: SnapshotHeader,
+    pub state: StateSerialize,
+}
+
+/// 计算 schema 指纹
+///
+/// 对键有序的 schema 定义 JSON 做 FNV-1a 哈希，同一 schema 在不同进程中结果一致
+pub fn schema_fingerprint(schema: &Schema) -> String {
+    let json = serde_json::to_value(&schema.spec)
+        .map(|value| value.to_string())
+        .unwrap_or_default();
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in json.as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+/// 读取快照头部，不读取数据部分
+pub fn read_snapshot_header(path: &Path) -> ForgeResult<SnapshotHeader> {
+    let io_error = |e: std::io::Error| {
+        error_utils::storage_error(format!(
+            "读取快照 {} 失败: {e}",
+            path.display()
+        ))
+    };
+    let mut file = fs::File::open(path).map_err(io_error)?;
+    let mut bytes = vec![0u8; PREFIX_LEN];
+    file.read_exact(&mut bytes).map_err(io_error)?;
+    let header_len = header_len(&bytes)?;
+    bytes.resize(PREFIX_LEN + header_len, 0);
+    file.read_exact(&mut bytes[PREFIX_LEN..]).map_err(io_error)?;
+    decode_header(&bytes).map(|(header, _)| header)
+}
+
+/// 列出目录中头部有效的快照，按创建时间从新到旧排列
+pub fn list_snapshots(dir: &Path) -> Vec<(PathBuf, SnapshotHeader)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut snapshots: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == SNAPSHOT_EXTENSION)
+        })
+        .filter_map(|path| {
+            read_snapshot_header(&path).ok().map(|header| (path, header))
+        })
+        .collect();
+    snapshots.sort_by(|(_, a), (_, b)| {
+        (b.created_at, b.state_version).cmp(&(a.created_at, a.state_version))
+    });
+    snapshots
+}
+
+/// 读取并校验完整的快照文件
+pub(crate) async fn read_snapshot(path: &Path) -> ForgeResult<SnapshotData> {
+    let bytes = tokio::fs::read(path).await.map_err(|e| {
+        error_utils::storage_error(format!(
+            "读取快照 {} 失败: {e}",
+            path.display()
+        ))
+    })?;
+    decode(&bytes)
+}
+
+/// 把状态写入快照文件，返回文件字节数
+pub(crate) async fn write_snapshot(
+    path: &Path,
+    state: &State,
+    actor: Option<&str>,
+    include_plugin_states: bool,
+) -> ForgeResult<u64> {
+    let mut serialized = state.serialize().await?;
+    if !include_plugin_states {
+        serialized.state_fields.clear();
+    }
+    let bytes = encode(
+        schema_fingerprint(&state.schema()),
+        state.version,
+        actor,
+        &serialized,
+    )?;
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || write_atomic(&path, &bytes))
+        .await
+        .map_err(|e| {
+            error_utils::runtime_error(format!("快照写入任务异常: {e}"))
+        })?
+}
+
+/// 写入快照并广播开始、成功或失败事件
+pub(crate) async fn take_snapshot(
+    event_bus: &EventBus<Event>,
+    path: PathBuf,
+    state: &State,
+    actor: Option<&str>,
+    include_plugin_states: bool,
+) -> ForgeResult<SnapshotInfo> {
+    let start = Instant::now();
+    let _ = event_bus
+        .broadcast(Event::SnapshotStarted { path: path.clone() })
+        .await;
+    match write_snapshot(&path, state, actor, include_plugin_states).await {
+        Ok(size) => {
+            let duration = start.elapsed();
+            let _ = event_bus
+                .broadcast(Event::SnapshotSucceeded {
+                    path: path.clone(),
+                    duration,
+                    size,
+                })
+                .await;
+            Ok(SnapshotInfo { path, size, duration })
+        },
+        Err(e) => {
+            let _ = event_bus
+                .broadcast(Event::SnapshotFailed {
+                    path,
+                    duration: start.elapsed(),
+                    error: e.to_string(),
+                })
+                .await;
+            Err(e)
+        },
+    }
+}
+
+fn encode(
+    schema_fingerprint: String,
+    state_version: u64,
+    actor: Option<&str>,
+    state: &StateSerialize,
+) -> ForgeResult<Vec<u8>> {
+    let mut keys: Vec<&String> = state.state_fields.keys().collect();
+    keys.sort();
+    let header = SnapshotHeader {
+        version: SNAPSHOT_FORMAT_VERSION,
+        created_at: unix_millis(),
+        schema_fingerprint,
+        state_version,
+        doc_len: state.node_pool.len() as u64,
+        plugin_states: keys
+            .iter()
+            .map(|key| ((*key).clone(), state.state_fields[*key].len() as u64))
+            .collect(),
+        actor: actor.map(str::to_string),
+    };
+    let header_bytes = serde_json::to_vec(&header).map_err(|e| {
+        error_utils::storage_error(format!("快照头部序列化失败: {e}"))
+    })?;
+    let data_len = state.node_pool.len()
+        + state.state_fields.values().map(Vec::len).sum::<usize>();
+    let mut bytes =
+        Vec::with_capacity(PREFIX_LEN + header_bytes.len() + data_len);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&(header_bytes.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&header_bytes);
+    bytes.extend_from_slice(&state.node_pool);
+    for key in keys {
+        bytes.extend_from_slice(&state.state_fields[key]);
+    }
+    Ok(bytes)
+}
+
+fn invalid(reason: impl std::fmt::Display) -> crate::error::ForgeError {
+    error_utils::storage_error(format!("无效的快照文件: {reason}"))
+}
+
+fn header_len(bytes: &[u8]) -> ForgeResult<usize> {
+    if bytes.len() < PREFIX_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(invalid("魔数不匹配"));
+    }
+    let mut len = [0u8; 4];
+    len.copy_from_slice(&bytes[MAGIC.len()..PREFIX_LEN]);
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_HEADER_LEN {
+        return Err(invalid("头部长度超出上限"));
+    }
+    Ok(len)
+}
+
+/// 解码头部，返回头部与数据部分的起始偏移
+fn decode_header(bytes: &[u8]) -> ForgeResult<(SnapshotHeader, usize)> {
+    let end = PREFIX_LEN + header_len(bytes)?;
+    if bytes.len() < end {
+        return Err(invalid("头部不完整"));
+    }
+    let header: SnapshotHeader =
+        serde_json::from_slice(&bytes[PREFIX_LEN..end]).map_err(invalid)?;
+    if header.version != SNAPSHOT_FORMAT_VERSION {
+        return Err(invalid(format!("不支持的格式版本 {}", header.version)));
+    }
+    Ok((header, end))
+}
+
+fn decode(bytes: &[u8]) -> ForgeResult<SnapshotData> {
+    let (header, mut offset) = decode_header(bytes)?;
+    let expected = header
+        .plugin_states
+        .iter()
+        .try_fold(header.doc_len, |total, (_, len)| total.checked_add(*len));
+    if expected != Some((bytes.len() - offset) as u64) {
+        return Err(invalid("数据长度与头部不一致"));
+    }
+    let mut take = |len: u64| {
+        let start = offset;
+        offset += len as usize;
+        bytes[start..offset].to_vec()
+    };
+    let node_pool = take(header.doc_len);
+    let state_fields = header
+        .plugin_states
+        .iter()
+        .map(|(key, len)| (key.clone(), take(*len)))
+        .collect();
+    Ok(SnapshotData {
+        header,
+        state: StateSerialize { state_fields, node_pool },
+    })
+}
+
+/// 先写同目录下的临时文件并落盘，再重命名为目标文件
+fn write_atomic(
+    path: &Path,
+    bytes: &[u8],
+) -> ForgeResult<u64> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| error_utils::storage_error("快照路径缺少文件名"))?;
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = (|| {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(error_utils::storage_error(format!(
+            "写入快照 {} 失败: {e}",
+            path.display()
+        )));
+    }
+    Ok(bytes.len() as u64)
+}
+
+/// 删除目录中超出保留数量的旧快照
+fn prune_snapshots(
+    dir: &Path,
+    keep: usize,
+) {
+    for (path, _) in list_snapshots(dir).into_iter().skip(keep) {
+        if let Err(e) = fs::remove_file(&path) {
+            warn!("删除旧快照 {} 失败: {}", path.display(), e);
+        }
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// 最新状态及其最后一次提交的发起者
+struct Latest {
+    state: Arc<State>,
+    actor: Option<String>,
+}
+
+/// 调度器与运行时共享的状态
+struct SchedulerShared {
+    /// 最新状态
+    latest: ArcSwap<Latest>,
+    /// 自上次快照以来的状态更新次数
+    pending: AtomicU64,
+    origin: Instant,
+    /// 最近一次状态更新时间（相对 `origin` 的毫秒数）
+    last_activity: AtomicU64,
+}
+
+impl SchedulerShared {
+    fn idle_for(&self) -> Duration {
+        let last =
+            Duration::from_millis(self.last_activity.load(Ordering::Relaxed));
+        self.origin.elapsed().saturating_sub(last)
+    }
+}
+
+/// 后台快照调度器
+///
+/// 运行时每次更新状态时调用 [`SnapshotScheduler::record`]，只做几次原子写入；
+/// 后台任务按 [`SnapshotConfig`] 检查触发条件，空闲时为最新状态写入快照。
+/// 调度器被丢弃时后台任务随之停止。
+pub(crate) struct SnapshotScheduler {
+    shared: Arc<SchedulerShared>,
+    token: CancellationToken,
+}
+
+impl SnapshotScheduler {
+    /// 启动后台任务，必须在 tokio 运行时中调用
+    pub(crate) fn start(
+        config: SnapshotConfig,
+        state: Arc<State>,
+        event_bus: EventBus<Event>,
+    ) -> Self {
+        let shared = Arc::new(SchedulerShared {
+            latest: ArcSwap::from_pointee(Latest { state, actor: None }),
+            pending: AtomicU64::new(0),
+            origin: Instant::now(),
+            last_activity: AtomicU64::new(0),
+        });
+        let token = CancellationToken::new();
+        tokio::spawn(run_scheduler(
+            config,
+            shared.clone(),
+            event_bus,
+            token.clone(),
+        ));
+        Self { shared, token }
+    }
+
+    /// 记录一次状态更新
+    pub(crate) fn record(
+        &self,
+        state: &Arc<State>,
+        actor: Option<&str>,
+    ) {
+        self.shared.latest.store(Arc::new(Latest {
+            state: state.clone(),
+            actor: actor.map(str::to_string),
+        }));
+        self.shared.last_activity.store(
+            self.shared.origin.elapsed().as_millis() as u64,
+            Ordering::Relaxed,
+        );
+        self.shared.pending.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 停止后台任务，正在写入的快照会继续完成
+    pub(crate) fn stop(&self) {
+        self.token.cancel();
+    }
+}
+
+impl Drop for SnapshotScheduler {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+async fn run_scheduler(
+    config: SnapshotConfig,
+    shared: Arc<SchedulerShared>,
+    event_bus: EventBus<Event>,
+    token: CancellationToken,
+) {
+    let poll = config
+        .idle_after
+        .clamp(Duration::from_millis(10), Duration::from_secs(1));
+    let mut ticker = tokio::time::interval(poll);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_snapshot = Instant::now();
+    let mut retry_at: Option<Instant> = None;
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = ticker.tick() => {},
+        }
+        let pending = shared.pending.load(Ordering::Relaxed);
+        if pending == 0
+            || shared.idle_for() < config.idle_after
+            || retry_at.is_some_and(|at| Instant::now() < at)
+        {
+            continue;
+        }
+        let due = config
+            .interval
+            .is_some_and(|interval| last_snapshot.elapsed() >= interval)
+            || config.every_transactions.is_some_and(|n| pending >= n);
+        if !due {
+            continue;
+        }
+
+        let latest = shared.latest.load_full();
+        let path = config.directory.join(format!(
+            "snapshot-{}-{}.{SNAPSHOT_EXTENSION}",
+            unix_millis(),
+            latest.state.version
+        ));
+        match take_snapshot(
+            &event_bus,
+            path,
+            &latest.state,
+            latest.actor.as_deref(),
+            config.include_plugin_states,
+        )
+        .await
+        {
+            Ok(info) => {
+                debug!(
+                    "后台快照完成: {} ({} 字节，耗时 {:?})",
+                    info.path.display(),
+                    info.size,
+                    info.duration
+                );
+                shared.pending.fetch_sub(pending, Ordering::Relaxed);
+                last_snapshot = Instant::now();
+                retry_at = None;
+                if config.keep > 0 {
+                    let dir = config.directory.clone();
+                    let keep = config.keep;
+                    let _ = tokio::task::spawn_blocking(move || {
+                        prune_snapshots(&dir, keep)
+                    })
+                    .await;
+                }
+            },
+            Err(e) => {
+                warn!("后台快照失败: {}", e);
+                retry_at = Some(Instant::now() + poll.max(config.idle_after));
+            },
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::{sync::Arc, time::Duration};
 
 use crate::{
+    config::ProcessorConfig,
     runtime::sync_processor::{
         ProcessorError, SyncProcessor, TaskProcessor, TaskResult,
     },
@@ -46,6 +47,16 @@ impl FlowEngine {
         Ok(Self { processor: Arc::new(processor) })
     }
 
+    /// 按处理器配置创建，同步处理器只使用重试相关的字段
+    pub fn with_config(config: &ProcessorConfig) -> ForgeResult<Self> {
+        let processor = SyncProcessor::new(
+            TransactionProcessor,
+            config.max_retries,
+            config.retry_delay,
+        );
+        Ok(Self { processor: Arc::new(processor) })
+    }
+
     pub async fn submit(
         &self,
         params: TaskParams,
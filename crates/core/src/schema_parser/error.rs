@@ -38,6 +38,50 @@ pub enum XmlSchemaError {
 
     #[error("相对路径解析错误: {0}")]
     PathResolutionError(String),
+
+    /// 带 XML 位置信息的错误，行列均从 1 开始
+    #[error("{source}（第 {line} 行第 {column} 列{}）", describe_target(.element, .attribute))]
+    Located {
+        line: usize,
+        column: usize,
+        element: Option<String>,
+        attribute: Option<String>,
+        source: Box<XmlSchemaError>,
+    },
+}
+
+impl XmlSchemaError {
+    /// 错误在 XML 中的位置 `(行, 列)`
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            XmlSchemaError::Located { line, column, .. } => {
+                Some((*line, *column))
+            },
+            _ => None,
+        }
+    }
+
+    /// 去掉位置信息后的原始错误
+    pub fn root_cause(&self) -> &XmlSchemaError {
+        match self {
+            XmlSchemaError::Located { source, .. } => source.root_cause(),
+            other => other,
+        }
+    }
+}
+
+fn describe_target(
+    element: &Option<String>,
+    attribute: &Option<String>,
+) -> String {
+    let mut target = String::new();
+    if let Some(element) = element {
+        target.push_str(&format!("，元素 <{element}>"));
+    }
+    if let Some(attribute) = attribute {
+        target.push_str(&format!("，属性 {attribute}"));
+    }
+    target
 }
 
 /// XML Schema 解析结果类型
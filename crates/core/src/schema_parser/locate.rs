@@ -0,0 +1,316 @@
+//! 带位置信息的 schema 解析
+//!
+//! 由 `quick_xml::Reader` 逐层读取元素，每个元素的文本片段再交给 serde
+//! 反序列化，因此错误直接使用读取时的位置；`<node>`、`<mark>` 的位置会保留下来，
+//! 供之后的定义校验定位重复或非法的名称。
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::Deserialize;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use super::error::{XmlSchemaError, XmlSchemaResult};
+use super::types::{
+    XmlAttr, XmlInclude, XmlMark, XmlMarks, XmlNode, XmlNodes,
+    XmlSchemaWithReferences,
+};
+
+/// 元素在 XML 文本中的字节范围
+struct ElementSpan {
+    name: String,
+    start: usize,
+    end: usize,
+}
+
+/// 根元素上的属性
+#[derive(Deserialize)]
+struct XmlRoot {
+    #[serde(rename = "@top_node")]
+    top_node: Option<String>,
+}
+
+/// 解析时记录的定义位置，按出现顺序保存 `(name, 字节偏移)`
+pub(crate) struct SchemaSpans<'a> {
+    xml: &'a str,
+    nodes: Vec<(String, usize)>,
+    marks: Vec<(String, usize)>,
+}
+
+/// 解析 schema，同时记录各定义的位置
+pub(crate) fn parse_schema(
+    xml: &str
+) -> XmlSchemaResult<(XmlSchemaWithReferences, SchemaSpans<'_>)> {
+    let mut spans = SchemaSpans { xml, nodes: Vec::new(), marks: Vec::new() };
+    let Some(root) = children(xml, None)?.into_iter().next() else {
+        return Err(XmlSchemaError::DeserializeError(
+            quick_xml::DeError::custom("缺少根元素"),
+        ));
+    };
+    let XmlRoot { top_node } = element(xml, &root)?;
+    let mut schema = XmlSchemaWithReferences {
+        top_node,
+        imports: None,
+        includes: None,
+        include_directives: Vec::new(),
+        global_attributes: None,
+        nodes: None,
+        marks: None,
+    };
+
+    for child in children(xml, Some(&root))? {
+        match child.name.as_str() {
+            "imports" => set_once(xml, &child, &mut schema.imports, "imports")?,
+            "includes" => {
+                set_once(xml, &child, &mut schema.includes, "includes")?
+            },
+            "include" => schema
+                .include_directives
+                .push(element::<XmlInclude>(xml, &child)?),
+            "global_attributes" => set_once(
+                xml,
+                &child,
+                &mut schema.global_attributes,
+                "global_attributes",
+            )?,
+            "nodes" => {
+                if schema.nodes.is_some() {
+                    return Err(duplicate(xml, &child, "nodes"));
+                }
+                let mut nodes = Vec::new();
+                for node in children(xml, Some(&child))? {
+                    if node.name == "node" {
+                        let def: XmlNode = element(xml, &node)?;
+                        spans.nodes.push((def.name.clone(), node.start));
+                        nodes.push(def);
+                    }
+                }
+                schema.nodes = Some(XmlNodes { nodes });
+            },
+            "marks" => {
+                if schema.marks.is_some() {
+                    return Err(duplicate(xml, &child, "marks"));
+                }
+                let mut marks = Vec::new();
+                for mark in children(xml, Some(&child))? {
+                    if mark.name == "mark" {
+                        let def: XmlMark = element(xml, &mark)?;
+                        spans.marks.push((def.name.clone(), mark.start));
+                        marks.push(def);
+                    }
+                }
+                schema.marks = Some(XmlMarks { marks });
+            },
+            _ => {},
+        }
+    }
+    Ok((schema, spans))
+}
+
+impl SchemaSpans<'_> {
+    /// 为定义校验的错误补充位置，无法定位时原样返回
+    pub(crate) fn locate(
+        &self,
+        err: XmlSchemaError,
+    ) -> XmlSchemaError {
+        let (tag, spans, name, nth) = match &err {
+            XmlSchemaError::InvalidNodeDefinition(_) => {
+                ("node", &self.nodes, "", 0)
+            },
+            XmlSchemaError::InvalidMarkDefinition(_) => {
+                ("mark", &self.marks, "", 0)
+            },
+            // 重复定义指向第二次出现的位置
+            XmlSchemaError::DuplicateNodeName(name) => {
+                ("node", &self.nodes, name.trim(), 1)
+            },
+            XmlSchemaError::DuplicateMarkName(name) => {
+                ("mark", &self.marks, name.trim(), 1)
+            },
+            _ => return err,
+        };
+        let found = spans.iter().filter(|(n, _)| n.trim() == name).nth(nth);
+        match found {
+            Some(&(_, offset)) => located(
+                self.xml,
+                offset,
+                Some(tag.to_string()),
+                Some("name".to_string()),
+                err,
+            ),
+            None => err,
+        }
+    }
+}
+
+/// 同名分组元素只允许出现一次，与 serde 的结构体字段规则一致
+fn set_once<T: DeserializeOwned>(
+    xml: &str,
+    span: &ElementSpan,
+    slot: &mut Option<T>,
+    field: &'static str,
+) -> XmlSchemaResult<()> {
+    if slot.is_some() {
+        return Err(duplicate(xml, span, field));
+    }
+    *slot = Some(element(xml, span)?);
+    Ok(())
+}
+
+fn duplicate(
+    xml: &str,
+    span: &ElementSpan,
+    field: &'static str,
+) -> XmlSchemaError {
+    located(
+        xml,
+        span.start,
+        Some(span.name.clone()),
+        None,
+        quick_xml::DeError::duplicate_field(field).into(),
+    )
+}
+
+/// 反序列化单个元素，错误定位到该元素；嵌套的 `<attr>` 出错时定位到具体的 `<attr>`
+fn element<T: DeserializeOwned>(
+    xml: &str,
+    span: &ElementSpan,
+) -> XmlSchemaResult<T> {
+    quick_xml::de::from_str(&xml[span.start..span.end]).map_err(|err| {
+        if let Some(err) = attr_error(xml, span) {
+            return err;
+        }
+        let attribute = missing_attribute(&err.to_string()).map(str::to_string);
+        located(xml, span.start, Some(span.name.clone()), attribute, err.into())
+    })
+}
+
+fn attr_error(
+    xml: &str,
+    span: &ElementSpan,
+) -> Option<XmlSchemaError> {
+    for child in children(xml, Some(span)).ok()? {
+        let err = if child.name == "attr" {
+            element::<XmlAttr>(xml, &child).err()
+        } else {
+            attr_error(xml, &child)
+        };
+        if err.is_some() {
+            return err;
+        }
+    }
+    None
+}
+
+/// `parent` 的直接子元素，`parent` 为 `None` 时返回顶层元素
+///
+/// 读取时校验标签配对，语法错误定位到出错处及当时所在的元素。
+fn children(
+    xml: &str,
+    parent: Option<&ElementSpan>,
+) -> XmlSchemaResult<Vec<ElementSpan>> {
+    let (base, fragment) = match parent {
+        Some(parent) => (parent.start, &xml[parent.start..parent.end]),
+        None => (0, xml),
+    };
+    let level = usize::from(parent.is_some());
+    let mut reader = Reader::from_str(fragment);
+    let mut open: Vec<(String, usize)> = Vec::new();
+    let mut found = Vec::new();
+    loop {
+        let offset = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(start)) => {
+                open.push((local_name(&start), offset));
+            },
+            Ok(Event::Empty(start)) => {
+                if open.len() == level {
+                    found.push(ElementSpan {
+                        name: local_name(&start),
+                        start: base + offset,
+                        end: base + reader.buffer_position() as usize,
+                    });
+                }
+            },
+            Ok(Event::End(_)) => {
+                if let Some((name, start)) = open.pop()
+                    && open.len() == level
+                {
+                    found.push(ElementSpan {
+                        name,
+                        start: base + start,
+                        end: base + reader.buffer_position() as usize,
+                    });
+                }
+            },
+            Ok(Event::Eof) => {
+                if let Some((name, _)) = open.pop() {
+                    let err = quick_xml::Error::IllFormed(
+                        quick_xml::errors::IllFormedError::MissingEndTag(
+                            name.clone(),
+                        ),
+                    );
+                    return Err(located(
+                        xml,
+                        base + fragment.len(),
+                        Some(name),
+                        None,
+                        err.into(),
+                    ));
+                }
+                return Ok(found);
+            },
+            Ok(_) => {},
+            Err(err) => {
+                return Err(located(
+                    xml,
+                    base + reader.error_position() as usize,
+                    open.pop().map(|(name, _)| name),
+                    None,
+                    err.into(),
+                ));
+            },
+        }
+    }
+}
+
+fn local_name(start: &BytesStart) -> String {
+    String::from_utf8_lossy(start.local_name().as_ref()).into_owned()
+}
+
+/// 字节偏移转换为从 1 开始的 (行, 列)，列按字符计
+fn line_column(
+    xml: &str,
+    offset: usize,
+) -> (usize, usize) {
+    let mut offset = offset.min(xml.len());
+    while !xml.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &xml[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn located(
+    xml: &str,
+    offset: usize,
+    element: Option<String>,
+    attribute: Option<String>,
+    source: XmlSchemaError,
+) -> XmlSchemaError {
+    let (line, column) = line_column(xml, offset);
+    XmlSchemaError::Located {
+        line,
+        column,
+        element,
+        attribute,
+        source: Box::new(source),
+    }
+}
+
+/// 从 serde 的 "missing field `@name`" 信息中取出缺失的属性名
+fn missing_attribute(message: &str) -> Option<&str> {
+    let rest = message.split("missing field `").nth(1)?;
+    rest.split('`').next()?.strip_prefix('@')
+}
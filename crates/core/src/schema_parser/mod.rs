@@ -3,6 +3,7 @@
 //! 拆分自历史上的大型 `schema_parser.rs`，模块化为：
 //! - `error`: 错误类型与结果别名
 //! - `types`: XML 映射结构与自定义反序列化器
+//! - `locate`: 为解析错误补充 XML 行列位置
 //! - `parser`: 解析器与多文件解析逻辑
 //! - `serializer`: 从 `SchemaSpec`/`Extensions` 生成 XML
 //!
@@ -12,10 +13,13 @@
 //! - `XmlSchemaError`、`XmlSchemaResult`
 
 pub mod error;
+mod locate;
 pub mod parser;
 pub mod serializer;
 pub mod types;
+pub mod xsd;
 
 pub use error::{XmlSchemaError, XmlSchemaResult};
 pub use parser::{MultiFileParseContext, XmlSchemaParser};
 pub use serializer::XmlSchemaSerializer;
+pub use xsd::SchemaXsdExt;
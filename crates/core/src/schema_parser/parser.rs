@@ -15,6 +15,7 @@ use crate::{
 };
 
 use super::error::{XmlSchemaError, XmlSchemaResult};
+use super::locate::parse_schema;
 use super::types::{
     XmlAttr, XmlAttrs, XmlGlobalAttribute, XmlMark, XmlMarks, XmlNode,
     XmlNodes, XmlSchema, XmlSchemaWithReferences,
@@ -27,15 +28,97 @@ pub struct XmlSchemaParser;
 #[derive(Debug, Clone)]
 pub struct MultiFileParseContext {
     pub base_path: std::path::PathBuf,
+    /// 已解析（含正在解析）的文件
     pub parsed_files: std::collections::HashSet<std::path::PathBuf>,
+    /// 正在解析的文件链，用于检测循环引用
+    pub include_stack: Vec<std::path::PathBuf>,
     pub max_depth: usize,
     pub current_depth: usize,
 }
 
+impl MultiFileParseContext {
+    pub const DEFAULT_MAX_DEPTH: usize = 10;
+
+    pub fn new(base_path: impl AsRef<std::path::Path>) -> Self {
+        Self {
+            base_path: base_path.as_ref().to_path_buf(),
+            parsed_files: std::collections::HashSet::new(),
+            include_stack: Vec::new(),
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            current_depth: 0,
+        }
+    }
+
+    pub fn with_max_depth(
+        mut self,
+        max_depth: usize,
+    ) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// 相对路径基于 `base_path` 解析
+    fn resolve(
+        &self,
+        file_path: &str,
+    ) -> std::path::PathBuf {
+        if std::path::Path::new(file_path).is_absolute() {
+            std::path::PathBuf::from(file_path)
+        } else {
+            self.base_path.join(file_path)
+        }
+    }
+
+    /// 开始解析文件，返回规范化路径；文件已合并过时返回 `None`
+    fn enter(
+        &mut self,
+        file_path: &std::path::Path,
+    ) -> XmlSchemaResult<Option<std::path::PathBuf>> {
+        if self.current_depth >= self.max_depth {
+            return Err(XmlSchemaError::CircularReference(format!(
+                "解析深度超过限制: {}",
+                self.max_depth
+            )));
+        }
+
+        let canonical_path = file_path.canonicalize().map_err(|e| {
+            XmlSchemaError::FileNotFound(format!(
+                "无法解析文件路径 {file_path:?}: {e}"
+            ))
+        })?;
+
+        if self.include_stack.contains(&canonical_path) {
+            let chain: Vec<String> = self
+                .include_stack
+                .iter()
+                .chain(std::iter::once(&canonical_path))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(XmlSchemaError::CircularReference(format!(
+                "检测到循环引用: {}",
+                chain.join(" -> ")
+            )));
+        }
+        if !self.parsed_files.insert(canonical_path.clone()) {
+            return Ok(None);
+        }
+
+        self.include_stack.push(canonical_path.clone());
+        self.current_depth += 1;
+        Ok(Some(canonical_path))
+    }
+
+    fn leave(&mut self) {
+        self.include_stack.pop();
+        self.current_depth -= 1;
+    }
+}
+
 impl XmlSchemaParser {
     pub fn parse_from_str(xml_content: &str) -> XmlSchemaResult<SchemaSpec> {
-        let xml_schema: XmlSchema = quick_xml::de::from_str(xml_content)?;
-        Self::convert_to_schema_spec(xml_schema)
+        let (xml_schema, spans) = parse_schema(xml_content)?;
+        Self::convert_to_schema_spec(xml_schema.into())
+            .map_err(|e| spans.locate(e))
     }
 
     pub fn parse_from_file(file_path: &str) -> XmlSchemaResult<SchemaSpec> {
@@ -48,16 +131,9 @@ impl XmlSchemaParser {
     pub fn parse_to_extensions(
         xml_content: &str
     ) -> XmlSchemaResult<Vec<Extensions>> {
-        if let Ok(xml_schema_with_refs) =
-            quick_xml::de::from_str::<XmlSchemaWithReferences>(xml_content)
-        {
-            Self::convert_xml_schema_with_refs_to_extensions(
-                xml_schema_with_refs,
-            )
-        } else {
-            let xml_schema: XmlSchema = quick_xml::de::from_str(xml_content)?;
-            Self::convert_to_extensions(xml_schema)
-        }
+        let (xml_schema, spans) = parse_schema(xml_content)?;
+        Self::convert_xml_schema_with_refs_to_extensions(xml_schema)
+            .map_err(|e| spans.locate(e))
     }
 
     pub fn parse_extensions_from_file(
@@ -70,61 +146,73 @@ impl XmlSchemaParser {
     }
 
     pub fn parse_multi_file(file_path: &str) -> XmlSchemaResult<SchemaSpec> {
-        let root_path =
-            std::path::Path::new(file_path).canonicalize().map_err(|e| {
-                XmlSchemaError::FileNotFound(format!(
-                    "无法找到文件 {file_path}: {e}"
-                ))
-            })?;
-
-        let mut context = MultiFileParseContext {
-            base_path: root_path
-                .parent()
-                .unwrap_or_else(|| std::path::Path::new("."))
-                .to_path_buf(),
-            parsed_files: std::collections::HashSet::new(),
-            max_depth: 10,
-            current_depth: 0,
-        };
-
+        let root_path = Self::canonicalize_root(file_path)?;
+        let mut context = MultiFileParseContext::new(
+            root_path.parent().unwrap_or_else(|| std::path::Path::new(".")),
+        );
         Self::parse_file_with_context_new(&root_path, &mut context)
     }
 
-    fn parse_file_with_context_new(
-        file_path: &std::path::Path,
-        context: &mut MultiFileParseContext,
+    /// 解析内存中的 schema，其中的 include/import 相对 `base_path` 解析
+    pub fn parse_from_str_with_base(
+        xml_content: &str,
+        base_path: impl AsRef<std::path::Path>,
     ) -> XmlSchemaResult<SchemaSpec> {
-        if context.current_depth >= context.max_depth {
-            return Err(XmlSchemaError::CircularReference(format!(
-                "解析深度超过限制: {}",
-                context.max_depth
-            )));
-        }
+        let mut context = MultiFileParseContext::new(base_path);
+        Self::parse_spec_with_refs(xml_content, &mut context)
+    }
 
-        let canonical_path = file_path.canonicalize().map_err(|e| {
+    fn canonicalize_root(
+        file_path: &str
+    ) -> XmlSchemaResult<std::path::PathBuf> {
+        std::path::Path::new(file_path).canonicalize().map_err(|e| {
             XmlSchemaError::FileNotFound(format!(
-                "无法解析文件路径 {file_path:?}: {e}"
+                "无法找到文件 {file_path}: {e}"
             ))
-        })?;
+        })
+    }
 
-        if context.parsed_files.contains(&canonical_path) {
-            return Err(XmlSchemaError::CircularReference(format!(
-                "检测到循环引用: {canonical_path:?}"
-            )));
-        }
+    fn read_schema_file(
+        canonical_path: &std::path::Path
+    ) -> XmlSchemaResult<String> {
+        std::fs::read_to_string(canonical_path).map_err(|e| {
+            XmlSchemaError::FileNotFound(format!(
+                "无法读取文件 {canonical_path:?}: {e}"
+            ))
+        })
+    }
 
-        context.parsed_files.insert(canonical_path.clone());
-        context.current_depth += 1;
+    /// 解析单个文件；已合并过的文件返回空结果，避免菱形引用重复合并
+    fn parse_file_with_context_new(
+        file_path: &std::path::Path,
+        context: &mut MultiFileParseContext,
+    ) -> XmlSchemaResult<SchemaSpec> {
+        let Some(canonical_path) = context.enter(file_path)? else {
+            return Ok(SchemaSpec {
+                nodes: HashMap::new(),
+                marks: HashMap::new(),
+                top_node: None,
+            });
+        };
+        let xml_content = Self::read_schema_file(&canonical_path)?;
 
-        let xml_content =
-            std::fs::read_to_string(&canonical_path).map_err(|e| {
-                XmlSchemaError::FileNotFound(format!(
-                    "无法读取文件 {canonical_path:?}: {e}"
-                ))
-            })?;
+        let old_base_path = context.base_path.clone();
+        if let Some(parent) = canonical_path.parent() {
+            context.base_path = parent.to_path_buf();
+        }
+        let spec = Self::parse_spec_with_refs(&xml_content, context)?;
+        context.base_path = old_base_path;
+
+        context.leave();
+        Ok(spec)
+    }
 
-        let xml_schema: XmlSchemaWithReferences =
-            quick_xml::de::from_str(&xml_content)?;
+    /// 按 import、include、当前文件的顺序合并，引用相对 `context.base_path`
+    fn parse_spec_with_refs(
+        xml_content: &str,
+        context: &mut MultiFileParseContext,
+    ) -> XmlSchemaResult<SchemaSpec> {
+        let (xml_schema, spans) = parse_schema(xml_content)?;
 
         let mut merged_spec = SchemaSpec {
             nodes: HashMap::new(),
@@ -132,50 +220,30 @@ impl XmlSchemaParser {
             top_node: xml_schema.top_node.clone(),
         };
 
-        let old_base_path = context.base_path.clone();
-        if let Some(parent) = canonical_path.parent() {
-            context.base_path = parent.to_path_buf();
+        for src in xml_schema.import_sources() {
+            let import_path =
+                Self::resolve_relative_path(&context.base_path, src)?;
+            let imported_spec =
+                Self::parse_file_with_context_new(&import_path, context)?;
+            Self::merge_schema_spec(&mut merged_spec, imported_spec, false)?;
         }
 
-        if let Some(imports) = xml_schema.imports {
-            for import in imports.imports {
-                let import_path = Self::resolve_relative_path(
-                    &context.base_path,
-                    &import.src,
-                )?;
-                let imported_spec =
-                    Self::parse_file_with_context_new(&import_path, context)?;
-                Self::merge_schema_spec(
-                    &mut merged_spec,
-                    imported_spec,
-                    false,
-                )?;
-            }
-        }
-
-        if let Some(includes) = xml_schema.includes {
-            for include in includes.includes {
-                let include_path = Self::resolve_relative_path(
-                    &context.base_path,
-                    &include.src,
-                )?;
-                let included_spec =
-                    Self::parse_file_with_context_new(&include_path, context)?;
-                Self::merge_schema_spec(&mut merged_spec, included_spec, true)?;
-            }
+        for src in xml_schema.include_sources() {
+            let include_path =
+                Self::resolve_relative_path(&context.base_path, src)?;
+            let included_spec =
+                Self::parse_file_with_context_new(&include_path, context)?;
+            Self::merge_schema_spec(&mut merged_spec, included_spec, true)?;
         }
 
-        context.base_path = old_base_path;
-
         let current_spec = Self::convert_xml_schema_to_spec(XmlSchema {
             top_node: xml_schema.top_node,
             nodes: xml_schema.nodes,
             marks: xml_schema.marks,
-        })?;
+        })
+        .map_err(|e| spans.locate(e))?;
 
         Self::merge_schema_spec(&mut merged_spec, current_spec, true)?;
-
-        context.current_depth -= 1;
         Ok(merged_spec)
     }
 
@@ -199,23 +267,10 @@ impl XmlSchemaParser {
     pub fn parse_multi_file_to_extensions(
         file_path: &str
     ) -> XmlSchemaResult<Vec<Extensions>> {
-        let root_path =
-            std::path::Path::new(file_path).canonicalize().map_err(|e| {
-                XmlSchemaError::FileNotFound(format!(
-                    "无法找到文件 {file_path}: {e}"
-                ))
-            })?;
-
-        let mut context = MultiFileParseContext {
-            base_path: root_path
-                .parent()
-                .unwrap_or_else(|| std::path::Path::new("."))
-                .to_path_buf(),
-            parsed_files: std::collections::HashSet::new(),
-            max_depth: 10,
-            current_depth: 0,
-        };
-
+        let root_path = Self::canonicalize_root(file_path)?;
+        let mut context = MultiFileParseContext::new(
+            root_path.parent().unwrap_or_else(|| std::path::Path::new(".")),
+        );
         Self::parse_file_to_extensions_with_context_new(
             &root_path,
             &mut context,
@@ -226,37 +281,12 @@ impl XmlSchemaParser {
         file_path: &std::path::Path,
         context: &mut MultiFileParseContext,
     ) -> XmlSchemaResult<Vec<Extensions>> {
-        if context.current_depth >= context.max_depth {
-            return Err(XmlSchemaError::CircularReference(format!(
-                "解析深度超过限制: {}",
-                context.max_depth
-            )));
-        }
-
-        let canonical_path = file_path.canonicalize().map_err(|e| {
-            XmlSchemaError::FileNotFound(format!(
-                "无法解析文件路径 {file_path:?}: {e}"
-            ))
-        })?;
-
-        if context.parsed_files.contains(&canonical_path) {
-            return Err(XmlSchemaError::CircularReference(format!(
-                "检测到循环引用: {canonical_path:?}"
-            )));
-        }
-
-        context.parsed_files.insert(canonical_path.clone());
-        context.current_depth += 1;
-
-        let xml_content =
-            std::fs::read_to_string(&canonical_path).map_err(|e| {
-                XmlSchemaError::FileNotFound(format!(
-                    "无法读取文件 {canonical_path:?}: {e}"
-                ))
-            })?;
+        let Some(canonical_path) = context.enter(file_path)? else {
+            return Ok(Vec::new());
+        };
+        let xml_content = Self::read_schema_file(&canonical_path)?;
 
-        let xml_schema: XmlSchemaWithReferences =
-            quick_xml::de::from_str(&xml_content)?;
+        let (xml_schema, spans) = parse_schema(&xml_content)?;
 
         let mut all_extensions = Vec::new();
 
@@ -265,34 +295,14 @@ impl XmlSchemaParser {
             context.base_path = parent.to_path_buf();
         }
 
-        if let Some(imports) = &xml_schema.imports {
-            for import in &imports.imports {
-                let import_path = Self::resolve_relative_path(
-                    &context.base_path,
-                    &import.src,
-                )?;
-                let imported_extensions =
-                    Self::parse_file_to_extensions_with_context_new(
-                        &import_path,
-                        context,
-                    )?;
-                all_extensions.extend(imported_extensions);
-            }
-        }
-
-        if let Some(includes) = &xml_schema.includes {
-            for include in &includes.includes {
-                let include_path = Self::resolve_relative_path(
-                    &context.base_path,
-                    &include.src,
-                )?;
-                let included_extensions =
-                    Self::parse_file_to_extensions_with_context_new(
-                        &include_path,
-                        context,
-                    )?;
-                all_extensions.extend(included_extensions);
-            }
+        for src in
+            xml_schema.import_sources().chain(xml_schema.include_sources())
+        {
+            let path = Self::resolve_relative_path(&context.base_path, src)?;
+            let extensions = Self::parse_file_to_extensions_with_context_new(
+                &path, context,
+            )?;
+            all_extensions.extend(extensions);
         }
 
         context.base_path = old_base_path;
@@ -302,7 +312,8 @@ impl XmlSchemaParser {
             nodes: xml_schema.nodes,
             marks: xml_schema.marks,
         };
-        let current_extensions = Self::convert_to_extensions(current_schema)?;
+        let current_extensions = Self::convert_to_extensions(current_schema)
+            .map_err(|e| spans.locate(e))?;
         all_extensions.extend(current_extensions);
 
         if let Some(xml_global_attrs) = &xml_schema.global_attributes {
@@ -317,191 +328,26 @@ impl XmlSchemaParser {
             all_extensions.push(Extensions::E(extension));
         }
 
-        context.current_depth -= 1;
+        context.leave();
         Ok(all_extensions)
     }
 
+    /// 解析文件为扩展列表，相对路径基于 `context.base_path`
     pub fn parse_file_to_extensions_with_context(
         file_path: &str,
         context: &mut MultiFileParseContext,
     ) -> XmlSchemaResult<Vec<Extensions>> {
-        if context.current_depth >= context.max_depth {
-            return Err(XmlSchemaError::CircularReference(format!(
-                "解析深度超过限制: {}",
-                context.max_depth
-            )));
-        }
-
-        let file_path_buf = if std::path::Path::new(file_path).is_absolute() {
-            std::path::PathBuf::from(file_path)
-        } else {
-            context.base_path.join(file_path)
-        };
-
-        let canonical_path = file_path_buf.canonicalize().map_err(|e| {
-            XmlSchemaError::FileNotFound(format!(
-                "无法解析文件路径 {file_path}: {e}"
-            ))
-        })?;
-
-        if context.parsed_files.contains(&canonical_path) {
-            return Err(XmlSchemaError::CircularReference(format!(
-                "检测到循环引用: {canonical_path:?}"
-            )));
-        }
-
-        context.parsed_files.insert(canonical_path.clone());
-        context.current_depth += 1;
-
-        let xml_content =
-            std::fs::read_to_string(&canonical_path).map_err(|e| {
-                XmlSchemaError::FileNotFound(format!(
-                    "无法读取文件 {canonical_path:?}: {e}"
-                ))
-            })?;
-
-        let xml_schema: XmlSchemaWithReferences =
-            quick_xml::de::from_str(&xml_content)?;
-
-        let mut all_extensions = Vec::new();
-
-        let old_base_path = context.base_path.clone();
-        if let Some(parent) = canonical_path.parent() {
-            context.base_path = parent.to_path_buf();
-        }
-
-        if let Some(imports) = &xml_schema.imports {
-            for import in &imports.imports {
-                let imported_extensions =
-                    Self::parse_file_to_extensions_with_context(
-                        &import.src,
-                        context,
-                    )?;
-                all_extensions.extend(imported_extensions);
-            }
-        }
-
-        if let Some(includes) = &xml_schema.includes {
-            for include in &includes.includes {
-                let included_extensions =
-                    Self::parse_file_to_extensions_with_context(
-                        &include.src,
-                        context,
-                    )?;
-                all_extensions.extend(included_extensions);
-            }
-        }
-
-        context.base_path = old_base_path;
-
-        let current_schema = XmlSchema {
-            top_node: xml_schema.top_node,
-            nodes: xml_schema.nodes,
-            marks: xml_schema.marks,
-        };
-        let current_extensions = Self::convert_to_extensions(current_schema)?;
-        all_extensions.extend(current_extensions);
-
-        if let Some(xml_global_attrs) = &xml_schema.global_attributes {
-            let mut extension = Extension::new();
-            for xml_global_attr in &xml_global_attrs.global_attributes {
-                let global_attr_item =
-                    Self::convert_xml_global_attribute_to_item(
-                        xml_global_attr.clone(),
-                    )?;
-                extension.add_global_attribute(global_attr_item);
-            }
-            all_extensions.push(Extensions::E(extension));
-        }
-
-        context.current_depth -= 1;
-        Ok(all_extensions)
+        let path = context.resolve(file_path);
+        Self::parse_file_to_extensions_with_context_new(&path, context)
     }
 
+    /// 解析文件为 schema 规范，相对路径基于 `context.base_path`
     pub fn parse_file_with_context(
         file_path: &str,
         context: &mut MultiFileParseContext,
     ) -> XmlSchemaResult<SchemaSpec> {
-        if context.current_depth >= context.max_depth {
-            return Err(XmlSchemaError::CircularReference(format!(
-                "解析深度超过限制: {}",
-                context.max_depth
-            )));
-        }
-
-        let file_path_buf = if std::path::Path::new(file_path).is_absolute() {
-            std::path::PathBuf::from(file_path)
-        } else {
-            context.base_path.join(file_path)
-        };
-
-        let canonical_path = file_path_buf.canonicalize().map_err(|e| {
-            XmlSchemaError::FileNotFound(format!(
-                "无法解析文件路径 {file_path}: {e}"
-            ))
-        })?;
-
-        if context.parsed_files.contains(&canonical_path) {
-            return Err(XmlSchemaError::CircularReference(format!(
-                "检测到循环引用: {canonical_path:?}"
-            )));
-        }
-
-        context.parsed_files.insert(canonical_path.clone());
-        context.current_depth += 1;
-
-        let xml_content =
-            std::fs::read_to_string(&canonical_path).map_err(|e| {
-                XmlSchemaError::FileNotFound(format!(
-                    "无法读取文件 {canonical_path:?}: {e}"
-                ))
-            })?;
-
-        let xml_schema: XmlSchemaWithReferences =
-            quick_xml::de::from_str(&xml_content)?;
-
-        let mut merged_spec = SchemaSpec {
-            nodes: HashMap::new(),
-            marks: HashMap::new(),
-            top_node: xml_schema.top_node.clone(),
-        };
-
-        let old_base_path = context.base_path.clone();
-        if let Some(parent) = canonical_path.parent() {
-            context.base_path = parent.to_path_buf();
-        }
-
-        if let Some(imports) = xml_schema.imports {
-            for import in imports.imports {
-                let imported_spec =
-                    Self::parse_file_with_context(&import.src, context)?;
-                Self::merge_schema_spec(
-                    &mut merged_spec,
-                    imported_spec,
-                    false,
-                )?;
-            }
-        }
-
-        if let Some(includes) = xml_schema.includes {
-            for include in includes.includes {
-                let included_spec =
-                    Self::parse_file_with_context(&include.src, context)?;
-                Self::merge_schema_spec(&mut merged_spec, included_spec, true)?;
-            }
-        }
-
-        context.base_path = old_base_path;
-
-        let current_spec = Self::convert_xml_schema_to_spec(XmlSchema {
-            top_node: xml_schema.top_node,
-            nodes: xml_schema.nodes,
-            marks: xml_schema.marks,
-        })?;
-        Self::merge_schema_spec(&mut merged_spec, current_spec, true)?;
-
-        context.current_depth -= 1;
-        Ok(merged_spec)
+        let path = context.resolve(file_path);
+        Self::parse_file_with_context_new(&path, context)
     }
 
     pub fn convert_to_extensions_from_spec(
@@ -519,12 +365,14 @@ impl XmlSchemaParser {
                         desc: spec.desc,
                         content: spec.content,
                         marks: spec.marks,
+                        sort_by: spec.sort_by,
                         attrs: spec.attrs.map(|attrs| XmlAttrs {
                             attrs: attrs
                                 .into_iter()
                                 .map(|(name, attr_spec)| XmlAttr {
                                     name,
                                     default: attr_spec.default,
+                                    computed: attr_spec.computed,
                                 })
                                 .collect(),
                         }),
@@ -541,12 +389,14 @@ impl XmlSchemaParser {
                         desc: spec.desc,
                         excludes: spec.excludes,
                         spanning: spec.spanning,
+                        cascade: spec.cascade,
                         attrs: spec.attrs.map(|attrs| XmlAttrs {
                             attrs: attrs
                                 .into_iter()
                                 .map(|(name, attr_spec)| XmlAttr {
                                     name,
                                     default: attr_spec.default,
+                                    computed: attr_spec.computed,
                                 })
                                 .collect(),
                         }),
@@ -645,6 +495,9 @@ impl XmlSchemaParser {
                 if let Some(spanning) = xml_mark.spanning {
                     mark.r#type.spanning = Some(spanning);
                 }
+                if let Some(cascade) = xml_mark.cascade {
+                    mark.r#type.cascade = Some(cascade);
+                }
                 if let Some(xml_attrs) = xml_mark.attrs {
                     let attrs =
                         Self::convert_xml_attrs_to_spec(xml_attrs.attrs)?;
@@ -660,9 +513,13 @@ impl XmlSchemaParser {
         Ok(extensions)
     }
 
-    fn convert_xml_schema_with_refs_to_extensions(
+    /// 将 schema 结构转换为扩展列表
+    ///
+    /// XML 与扩展清单（TOML）共用此转换与校验逻辑
+    pub(crate) fn convert_xml_schema_with_refs_to_extensions(
         xml_schema: XmlSchemaWithReferences
     ) -> XmlSchemaResult<Vec<Extensions>> {
+        Self::validate_definitions(&xml_schema)?;
         let mut extensions = Vec::new();
 
         if let Some(xml_nodes) = xml_schema.nodes {
@@ -686,6 +543,9 @@ impl XmlSchemaParser {
                         Self::convert_xml_attrs_to_spec(xml_attrs.attrs)?;
                     node.set_attrs(attrs);
                 }
+                if xml_schema.top_node.as_deref() == Some(&xml_node.name) {
+                    node.set_top_node();
+                }
                 extensions.push(Extensions::N(node));
             }
         }
@@ -705,6 +565,9 @@ impl XmlSchemaParser {
                 if let Some(spanning) = xml_mark.spanning {
                     mark.r#type.spanning = Some(spanning);
                 }
+                if let Some(cascade) = xml_mark.cascade {
+                    mark.r#type.cascade = Some(cascade);
+                }
                 if let Some(xml_attrs) = xml_mark.attrs {
                     let attrs =
                         Self::convert_xml_attrs_to_spec(xml_attrs.attrs)?;
@@ -732,6 +595,39 @@ impl XmlSchemaParser {
         Ok(extensions)
     }
 
+    /// 校验节点与标记定义：名称不能为空且不能重复
+    fn validate_definitions(
+        xml_schema: &XmlSchemaWithReferences
+    ) -> XmlSchemaResult<()> {
+        let mut node_names = std::collections::HashSet::new();
+        for xml_node in xml_schema.nodes.iter().flat_map(|n| &n.nodes) {
+            if xml_node.name.trim().is_empty() {
+                return Err(XmlSchemaError::InvalidNodeDefinition(
+                    "节点名称不能为空".to_string(),
+                ));
+            }
+            if !node_names.insert(xml_node.name.as_str()) {
+                return Err(XmlSchemaError::DuplicateNodeName(
+                    xml_node.name.clone(),
+                ));
+            }
+        }
+        let mut mark_names = std::collections::HashSet::new();
+        for xml_mark in xml_schema.marks.iter().flat_map(|m| &m.marks) {
+            if xml_mark.name.trim().is_empty() {
+                return Err(XmlSchemaError::InvalidMarkDefinition(
+                    "标记名称不能为空".to_string(),
+                ));
+            }
+            if !mark_names.insert(xml_mark.name.as_str()) {
+                return Err(XmlSchemaError::DuplicateMarkName(
+                    xml_mark.name.clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     fn convert_to_schema_spec(
         xml_schema: XmlSchema
     ) -> XmlSchemaResult<SchemaSpec> {
@@ -782,6 +678,7 @@ impl XmlSchemaParser {
             group: xml_node.group,
             desc: xml_node.desc,
             attrs,
+            sort_by: xml_node.sort_by,
         })
     }
 
@@ -799,6 +696,7 @@ impl XmlSchemaParser {
             excludes: xml_mark.excludes,
             group: xml_mark.group,
             spanning: xml_mark.spanning,
+            cascade: xml_mark.cascade,
             desc: xml_mark.desc,
         })
     }
@@ -810,7 +708,10 @@ impl XmlSchemaParser {
         for xml_attr in xml_attrs {
             attrs.insert(
                 xml_attr.name.clone(),
-                AttributeSpec { default: xml_attr.default },
+                AttributeSpec {
+                    computed: xml_attr.computed,
+                    ..AttributeSpec::new(xml_attr.default)
+                },
             );
         }
         Ok(attrs)
@@ -836,15 +737,29 @@ impl XmlSchemaParser {
                 .collect()
         };
 
+        let groups = xml_global_attr
+            .groups
+            .as_deref()
+            .map(|groups| {
+                groups.split_whitespace().map(|s| s.to_string()).collect()
+            })
+            .unwrap_or_default();
+
         let attributes =
             Self::convert_xml_attrs_to_spec(xml_global_attr.attrs)?;
-        Ok(GlobalAttributeItem { types, attributes })
+        Ok(GlobalAttributeItem {
+            types,
+            attributes,
+            groups,
+            ..Default::default()
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use mf_model::schema::Schema;
 
     #[test]
     fn test_parse_simple_schema() {
@@ -868,4 +783,183 @@ mod tests {
         assert!(schema_spec.nodes.contains_key("paragraph"));
         assert!(schema_spec.nodes.contains_key("text"));
     }
+
+    #[test]
+    fn test_parse_sort_by() {
+        let xml = r#"<schema top_node="chapter">
+          <nodes>
+            <node name="chapter" content="section*" sort_by="attrs.seq desc"/>
+            <node name="section">
+              <attrs><attr name="seq"/></attrs>
+            </node>
+          </nodes>
+        </schema>"#;
+
+        let spec = XmlSchemaParser::parse_from_str(xml).unwrap();
+        assert_eq!(
+            spec.nodes["chapter"].sort_by.as_deref(),
+            Some("attrs.seq desc")
+        );
+        let schema = Schema::compile(spec).unwrap();
+        let sort = schema.top_node().unwrap().sort_spec().unwrap();
+        assert_eq!(sort.key, "seq");
+        assert!(sort.descending);
+
+        let invalid = xml.replace("attrs.seq desc", "attrs.seq sideways");
+        let spec = XmlSchemaParser::parse_from_str(&invalid).unwrap();
+        assert!(Schema::compile(spec).is_err());
+    }
+
+    #[test]
+    fn test_error_reports_position() {
+        // 结束标签不匹配
+        let xml = "<schema top_node=\"doc\">\n  <nodes>\n    <node name=\"doc\">\n  </nodes>\n</schema>";
+        let err = XmlSchemaParser::parse_from_str(xml).unwrap_err();
+        assert_eq!(err.location(), Some((4, 3)));
+        assert!(err.to_string().contains("第 4 行第 3 列"));
+
+        // 缺少必填属性
+        let xml = "<schema>\n  <nodes>\n    <node name=\"doc\"/>\n    <node content=\"text*\"/>\n  </nodes>\n</schema>";
+        let err = XmlSchemaParser::parse_from_str(xml).unwrap_err();
+        assert_eq!(err.location(), Some((4, 5)));
+        match &err {
+            XmlSchemaError::Located { element, attribute, .. } => {
+                assert_eq!(element.as_deref(), Some("node"));
+                assert_eq!(attribute.as_deref(), Some("name"));
+            },
+            other => panic!("缺少位置信息: {other}"),
+        }
+
+        // 重复定义指向第二次出现的位置
+        let xml = "<schema>\n  <nodes>\n    <node name=\"doc\"/>\n      <node name=\"doc\"/>\n  </nodes>\n</schema>";
+        let Err(err) = XmlSchemaParser::parse_to_extensions(xml) else {
+            panic!("重复节点应解析失败");
+        };
+        assert_eq!(err.location(), Some((4, 7)));
+        assert!(matches!(
+            err.root_cause(),
+            XmlSchemaError::DuplicateNodeName(name) if name == "doc"
+        ));
+
+        // 嵌套的 <attr> 缺少名称时指向该 <attr>，而不是外层的 <node>
+        let xml = "<schema>\n  <nodes>\n    <node name=\"doc\">\n      <attrs>\n        <attr name=\"a\"/>\n        <attr default=\"1\"/>\n      </attrs>\n    </node>\n  </nodes>\n</schema>";
+        let err = XmlSchemaParser::parse_from_str(xml).unwrap_err();
+        assert_eq!(err.location(), Some((6, 9)));
+        match &err {
+            XmlSchemaError::Located { element, attribute, .. } => {
+                assert_eq!(element.as_deref(), Some("attr"));
+                assert_eq!(attribute.as_deref(), Some("name"));
+            },
+            other => panic!("缺少位置信息: {other}"),
+        }
+    }
+
+    #[test]
+    fn test_include_directives() {
+        let dir = std::env::temp_dir()
+            .join(format!("mf_schema_include_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("groups")).unwrap();
+        let write = |name: &str, xml: &str| {
+            std::fs::write(dir.join(name), xml).unwrap()
+        };
+        write(
+            "common.xml",
+            r#"<schema><nodes><node name="text"/></nodes></schema>"#,
+        );
+        write(
+            "groups/gcxm.xml",
+            r#"<schema>
+              <include href="../common.xml"/>
+              <nodes><node name="GCXM" group="GCXM" content="DXGC*"/></nodes>
+            </schema>"#,
+        );
+        write(
+            "groups/dxgc.xml",
+            r#"<schema>
+              <include href="../common.xml"/>
+              <nodes><node name="DXGC" group="DXGC" content="text*"/></nodes>
+            </schema>"#,
+        );
+        write(
+            "main.xml",
+            r#"<schema top_node="GCXM">
+              <include href="groups/gcxm.xml"/>
+              <include href="groups/dxgc.xml"/>
+            </schema>"#,
+        );
+
+        // 相对路径基于包含它的文件，重复包含的公共文件只合并一次
+        let root = dir.join("main.xml");
+        let spec =
+            XmlSchemaParser::parse_multi_file(root.to_str().unwrap()).unwrap();
+        assert_eq!(spec.top_node.as_deref(), Some("GCXM"));
+        assert_eq!(spec.nodes.len(), 3);
+        let extensions = XmlSchemaParser::parse_multi_file_to_extensions(
+            root.to_str().unwrap(),
+        )
+        .unwrap();
+        let nodes =
+            extensions.iter().filter(|e| matches!(e, Extensions::N(_))).count();
+        assert_eq!(nodes, 3);
+
+        let spec = XmlSchemaParser::parse_from_str_with_base(
+            r#"<schema><include href="groups/dxgc.xml"/></schema>"#,
+            &dir,
+        )
+        .unwrap();
+        assert!(spec.nodes.contains_key("DXGC"));
+
+        write(
+            "common.xml",
+            r#"<schema><include href="groups/gcxm.xml"/></schema>"#,
+        );
+        let err = XmlSchemaParser::parse_multi_file(root.to_str().unwrap())
+            .unwrap_err();
+        assert!(matches!(err, XmlSchemaError::CircularReference(_)));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_global_attribute_groups() {
+        use crate::helpers::get_schema_by_resolved_extensions::get_schema_by_resolved_extensions;
+        use crate::schema_parser::XmlSchemaSerializer;
+
+        let xml = r#"<schema top_node="doc">
+          <nodes>
+            <node name="doc" content="report+"/>
+            <node name="report" group="block report"/>
+            <node name="summary" group="report">
+              <attrs><attr name="status" default="final"/></attrs>
+            </node>
+          </nodes>
+          <global_attributes>
+            <global_attribute groups="report">
+              <attr name="status" default="draft"/>
+            </global_attribute>
+            <global_attribute types="*">
+              <attr name="locked" default="false"/>
+            </global_attribute>
+          </global_attributes>
+        </schema>"#;
+
+        let extensions = XmlSchemaParser::parse_to_extensions(xml).unwrap();
+        let schema = get_schema_by_resolved_extensions(&extensions).unwrap();
+        let factory = schema.factory();
+        let report =
+            factory.create_node("report", None, None, vec![], None).unwrap();
+        assert_eq!(report.attrs["status"], "draft");
+        assert_eq!(report.attrs["locked"], false);
+        let summary =
+            factory.create_node("summary", None, None, vec![], None).unwrap();
+        assert_eq!(summary.attrs["status"], "final");
+        let doc = factory.node_definition("doc").unwrap();
+        assert!(!doc.attrs.contains_key("status"));
+        assert!(doc.attrs.contains_key("locked"));
+
+        // 写回 XML 后分组仍然保留
+        let written =
+            XmlSchemaSerializer::extensions_to_string(&extensions, None)
+                .unwrap();
+        assert!(written.contains(r#"groups="report""#));
+    }
 }
@@ -101,6 +101,9 @@ impl XmlSchemaSerializer {
                         el.push_attribute(("spanning", "true"));
                     }
                 }
+                if spec.cascade == Some(true) {
+                    el.push_attribute(("cascade", "true"));
+                }
 
                 writer.write_event(Event::Start(el)).map_err(map_io)?;
 
@@ -237,6 +240,9 @@ impl XmlSchemaSerializer {
                         el.push_attribute(("spanning", "true"));
                     }
                 }
+                if m.r#type.cascade == Some(true) {
+                    el.push_attribute(("cascade", "true"));
+                }
                 writer.write_event(Event::Start(el)).map_err(map_io)?;
 
                 if let Some(attrs) = &m.r#type.attrs {
@@ -327,6 +333,11 @@ fn write_global_attribute(
         item.types.join(" ")
     };
     el.push_attribute(("types", types_value.as_str()));
+    let groups_value = item.groups.join(" ");
+    if !groups_value.is_empty() {
+        el.push_attribute(("groups", groups_value.as_str()));
+    }
+    // filter 与动态默认值是回调，无法写入 XML
     writer.write_event(Event::Start(el)).map_err(map_io)?;
 
     if !item.attributes.is_empty() {
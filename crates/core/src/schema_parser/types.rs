@@ -19,11 +19,47 @@ pub struct XmlSchemaWithReferences {
     pub top_node: Option<String>,
     pub imports: Option<XmlImports>,
     pub includes: Option<XmlIncludes>,
+    /// 直接写在根元素下的 `<include href="..."/>`
+    #[serde(
+        rename = "include",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub include_directives: Vec<XmlInclude>,
     pub global_attributes: Option<XmlGlobalAttributes>,
     pub nodes: Option<XmlNodes>,
     pub marks: Option<XmlMarks>,
 }
 
+impl XmlSchemaWithReferences {
+    pub fn import_sources(&self) -> impl Iterator<Item = &str> {
+        self.imports
+            .iter()
+            .flat_map(|imports| &imports.imports)
+            .map(|import| import.src.as_str())
+    }
+
+    /// `<includes>` 分组与根元素下的 `<include>` 按出现顺序合并
+    pub fn include_sources(&self) -> impl Iterator<Item = &str> {
+        self.includes
+            .iter()
+            .flat_map(|includes| &includes.includes)
+            .chain(&self.include_directives)
+            .map(|include| include.src.as_str())
+    }
+}
+
+/// 去掉引用部分，只保留当前文件的定义
+impl From<XmlSchemaWithReferences> for XmlSchema {
+    fn from(schema: XmlSchemaWithReferences) -> Self {
+        XmlSchema {
+            top_node: schema.top_node,
+            nodes: schema.nodes,
+            marks: schema.marks,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct XmlImports {
     #[serde(rename = "import")]
@@ -42,9 +78,10 @@ pub struct XmlImport {
     pub src: String,
 }
 
+/// 被包含的 schema 文件，`href` 与 `src` 等价
 #[derive(Debug, Deserialize, Serialize)]
 pub struct XmlInclude {
-    #[serde(rename = "@src")]
+    #[serde(rename = "@src", alias = "@href")]
     pub src: String,
 }
 
@@ -56,8 +93,11 @@ pub struct XmlGlobalAttributes {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct XmlGlobalAttribute {
-    #[serde(rename = "@types")]
+    #[serde(rename = "@types", default)]
     pub types: String,
+    /// 以空格分隔的节点分组
+    #[serde(rename = "@groups", default)]
+    pub groups: Option<String>,
     #[serde(rename = "attr")]
     pub attrs: Vec<XmlAttr>,
 }
@@ -86,6 +126,8 @@ pub struct XmlNode {
     pub content: Option<String>,
     #[serde(rename = "@marks")]
     pub marks: Option<String>,
+    #[serde(rename = "@sort_by")]
+    pub sort_by: Option<String>,
     pub attrs: Option<XmlAttrs>,
 }
 
@@ -105,6 +147,12 @@ pub struct XmlMark {
         default
     )]
     pub spanning: Option<bool>,
+    #[serde(
+        rename = "@cascade",
+        deserialize_with = "deserialize_optional_bool",
+        default
+    )]
+    pub cascade: Option<bool>,
     pub attrs: Option<XmlAttrs>,
 }
 
@@ -124,6 +172,13 @@ pub struct XmlAttr {
         default
     )]
     pub default: Option<Value>,
+    /// 派生属性表达式
+    #[serde(
+        rename = "@computed",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub computed: Option<String>,
 }
 
 // -------- 自定义反序列化器 --------
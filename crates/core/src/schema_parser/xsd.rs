@@ -0,0 +1,626 @@
+//! XSD 到 `SchemaSpec` 的转换
+//!
+//! 映射规则：
+//! - 类型为复杂类型（内联或命名的 `complexType`）的元素声明 → 节点类型，名称取元素名
+//! - `sequence` / `choice` / `all` → 内容表达式，`minOccurs` / `maxOccurs` → 量词
+//! - `attribute` 以及简单类型的子元素 → 节点属性，`simpleType` 的 restriction → [`AttributeConstraint`]
+//! - 第一个复杂类型的全局元素 → 顶级节点
+//!
+//! XSD 没有标记的概念，生成的 `SchemaSpec` 不包含标记类型。
+
+use std::collections::{HashMap, HashSet};
+
+use mf_model::{
+    node_definition::NodeSpec,
+    schema::{AttributeConstraint, AttributeSpec, Schema, SchemaSpec},
+};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::Value;
+
+use super::error::{XmlSchemaError, XmlSchemaResult};
+use super::parser::XmlSchemaParser;
+
+/// 从 XSD 生成 `SchemaSpec`
+///
+/// ```rust,no_run
+/// use mf_core::schema_parser::SchemaXsdExt;
+/// use mf_model::schema::Schema;
+///
+/// # fn main() -> mf_core::schema_parser::XmlSchemaResult<()> {
+/// let xsd = std::fs::read_to_string("project.xsd").unwrap();
+/// let spec = Schema::from_xml_schema(&xsd)?;
+/// let schema = Schema::compile(spec).unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub trait SchemaXsdExt {
+    fn from_xml_schema(xsd: &str) -> XmlSchemaResult<SchemaSpec>;
+}
+
+impl SchemaXsdExt for Schema {
+    fn from_xml_schema(xsd: &str) -> XmlSchemaResult<SchemaSpec> {
+        XmlSchemaParser::parse_xsd(xsd)
+    }
+}
+
+impl XmlSchemaParser {
+    /// 解析 XSD 内容并转换为 `SchemaSpec`
+    pub fn parse_xsd(xsd: &str) -> XmlSchemaResult<SchemaSpec> {
+        let root = XsdElement::parse(xsd)?;
+        if root.name != "schema" {
+            return Err(XmlSchemaError::InvalidNodeDefinition(format!(
+                "XSD 根元素应为 schema，实际为 {}",
+                root.name
+            )));
+        }
+        XsdConverter::new(&root).convert()
+    }
+}
+
+/// XSD 文档中的元素（去掉命名空间前缀）
+#[derive(Debug, Default)]
+struct XsdElement {
+    name: String,
+    attrs: HashMap<String, String>,
+    children: Vec<XsdElement>,
+    text: String,
+}
+
+impl XsdElement {
+    fn parse(xsd: &str) -> XmlSchemaResult<XsdElement> {
+        let mut reader = Reader::from_str(xsd);
+        let mut stack: Vec<XsdElement> = Vec::new();
+        let mut root = None;
+        loop {
+            match reader.read_event()? {
+                Event::Start(start) => stack.push(Self::from_start(&start)?),
+                Event::Empty(start) => {
+                    let element = Self::from_start(&start)?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => root = Some(element),
+                    }
+                },
+                Event::End(_) => {
+                    if let Some(element) = stack.pop() {
+                        match stack.last_mut() {
+                            Some(parent) => parent.children.push(element),
+                            None => root = Some(element),
+                        }
+                    }
+                },
+                Event::Text(text) => {
+                    if let Some(current) = stack.last_mut() {
+                        current.text.push_str(text.unescape()?.trim());
+                    }
+                },
+                Event::Eof => break,
+                _ => {},
+            }
+        }
+        root.ok_or_else(|| {
+            XmlSchemaError::InvalidNodeDefinition(
+                "XSD 中没有根元素".to_string(),
+            )
+        })
+    }
+
+    fn from_start(start: &BytesStart) -> XmlSchemaResult<XsdElement> {
+        let mut attrs = HashMap::new();
+        for attr in start.attributes() {
+            let attr = attr.map_err(quick_xml::Error::from)?;
+            let key = String::from_utf8_lossy(attr.key.local_name().as_ref())
+                .into_owned();
+            attrs.insert(key, attr.unescape_value()?.into_owned());
+        }
+        Ok(XsdElement {
+            name: String::from_utf8_lossy(start.local_name().as_ref())
+                .into_owned(),
+            attrs,
+            ..Default::default()
+        })
+    }
+
+    fn attr(
+        &self,
+        key: &str,
+    ) -> Option<&str> {
+        self.attrs.get(key).map(String::as_str)
+    }
+
+    fn child(
+        &self,
+        name: &str,
+    ) -> Option<&XsdElement> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn children_named<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a XsdElement> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// `annotation/documentation` 中的说明文字
+    fn documentation(&self) -> Option<String> {
+        let doc = self.child("annotation")?.child("documentation")?;
+        (!doc.text.is_empty()).then(|| doc.text.clone())
+    }
+}
+
+/// 去掉类型引用中的命名空间前缀
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+struct XsdConverter<'a> {
+    elements: HashMap<&'a str, &'a XsdElement>,
+    complex_types: HashMap<&'a str, &'a XsdElement>,
+    simple_types: HashMap<&'a str, &'a XsdElement>,
+    groups: HashMap<&'a str, &'a XsdElement>,
+    attribute_groups: HashMap<&'a str, &'a XsdElement>,
+    global_elements: Vec<&'a XsdElement>,
+    nodes: HashMap<String, NodeSpec>,
+    visiting: HashSet<String>,
+}
+
+impl<'a> XsdConverter<'a> {
+    fn new(root: &'a XsdElement) -> Self {
+        let named = |kind: &str| -> HashMap<&'a str, &'a XsdElement> {
+            root.children
+                .iter()
+                .filter(|c| c.name == kind)
+                .filter_map(|c| c.attr("name").map(|name| (name, c)))
+                .collect()
+        };
+        XsdConverter {
+            elements: named("element"),
+            complex_types: named("complexType"),
+            simple_types: named("simpleType"),
+            groups: named("group"),
+            attribute_groups: named("attributeGroup"),
+            global_elements: root.children_named("element").collect(),
+            nodes: HashMap::new(),
+            visiting: HashSet::new(),
+        }
+    }
+
+    fn convert(mut self) -> XmlSchemaResult<SchemaSpec> {
+        let mut top_node = None;
+        for element in self.global_elements.clone() {
+            let name = self.element_node(element)?;
+            if top_node.is_none() {
+                top_node = name;
+            }
+        }
+        if top_node.is_none() {
+            return Err(XmlSchemaError::InvalidNodeDefinition(
+                "XSD 中没有复杂类型的全局元素，无法确定顶级节点".to_string(),
+            ));
+        }
+        Ok(SchemaSpec { nodes: self.nodes, marks: HashMap::new(), top_node })
+    }
+
+    /// 元素的复杂类型定义，简单类型元素返回 None
+    fn complex_type_of(
+        &self,
+        element: &'a XsdElement,
+    ) -> XmlSchemaResult<Option<&'a XsdElement>> {
+        if let Some(inline) = element.child("complexType") {
+            return Ok(Some(inline));
+        }
+        let Some(type_name) = element.attr("type").map(local_name) else {
+            // 既没有类型也没有内联定义时视为空的复杂类型
+            return Ok(element
+                .child("simpleType")
+                .is_none()
+                .then_some(element));
+        };
+        if let Some(complex) = self.complex_types.get(type_name) {
+            return Ok(Some(complex));
+        }
+        if self.simple_types.contains_key(type_name)
+            || element.attr("type").is_some_and(|t| t.contains(':'))
+        {
+            return Ok(None);
+        }
+        Err(XmlSchemaError::InvalidNodeDefinition(format!(
+            "元素 {} 引用了未定义的类型 {}",
+            element.attr("name").unwrap_or_default(),
+            type_name
+        )))
+    }
+
+    /// 把元素声明转换为节点类型，返回节点名称；简单类型元素返回 None
+    fn element_node(
+        &mut self,
+        element: &'a XsdElement,
+    ) -> XmlSchemaResult<Option<String>> {
+        let element = match element.attr("ref").map(local_name) {
+            Some(reference) => {
+                *self.elements.get(reference).ok_or_else(|| {
+                    XmlSchemaError::InvalidNodeDefinition(format!(
+                        "引用了未定义的元素 {reference}"
+                    ))
+                })?
+            },
+            None => element,
+        };
+        let name = element
+            .attr("name")
+            .ok_or_else(|| {
+                XmlSchemaError::MissingAttribute("element@name".to_string())
+            })?
+            .to_string();
+        let Some(complex) = self.complex_type_of(element)? else {
+            return Ok(None);
+        };
+        if name.chars().any(|c| !c.is_alphanumeric() && c != '_') {
+            return Err(XmlSchemaError::InvalidNodeDefinition(format!(
+                "元素名 {name} 含有内容表达式不支持的字符"
+            )));
+        }
+        if self.nodes.contains_key(&name) || !self.visiting.insert(name.clone())
+        {
+            return Ok(Some(name));
+        }
+
+        let mut attrs = HashMap::new();
+        let content = if std::ptr::eq(complex, element) {
+            None
+        } else {
+            self.complex_content(complex, &mut attrs)?
+        };
+        let spec = NodeSpec {
+            content,
+            marks: None,
+            group: None,
+            desc: element.documentation().or_else(|| complex.documentation()),
+            attrs: (!attrs.is_empty()).then_some(attrs),
+            sort_by: None,
+        };
+        self.visiting.remove(&name);
+        self.nodes.insert(name.clone(), spec);
+        Ok(Some(name))
+    }
+
+    /// 解析复杂类型，返回内容表达式并收集属性
+    fn complex_content(
+        &mut self,
+        complex: &'a XsdElement,
+        attrs: &mut HashMap<String, AttributeSpec>,
+    ) -> XmlSchemaResult<Option<String>> {
+        let mut parts = Vec::new();
+        for child in &complex.children {
+            match child.name.as_str() {
+                "sequence" | "choice" | "all" | "group" => {
+                    parts.extend(self.particle(child, attrs)?);
+                },
+                "attribute" => self.attribute(child, attrs)?,
+                "attributeGroup" => self.attribute_group(child, attrs)?,
+                "complexContent" | "simpleContent" => {
+                    let Some(derivation) = child
+                        .child("extension")
+                        .or_else(|| child.child("restriction"))
+                    else {
+                        continue;
+                    };
+                    if derivation.name == "extension"
+                        && let Some(base) = derivation
+                            .attr("base")
+                            .and_then(|b| self.complex_types.get(local_name(b)))
+                    {
+                        parts.extend(self.complex_content(base, attrs)?);
+                    }
+                    parts.extend(self.complex_content(derivation, attrs)?);
+                },
+                _ => {},
+            }
+        }
+        Ok((!parts.is_empty()).then(|| parts.join(" ")))
+    }
+
+    /// 把 sequence / choice / all / group / element 转换为内容表达式片段
+    fn particle(
+        &mut self,
+        particle: &'a XsdElement,
+        attrs: &mut HashMap<String, AttributeSpec>,
+    ) -> XmlSchemaResult<Option<String>> {
+        let occurs = occurs(particle);
+        let expr = match particle.name.as_str() {
+            "element" => match self.element_node(particle)? {
+                Some(name) => return Ok(Some(format!("{name}{occurs}"))),
+                None => {
+                    self.element_attribute(particle, attrs)?;
+                    return Ok(None);
+                },
+            },
+            "group" => {
+                let Some(reference) = particle.attr("ref").map(local_name)
+                else {
+                    return Ok(None);
+                };
+                let group = *self.groups.get(reference).ok_or_else(|| {
+                    XmlSchemaError::InvalidNodeDefinition(format!(
+                        "引用了未定义的 group {reference}"
+                    ))
+                })?;
+                let mut items = Vec::new();
+                for child in &group.children {
+                    items.extend(self.particle(child, attrs)?);
+                }
+                items.join(" ")
+            },
+            "sequence" | "all" | "choice" => {
+                let mut items = Vec::new();
+                for child in &particle.children {
+                    items.extend(self.particle(child, attrs)?);
+                }
+                if particle.name == "choice" && items.len() > 1 {
+                    format!("({})", items.join(" | "))
+                } else {
+                    items.join(" ")
+                }
+            },
+            _ => return Ok(None),
+        };
+        if expr.is_empty() {
+            return Ok(None);
+        }
+        if occurs.is_empty() || is_atomic(&expr) {
+            return Ok(Some(format!("{expr}{occurs}")));
+        }
+        Ok(Some(format!("({expr}){occurs}")))
+    }
+
+    fn attribute(
+        &self,
+        attribute: &'a XsdElement,
+        attrs: &mut HashMap<String, AttributeSpec>,
+    ) -> XmlSchemaResult<()> {
+        let Some(name) =
+            attribute.attr("name").or_else(|| attribute.attr("ref"))
+        else {
+            return Err(XmlSchemaError::MissingAttribute(
+                "attribute@name".to_string(),
+            ));
+        };
+        let required = attribute.attr("use") == Some("required");
+        let spec = self.attribute_spec(attribute, required)?;
+        attrs.insert(local_name(name).to_string(), spec);
+        Ok(())
+    }
+
+    fn attribute_group(
+        &self,
+        group: &'a XsdElement,
+        attrs: &mut HashMap<String, AttributeSpec>,
+    ) -> XmlSchemaResult<()> {
+        let group = match group.attr("ref").map(local_name) {
+            Some(reference) => {
+                *self.attribute_groups.get(reference).ok_or_else(|| {
+                    XmlSchemaError::InvalidNodeDefinition(format!(
+                        "引用了未定义的 attributeGroup {reference}"
+                    ))
+                })?
+            },
+            None => group,
+        };
+        for child in &group.children {
+            match child.name.as_str() {
+                "attribute" => self.attribute(child, attrs)?,
+                "attributeGroup" => self.attribute_group(child, attrs)?,
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    /// 简单类型的子元素作为父节点的属性
+    fn element_attribute(
+        &self,
+        element: &'a XsdElement,
+        attrs: &mut HashMap<String, AttributeSpec>,
+    ) -> XmlSchemaResult<()> {
+        let element = match element.attr("ref").map(local_name) {
+            Some(reference) => {
+                self.elements.get(reference).copied().unwrap_or(element)
+            },
+            None => element,
+        };
+        let Some(name) = element.attr("name") else {
+            return Ok(());
+        };
+        let required = element.attr("minOccurs") != Some("0");
+        attrs.insert(name.to_string(), self.attribute_spec(element, required)?);
+        Ok(())
+    }
+
+    /// 属性的默认值与约束
+    ///
+    /// 有 `default` / `fixed` 时使用其值；没有默认值的可选属性默认值为 null，
+    /// 必填属性没有默认值
+    fn attribute_spec(
+        &self,
+        declaration: &'a XsdElement,
+        required: bool,
+    ) -> XmlSchemaResult<AttributeSpec> {
+        let default = match declaration
+            .attr("default")
+            .or_else(|| declaration.attr("fixed"))
+        {
+            Some(value) => Some(XmlSchemaParser::parse_attribute_value(value)?),
+            None if required => None,
+            None => Some(Value::Null),
+        };
+        let constraint = match declaration.child("simpleType") {
+            Some(simple) => self.simple_constraint(simple),
+            None => {
+                declaration.attr("type").and_then(|t| self.type_constraint(t))
+            },
+        };
+        Ok(AttributeSpec { constraint, ..AttributeSpec::new(default) })
+    }
+
+    fn type_constraint(
+        &self,
+        type_name: &str,
+    ) -> Option<AttributeConstraint> {
+        let name = local_name(type_name);
+        match self.simple_types.get(name) {
+            Some(simple) => self.simple_constraint(simple),
+            None => Some(AttributeConstraint {
+                base: Some(name.to_string()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn simple_constraint(
+        &self,
+        simple: &'a XsdElement,
+    ) -> Option<AttributeConstraint> {
+        let restriction = simple.child("restriction")?;
+        let mut constraint = restriction
+            .attr("base")
+            .and_then(|base| self.type_constraint(base))
+            .unwrap_or_default();
+        for facet in &restriction.children {
+            let Some(value) = facet.attr("value") else {
+                continue;
+            };
+            match facet.name.as_str() {
+                "enumeration" => constraint.enumeration.push(value.to_string()),
+                "pattern" => constraint.pattern = Some(value.to_string()),
+                "length" => {
+                    constraint.min_length = value.parse().ok();
+                    constraint.max_length = value.parse().ok();
+                },
+                "minLength" => constraint.min_length = value.parse().ok(),
+                "maxLength" => constraint.max_length = value.parse().ok(),
+                "minInclusive" => {
+                    constraint.min_inclusive = Some(value.to_string())
+                },
+                "maxInclusive" => {
+                    constraint.max_inclusive = Some(value.to_string())
+                },
+                _ => {},
+            }
+        }
+        Some(constraint)
+    }
+}
+
+/// 表达式在括号外没有空格时，量词可以直接追加在后面
+fn is_atomic(expr: &str) -> bool {
+    let mut depth = 0usize;
+    for c in expr.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ' ' if depth == 0 => return false,
+            _ => {},
+        }
+    }
+    true
+}
+
+/// `minOccurs` / `maxOccurs` 对应的量词
+fn occurs(particle: &XsdElement) -> String {
+    let min: usize =
+        particle.attr("minOccurs").and_then(|v| v.parse().ok()).unwrap_or(1);
+    let max = particle.attr("maxOccurs").unwrap_or("1");
+    match (min, max) {
+        (1, "1") => String::new(),
+        (0, "1") => "?".to_string(),
+        (0, "unbounded") => "*".to_string(),
+        (1, "unbounded") => "+".to_string(),
+        (min, "unbounded") => format!("{{{min},}}"),
+        (min, max) if max == min.to_string() => format!("{{{min}}}"),
+        (min, max) => format!("{{{min},{max}}}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/fixtures/construction_project.xsd"
+    );
+
+    #[test]
+    fn test_construction_xsd_to_schema() {
+        let xsd = std::fs::read_to_string(FIXTURE).unwrap();
+        let spec = Schema::from_xml_schema(&xsd).unwrap();
+
+        assert_eq!(spec.top_node.as_deref(), Some("GCXM"));
+        assert!(spec.marks.is_empty());
+        assert_eq!(spec.nodes["GCXM"].content.as_deref(), Some("DXGC+"));
+        assert_eq!(spec.nodes["GCXM"].desc.as_deref(), Some("工程项目"));
+        assert_eq!(spec.nodes["DXGC"].content.as_deref(), Some("DWGC+"));
+        assert_eq!(
+            spec.nodes["DWGC"].content.as_deref(),
+            Some("(FBFX | CSXM)* QTXM?")
+        );
+        assert_eq!(spec.nodes["QTXM"].content, None);
+
+        let attrs = spec.nodes["DWGC"].attrs.as_ref().unwrap();
+        // 必填属性没有默认值
+        assert_eq!(attrs["bm"].default, None);
+        let bm = attrs["bm"].constraint.as_ref().unwrap();
+        assert_eq!(bm.pattern.as_deref(), Some("[0-9]{12}"));
+        assert_eq!(bm.base.as_deref(), Some("string"));
+
+        let zy = attrs["zy"].constraint.as_ref().unwrap();
+        assert_eq!(zy.enumeration, vec!["建筑", "安装", "市政"]);
+        assert_eq!(attrs["zy"].default, Some(Value::from("建筑")));
+
+        let je = attrs["je"].constraint.as_ref().unwrap();
+        assert_eq!(je.base.as_deref(), Some("decimal"));
+        assert_eq!(je.min_inclusive.as_deref(), Some("0"));
+        assert!(je.allows(&Value::from(12.5)));
+        assert!(!je.allows(&Value::from(-1)));
+
+        // 简单类型子元素映射为可选属性
+        assert_eq!(attrs["bz"].default, Some(Value::Null));
+        // attributeGroup 中的公共属性
+        assert!(spec.nodes["FBFX"].attrs.as_ref().unwrap().contains_key("mc"));
+
+        let schema = Schema::compile(spec).unwrap();
+        assert_eq!(
+            schema.content_completer("DWGC", &["FBFX", "QTXM"]),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            schema.content_completer("DWGC", &["FBFX"]),
+            vec!["CSXM", "FBFX", "QTXM"]
+        );
+    }
+
+    #[test]
+    fn test_xsd_occurs_and_errors() {
+        let xsd = r#"
+        <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+          <xs:element name="doc">
+            <xs:complexType>
+              <xs:sequence minOccurs="0" maxOccurs="3">
+                <xs:element name="a" type="Empty" minOccurs="2" maxOccurs="2"/>
+                <xs:element name="b" type="Empty"/>
+              </xs:sequence>
+            </xs:complexType>
+          </xs:element>
+          <xs:complexType name="Empty"/>
+        </xs:schema>
+        "#;
+        let spec = XmlSchemaParser::parse_xsd(xsd).unwrap();
+        assert_eq!(spec.nodes["doc"].content.as_deref(), Some("(a{2} b){0,3}"));
+
+        let unknown = xsd.replace(r#"type="Empty"/>"#, r#"type="Missing"/>"#);
+        assert!(XmlSchemaParser::parse_xsd(&unknown).is_err());
+        assert!(XmlSchemaParser::parse_xsd("<nodes/>").is_err());
+    }
+}
@@ -0,0 +1,798 @@
+//! 插件测试工具
+//!
+//! [`PluginTestHarness`] 统一了插件测试的准备工作：构建 schema 与状态、
+//! 提交命令、读取插件状态。同一套断言既可以直接跑在 `State::apply` 上，
+//! 也可以跑在完整的 [`ForgeRuntime`] 上（包含中间件、历史与事件）。
+//!
+//! 每次提交后都会检查资源管理器中的 gotham 状态条数是否变化，
+//! 插件在事务处理中遗留的临时数据会让测试直接失败。
+//!
+//! 需要启用 `test-utils` feature，本 crate 自身的测试中始终可用。
+//!
+//! ```rust,ignore
+//! let mut harness = PluginTestHarness::builder()
+//!     .xml_schema(XML)
+//!     .plugin(my_plugin())
+//!     .build_state()
+//!     .await?;
+//! harness.apply(Arc::new(SetTitle("标题"))).await?;
+//! harness
+//!     .expect_appended_transactions(1)
+//!     .assert_plugin_state::<Counter>("counter", |c| assert_eq!(c.count, 1));
+//! ```
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use mf_model::{node_pool::NodePool, schema::Schema};
+use mf_state::{
+    ops::GlobalResourceManager,
+    plugin::Plugin,
+    resource::Resource,
+    state::StateGeneric,
+    transaction::{CommandGeneric, TransactionGeneric},
+    State, StateConfig, Transaction,
+};
+
+use crate::{
+    config::ForgeConfig,
+    error::{error_utils, ForgeResult},
+    extension::Extension,
+    extension_manager::ExtensionManager,
+    helpers::create_doc,
+    middleware::MiddlewareGeneric,
+    runtime::runtime::ForgeRuntime,
+    types::{Extensions, RuntimeOptions},
+};
+
+/// 可控的时钟
+///
+/// 构建测试工具时存入资源管理器的 gotham 状态，需要时间戳的插件通过
+/// `state.resource_manager().get::<TestClock>()` 读取，测试中用
+/// [`TestClock::advance`] 推进时间
+#[derive(Debug, Clone, Default)]
+pub struct TestClock {
+    millis: Arc<AtomicU64>,
+}
+
+impl TestClock {
+    pub fn new(millis: u64) -> Self {
+        Self { millis: Arc::new(AtomicU64::new(millis)) }
+    }
+
+    /// 当前时间（毫秒）
+    pub fn now_millis(&self) -> u64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+
+    pub fn set_millis(
+        &self,
+        millis: u64,
+    ) {
+        self.millis.store(millis, Ordering::SeqCst);
+    }
+
+    pub fn advance(
+        &self,
+        duration: Duration,
+    ) {
+        self.millis.fetch_add(duration.as_millis() as u64, Ordering::SeqCst);
+    }
+}
+
+/// 一次提交的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ApplyRecord {
+    /// 事务被插件过滤，状态未变化
+    filtered: bool,
+    /// 插件追加的事务数（不含提交的事务本身）
+    appended: usize,
+}
+
+impl ApplyRecord {
+    fn new(
+        before: &Arc<State>,
+        after: &Arc<State>,
+        transactions: usize,
+    ) -> Self {
+        let filtered = Arc::ptr_eq(before, after);
+        Self {
+            filtered,
+            appended: if filtered { 0 } else { transactions.saturating_sub(1) },
+        }
+    }
+}
+
+/// 记录运行时每次分发结果的后置中间件
+#[derive(Debug, Clone, Default)]
+struct ApplyRecorder {
+    before: Arc<Mutex<Option<Arc<State>>>>,
+    record: Arc<Mutex<Option<ApplyRecord>>>,
+}
+
+#[async_trait]
+impl MiddlewareGeneric<NodePool, Schema> for ApplyRecorder {
+    fn name(&self) -> String {
+        "plugin_test_harness".to_string()
+    }
+
+    async fn after_dispatch(
+        &self,
+        state: Option<Arc<StateGeneric<NodePool, Schema>>>,
+        transactions: &[Arc<TransactionGeneric<NodePool, Schema>>],
+    ) -> ForgeResult<Option<TransactionGeneric<NodePool, Schema>>> {
+        let before = self.before.lock().unwrap().clone();
+        if let (Some(before), Some(after)) = (before, state) {
+            *self.record.lock().unwrap() =
+                Some(ApplyRecord::new(&before, &after, transactions.len()));
+        }
+        Ok(None)
+    }
+}
+
+enum Backend {
+    State(Arc<State>),
+    Runtime { runtime: Box<ForgeRuntime>, recorder: ApplyRecorder },
+}
+
+/// 插件测试工具构建器
+#[derive(Default)]
+pub struct PluginTestHarnessBuilder {
+    xml: Option<String>,
+    extensions: Vec<Extensions>,
+    check_gotham_state: bool,
+}
+
+impl PluginTestHarnessBuilder {
+    pub fn new() -> Self {
+        Self { check_gotham_state: true, ..Default::default() }
+    }
+
+    /// 从 XML schema 构建节点与标记定义
+    pub fn xml_schema(
+        mut self,
+        xml: &str,
+    ) -> Self {
+        self.xml = Some(xml.to_string());
+        self
+    }
+
+    pub fn extension(
+        mut self,
+        extension: Extensions,
+    ) -> Self {
+        self.extensions.push(extension);
+        self
+    }
+
+    pub fn extensions(
+        mut self,
+        extensions: Vec<Extensions>,
+    ) -> Self {
+        self.extensions.extend(extensions);
+        self
+    }
+
+    /// 添加待测插件
+    pub fn plugin(
+        self,
+        plugin: Arc<Plugin>,
+    ) -> Self {
+        let mut extension = Extension::new();
+        extension.add_plugin(plugin);
+        self.extension(Extensions::E(extension))
+    }
+
+    /// 允许插件在提交之间增删 gotham 状态
+    pub fn allow_gotham_state_changes(mut self) -> Self {
+        self.check_gotham_state = false;
+        self
+    }
+
+    fn options(&self) -> ForgeResult<RuntimeOptions> {
+        let options = match &self.xml {
+            Some(xml) => RuntimeOptions::from_extension_manager(
+                ExtensionManager::from_xml_string(xml)?,
+            ),
+            None => RuntimeOptions::default(),
+        };
+        Ok(self
+            .extensions
+            .iter()
+            .cloned()
+            .fold(options, |options, extension| {
+                options.add_extension(extension)
+            }))
+    }
+
+    /// 直接基于 `State::apply` 提交事务
+    pub async fn build_state(self) -> ForgeResult<PluginTestHarness> {
+        let options = self.options()?;
+        let extension_manager =
+            ExtensionManager::new(&options.get_extensions())?;
+        let op_state = Arc::new(GlobalResourceManager::new());
+        for op_fn in extension_manager.get_op_fns() {
+            op_fn.run(&op_state)?;
+        }
+        let mut config = StateConfig {
+            schema: Some(extension_manager.get_schema()),
+            doc: None,
+            stored_marks: None,
+            plugins: Some(extension_manager.get_plugins().clone()),
+            resource_manager: Some(op_state),
+        };
+        create_doc::create_doc(&options.get_content(), &mut config).await?;
+        let state = Arc::new(State::create(config).await?);
+        Ok(PluginTestHarness::new(
+            Backend::State(state),
+            self.check_gotham_state,
+        ))
+    }
+
+    /// 基于 [`ForgeRuntime`] 提交命令，经过中间件与历史记录
+    pub async fn build_runtime(self) -> ForgeResult<PluginTestHarness> {
+        let recorder = ApplyRecorder::default();
+        let options = self.options()?;
+        let mut middleware_stack = options.get_middleware_stack();
+        middleware_stack.add(recorder.clone());
+        let options = options.set_middleware_stack(middleware_stack);
+        let runtime =
+            ForgeRuntime::create_with_config(options, ForgeConfig::default())
+                .await?;
+        Ok(PluginTestHarness::new(
+            Backend::Runtime { runtime: Box::new(runtime), recorder },
+            self.check_gotham_state,
+        ))
+    }
+}
+
+/// 插件测试工具
+///
+/// 断言失败时直接 panic，便于在测试中链式调用
+pub struct PluginTestHarness {
+    backend: Backend,
+    clock: TestClock,
+    last: Option<ApplyRecord>,
+    check_gotham_state: bool,
+}
+
+impl PluginTestHarness {
+    pub fn builder() -> PluginTestHarnessBuilder {
+        PluginTestHarnessBuilder::new()
+    }
+
+    fn new(
+        backend: Backend,
+        check_gotham_state: bool,
+    ) -> Self {
+        let harness = Self {
+            backend,
+            clock: TestClock::default(),
+            last: None,
+            check_gotham_state,
+        };
+        harness.state().resource_manager().put(harness.clock.clone());
+        harness
+    }
+
+    /// 当前状态
+    pub fn state(&self) -> Arc<State> {
+        match &self.backend {
+            Backend::State(state) => state.clone(),
+            Backend::Runtime { runtime, .. } => runtime.get_state().clone(),
+        }
+    }
+
+    /// 基于运行时构建时返回运行时
+    pub fn runtime(&mut self) -> Option<&mut ForgeRuntime> {
+        match &mut self.backend {
+            Backend::State(_) => None,
+            Backend::Runtime { runtime, .. } => Some(runtime),
+        }
+    }
+
+    pub fn clock(&self) -> &TestClock {
+        &self.clock
+    }
+
+    /// 基于当前状态创建事务
+    pub fn tr(&self) -> Transaction {
+        self.state().tr()
+    }
+
+    /// 执行命令并提交
+    pub async fn apply(
+        &mut self,
+        command: Arc<dyn CommandGeneric<NodePool, Schema>>,
+    ) -> ForgeResult<&mut Self> {
+        match &mut self.backend {
+            Backend::State(_) => {
+                let mut tr = self.tr();
+                command.execute(&mut tr).await.map_err(|e| {
+                    error_utils::transaction_error(format!(
+                        "命令 {} 执行失败: {e}",
+                        command.name()
+                    ))
+                })?;
+                self.apply_transaction(tr).await
+            },
+            Backend::Runtime { runtime, recorder } => {
+                let gotham_len = gotham_len(runtime.get_state());
+                recorder.begin(runtime.get_state().clone());
+                runtime.command(command).await?;
+                self.last = recorder.take();
+                self.check_gotham(gotham_len);
+                Ok(self)
+            },
+        }
+    }
+
+    /// 提交事务
+    pub async fn apply_transaction(
+        &mut self,
+        tr: Transaction,
+    ) -> ForgeResult<&mut Self> {
+        match &mut self.backend {
+            Backend::State(state) => {
+                let gotham_len = gotham_len(state);
+                let result = state.apply(tr).await?;
+                self.last = Some(ApplyRecord::new(
+                    state,
+                    &result.state,
+                    result.transactions.len(),
+                ));
+                *state = result.state;
+                self.check_gotham(gotham_len);
+            },
+            Backend::Runtime { runtime, recorder } => {
+                let gotham_len = gotham_len(runtime.get_state());
+                recorder.begin(runtime.get_state().clone());
+                runtime.dispatch(tr).await?;
+                self.last = recorder.take();
+                self.check_gotham(gotham_len);
+            },
+        }
+        Ok(self)
+    }
+
+    /// 执行命令并断言事务被插件过滤
+    pub async fn expect_filtered(
+        &mut self,
+        command: Arc<dyn CommandGeneric<NodePool, Schema>>,
+    ) -> &mut Self {
+        let name = command.name();
+        let before = self.state();
+        if let Err(e) = self.apply(command).await {
+            panic!("命令 {name} 提交失败: {e}");
+        }
+        assert!(
+            self.last.is_some_and(|record| record.filtered),
+            "命令 {name} 的事务未被过滤"
+        );
+        assert!(
+            Arc::ptr_eq(&before, &self.state()),
+            "事务被过滤后状态发生了变化"
+        );
+        self
+    }
+
+    /// 断言上一次提交中插件追加了 `n` 个事务
+    pub fn expect_appended_transactions(
+        &self,
+        n: usize,
+    ) -> &Self {
+        let Some(record) = self.last else {
+            panic!("尚未提交任何事务");
+        };
+        assert!(!record.filtered, "上一次提交的事务被过滤");
+        assert_eq!(record.appended, n, "追加的事务数不符");
+        self
+    }
+
+    /// 对当前文档断言
+    pub fn assert_doc<F>(
+        &self,
+        f: F,
+    ) -> &Self
+    where
+        F: FnOnce(&NodePool),
+    {
+        f(&self.state().doc());
+        self
+    }
+
+    /// 对插件状态断言，插件不存在或状态类型不符时 panic
+    pub fn assert_plugin_state<T: Resource>(
+        &self,
+        key: &str,
+        f: impl FnOnce(&T),
+    ) -> &Self {
+        let Some(value) = self.state().get::<T>(key) else {
+            panic!(
+                "插件 {key} 没有类型为 {} 的状态",
+                std::any::type_name::<T>()
+            );
+        };
+        f(&value);
+        self
+    }
+
+    fn check_gotham(
+        &self,
+        before: usize,
+    ) {
+        if self.check_gotham_state {
+            let after = gotham_len(&self.state());
+            assert_eq!(
+                before, after,
+                "提交前后 gotham 状态条数不一致，插件可能遗留了事务内的临时数据"
+            );
+        }
+    }
+}
+
+impl ApplyRecorder {
+    fn begin(
+        &self,
+        state: Arc<State>,
+    ) {
+        *self.before.lock().unwrap() = Some(state);
+        *self.record.lock().unwrap() = None;
+    }
+
+    fn take(&self) -> Option<ApplyRecord> {
+        self.before.lock().unwrap().take();
+        self.record.lock().unwrap().take()
+    }
+}
+
+fn gotham_len(state: &State) -> usize {
+    state.resource_manager().gotham_state.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mf_model::rpds::HashTrieMapSync;
+    use mf_state::plugin::{
+        PluginMetadata, PluginSpec, PluginTrait, PluginTraitGeneric,
+        StateFieldGeneric,
+    };
+    use mf_state::TransactionOrigin;
+    use mf_transform::TransformResult;
+    use serde_json::Value;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<schema top_node="doc">
+  <nodes>
+    <node name="doc" desc="文档">
+      <attrs>
+        <attr name="title" default=""/>
+        <attr name="stamped" default="false"/>
+      </attrs>
+    </node>
+  </nodes>
+</schema>"#;
+
+    const PLUGIN: &str = "edits";
+    const STAMPED: &str = "stamped";
+    const LOCKED: &str = "locked";
+
+    fn metadata(name: &str) -> PluginMetadata {
+        PluginMetadata {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            author: String::new(),
+            dependencies: vec![],
+            conflicts: vec![],
+            state_fields: vec![],
+            tags: vec![],
+        }
+    }
+
+    #[derive(Debug)]
+    struct Edits {
+        count: u64,
+        last_edit: u64,
+    }
+
+    impl Resource for Edits {}
+
+    /// 对每次编辑追加一个盖章事务，状态中统计盖章次数与时间，
+    /// 拒绝带锁定标记的事务
+    #[derive(Debug)]
+    struct EditsPlugin;
+
+    #[async_trait]
+    impl PluginTraitGeneric<NodePool, Schema> for EditsPlugin {
+        fn metadata(&self) -> PluginMetadata {
+            metadata(PLUGIN)
+        }
+
+        async fn append_transaction(
+            &self,
+            trs: &[Arc<Transaction>],
+            _: &Arc<State>,
+            new_state: &Arc<State>,
+        ) -> mf_state::error::StateResult<Option<Transaction>> {
+            if !trs.iter().any(|tr| tr.get_meta::<bool>(STAMPED).is_none()) {
+                return Ok(None);
+            }
+            let mut tr = new_state.tr();
+            tr.set_meta(STAMPED, true);
+            tr.set_node_attribute(
+                new_state.doc().root_id().clone(),
+                HashTrieMapSync::new_sync()
+                    .insert(STAMPED.to_string(), Value::from(true)),
+            )?;
+            Ok(Some(tr))
+        }
+
+        async fn filter_transaction(
+            &self,
+            tr: &Transaction,
+            _: &State,
+        ) -> bool {
+            tr.get_meta::<bool>(LOCKED).is_none()
+        }
+    }
+
+    impl PluginTrait for EditsPlugin {}
+
+    #[derive(Debug)]
+    struct EditsField;
+
+    #[async_trait]
+    impl StateFieldGeneric<NodePool, Schema> for EditsField {
+        type Value = Edits;
+
+        async fn init(
+            &self,
+            _: &StateConfig,
+            _: &State,
+        ) -> Arc<Edits> {
+            Arc::new(Edits { count: 0, last_edit: 0 })
+        }
+
+        async fn apply(
+            &self,
+            tr: &Transaction,
+            value: Arc<Edits>,
+            _: &State,
+            new_state: &State,
+        ) -> Arc<Edits> {
+            if tr.get_meta::<bool>(STAMPED).is_none() {
+                return value;
+            }
+            let now = new_state
+                .resource_manager()
+                .get::<TestClock>()
+                .map_or(0, |clock| clock.now_millis());
+            Arc::new(Edits { count: value.count + 1, last_edit: now })
+        }
+    }
+
+    fn edits_plugin() -> Arc<Plugin> {
+        Arc::new(Plugin::new(PluginSpec {
+            state_field: Some(Arc::new(EditsField)),
+            tr: Arc::new(EditsPlugin),
+        }))
+    }
+
+    #[derive(Debug)]
+    struct SetTitle {
+        title: &'static str,
+        locked: bool,
+    }
+
+    #[async_trait]
+    impl CommandGeneric<NodePool, Schema> for SetTitle {
+        async fn execute(
+            &self,
+            tr: &mut Transaction,
+        ) -> TransformResult<()> {
+            if self.locked {
+                tr.set_meta(LOCKED, true);
+            }
+            let root = tr.doc().root_id().clone();
+            tr.set_node_attribute(
+                root,
+                HashTrieMapSync::new_sync()
+                    .insert("title".to_string(), Value::from(self.title)),
+            )?;
+            Ok(())
+        }
+
+        fn name(&self) -> String {
+            "set_title".to_string()
+        }
+    }
+
+    fn set_title(title: &'static str) -> Arc<SetTitle> {
+        Arc::new(SetTitle { title, locked: false })
+    }
+
+    fn root_attr(
+        doc: &NodePool,
+        key: &str,
+    ) -> Option<Value> {
+        doc.root().and_then(|root| root.attrs.get(key).cloned())
+    }
+
+    /// 两种后端共用的脚本
+    async fn exercise(harness: &mut PluginTestHarness) {
+        harness.apply(set_title("初稿")).await.unwrap();
+        harness
+            .expect_appended_transactions(1)
+            .assert_doc(|doc| {
+                assert_eq!(root_attr(doc, "title"), Some(Value::from("初稿")));
+                assert_eq!(root_attr(doc, STAMPED), Some(Value::from(true)));
+            })
+            .assert_plugin_state::<Edits>(PLUGIN, |edits| {
+                assert_eq!((edits.count, edits.last_edit), (1, 0));
+            });
+
+        harness.clock().advance(Duration::from_secs(5));
+        harness.apply(set_title("终稿")).await.unwrap();
+        harness.assert_plugin_state::<Edits>(PLUGIN, |edits| {
+            assert_eq!((edits.count, edits.last_edit), (2, 5000));
+        });
+
+        harness
+            .expect_filtered(Arc::new(SetTitle {
+                title: "锁定", locked: true
+            }))
+            .await;
+        harness
+            .assert_doc(|doc| {
+                assert_eq!(root_attr(doc, "title"), Some(Value::from("终稿")));
+            })
+            .assert_plugin_state::<Edits>(PLUGIN, |edits| {
+                assert_eq!(edits.count, 2)
+            });
+    }
+
+    #[tokio::test]
+    async fn test_harness_on_state() {
+        let mut harness = PluginTestHarness::builder()
+            .xml_schema(XML)
+            .plugin(edits_plugin())
+            .build_state()
+            .await
+            .unwrap();
+        assert!(harness.runtime().is_none());
+        exercise(&mut harness).await;
+    }
+
+    #[tokio::test]
+    async fn test_harness_on_runtime() {
+        let mut harness = PluginTestHarness::builder()
+            .xml_schema(XML)
+            .plugin(edits_plugin())
+            .build_runtime()
+            .await
+            .unwrap();
+        assert!(harness.runtime().is_some());
+        exercise(&mut harness).await;
+    }
+
+    #[tokio::test]
+    async fn test_appended_transactions_are_tagged() {
+        let harness = PluginTestHarness::builder()
+            .xml_schema(XML)
+            .plugin(edits_plugin())
+            .build_state()
+            .await
+            .unwrap();
+        let state = harness.state();
+        let mut tr = state.tr();
+        tr.set_node_attribute(
+            state.doc().root_id().clone(),
+            HashTrieMapSync::new_sync()
+                .insert("title".to_string(), Value::from("初稿")),
+        )
+        .unwrap();
+        tr.set_actor("alice");
+        let root_id = tr.id;
+
+        let result = state.apply(tr).await.unwrap();
+        let [root, appended] = &result.transactions[..] else {
+            panic!("expected one appended transaction");
+        };
+        assert_eq!(root.origin(), TransactionOrigin::User);
+        assert_eq!(root.caused_by(), None);
+        assert_eq!(
+            appended.origin(),
+            TransactionOrigin::Plugin(PLUGIN.to_string())
+        );
+        assert_eq!(appended.caused_by(), Some(root_id));
+        assert_eq!(root.actor(), Some("alice"));
+        assert_eq!(appended.actor(), Some("alice"));
+    }
+
+    #[tokio::test]
+    async fn test_read_snapshot_ignores_later_edits() {
+        let mut harness = PluginTestHarness::builder()
+            .xml_schema(XML)
+            .plugin(edits_plugin())
+            .build_runtime()
+            .await
+            .unwrap();
+        harness.apply(set_title("初稿")).await.unwrap();
+        let view = harness.state().read_snapshot();
+
+        let report = tokio::spawn({
+            let view = view.clone();
+            async move { root_attr(&view.doc(), "title") }
+        });
+        harness.apply(set_title("终稿")).await.unwrap();
+        assert_eq!(report.await.unwrap(), Some(Value::from("初稿")));
+
+        assert_eq!(root_attr(&view.doc(), "title"), Some(Value::from("初稿")));
+        assert_eq!(view.plugin_state::<Edits>(PLUGIN).unwrap().count, 1);
+        assert_ne!(view.version(), harness.state().version);
+        harness.assert_doc(|doc| {
+            assert_eq!(root_attr(doc, "title"), Some(Value::from("终稿")));
+        });
+    }
+
+    #[derive(Debug, Clone)]
+    struct Leaked;
+
+    /// 在过滤阶段向 gotham 状态写入数据且不清理
+    #[derive(Debug)]
+    struct LeakyPlugin;
+
+    #[async_trait]
+    impl PluginTraitGeneric<NodePool, Schema> for LeakyPlugin {
+        fn metadata(&self) -> PluginMetadata {
+            metadata("leaky")
+        }
+
+        async fn filter_transaction(
+            &self,
+            _: &Transaction,
+            state: &State,
+        ) -> bool {
+            state.resource_manager().put(Leaked);
+            true
+        }
+    }
+
+    impl PluginTrait for LeakyPlugin {}
+
+    fn leaky_harness() -> PluginTestHarnessBuilder {
+        PluginTestHarness::builder().xml_schema(XML).plugin(Arc::new(
+            Plugin::new(PluginSpec {
+                state_field: None,
+                tr: Arc::new(LeakyPlugin),
+            }),
+        ))
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "gotham")]
+    async fn test_gotham_leak_detected() {
+        let mut harness = leaky_harness().build_state().await.unwrap();
+        harness.apply(set_title("标题")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_gotham_changes_allowed() {
+        let mut harness = leaky_harness()
+            .allow_gotham_state_changes()
+            .build_state()
+            .await
+            .unwrap();
+        harness.apply(set_title("标题")).await.unwrap();
+        harness.expect_appended_transactions(0);
+    }
+}
@@ -10,7 +10,13 @@ use crate::{
     node::Node,
     ForgeResult,
 };
-use mf_model::{node_pool::NodePool, schema::AttributeSpec};
+use mf_model::{
+    dynamic_default::DynamicDefault,
+    node_definition::NodeSpec,
+    node_pool::NodePool,
+    schema::AttributeSpec,
+};
+use serde_json::Value;
 
 #[async_trait]
 pub trait NodePoolFnTrait: Send + Sync + std::fmt::Debug {
@@ -21,13 +27,130 @@ pub trait NodePoolFnTrait: Send + Sync + std::fmt::Debug {
 }
 
 pub type GlobalAttributes = Vec<GlobalAttributeItem>;
+
+type FilterFn = dyn Fn(&str, &NodeSpec) -> bool + Send + Sync;
+
+/// 按节点规范选择全局属性的作用范围
+#[derive(Clone)]
+pub struct NodeSpecFilter(Arc<FilterFn>);
+
+impl NodeSpecFilter {
+    /// 回调参数为节点类型名称与节点规范
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&str, &NodeSpec) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    pub fn matches(
+        &self,
+        name: &str,
+        spec: &NodeSpec,
+    ) -> bool {
+        (self.0)(name, spec)
+    }
+}
+
+impl PartialEq for NodeSpecFilter {
+    fn eq(
+        &self,
+        other: &Self,
+    ) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for NodeSpecFilter {}
+
+impl std::fmt::Debug for NodeSpecFilter {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str("NodeSpecFilter(..)")
+    }
+}
+
+/// 全局属性项
+///
+/// 作用于 `types` 中的节点类型（`*` 表示全部）、`groups` 中任一分组的节点类型，
+/// 以及满足 `filter` 的节点类型。节点类型自身声明的同名属性优先。
 #[derive(Clone, PartialEq, Debug, Eq, Default)]
 pub struct GlobalAttributeItem {
     pub types: Vec<String>,
     pub attributes: HashMap<String, AttributeSpec>,
+    /// 按分组匹配节点类型
+    pub groups: Vec<String>,
+    /// 按节点规范匹配节点类型
+    pub filter: Option<NodeSpecFilter>,
+    /// 创建节点时由回调计算的默认值（属性名 -> 回调）
+    pub dynamic_defaults: HashMap<String, DynamicDefault>,
 }
 
 impl GlobalAttributeItem {
+    /// 作用于指定分组的全局属性项
+    pub fn for_groups<I, S>(groups: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            groups: groups.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// 作用于满足条件的节点类型的全局属性项
+    pub fn for_filter<F>(filter: F) -> Self
+    where
+        F: Fn(&str, &NodeSpec) -> bool + Send + Sync + 'static,
+    {
+        Self { filter: Some(NodeSpecFilter::new(filter)), ..Default::default() }
+    }
+
+    /// 添加静态默认值的属性
+    pub fn with_attribute(
+        mut self,
+        key: &str,
+        default: Value,
+    ) -> Self {
+        self.attributes.insert(
+            key.to_string(),
+            AttributeSpec::new(Some(default)),
+        );
+        self
+    }
+
+    /// 添加动态默认值的属性，回调参数为节点类型名称
+    pub fn with_dynamic_default<F>(
+        mut self,
+        key: &str,
+        default: F,
+    ) -> Self
+    where
+        F: Fn(&str) -> Value + Send + Sync + 'static,
+    {
+        self.dynamic_defaults
+            .insert(key.to_string(), DynamicDefault::new(default));
+        self
+    }
+
+    /// 是否作用于指定的节点类型
+    pub fn applies_to(
+        &self,
+        name: &str,
+        spec: &NodeSpec,
+    ) -> bool {
+        self.types.iter().any(|t| t == "*" || t == name)
+            || spec.group.as_deref().is_some_and(|group| {
+                group
+                    .split_whitespace()
+                    .any(|g| self.groups.iter().any(|target| target == g))
+            })
+            || self.filter.as_ref().is_some_and(|f| f.matches(name, spec))
+    }
+
     /// Get the first attribute key for testing purposes
     pub fn key(&self) -> Option<&str> {
         self.attributes.keys().next().map(|s| s.as_str())
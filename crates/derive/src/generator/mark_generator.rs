@@ -95,9 +95,7 @@ impl<'a> MarkGenerator<'a> {
     ///         // 只为 #[attr] 字段构建属性映射
     ///         let mut attrs_map = std::collections::HashMap::new();
     ///         // 支持自定义类型表达式 (需要实现 Default + Serialize)
-    ///         attrs_map.insert("field_name".to_string(), AttributeSpec {
-    ///             default: Some(serde_json::to_value(CustomType::new()).unwrap_or(null))
-    ///         });
+    ///         attrs_map.insert("field_name".to_string(), AttributeSpec::new(Some(serde_json::to_value(CustomType::new()).unwrap_or(null))));
     ///         
     ///         // 构建 MarkSpec
     ///         let spec = MarkSpec { attrs: Some(attrs_map), ... };
@@ -296,6 +294,7 @@ impl<'a> MarkGenerator<'a> {
                 excludes: None,
                 group: None,
                 spanning: None,
+                cascade: None,
                 desc: None,
             };
         };
@@ -357,14 +356,10 @@ impl<'a> MarkGenerator<'a> {
     ///
     /// ```rust
     /// // 如果有 default 属性，使用 default 值
-    /// attrs_map.insert("field_name".to_string(), mf_model::schema::AttributeSpec {
-    ///     default: Some(serde_json::json!("default_value"))
-    /// });
+    /// attrs_map.insert("field_name".to_string(), mf_model::schema::AttributeSpec::new(Some(serde_json::json!("default_value"))));
     ///
     /// // 如果没有 default 属性，使用类型默认值
-    /// attrs_map.insert("field_name".to_string(), mf_model::schema::AttributeSpec {
-    ///     default: Some(serde_json::json!(String::default()))
-    /// });
+    /// attrs_map.insert("field_name".to_string(), mf_model::schema::AttributeSpec::new(Some(serde_json::json!(String::default()))));
     /// ```
     ///
     /// # 设计原则体现
@@ -384,9 +379,7 @@ impl<'a> MarkGenerator<'a> {
 
         // 生成属性设置代码，创建 AttributeSpec
         let attr_code = quote! {
-            attrs_map.insert(#field_name.to_string(), mf_model::schema::AttributeSpec {
-                default: Some(#default_value_expr)
-            });
+            attrs_map.insert(#field_name.to_string(), mf_model::schema::AttributeSpec::new(Some(#default_value_expr)));
         };
 
         Ok(attr_code)
@@ -126,9 +126,7 @@ impl<'a> NodeGenerator<'a> {
     ///         // 只为 #[attr] 字段构建属性映射
     ///         let mut attrs_map = std::collections::HashMap::new();
     ///         // 支持自定义类型表达式 (需要实现 Default + Serialize)
-    ///         attrs_map.insert("field_name".to_string(), AttributeSpec {
-    ///             default: Some(serde_json::to_value(CustomType::new()).unwrap_or(null))
-    ///         });
+    ///         attrs_map.insert("field_name".to_string(), AttributeSpec::new(Some(serde_json::to_value(CustomType::new()).unwrap_or(null))));
     ///         
     ///         // 构建 NodeSpec
     ///         let spec = NodeSpec { attrs: Some(attrs_map), ... };
@@ -226,6 +224,7 @@ impl<'a> NodeGenerator<'a> {
     ///     attrs: attrs,
     ///     group: None,
     ///     desc: None,
+    ///     sort_by: None,
     /// };
     /// ```
     ///
@@ -266,6 +265,7 @@ impl<'a> NodeGenerator<'a> {
                 attrs,
                 group: None,
                 desc: #desc,
+                sort_by: None,
             };
         };
 
@@ -288,19 +288,13 @@ impl<'a> NodeGenerator<'a> {
     /// let mut attrs_map = std::collections::HashMap::new();
     ///
     /// // 基本类型默认值
-    /// attrs_map.insert("title".to_string(), AttributeSpec {
-    ///     default: Some(serde_json::json!(String::default()))
-    /// });
+    /// attrs_map.insert("title".to_string(), AttributeSpec::new(Some(serde_json::json!(String::default()))));
     ///
     /// // 自定义类型表达式 (from #[attr(default="CustomType::new()")])
-    /// attrs_map.insert("custom_field".to_string(), AttributeSpec {
-    ///     default: Some(serde_json::to_value(CustomType::new()).unwrap_or(serde_json::json!(null)))
-    /// });
+    /// attrs_map.insert("custom_field".to_string(), AttributeSpec::new(Some(serde_json::to_value(CustomType::new()).unwrap_or(serde_json::json!(null)))));
     ///
     /// // Option 类型
-    /// attrs_map.insert("optional_field".to_string(), AttributeSpec {
-    ///     default: Some(serde_json::json!(null))
-    /// });
+    /// attrs_map.insert("optional_field".to_string(), AttributeSpec::new(Some(serde_json::json!(null))));
     ///
     /// let attrs = Some(attrs_map);
     /// ```
@@ -524,9 +518,7 @@ impl<'a> NodeGenerator<'a> {
 
         // 生成属性设置代码，创建 AttributeSpec
         let attr_code = quote! {
-            attrs_map.insert(#field_name.to_string(), mf_model::schema::AttributeSpec {
-                default: Some(#default_value_expr)
-            });
+            attrs_map.insert(#field_name.to_string(), mf_model::schema::AttributeSpec::new(Some(#default_value_expr)));
         };
 
         Ok(attr_code)
@@ -549,14 +541,10 @@ impl<'a> NodeGenerator<'a> {
     ///
     /// ```rust
     /// // 如果有 default 属性，使用 default 值
-    /// attrs_map.insert("field_name".to_string(), mf_model::schema::AttributeSpec {
-    ///     default: Some(serde_json::json!("default_value"))
-    /// });
+    /// attrs_map.insert("field_name".to_string(), mf_model::schema::AttributeSpec::new(Some(serde_json::json!("default_value"))));
     ///
     /// // 如果没有 default 属性，使用类型默认值
-    /// attrs_map.insert("field_name".to_string(), mf_model::schema::AttributeSpec {
-    ///     default: Some(serde_json::json!(String::default()))
-    /// });
+    /// attrs_map.insert("field_name".to_string(), mf_model::schema::AttributeSpec::new(Some(serde_json::json!(String::default()))));
     /// ```
     ///
     /// # 设计原则体现
@@ -576,9 +564,7 @@ impl<'a> NodeGenerator<'a> {
 
         // 生成属性设置代码，创建 AttributeSpec
         let attr_code = quote! {
-            attrs_map.insert(#field_name.to_string(), mf_model::schema::AttributeSpec {
-                default: Some(#default_value_expr)
-            });
+            attrs_map.insert(#field_name.to_string(), mf_model::schema::AttributeSpec::new(Some(#default_value_expr)));
         };
 
         Ok(attr_code)
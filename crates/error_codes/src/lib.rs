@@ -0,0 +1,403 @@
+//! ModuForge-RS 统一错误码
+//!
+//! 为各 crate 的错误提供稳定的字符串错误码与可序列化的 [`ErrorWire`]，
+//! 供前端（Tauri、HTTP 客户端）按错误类别分支处理：
+//! - `ErrorCode`: 错误码注册表，字符串值属于对外 API，不可随意改名
+//! - `ErrorKind`: 错误类别（校验、冲突、未找到、内部错误等）
+//! - `ErrorWire`: 传输格式 `{ code, message, details }`
+//! - `ToWire`: 各错误类型到 `ErrorWire` 的转换
+//! - `CodedError`: 携带错误码的错误，用于基于 anyhow 的 crate
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// 错误类别，前端据此决定提示方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// 输入或数据不合法
+    Validation,
+    /// 与当前状态或并发修改冲突
+    Conflict,
+    /// 目标不存在
+    NotFound,
+    /// 操作超时
+    Timeout,
+    /// 操作被取消
+    Cancelled,
+    /// 资源或外部依赖不可用
+    Unavailable,
+    /// 内部错误
+    Internal,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Validation => "validation",
+            ErrorKind::Conflict => "conflict",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Cancelled => "cancelled",
+            ErrorKind::Unavailable => "unavailable",
+            ErrorKind::Internal => "internal",
+        }
+    }
+}
+
+macro_rules! error_codes {
+    ($($(#[$meta:meta])* $variant:ident => $code:literal, $kind:ident;)*) => {
+        /// 错误码注册表
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum ErrorCode {
+            $($(#[$meta])* $variant,)*
+        }
+
+        impl ErrorCode {
+            /// 全部错误码，按声明顺序排列
+            pub const ALL: &'static [ErrorCode] = &[$(ErrorCode::$variant,)*];
+
+            /// 稳定的字符串错误码
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(ErrorCode::$variant => $code,)*
+                }
+            }
+
+            pub fn kind(&self) -> ErrorKind {
+                match self {
+                    $(ErrorCode::$variant => ErrorKind::$kind,)*
+                }
+            }
+
+            pub fn parse(code: &str) -> Option<ErrorCode> {
+                match code {
+                    $($code => Some(ErrorCode::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    // -- mf_core::ForgeError --
+    State => "STATE_ERROR", Internal;
+    Event => "EVENT_ERROR", Internal;
+    Middleware => "MIDDLEWARE_ERROR", Internal;
+    Extension => "EXTENSION_ERROR", Internal;
+    Transaction => "TRANSACTION_ERROR", Internal;
+    History => "HISTORY_ERROR", Internal;
+    Config => "CONFIG_ERROR", Validation;
+    Storage => "STORAGE_ERROR", Internal;
+    Cache => "CACHE_ERROR", Internal;
+    Engine => "ENGINE_ERROR", Internal;
+    Timeout => "TIMEOUT_ERROR", Timeout;
+    Cancelled => "CANCELLED", Cancelled;
+    ResourceExhausted => "RESOURCE_EXHAUSTED", Unavailable;
+    Concurrency => "CONCURRENCY_ERROR", Conflict;
+    Validation => "VALIDATION_ERROR", Validation;
+    ExternalDependency => "EXTERNAL_DEPENDENCY_ERROR", Unavailable;
+    Internal => "INTERNAL_ERROR", Internal;
+    Other => "OTHER_ERROR", Internal;
+
+    // -- mf_model 节点池 --
+    ModelNodeNotFound => "MODEL_NODE_NOT_FOUND", NotFound;
+    ModelParentNotFound => "MODEL_PARENT_NOT_FOUND", NotFound;
+    ModelChildNotFound => "MODEL_CHILD_NOT_FOUND", NotFound;
+    ModelNodeDeleted => "MODEL_NODE_DELETED", NotFound;
+    ModelDuplicateNode => "MODEL_DUPLICATE_NODE", Conflict;
+    ModelNodeLocked => "MODEL_NODE_LOCKED", Conflict;
+    /// 孤立节点、错误的父子关系、循环引用等结构错误
+    ModelInvalidStructure => "MODEL_INVALID_STRUCTURE", Validation;
+    ModelSchema => "MODEL_SCHEMA_ERROR", Validation;
+
+    // -- mf_state --
+    StatePluginInit => "STATE_PLUGIN_INIT_FAILED", Internal;
+    StatePluginApply => "STATE_PLUGIN_APPLY_FAILED", Internal;
+    StateTransaction => "STATE_TRANSACTION_FAILED", Validation;
+    StateConfig => "STATE_CONFIG_ERROR", Validation;
+    StateField => "STATE_FIELD_ERROR", Internal;
+    StateSchema => "STATE_SCHEMA_ERROR", Validation;
+    StatePluginNotFound => "STATE_PLUGIN_NOT_FOUND", NotFound;
+    StateInvalidPluginState => "STATE_INVALID_PLUGIN_STATE", Internal;
+    StateSerialize => "STATE_SERIALIZE_FAILED", Internal;
+    StateDeserialize => "STATE_DESERIALIZE_FAILED", Validation;
+    StateDivergence => "STATE_DIVERGENCE_CONFLICT", Conflict;
+
+    // -- mf_transform --
+    TransformApply => "TRANSFORM_APPLY_FAILED", Validation;
+    TransformRebaseConflict => "TRANSFORM_REBASE_CONFLICT", Conflict;
+    TransformRebaseApply => "TRANSFORM_REBASE_APPLY_FAILED", Conflict;
+
+    // -- mf_file --
+    FileIo => "FILE_IO_ERROR", Internal;
+    FileNotFound => "FILE_NOT_FOUND", NotFound;
+    FileBadHeader => "FILE_BAD_HEADER", Validation;
+    FileRecordTooLarge => "FILE_RECORD_TOO_LARGE", Validation;
+    FileEmptyRecord => "FILE_EMPTY_RECORD", Validation;
+    FileCrcMismatch => "FILE_CRC_MISMATCH", Validation;
+    FileUnsupportedFormat => "FILE_UNSUPPORTED_FORMAT", Validation;
+
+    // -- 应用层命令分发 --
+    CommandNotFound => "COMMAND_NOT_FOUND", NotFound;
+    CommandInvalidParams => "COMMAND_INVALID_PARAMS", Validation;
+    CommandEditorNotFound => "COMMAND_EDITOR_NOT_FOUND", NotFound;
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D
+    ) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        ErrorCode::parse(&code).ok_or_else(|| {
+            serde::de::Error::custom(format!("未知的错误码: {code}"))
+        })
+    }
+}
+
+/// 错误的传输格式
+///
+/// `details` 始终是对象，至少包含 `kind`（[`ErrorKind`]）与
+/// `sources`（源错误链的消息，由外到内）。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorWire {
+    pub code: String,
+    pub message: String,
+    pub details: Value,
+}
+
+impl ErrorWire {
+    pub fn new(
+        code: ErrorCode,
+        message: impl Into<String>,
+    ) -> Self {
+        let mut details = Map::new();
+        details.insert("kind".into(), Value::from(code.kind().as_str()));
+        details.insert("sources".into(), Value::Array(Vec::new()));
+        ErrorWire {
+            code: code.as_str().to_string(),
+            message: message.into(),
+            details: Value::Object(details),
+        }
+    }
+
+    /// 附加一个详情字段，`None` 值会被忽略
+    pub fn with_detail(
+        mut self,
+        key: &str,
+        value: impl Into<Value>,
+    ) -> Self {
+        let value = value.into();
+        if let (Some(details), false) =
+            (self.details.as_object_mut(), value.is_null())
+        {
+            details.insert(key.to_string(), value);
+        }
+        self
+    }
+
+    /// 记录源错误链
+    pub fn with_sources(
+        mut self,
+        sources: Vec<String>,
+    ) -> Self {
+        if let Some(details) = self.details.as_object_mut() {
+            details.insert(
+                "sources".into(),
+                Value::Array(sources.into_iter().map(Value::from).collect()),
+            );
+        }
+        self
+    }
+
+    /// 解析错误码，未知错误码返回 `None`
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        ErrorCode::parse(&self.code)
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+}
+
+impl fmt::Display for ErrorWire {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)
+    }
+}
+
+/// 转换为 [`ErrorWire`]
+pub trait ToWire {
+    fn to_wire(&self) -> ErrorWire;
+}
+
+/// 携带错误码的错误
+///
+/// 基于 anyhow 的 crate 用它构造错误，`Display` 只输出 `message`，
+/// 因此不会改变已有的错误文本。
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{message}")]
+pub struct CodedError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl CodedError {
+    pub fn new(
+        code: ErrorCode,
+        message: impl Into<String>,
+    ) -> Self {
+        CodedError { code, message: message.into() }
+    }
+}
+
+/// 构造携带错误码的 anyhow 错误
+pub fn coded(
+    code: ErrorCode,
+    message: impl Into<String>,
+) -> anyhow::Error {
+    anyhow::Error::new(CodedError::new(code, message))
+}
+
+/// 在 anyhow 错误链中查找第一个错误码
+pub fn find_code(err: &anyhow::Error) -> Option<ErrorCode> {
+    err.chain().find_map(|e| e.downcast_ref::<CodedError>().map(|c| c.code))
+}
+
+/// 源错误链的消息（不含错误本身）
+pub fn source_chain(err: &(dyn std::error::Error + 'static)) -> Vec<String> {
+    let mut sources = Vec::new();
+    let mut current = err.source();
+    while let Some(source) = current {
+        sources.push(source.to_string());
+        current = source.source();
+    }
+    sources
+}
+
+impl ToWire for CodedError {
+    fn to_wire(&self) -> ErrorWire {
+        ErrorWire::new(self.code, self.message.clone())
+    }
+}
+
+/// 未携带错误码的 anyhow 错误视为 [`ErrorCode::Internal`]
+impl ToWire for anyhow::Error {
+    fn to_wire(&self) -> ErrorWire {
+        let code = find_code(self).unwrap_or(ErrorCode::Internal);
+        ErrorWire::new(code, self.to_string())
+            .with_sources(self.chain().skip(1).map(|e| e.to_string()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 错误码属于对外 API，改名或删除必须同步更新此列表
+    const CODE_SNAPSHOT: &[(&str, &str)] = &[
+        ("STATE_ERROR", "internal"),
+        ("EVENT_ERROR", "internal"),
+        ("MIDDLEWARE_ERROR", "internal"),
+        ("EXTENSION_ERROR", "internal"),
+        ("TRANSACTION_ERROR", "internal"),
+        ("HISTORY_ERROR", "internal"),
+        ("CONFIG_ERROR", "validation"),
+        ("STORAGE_ERROR", "internal"),
+        ("CACHE_ERROR", "internal"),
+        ("ENGINE_ERROR", "internal"),
+        ("TIMEOUT_ERROR", "timeout"),
+        ("CANCELLED", "cancelled"),
+        ("RESOURCE_EXHAUSTED", "unavailable"),
+        ("CONCURRENCY_ERROR", "conflict"),
+        ("VALIDATION_ERROR", "validation"),
+        ("EXTERNAL_DEPENDENCY_ERROR", "unavailable"),
+        ("INTERNAL_ERROR", "internal"),
+        ("OTHER_ERROR", "internal"),
+        ("MODEL_NODE_NOT_FOUND", "not_found"),
+        ("MODEL_PARENT_NOT_FOUND", "not_found"),
+        ("MODEL_CHILD_NOT_FOUND", "not_found"),
+        ("MODEL_NODE_DELETED", "not_found"),
+        ("MODEL_DUPLICATE_NODE", "conflict"),
+        ("MODEL_NODE_LOCKED", "conflict"),
+        ("MODEL_INVALID_STRUCTURE", "validation"),
+        ("MODEL_SCHEMA_ERROR", "validation"),
+        ("STATE_PLUGIN_INIT_FAILED", "internal"),
+        ("STATE_PLUGIN_APPLY_FAILED", "internal"),
+        ("STATE_TRANSACTION_FAILED", "validation"),
+        ("STATE_CONFIG_ERROR", "validation"),
+        ("STATE_FIELD_ERROR", "internal"),
+        ("STATE_SCHEMA_ERROR", "validation"),
+        ("STATE_PLUGIN_NOT_FOUND", "not_found"),
+        ("STATE_INVALID_PLUGIN_STATE", "internal"),
+        ("STATE_SERIALIZE_FAILED", "internal"),
+        ("STATE_DESERIALIZE_FAILED", "validation"),
+        ("STATE_DIVERGENCE_CONFLICT", "conflict"),
+        ("TRANSFORM_APPLY_FAILED", "validation"),
+        ("TRANSFORM_REBASE_CONFLICT", "conflict"),
+        ("TRANSFORM_REBASE_APPLY_FAILED", "conflict"),
+        ("FILE_IO_ERROR", "internal"),
+        ("FILE_NOT_FOUND", "not_found"),
+        ("FILE_BAD_HEADER", "validation"),
+        ("FILE_RECORD_TOO_LARGE", "validation"),
+        ("FILE_EMPTY_RECORD", "validation"),
+        ("FILE_CRC_MISMATCH", "validation"),
+        ("FILE_UNSUPPORTED_FORMAT", "validation"),
+        ("COMMAND_NOT_FOUND", "not_found"),
+        ("COMMAND_INVALID_PARAMS", "validation"),
+        ("COMMAND_EDITOR_NOT_FOUND", "not_found"),
+    ];
+
+    #[test]
+    fn code_list_snapshot() {
+        let actual: Vec<(&str, &str)> = ErrorCode::ALL
+            .iter()
+            .map(|c| (c.as_str(), c.kind().as_str()))
+            .collect();
+        assert_eq!(actual, CODE_SNAPSHOT);
+        for code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::parse(code.as_str()), Some(*code));
+        }
+    }
+
+    #[test]
+    fn anyhow_to_wire_keeps_code_and_sources() {
+        let err = coded(ErrorCode::StatePluginNotFound, "插件未找到: history")
+            .context("加载状态失败");
+        let wire = err.to_wire();
+        assert_eq!(wire.code, "STATE_PLUGIN_NOT_FOUND");
+        assert_eq!(wire.message, "加载状态失败");
+        assert_eq!(wire.details["kind"], "not_found");
+        assert_eq!(wire.details["sources"][0], "插件未找到: history");
+
+        let plain = anyhow::anyhow!("boom").to_wire();
+        assert_eq!(plain.error_code(), Some(ErrorCode::Internal));
+        let json = serde_json::to_value(&plain).unwrap();
+        assert_eq!(json["code"], "INTERNAL_ERROR");
+        assert_eq!(json["message"], "boom");
+    }
+}
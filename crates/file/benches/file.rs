@@ -21,6 +21,7 @@ fn bench_file_operations(c: &mut Criterion) {
             let wrapper = TypeWrapper {
                 type_id: "test".to_string(),
                 data: b"test data".to_vec(),
+                actor: None,
             };
             criterion::black_box(wrapper)
         })
@@ -31,6 +32,7 @@ fn bench_file_operations(c: &mut Criterion) {
         let test_data = vec![TypeWrapper {
             type_id: "test".to_string(),
             data: b"test data".to_vec(),
+            actor: None,
         }];
         b.iter(|| {
             let encoded = encode_history_frames(&test_data, false);
@@ -0,0 +1,85 @@
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use mf_file::{ZipDocumentReader, ZipDocumentWriter};
+use std::fs::File;
+use std::path::Path;
+
+/// 夹具条目数量
+const ENTRIES: usize = 500;
+
+fn entry_value(
+    index: usize,
+    revision: usize,
+) -> serde_json::Value {
+    serde_json::json!({
+        "index": index,
+        "revision": revision,
+        "items": (0..64).map(|i| format!("node-{index}-{i}")).collect::<Vec<_>>(),
+    })
+}
+
+// 完整写入：每次保存重写全部条目（当前的保存方式）
+fn write_full(
+    path: &Path,
+    changed: usize,
+    revision: usize,
+) {
+    let mut writer =
+        ZipDocumentWriter::new(File::create(path).unwrap()).unwrap();
+    for i in 0..ENTRIES {
+        let rev = if i == changed { revision } else { 0 };
+        writer
+            .add_json(&format!("entries/{i}.json"), &entry_value(i, rev))
+            .unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+/// 自动保存延迟基准：500 个条目中只有 1 个发生变更
+fn bench_autosave(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = dir.path().join("fixture.ysf");
+    write_full(&fixture, 0, 0);
+    let target = dir.path().join("doc.ysf");
+
+    let mut group = c.benchmark_group("自动保存");
+    group.sample_size(30);
+
+    group.bench_function("完整重写", |b| {
+        let mut revision = 0;
+        b.iter(|| {
+            revision += 1;
+            write_full(&target, 42, revision);
+        })
+    });
+
+    group.bench_function("增量追加", |b| {
+        let mut revision = 0;
+        b.iter_batched(
+            || {
+                std::fs::copy(&fixture, &target).unwrap();
+            },
+            |_| {
+                revision += 1;
+                let mut updater =
+                    ZipDocumentWriter::open_existing(&target).unwrap();
+                updater
+                    .add_json("entries/42.json", &entry_value(42, revision))
+                    .unwrap();
+                criterion::black_box(updater.commit().unwrap())
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.finish();
+
+    // 校验增量保存后读取到的是最新版本
+    let mut reader = ZipDocumentReader::open(&target).unwrap();
+    let value: serde_json::Value =
+        serde_json::from_slice(&reader.read_all("entries/42.json").unwrap())
+            .unwrap();
+    assert!(value["revision"].as_u64().unwrap() > 0);
+}
+
+criterion_group!(benches, bench_autosave);
+criterion_main!(benches);
@@ -6,7 +6,7 @@ use crate::common::{
     DEFAULT_ZSTD_LEVEL, TAIL_MAGIC, TAIL_POINTER_SIZE, ZSTD_MAGIC_PREFIX,
 };
 use crate::error::{FileError, Result};
-use crate::document::{Directory, SegmentEntry, SegmentType};
+use crate::document::{Directory, SegmentEntry, SegmentTable, SegmentType};
 use crate::parallel_compression::{AsyncParallelCompressor, ParallelCompressionConfig};
 use blake3::Hasher as Blake3;
 use futures::stream::{Stream, StreamExt};
@@ -19,7 +19,7 @@ use tokio::io::{AsyncSeekExt, AsyncWriteExt};
 /// Async document writer with parallel compression support
 pub struct AsyncDocumentWriter {
     writer: Arc<crate::async_record::AsyncWriter>,  // 底层异步记录写入器 / Underlying async record writer
-    segments: Arc<Mutex<Vec<SegmentEntry>>>,        // 段条目列表 / List of segment entries
+    segments: Arc<Mutex<SegmentTable>>,             // 段条目列表（按内容去重）/ Segment table, deduplicated by content
     compressor: Arc<AsyncParallelCompressor>,       // 异步并行压缩器 / Async parallel compressor
     enable_parallel: bool,                          // 是否启用并行压缩 / Whether parallel compression is enabled
     path: PathBuf,                                  // 文件路径（用于哈希计算和尾指针写入）/ File path (for hash calculation and tail pointer)
@@ -51,7 +51,7 @@ impl AsyncDocumentWriter {
 
         Ok(Self {
             writer: Arc::new(writer),
-            segments: Arc::new(Mutex::new(Vec::new())),
+            segments: Arc::new(Mutex::new(SegmentTable::default())),
             compressor: Arc::new(compressor),
             enable_parallel,
             path: path_buf,
@@ -68,6 +68,13 @@ impl AsyncDocumentWriter {
         // 验证负载不为空
         validate_payload(&payload)?;
 
+        // 内容相同的段已写入时只记录目录项
+        // Identical payloads are stored once and referenced as shared segments
+        let digest = SegmentTable::digest(&payload);
+        if self.segments.lock().await.push_shared(&kind, &digest) {
+            return Ok(());
+        }
+
         // 压缩数据
         // Compress data
         let compressed = if self.enable_parallel {
@@ -94,12 +101,15 @@ impl AsyncDocumentWriter {
         // 添加到段列表
         // Add to segment list
         let mut segments = self.segments.lock().await;
-        segments.push(SegmentEntry {
-            kind,
-            offset,
-            length: (crate::record::REC_HDR as u64) + compressed.len() as u64,
-            crc32: crc,
-        });
+        segments.push(
+            SegmentEntry {
+                kind,
+                offset,
+                length: (crate::record::REC_HDR as u64) + compressed.len() as u64,
+                crc32: crc,
+            },
+            digest,
+        );
 
         Ok(())
     }
@@ -129,22 +139,17 @@ impl AsyncDocumentWriter {
         let hash = self.calculate_hash().await?;
 
         // 创建目录
-        let segments = self.segments.lock().await.clone();
+        let segments = std::mem::take(&mut *self.segments.lock().await);
         let flags = if self.enable_parallel {
             DIR_FLAG_ZSTD_SEGMENTS | DIR_FLAG_PARALLEL_COMPRESSION
         } else {
             DIR_FLAG_ZSTD_SEGMENTS
         };
 
-        let dir = Directory {
-            entries: segments,
-            flags,
-            file_hash: hash,
-        };
+        let dir = segments.into_directory(flags, hash);
 
         // 序列化并追加目录
-        let bytes = bincode::serde::encode_to_vec(&dir, bincode::config::standard())
-            .map_err(|e| FileError::Io(std::io::Error::other(e)))?;
+        let bytes = dir.encode()?;
 
         let dir_off = self.writer.append(&bytes).await?;
         self.writer.flush().await?;
@@ -267,12 +272,7 @@ impl AsyncDocumentReader {
         };
 
         // 反序列化目录
-        let dir: Directory = bincode::serde::decode_from_slice(
-            &dir_bytes,
-            bincode::config::standard()
-        )
-        .map_err(|e| FileError::Io(std::io::Error::other(e)))?
-        .0;
+        let dir = Directory::decode(&dir_bytes)?;
 
         // 根据标志位配置压缩器
         let compression_config = if has_parallel_compression(dir.flags) {
@@ -305,8 +305,13 @@ impl AsyncDocumentReader {
                 let compressed = self.reader.get_at(entry.offset).await?;
 
                 // 验证 CRC
-                if crc32fast::hash(&compressed) != entry.crc32 {
-                    return Err(FileError::CrcMismatch(entry.offset));
+                let actual = crc32fast::hash(&compressed);
+                if actual != entry.crc32 {
+                    return Err(FileError::CrcMismatch {
+                        offset: entry.offset,
+                        expected: entry.crc32,
+                        actual,
+                    });
                 }
 
                 // 解压数据（兼容同步和异步生成的文件）
@@ -351,8 +356,13 @@ impl AsyncDocumentReader {
         let futures = entries.into_iter().map(|entry| async move {
             let compressed = self.reader.get_at(entry.offset).await?;
 
-            if crc32fast::hash(&compressed) != entry.crc32 {
-                return Err(FileError::CrcMismatch(entry.offset));
+            let actual = crc32fast::hash(&compressed);
+            if actual != entry.crc32 {
+                return Err(FileError::CrcMismatch {
+                    offset: entry.offset,
+                    expected: entry.crc32,
+                    actual,
+                });
             }
 
             if has_parallel_compression(self.dir.flags) {
@@ -387,8 +397,13 @@ impl AsyncDocumentReader {
                 async move {
                     let compressed = reader.get_at(entry.offset).await?;
 
-                    if crc32fast::hash(&compressed) != entry.crc32 {
-                        return Err(FileError::CrcMismatch(entry.offset));
+                    let actual = crc32fast::hash(&compressed);
+                    if actual != entry.crc32 {
+                        return Err(FileError::CrcMismatch {
+                            offset: entry.offset,
+                            expected: entry.crc32,
+                            actual,
+                        });
                     }
 
                     let decompressed = if has_parallel_compression(flags) {
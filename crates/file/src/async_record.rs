@@ -244,8 +244,13 @@ impl AsyncReader {
         }
 
         let payload = &self.mmap[s..e];
-        if crc32(payload) != stored_crc {
-            return Err(FileError::CrcMismatch(offset));
+        let actual = crc32(payload);
+        if actual != stored_crc {
+            return Err(FileError::CrcMismatch {
+                offset,
+                expected: stored_crc,
+                actual,
+            });
         }
 
         Ok(Bytes::copy_from_slice(payload))
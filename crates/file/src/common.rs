@@ -21,6 +21,16 @@ pub const TAIL_POINTER_SIZE: usize = 16; // 8字节魔数 + 8字节偏移量
 /// Directory flags
 pub const DIR_FLAG_ZSTD_SEGMENTS: u32 = 0x0001;         // 段使用zstd压缩
 pub const DIR_FLAG_PARALLEL_COMPRESSION: u32 = 0x0002;  // 启用并行压缩
+pub const DIR_FLAG_SHARED_SEGMENTS: u32 = 0x0004;       // 存在共享段
+
+/// 段条目标志位
+/// Segment entry flags
+///
+/// 共享段不单独存储字节，而是引用之前内容相同的段的记录；
+/// 只要还有任一条目引用，该记录就必须保留（删除或压缩时需注意）
+/// A shared entry references the record of an earlier identical segment;
+/// the record stays live while any entry references it
+pub const SEGMENT_FLAG_SHARED: u32 = 0x0001;
 
 /// 默认zstd压缩级别
 /// Default zstd compression level
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io;
 use std::io::{Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
@@ -9,7 +10,8 @@ use crate::common::{
     encode_segment, decode_segment,
     create_tail_pointer, parse_tail_pointer, validate_tail_offset,
     validate_payload,
-    DIR_FLAG_ZSTD_SEGMENTS, TAIL_MAGIC, TAIL_POINTER_SIZE,
+    DIR_FLAG_SHARED_SEGMENTS, DIR_FLAG_ZSTD_SEGMENTS, SEGMENT_FLAG_SHARED,
+    TAIL_POINTER_SIZE,
 };
 use crate::error::{FileError, Result};
 use crate::record::{crc32, read_u32_le, Reader, Writer, HEADER_LEN, REC_HDR};
@@ -36,13 +38,141 @@ pub struct Directory {
     pub entries: Vec<SegmentEntry>,  // 所有段的条目列表
     pub flags: u32,                  // 目录标志（压缩等）
     pub file_hash: [u8; 32],         // 文件内容的Blake3哈希
+    /// 与 entries 一一对应的条目标志（见 `SEGMENT_FLAG_SHARED`），旧文件中为空
+    /// Entry flags parallel to entries, empty in older files
+    #[serde(default)]
+    pub entry_flags: Vec<u32>,
+}
+
+/// 不含条目标志的旧版目录布局
+/// Directory layout of files written before entry flags existed
+#[derive(Deserialize)]
+struct LegacyDirectory {
+    entries: Vec<SegmentEntry>,
+    flags: u32,
+    file_hash: [u8; 32],
+}
+
+impl Directory {
+    /// 序列化目录
+    /// Encode the directory
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .map_err(io::Error::other)
+            .map_err(FileError::Io)
+    }
+
+    /// 反序列化目录，兼容不含条目标志的旧文件，并校验共享段引用
+    /// Decode the directory, accepting the legacy layout, and validate shared references
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let config = bincode::config::standard();
+        let dir = match bincode::serde::decode_from_slice::<Directory, _>(bytes, config) {
+            Ok((dir, _)) => dir,
+            Err(_) => {
+                let (legacy, _) = bincode::serde::decode_from_slice::<LegacyDirectory, _>(bytes, config)
+                    .map_err(io::Error::other)
+                    .map_err(FileError::Io)?;
+                Directory {
+                    entries: legacy.entries,
+                    flags: legacy.flags,
+                    file_hash: legacy.file_hash,
+                    entry_flags: Vec::new(),
+                }
+            },
+        };
+        dir.validate_shared()?;
+        Ok(dir)
+    }
+
+    /// 条目是否为共享段
+    /// Whether the entry shares the bytes of an earlier entry
+    pub fn is_shared(&self, index: usize) -> bool {
+        self.entry_flags.get(index).is_some_and(|f| f & SEGMENT_FLAG_SHARED != 0)
+    }
+
+    /// 实际存储的段字节数，共享段只计一次
+    /// Bytes of segment records actually stored, counting shared records once
+    pub fn stored_len(&self) -> u64 {
+        (0..self.entries.len())
+            .filter(|&i| !self.is_shared(i))
+            .map(|i| self.entries[i].length)
+            .sum()
+    }
+
+    // 共享段必须引用之前某个非共享条目的记录（偏移、长度与CRC一致）
+    fn validate_shared(&self) -> Result<()> {
+        if !self.entry_flags.is_empty() && self.entry_flags.len() != self.entries.len() {
+            return Err(FileError::BadHeader);
+        }
+        let mut owners: HashMap<u64, &SegmentEntry> = HashMap::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            if !self.is_shared(index) {
+                owners.insert(entry.offset, entry);
+                continue;
+            }
+            match owners.get(&entry.offset) {
+                Some(owner) if owner.length == entry.length && owner.crc32 == entry.crc32 => {},
+                _ => return Err(FileError::BadHeader),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 写入中的段表：按负载内容去重，内容相同的段只存储一次
+/// Segment table used while writing: content-addressed deduplication of payloads
+#[derive(Debug, Default)]
+pub(crate) struct SegmentTable {
+    entries: Vec<SegmentEntry>,
+    entry_flags: Vec<u32>,
+    digests: HashMap<[u8; 32], usize>,
+}
+
+impl SegmentTable {
+    /// 负载的内容哈希
+    pub(crate) fn digest(payload: &[u8]) -> [u8; 32] {
+        *blake3::hash(payload).as_bytes()
+    }
+
+    /// 已存在相同内容的段时追加一个共享条目并返回 true
+    pub(crate) fn push_shared(&mut self, kind: &SegmentType, digest: &[u8; 32]) -> bool {
+        let Some(&owner) = self.digests.get(digest) else {
+            return false;
+        };
+        let entry = SegmentEntry { kind: kind.clone(), ..self.entries[owner].clone() };
+        self.entries.push(entry);
+        self.entry_flags.push(SEGMENT_FLAG_SHARED);
+        true
+    }
+
+    /// 追加一个新写入的段
+    pub(crate) fn push(&mut self, entry: SegmentEntry, digest: [u8; 32]) {
+        self.digests.entry(digest).or_insert(self.entries.len());
+        self.entries.push(entry);
+        self.entry_flags.push(0);
+    }
+
+    /// 生成目录，存在共享段时附加 `DIR_FLAG_SHARED_SEGMENTS`
+    pub(crate) fn into_directory(self, flags: u32, file_hash: [u8; 32]) -> Directory {
+        let shared = self.entry_flags.iter().any(|f| f & SEGMENT_FLAG_SHARED != 0);
+        Directory {
+            entries: self.entries,
+            flags: if shared { flags | DIR_FLAG_SHARED_SEGMENTS } else { flags },
+            file_hash,
+            entry_flags: self.entry_flags,
+        }
+    }
 }
 
 /// 文档写入器：基于append-only模式写入段，并在末尾写入目录
 /// Document writer: writes segments in append-only mode and writes directory at the end
+///
+/// 内容相同的段只存储一次，后续条目以共享段引用已有记录，
+/// 适合在一个文件中保存多个相近的文档版本
+/// Identical payloads are stored once; later entries reference the existing record
 pub struct DocumentWriter {
     w: Writer,                    // 底层记录写入器
-    segments: Vec<SegmentEntry>,  // 已写入段的列表
+    segments: SegmentTable,       // 已写入段的列表
     path: PathBuf,                // 文件路径
 }
 impl DocumentWriter {
@@ -54,7 +184,7 @@ impl DocumentWriter {
     )))]
     pub fn begin<P: AsRef<Path>>(path: P) -> Result<Self> {
         let p = path.as_ref().to_path_buf();
-        Ok(Self { w: Writer::create(&p, 0)?, segments: Vec::new(), path: p })
+        Ok(Self { w: Writer::create(&p, 0)?, segments: SegmentTable::default(), path: p })
     }
 
     /// 追加一个段到文档
@@ -67,6 +197,12 @@ impl DocumentWriter {
         // 验证负载不为空
         validate_payload(payload)?;
 
+        // 内容相同的段已写入时只记录目录项
+        let digest = SegmentTable::digest(payload);
+        if self.segments.push_shared(&kind, &digest) {
+            return Ok(());
+        }
+
         // 压缩数据
         let stored = encode_segment(payload)?;
 
@@ -75,12 +211,15 @@ impl DocumentWriter {
         let crc = crc32(&stored);
 
         // 记录段信息
-        self.segments.push(SegmentEntry {
-            kind,
-            offset: off,
-            length: (REC_HDR as u64) + stored.len() as u64,
-            crc32: crc,
-        });
+        self.segments.push(
+            SegmentEntry {
+                kind,
+                offset: off,
+                length: (REC_HDR as u64) + stored.len() as u64,
+                crc32: crc,
+            },
+            digest,
+        );
         Ok(())
     }
 
@@ -88,7 +227,7 @@ impl DocumentWriter {
     /// Finalize writing: generate and write directory, calculate file hash
     #[cfg_attr(feature = "dev-tracing", tracing::instrument(skip(self), fields(
         crate_name = "file",
-        segment_count = self.segments.len(),
+        segment_count = self.segments.entries.len(),
         file_path = %self.path.display()
     )))]
     pub fn finalize(mut self) -> Result<()> {
@@ -98,18 +237,14 @@ impl DocumentWriter {
         let mut hasher = Blake3::new();
         let r = Reader::open(&self.path)?;
         for bytes in r.iter() {
-            hasher.update(bytes);
+            hasher.update(bytes?);
         }
         let hash = *hasher.finalize().as_bytes();
 
         // 创建并序列化目录
         // Create and serialize directory
-        let flags = DIR_FLAG_ZSTD_SEGMENTS;
-        let dir = Directory { entries: self.segments, flags, file_hash: hash };
-        let bytes =
-            bincode::serde::encode_to_vec(&dir, bincode::config::standard())
-                .map_err(io::Error::other)
-                .map_err(FileError::Io)?;
+        let dir = self.segments.into_directory(DIR_FLAG_ZSTD_SEGMENTS, hash);
+        let bytes = dir.encode()?;
         let dir_off = self.w.append(&bytes)?;
         self.w.flush()?;
 
@@ -192,14 +327,8 @@ impl DocumentReader {
             }
             last_off = fallback_last;
         }
-        let dir_bytes = r.get_at(last_off)?;
-        let (dir, _) = bincode::serde::decode_from_slice::<Directory, _>(
-            dir_bytes,
-            bincode::config::standard(),
-        )
-        .map_err(io::Error::other)
-        .map_err(FileError::Io)?;
-        // 校验除目录外的数据哈希
+        let dir = Directory::decode(r.get_at(last_off)?)?;
+        // 校验除目录外的数据哈希（共享段的字节只存储一次，哈希与目录条目数无关）
         let mut hasher = Blake3::new();
         let mut q = HEADER_LEN;
         let end2 = last_off as usize;
@@ -244,8 +373,13 @@ impl DocumentReader {
         for (index, entry) in self.dir.entries.iter().enumerate() {
             if entry.kind == kind {
                 let bytes = self.r.get_at(entry.offset)?;
-                if crc32(bytes) != entry.crc32 {
-                    return Err(FileError::CrcMismatch(entry.offset));
+                let actual = crc32(bytes);
+                if actual != entry.crc32 {
+                    return Err(FileError::CrcMismatch {
+                        offset: entry.offset,
+                        expected: entry.crc32,
+                        actual,
+                    });
                 }
                 let decoded = decode_segment(bytes, self.dir.flags)?;
                 callback(index, decoded.as_ref())?;
@@ -276,8 +410,13 @@ impl DocumentReader {
     ) -> Result<Vec<u8>> {
         let entry = self.dir.entries.get(index).ok_or(FileError::BadHeader)?;
         let bytes = self.r.get_at(entry.offset)?;
-        if crc32(bytes) != entry.crc32 {
-            return Err(FileError::CrcMismatch(entry.offset));
+        let actual = crc32(bytes);
+        if actual != entry.crc32 {
+            return Err(FileError::CrcMismatch {
+                offset: entry.offset,
+                expected: entry.crc32,
+                actual,
+            });
         }
         let decoded = decode_segment(bytes, self.dir.flags)?;
         Ok(decoded.into_owned())
@@ -316,4 +455,88 @@ mod tests {
         assert_eq!(seen, vec![vec![1, 2, 3, 4]]);
         Ok(())
     }
+
+    // 生成不可压缩的伪随机数据
+    fn noise(seed: u64, len: usize) -> Vec<u8> {
+        let mut x = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_segments_are_stored_once() -> Result<()> {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("versions.mff");
+
+        let base: Vec<Vec<u8>> = (0..4).map(|i| noise(i, 64 * 1024)).collect();
+        let mut versions = Vec::new();
+        for v in 0..3u64 {
+            let mut doc = base.clone();
+            doc[3] = noise(100 + v, 256);
+            versions.push(doc);
+        }
+
+        let mut writer = DocumentWriter::begin(&path)?;
+        for doc in &versions {
+            for payload in doc {
+                writer.add_segment(SegmentType("node".to_string()), payload)?;
+            }
+        }
+        writer.finalize()?;
+
+        let doc_len: usize = versions[0].iter().map(Vec::len).sum();
+        let file_len = std::fs::metadata(&path)?.len() as usize;
+        assert!(file_len < doc_len + doc_len / 10, "file {file_len} vs doc {doc_len}");
+
+        let reader = DocumentReader::open(&path)?;
+        let directory = reader.directory();
+        assert_eq!(directory.flags & DIR_FLAG_SHARED_SEGMENTS, DIR_FLAG_SHARED_SEGMENTS);
+        assert_eq!(reader.segments().len(), 12);
+        assert_eq!((0..12).filter(|&i| directory.is_shared(i)).count(), 6);
+        for (v, doc) in versions.iter().enumerate() {
+            for (i, payload) in doc.iter().enumerate() {
+                assert_eq!(&reader.segment_payload(v * 4 + i)?, payload);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn decode_legacy_directory() -> Result<()> {
+        #[derive(Serialize)]
+        struct Legacy {
+            entries: Vec<SegmentEntry>,
+            flags: u32,
+            file_hash: [u8; 32],
+        }
+        let entry = SegmentEntry { kind: SegmentType("json".to_string()), offset: 16, length: 32, crc32: 7 };
+        let legacy = Legacy { entries: vec![entry], flags: DIR_FLAG_ZSTD_SEGMENTS, file_hash: [1; 32] };
+        let bytes = bincode::serde::encode_to_vec(&legacy, bincode::config::standard()).unwrap();
+
+        let dir = Directory::decode(&bytes)?;
+        assert_eq!(dir.entries.len(), 1);
+        assert_eq!(dir.flags, DIR_FLAG_ZSTD_SEGMENTS);
+        assert!(dir.entry_flags.is_empty());
+        assert!(!dir.is_shared(0));
+        Ok(())
+    }
+
+    #[test]
+    fn shared_entry_must_reference_earlier_segment() {
+        let entry = SegmentEntry { kind: SegmentType("json".to_string()), offset: 16, length: 32, crc32: 7 };
+        let dir = Directory {
+            entries: vec![entry],
+            flags: DIR_FLAG_ZSTD_SEGMENTS | DIR_FLAG_SHARED_SEGMENTS,
+            file_hash: [0; 32],
+            entry_flags: vec![SEGMENT_FLAG_SHARED],
+        };
+        let bytes = dir.encode().unwrap();
+        assert!(matches!(Directory::decode(&bytes), Err(FileError::BadHeader)));
+    }
 }
@@ -1,5 +1,7 @@
 use std::io;
 
+use mf_error_codes::{ErrorCode, ErrorWire, ToWire, source_chain};
+
 #[derive(thiserror::Error, Debug)]
 pub enum FileError {
     #[error("IO 错误: {0}")]
@@ -10,8 +12,40 @@ pub enum FileError {
     RecordTooLarge(usize),
     #[error("记录为空")]
     EmptyRecord,
-    #[error("CRC 校验失败，偏移量 {0}")]
-    CrcMismatch(u64),
+    #[error(
+        "CRC 校验失败，偏移量 {offset}，期望 {expected:#010x}，实际 {actual:#010x}"
+    )]
+    CrcMismatch { offset: u64, expected: u32, actual: u32 },
 }
 
 pub type Result<T> = anyhow::Result<T, FileError>;
+
+impl ToWire for FileError {
+    fn to_wire(&self) -> ErrorWire {
+        let wire = match self {
+            FileError::Io(e) if e.kind() == io::ErrorKind::NotFound => {
+                ErrorWire::new(ErrorCode::FileNotFound, self.to_string())
+            },
+            FileError::Io(_) => {
+                ErrorWire::new(ErrorCode::FileIo, self.to_string())
+            },
+            FileError::BadHeader => {
+                ErrorWire::new(ErrorCode::FileBadHeader, self.to_string())
+            },
+            FileError::RecordTooLarge(size) => {
+                ErrorWire::new(ErrorCode::FileRecordTooLarge, self.to_string())
+                    .with_detail("size", *size)
+            },
+            FileError::EmptyRecord => {
+                ErrorWire::new(ErrorCode::FileEmptyRecord, self.to_string())
+            },
+            FileError::CrcMismatch { offset, expected, actual } => {
+                ErrorWire::new(ErrorCode::FileCrcMismatch, self.to_string())
+                    .with_detail("offset", *offset)
+                    .with_detail("expected", *expected)
+                    .with_detail("actual", *actual)
+            },
+        };
+        wire.with_sources(source_chain(self))
+    }
+}
@@ -1,21 +1,57 @@
 use std::io;
 use serde::{Deserialize, Serialize};
 
-// 步骤帧：type_id 表示类型，data 为该类型的序列化字节
+// 步骤帧：type_id 表示类型，data 为该类型的序列化字节，actor 为发起修改的用户
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeWrapper {
     pub type_id: String,
     pub data: Vec<u8>,
+    #[serde(default)]
+    pub actor: Option<String>,
 }
 
-// 编码步骤帧；可选 zstd 压缩
+// 新版编码的首字节；bincode 变长整数不会以 0xFF 开头，可与旧版区分
+const FRAMES_MARKER: u8 = 0xFF;
+// 当前步骤帧格式版本
+const FRAMES_VERSION: u8 = 1;
+
+impl TypeWrapper {
+    pub fn new(
+        type_id: impl Into<String>,
+        data: Vec<u8>,
+    ) -> Self {
+        Self { type_id: type_id.into(), data, actor: None }
+    }
+
+    // 记录发起修改的用户
+    pub fn with_actor(
+        mut self,
+        actor: Option<String>,
+    ) -> Self {
+        self.actor = actor;
+        self
+    }
+}
+
+// 不含 actor 的旧版步骤帧，仅用于解码
+#[derive(Deserialize)]
+struct LegacyTypeWrapper {
+    type_id: String,
+    data: Vec<u8>,
+}
+
+// 编码步骤帧：标记字节、版本字节后接 bincode 数据；可选 zstd 压缩
 pub fn encode_history_frames(
     frames: &[TypeWrapper],
     compress: bool,
 ) -> io::Result<Vec<u8>> {
-    let bytes =
-        bincode::serde::encode_to_vec(frames, bincode::config::standard())
-            .map_err(io::Error::other)?;
+    let mut bytes = vec![FRAMES_MARKER, FRAMES_VERSION];
+    bincode::serde::encode_into_std_write(
+        frames,
+        &mut bytes,
+        bincode::config::standard(),
+    )
+    .map_err(io::Error::other)?;
     if compress {
         Ok(zstd::stream::encode_all(&bytes[..], 1).map_err(io::Error::other)?)
     } else {
@@ -23,7 +59,7 @@ pub fn encode_history_frames(
     }
 }
 
-// 解码步骤帧；如 compressed 为真先解压
+// 解码步骤帧；如 compressed 为真先解压。兼容不含 actor 的旧版帧
 pub fn decode_history_frames(
     bytes: &[u8],
     compressed: bool,
@@ -33,10 +69,88 @@ pub fn decode_history_frames(
     } else {
         bytes.to_vec()
     };
-    let (frames, _) = bincode::serde::decode_from_slice::<Vec<TypeWrapper>, _>(
-        &raw,
-        bincode::config::standard(),
-    )
+    let config = bincode::config::standard();
+    if let [FRAMES_MARKER, version, body @ ..] = raw.as_slice() {
+        if *version != FRAMES_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("不支持的步骤帧版本 {version}"),
+            ));
+        }
+        let (frames, _) = bincode::serde::decode_from_slice(body, config)
+            .map_err(io::Error::other)?;
+        return Ok(frames);
+    }
+    let (frames, _) = bincode::serde::decode_from_slice::<
+        Vec<LegacyTypeWrapper>,
+        _,
+    >(&raw, config)
     .map_err(io::Error::other)?;
-    Ok(frames)
+    Ok(frames
+        .into_iter()
+        .map(|f| TypeWrapper { type_id: f.type_id, data: f.data, actor: None })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Legacy<'a> {
+        type_id: &'a str,
+        data: &'a [u8],
+    }
+
+    #[test]
+    fn test_history_frames_actor_roundtrip() {
+        let frames = vec![
+            TypeWrapper {
+                type_id: "attr_step".into(),
+                data: b"{}".to_vec(),
+                actor: Some("alice".into()),
+            },
+            TypeWrapper {
+                type_id: "add_mark_step".into(),
+                data: vec![1, 2, 3],
+                actor: None,
+            },
+        ];
+        for compress in [false, true] {
+            let bytes = encode_history_frames(&frames, compress).unwrap();
+            let decoded = decode_history_frames(&bytes, compress).unwrap();
+            assert_eq!(decoded[0].actor.as_deref(), Some("alice"));
+            assert_eq!(decoded[1].actor, None);
+            assert_eq!(decoded[1].data, vec![1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn test_history_frames_version_byte() {
+        let frames = vec![
+            TypeWrapper::new("attr_step", b"{}".to_vec())
+                .with_actor(Some("bob".into())),
+        ];
+        let mut bytes = encode_history_frames(&frames, false).unwrap();
+        assert_eq!(&bytes[..2], &[FRAMES_MARKER, FRAMES_VERSION]);
+        bytes[1] = FRAMES_VERSION + 1;
+        let err = decode_history_frames(&bytes, false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decode_legacy_history_frames() {
+        let legacy = vec![
+            Legacy { type_id: "attr_step", data: b"{}" },
+            Legacy { type_id: "move_node_step", data: &[0, 1] },
+        ];
+        let bytes =
+            bincode::serde::encode_to_vec(&legacy, bincode::config::standard())
+                .unwrap();
+        let decoded = decode_history_frames(&bytes, false).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[1].type_id, "move_node_step");
+        assert_eq!(decoded[1].data, vec![0, 1]);
+        assert!(decoded.iter().all(|f| f.actor.is_none()));
+    }
 }
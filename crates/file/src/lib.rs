@@ -12,13 +12,14 @@ pub mod parallel_compression;
 pub mod async_record;
 pub mod async_document;
 pub use error::{FileError, Result};
-pub use record::{Writer, Reader, Iter, HEADER_LEN, REC_HDR};
+pub use record::{Writer, Reader, Iter, UncheckedIter, HEADER_LEN, REC_HDR};
 pub use document::{
     DocumentWriter, DocumentReader, SegmentType, Directory, SegmentEntry,
 };
 pub use history::{TypeWrapper, encode_history_frames, decode_history_frames};
 pub use zipdoc::{
-    ZipDocumentWriter, ZipDocumentReader, MmapConfig, MmapStats,
+    ZipDocumentWriter, ZipDocumentReader, ZipDocumentUpdater, IncrementalStats,
+    MmapConfig, MmapStats,
     ZipStreamReader, FileSizeCategory, ProcessingStrategy, FileInfo,
     formats::strategy::{
         SnapshotFormat, export_zip_with_format, import_zip_with_format,
@@ -224,25 +224,62 @@ impl Reader {
             return Err(FileError::BadHeader);
         }
         let payload = &self.mmap[s..e];
-        if crc32(payload) != stored_crc {
-            return Err(FileError::CrcMismatch(offset));
+        let actual = crc32(payload);
+        if actual != stored_crc {
+            return Err(FileError::CrcMismatch {
+                offset,
+                expected: stored_crc,
+                actual,
+            });
         }
         Ok(payload)
     }
-    // 迭代所有记录（校验 CRC，遇到损坏或不完整即停止）
+    // 迭代所有记录并校验 CRC，校验失败时返回错误，随后停止
     pub fn iter(&self) -> Iter<'_> {
-        Iter { mmap: &self.mmap, p: HEADER_LEN, end: self.logical_end as usize }
+        Iter { inner: self.iter_unchecked() }
+    }
+    // 迭代所有记录但不校验 CRC，适合已确认完整性且追求吞吐的场景
+    pub fn iter_unchecked(&self) -> UncheckedIter<'_> {
+        UncheckedIter {
+            mmap: &self.mmap,
+            p: HEADER_LEN,
+            end: self.logical_end as usize,
+        }
     }
 }
 
+/// 校验 CRC 的记录迭代器
 pub struct Iter<'a> {
+    inner: UncheckedIter<'a>,
+}
+impl<'a> Iterator for Iter<'a> {
+    type Item = Result<&'a [u8]>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.inner.p as u64;
+        let (payload, expected) = self.inner.next_record()?;
+        let actual = crc32(payload);
+        if actual != expected {
+            // 损坏位置之后的记录边界不可信，不再继续
+            self.inner.p = self.inner.end;
+            return Some(Err(FileError::CrcMismatch {
+                offset,
+                expected,
+                actual,
+            }));
+        }
+        Some(Ok(payload))
+    }
+}
+
+/// 不校验 CRC 的记录迭代器
+pub struct UncheckedIter<'a> {
     mmap: &'a Mmap,
     p: usize,
     end: usize,
 }
-impl<'a> Iterator for Iter<'a> {
-    type Item = &'a [u8];
-    fn next(&mut self) -> Option<Self::Item> {
+impl<'a> UncheckedIter<'a> {
+    // 读取下一条记录的负载与存储的 CRC
+    fn next_record(&mut self) -> Option<(&'a [u8], u32)> {
         if self.p + REC_HDR > self.end {
             return None;
         }
@@ -256,12 +293,14 @@ impl<'a> Iterator for Iter<'a> {
         if e > self.end {
             return None;
         }
-        let payload = &self.mmap[s..e];
-        if crc32(payload) != stored_crc {
-            return None;
-        }
         self.p = e;
-        Some(payload)
+        Some((&self.mmap[s..e], stored_crc))
+    }
+}
+impl<'a> Iterator for UncheckedIter<'a> {
+    type Item = &'a [u8];
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().map(|(payload, _)| payload)
     }
 }
 
@@ -313,4 +352,39 @@ mod tests {
         assert_eq!(reader.logical_len(), HEADER_LEN as u64);
         assert_eq!(reader.iter().count(), 0);
     }
+
+    #[test]
+    fn iter_reports_crc_mismatch() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("crc.mff");
+
+        let mut writer = Writer::create(&path, 0).unwrap();
+        writer.append(b"first").unwrap();
+        let off2 = writer.append(b"second").unwrap();
+        writer.append(b"third").unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+
+        let reader = Reader::open(&path).unwrap();
+        // 打开后篡改第二条记录的负载
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(off2 + REC_HDR as u64)).unwrap();
+        file.write_all(b"X").unwrap();
+        file.sync_all().unwrap();
+
+        let mut iter = reader.iter();
+        assert_eq!(iter.next().unwrap().unwrap(), b"first");
+        match iter.next() {
+            Some(Err(FileError::CrcMismatch { offset, expected, actual })) => {
+                assert_eq!(offset, off2);
+                assert_eq!(expected, crc32(b"second"));
+                assert_eq!(actual, crc32(b"Xecond"));
+            },
+            other => panic!("应返回 CRC 校验错误: {other:?}"),
+        }
+        assert!(iter.next().is_none());
+
+        let unchecked: Vec<&[u8]> = reader.iter_unchecked().collect();
+        assert_eq!(unchecked, [&b"first"[..], b"Xecond", b"third"]);
+    }
 }
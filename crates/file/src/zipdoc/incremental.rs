@@ -0,0 +1,568 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+
+use tempfile::NamedTempFile;
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use super::writer::ZipDocumentWriter;
+
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const CENTRAL_SIGNATURE: u32 = 0x0201_4b50;
+const EOCD_LEN: usize = 22;
+const CENTRAL_HEADER_LEN: usize = 46;
+const LOCAL_HEADER_LEN: u64 = 30;
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// 中央目录中的一条记录（原始字节）
+#[derive(Clone)]
+struct CentralRecord {
+    name: Vec<u8>,
+    raw: Vec<u8>,
+}
+
+impl CentralRecord {
+    fn compressed_size(&self) -> u64 {
+        read_u32(&self.raw, 20) as u64
+    }
+
+    fn local_header_offset(&self) -> u64 {
+        read_u32(&self.raw, 42) as u64
+    }
+
+    fn set_local_header_offset(
+        &mut self,
+        offset: u64,
+    ) -> io::Result<()> {
+        let offset = u32::try_from(offset).map_err(|_| too_large())?;
+        self.raw[42..46].copy_from_slice(&offset.to_le_bytes());
+        Ok(())
+    }
+
+    // 估算条目在文件中占用的字节数（本地头 + 数据）
+    fn stored_len(&self) -> u64 {
+        let name_len = read_u16(&self.raw, 28) as u64;
+        let extra_len = read_u16(&self.raw, 30) as u64;
+        LOCAL_HEADER_LEN + name_len + extra_len + self.compressed_size()
+    }
+}
+
+/// 增量保存后的文件统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IncrementalStats {
+    /// 文件总大小（字节）
+    pub file_size: u64,
+    /// 被新版本覆盖或已删除条目占用的字节数（估算）
+    pub garbage_size: u64,
+}
+
+impl IncrementalStats {
+    /// 垃圾字节占文件大小的比例
+    pub fn garbage_ratio(&self) -> f64 {
+        if self.file_size == 0 {
+            0.0
+        } else {
+            self.garbage_size as f64 / self.file_size as f64
+        }
+    }
+}
+
+/// 写入者锁：同一文档同一时刻只允许一个增量写入或压缩
+///
+/// 使用 `<文档路径>.lock` 上的排他锁，进程退出时由系统自动释放
+struct WriterLock {
+    _file: File,
+}
+
+impl WriterLock {
+    fn acquire(path: &Path) -> io::Result<Self> {
+        let mut lock_path = OsString::from(path.as_os_str());
+        lock_path.push(".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(PathBuf::from(lock_path))?;
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                format!("文档正在被其他写入者更新: {}", path.display()),
+            )),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+}
+
+/// 增量更新器：只追加变更的条目并写入新的中央目录
+///
+/// 通过 [`ZipDocumentWriter::open_existing`] 创建，解引用为
+/// `ZipDocumentWriter`，因此 `add_json`、`add_plugin_state` 等方法可直接使用；
+/// 与原有条目同名的新条目会替换旧版本。必须调用 [`commit`](Self::commit)
+/// 才会写入文件，直接丢弃则不修改文档。
+///
+/// 旧版本的数据保留在文件中成为垃圾字节，可用
+/// [`ZipDocumentWriter::compact`] 在垃圾比例过高时重写文件。
+/// 提交过程中不会修改已有字节，并持有文档文件的排他锁，
+/// 通过 [`ZipDocumentReader::open`](super::ZipDocumentReader::open)
+/// 打开的读取器总能看到完整的某一版本。
+pub struct ZipDocumentUpdater {
+    writer: ZipDocumentWriter<Cursor<Vec<u8>>>,
+    file: File,
+    records: Vec<CentralRecord>,
+    removed: HashSet<String>,
+    _lock: WriterLock,
+}
+
+impl ZipDocumentWriter<File> {
+    /// 以增量模式打开已有文档
+    pub fn open_existing(
+        path: impl AsRef<Path>
+    ) -> io::Result<ZipDocumentUpdater> {
+        let path = path.as_ref();
+        let lock = WriterLock::acquire(path)?;
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let records = read_central_directory(&mut file)?;
+
+        let mut writer = ZipDocumentWriter::new(Cursor::new(Vec::new()))?;
+        if records.iter().any(|r| r.name == MANIFEST_NAME.as_bytes()) {
+            let mut zip =
+                ZipArchive::new(&mut file).map_err(io::Error::other)?;
+            let manifest = zip.by_name(MANIFEST_NAME)?;
+            writer.manifest =
+                serde_json::from_reader(manifest).map_err(io::Error::other)?;
+        }
+
+        Ok(ZipDocumentUpdater {
+            writer,
+            file,
+            records,
+            removed: HashSet::new(),
+            _lock: lock,
+        })
+    }
+
+    /// 当垃圾比例超过 `threshold` 时重写文档，丢弃已被覆盖的条目
+    ///
+    /// 新文件先写入同目录的临时文件，再原子重命名替换原文件，
+    /// 已打开的读取器不受影响。返回是否执行了重写。
+    pub fn compact(
+        path: impl AsRef<Path>,
+        threshold: f64,
+    ) -> io::Result<bool> {
+        let path = path.as_ref();
+        let _lock = WriterLock::acquire(path)?;
+        let mut file = File::open(path)?;
+        let records = read_central_directory(&mut file)?;
+        let file_size = file.metadata()?.len();
+        if stats_for(&records, file_size).garbage_ratio() <= threshold {
+            return Ok(false);
+        }
+
+        let dir = path.parent().filter(|p| !p.as_os_str().is_empty());
+        let temp = match dir {
+            Some(dir) => NamedTempFile::new_in(dir)?,
+            None => NamedTempFile::new_in(".")?,
+        };
+        let mut source = ZipArchive::new(file).map_err(io::Error::other)?;
+        let mut zip = ZipWriter::new(temp);
+        for i in 0..source.len() {
+            let entry = source.by_index_raw(i).map_err(io::Error::other)?;
+            zip.raw_copy_file(entry).map_err(io::Error::other)?;
+        }
+        let temp = zip.finish().map_err(io::Error::other)?;
+        temp.as_file().sync_all()?;
+        temp.persist(path).map_err(|e| e.error)?;
+        Ok(true)
+    }
+}
+
+impl Deref for ZipDocumentUpdater {
+    type Target = ZipDocumentWriter<Cursor<Vec<u8>>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.writer
+    }
+}
+
+impl DerefMut for ZipDocumentUpdater {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.writer
+    }
+}
+
+impl ZipDocumentUpdater {
+    /// 删除原有条目，返回条目是否存在
+    ///
+    /// 只作用于打开时已存在的条目；本次会话中新写入的同名条目仍会保留
+    pub fn remove(
+        &mut self,
+        name: &str,
+    ) -> bool {
+        let exists = self.records.iter().any(|r| r.name == name.as_bytes());
+        if exists {
+            self.removed.insert(name.to_string());
+        }
+        exists
+    }
+
+    /// 原有条目名称（不含已删除的条目）
+    pub fn existing_entries(&self) -> Vec<String> {
+        self.records
+            .iter()
+            .map(|r| String::from_utf8_lossy(&r.name).into_owned())
+            .filter(|name| !self.removed.contains(name))
+            .collect()
+    }
+
+    /// 提交变更：追加新条目数据与新的中央目录
+    ///
+    /// 失败时会截断回原始长度，文档保持提交前的版本
+    pub fn commit(self) -> io::Result<IncrementalStats> {
+        let Self { writer, mut file, records, removed, _lock } = self;
+        let ZipDocumentWriter { zip, mut manifest } = writer;
+
+        let staged = zip.finish().map_err(io::Error::other)?.into_inner();
+        let (data, staged_records) = split_archive(&staged)?;
+        let staged_names: HashSet<&[u8]> =
+            staged_records.iter().map(|r| r.name.as_slice()).collect();
+
+        // manifest 中每个名称只保留最新的记录，并去掉已删除的条目
+        if let Some(entries) =
+            manifest.get_mut("entries").and_then(|v| v.as_array_mut())
+        {
+            let mut latest: HashMap<String, usize> = HashMap::new();
+            for (i, entry) in entries.iter().enumerate() {
+                if let Some(name) = entry.get("name").and_then(|n| n.as_str()) {
+                    latest.insert(name.to_string(), i);
+                }
+            }
+            let mut i = 0;
+            entries.retain(|entry| {
+                let index = i;
+                i += 1;
+                let Some(name) = entry.get("name").and_then(|n| n.as_str())
+                else {
+                    return true;
+                };
+                let deleted = removed.contains(name)
+                    && !staged_names.contains(name.as_bytes());
+                !deleted && latest.get(name) == Some(&index)
+            });
+        }
+        let mut manifest_zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let opts = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated);
+        manifest_zip
+            .start_file(MANIFEST_NAME, opts)
+            .map_err(io::Error::other)?;
+        let bytes = serde_json::to_vec(&manifest).map_err(io::Error::other)?;
+        manifest_zip.write_all(&bytes)?;
+        let manifest_bytes =
+            manifest_zip.finish().map_err(io::Error::other)?.into_inner();
+        let (manifest_data, manifest_records) = split_archive(&manifest_bytes)?;
+
+        let mut directory: Vec<CentralRecord> = records
+            .into_iter()
+            .filter(|r| {
+                let name = String::from_utf8_lossy(&r.name);
+                r.name != MANIFEST_NAME.as_bytes()
+                    && !staged_names.contains(r.name.as_slice())
+                    && !removed.contains(name.as_ref())
+            })
+            .collect();
+
+        file.lock()?;
+        let base_len = file.seek(SeekFrom::End(0))?;
+        let result = append_update(
+            &mut file,
+            base_len,
+            &[(data, staged_records), (manifest_data, manifest_records)],
+            &mut directory,
+        );
+        if result.is_err() {
+            let _ = file.set_len(base_len);
+        }
+        let unlocked = file.unlock();
+        let file_size = result?;
+        unlocked?;
+        Ok(stats_for(&directory, file_size))
+    }
+}
+
+// 依次追加各段条目数据，随后写入中央目录与目录结束记录，返回新的文件长度
+fn append_update(
+    file: &mut File,
+    base_len: u64,
+    parts: &[(&[u8], Vec<CentralRecord>)],
+    directory: &mut Vec<CentralRecord>,
+) -> io::Result<u64> {
+    let mut offset = base_len;
+    let mut out = Vec::new();
+    for (data, records) in parts {
+        for record in records {
+            let mut record = record.clone();
+            record.set_local_header_offset(
+                offset + record.local_header_offset(),
+            )?;
+            directory.push(record);
+        }
+        out.extend_from_slice(data);
+        offset += data.len() as u64;
+    }
+
+    let entries = u16::try_from(directory.len())
+        .ok()
+        .filter(|n| *n != u16::MAX)
+        .ok_or_else(too_large)?;
+    let cd_offset = u32::try_from(offset).map_err(|_| too_large())?;
+    let cd_start = out.len();
+    for record in directory.iter() {
+        out.extend_from_slice(&record.raw);
+    }
+    let cd_size =
+        u32::try_from(out.len() - cd_start).map_err(|_| too_large())?;
+    out.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&entries.to_le_bytes());
+    out.extend_from_slice(&entries.to_le_bytes());
+    out.extend_from_slice(&cd_size.to_le_bytes());
+    out.extend_from_slice(&cd_offset.to_le_bytes());
+    out.extend_from_slice(&[0; 2]);
+
+    file.write_all(&out)?;
+    file.sync_data()?;
+    Ok(base_len + out.len() as u64)
+}
+
+fn stats_for(
+    records: &[CentralRecord],
+    file_size: u64,
+) -> IncrementalStats {
+    let live: u64 = records
+        .iter()
+        .map(|r| r.stored_len() + r.raw.len() as u64)
+        .sum::<u64>()
+        + EOCD_LEN as u64;
+    IncrementalStats { file_size, garbage_size: file_size.saturating_sub(live) }
+}
+
+// 读取文件末尾的中央目录
+fn read_central_directory(file: &mut File) -> io::Result<Vec<CentralRecord>> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let tail_len = len.min((EOCD_LEN + u16::MAX as usize) as u64);
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    file.read_exact(&mut tail)?;
+    let eocd = find_eocd(&tail)?;
+    let (cd_offset, cd_size) = eocd_directory(&tail[eocd..])?;
+
+    file.seek(SeekFrom::Start(cd_offset))?;
+    let mut directory = vec![0; cd_size as usize];
+    file.read_exact(&mut directory)?;
+    parse_central_records(&directory)
+}
+
+// 拆分内存中的完整归档：返回条目数据区与中央目录记录
+fn split_archive(bytes: &[u8]) -> io::Result<(&[u8], Vec<CentralRecord>)> {
+    let eocd = find_eocd(bytes)?;
+    let (cd_offset, cd_size) = eocd_directory(&bytes[eocd..])?;
+    let start = cd_offset as usize;
+    let end = start + cd_size as usize;
+    if end > bytes.len() {
+        return Err(invalid("中央目录超出文件范围"));
+    }
+    Ok((&bytes[..start], parse_central_records(&bytes[start..end])?))
+}
+
+fn find_eocd(bytes: &[u8]) -> io::Result<usize> {
+    if bytes.len() < EOCD_LEN {
+        return Err(invalid("文件过短，不是有效的 ZIP 文档"));
+    }
+    (0..=bytes.len() - EOCD_LEN)
+        .rev()
+        .find(|&i| {
+            read_u32(bytes, i) == EOCD_SIGNATURE
+                && i + EOCD_LEN + read_u16(bytes, i + 20) as usize
+                    == bytes.len()
+        })
+        .ok_or_else(|| invalid("未找到 ZIP 目录结束记录"))
+}
+
+// 解析目录结束记录，返回中央目录的偏移与大小
+fn eocd_directory(eocd: &[u8]) -> io::Result<(u64, u64)> {
+    let entries = read_u16(eocd, 10);
+    let cd_size = read_u32(eocd, 12);
+    let cd_offset = read_u32(eocd, 16);
+    if entries == u16::MAX || cd_size == u32::MAX || cd_offset == u32::MAX {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "增量更新不支持 ZIP64 文档",
+        ));
+    }
+    Ok((cd_offset as u64, cd_size as u64))
+}
+
+fn parse_central_records(mut bytes: &[u8]) -> io::Result<Vec<CentralRecord>> {
+    let mut records = Vec::new();
+    while !bytes.is_empty() {
+        if bytes.len() < CENTRAL_HEADER_LEN
+            || read_u32(bytes, 0) != CENTRAL_SIGNATURE
+        {
+            return Err(invalid("中央目录记录损坏"));
+        }
+        let name_len = read_u16(bytes, 28) as usize;
+        let extra_len = read_u16(bytes, 30) as usize;
+        let comment_len = read_u16(bytes, 32) as usize;
+        let len = CENTRAL_HEADER_LEN + name_len + extra_len + comment_len;
+        if bytes.len() < len {
+            return Err(invalid("中央目录记录损坏"));
+        }
+        if read_u32(bytes, 20) == u32::MAX || read_u32(bytes, 42) == u32::MAX {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "增量更新不支持 ZIP64 条目",
+            ));
+        }
+        records.push(CentralRecord {
+            name: bytes[CENTRAL_HEADER_LEN..CENTRAL_HEADER_LEN + name_len]
+                .to_vec(),
+            raw: bytes[..len].to_vec(),
+        });
+        bytes = &bytes[len..];
+    }
+    Ok(records)
+}
+
+fn read_u16(
+    bytes: &[u8],
+    at: usize,
+) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn read_u32(
+    bytes: &[u8],
+    at: usize,
+) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn too_large() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "文档超出增量更新支持的大小，请使用完整写入",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zipdoc::ZipDocumentReader;
+    use tempfile::tempdir;
+
+    fn create_document(path: &Path) -> io::Result<()> {
+        let mut writer = ZipDocumentWriter::new(File::create(path)?)?;
+        for i in 0..5 {
+            writer.add_json(
+                &format!("entry-{i}.json"),
+                &serde_json::json!({ "index": i }),
+            )?;
+        }
+        writer.add_plugin_state("history", b"v1")?;
+        writer.finalize()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental_replace_add_remove() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("doc.ysf");
+        create_document(&path)?;
+        let original_len = std::fs::metadata(&path)?.len();
+
+        let mut updater = ZipDocumentWriter::open_existing(&path)?;
+        updater
+            .add_json("entry-1.json", &serde_json::json!({ "index": 10 }))?;
+        updater.add_stored("extra.bin", b"extra")?;
+        updater.add_plugin_state("history", b"v2")?;
+        assert!(updater.remove("entry-3.json"));
+        assert!(!updater.remove("missing.json"));
+        let stats = updater.commit()?;
+        assert!(stats.garbage_size > 0);
+
+        // 旧字节保持不变，新数据追加在文件末尾
+        assert!(stats.file_size > original_len);
+
+        let mut reader = ZipDocumentReader::open(&path)?;
+        let entry: serde_json::Value =
+            serde_json::from_slice(&reader.read_all("entry-1.json")?)?;
+        assert_eq!(entry["index"], 10);
+        assert_eq!(reader.read_all("entry-0.json")?, br#"{"index":0}"#);
+        assert_eq!(reader.read_all("extra.bin")?, b"extra");
+        assert!(reader.read_all("entry-3.json").is_err());
+        assert_eq!(reader.read_plugin_state("history")?.unwrap(), b"v2");
+
+        let manifest: serde_json::Value =
+            serde_json::from_slice(&reader.read_all("manifest.json")?)?;
+        let names: Vec<&str> = manifest["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names.iter().filter(|n| **n == "entry-1.json").count(), 1);
+        assert!(!names.contains(&"entry-3.json"));
+        assert!(names.contains(&"extra.bin"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_drops_garbage() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("doc.ysf");
+        create_document(&path)?;
+
+        for round in 0..3 {
+            let mut updater = ZipDocumentWriter::open_existing(&path)?;
+            updater.add_plugin_state("history", &[round; 256])?;
+            updater.commit()?;
+        }
+        let before = std::fs::metadata(&path)?.len();
+        assert!(!ZipDocumentWriter::compact(&path, 0.99)?);
+        assert!(ZipDocumentWriter::compact(&path, 0.0)?);
+        assert!(std::fs::metadata(&path)?.len() < before);
+
+        let mut reader = ZipDocumentReader::open(&path)?;
+        assert_eq!(reader.read_plugin_state("history")?.unwrap(), [2; 256]);
+        assert_eq!(reader.list_plugins()?, vec!["history".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_single_writer_enforced() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("doc.ysf");
+        create_document(&path)?;
+
+        let updater = ZipDocumentWriter::open_existing(&path)?;
+        let Err(err) = ZipDocumentWriter::open_existing(&path) else {
+            panic!("同一文档不允许同时存在两个写入者");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        assert!(ZipDocumentWriter::compact(&path, 0.0).is_err());
+
+        // 丢弃更新器不会修改文档
+        drop(updater);
+        let mut reader = ZipDocumentReader::open(&path)?;
+        assert_eq!(reader.read_plugin_state("history")?.unwrap(), b"v1");
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
 pub mod formats;
+mod incremental;
 mod reader;
 mod snapshot;
 mod writer;
 
 pub use writer::ZipDocumentWriter;
+pub use incremental::{ZipDocumentUpdater, IncrementalStats};
 pub use reader::{
     ZipDocumentReader, MmapConfig, MmapStats, ZipStreamReader,
     FileSizeCategory, ProcessingStrategy, FileInfo,
@@ -1,6 +1,7 @@
 use std::io::{self, Read, Seek, Write, BufWriter};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fs::File;
+use std::path::{Path, PathBuf};
 use zip::ZipArchive;
 use memmap2::{Mmap, MmapOptions};
 use tempfile::NamedTempFile;
@@ -620,6 +621,29 @@ impl<R: Read + Seek> ZipDocumentReader<R> {
     }
 }
 
+impl ZipDocumentReader<File> {
+    // 按路径打开文档
+    //
+    // 解析中央目录期间持有文档的共享锁，与增量保存互斥，
+    // 因此不会读到保存了一半的目录
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::open_with_mmap_config(path, MmapConfig::default())
+    }
+
+    // 使用指定配置按路径打开文档
+    pub fn open_with_mmap_config(
+        path: impl AsRef<Path>,
+        config: MmapConfig,
+    ) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let guard = file.try_clone()?;
+        guard.lock_shared()?;
+        let reader = Self::with_mmap_config(file, config);
+        guard.unlock()?;
+        reader
+    }
+}
+
 /// mmap 缓存统计信息
 #[derive(Debug, Clone)]
 pub struct MmapStats {
@@ -116,25 +116,34 @@ mf_extension!(
             vec![
                 (
                     "theme",
-                    AttributeSpec {
-                        default: Some(Value::String("light".to_string()))
-                    }
+                    AttributeSpec::new(Some(Value::String(
+                        "light".to_string()
+                    )))
                 ),
                 (
                     "font_size",
-                    AttributeSpec { default: Some(Value::Number(14.into())) }
+                    AttributeSpec::new(Some(Value::Number(14.into())))
                 ),
                 (
                     "line_height",
-                    AttributeSpec {
-                        default: Some(Value::String("1.5".to_string()))
-                    }
+                    AttributeSpec::new(Some(Value::String("1.5".to_string())))
                 )
             ]
         ),
         // 工具栏配置
         mf_global_attr!("toolbar", "visible", "true"),
-        mf_global_attr!("toolbar", "position", "top")
+        mf_global_attr!("toolbar", "position", "top"),
+        // 报表分组的节点在创建时记录创建时间
+        mf_global_attr!(groups: ["report"], vec![]).with_dynamic_default(
+            "created_at",
+            |_| {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                Value::from(now)
+            }
+        )
     ],
     // 节点转换函数
     node_transform = transform_nodes,
@@ -0,0 +1,78 @@
+//! mf_plugin 宏生命周期钩子示例
+//!
+//! 演示 `init` / `teardown` 钩子：init 在状态创建完成后执行，返回错误时
+//! `State::create` 失败；teardown 在状态销毁时执行。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mf_macro::{mf_meta, mf_plugin};
+use mf_model::node_definition::NodeSpec;
+use mf_model::schema::{Schema, SchemaSpec};
+use mf_state::{State, StateConfig};
+
+/// 当前存活的状态数
+static LIVE_STATES: AtomicUsize = AtomicUsize::new(0);
+
+mf_plugin!(
+    counter_plugin,
+    metadata = mf_meta!(version = "1.0.0", description = "统计存活的状态"),
+    init = async |_config, state| {
+        println!("状态已创建，版本: {:?}", state.map(|s| s.version));
+        LIVE_STATES.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    },
+    teardown = async |_state| {
+        LIVE_STATES.fetch_sub(1, Ordering::SeqCst);
+        println!("状态已销毁");
+    },
+    docs = "在状态创建和销毁时更新计数"
+);
+
+mf_plugin!(
+    license_plugin,
+    metadata = mf_meta!(version = "1.0.0", description = "校验授权"),
+    init = async |_config, _state| { Err(anyhow::anyhow!("授权已过期")) }
+);
+
+fn schema() -> Arc<Schema> {
+    let mut nodes = HashMap::new();
+    nodes.insert("doc".to_string(), NodeSpec::default());
+    Arc::new(
+        Schema::compile(SchemaSpec {
+            nodes,
+            marks: HashMap::new(),
+            top_node: Some("doc".to_string()),
+        })
+        .unwrap(),
+    )
+}
+
+fn config(plugins: Vec<mf_state::plugin::Plugin>) -> StateConfig {
+    StateConfig {
+        schema: Some(schema()),
+        doc: None,
+        stored_marks: None,
+        plugins: Some(plugins.into_iter().map(Arc::new).collect()),
+        resource_manager: None,
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let state = State::create(config(vec![counter_plugin::new()]))
+        .await
+        .expect("创建状态失败");
+    assert_eq!(LIVE_STATES.load(Ordering::SeqCst), 1);
+
+    state.destroy().await;
+    assert_eq!(LIVE_STATES.load(Ordering::SeqCst), 0);
+
+    // init 返回错误时 State::create 失败
+    let result = State::create(config(vec![license_plugin::new()])).await;
+    match result {
+        Err(err) => println!("创建状态失败（预期）: {err}"),
+        Ok(_) => panic!("授权校验失败时不应创建状态"),
+    }
+}
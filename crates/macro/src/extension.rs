@@ -165,7 +165,9 @@ macro_rules! mf_ops {
 ///
 /// ## 可用选项：
 ///
-/// - `ops`: 操作函数列表，函数签名为 `fn(&GlobalResourceManager) -> ForgeResult<()>`
+/// - `ops`: 操作函数列表，函数签名为 `fn(&GlobalResourceManager) -> ForgeResult<()>`；
+///   可写作 `op_name(timeout = Duration::from_secs(1))` 为单个操作声明超时，
+///   超时或 panic 时返回携带 `mf_state::ops::OpError` 的状态错误
 /// - `plugins`: 要包含的插件实例列表
 /// - `global_attributes`: 全局属性项列表
 /// - `node_transform`: 节点转换函数，签名为 `fn(&Node) -> Option<Node>`
@@ -176,7 +178,7 @@ macro_rules! mf_ops {
 macro_rules! mf_extension {
     (
         $name:ident
-        $(, ops = [ $( $op:ident ),+ $(,)? ] )?
+        $(, ops = [ $( $op:ident $( ( timeout = $op_timeout:expr ) )? ),+ $(,)? ] )?
         $(, plugins = [ $( $plugin:expr ),+ $(,)? ] )?
         $(, global_attributes = [ $( $attr:expr ),+ $(,)? ] )?
         $(, node_transform = $node_transform_fn:expr )?
@@ -212,7 +214,8 @@ macro_rules! mf_extension {
                 // 添加操作函数
                 $(
                     $(
-                        let op_item = mf_core::extension::OpFnItem::new(std::sync::Arc::new($op));
+                        let op_item = mf_core::extension::OpFnItem::new(std::sync::Arc::new($op))
+                            $( .with_timeout(stringify!($op), $op_timeout) )?;
                         ext.add_op_fn(op_item);
                     )+
                 )?
@@ -320,8 +323,24 @@ macro_rules! mf_extension_with_config {
 }
 
 /// 用于创建全局属性项的辅助宏
+///
+/// 目标可以是节点类型列表、`groups: [...]` 分组列表或
+/// `filter: |name, spec| ...` 节点规范判断；动态默认值通过返回值的
+/// `with_dynamic_default` 添加。
 #[macro_export]
 macro_rules! mf_global_attr {
+    (groups: $groups:expr, $attributes:expr) => {{
+        let mut item = $crate::mf_global_attr!(Vec::<&str>::new(), $attributes);
+        item.groups = $groups.iter().map(|s| s.to_string()).collect();
+        item
+    }};
+
+    (filter: $filter:expr, $attributes:expr) => {{
+        let mut item = $crate::mf_global_attr!(Vec::<&str>::new(), $attributes);
+        item.filter = Some(mf_core::types::NodeSpecFilter::new($filter));
+        item
+    }};
+
     ($types:expr, $attributes:expr) => {{
         use std::collections::HashMap;
         use mf_model::schema::AttributeSpec;
@@ -335,6 +354,7 @@ macro_rules! mf_global_attr {
         mf_core::types::GlobalAttributeItem {
             types: $types.iter().map(|s| s.to_string()).collect(),
             attributes: attr_map,
+            ..Default::default()
         }
     }};
 
@@ -347,12 +367,13 @@ macro_rules! mf_global_attr {
         let mut attr_map = HashMap::new();
         attr_map.insert(
             $key.to_string(),
-            AttributeSpec { default: Some(Value::String($value.to_string())) },
+            AttributeSpec::new(Some(Value::String($value.to_string()))),
         );
 
         mf_core::types::GlobalAttributeItem {
             types: vec![$type_name.to_string()],
             attributes: attr_map,
+            ..Default::default()
         }
     }};
 }
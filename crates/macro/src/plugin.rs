@@ -247,6 +247,15 @@ macro_rules! mf_plugin_config {
 ///     ),
 ///     append_transaction = validate_transaction,
 ///     filter_transaction = filter_transaction,
+///     // 可选的生命周期钩子：init 在 State::create 完成时执行，返回 Err 时创建失败；
+///     // teardown 在状态销毁时执行
+///     init = async |_config, state| {
+///         println!("状态已创建: {:?}", state.map(|s| s.version));
+///         Ok(())
+///     },
+///     teardown = async |_state| {
+///         println!("状态已销毁");
+///     },
 ///     docs = "用于事务验证和安全检查的插件"
 /// );
 ///
@@ -263,6 +272,8 @@ macro_rules! mf_plugin {
         $(, append_transaction = $append_fn:expr)?
         $(, filter_transaction = $filter_fn:expr)?
         $(, state_field = $state_field:expr)?
+        $(, init = async |$init_config:pat_param, $init_state:pat_param| $init:block)?
+        $(, teardown = async |$teardown_state:pat_param| $teardown:block)?
         $(, docs = $docs:expr)?
         $(,)?
     ) => {
@@ -354,6 +365,21 @@ macro_rules! mf_plugin {
                     ($filter_fn)(tr, state).await
                 }
             )?
+
+            $(
+                async fn on_state_created(
+                    &self,
+                    $init_config: &mf_state::state::StateConfigGeneric<mf_model::node_pool::NodePool, mf_model::schema::Schema>,
+                    $init_state: Option<&mf_state::state::StateGeneric<mf_model::node_pool::NodePool, mf_model::schema::Schema>>,
+                ) -> mf_state::error::StateResult<()> $init
+            )?
+
+            $(
+                async fn on_state_destroyed(
+                    &self,
+                    $teardown_state: &mf_state::state::StateGeneric<mf_model::node_pool::NodePool, mf_model::schema::Schema>,
+                ) $teardown
+            )?
         }
 
     };
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use mf_macro::{mf_meta, mf_plugin};
+use mf_model::node_definition::NodeSpec;
+use mf_model::schema::{Schema, SchemaSpec};
+use mf_state::plugin::Plugin;
+use mf_state::{State, StateConfig};
+
+/// 钩子调用记录，每个测试使用独立的插件，互不干扰
+static CREATED_EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static DESTROYED_EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+mf_plugin!(
+    created_plugin,
+    metadata = mf_meta!(version = "1.0.0"),
+    init = async |config, state| {
+        // init 时状态已创建完成，可以读取 schema
+        let top =
+            state.and_then(|s| s.schema().top_node().map(|n| n.name.clone()));
+        assert!(config.plugins.is_some());
+        CREATED_EVENTS.lock().unwrap().push(top.unwrap_or_default());
+        Ok(())
+    }
+);
+
+mf_plugin!(
+    failing_plugin,
+    metadata = mf_meta!(version = "1.0.0"),
+    init = async |_config, _state| { Err(anyhow::anyhow!("初始化失败")) }
+);
+
+mf_plugin!(
+    first_plugin,
+    metadata = mf_meta!(version = "1.0.0"),
+    teardown = async |_state| {
+        DESTROYED_EVENTS.lock().unwrap().push("first_plugin".to_string());
+    }
+);
+
+mf_plugin!(
+    second_plugin,
+    metadata = mf_meta!(version = "1.0.0"),
+    teardown = async |_state| {
+        DESTROYED_EVENTS.lock().unwrap().push("second_plugin".to_string());
+    }
+);
+
+fn config(plugins: Vec<Plugin>) -> StateConfig {
+    let mut nodes = HashMap::new();
+    nodes.insert("doc".to_string(), NodeSpec::default());
+    let schema = Schema::compile(SchemaSpec {
+        nodes,
+        marks: HashMap::new(),
+        top_node: Some("doc".to_string()),
+    })
+    .expect("测试 Schema 编译失败");
+    StateConfig {
+        schema: Some(Arc::new(schema)),
+        doc: None,
+        stored_marks: None,
+        plugins: Some(plugins.into_iter().map(Arc::new).collect()),
+        resource_manager: None,
+    }
+}
+
+#[tokio::test]
+async fn init_runs_after_state_created() {
+    State::create(config(vec![created_plugin::new()])).await.unwrap();
+    assert_eq!(*CREATED_EVENTS.lock().unwrap(), ["doc"]);
+}
+
+#[tokio::test]
+async fn init_error_fails_state_create() {
+    let result = State::create(config(vec![failing_plugin::new()])).await;
+    let err = result.expect_err("init 返回错误时不应创建状态");
+    assert!(err.to_string().contains("初始化失败"), "{err}");
+}
+
+#[tokio::test]
+async fn teardown_runs_on_destroy_in_reverse_order() {
+    let state =
+        State::create(config(vec![first_plugin::new(), second_plugin::new()]))
+            .await
+            .unwrap();
+    let order: Vec<String> = state
+        .sorted_plugins()
+        .await
+        .iter()
+        .map(|p| p.get_name().to_string())
+        .collect();
+    assert!(DESTROYED_EVENTS.lock().unwrap().is_empty());
+
+    state.destroy().await;
+    let expected: Vec<String> = order.into_iter().rev().collect();
+    assert_eq!(*DESTROYED_EVENTS.lock().unwrap(), expected);
+}
@@ -0,0 +1,67 @@
+use criterion::{
+    black_box, criterion_group, criterion_main, BenchmarkId, Criterion,
+};
+use mf_model::node_definition::NodeSpec;
+use mf_model::schema::{Schema, SchemaSpec};
+use std::collections::HashMap;
+
+/// 创建包含 `block_count` 个块级节点的 Schema 规范
+///
+/// 块级节点只使用少数几种内容表达式，模拟真实文档中大量节点共用表达式的情况
+fn create_schema_spec(block_count: usize) -> SchemaSpec {
+    let mut nodes = HashMap::new();
+    let node = |content: &str| NodeSpec {
+        content: (!content.is_empty()).then(|| content.to_string()),
+        ..Default::default()
+    };
+
+    nodes.insert("doc".to_string(), node("heading? block_0+"));
+    nodes.insert("heading".to_string(), node("text*"));
+    nodes.insert("text".to_string(), node(""));
+    for i in 0..block_count {
+        let content = match i % 3 {
+            0 => "text*",
+            1 => "(heading | block_0)+",
+            _ => "heading block_0*",
+        };
+        nodes.insert(format!("block_{i}"), node(content));
+    }
+
+    SchemaSpec { nodes, marks: HashMap::new(), top_node: Some("doc".into()) }
+}
+
+/// 基准测试：Schema 编译时内容表达式缓存的效果
+fn bench_schema_compile(c: &mut Criterion) {
+    let mut group = c.benchmark_group("content_cache");
+
+    for block_count in [10, 100] {
+        let spec = create_schema_spec(block_count);
+
+        // 每次编译前清空缓存，所有表达式都重新解析
+        group.bench_with_input(
+            BenchmarkId::new("compile_cold", block_count),
+            &spec,
+            |b, spec| {
+                b.iter(|| {
+                    Schema::clear_content_cache();
+                    black_box(Schema::compile(spec.clone()).unwrap())
+                });
+            },
+        );
+
+        // 缓存已预热，只需实例化
+        Schema::compile(spec.clone()).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("compile_warm", block_count),
+            &spec,
+            |b, spec| {
+                b.iter(|| black_box(Schema::compile(spec.clone()).unwrap()));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_schema_compile);
+criterion_main!(benches);
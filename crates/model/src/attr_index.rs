@@ -0,0 +1,172 @@
+use rpds::{HashTrieMapSync, HashTrieSetSync};
+use serde_json::Value;
+
+use crate::{node::Node, types::NodeId};
+
+/// 属性值二级索引：(属性名, 属性值) -> 节点 id 集合
+///
+/// 使用不可变数据结构，随 [`Tree`](crate::tree::Tree) 一起低成本克隆。
+/// 只索引标量属性值（字符串、数字、布尔），`null`、缺失值与复合值不入索引。
+/// 索引是派生数据，不参与序列化，反序列化后需重新建立。
+#[derive(Clone, Default, PartialEq)]
+pub struct AttrIndex {
+    names: HashTrieSetSync<String>,
+    entries: HashTrieMapSync<(String, AttrKey), HashTrieSetSync<NodeId>>,
+}
+
+/// 带类型的索引键，数字 `7` 与字符串 `"7"` 是不同的键
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AttrKey {
+    String(String),
+    /// 数值的规范化文本，整数值的浮点数与整数相同（`7.0` 与 `7`）
+    Number(String),
+    Bool(bool),
+}
+
+impl AttrKey {
+    /// 属性值对应的索引键，不可索引时返回 `None`
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(s) => Some(AttrKey::String(s.clone())),
+            Value::Number(n) => {
+                let text = match (n.as_i64(), n.as_u64(), n.as_f64()) {
+                    (Some(i), _, _) => i.to_string(),
+                    (_, Some(u), _) => u.to_string(),
+                    (_, _, Some(f)) if f.fract() == 0.0 && f.abs() < 1e15 => {
+                        (f as i64).to_string()
+                    },
+                    _ => n.to_string(),
+                };
+                Some(AttrKey::Number(text))
+            },
+            Value::Bool(b) => Some(AttrKey::Bool(*b)),
+            _ => None,
+        }
+    }
+}
+
+impl AttrIndex {
+    /// 属性值对应的索引键，不可索引时返回 `None`
+    pub fn index_key(value: &Value) -> Option<AttrKey> {
+        AttrKey::from_value(value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn contains_attr(
+        &self,
+        attr_name: &str,
+    ) -> bool {
+        self.names.contains(attr_name)
+    }
+
+    /// 已建立索引的属性名（按名称排序）
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.names.iter().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 为属性建立索引，并将现有节点写入索引
+    pub fn add_attr<'a>(
+        &mut self,
+        attr_name: &str,
+        nodes: impl Iterator<Item = &'a Node>,
+    ) {
+        if self.names.contains(attr_name) {
+            return;
+        }
+        self.names.insert_mut(attr_name.to_string());
+        for node in nodes {
+            if let Some(key) =
+                node.attrs.get_safe(attr_name).and_then(Self::index_key)
+            {
+                self.insert(attr_name, key, &node.id);
+            }
+        }
+    }
+
+    pub fn find(
+        &self,
+        attr_name: &str,
+        attr_value: &Value,
+    ) -> Vec<NodeId> {
+        let Some(key) = AttrKey::from_value(attr_value) else {
+            return Vec::new();
+        };
+        let key = (attr_name.to_string(), key);
+        let mut ids: Vec<NodeId> = self
+            .entries
+            .get(&key)
+            .map(|ids| ids.iter().cloned().collect())
+            .unwrap_or_default();
+        ids.sort();
+        ids
+    }
+
+    /// 节点被替换（`old` -> `new`）后同步索引，任一侧为 `None` 表示新增或删除
+    pub fn replace(
+        &mut self,
+        old: Option<&Node>,
+        new: Option<&Node>,
+    ) {
+        if self.names.is_empty() {
+            return;
+        }
+        let names: Vec<String> = self.names.iter().cloned().collect();
+        for name in &names {
+            let old_key = old.and_then(|n| {
+                n.attrs
+                    .get_safe(name)
+                    .and_then(Self::index_key)
+                    .map(|k| (k, &n.id))
+            });
+            let new_key = new.and_then(|n| {
+                n.attrs
+                    .get_safe(name)
+                    .and_then(Self::index_key)
+                    .map(|k| (k, &n.id))
+            });
+            if old_key == new_key {
+                continue;
+            }
+            if let Some((key, id)) = old_key {
+                self.remove(name, key, id);
+            }
+            if let Some((key, id)) = new_key {
+                self.insert(name, key, id);
+            }
+        }
+    }
+
+    fn insert(
+        &mut self,
+        attr_name: &str,
+        key: AttrKey,
+        id: &NodeId,
+    ) {
+        let key = (attr_name.to_string(), key);
+        let ids = self.entries.get(&key).cloned().unwrap_or_default();
+        self.entries.insert_mut(key, ids.insert(id.clone()));
+    }
+
+    fn remove(
+        &mut self,
+        attr_name: &str,
+        key: AttrKey,
+        id: &NodeId,
+    ) {
+        let key = (attr_name.to_string(), key);
+        let Some(ids) = self.entries.get(&key) else {
+            return;
+        };
+        let ids = ids.remove(id);
+        if ids.is_empty() {
+            self.entries.remove_mut(&key);
+        } else {
+            self.entries.insert_mut(key, ids);
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use std::fmt;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
+use lru::LruCache;
+use once_cell::sync::Lazy;
+
 use std::cmp::Ordering;
 
 use crate::error::PoolResult;
@@ -43,15 +48,15 @@ impl ContentMatch {
         str: String,
         nodes: &HashMap<String, NodeDefinition>,
     ) -> ContentMatch {
-        let mut stream = TokenStream::new(str, nodes.clone());
-        if stream.next().is_none() {
-            return ContentMatch::empty();
-        }
-        let expr = parse_expr(&mut stream);
-
-        let arr = nfa(expr);
+        CompiledContentExpr::compile(str, nodes).instantiate(nodes)
+    }
 
-        dfa(arr)
+    /// 与 [`ContentMatch::parse`] 相同，但优先复用进程级缓存中的编译结果
+    pub fn parse_cached(
+        str: String,
+        nodes: &HashMap<String, NodeDefinition>,
+    ) -> ContentMatch {
+        CompiledContentExpr::cached(str, nodes).instantiate(nodes)
     }
     pub fn empty() -> Self {
         ContentMatch {
@@ -61,13 +66,15 @@ impl ContentMatch {
         }
     }
 
+    /// 按类型名匹配：边上保存的是编译时的节点定义副本，之后注册的动态默认值
+    /// 不会同步到副本上
     pub fn match_type(
         &self,
         node_type: &NodeDefinition,
     ) -> Option<&ContentMatch> {
         self.next
             .iter()
-            .find(|edge| &edge.node_type == node_type)
+            .find(|edge| edge.node_type.name == node_type.name)
             .map(|edge| &edge.next)
     }
 
@@ -158,7 +165,7 @@ impl ContentMatch {
     ) -> bool {
         for edge1 in &self.next {
             for edge2 in &other.next {
-                if edge1.node_type == edge2.node_type {
+                if edge1.node_type.name == edge2.node_type.name {
                     return true;
                 }
             }
@@ -182,6 +189,143 @@ impl ContentMatch {
         }
     }
 }
+/// 内容表达式的部分匹配器
+///
+/// 直接在 NFA 上模拟匹配，用于查询某个子节点前缀之后还能追加哪些类型。
+/// `ContentMatch` 中的循环状态只保留了一层展开，不适合做任意长度前缀的查询。
+#[derive(Debug, Clone, Default)]
+pub struct ContentPartialMatch {
+    nfa: Vec<Vec<Rc<RefCell<Edge>>>>,
+}
+
+impl ContentPartialMatch {
+    pub fn parse(
+        str: String,
+        nodes: &HashMap<String, NodeDefinition>,
+    ) -> ContentPartialMatch {
+        let mut stream = TokenStream::new(str, nodes.clone());
+        if stream.next().is_none() {
+            return ContentPartialMatch::default();
+        }
+        ContentPartialMatch { nfa: nfa(parse_expr(&mut stream)) }
+    }
+
+    /// 按类型名依次匹配前缀，返回匹配后的状态集合；前缀不合法时返回 None
+    fn advance(
+        &self,
+        prefix: &[&str],
+    ) -> Option<Vec<usize>> {
+        if self.nfa.is_empty() {
+            return prefix.is_empty().then(Vec::new);
+        }
+        let mut states = null_from(&self.nfa, 0);
+        for name in prefix {
+            states = self.step(&states, name);
+            if states.is_empty() {
+                return None;
+            }
+        }
+        Some(states)
+    }
+
+    fn step(
+        &self,
+        states: &[usize],
+        name: &str,
+    ) -> Vec<usize> {
+        let mut next = Vec::new();
+        for &state in states {
+            for edge in &self.nfa[state] {
+                let edge = edge.borrow();
+                let matched =
+                    edge.term.as_ref().is_some_and(|term| term.name == name);
+                if !matched {
+                    continue;
+                }
+                for to in null_from(&self.nfa, edge.to.unwrap_or(0)) {
+                    if !next.contains(&to) {
+                        next.push(to);
+                    }
+                }
+            }
+        }
+        next.sort();
+        next
+    }
+
+    /// 能够到达终止状态的 NFA 状态
+    fn live_states(&self) -> Vec<bool> {
+        let end = self.nfa.len() - 1;
+        let mut live = vec![false; self.nfa.len()];
+        live[end] = true;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (state, edges) in self.nfa.iter().enumerate() {
+                if live[state] {
+                    continue;
+                }
+                let reaches_live = edges
+                    .iter()
+                    .any(|edge| edge.borrow().to.is_some_and(|to| live[to]));
+                if reaches_live {
+                    live[state] = true;
+                    changed = true;
+                }
+            }
+        }
+        live
+    }
+
+    /// 前缀之后能够追加、且追加后表达式仍可被满足的类型名（按名称排序）
+    ///
+    /// 前缀本身不合法时返回空列表
+    pub fn next_types(
+        &self,
+        prefix: &[&str],
+    ) -> Vec<String> {
+        let Some(states) = self.advance(prefix) else {
+            return Vec::new();
+        };
+        if states.is_empty() {
+            return Vec::new();
+        }
+        let live = self.live_states();
+        let mut names: Vec<String> = Vec::new();
+        for &state in &states {
+            for edge in &self.nfa[state] {
+                let edge = edge.borrow();
+                let Some(term) = edge.term.as_ref() else {
+                    continue;
+                };
+                if names.contains(&term.name) {
+                    continue;
+                }
+                let satisfiable = null_from(&self.nfa, edge.to.unwrap_or(0))
+                    .iter()
+                    .any(|&to| live[to]);
+                if satisfiable {
+                    names.push(term.name.clone());
+                }
+            }
+        }
+        names.sort();
+        names
+    }
+
+    /// 前缀是否已经是完整的合法内容
+    pub fn valid_end(
+        &self,
+        prefix: &[&str],
+    ) -> bool {
+        match self.advance(prefix) {
+            Some(states) if self.nfa.is_empty() => states.is_empty(),
+            Some(states) => states.contains(&(self.nfa.len() - 1)),
+            None => false,
+        }
+    }
+}
+
 impl fmt::Display for ContentMatch {
     fn fmt(
         &self,
@@ -462,67 +606,232 @@ pub struct Edge {
     term: Option<NodeDefinition>,
     to: Option<usize>,
 }
-fn dfa(nfa: Vec<Vec<Rc<RefCell<Edge>>>>) -> ContentMatch {
-    let mut labeled: HashMap<String, ContentMatch> = HashMap::new();
+fn dfa(nfa: &[Vec<Rc<RefCell<Edge>>>]) -> CompiledContentExpr {
+    let mut ids: HashMap<Vec<usize>, usize> = HashMap::new();
+    let mut sets: Vec<Vec<usize>> = vec![null_from(nfa, 0)];
+    let mut states: Vec<CompiledState> = Vec::new();
+    ids.insert(sets[0].clone(), 0);
 
-    fn explore(
-        states: Vec<usize>,
-        nfa: &Vec<Vec<Rc<RefCell<Edge>>>>,
-        labeled: &mut HashMap<String, ContentMatch>,
-    ) -> ContentMatch {
-        let mut out: Vec<(NodeDefinition, Vec<usize>)> = Vec::new();
-        for &node in &states {
+    while states.len() < sets.len() {
+        let current = sets[states.len()].clone();
+        let mut out: Vec<(String, Vec<usize>)> = Vec::new();
+        for &node in &current {
             for edge in &nfa[node] {
-                if edge.borrow().term.is_none() {
+                let edge = edge.borrow();
+                let Some(term) = &edge.term else {
                     continue;
-                }
-                let term = edge.borrow().term.clone().unwrap();
-                let mut set: Option<&mut Vec<usize>> = None;
+                };
+                let index = match out.iter().position(|(t, _)| t == &term.name)
+                {
+                    Some(index) => index,
+                    None => {
+                        out.push((term.name.clone(), Vec::new()));
+                        out.len() - 1
+                    },
+                };
+                out[index].1.extend(null_from(nfa, edge.to.unwrap_or(0)));
+            }
+        }
 
-                for (t, s) in &mut out {
-                    if *t == term {
-                        set = Some(s);
-                        break;
-                    }
-                }
+        let mut edges = Vec::with_capacity(out.len());
+        for (name, set) in out {
+            let id = match ids.get(&set) {
+                Some(&id) => id,
+                None => {
+                    let id = sets.len();
+                    ids.insert(set.clone(), id);
+                    sets.push(set);
+                    id
+                },
+            };
+            edges.push((name, id));
+        }
+        states.push(CompiledState {
+            edges,
+            valid_end: current.contains(&(nfa.len() - 1)),
+        });
+    }
 
-                if set.is_none() {
-                    out.push((term.clone(), Vec::new()));
-                    set = Some(&mut out.last_mut().unwrap().1);
-                }
-                for &node in &null_from(nfa, edge.borrow().to.unwrap_or(0)) {
-                    set.as_mut().unwrap().push(node);
-                }
-            }
+    CompiledContentExpr { states }
+}
+
+/// 进程级内容表达式缓存的最大条目数，超出后淘汰最久未使用的条目
+pub const CONTENT_CACHE_CAPACITY: usize = 1024;
+
+/// 进程级内容表达式缓存
+///
+/// 键为（原始表达式，表达式中名称的解析结果），节点集合或分组成员不同的 Schema
+/// 不会共用同一条目
+static CONTENT_EXPR_CACHE: Lazy<Mutex<ContentExprCache>> = Lazy::new(|| {
+    Mutex::new(ContentExprCache::new(
+        NonZeroUsize::new(CONTENT_CACHE_CAPACITY).unwrap(),
+    ))
+});
+
+/// 按最近使用淘汰的编译结果缓存
+struct ContentExprCache {
+    entries: LruCache<(String, String), Arc<CompiledContentExpr>>,
+}
+
+impl ContentExprCache {
+    fn new(capacity: NonZeroUsize) -> Self {
+        ContentExprCache { entries: LruCache::new(capacity) }
+    }
+
+    fn get(
+        &mut self,
+        key: &(String, String),
+    ) -> Option<Arc<CompiledContentExpr>> {
+        self.entries.get(key).cloned()
+    }
+
+    /// 写入编译结果，并发编译同一表达式时返回先写入的一份
+    fn insert(
+        &mut self,
+        key: (String, String),
+        compiled: Arc<CompiledContentExpr>,
+    ) -> Arc<CompiledContentExpr> {
+        self.entries.get_or_insert(key, || compiled).clone()
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// 清空进程级内容表达式缓存
+pub fn clear_content_cache() {
+    CONTENT_EXPR_CACHE.lock().unwrap().clear();
+}
+
+/// 编译后的内容表达式
+///
+/// 以节点类型名描述的 DFA 状态表，与具体的节点定义无关，
+/// 实例化时再按名称取出当前 Schema 的节点定义，因此可以在多个 Schema 间共享
+#[derive(Debug, PartialEq, Eq)]
+pub struct CompiledContentExpr {
+    states: Vec<CompiledState>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct CompiledState {
+    edges: Vec<(String, usize)>,
+    valid_end: bool,
+}
+
+impl CompiledContentExpr {
+    /// 解析并编译内容表达式
+    pub fn compile(
+        str: String,
+        nodes: &HashMap<String, NodeDefinition>,
+    ) -> Self {
+        let mut stream = TokenStream::new(str, nodes.clone());
+        if stream.next().is_none() {
+            return CompiledContentExpr {
+                states: vec![CompiledState { edges: vec![], valid_end: true }],
+            };
         }
-        let mut state = ContentMatch {
-            next: Vec::new(),
-            wrap_cache: vec![],
-            valid_end: states.contains(&(nfa.len() - 1)),
+        let expr = parse_expr(&mut stream);
+
+        dfa(&nfa(expr))
+    }
+
+    /// 从进程级缓存获取编译结果，未命中时编译并写入缓存
+    ///
+    /// 表达式引用了未知名称时不写入缓存，直接走 [`CompiledContentExpr::compile`]
+    /// 以便报告解析错误
+    pub fn cached(
+        str: String,
+        nodes: &HashMap<String, NodeDefinition>,
+    ) -> Arc<Self> {
+        let Some(resolution) = resolution_key(&str, nodes) else {
+            return Arc::new(Self::compile(str, nodes));
         };
+        let key = (str, resolution);
+        if let Some(compiled) = CONTENT_EXPR_CACHE.lock().unwrap().get(&key) {
+            return compiled;
+        }
+        let compiled = Arc::new(Self::compile(key.0.clone(), nodes));
+        CONTENT_EXPR_CACHE.lock().unwrap().insert(key, compiled)
+    }
+
+    /// 使用给定的节点定义生成 [`ContentMatch`]
+    pub fn instantiate(
+        &self,
+        nodes: &HashMap<String, NodeDefinition>,
+    ) -> ContentMatch {
+        fn build(
+            expr: &CompiledContentExpr,
+            id: usize,
+            nodes: &HashMap<String, NodeDefinition>,
+            labeled: &mut HashMap<usize, ContentMatch>,
+        ) -> ContentMatch {
+            let compiled = &expr.states[id];
+            let mut state = ContentMatch {
+                next: Vec::new(),
+                wrap_cache: vec![],
+                valid_end: compiled.valid_end,
+            };
+            labeled.insert(id, state.clone());
 
-        let state_key =
-            states.iter().map(|&x| x.to_string()).collect::<Vec<_>>().join(",");
-        labeled.insert(state_key.clone(), state.clone());
+            for (name, to) in &compiled.edges {
+                let next_state = labeled
+                    .get(to)
+                    .cloned()
+                    .unwrap_or_else(|| build(expr, *to, nodes, labeled));
+                labeled.insert(*to, next_state.clone());
+                state.next.push(MatchEdge {
+                    node_type: nodes[name].clone(),
+                    next: next_state,
+                });
+            }
 
-        for (term, states) in out {
-            let states_key = states
-                .iter()
-                .map(|&x| x.to_string())
-                .collect::<Vec<_>>()
-                .join(",");
-            let next_state = labeled
-                .get(&states_key)
-                .cloned()
-                .unwrap_or_else(|| explore(states, nfa, labeled));
-            labeled.insert(states_key, next_state.clone());
-            state.next.push(MatchEdge { node_type: term, next: next_state });
+            state
         }
 
-        state
+        build(self, 0, nodes, &mut HashMap::new())
     }
+}
 
-    explore(null_from(&nfa, 0), &nfa, &mut labeled)
+/// 表达式中每个名称解析到的节点类型，作为缓存键的一部分
+///
+/// 存在无法解析的名称时返回 `None`
+fn resolution_key(
+    str: &str,
+    nodes: &HashMap<String, NodeDefinition>,
+) -> Option<String> {
+    let mut names: Vec<&str> = str
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|name| !name.is_empty())
+        .collect();
+    names.sort();
+    names.dedup();
+
+    let mut key = String::new();
+    for name in names {
+        let mut resolved: Vec<&str> = if nodes.contains_key(name) {
+            vec![name]
+        } else {
+            nodes
+                .values()
+                .filter(|type_| type_.groups.iter().any(|group| group == name))
+                .map(|type_| type_.name.as_str())
+                .collect()
+        };
+        if resolved.is_empty() {
+            // 范围量词中的数字不是名称
+            if name.chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            return None;
+        }
+        resolved.sort();
+        key.push_str(name);
+        key.push('=');
+        key.push_str(&resolved.join(","));
+        key.push(';');
+    }
+    Some(key)
 }
 
 pub fn null_from(
@@ -620,6 +929,96 @@ mod tests {
         assert!(msg.contains("无法在 Schema 中找到名称为"), "actual: {msg}");
         assert!(msg.contains("可用的节点/分组示例"), "actual: {msg}");
     }
+
+    fn build_ab_nodes() -> HashMap<String, NodeDefinition> {
+        let mut nodes = HashMap::new();
+        for name in ["A", "B"] {
+            nodes.insert(
+                name.to_string(),
+                NodeDefinition::new(name.to_string(), NodeSpec::default()),
+            );
+        }
+        nodes
+    }
+
+    #[test]
+    fn partial_match_next_types_in_loop() {
+        let nodes = build_ab_nodes();
+        let matcher = ContentPartialMatch::parse("(A|B)+".to_string(), &nodes);
+
+        assert_eq!(matcher.next_types(&[]), vec!["A", "B"]);
+        assert_eq!(matcher.next_types(&["A", "A"]), vec!["A", "B"]);
+        assert_eq!(matcher.next_types(&["A", "B", "A", "B"]), vec!["A", "B"]);
+        assert!(!matcher.valid_end(&[]));
+        assert!(matcher.valid_end(&["A", "A"]));
+    }
+
+    #[test]
+    fn partial_match_sequence_and_invalid_prefix() {
+        let nodes = build_ab_nodes();
+        let matcher =
+            ContentPartialMatch::parse("A{1,2} B".to_string(), &nodes);
+
+        assert_eq!(matcher.next_types(&["A"]), vec!["A", "B"]);
+        assert_eq!(matcher.next_types(&["A", "A"]), vec!["B"]);
+        assert!(matcher.next_types(&["A", "A", "B"]).is_empty());
+        assert!(matcher.next_types(&["B"]).is_empty());
+
+        let empty = ContentPartialMatch::parse(String::new(), &nodes);
+        assert!(empty.next_types(&[]).is_empty());
+        assert!(empty.valid_end(&[]));
+        assert!(!empty.valid_end(&["A"]));
+    }
+
+    #[test]
+    fn cached_expr_is_shared_and_keyed_by_resolution() {
+        let nodes = build_ab_nodes();
+        let first = CompiledContentExpr::cached("A B*".to_string(), &nodes);
+        let second =
+            CompiledContentExpr::cached("A B*".to_string(), &build_ab_nodes());
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(
+            ContentMatch::parse_cached("A B*".to_string(), &nodes),
+            ContentMatch::parse("A B*".to_string(), &nodes)
+        );
+
+        // 同名分组在不同 Schema 中成员不同，不能共用缓存条目
+        let mut grouped = build_ab_nodes();
+        grouped.get_mut("A").unwrap().groups = vec!["block".to_string()];
+        let only_a =
+            CompiledContentExpr::cached("block+".to_string(), &grouped);
+        grouped.get_mut("B").unwrap().groups = vec!["block".to_string()];
+        let both = CompiledContentExpr::cached("block+".to_string(), &grouped);
+        assert!(!Arc::ptr_eq(&only_a, &both));
+        let matcher = both.instantiate(&grouped);
+        assert!(matcher.match_type(&grouped["B"]).is_some());
+
+        clear_content_cache();
+        let third = CompiledContentExpr::cached("A B*".to_string(), &nodes);
+        assert!(!Arc::ptr_eq(&first, &third));
+    }
+
+    #[test]
+    fn content_cache_evicts_least_recently_used() {
+        let nodes = build_ab_nodes();
+        let mut cache = ContentExprCache::new(NonZeroUsize::new(2).unwrap());
+        let key = |expr: &str| (expr.to_string(), String::new());
+        let compiled = |expr: &str| {
+            Arc::new(CompiledContentExpr::compile(expr.to_string(), &nodes))
+        };
+
+        let a = cache.insert(key("A"), compiled("A"));
+        cache.insert(key("B"), compiled("B"));
+        // 访问 A 后 B 成为最久未使用的条目
+        assert!(Arc::ptr_eq(&cache.get(&key("A")).unwrap(), &a));
+        cache.insert(key("A B"), compiled("A B"));
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.get(&key("B")).is_none());
+        assert!(cache.get(&key("A")).is_some());
+
+        // 已有条目时保留先写入的一份
+        assert!(Arc::ptr_eq(&cache.insert(key("A"), compiled("A")), &a));
+    }
 }
 fn node(nfa: &mut Vec<Vec<Rc<RefCell<Edge>>>>) -> usize {
     nfa.push(vec![]);
@@ -0,0 +1,623 @@
+//! 派生属性
+//!
+//! 属性规范通过 [`AttributeSpec::computed`] 声明为派生属性，值由表达式计算：
+//! - `qty`、`attrs.qty`：节点自身的属性
+//! - `parent.rate`：父节点的属性，没有父节点时按 0 计算
+//! - `sum(children.amount)`：子节点属性的聚合，支持 `sum`、`count`、`min`、`max`、`avg`
+//!
+//! 表达式由数字、上述引用、`+ - * /` 与括号组成。非数字的属性值按 0 计算，
+//! `count` 统计该属性不为 null 的子节点数，空集合的聚合结果为 0。
+//! 结果不是有限数字（如除以 0）时写入 null。
+//!
+//! 派生属性只能由重算流程写入，重算由事务提交后根据表达式引用的路径增量完成。
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use rpds::HashTrieMapSync;
+use serde_json::Value;
+
+use crate::node::Node;
+use crate::schema::AttributeSpec;
+
+/// 聚合函数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,
+    Count,
+    Min,
+    Max,
+    Avg,
+}
+
+impl Aggregate {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "sum" => Some(Aggregate::Sum),
+            "count" => Some(Aggregate::Count),
+            "min" => Some(Aggregate::Min),
+            "max" => Some(Aggregate::Max),
+            "avg" => Some(Aggregate::Avg),
+            _ => None,
+        }
+    }
+}
+
+/// 二元运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// 派生属性表达式
+#[derive(Debug, Clone, PartialEq)]
+pub enum DerivedExpr {
+    Number(f64),
+    /// 节点自身的属性
+    Own(String),
+    /// 父节点的属性
+    Parent(String),
+    /// 子节点属性的聚合
+    Children {
+        aggregate: Aggregate,
+        attr: String,
+    },
+    Neg(Box<DerivedExpr>),
+    Binary {
+        op: BinaryOp,
+        lhs: Box<DerivedExpr>,
+        rhs: Box<DerivedExpr>,
+    },
+}
+
+/// 表达式引用的属性
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DerivedDeps {
+    pub own: BTreeSet<String>,
+    pub parent: BTreeSet<String>,
+    pub children: BTreeSet<String>,
+}
+
+/// 派生属性表达式解析错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedParseError {
+    pub expr: String,
+    pub message: String,
+}
+
+impl fmt::Display for DerivedParseError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "派生属性表达式 \"{}\" 无效: {}", self.expr, self.message)
+    }
+}
+
+impl std::error::Error for DerivedParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Symbol(char),
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut text = String::new();
+            while let Some(&c) = chars.peek() {
+                if !c.is_ascii_digit() && c != '.' {
+                    break;
+                }
+                text.push(c);
+                chars.next();
+            }
+            let number = text
+                .parse::<f64>()
+                .map_err(|_| format!("无法解析数字 \"{text}\""))?;
+            tokens.push(Token::Number(number));
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut text = String::new();
+            while let Some(&c) = chars.peek() {
+                if !c.is_alphanumeric() && c != '_' {
+                    break;
+                }
+                text.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Ident(text));
+        } else if "+-*/().".contains(c) {
+            tokens.push(Token::Symbol(c));
+            chars.next();
+        } else {
+            return Err(format!("无法识别的字符 '{c}'"));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(
+        &mut self,
+        symbol: char,
+    ) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(
+        &mut self,
+        symbol: char,
+    ) -> Result<(), String> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(format!("缺少 '{symbol}'"))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                Ok(name.clone())
+            },
+            _ => Err("需要属性名".to_string()),
+        }
+    }
+
+    fn expr(&mut self) -> Result<DerivedExpr, String> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                BinaryOp::Add
+            } else if self.eat('-') {
+                BinaryOp::Sub
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.term()?;
+            lhs = DerivedExpr::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+        }
+    }
+
+    fn term(&mut self) -> Result<DerivedExpr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                BinaryOp::Mul
+            } else if self.eat('/') {
+                BinaryOp::Div
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.unary()?;
+            lhs = DerivedExpr::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+        }
+    }
+
+    fn unary(&mut self) -> Result<DerivedExpr, String> {
+        if self.eat('-') {
+            return Ok(DerivedExpr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<DerivedExpr, String> {
+        if self.eat('(') {
+            let expr = self.expr()?;
+            self.expect(')')?;
+            return Ok(expr);
+        }
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::Number(number)) => {
+                self.pos += 1;
+                Ok(DerivedExpr::Number(number))
+            },
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                if let Some(aggregate) = Aggregate::parse(&name)
+                    && self.eat('(')
+                {
+                    if self.ident()? != "children" {
+                        return Err(format!(
+                            "{name}() 只能聚合 children.<属性名>"
+                        ));
+                    }
+                    self.expect('.')?;
+                    let attr = self.ident()?;
+                    self.expect(')')?;
+                    return Ok(DerivedExpr::Children { aggregate, attr });
+                }
+                match name.as_str() {
+                    "attrs" => {
+                        self.expect('.')?;
+                        Ok(DerivedExpr::Own(self.ident()?))
+                    },
+                    "parent" => {
+                        self.expect('.')?;
+                        let mut attr = self.ident()?;
+                        if attr == "attrs" {
+                            self.expect('.')?;
+                            attr = self.ident()?;
+                        }
+                        Ok(DerivedExpr::Parent(attr))
+                    },
+                    "children" => {
+                        Err("children 需要放在聚合函数中使用".to_string())
+                    },
+                    _ => Ok(DerivedExpr::Own(name)),
+                }
+            },
+            Some(Token::Symbol(c)) => Err(format!("意外的符号 '{c}'")),
+            None => Err("表达式意外结束".to_string()),
+        }
+    }
+}
+
+impl DerivedExpr {
+    pub fn parse(expr: &str) -> Result<Self, DerivedParseError> {
+        let error =
+            |message| DerivedParseError { expr: expr.to_string(), message };
+        let tokens = tokenize(expr).map_err(error)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let parsed = parser.expr().map_err(error)?;
+        if let Some(token) = parser.peek() {
+            return Err(error(format!("多余的内容 {token:?}")));
+        }
+        Ok(parsed)
+    }
+
+    /// 收集表达式引用的属性
+    pub fn deps(&self) -> DerivedDeps {
+        fn collect(
+            expr: &DerivedExpr,
+            deps: &mut DerivedDeps,
+        ) {
+            match expr {
+                DerivedExpr::Number(_) => {},
+                DerivedExpr::Own(attr) => {
+                    deps.own.insert(attr.clone());
+                },
+                DerivedExpr::Parent(attr) => {
+                    deps.parent.insert(attr.clone());
+                },
+                DerivedExpr::Children { attr, .. } => {
+                    deps.children.insert(attr.clone());
+                },
+                DerivedExpr::Neg(expr) => collect(expr, deps),
+                DerivedExpr::Binary { lhs, rhs, .. } => {
+                    collect(lhs, deps);
+                    collect(rhs, deps);
+                },
+            }
+        }
+        let mut deps = DerivedDeps::default();
+        collect(self, &mut deps);
+        deps
+    }
+
+    /// 计算表达式，`own` 为节点自身的属性读取函数
+    pub fn evaluate(
+        &self,
+        own: &dyn Fn(&str) -> f64,
+        parent: Option<&Node>,
+        children: &[&Node],
+    ) -> f64 {
+        match self {
+            DerivedExpr::Number(number) => *number,
+            DerivedExpr::Own(attr) => own(attr),
+            DerivedExpr::Parent(attr) => {
+                parent.map_or(0.0, |parent| attr_number(parent, attr))
+            },
+            DerivedExpr::Children { aggregate, attr } => {
+                let values = children
+                    .iter()
+                    .filter(|child| {
+                        child.attrs.get_safe(attr).is_some_and(|v| !v.is_null())
+                    })
+                    .map(|child| attr_number(child, attr));
+                aggregate_values(*aggregate, values)
+            },
+            DerivedExpr::Neg(expr) => -expr.evaluate(own, parent, children),
+            DerivedExpr::Binary { op, lhs, rhs } => {
+                let lhs = lhs.evaluate(own, parent, children);
+                let rhs = rhs.evaluate(own, parent, children);
+                match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Sub => lhs - rhs,
+                    BinaryOp::Mul => lhs * rhs,
+                    BinaryOp::Div => lhs / rhs,
+                }
+            },
+        }
+    }
+}
+
+fn aggregate_values(
+    aggregate: Aggregate,
+    values: impl Iterator<Item = f64>,
+) -> f64 {
+    let values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return 0.0;
+    }
+    match aggregate {
+        Aggregate::Sum => values.iter().sum(),
+        Aggregate::Count => values.len() as f64,
+        Aggregate::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+        Aggregate::Max => {
+            values.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+        },
+        Aggregate::Avg => values.iter().sum::<f64>() / values.len() as f64,
+    }
+}
+
+/// 属性值转换为数字，数字与数字字符串以外的值按 0 计算
+pub fn value_number(value: &Value) -> f64 {
+    match value {
+        Value::Number(number) => number.as_f64().unwrap_or(0.0),
+        Value::String(text) => text.trim().parse().unwrap_or(0.0),
+        _ => 0.0,
+    }
+}
+
+fn attr_number(
+    node: &Node,
+    attr: &str,
+) -> f64 {
+    node.attrs.get_safe(attr).map_or(0.0, value_number)
+}
+
+/// 计算结果转换为属性值，整数结果保存为整数
+pub fn number_value(number: f64) -> Value {
+    if !number.is_finite() {
+        return Value::Null;
+    }
+    if number.fract() == 0.0 && number.abs() < 9_007_199_254_740_992.0 {
+        return Value::from(number as i64);
+    }
+    serde_json::Number::from_f64(number).map_or(Value::Null, Value::Number)
+}
+
+/// 单个派生属性
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivedAttr {
+    pub name: String,
+    pub expr: DerivedExpr,
+    pub deps: DerivedDeps,
+}
+
+/// 节点类型的全部派生属性，按自身属性之间的依赖排序
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DerivedAttrs {
+    attrs: Vec<DerivedAttr>,
+    parent_deps: BTreeSet<String>,
+    children_deps: BTreeSet<String>,
+}
+
+impl DerivedAttrs {
+    /// 编译节点类型的属性规范，引用未声明的属性或存在循环依赖时返回错误
+    pub fn compile(
+        specs: &HashMap<String, AttributeSpec>
+    ) -> Result<Self, String> {
+        let mut pending: Vec<DerivedAttr> = Vec::new();
+        for (name, spec) in specs {
+            let Some(expr) = &spec.computed else {
+                continue;
+            };
+            let expr = DerivedExpr::parse(expr).map_err(|e| e.to_string())?;
+            let deps = expr.deps();
+            if let Some(missing) =
+                deps.own.iter().find(|attr| !specs.contains_key(*attr))
+            {
+                return Err(format!(
+                    "派生属性 {name} 引用了未声明的属性 {missing}"
+                ));
+            }
+            pending.push(DerivedAttr { name: name.clone(), expr, deps });
+        }
+        pending.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut derived = DerivedAttrs::default();
+        for attr in &pending {
+            derived.parent_deps.extend(attr.deps.parent.iter().cloned());
+            derived.children_deps.extend(attr.deps.children.iter().cloned());
+        }
+        // 依赖的派生属性先计算
+        while !pending.is_empty() {
+            let ready = pending.iter().position(|attr| {
+                attr.deps
+                    .own
+                    .iter()
+                    .all(|dep| !pending.iter().any(|other| &other.name == dep))
+            });
+            match ready {
+                Some(index) => derived.attrs.push(pending.remove(index)),
+                None => {
+                    let names: Vec<&str> =
+                        pending.iter().map(|attr| attr.name.as_str()).collect();
+                    return Err(format!(
+                        "派生属性存在循环依赖: {}",
+                        names.join(", ")
+                    ));
+                },
+            }
+        }
+        Ok(derived)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attrs.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DerivedAttr> {
+        self.attrs.iter()
+    }
+
+    pub fn contains(
+        &self,
+        name: &str,
+    ) -> bool {
+        self.attrs.iter().any(|attr| attr.name == name)
+    }
+
+    /// 引用的父节点属性
+    pub fn parent_deps(&self) -> &BTreeSet<String> {
+        &self.parent_deps
+    }
+
+    /// 聚合的子节点属性
+    pub fn children_deps(&self) -> &BTreeSet<String> {
+        &self.children_deps
+    }
+
+    /// 重新计算节点的派生属性，只返回值发生变化的属性
+    pub fn evaluate(
+        &self,
+        node: &Node,
+        parent: Option<&Node>,
+        children: &[&Node],
+    ) -> HashTrieMapSync<String, Value> {
+        let mut computed: HashMap<&str, f64> = HashMap::new();
+        let mut changes = HashTrieMapSync::new_sync();
+        for attr in &self.attrs {
+            let own = |name: &str| {
+                computed
+                    .get(name)
+                    .copied()
+                    .unwrap_or_else(|| attr_number(node, name))
+            };
+            let number = attr.expr.evaluate(&own, parent, children);
+            computed.insert(&attr.name, number);
+            let value = number_value(number);
+            if node.attrs.get_safe(&attr.name) != Some(&value) {
+                changes.insert_mut(attr.name.clone(), value);
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attrs::Attrs;
+    use rpds::ht_map_sync;
+    use serde_json::json;
+
+    fn spec(computed: Option<&str>) -> AttributeSpec {
+        AttributeSpec {
+            computed: computed.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn node(
+        id: &str,
+        attrs: HashTrieMapSync<String, Value>,
+    ) -> Node {
+        Node::new(id, "item".to_string(), Attrs::from(attrs), vec![], vec![])
+    }
+
+    #[test]
+    fn parse_and_deps() {
+        let expr =
+            DerivedExpr::parse("qty * attrs.price - sum(children.amount) / 2")
+                .unwrap();
+        let deps = expr.deps();
+        assert_eq!(deps.own, ["price", "qty"].map(String::from).into());
+        assert!(deps.parent.is_empty());
+        assert_eq!(deps.children, ["amount"].map(String::from).into());
+
+        assert_eq!(
+            DerivedExpr::parse("-(parent.attrs.rate)").unwrap().deps().parent,
+            ["rate"].map(String::from).into()
+        );
+        for invalid in ["qty *", "sum(amount)", "children.amount", "a $ b"] {
+            assert!(DerivedExpr::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn evaluate_in_dependency_order() {
+        let mut specs = HashMap::new();
+        specs.insert("qty".to_string(), spec(None));
+        specs.insert("price".to_string(), spec(None));
+        specs.insert("total".to_string(), spec(Some("amount * parent.rate")));
+        specs.insert("amount".to_string(), spec(Some("qty * price")));
+        let derived = DerivedAttrs::compile(&specs).unwrap();
+        let names: Vec<&str> =
+            derived.iter().map(|attr| attr.name.as_str()).collect();
+        assert_eq!(names, vec!["amount", "total"]);
+
+        let item = node(
+            "a",
+            ht_map_sync! {
+                "qty".to_string() => json!(3),
+                "price".to_string() => json!("2.5")
+            },
+        );
+        let parent = node("p", ht_map_sync! { "rate".to_string() => json!(2) });
+        let changes = derived.evaluate(&item, Some(&parent), &[]);
+        assert_eq!(changes.get("amount"), Some(&json!(7.5)));
+        assert_eq!(changes.get("total"), Some(&json!(15)));
+
+        let children = [&item, &parent];
+        let aggregate = |expr: &str| {
+            DerivedExpr::parse(expr).unwrap().evaluate(
+                &|_| 0.0,
+                None,
+                &children,
+            )
+        };
+        assert_eq!(aggregate("count(children.qty)"), 1.0);
+        assert_eq!(aggregate("max(children.rate) + min(children.price)"), 4.5);
+        assert_eq!(aggregate("avg(children.missing)"), 0.0);
+        assert_eq!(number_value(1.0 / 0.0), Value::Null);
+    }
+
+    #[test]
+    fn compile_rejects_cycles_and_unknown_attrs() {
+        let mut specs = HashMap::new();
+        specs.insert("a".to_string(), spec(Some("b + 1")));
+        specs.insert("b".to_string(), spec(Some("a + 1")));
+        let err = DerivedAttrs::compile(&specs).unwrap_err();
+        assert!(err.contains("循环依赖"), "{err}");
+
+        let mut specs = HashMap::new();
+        specs.insert("a".to_string(), spec(Some("missing * 2")));
+        let err = DerivedAttrs::compile(&specs).unwrap_err();
+        assert!(err.contains("missing"), "{err}");
+    }
+}
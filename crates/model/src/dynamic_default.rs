@@ -0,0 +1,63 @@
+//! 动态默认值
+//!
+//! 属性的默认值可以在节点创建时由回调计算，例如创建时间或当前用户。
+//! 通过 [`Schema::set_dynamic_default`](crate::schema::Schema::set_dynamic_default)
+//! 注册后，调用方未提供该属性时，[`NodeFactory`](crate::node_factory::NodeFactory)
+//! 与 [`NodeDefinition::create_with_defaults`](crate::node_definition::NodeDefinition::create_with_defaults)
+//! 在创建节点时求值。
+//! 回调所需的运行时上下文（如当前用户）由闭包自行捕获。
+//!
+//! 动态默认值只在创建节点时生效，反序列化或从快照恢复的节点保留已有的值。
+
+use std::fmt::{self, Debug};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+type DefaultFn = dyn Fn(&str) -> Value + Send + Sync;
+
+/// 动态默认值回调，参数为正在创建的节点类型名称
+#[derive(Clone)]
+pub struct DynamicDefault(Arc<DefaultFn>);
+
+impl DynamicDefault {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&str) -> Value + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// 为指定节点类型计算默认值
+    pub fn resolve(
+        &self,
+        node_type: &str,
+    ) -> Value {
+        (self.0)(node_type)
+    }
+}
+
+impl PartialEq for DynamicDefault {
+    fn eq(
+        &self,
+        other: &Self,
+    ) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for DynamicDefault {}
+
+// 回调只以共享引用调用，不影响节点类型定义在 panic 后的一致性
+impl UnwindSafe for DynamicDefault {}
+impl RefUnwindSafe for DynamicDefault {}
+
+impl Debug for DynamicDefault {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str("DynamicDefault(..)")
+    }
+}
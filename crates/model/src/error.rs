@@ -15,44 +15,64 @@ pub mod error_messages {
     pub const NODE_LOCKED: &str = "节点已被锁定，无法执行操作";
     pub const NODE_DELETED: &str = "节点已被删除";
     pub const CANNOT_REMOVE_ROOT: &str = "无法删除根节点";
+    pub const INVALID_PERMUTATION: &str = "无效的子节点排列";
 }
 
 /// Helper functions for creating node pool errors
 pub mod error_helpers {
     use super::*;
+    use mf_error_codes::{coded, ErrorCode};
 
     pub fn duplicate_node(id: NodeId) -> anyhow::Error {
-        anyhow::anyhow!("{}: {}", error_messages::DUPLICATE_NODE, id)
+        coded(
+            ErrorCode::ModelDuplicateNode,
+            format!("{}: {}", error_messages::DUPLICATE_NODE, id),
+        )
     }
     pub fn schema_error(msg: &str) -> anyhow::Error {
-        anyhow::anyhow!("schema 错误：{}", msg)
+        coded(ErrorCode::ModelSchema, format!("schema 错误：{}", msg))
     }
 
     pub fn parent_not_found(id: NodeId) -> anyhow::Error {
-        anyhow::anyhow!("{}: {}", error_messages::PARENT_NOT_FOUND, id)
+        coded(
+            ErrorCode::ModelParentNotFound,
+            format!("{}: {}", error_messages::PARENT_NOT_FOUND, id),
+        )
     }
 
     pub fn child_not_found(id: NodeId) -> anyhow::Error {
-        anyhow::anyhow!("{}: {}", error_messages::CHILD_NOT_FOUND, id)
+        coded(
+            ErrorCode::ModelChildNotFound,
+            format!("{}: {}", error_messages::CHILD_NOT_FOUND, id),
+        )
     }
 
     pub fn node_not_found(id: NodeId) -> anyhow::Error {
-        anyhow::anyhow!("{}: {}", error_messages::NODE_NOT_FOUND, id)
+        coded(
+            ErrorCode::ModelNodeNotFound,
+            format!("{}: {}", error_messages::NODE_NOT_FOUND, id),
+        )
     }
 
     pub fn orphan_node(id: NodeId) -> anyhow::Error {
-        anyhow::anyhow!("{}: {}", error_messages::ORPHAN_NODE, id)
+        coded(
+            ErrorCode::ModelInvalidStructure,
+            format!("{}: {}", error_messages::ORPHAN_NODE, id),
+        )
     }
 
     pub fn invalid_parenting(
         child: NodeId,
         alleged_parent: NodeId,
     ) -> anyhow::Error {
-        anyhow::anyhow!(
-            "{}: 子节点 {} 不是父节点 {} 的子节点",
-            error_messages::INVALID_PARENTING,
-            child,
-            alleged_parent
+        coded(
+            ErrorCode::ModelInvalidStructure,
+            format!(
+                "{}: 子节点 {} 不是父节点 {} 的子节点",
+                error_messages::INVALID_PARENTING,
+                child,
+                alleged_parent
+            ),
         )
     }
 
@@ -60,44 +80,73 @@ pub mod error_helpers {
         nodeid: NodeId,
         new_node_id: NodeId,
     ) -> anyhow::Error {
-        anyhow::anyhow!(
-            "{}: 新节点ID({})与要替换的节点ID({})不一致",
-            error_messages::INVALID_NODE_ID,
-            nodeid,
-            new_node_id
+        coded(
+            ErrorCode::ModelInvalidStructure,
+            format!(
+                "{}: 新节点ID({})与要替换的节点ID({})不一致",
+                error_messages::INVALID_NODE_ID,
+                nodeid,
+                new_node_id
+            ),
         )
     }
 
     pub fn empty_pool() -> anyhow::Error {
-        anyhow::anyhow!(error_messages::EMPTY_POOL)
+        coded(ErrorCode::ModelInvalidStructure, error_messages::EMPTY_POOL)
     }
 
     pub fn cyclic_reference(id: NodeId) -> anyhow::Error {
-        anyhow::anyhow!(
-            "{}: 节点 {} 不能成为自己的祖先",
-            error_messages::CYCLIC_REFERENCE,
-            id
+        coded(
+            ErrorCode::ModelInvalidStructure,
+            format!(
+                "{}: 节点 {} 不能成为自己的祖先",
+                error_messages::CYCLIC_REFERENCE,
+                id
+            ),
         )
     }
 
     pub fn invalid_node_move(id: NodeId) -> anyhow::Error {
-        anyhow::anyhow!(
-            "{}: 无法将节点 {} 移动到目标位置",
-            error_messages::INVALID_NODE_MOVE,
-            id
+        coded(
+            ErrorCode::ModelInvalidStructure,
+            format!(
+                "{}: 无法将节点 {} 移动到目标位置",
+                error_messages::INVALID_NODE_MOVE,
+                id
+            ),
         )
     }
 
     pub fn node_locked(id: NodeId) -> anyhow::Error {
-        anyhow::anyhow!("{}: {}", error_messages::NODE_LOCKED, id)
+        coded(
+            ErrorCode::ModelNodeLocked,
+            format!("{}: {}", error_messages::NODE_LOCKED, id),
+        )
     }
 
     pub fn node_deleted(id: NodeId) -> anyhow::Error {
-        anyhow::anyhow!("{}: {}", error_messages::NODE_DELETED, id)
+        coded(
+            ErrorCode::ModelNodeDeleted,
+            format!("{}: {}", error_messages::NODE_DELETED, id),
+        )
     }
 
     pub fn cannot_remove_root() -> anyhow::Error {
-        anyhow::anyhow!(error_messages::CANNOT_REMOVE_ROOT)
+        coded(
+            ErrorCode::ModelInvalidStructure,
+            error_messages::CANNOT_REMOVE_ROOT,
+        )
+    }
+
+    pub fn invalid_permutation(parent_id: NodeId) -> anyhow::Error {
+        coded(
+            ErrorCode::ModelInvalidStructure,
+            format!(
+                "{}: 新顺序必须与节点 {} 现有的子节点一一对应",
+                error_messages::INVALID_PERMUTATION,
+                parent_id
+            ),
+        )
     }
 }
 
@@ -13,10 +13,14 @@
 //! - `node`: 节点定义，表示文档中的基本元素
 //! - `mark`: 标记定义，用于文档的格式化
 //! - `attrs`: 属性定义，存储节点和标记的属性
+//! - `attr_index`: 属性值二级索引，加速按属性值查找节点
+//! - `mark_cascade`: 标记级联，计算节点从祖先继承的标记
 //! - `mark_type`: 标记类型定义，定义不同类型的标记
 //! - `node_type`: 节点类型定义，定义不同类型的节点
 //! - `schema`: 模式定义，定义文档结构规则
 //! - `content`: 内容匹配定义，处理内容验证和匹配
+//! - `derived`: 派生属性表达式与计算
+//! - `dynamic_default`: 节点创建时由回调计算的属性默认值
 //! - `error`: 错误类型和处理
 //! - `id_generator`: ID 生成器，生成唯一标识符
 //! - `node_pool`: 节点池，管理节点实例
@@ -27,8 +31,10 @@ pub mod node;
 //标记定义
 pub mod mark;
 //属性定义
+pub mod attr_index;
 pub mod attrs;
 //标记类型定义
+pub mod mark_cascade;
 pub mod mark_definition;
 //节点类型定义
 pub mod node_definition;
@@ -37,6 +43,9 @@ pub mod node_factory;
 pub mod schema;
 //内容匹配定义
 pub mod content;
+//派生属性
+pub mod derived;
+pub mod dynamic_default;
 //id生成器定义
 pub mod error;
 pub mod id_generator;
@@ -61,7 +70,7 @@ pub use ops::*;
 pub use tree::Tree;
 pub use types::*;
 pub use mark_definition::MarkDefinition;
-pub use node_definition::NodeDefinition;
+pub use node_definition::{NodeCreationError, NodeDefinition, SchemaError};
 pub use schema::Schema;
 pub use node_factory::NodeFactory;
 // 导出通用抽象层
@@ -0,0 +1,328 @@
+//! 标记级联
+//!
+//! 标记规范通过 [`MarkSpec::cascade`](crate::mark_definition::MarkSpec::cascade)
+//! 声明为级联标记后，对所有后代节点生效。节点的有效标记由自身标记与祖先的级联标记合并：
+//! - 节点自身的同类型标记整体覆盖继承的标记
+//! - 多个祖先带有同类型标记时按属性合并，冲突的属性以最近的祖先为准
+//!
+//! 继承的标记按节点惰性计算并缓存在节点池中。节点池不可变，
+//! 任何修改都会产生新的节点池与新的空缓存，因此缓存不需要失效处理。
+
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+
+use crate::{mark::Mark, node_pool::NodePool, schema::Schema, types::NodeId};
+
+/// 节点从祖先继承的标记缓存：节点 id -> 祖先标记（不区分是否级联）
+///
+/// 缓存只属于一个版本的节点池，克隆节点池时共享，不参与序列化与相等比较。
+/// 祖先都没有标记的节点共享同一份结果，不会额外分配。
+#[derive(Clone, Default)]
+pub struct InheritedMarks {
+    pub(crate) entries: Arc<DashMap<NodeId, Arc<[Mark]>>>,
+}
+
+impl PartialEq for InheritedMarks {
+    fn eq(
+        &self,
+        _other: &Self,
+    ) -> bool {
+        true
+    }
+}
+
+impl Debug for InheritedMarks {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "InheritedMarks {{ 缓存节点数: {} }}", self.entries.len())
+    }
+}
+
+impl InheritedMarks {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// 将父节点自身的标记叠加到父节点继承的标记上，得到子节点继承的标记
+fn inherit(
+    inherited: &Arc<[Mark]>,
+    parent_marks: &[Mark],
+) -> Arc<[Mark]> {
+    if parent_marks.is_empty() {
+        return inherited.clone();
+    }
+    let mut marks = inherited.to_vec();
+    for mark in parent_marks {
+        match marks.iter_mut().find(|m| m.r#type == mark.r#type) {
+            Some(existing) => {
+                existing.attrs =
+                    existing.attrs.update(mark.attrs.attrs.clone());
+            },
+            None => marks.push(mark.clone()),
+        }
+    }
+    marks.into()
+}
+
+impl NodePool {
+    /// 祖先标记缓存
+    pub fn inherited_marks_cache(&self) -> &InheritedMarks {
+        &self.inherited_marks
+    }
+
+    /// 节点从祖先继承的全部标记（含不级联的类型），节点不存在时返回空
+    ///
+    /// 从节点向上查找最近的已缓存祖先，再自上而下补齐路径上的缓存，
+    /// 同一版本内重复查询不会重新遍历祖先链。
+    fn ancestor_marks(
+        &self,
+        node_id: &NodeId,
+    ) -> Arc<[Mark]> {
+        let cache = &self.inherited_marks.entries;
+        if let Some(hit) = cache.get(node_id) {
+            return hit.clone();
+        }
+        let mut chain = vec![node_id];
+        let mut parent = None;
+        let mut inherited: Arc<[Mark]> = Arc::from([]);
+        let mut current = node_id;
+        while let Some(parent_id) = self.parent_id(current) {
+            if let Some(hit) = cache.get(parent_id) {
+                inherited = hit.clone();
+                parent = Some(parent_id);
+                break;
+            }
+            chain.push(parent_id);
+            current = parent_id;
+        }
+        for id in chain.into_iter().rev() {
+            if let Some(parent_node) = parent.and_then(|p| self.get_node(p)) {
+                let parent_marks: Vec<Mark> =
+                    parent_node.marks.iter().cloned().collect();
+                inherited = inherit(&inherited, &parent_marks);
+            }
+            cache.insert(id.clone(), inherited.clone());
+            parent = Some(id);
+        }
+        inherited
+    }
+
+    /// 节点的有效标记：自身标记加上祖先的级联标记，节点不存在时返回空
+    ///
+    /// 自身标记在前并保持原有顺序，继承的标记按祖先由近到远首次出现的顺序排列
+    pub fn effective_marks(
+        &self,
+        node_id: &NodeId,
+        schema: &Schema,
+    ) -> Vec<Mark> {
+        let Some(node) = self.get_node(node_id) else {
+            return Vec::new();
+        };
+        let mut marks: Vec<Mark> = node.marks.iter().cloned().collect();
+        for mark in self.ancestor_marks(node_id).iter() {
+            if schema.mark_cascades(&mark.r#type)
+                && marks.iter().all(|own| own.r#type != mark.r#type)
+            {
+                marks.push(mark.clone());
+            }
+        }
+        marks
+    }
+
+    /// 节点自身或经祖先级联是否带有指定类型的标记
+    pub fn has_effective_mark(
+        &self,
+        node_id: &NodeId,
+        schema: &Schema,
+        mark_type: &str,
+    ) -> bool {
+        let Some(node) = self.get_node(node_id) else {
+            return false;
+        };
+        if node.marks.iter().any(|m| m.r#type == mark_type) {
+            return true;
+        }
+        schema.mark_cascades(mark_type)
+            && self
+                .ancestor_marks(node_id)
+                .iter()
+                .any(|m| m.r#type == mark_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rpds::HashTrieMapSync;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        attrs::Attrs, mark_definition::MarkSpec, node::Node,
+        node_definition::NodeSpec, schema::SchemaSpec, tree::Tree,
+    };
+
+    fn schema() -> Schema {
+        let mut marks = HashMap::new();
+        marks.insert(
+            "locked".to_string(),
+            MarkSpec { cascade: Some(true), ..Default::default() },
+        );
+        marks.insert("bold".to_string(), MarkSpec::default());
+        let mut nodes = HashMap::new();
+        nodes.insert("node".to_string(), NodeSpec::default());
+        Schema::compile(SchemaSpec {
+            nodes,
+            marks,
+            top_node: Some("node".to_string()),
+        })
+        .unwrap()
+    }
+
+    fn mark(
+        r#type: &str,
+        attrs: &[(&str, Value)],
+    ) -> Mark {
+        let mut map = HashTrieMapSync::new_sync();
+        for (key, value) in attrs {
+            map.insert_mut(key.to_string(), value.clone());
+        }
+        Mark { r#type: r#type.to_string(), attrs: Attrs::from(map) }
+    }
+
+    fn node(id: &str) -> Node {
+        Node::new(id, "node".to_string(), Attrs::default(), vec![], vec![])
+    }
+
+    /// root -> project -> unit -> item
+    fn tree() -> Tree {
+        let mut tree = Tree::new(node("root"));
+        tree.add_node(&"root".into(), &vec![node("project")]).unwrap();
+        tree.add_node(&"project".into(), &vec![node("unit")]).unwrap();
+        tree.add_node(&"unit".into(), &vec![node("item")]).unwrap();
+        tree
+    }
+
+    fn types(marks: &[Mark]) -> Vec<&str> {
+        marks.iter().map(|m| m.r#type.as_str()).collect()
+    }
+
+    #[test]
+    fn test_cascade_merges_ancestor_marks() {
+        let schema = schema();
+        let mut tree = tree();
+        tree.add_mark(
+            &"project".into(),
+            &[
+                mark(
+                    "locked",
+                    &[("by", json!("a")), ("reason", json!("审核"))],
+                ),
+                mark("bold", &[]),
+            ],
+        )
+        .unwrap();
+        tree.add_mark(&"unit".into(), &[mark("locked", &[("by", json!("b"))])])
+            .unwrap();
+        let pool = NodePool::new(Arc::new(tree));
+
+        // 最近的祖先覆盖冲突属性，其余属性保留；不级联的 bold 不继承
+        let marks = pool.effective_marks(&"item".into(), &schema);
+        assert_eq!(types(&marks), ["locked"]);
+        assert_eq!(marks[0].attrs.get_value::<String>("by").unwrap(), "b");
+        assert_eq!(
+            marks[0].attrs.get_value::<String>("reason").unwrap(),
+            "审核"
+        );
+
+        // 自身标记整体覆盖继承的标记
+        let mut tree = pool.get_inner().as_ref().clone();
+        tree.add_mark(&"item".into(), &[mark("locked", &[])]).unwrap();
+        let pool = NodePool::new(Arc::new(tree));
+        let marks = pool.effective_marks(&"item".into(), &schema);
+        assert_eq!(types(&marks), ["locked"]);
+        assert!(marks[0].attrs.attrs.is_empty());
+
+        assert_eq!(
+            types(&pool.effective_marks(&"project".into(), &schema)),
+            ["locked", "bold"]
+        );
+        assert!(pool.effective_marks(&"root".into(), &schema).is_empty());
+        assert!(pool.effective_marks(&"missing".into(), &schema).is_empty());
+    }
+
+    #[test]
+    fn test_cache_follows_generations() {
+        let schema = schema();
+        let mut tree = tree();
+        let base = NodePool::new(Arc::new(tree.clone()));
+        assert!(!base.has_effective_mark(&"item".into(), &schema, "locked"));
+        assert_eq!(base.inherited_marks_cache().len(), 4);
+
+        // 祖先加锁：新版本可见，旧版本的缓存结果不变
+        tree.add_mark(&"project".into(), &[mark("locked", &[])]).unwrap();
+        let locked = NodePool::new(Arc::new(tree.clone()));
+        assert!(locked.inherited_marks_cache().is_empty());
+        assert!(locked.has_effective_mark(&"item".into(), &schema, "locked"));
+        assert!(locked.has_effective_mark(&"unit".into(), &schema, "locked"));
+        assert!(!locked.has_effective_mark(&"root".into(), &schema, "locked"));
+        assert!(!base.has_effective_mark(&"item".into(), &schema, "locked"));
+
+        // 先查询子节点再查询祖先：路径上的缓存已补齐
+        let cached = locked.inherited_marks_cache().len();
+        assert!(locked.has_effective_mark(&"unit".into(), &schema, "locked"));
+        assert_eq!(locked.inherited_marks_cache().len(), cached);
+
+        // 解锁后再查询
+        tree.remove_mark(&"project".into(), &["locked".to_string()]).unwrap();
+        let unlocked = NodePool::new(Arc::new(tree.clone()));
+        assert!(!unlocked.has_effective_mark(
+            &"item".into(),
+            &schema,
+            "locked"
+        ));
+        assert!(locked.has_effective_mark(&"item".into(), &schema, "locked"));
+
+        // 在更低的祖先重新加锁，只影响其子树
+        tree.add_mark(&"unit".into(), &[mark("locked", &[])]).unwrap();
+        let relocked = NodePool::new(Arc::new(tree.clone()));
+        assert!(!relocked.has_effective_mark(
+            &"project".into(),
+            &schema,
+            "locked"
+        ));
+        assert!(relocked.has_effective_mark(&"item".into(), &schema, "locked"));
+
+        // 克隆的节点池共享同一份缓存
+        let shared = relocked.as_ref().clone();
+        assert_eq!(
+            shared.inherited_marks_cache().len(),
+            relocked.inherited_marks_cache().len()
+        );
+        assert!(!unlocked.has_effective_mark(
+            &"unit".into(),
+            &schema,
+            "locked"
+        ));
+    }
+
+    #[test]
+    fn test_non_cascading_mark_stays_on_node() {
+        let schema = schema();
+        let mut tree = tree();
+        tree.add_mark(&"project".into(), &[mark("bold", &[])]).unwrap();
+        let pool = NodePool::new(Arc::new(tree));
+        assert!(pool.has_effective_mark(&"project".into(), &schema, "bold"));
+        assert!(!pool.has_effective_mark(&"unit".into(), &schema, "bold"));
+    }
+}
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
@@ -12,7 +12,10 @@ pub struct MarkDefinition {
     pub rank: usize,
     pub spec: MarkSpec,
     pub attrs: HashMap<String, Attribute>,
-    pub excluded: Option<Vec<MarkDefinition>>,
+    /// 与本标记互斥的标记名称，由 [`MarkSpec::excludes`] 在编译 Schema 时解析
+    ///
+    /// 此前为 `Option<Vec<MarkDefinition>>` 且从未填充，现改为只保存名称。
+    pub excluded: Vec<String>,
 }
 
 impl MarkDefinition {
@@ -45,7 +48,7 @@ impl MarkDefinition {
                 .collect()
         });
 
-        MarkDefinition { name, rank, spec, attrs, excluded: None }
+        MarkDefinition { name, rank, spec, attrs, excluded: Vec::new() }
     }
 
     pub fn create(
@@ -64,14 +67,34 @@ impl MarkDefinition {
         }
     }
 
+    /// 两个标记是否不能同时存在于同一节点上
+    ///
+    /// 任一方的 `excluded` 包含另一方即视为互斥。
+    pub fn is_exclusive_with(
+        &self,
+        other: &MarkDefinition,
+    ) -> bool {
+        self.excluded.contains(&other.name)
+            || other.excluded.contains(&self.name)
+    }
+
+    /// 标记是否对所有后代节点生效
+    pub fn cascades(&self) -> bool {
+        self.spec.cascade == Some(true)
+    }
+
     // 其他方法...
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Default)]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default)]
 pub struct MarkSpec {
     pub attrs: Option<HashMap<String, AttributeSpec>>,
+    /// 互斥的标记名称或分组，以空格分隔，`_` 表示所有标记；
+    /// 未设置时只与同类型标记互斥，空字符串表示不与任何标记互斥
     pub excludes: Option<String>,
     pub group: Option<String>,
     pub spanning: Option<bool>,
+    /// 为 true 时标记对所有后代节点生效，见 `NodePool::effective_marks`
+    pub cascade: Option<bool>,
     pub desc: Option<String>,
 }
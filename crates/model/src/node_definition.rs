@@ -1,11 +1,15 @@
-﻿use super::attrs::Attrs;
+use super::attrs::Attrs;
+use super::id_generator::IdGenerator;
 use super::content::ContentMatch;
+use super::dynamic_default::DynamicDefault;
 use super::mark::Mark;
 use super::mark_definition::MarkDefinition;
 use super::node::Node;
 use super::schema::{compute_attrs, Attribute, AttributeSpec, Schema};
+use super::types::NodeId;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::{self, Debug};
 
@@ -28,6 +32,60 @@ impl NodeTree {
         )
     }
 }
+/// 节点创建错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeCreationError {
+    /// 属性没有默认值且调用方未提供
+    MissingRequired { node_type: String, attr: String },
+}
+
+impl fmt::Display for NodeCreationError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            NodeCreationError::MissingRequired { node_type, attr } => {
+                write!(f, "节点 {node_type} 属性 {attr} 没有值，这个属性必填")
+            },
+        }
+    }
+}
+
+impl std::error::Error for NodeCreationError {}
+
+/// 属性不符合节点类型的属性规范，由 [`NodeDefinition::create_validated`] 返回
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// 必需属性没有提供
+    MissingRequired { node_type: String, attr: String },
+    /// 提供了节点类型未定义的属性
+    UnknownAttr { node_type: String, attr: String },
+    /// 属性值不满足属性规范的约束
+    InvalidValue { node_type: String, attr: String, value: Value },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            SchemaError::MissingRequired { node_type, attr } => {
+                write!(f, "节点 {node_type} 属性 {attr} 没有值，这个属性必填")
+            },
+            SchemaError::UnknownAttr { node_type, attr } => {
+                write!(f, "节点 {node_type} 属性 {attr} 没有定义")
+            },
+            SchemaError::InvalidValue { node_type, attr, value } => {
+                write!(f, "节点 {node_type} 的属性 {attr} 不满足约束: {value}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
 /// 用于描述节点类型的行为规则和属性约束，通过[Schema](super::schema::Schema)进行统一管理
 #[derive(Clone, PartialEq, Eq)]
 pub struct NodeDefinition {
@@ -47,6 +105,8 @@ pub struct NodeDefinition {
     pub content_match: Option<ContentMatch>,
     /// 允许附加的Mark类型集合
     pub mark_set: Option<Vec<MarkDefinition>>,
+    /// 创建节点时由回调计算的默认值（属性名 -> 回调）
+    pub(crate) dynamic_defaults: HashMap<String, DynamicDefault>,
 }
 impl Debug for NodeDefinition {
     fn fmt(
@@ -61,6 +121,7 @@ impl Debug for NodeDefinition {
             .field("attrs", &self.attrs)
             .field("default_attrs", &self.default_attrs)
             .field("mark_set", &self.mark_set)
+            .field("dynamic_defaults", &self.dynamic_defaults)
             .finish()
     }
 }
@@ -90,8 +151,10 @@ impl NodeDefinition {
         let result_clone = result.clone();
         for (_, node_type) in result.iter_mut() {
             if let Some(content) = &node_type.spec.content {
-                node_type.content_match =
-                    Some(ContentMatch::parse(content.clone(), &result_clone));
+                node_type.content_match = Some(ContentMatch::parse_cached(
+                    content.clone(),
+                    &result_clone,
+                ));
             }
         }
 
@@ -137,6 +200,7 @@ impl NodeDefinition {
             default_attrs,
             content_match: None,
             mark_set: None,
+            dynamic_defaults: HashMap::new(),
         }
     }
     /// 验证节点内容是否符合类型约束
@@ -187,6 +251,155 @@ impl NodeDefinition {
         }
     }
 
+    /// 子节点排序规则，未声明或表达式无效时返回 None
+    pub fn sort_spec(&self) -> Option<SortSpec> {
+        self.spec.sort_by.as_deref().and_then(SortSpec::parse)
+    }
+
+    /// 以默认属性创建节点，`attrs` 只需提供非默认值
+    ///
+    /// 提供的属性覆盖默认值，未定义的属性被忽略；
+    /// 没有默认值且未提供的属性返回 [`NodeCreationError::MissingRequired`]。
+    pub fn create_with_defaults(
+        &self,
+        attrs: Option<HashMap<String, Value>>,
+    ) -> Result<Node, NodeCreationError> {
+        let given = attrs.unwrap_or_default();
+        let mut values = self.default_attrs.clone();
+        values.extend(given.iter().map(|(k, v)| (k.clone(), v.clone())));
+        let mut missing: Vec<&String> = self
+            .attrs
+            .iter()
+            .filter(|(name, attr)| {
+                attr.is_required()
+                    && !values.contains_key(*name)
+                    && !self.dynamic_defaults.contains_key(*name)
+            })
+            .map(|(name, _)| name)
+            .collect();
+        missing.sort();
+        if let Some(attr) = missing.first() {
+            return Err(NodeCreationError::MissingRequired {
+                node_type: self.name.clone(),
+                attr: attr.to_string(),
+            });
+        }
+        let mut computed = compute_attrs(&self.attrs, Some(&values));
+        self.resolve_dynamic_defaults(&mut computed, Some(&given));
+        Ok(Node::new(
+            &IdGenerator::get_id(),
+            self.name.clone(),
+            computed,
+            vec![],
+            self.compute_marks(None),
+        ))
+    }
+
+    /// 校验属性后创建节点
+    ///
+    /// 提供的属性必须已定义且满足属性规范的约束，必需属性必须提供；
+    /// 未提供的属性取默认值。不需要校验的内部路径使用
+    /// [`NodeFactory::create_node`](crate::node_factory::NodeFactory::create_node)。
+    pub fn create_validated(
+        &self,
+        id: Option<NodeId>,
+        attrs: Option<&HashMap<String, Value>>,
+        content: Vec<NodeId>,
+        marks: Option<Vec<Mark>>,
+    ) -> Result<Node, SchemaError> {
+        let given = attrs.cloned().unwrap_or_default();
+        let mut keys: Vec<&String> = given.keys().collect();
+        keys.sort();
+        for key in keys {
+            if !self.attrs.contains_key(key) {
+                return Err(SchemaError::UnknownAttr {
+                    node_type: self.name.clone(),
+                    attr: key.clone(),
+                });
+            }
+            let constraint = self
+                .spec
+                .attrs
+                .as_ref()
+                .and_then(|specs| specs.get(key))
+                .and_then(|spec| spec.constraint.as_ref());
+            if constraint.is_some_and(|c| !c.allows(&given[key])) {
+                return Err(SchemaError::InvalidValue {
+                    node_type: self.name.clone(),
+                    attr: key.clone(),
+                    value: given[key].clone(),
+                });
+            }
+        }
+        let mut missing: Vec<&String> = self
+            .attrs
+            .iter()
+            .filter(|(name, attr)| {
+                attr.is_required()
+                    && !given.contains_key(*name)
+                    && !self.dynamic_defaults.contains_key(*name)
+            })
+            .map(|(name, _)| name)
+            .collect();
+        missing.sort();
+        if let Some(attr) = missing.first() {
+            return Err(SchemaError::MissingRequired {
+                node_type: self.name.clone(),
+                attr: attr.to_string(),
+            });
+        }
+        let id = id.unwrap_or_else(IdGenerator::get_id);
+        Ok(Node::new(
+            &id,
+            self.name.clone(),
+            self.compute_attrs(Some(&given)),
+            content,
+            self.compute_marks(marks),
+        ))
+    }
+
+    /// 属性是否声明了动态默认值
+    pub fn has_dynamic_default(
+        &self,
+        attr: &str,
+    ) -> bool {
+        self.dynamic_defaults.contains_key(attr)
+    }
+
+    /// 节点类型的有效属性集合（按名称排序），包含扩展合并的全局属性
+    ///
+    /// 供导出 JSON Schema 等需要完整属性清单的场景使用
+    pub fn effective_attrs(&self) -> Vec<EffectiveAttr<'_>> {
+        let mut attrs: Vec<EffectiveAttr<'_>> = self
+            .attrs
+            .iter()
+            .map(|(name, attr)| {
+                let dynamic = self.dynamic_defaults.contains_key(name);
+                EffectiveAttr {
+                    name,
+                    default: attr.default.as_ref().filter(|_| !dynamic),
+                    required: attr.is_required() && !dynamic,
+                    dynamic,
+                }
+            })
+            .collect();
+        attrs.sort_by(|a, b| a.name.cmp(b.name));
+        attrs
+    }
+
+    /// 为调用方未提供的属性计算动态默认值
+    fn resolve_dynamic_defaults(
+        &self,
+        attrs: &mut Attrs,
+        given: Option<&HashMap<String, Value>>,
+    ) {
+        for (name, default) in &self.dynamic_defaults {
+            if given.is_none_or(|given| !given.contains_key(name)) {
+                attrs[name] = default.resolve(&self.name);
+            }
+        }
+    }
+
     /// 检查节点是否包含必须的属性
     pub fn has_required_attrs(&self) -> bool {
         self.attrs.values().any(|attr: &Attribute| attr.is_required())
@@ -212,17 +425,31 @@ impl NodeDefinition {
         &self,
         attrs: Option<&HashMap<String, Value>>,
     ) -> Attrs {
-        match attrs {
+        let mut computed = match attrs {
             Some(attr) => compute_attrs(&self.attrs, Some(attr)),
             None => compute_attrs(&self.attrs, Some(&self.default_attrs)),
-        }
+        };
+        self.resolve_dynamic_defaults(&mut computed, attrs);
+        computed
     }
 }
 
+/// 节点类型的一个有效属性
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveAttr<'a> {
+    pub name: &'a str,
+    /// 静态默认值，动态默认值的属性为 None
+    pub default: Option<&'a Value>,
+    /// 创建节点时必须提供
+    pub required: bool,
+    /// 默认值在创建节点时由回调计算
+    pub dynamic: bool,
+}
+
 /// 定义节点类型的约束规范
 ///
 /// 用于配置节点类型的元数据和行为规则，通过[NodeType::compile]转换为可用类型
-#[derive(Clone, PartialEq, Debug, Eq, Default)]
+#[derive(Clone, PartialEq, Debug, Eq, Default, Serialize, Deserialize)]
 pub struct NodeSpec {
     /// 内容约束表达式（例如："*"）
     pub content: Option<String>,
@@ -234,4 +461,86 @@ pub struct NodeSpec {
     pub desc: Option<String>,
     /// 属性规范定义（属性名 -> 属性规范）
     pub attrs: Option<HashMap<String, AttributeSpec>>,
+    /// 子节点排序规则（例如："attrs.seq"、"attrs.seq desc"），见 [SortSpec]
+    pub sort_by: Option<String>,
+}
+
+/// 子节点排序规则
+///
+/// 子节点按属性值排序，值相同时保持插入顺序；缺少该属性（或为 null）的
+/// 子节点排在最后。数字按数值比较，字符串按字典序比较。
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct SortSpec {
+    /// 排序使用的属性名
+    pub key: String,
+    /// 是否降序
+    pub descending: bool,
+}
+
+impl SortSpec {
+    /// 解析排序表达式：`[attrs.]<属性名> [asc|desc]`
+    pub fn parse(expr: &str) -> Option<Self> {
+        let mut parts = expr.split_whitespace();
+        let key = parts.next()?;
+        let key = key.strip_prefix("attrs.").unwrap_or(key);
+        let descending = match parts.next() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(_) => return None,
+        };
+        if key.is_empty() || parts.next().is_some() {
+            return None;
+        }
+        Some(Self { key: key.to_string(), descending })
+    }
+
+    /// 排序键，缺少属性时返回 None
+    pub fn key_of<'a>(
+        &self,
+        node: &'a Node,
+    ) -> Option<&'a Value> {
+        node.attrs.get_safe(&self.key).filter(|value| !value.is_null())
+    }
+
+    /// 比较两个节点的排序键，相等时由调用方按插入顺序决定
+    pub fn compare(
+        &self,
+        a: &Node,
+        b: &Node,
+    ) -> Ordering {
+        match (self.key_of(a), self.key_of(b)) {
+            (Some(a), Some(b)) => {
+                let ordering = compare_values(a, b);
+                if self.descending { ordering.reverse() } else { ordering }
+            },
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+fn compare_values(
+    a: &Value,
+    b: &Value,
+) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64(), b.as_f64());
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        },
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
 }
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use serde_json::Value;
 
 use crate::{
+    content::ContentMatch,
     error::{error_helpers::schema_error, PoolResult},
     id_generator::IdGenerator,
     mark::Mark,
@@ -46,6 +47,20 @@ impl<'schema> NodeFactory<'schema> {
         Ok(Self::instantiate_node(node_type, id, attrs, content, marks))
     }
 
+    /// 按类型名称创建单节点，创建前按属性规范校验提供的属性。
+    pub fn create_node_validated(
+        &self,
+        type_name: &str,
+        id: Option<NodeId>,
+        attrs: Option<&HashMap<String, Value>>,
+        content: Vec<NodeId>,
+        marks: Option<Vec<Mark>>,
+    ) -> PoolResult<Node> {
+        self.ensure_node(type_name)?
+            .create_validated(id, attrs, content, marks)
+            .map_err(|e| schema_error(&e.to_string()))
+    }
+
     /// 获取节点类型定义引用，便于上层读取配置。
     pub fn node_definition(
         &self,
@@ -284,6 +299,117 @@ impl<'schema> NodeFactory<'schema> {
         Ok(NodeTree(node, filled_nodes))
     }
 
+    /// 按内容规则构建满足约束的最小子树，属性全部取默认值。
+    ///
+    /// 与 [`create_tree`](Self::create_tree) 不同，必需属性没有默认值、
+    /// 内容规则无法满足或必需内容形成循环时返回错误，而不是生成不完整的节点。
+    pub fn create_minimal_tree(
+        &self,
+        type_name: &str,
+        id: Option<NodeId>,
+    ) -> PoolResult<NodeTree> {
+        let node_type = self.ensure_node(type_name)?;
+        self.minimal_tree(node_type, id, &mut Vec::new())
+    }
+
+    fn minimal_tree<'a>(
+        &'a self,
+        node_type: &'a NodeDefinition,
+        id: Option<NodeId>,
+        path: &mut Vec<&'a str>,
+    ) -> PoolResult<NodeTree> {
+        let name = node_type.name.as_str();
+        if path.contains(&name) {
+            path.push(name);
+            return Err(schema_error(&format!(
+                "节点类型 {name} 的必需内容形成循环: {}",
+                path.join(" -> ")
+            )));
+        }
+        let mut missing: Vec<&str> = node_type
+            .attrs
+            .iter()
+            .filter(|(key, attr)| {
+                attr.is_required() && !node_type.has_dynamic_default(key)
+            })
+            .map(|(key, _)| key.as_str())
+            .collect();
+        if !missing.is_empty() {
+            missing.sort();
+            return Err(schema_error(&format!(
+                "节点类型 {name} 的属性 {} 没有默认值，无法自动创建",
+                missing.join(", ")
+            )));
+        }
+        path.push(name);
+        let children = match &node_type.content_match {
+            Some(content_match) => {
+                let mut error = None;
+                self.minimal_children(
+                    content_match,
+                    &mut vec![content_match.clone()],
+                    path,
+                    &mut error,
+                )
+                .ok_or_else(|| {
+                    // 所有分支都失败时报告遇到的第一个子节点错误
+                    error.unwrap_or_else(|| {
+                        schema_error(&format!(
+                            "节点类型 {name} 的内容规则无法满足"
+                        ))
+                    })
+                })
+            },
+            None => Ok(Vec::new()),
+        };
+        path.pop();
+        let children = children?;
+
+        let content = children.iter().map(|child| child.0.id.clone()).collect();
+        let node = Self::instantiate_node(node_type, id, None, content, None);
+        Ok(NodeTree(node, children))
+    }
+
+    /// 沿内容规则搜索可以补齐的子节点序列
+    ///
+    /// 某个子节点无法创建（例如形成循环）时回退并尝试其他分支，
+    /// `error` 记录遇到的第一个子节点错误
+    fn minimal_children<'a>(
+        &'a self,
+        state: &ContentMatch,
+        seen: &mut Vec<ContentMatch>,
+        path: &mut Vec<&'a str>,
+        error: &mut Option<anyhow::Error>,
+    ) -> Option<Vec<NodeTree>> {
+        if state.valid_end {
+            return Some(Vec::new());
+        }
+        for edge in &state.next {
+            if seen.contains(&edge.next) {
+                continue;
+            }
+            let child =
+                self.ensure_node(&edge.node_type.name).and_then(|child_type| {
+                    self.minimal_tree(child_type, None, path)
+                });
+            match child {
+                Ok(child) => {
+                    seen.push(edge.next.clone());
+                    if let Some(mut rest) =
+                        self.minimal_children(&edge.next, seen, path, error)
+                    {
+                        rest.insert(0, child);
+                        return Some(rest);
+                    }
+                },
+                Err(err) => {
+                    error.get_or_insert(err);
+                },
+            }
+        }
+        None
+    }
+
     fn instantiate_node(
         node_type: &NodeDefinition,
         id: Option<NodeId>,
@@ -350,6 +476,22 @@ mod tests {
         assert!(msg.contains("italic"), "actual: {msg}");
         assert!(msg.contains("bold"), "actual: {msg}");
     }
+    #[test]
+    fn schema_catalog_introspection() {
+        let schema = build_schema();
+        let nodes: Vec<&str> =
+            schema.node_types().map(|node| node.name.as_str()).collect();
+        assert_eq!(nodes, vec!["doc", "paragraph"]);
+        assert!(schema.node_types().all(|node| node.content_match.is_some()));
+        let marks: Vec<&str> =
+            schema.mark_types().map(|mark| mark.name.as_str()).collect();
+        assert_eq!(marks, vec!["bold"]);
+        assert_eq!(
+            schema.top_node().map(|node| node.name.as_str()),
+            Some("doc")
+        );
+    }
+
     #[test]
     fn node_and_mark_names_exposed() {
         let schema = build_schema();
@@ -359,4 +501,258 @@ mod tests {
         let marks = factory.mark_names();
         assert!(marks.contains(&"bold"));
     }
+
+    #[test]
+    fn create_with_defaults_fills_attrs() {
+        use crate::node_definition::NodeCreationError;
+        use crate::schema::AttributeSpec;
+
+        let mut attrs = HashMap::new();
+        attrs.insert(
+            "level".to_string(),
+            AttributeSpec::new(Some(Value::from(1))),
+        );
+        attrs.insert("title".to_string(), AttributeSpec::default());
+        let mut spec = SchemaSpec {
+            nodes: HashMap::new(),
+            marks: HashMap::new(),
+            top_node: Some("heading".to_string()),
+        };
+        spec.nodes.insert(
+            "heading".to_string(),
+            NodeSpec { attrs: Some(attrs), ..Default::default() },
+        );
+        let schema = Schema::compile(spec).unwrap();
+        let heading = schema.nodes.get("heading").unwrap();
+
+        assert_eq!(
+            heading.create_with_defaults(None).unwrap_err(),
+            NodeCreationError::MissingRequired {
+                node_type: "heading".to_string(),
+                attr: "title".to_string(),
+            }
+        );
+
+        let given = HashMap::from([("title".to_string(), Value::from("t"))]);
+        let node = heading.create_with_defaults(Some(given)).unwrap();
+        assert_eq!(node.r#type, "heading");
+        assert_eq!(node.attrs["level"], Value::from(1));
+        assert_eq!(node.attrs["title"], Value::from("t"));
+        assert!(node.content.is_empty());
+
+        let mut defaulted = heading.clone();
+        defaulted.attrs.remove("title");
+        let node = defaulted.create_with_defaults(None).unwrap();
+        assert_eq!(node.attrs["level"], Value::from(1));
+        assert!(node.attrs.get_safe("title").is_none());
+    }
+
+    fn content_schema(nodes: &[(&str, &str)]) -> SchemaSpec {
+        let mut spec = SchemaSpec {
+            nodes: HashMap::new(),
+            marks: HashMap::new(),
+            top_node: Some(nodes[0].0.to_string()),
+        };
+        for (name, content) in nodes {
+            spec.nodes.insert(
+                name.to_string(),
+                NodeSpec {
+                    content: Some(content.to_string()),
+                    ..Default::default()
+                },
+            );
+        }
+        spec
+    }
+
+    #[test]
+    fn create_default_doc_fills_required_content() {
+        use crate::schema::AttributeSpec;
+
+        let mut spec = content_schema(&[
+            ("GCXM", "DXGC+ note*"),
+            ("DXGC", "DWGC"),
+            ("DWGC", ""),
+            ("note", ""),
+        ]);
+        spec.nodes.get_mut("DXGC").unwrap().attrs = Some(HashMap::from([(
+            "name".to_string(),
+            AttributeSpec::new(Some(Value::from("单项工程"))),
+        )]));
+        let schema = Schema::compile(spec).unwrap();
+        let doc = schema.create_default_doc().unwrap();
+
+        let root = doc.root().unwrap();
+        assert_eq!(root.r#type, "GCXM");
+        assert_eq!(root.content.len(), 1);
+        let dxgc = doc.get_node(&root.content[0]).unwrap();
+        assert_eq!(dxgc.r#type, "DXGC");
+        assert_eq!(dxgc.attrs["name"], Value::from("单项工程"));
+        let dwgc = doc.get_node(&dxgc.content[0]).unwrap();
+        assert_eq!(dwgc.r#type, "DWGC");
+        assert!(dwgc.content.is_empty());
+        assert_eq!(doc.size(), 3);
+    }
+
+    #[test]
+    fn create_default_doc_reports_impossible_trees() {
+        let schema = Schema::compile(content_schema(&[
+            ("doc", "section+"),
+            ("section", "item"),
+            ("item", "section"),
+        ]))
+        .unwrap();
+        let err = schema.create_default_doc().unwrap_err().to_string();
+        assert!(err.contains("section -> item -> section"), "{err}");
+
+        let mut spec = content_schema(&[("doc", "item"), ("item", "")]);
+        spec.nodes.get_mut("item").unwrap().attrs = Some(HashMap::from([(
+            "code".to_string(),
+            crate::schema::AttributeSpec::default(),
+        )]));
+        let schema = Schema::compile(spec).unwrap();
+        let err = schema.create_default_doc().unwrap_err().to_string();
+        assert!(err.contains("item 的属性 code 没有默认值"), "{err}");
+    }
+
+    #[test]
+    fn dynamic_default_child_matches_content() {
+        use crate::dynamic_default::DynamicDefault;
+        use crate::schema::AttributeSpec;
+
+        let mut spec = content_schema(&[("doc", "report+"), ("report", "")]);
+        spec.nodes.get_mut("report").unwrap().attrs = Some(HashMap::from([(
+            "author".to_string(),
+            AttributeSpec::default(),
+        )]));
+        let mut schema = Schema::compile(spec).unwrap();
+        schema
+            .set_dynamic_default(
+                "report",
+                "author",
+                DynamicDefault::new(|_| Value::from("alice")),
+            )
+            .unwrap();
+
+        let doc = schema.create_default_doc().unwrap();
+        let root = doc.root().unwrap();
+        let reports: Vec<Node> = root
+            .content
+            .iter()
+            .map(|id| doc.get_node(id).unwrap().clone())
+            .collect();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].attrs["author"], Value::from("alice"));
+        // 注册动态默认值后，doc 的内容规则仍能匹配 report
+        let content = schema.nodes["doc"].content_match.as_ref().unwrap();
+        let matched = content.match_fragment(&reports, &schema);
+        assert!(matched.is_some_and(|m| m.valid_end));
+        assert!(schema.nodes["doc"].check_content(&reports, &schema));
+    }
+
+    #[test]
+    fn create_default_doc_backtracks_over_alternatives() {
+        // a 形成循环，b 可以创建，应回退到 b
+        let schema = Schema::compile(content_schema(&[
+            ("doc", "(a | b)+"),
+            ("a", "a"),
+            ("b", ""),
+        ]))
+        .unwrap();
+        let doc = schema.create_default_doc().unwrap();
+        let root = doc.root().unwrap();
+        assert_eq!(root.content.len(), 1);
+        assert_eq!(doc.get_node(&root.content[0]).unwrap().r#type, "b");
+
+        // 先选 a 后续无法补齐时同样回退
+        let schema = Schema::compile(content_schema(&[
+            ("doc", "a c | b"),
+            ("a", ""),
+            ("b", ""),
+            ("c", "c"),
+        ]))
+        .unwrap();
+        let doc = schema.create_default_doc().unwrap();
+        let root = doc.root().unwrap();
+        assert_eq!(doc.get_node(&root.content[0]).unwrap().r#type, "b");
+    }
+
+    #[test]
+    fn create_validated_checks_attrs() {
+        use crate::node_definition::SchemaError;
+        use crate::schema::{AttributeConstraint, AttributeSpec};
+
+        let mut attrs = HashMap::new();
+        attrs.insert(
+            "level".to_string(),
+            AttributeSpec {
+                default: Some(Value::from(1)),
+                constraint: Some(AttributeConstraint {
+                    base: Some("integer".to_string()),
+                    max_inclusive: Some("6".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+        attrs.insert("title".to_string(), AttributeSpec::default());
+        let mut spec = SchemaSpec {
+            nodes: HashMap::new(),
+            marks: HashMap::new(),
+            top_node: Some("heading".to_string()),
+        };
+        spec.nodes.insert(
+            "heading".to_string(),
+            NodeSpec { attrs: Some(attrs), ..Default::default() },
+        );
+        let schema = Schema::compile(spec).unwrap();
+        let heading = schema.nodes.get("heading").unwrap();
+        let create = |attrs: &[(&str, Value)]| {
+            let attrs: HashMap<String, Value> =
+                attrs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+            heading.create_validated(None, Some(&attrs), vec![], None)
+        };
+
+        let node = create(&[("title", Value::from("t"))]).unwrap();
+        assert_eq!(node.attrs["level"], Value::from(1));
+        assert_eq!(node.attrs["title"], Value::from("t"));
+        assert_eq!(
+            create(&[("level", Value::from(2))]).unwrap_err(),
+            SchemaError::MissingRequired {
+                node_type: "heading".to_string(),
+                attr: "title".to_string(),
+            }
+        );
+        assert_eq!(
+            create(&[("title", Value::from("t")), ("color", Value::from(1))])
+                .unwrap_err(),
+            SchemaError::UnknownAttr {
+                node_type: "heading".to_string(),
+                attr: "color".to_string(),
+            }
+        );
+        for level in [Value::from(7), Value::from("一级")] {
+            assert_eq!(
+                create(&[
+                    ("title", Value::from("t")),
+                    ("level", level.clone())
+                ])
+                .unwrap_err(),
+                SchemaError::InvalidValue {
+                    node_type: "heading".to_string(),
+                    attr: "level".to_string(),
+                    value: level,
+                }
+            );
+        }
+
+        let factory = schema.factory();
+        let err = factory
+            .create_node_validated("heading", None, None, vec![], None)
+            .unwrap_err();
+        assert!(err.to_string().contains("title"), "{err}");
+        assert!(
+            factory.create_node("heading", None, None, vec![], None).is_ok()
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use crate::error::PoolResult;
+use crate::mark_cascade::InheritedMarks;
 use crate::{node_definition::NodeTree, tree::Tree};
 
 use super::{error::error_helpers, node::Node, types::NodeId};
@@ -24,6 +25,9 @@ pub struct NodePool {
     inner: Arc<Tree>,
     // 节点池的唯一标识符
     key: String,
+    // 祖先标记缓存，只属于当前版本的节点池
+    #[serde(skip)]
+    pub(crate) inherited_marks: InheritedMarks,
 }
 
 impl NodePool {
@@ -33,7 +37,11 @@ impl NodePool {
     )))]
     pub fn new(inner: Arc<Tree>) -> Arc<NodePool> {
         let id = POOL_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
-        let pool = Self { inner, key: format!("pool_{id}") };
+        let pool = Self {
+            inner,
+            key: format!("pool_{id}"),
+            inherited_marks: InheritedMarks::default(),
+        };
         let pool: Arc<NodePool> = Arc::new(pool);
 
         pool
@@ -76,6 +84,7 @@ impl NodePool {
         let pool = Self {
             inner: Arc::new(Tree::from(nodes)),
             key: format!("pool_{id}"),
+            inherited_marks: InheritedMarks::default(),
         };
         let pool: Arc<NodePool> = Arc::new(pool);
         pool
@@ -224,6 +233,28 @@ impl NodePool {
         self.get_all_nodes().into_iter().find(|n| predicate(n))
     }
 
+    /// 按属性值查找节点 id，属性已建立索引时为 O(1) 查找
+    pub fn find_by_attr(
+        &self,
+        attr_name: &str,
+        attr_value: &serde_json::Value,
+    ) -> Vec<NodeId> {
+        self.inner.find_by_attr(attr_name, attr_value)
+    }
+
+    /// 已建立索引的属性名
+    pub fn indexed_attr_names(&self) -> Vec<String> {
+        self.inner.indexed_attr_names()
+    }
+
+    /// 为属性建立值索引，后续基于该节点池的修改会继续维护索引
+    pub fn add_attr_index(
+        &mut self,
+        attr_name: &str,
+    ) {
+        Arc::make_mut(&mut self.inner).add_attr_index(attr_name);
+    }
+
     /// 获取节点在树中的深度
     ///
     /// # 参数
@@ -947,6 +978,7 @@ impl DataContainer for NodePool {
         Self {
             inner: Arc::new(inner),
             key: format!("pool_{id}"),
+            inherited_marks: InheritedMarks::default(),
         }
     }
 }
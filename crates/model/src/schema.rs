@@ -2,11 +2,13 @@ use crate::error::error_helpers::schema_error;
 use crate::error::PoolResult;
 
 use super::attrs::Attrs;
-use super::content::ContentMatch;
+use super::content::{self, ContentMatch, ContentPartialMatch};
+use super::derived::DerivedAttrs;
+use super::dynamic_default::DynamicDefault;
 use super::mark_definition::{MarkDefinition, MarkSpec};
-use super::node_definition::{NodeDefinition, NodeSpec};
+use super::node_definition::{NodeDefinition, NodeSpec, SortSpec};
 use crate::node_factory::NodeFactory;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::any::Any;
 use std::collections::HashMap;
@@ -22,10 +24,11 @@ pub struct Attribute {
 impl Attribute {
     /// 从 AttributeSpec 创建新的 Attribute 实例
     pub(crate) fn new(options: AttributeSpec) -> Self {
-        Attribute {
-            has_default: options.default.is_some(),
-            default: options.default,
-        }
+        // 派生属性由重算写入，创建节点时先以 null 占位
+        let default = options
+            .default
+            .or_else(|| options.computed.as_ref().map(|_| Value::Null));
+        Attribute { has_default: default.is_some(), default }
     }
     /// 检查属性是否为必需的
     /// 如果没有默认值，则属性为必需
@@ -47,6 +50,8 @@ pub struct Schema {
     pub(crate) nodes: HashMap<String, NodeDefinition>,
     /// 标记类型映射表
     pub(crate) marks: HashMap<String, MarkDefinition>,
+    /// 声明了派生属性的节点类型（节点类型名 -> 派生属性）
+    pub(crate) derived: HashMap<String, Arc<DerivedAttrs>>,
 }
 impl PartialEq for Schema {
     fn eq(
@@ -86,11 +91,133 @@ impl Schema {
             cached: Arc::new(Mutex::new(HashMap::new())),
             nodes: HashMap::new(),
             marks: HashMap::new(),
+            derived: HashMap::new(),
         }
     }
     pub fn factory(&self) -> NodeFactory<'_> {
         NodeFactory::new(self)
     }
+
+    /// 已编译的全部节点类型（按名称排序），包含解析后的内容匹配与标记集合
+    pub fn node_types(&self) -> impl Iterator<Item = &NodeDefinition> {
+        let mut types: Vec<&NodeDefinition> = self.nodes.values().collect();
+        types.sort_by(|a, b| a.name.cmp(&b.name));
+        types.into_iter()
+    }
+
+    /// 已编译的全部标记类型（按名称排序）
+    pub fn mark_types(&self) -> impl Iterator<Item = &MarkDefinition> {
+        let mut types: Vec<&MarkDefinition> = self.marks.values().collect();
+        types.sort_by(|a, b| a.name.cmp(&b.name));
+        types.into_iter()
+    }
+
+    pub fn mark_type(
+        &self,
+        name: &str,
+    ) -> Option<&MarkDefinition> {
+        self.marks.get(name)
+    }
+
+    /// 标记类型是否声明了级联（对后代节点生效），未定义的标记类型视为不级联
+    pub fn mark_cascades(
+        &self,
+        mark_type: &str,
+    ) -> bool {
+        self.marks.get(mark_type).is_some_and(MarkDefinition::cascades)
+    }
+
+    /// 两个标记类型是否互斥，未定义的标记类型视为不互斥
+    pub fn marks_exclusive(
+        &self,
+        a: &str,
+        b: &str,
+    ) -> bool {
+        match (self.marks.get(a), self.marks.get(b)) {
+            (Some(a), Some(b)) => a.is_exclusive_with(b),
+            _ => false,
+        }
+    }
+
+    /// 校验节点的类型与标记：类型与标记必须已定义、标记须在节点允许的集合内，
+    /// 且不能同时存在互斥的标记
+    pub fn validate_node(
+        &self,
+        node: &Node,
+    ) -> PoolResult<()> {
+        let node_type = self.nodes.get(&node.r#type).ok_or_else(|| {
+            schema_error(&format!("未定义的节点类型: {}", node.r#type))
+        })?;
+        for (i, mark) in node.marks.iter().enumerate() {
+            if !self.marks.contains_key(&mark.r#type) {
+                return Err(schema_error(&format!(
+                    "未定义的标记类型: {}",
+                    mark.r#type
+                )));
+            }
+            if let Some(set) = &node_type.mark_set
+                && !set.iter().any(|m| m.name == mark.r#type)
+            {
+                return Err(schema_error(&format!(
+                    "节点 {} 不允许标记类型 {}",
+                    node.id, mark.r#type
+                )));
+            }
+            if let Some(other) =
+                node.marks.iter().skip(i + 1).find(|other| {
+                    self.marks_exclusive(&mark.r#type, &other.r#type)
+                })
+            {
+                return Err(schema_error(&format!(
+                    "节点 {} 的标记 {} 与 {} 互斥",
+                    node.id, mark.r#type, other.r#type
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 顶级节点类型，未经 [`Schema::compile`] 编译的 Schema 返回 None
+    pub fn top_node(&self) -> Option<&NodeDefinition> {
+        self.top_node_type.as_ref()
+    }
+    /// 为节点类型的属性注册动态默认值，创建节点且未提供该属性时由回调计算
+    ///
+    /// 属性必须已在节点类型中声明
+    pub fn set_dynamic_default(
+        &mut self,
+        node_type: &str,
+        attr: &str,
+        default: DynamicDefault,
+    ) -> PoolResult<()> {
+        let definition = self.nodes.get_mut(node_type).ok_or_else(|| {
+            schema_error(&format!("未找到节点类型 {node_type}"))
+        })?;
+        if !definition.attrs.contains_key(attr) {
+            return Err(schema_error(&format!(
+                "节点类型 {node_type} 未声明属性 {attr}"
+            )));
+        }
+        definition.dynamic_defaults.insert(attr.to_string(), default.clone());
+        if let Some(top) = self.top_node_type.as_mut()
+            && top.name == node_type
+        {
+            top.dynamic_defaults.insert(attr.to_string(), default);
+        }
+        Ok(())
+    }
+
+    /// 以顶级节点为根，按内容规则生成包含最少必需子节点的默认文档
+    ///
+    /// 属性取各自的默认值；必需属性没有默认值、内容规则无法满足或必需内容
+    /// 形成循环时返回错误
+    pub fn create_default_doc(&self) -> PoolResult<Arc<NodePool>> {
+        let top_node = self
+            .top_node()
+            .ok_or_else(|| schema_error("未找到顶级节点类型定义"))?;
+        let tree = self.factory().create_minimal_tree(&top_node.name, None)?;
+        Ok(NodePool::from(tree))
+    }
     /// 编译 Schema 定义
     /// 处理节点和标记的定义，建立它们之间的关系
     #[cfg_attr(feature = "dev-tracing", tracing::instrument(skip(instance_spec), fields(
@@ -102,7 +229,30 @@ impl Schema {
         let mut schema: Schema = Schema::new(instance_spec);
         let nodes: HashMap<String, NodeDefinition> =
             NodeDefinition::compile(schema.spec.nodes.clone());
-        let marks = MarkDefinition::compile(schema.spec.marks.clone());
+        let mut marks = MarkDefinition::compile(schema.spec.marks.clone());
+        let mut excluded = HashMap::new();
+        for (name, mark) in &marks {
+            let names = match mark.spec.excludes.as_deref() {
+                None => vec![name.clone()],
+                Some(expr) => {
+                    let mut names: Vec<String> =
+                        gather_marks(&marks, expr.split_whitespace().collect())
+                            .map_err(|e| schema_error(&e))?
+                            .into_iter()
+                            .map(|m| m.name.clone())
+                            .collect();
+                    names.sort();
+                    names.dedup();
+                    names
+                },
+            };
+            excluded.insert(name.clone(), names);
+        }
+        for (name, names) in excluded {
+            if let Some(mark) = marks.get_mut(&name) {
+                mark.excluded = names;
+            }
+        }
         let mut content_expr_cache = HashMap::new();
         let mut updated_nodes = HashMap::new();
         for (prop, type_) in &nodes {
@@ -112,6 +262,23 @@ impl Schema {
                 )));
             }
 
+            if let Some(sort_by) = &type_.spec.sort_by
+                && SortSpec::parse(sort_by).is_none()
+            {
+                return Err(schema_error(&format!(
+                    "节点 {prop} 的排序规则无效: {sort_by}"
+                )));
+            }
+
+            if let Some(attrs) = &type_.spec.attrs {
+                let derived = DerivedAttrs::compile(attrs).map_err(|e| {
+                    schema_error(&format!("节点 {prop} 的{e}"))
+                })?;
+                if !derived.is_empty() {
+                    schema.derived.insert(prop.clone(), Arc::new(derived));
+                }
+            }
+
             let content_expr = type_.spec.content.as_deref().unwrap_or("");
             let mark_expr = type_.spec.marks.as_deref();
 
@@ -119,7 +286,7 @@ impl Schema {
             let content_match = content_expr_cache
                 .entry(content_expr_string.clone())
                 .or_insert_with(|| {
-                    ContentMatch::parse(content_expr_string, &nodes)
+                    ContentMatch::parse_cached(content_expr_string, &nodes)
                 })
                 .clone();
 
@@ -154,10 +321,45 @@ impl Schema {
 
         Ok(schema)
     }
+
+    /// 节点类型声明的派生属性
+    pub fn derived_attrs(
+        &self,
+        node_type: &str,
+    ) -> Option<&DerivedAttrs> {
+        self.derived.get(node_type).map(|derived| derived.as_ref())
+    }
+
+    /// 是否有节点类型声明了派生属性
+    pub fn has_derived_attrs(&self) -> bool {
+        !self.derived.is_empty()
+    }
+
+    /// 清空进程级内容表达式缓存，主要用于测试隔离
+    pub fn clear_content_cache() {
+        content::clear_content_cache();
+    }
+
+    /// 内容补全：给定节点类型及其已有子节点的类型名前缀，
+    /// 返回追加后内容表达式仍然可被满足的所有子节点类型名
+    ///
+    /// 节点类型不存在或前缀本身不合法时返回空列表
+    pub fn content_completer(
+        &self,
+        node_type: &str,
+        current_children: &[&str],
+    ) -> Vec<String> {
+        let Some(node) = self.nodes.get(node_type) else {
+            return Vec::new();
+        };
+        let content_expr = node.spec.content.clone().unwrap_or_default();
+        ContentPartialMatch::parse(content_expr, &self.nodes)
+            .next_types(current_children)
+    }
 }
 /// Schema 规范定义
 /// 包含节点和标记的原始定义信息
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct SchemaSpec {
     pub nodes: HashMap<String, NodeSpec>,
     pub marks: HashMap<String, MarkSpec>,
@@ -184,10 +386,90 @@ pub fn default_attrs(
     Some(defaults)
 }
 /// 属性规范定义
-#[derive(Clone, PartialEq, Debug, Eq, Hash, Serialize)]
+#[derive(
+    Clone, PartialEq, Debug, Default, Eq, Hash, Serialize, Deserialize,
+)]
 pub struct AttributeSpec {
     /// 属性的默认值
     pub default: Option<Value>,
+    /// 属性值约束
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constraint: Option<AttributeConstraint>,
+    /// 派生属性表达式，见 [`crate::derived`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub computed: Option<String>,
+}
+
+impl AttributeSpec {
+    /// 只带默认值的属性规范，约束与派生表达式用 `..Default::default()` 补充
+    pub fn new(default: Option<Value>) -> Self {
+        Self { default, ..Default::default() }
+    }
+}
+
+/// 属性值约束
+/// 对应 XSD simpleType 的 restriction，数值边界以字符串保存
+#[derive(
+    Clone, PartialEq, Debug, Default, Eq, Hash, Serialize, Deserialize,
+)]
+pub struct AttributeConstraint {
+    /// 基础类型，如 `string`、`integer`、`decimal`、`boolean`
+    pub base: Option<String>,
+    /// 可选值列表，为空表示不限制
+    #[serde(default)]
+    pub enumeration: Vec<String>,
+    /// 正则表达式
+    pub pattern: Option<String>,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub min_inclusive: Option<String>,
+    pub max_inclusive: Option<String>,
+}
+
+impl AttributeConstraint {
+    /// 检查属性值是否满足约束（不校验 pattern）
+    pub fn allows(
+        &self,
+        value: &Value,
+    ) -> bool {
+        let text = match value {
+            Value::String(s) => s.clone(),
+            Value::Null => return true,
+            other => other.to_string(),
+        };
+        if !self.enumeration.is_empty() && !self.enumeration.contains(&text) {
+            return false;
+        }
+        let len = text.chars().count();
+        if self.min_length.is_some_and(|min| len < min)
+            || self.max_length.is_some_and(|max| len > max)
+        {
+            return false;
+        }
+        let is_numeric = matches!(
+            self.base.as_deref(),
+            Some("integer" | "int" | "long" | "short" | "decimal" | "double")
+                | Some("float" | "positiveInteger" | "nonNegativeInteger")
+        );
+        if is_numeric || self.min_inclusive.is_some() || self.max_inclusive.is_some()
+        {
+            let Ok(number) = text.parse::<f64>() else {
+                return false;
+            };
+            let bound = |b: &Option<String>| {
+                b.as_deref().and_then(|b| b.parse::<f64>().ok())
+            };
+            if bound(&self.min_inclusive).is_some_and(|min| number < min)
+                || bound(&self.max_inclusive).is_some_and(|max| number > max)
+            {
+                return false;
+            }
+        }
+        if self.base.as_deref() == Some("boolean") {
+            return matches!(text.as_str(), "true" | "false" | "1" | "0");
+        }
+        true
+    }
 }
 /// 收集标记类型
 /// 根据给定的标记名称列表，收集对应的标记类型
@@ -212,7 +494,6 @@ fn gather_marks<'a>(
                 }) {
                     found.push(mark_ref);
                     matched = true;
-                    break;
                 }
             }
             if !matched {
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::num::NonZeroUsize;
 use std::ops::Index;
 use std::hash::{Hash, Hasher};
@@ -11,6 +12,7 @@ use ahash::{AHasher, RandomState};
 use std::fmt::{self, Debug};
 use crate::error::PoolResult;
 use crate::node_definition::NodeTree;
+use crate::attr_index::AttrIndex;
 use crate::{
     error::error_helpers,
     mark::Mark,
@@ -50,6 +52,8 @@ pub struct Tree {
     pub parent_map: TreeParentMap,
     #[serde(skip)]
     num_shards: usize, // 缓存分片数量，避免重复计算
+    #[serde(skip)]
+    attr_index: AttrIndex, // 属性值二级索引，随节点写入同步维护
 }
 impl Debug for Tree {
     fn fmt(
@@ -251,7 +255,13 @@ impl Tree {
             num_shards,
         );
 
-        Self { root_id, nodes: shards, parent_map, num_shards }
+        Self {
+            root_id,
+            nodes: shards,
+            parent_map,
+            num_shards,
+            attr_index: AttrIndex::default(),
+        }
     }
 
     pub fn new(root: Node) -> Self {
@@ -275,7 +285,68 @@ impl Tree {
             nodes,
             parent_map: HashTrieMapSync::new_sync(),
             num_shards,
+            attr_index: AttrIndex::default(),
+        }
+    }
+
+    /// 写入节点到对应分片，并同步属性索引
+    fn put_node(
+        &mut self,
+        node: Node,
+    ) {
+        let shard_index = self.get_shard_index(&node.id);
+        if !self.attr_index.is_empty() {
+            let old = self.nodes[shard_index].get(&node.id);
+            self.attr_index.replace(old, Some(&node));
+        }
+        self.nodes[shard_index] =
+            self.nodes[shard_index].insert(node.id.clone(), node);
+    }
+
+    /// 为属性建立值索引，已存在时忽略
+    ///
+    /// 索引不参与序列化，反序列化后需重新建立。
+    pub fn add_attr_index(
+        &mut self,
+        attr_name: &str,
+    ) {
+        let nodes = self.nodes.iter().flat_map(|shard| shard.values());
+        self.attr_index.add_attr(attr_name, nodes);
+    }
+
+    /// 已建立索引的属性名
+    pub fn indexed_attr_names(&self) -> Vec<String> {
+        self.attr_index.names()
+    }
+
+    /// 按属性值查找节点；属性未建立索引时回退为线性扫描
+    ///
+    /// 值按类型比较，数字 `7` 不会匹配字符串 `"7"`。
+    pub fn find_by_attr(
+        &self,
+        attr_name: &str,
+        attr_value: &Value,
+    ) -> Vec<NodeId> {
+        if self.attr_index.contains_attr(attr_name) {
+            return self.attr_index.find(attr_name, attr_value);
         }
+        let Some(expected) = AttrIndex::index_key(attr_value) else {
+            return Vec::new();
+        };
+        let mut ids: Vec<NodeId> = self
+            .nodes
+            .iter()
+            .flat_map(|shard| shard.values())
+            .filter(|node| {
+                node.attrs
+                    .get_safe(attr_name)
+                    .and_then(AttrIndex::index_key)
+                    .is_some_and(|key| key == expected)
+            })
+            .map(|node| node.id.clone())
+            .collect();
+        ids.sort();
+        ids
     }
 
     pub fn update_attr(
@@ -286,19 +357,47 @@ impl Tree {
         let shard_index = self.get_shard_index(id);
         let node = self.nodes[shard_index]
             .get(id)
-            .ok_or(error_helpers::node_not_found(id.clone()))?;
+            .ok_or_else(|| error_helpers::node_not_found(id.clone()))?;
         let new_node = node.update_attr(new_values);
-        self.nodes[shard_index] =
-            self.nodes[shard_index].insert(id.clone(), new_node);
+        self.put_node(new_node);
+        Ok(())
+    }
+    /// 批量更新多个节点的属性
+    ///
+    /// 先确认所有节点存在，任一缺失则不做修改；每个分片只写回一次。
+    pub fn update_attrs(
+        &mut self,
+        updates: Vec<(NodeId, HashTrieMapSync<String, Value>)>,
+    ) -> PoolResult<()> {
+        let mut by_shard: HashMap<usize, Vec<_>> = HashMap::new();
+        for (id, values) in updates {
+            let shard_index = self.get_shard_index(&id);
+            if !self.nodes[shard_index].contains_key(&id) {
+                return Err(error_helpers::node_not_found(id));
+            }
+            by_shard.entry(shard_index).or_default().push((id, values));
+        }
+        for (shard_index, updates) in by_shard {
+            let mut shard = self.nodes[shard_index].clone();
+            for (id, values) in updates {
+                let Some(node) = shard.get(&id) else {
+                    continue;
+                };
+                let new_node = node.update_attr(values);
+                if !self.attr_index.is_empty() {
+                    self.attr_index.replace(Some(node), Some(&new_node));
+                }
+                shard.insert_mut(id, new_node);
+            }
+            self.nodes[shard_index] = shard;
+        }
         Ok(())
     }
     pub fn update_node(
         &mut self,
         node: Node,
     ) -> PoolResult<()> {
-        let shard_index = self.get_shard_index(&node.id);
-        self.nodes[shard_index] =
-            self.nodes[shard_index].insert(node.id.clone(), node);
+        self.put_node(node);
         Ok(())
     }
 
@@ -335,8 +434,7 @@ impl Tree {
         }
 
         // 更新当前节点
-        self.nodes[parent_shard_index] = self.nodes[parent_shard_index]
-            .insert(parent_id.clone(), new_parent);
+        self.put_node(new_parent);
 
         // 使用队列进行广度优先遍历，处理所有子节点
         let mut node_queue = Vec::new();
@@ -359,9 +457,7 @@ impl Tree {
                 }
 
                 // 将当前节点存储到对应的分片中
-                let shard_index = self.get_shard_index(&current_node_id);
-                self.nodes[shard_index] = self.nodes[shard_index]
-                    .insert(current_node_id.clone(), child_node);
+                self.put_node(child_node);
 
                 // 更新父子关系映射
                 self.parent_map = self
@@ -388,15 +484,12 @@ impl Tree {
             .ok_or(error_helpers::parent_not_found(parent_id.clone()))?;
         let new_parent = parent.insert_content_at_index(index, &node.id);
         //更新父节点
-        self.nodes[parent_shard_index] = self.nodes[parent_shard_index]
-            .insert(parent_id.clone(), new_parent);
+        self.put_node(new_parent);
         //更新父子关系映射
         self.parent_map =
             self.parent_map.insert(node.id.clone(), parent_id.clone());
         //更新子节点
-        let shard_index = self.get_shard_index(&node.id);
-        self.nodes[shard_index] =
-            self.nodes[shard_index].insert(node.id.clone(), node.clone());
+        self.put_node(node.clone());
         Ok(())
     }
     pub fn add_node(
@@ -413,8 +506,7 @@ impl Tree {
         let new_parent = parent.insert_contents(&node_ids);
 
         // 更新父节点到分片中
-        self.nodes[parent_shard_index] = self.nodes[parent_shard_index]
-            .insert(parent_id.clone(), new_parent);
+        self.put_node(new_parent);
 
         // 更新所有子节点
         for node in nodes {
@@ -429,9 +521,7 @@ impl Tree {
             }
 
             // 将节点添加到对应的分片中
-            let shard_index = self.get_shard_index(&node.id);
-            self.nodes[shard_index] =
-                self.nodes[shard_index].insert(node.id.clone(), node.clone());
+            self.put_node(node.clone());
         }
         Ok(())
     }
@@ -515,8 +605,7 @@ impl Tree {
             .get(id)
             .ok_or(error_helpers::node_not_found(id.clone()))?;
         let new_node = node.remove_mark_by_name(mark_name);
-        self.nodes[shard_index] =
-            self.nodes[shard_index].insert(id.clone(), new_node);
+        self.put_node(new_node);
         Ok(())
     }
     pub fn get_marks(
@@ -536,8 +625,7 @@ impl Tree {
             .get(id)
             .ok_or(error_helpers::node_not_found(id.clone()))?;
         let new_node = node.remove_mark(mark_types);
-        self.nodes[shard_index] =
-            self.nodes[shard_index].insert(id.clone(), new_node);
+        self.put_node(new_node);
         Ok(())
     }
 
@@ -551,8 +639,7 @@ impl Tree {
             .get(id)
             .ok_or(error_helpers::node_not_found(id.clone()))?;
         let new_node = node.add_marks(marks);
-        self.nodes[shard_index] =
-            self.nodes[shard_index].insert(id.clone(), new_node);
+        self.put_node(new_node);
         Ok(())
     }
 
@@ -588,7 +675,12 @@ impl Tree {
             .filter(|&id| id != node_id)
             .cloned()
             .collect();
-        let mut new_target_parent = target_parent.clone();
+        // 同一父节点内移动时，需要基于已移除该节点的内容插入，避免重复
+        let mut new_target_parent = if source_parent_id == target_parent_id {
+            new_source_parent.clone()
+        } else {
+            target_parent.clone()
+        };
         if let Some(pos) = position {
             // 确保position不超过当前content的长度
             let insert_pos = pos.min(new_target_parent.content.len());
@@ -601,15 +693,39 @@ impl Tree {
             new_target_parent.content =
                 new_target_parent.content.push_back(node_id.clone());
         }
-        self.nodes[source_shard_index] = self.nodes[source_shard_index]
-            .insert(source_parent_id.clone(), new_source_parent);
-        self.nodes[target_shard_index] = self.nodes[target_shard_index]
-            .insert(target_parent_id.clone(), new_target_parent);
+        self.put_node(new_source_parent);
+        self.put_node(new_target_parent);
         self.parent_map =
             self.parent_map.insert(node_id.clone(), target_parent_id.clone());
         Ok(())
     }
 
+    /// 按 `new_order` 重排父节点的直接子节点，节点 ID 保持不变
+    ///
+    /// `new_order` 必须是现有子节点的一个排列，否则返回错误且树保持不变
+    pub fn reorder_children(
+        &mut self,
+        parent_id: &NodeId,
+        new_order: &[NodeId],
+    ) -> PoolResult<()> {
+        let shard_index = self.get_shard_index(parent_id);
+        let parent =
+            self.nodes[shard_index].get(parent_id).ok_or_else(|| {
+                error_helpers::parent_not_found(parent_id.clone())
+            })?;
+        let mut current: Vec<&NodeId> = parent.content.iter().collect();
+        let mut expected: Vec<&NodeId> = new_order.iter().collect();
+        current.sort();
+        expected.sort();
+        if current != expected {
+            return Err(error_helpers::invalid_permutation(parent_id.clone()));
+        }
+        let mut new_parent = parent.clone();
+        new_parent.content = new_order.iter().cloned().collect();
+        self.put_node(new_parent);
+        Ok(())
+    }
+
     pub fn remove_node(
         &mut self,
         parent_id: &NodeId,
@@ -640,8 +756,7 @@ impl Tree {
             .collect();
         let mut parent_node = parent.clone();
         parent_node.content = filtered_children;
-        self.nodes[parent_shard_index] = self.nodes[parent_shard_index]
-            .insert(parent_id.clone(), parent_node);
+        self.put_node(parent_node);
         let mut remove_nodes = Vec::new();
         for node_id in nodes {
             self.remove_subtree(&node_id, &mut remove_nodes)?;
@@ -676,8 +791,7 @@ impl Tree {
                     .filter(|&id| id != node_id)
                     .cloned()
                     .collect();
-                self.nodes[parent_shard_index] = self.nodes[parent_shard_index]
-                    .insert(parent_id.clone(), new_parent);
+                self.put_node(new_parent);
             }
         }
 
@@ -707,8 +821,7 @@ impl Tree {
             }
         };
         new_parent = new_parent.remove_content(&remove_node_id);
-        self.nodes[shard_index] =
-            self.nodes[shard_index].insert(parent_id.clone(), new_parent);
+        self.put_node(new_parent);
         let mut remove_nodes = Vec::new();
         self.remove_subtree(&remove_node_id, &mut remove_nodes)?;
 
@@ -736,6 +849,7 @@ impl Tree {
         self.parent_map = self.parent_map.remove(node_id);
 
         if let Some(remove_node) = self.nodes[shard_index].get(node_id) {
+            self.attr_index.replace(Some(remove_node), None);
             remove_nodes.push(remove_node.clone());
             self.nodes[shard_index] = self.nodes[shard_index].remove(node_id);
         }
@@ -1000,6 +1114,40 @@ mod tests {
         assert_eq!(container_children[1], child1.id);
     }
 
+    #[test]
+    fn test_move_node_within_same_parent() {
+        let root = create_test_node("root");
+        let mut tree = Tree::new(root.clone());
+
+        let child1 = create_test_node("child1");
+        let child2 = create_test_node("child2");
+        let child3 = create_test_node("child3");
+        tree.add_node(
+            &root.id,
+            &vec![child1.clone(), child2.clone(), child3.clone()],
+        )
+        .unwrap();
+
+        // 移到末尾，节点只出现一次
+        tree.move_node(&root.id, &root.id, &child1.id, None).unwrap();
+        let children: Vec<NodeId> =
+            tree.children(&root.id).unwrap().iter().cloned().collect();
+        assert_eq!(
+            children,
+            vec![child2.id.clone(), child3.id.clone(), child1.id.clone()]
+        );
+
+        // 移到开头
+        tree.move_node(&root.id, &root.id, &child3.id, Some(0)).unwrap();
+        let children: Vec<NodeId> =
+            tree.children(&root.id).unwrap().iter().cloned().collect();
+        assert_eq!(
+            children,
+            vec![child3.id.clone(), child2.id.clone(), child1.id.clone()]
+        );
+        assert_eq!(tree.get_parent_node(&child3.id).unwrap().id, root.id);
+    }
+
     #[test]
     fn test_cannot_remove_root_node() {
         let root = create_test_node("root");
@@ -1021,4 +1169,94 @@ mod tests {
         let parent = tree.get_parent_node(&child.id).unwrap();
         assert_eq!(parent.id, root.id);
     }
+
+    #[test]
+    fn test_attr_index() {
+        let root = create_test_node("root");
+        let mut tree = Tree::new(root.clone());
+        let mut a = create_test_node("a");
+        a.attrs = Attrs::from(
+            HashTrieMapSync::new_sync().insert("code".to_string(), json!("X1")),
+        );
+        tree.add_node(&root.id, &vec![a.clone()]).unwrap();
+        tree.add_attr_index("code");
+        assert_eq!(tree.indexed_attr_names(), vec!["code".to_string()]);
+        assert_eq!(tree.find_by_attr("code", &json!("X1")), vec![a.id.clone()]);
+
+        // 新增节点与修改属性都会同步索引
+        let mut b = create_test_node("b");
+        b.attrs = Attrs::from(
+            HashTrieMapSync::new_sync().insert("code".to_string(), json!(7)),
+        );
+        tree.add_node(&root.id, &vec![b.clone()]).unwrap();
+        assert_eq!(tree.find_by_attr("code", &json!(7)), vec![b.id.clone()]);
+        assert_eq!(tree.find_by_attr("code", &json!(7.0)), vec![b.id.clone()]);
+        // 值按类型区分，数字 7 与字符串 "7" 不会互相匹配
+        let mut c = create_test_node("c");
+        c.attrs = Attrs::from(
+            HashTrieMapSync::new_sync().insert("code".to_string(), json!("7")),
+        );
+        tree.add_node(&root.id, &vec![c.clone()]).unwrap();
+        assert_eq!(tree.find_by_attr("code", &json!("7")), vec![c.id.clone()]);
+        tree.update_attr(
+            &a.id,
+            HashTrieMapSync::new_sync().insert("code".to_string(), json!("X2")),
+        )
+        .unwrap();
+        assert!(tree.find_by_attr("code", &json!("X1")).is_empty());
+        assert_eq!(tree.find_by_attr("code", &json!("X2")), vec![a.id.clone()]);
+
+        // null 值不入索引，删除节点后从索引移除
+        tree.update_attr(
+            &b.id,
+            HashTrieMapSync::new_sync().insert("code".to_string(), json!(null)),
+        )
+        .unwrap();
+        assert!(tree.find_by_attr("code", &json!(7)).is_empty());
+        tree.remove_node_by_id(&a.id).unwrap();
+        assert!(tree.find_by_attr("code", &json!("X2")).is_empty());
+
+        // 未建立索引的属性回退为线性扫描，同样按类型比较
+        assert_eq!(
+            tree.find_by_attr("missing", &json!("X2")),
+            Vec::<NodeId>::new()
+        );
+        let mut unindexed = tree.clone();
+        unindexed.attr_index = AttrIndex::default();
+        assert_ne!(unindexed, tree);
+        assert_eq!(
+            unindexed.find_by_attr("code", &json!("7")),
+            vec![c.id.clone()]
+        );
+        assert!(unindexed.find_by_attr("code", &json!(7)).is_empty());
+    }
+
+    #[test]
+    fn test_reorder_children() {
+        let root = create_test_node("root");
+        let mut tree = Tree::new(root.clone());
+        let children: Vec<Node> =
+            ["a", "b", "c"].iter().map(|id| create_test_node(id)).collect();
+        tree.add_node(&root.id, &children).unwrap();
+
+        let new_order: Vec<NodeId> = vec!["c".into(), "a".into(), "b".into()];
+        tree.reorder_children(&root.id, &new_order).unwrap();
+        let reordered: Vec<NodeId> =
+            tree.children(&root.id).unwrap().iter().cloned().collect();
+        assert_eq!(reordered, new_order);
+        assert_eq!(tree.get_parent_node(&"a".into()).unwrap().id, root.id);
+
+        // 缺少、多出或重复的子节点都不是合法排列
+        for invalid in [
+            vec!["a".into(), "b".into()],
+            vec!["a".into(), "b".into(), "c".into(), "d".into()],
+            vec!["a".into(), "a".into(), "b".into()],
+        ] {
+            assert!(tree.reorder_children(&root.id, &invalid).is_err());
+        }
+        let unchanged: Vec<NodeId> =
+            tree.children(&root.id).unwrap().iter().cloned().collect();
+        assert_eq!(unchanged, new_order);
+        assert!(tree.reorder_children(&"missing".into(), &[]).is_err());
+    }
 }
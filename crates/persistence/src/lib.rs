@@ -4,3 +4,4 @@ pub mod ser;
 pub mod sqlite;
 pub mod step_factory;
 pub mod subscriber;
+pub mod wal;
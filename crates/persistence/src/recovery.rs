@@ -44,6 +44,13 @@ pub async fn recover_state<E: EventStore + 'static>(
             let payload = zstd::decode_all(std::io::Cursor::new(ev.payload))?;
             let frames: Vec<TypeWrapper> = serde_json::from_slice(&payload)?;
             let mut tr = mf_state::Transaction::new(&state);
+            // 旧事件的 actor 只记录在步骤帧中
+            let actor = ev
+                .actor
+                .or_else(|| frames.first().and_then(|f| f.actor.clone()));
+            if let Some(actor) = actor {
+                tr.set_actor(actor);
+            }
             for f in frames {
                 tr.step(step_factory.create(&f.type_id, &f.data))?;
             }
@@ -53,3 +60,222 @@ pub async fn recover_state<E: EventStore + 'static>(
     }
     Ok(state)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use async_trait::async_trait;
+    use mf_model::{
+        attrs::Attrs,
+        node::Node,
+        node_definition::NodeSpec,
+        node_pool::NodePool,
+        rpds::HashTrieMapSync,
+        schema::{AttributeSpec, Schema, SchemaSpec},
+        tree::Tree,
+    };
+    use mf_state::{
+        error::StateResult,
+        plugin::{
+            Plugin, PluginMetadata, PluginSpec, PluginTrait, PluginTraitGeneric,
+        },
+        State, Transaction,
+    };
+    use parking_lot::Mutex;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::api::{PersistedEvent, Snapshot};
+    use crate::ser::{checksum32, compress_if_needed, frame_steps};
+
+    /// 只保存事件的内存存储
+    #[derive(Default)]
+    struct MemoryStore(Mutex<Vec<PersistedEvent>>);
+
+    #[async_trait]
+    impl EventStore for MemoryStore {
+        async fn append(
+            &self,
+            mut ev: PersistedEvent,
+        ) -> anyhow::Result<i64> {
+            let mut events = self.0.lock();
+            ev.lsn = events.len() as i64 + 1;
+            events.push(ev);
+            Ok(events.len() as i64)
+        }
+
+        async fn append_batch(
+            &self,
+            evs: Vec<PersistedEvent>,
+        ) -> anyhow::Result<i64> {
+            let mut lsn = 0;
+            for ev in evs {
+                lsn = self.append(ev).await?;
+            }
+            Ok(lsn)
+        }
+
+        async fn load_since(
+            &self,
+            doc_id: &str,
+            from_lsn: i64,
+            limit: u32,
+        ) -> anyhow::Result<Vec<PersistedEvent>> {
+            Ok(self
+                .0
+                .lock()
+                .iter()
+                .filter(|ev| ev.doc_id == doc_id && ev.lsn > from_lsn)
+                .take(limit as usize)
+                .cloned()
+                .collect())
+        }
+
+        async fn latest_snapshot(
+            &self,
+            _: &str,
+        ) -> anyhow::Result<Option<Snapshot>> {
+            Ok(None)
+        }
+
+        async fn write_snapshot(
+            &self,
+            _: Snapshot,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn compact(
+            &self,
+            _: &str,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// 记录重放时插件看到的 actor
+    #[derive(Debug)]
+    struct ActorProbe(Arc<Mutex<Vec<Option<String>>>>);
+
+    #[async_trait]
+    impl PluginTraitGeneric<NodePool, Schema> for ActorProbe {
+        fn metadata(&self) -> PluginMetadata {
+            PluginMetadata {
+                name: "actor_probe".to_string(),
+                version: "1.0.0".to_string(),
+                description: String::new(),
+                author: String::new(),
+                dependencies: vec![],
+                conflicts: vec![],
+                state_fields: vec![],
+                tags: vec![],
+            }
+        }
+
+        async fn append_transaction(
+            &self,
+            trs: &[Arc<Transaction>],
+            _: &Arc<State>,
+            _: &Arc<State>,
+        ) -> StateResult<Option<Transaction>> {
+            let actor = trs.first().and_then(|tr| tr.actor());
+            self.0.lock().push(actor.map(str::to_string));
+            Ok(None)
+        }
+    }
+
+    impl PluginTrait for ActorProbe {}
+
+    async fn configuration(
+        seen: Arc<Mutex<Vec<Option<String>>>>
+    ) -> mf_state::Configuration {
+        let title = AttributeSpec::new(Some(Value::from("")));
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            "doc".to_string(),
+            NodeSpec {
+                attrs: Some(HashMap::from([("title".to_string(), title)])),
+                ..Default::default()
+            },
+        );
+        let spec = SchemaSpec {
+            nodes,
+            marks: HashMap::new(),
+            top_node: Some("doc".to_string()),
+        };
+        let schema = Arc::new(Schema::compile(spec).unwrap());
+        let root = Node::new(
+            "root",
+            "doc".to_string(),
+            Attrs::default(),
+            vec![],
+            vec![],
+        );
+        let doc = NodePool::new(Arc::new(Tree::new(root)));
+        let plugin = Arc::new(Plugin::new(PluginSpec {
+            state_field: None,
+            tr: Arc::new(ActorProbe(seen)),
+        }));
+        mf_state::Configuration::new(
+            schema,
+            Some(vec![plugin]),
+            Some(doc),
+            None,
+        )
+        .await
+        .unwrap()
+    }
+
+    fn event(
+        tr: &Transaction,
+        actor: Option<String>,
+    ) -> PersistedEvent {
+        let framed = serde_json::to_vec(&frame_steps(tr)).unwrap();
+        let payload = compress_if_needed(&framed, true).unwrap();
+        PersistedEvent {
+            lsn: 0,
+            tr_id: tr.id,
+            doc_id: "doc".to_string(),
+            ts: 0,
+            actor,
+            idempotency_key: format!("tr:{}", tr.id),
+            checksum: checksum32(&payload),
+            payload,
+            meta: Value::Null,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recover_restores_actor() {
+        let store = MemoryStore::default();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let config = configuration(seen.clone()).await;
+        let state = Arc::new(State::new(Arc::new(config.clone())).unwrap());
+
+        for (i, actor) in ["alice", "bob"].into_iter().enumerate() {
+            let mut tr = state.tr().with_actor(actor.to_string());
+            tr.set_node_attribute(
+                "root".into(),
+                HashTrieMapSync::new_sync()
+                    .insert("title".to_string(), Value::from(format!("v{i}"))),
+            )
+            .unwrap();
+            // 第二条事件只在步骤帧中记录 actor
+            let event_actor = (i == 0).then(|| actor.to_string());
+            store.append(event(&tr, event_actor)).await.unwrap();
+        }
+
+        let factory = StepFactoryRegistry::new();
+        let recovered =
+            recover_state(&store, "doc", &config, &factory, 10).await.unwrap();
+        assert_eq!(
+            recovered.doc().get_node(&"root".into()).unwrap().attrs["title"],
+            Value::from("v1")
+        );
+        assert_eq!(
+            *seen.lock(),
+            vec![Some("alice".to_string()), Some("bob".to_string())]
+        );
+    }
+}
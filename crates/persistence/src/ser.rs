@@ -40,12 +40,8 @@ pub fn serialize_steps_concat(transaction: &Transaction) -> Vec<u8> {
     payload
 }
 
-// 标准化的步骤帧格式，便于跨版本重放
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct TypeWrapper {
-    pub type_id: String,
-    pub data: Vec<u8>,
-}
+// 标准化的步骤帧格式，便于跨版本重放；与 mf-file 历史帧共用同一类型
+pub use mf_file::TypeWrapper;
 
 // 快照序列化载体（与 StateSerialize 的字段对应）
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -57,9 +53,12 @@ pub struct SnapshotData {
 pub fn frame_steps(transaction: &Transaction) -> Vec<TypeWrapper> {
     let mut frames: Vec<TypeWrapper> =
         Vec::with_capacity(transaction.steps.len());
+    let actor = transaction.actor().map(str::to_string);
     for step in transaction.steps.iter() {
         if let Some(data) = step.serialize() {
-            frames.push(TypeWrapper { type_id: step.name(), data });
+            frames.push(
+                TypeWrapper::new(step.name(), data).with_actor(actor.clone()),
+            );
         }
     }
     frames
@@ -74,9 +73,12 @@ pub fn frame_invert_steps(transaction: &Transaction) -> Vec<TypeWrapper> {
     let mut invert_steps: Vec<_> =
         transaction.invert_steps.iter().cloned().collect();
     invert_steps.reverse();
+    let actor = transaction.actor().map(str::to_string);
     for step in invert_steps {
         if let Some(data) = step.serialize() {
-            frames.push(TypeWrapper { type_id: step.name(), data });
+            frames.push(
+                TypeWrapper::new(step.name(), data).with_actor(actor.clone()),
+            );
         }
     }
     frames
@@ -3,9 +3,12 @@ use std::{collections::HashMap, sync::Arc};
 use mf_model::node_pool::NodePool;
 use mf_model::schema::Schema;
 use mf_transform::{
-    attr_step::AttrStep,
+    attr_step::{AttrStep, BulkAttrStep},
+    derived::DerivedAttrStep,
     mark_step::AddMarkStep,
-    node_step::{AddNodeStep, MoveNodeStep, RemoveNodeStep},
+    node_step::{
+        AddNodeStep, MoveNodeStep, RemoveNodeStep, ReorderChildrenStep,
+    },
     step::StepGeneric,
 };
 use std::fmt::Debug;
@@ -33,6 +36,11 @@ impl StepFactoryRegistry {
         let mut registry = StepFactoryRegistry { factories: HashMap::new() };
         // 属性更新
         registry.register("attr_step", Arc::new(AttrStepFactory));
+        // 批量属性更新
+        registry.register("bulk_attr_step", Arc::new(BulkAttrStepFactory));
+        // 派生属性更新
+        registry
+            .register("derived_attr_step", Arc::new(DerivedAttrStepFactory));
         // 添加标记
         registry.register("add_mark_step", Arc::new(AddMarkStepFactory));
         // 添加节点
@@ -41,6 +49,11 @@ impl StepFactoryRegistry {
         registry.register("remove_node_step", Arc::new(RemoveNodeStepFactory));
         // 移动节点
         registry.register("move_node_step", Arc::new(MoveNodeStepFactory));
+        // 重排子节点
+        registry.register(
+            "reorder_children_step",
+            Arc::new(ReorderChildrenStepFactory),
+        );
         registry
     }
 
@@ -76,6 +89,30 @@ impl StepFactory for AttrStepFactory {
     }
 }
 
+#[derive(Debug)]
+pub struct BulkAttrStepFactory;
+impl StepFactory for BulkAttrStepFactory {
+    fn create_from_bytes(
+        &self,
+        bytes: &[u8],
+    ) -> Arc<dyn StepGeneric<NodePool, Schema>> {
+        let step: BulkAttrStep = serde_json::from_slice(bytes).unwrap();
+        Arc::new(step)
+    }
+}
+
+#[derive(Debug)]
+pub struct DerivedAttrStepFactory;
+impl StepFactory for DerivedAttrStepFactory {
+    fn create_from_bytes(
+        &self,
+        bytes: &[u8],
+    ) -> Arc<dyn StepGeneric<NodePool, Schema>> {
+        let step: DerivedAttrStep = serde_json::from_slice(bytes).unwrap();
+        Arc::new(step)
+    }
+}
+
 #[derive(Debug)]
 pub struct AddMarkStepFactory;
 impl StepFactory for AddMarkStepFactory {
@@ -123,3 +160,15 @@ impl StepFactory for MoveNodeStepFactory {
         Arc::new(step)
     }
 }
+
+#[derive(Debug)]
+pub struct ReorderChildrenStepFactory;
+impl StepFactory for ReorderChildrenStepFactory {
+    fn create_from_bytes(
+        &self,
+        bytes: &[u8],
+    ) -> Arc<dyn StepGeneric<NodePool, Schema>> {
+        let step: ReorderChildrenStep = serde_json::from_slice(bytes).unwrap();
+        Arc::new(step)
+    }
+}
@@ -88,6 +88,7 @@ impl<E: EventStore + 'static> SnapshotSubscriber<E> {
         &self,
         tr_id: u64,
         doc_id: &str,
+        actor: Option<&str>,
         frames: Vec<TypeWrapper>,
         meta: serde_json::Value,
     ) -> ForgeResult<Option<(i64, usize)>> {
@@ -110,7 +111,7 @@ impl<E: EventStore + 'static> SnapshotSubscriber<E> {
             tr_id,
             doc_id: doc_id.to_string(),
             ts: chrono::Utc::now().timestamp_millis(),
-            actor: None,
+            actor: actor.map(str::to_string),
             idempotency_key: format!("tr:{tr_id}"),
             payload,
             meta,
@@ -257,7 +258,10 @@ impl<E: EventStore + 'static> SnapshotSubscriber<E> {
                 continue;
             }
 
-            match self.persist_one(persist_id, &doc_id, frames, meta).await {
+            match self
+                .persist_one(persist_id, &doc_id, tr.actor(), frames, meta)
+                .await
+            {
                 Ok(Some((lsn, bytes))) => {
                     self.persisted.insert(persist_id, ());
                     touched_docs.insert(doc_id.clone());
@@ -0,0 +1,922 @@
+//! 基于文件的事务预写日志（WAL）。
+//!
+//! 每条记录为 `[长度 u32][CRC32 u32][JSON 负载]`（小端），负载为事务的步骤帧。
+//! 日志按段存放在目录中，文件名为段内首条记录的 `lsn`，超过
+//! `segment_max_bytes` 后切换到新段。[`Wal::checkpoint`] 写入完整快照后删除
+//! 已被快照覆盖的旧段。
+//!
+//! 崩溃可能在最后一段末尾留下不完整的记录，[`Wal::open`] 会截断到最后一条
+//! 完整记录，[`Wal::recover`] 在快照上重放其后的记录。重放直接应用记录中的
+//! 步骤，不再经过插件的过滤与追加：日志中已经包含插件追加与派生属性的事务。
+//!
+//! [`WalRecorder`] 作为后置中间件在状态提交前写入日志，写入失败则事务分发失败；
+//! 撤销与重做不经过中间件，需同时注册为事件处理器才会记录。事件异步处理，
+//! 撤销后应等待记录写入再分发新的事务，否则重放顺序可能与实际不一致。
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mf_core::{
+    error::error_utils::middleware_error,
+    event::{Event, EventHandler},
+    middleware::MiddlewareGeneric,
+    ForgeResult,
+};
+use mf_model::{node_pool::NodePool, schema::Schema};
+use mf_state::transaction::{get_tr_id, Transaction};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::api::CommitMode;
+use crate::ser::{
+    checksum32, frame_invert_steps, frame_steps, SnapshotData, TypeWrapper,
+};
+use crate::step_factory::StepFactoryRegistry;
+
+const RECORD_HEADER_LEN: usize = 8;
+const SEGMENT_EXT: &str = "wal";
+const SNAPSHOT_FILE: &str = "snapshot";
+const SNAPSHOT_TMP_FILE: &str = "snapshot.tmp";
+
+/// 预写日志配置。
+#[derive(Clone, Debug)]
+pub struct WalOptions {
+    /// 日志段与快照所在目录
+    pub dir: PathBuf,
+    /// 单个日志段的大小上限（字节），超过后切换到新段
+    pub segment_max_bytes: u64,
+    /// `SyncDurable` 时每条记录写入后 fsync，其余模式只写入系统缓存
+    pub commit_mode: CommitMode,
+}
+
+/// 日志中的单条事务记录。
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WalRecord {
+    pub lsn: u64,
+    pub tr_id: u64,
+    pub actor: Option<String>,
+    pub frames: Vec<TypeWrapper>,
+}
+
+/// 截止到 `upto_lsn` 的完整快照。
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WalSnapshot {
+    pub upto_lsn: u64,
+    pub data: SnapshotData,
+}
+
+struct ActiveSegment {
+    file: File,
+    start_lsn: u64,
+    bytes: u64,
+}
+
+struct WalInner {
+    segment: ActiveSegment,
+    next_lsn: u64,
+    snapshot_lsn: Option<u64>,
+}
+
+/// 分段的事务预写日志。
+pub struct Wal {
+    options: WalOptions,
+    inner: Mutex<WalInner>,
+}
+
+impl Wal {
+    /// 打开（或创建）日志目录，并截断最后一段末尾不完整的记录。
+    pub fn open(options: WalOptions) -> anyhow::Result<Self> {
+        fs::create_dir_all(&options.dir)?;
+        let snapshot_lsn = read_snapshot(&options.dir)?.map(|s| s.upto_lsn);
+        let base_lsn = snapshot_lsn.unwrap_or(0);
+        let (start_lsn, next_lsn, bytes) =
+            match list_segments(&options.dir)?.last() {
+                Some((start, path)) => {
+                    let data = fs::read(path)?;
+                    let (records, valid) = decode_records(&data);
+                    if valid < data.len() {
+                        tracing::warn!(
+                            "日志段 {} 末尾有 {} 字节不完整的记录，已截断",
+                            path.display(),
+                            data.len() - valid
+                        );
+                        let file = OpenOptions::new().write(true).open(path)?;
+                        file.set_len(valid as u64)?;
+                        file.sync_all()?;
+                    }
+                    let next = records.last().map_or(*start, |r| r.lsn + 1);
+                    (*start, next.max(base_lsn + 1), valid as u64)
+                },
+                None => (base_lsn + 1, base_lsn + 1, 0),
+            };
+        let file = open_segment(&options.dir, start_lsn)?;
+        Ok(Self {
+            options,
+            inner: Mutex::new(WalInner {
+                segment: ActiveSegment { file, start_lsn, bytes },
+                next_lsn,
+                snapshot_lsn,
+            }),
+        })
+    }
+
+    /// 追加事务的步骤帧，返回分配的 `lsn`；没有可序列化的步骤时返回 `None`。
+    pub fn append(
+        &self,
+        tr: &Transaction,
+    ) -> anyhow::Result<Option<u64>> {
+        self.append_frames(tr.id, tr.actor(), frame_steps(tr))
+    }
+
+    /// 追加一组步骤帧，返回分配的 `lsn`。
+    pub fn append_frames(
+        &self,
+        tr_id: u64,
+        actor: Option<&str>,
+        frames: Vec<TypeWrapper>,
+    ) -> anyhow::Result<Option<u64>> {
+        if frames.is_empty() {
+            return Ok(None);
+        }
+        let mut inner = self.inner.lock();
+        let lsn = inner.next_lsn;
+        let record =
+            WalRecord { lsn, tr_id, actor: actor.map(str::to_string), frames };
+        let frame = encode_frame(&serde_json::to_vec(&record)?);
+        if inner.segment.bytes > 0
+            && inner.segment.bytes + frame.len() as u64
+                > self.options.segment_max_bytes
+        {
+            self.rotate(&mut inner)?;
+        }
+        inner.segment.file.write_all(&frame)?;
+        if matches!(self.options.commit_mode, CommitMode::SyncDurable) {
+            inner.segment.file.sync_data()?;
+        }
+        inner.segment.bytes += frame.len() as u64;
+        inner.next_lsn = lsn + 1;
+        Ok(Some(lsn))
+    }
+
+    /// 读取 `lsn` 大于 `after_lsn` 的全部记录。
+    pub fn records_since(
+        &self,
+        after_lsn: u64,
+    ) -> anyhow::Result<Vec<WalRecord>> {
+        let _inner = self.inner.lock();
+        let segments = list_segments(&self.options.dir)?;
+        let mut records = Vec::new();
+        for (i, (_, path)) in segments.iter().enumerate() {
+            // 下一段的起点不超过 after_lsn + 1 时，本段已全部被跳过
+            if segments
+                .get(i + 1)
+                .is_some_and(|(next, _)| *next <= after_lsn + 1)
+            {
+                continue;
+            }
+            let (segment_records, _) = decode_records(&fs::read(path)?);
+            records.extend(
+                segment_records.into_iter().filter(|r| r.lsn > after_lsn),
+            );
+        }
+        Ok(records)
+    }
+
+    /// 最新的快照。
+    pub fn latest_snapshot(&self) -> anyhow::Result<Option<WalSnapshot>> {
+        read_snapshot(&self.options.dir)
+    }
+
+    /// 最新快照的 `upto_lsn`，没有快照时返回 `None`。
+    pub fn snapshot_lsn(&self) -> Option<u64> {
+        self.inner.lock().snapshot_lsn
+    }
+
+    /// 最后一条已写入记录的 `lsn`，日志为空时返回快照的 `upto_lsn` 或 0。
+    pub fn last_lsn(&self) -> u64 {
+        self.inner.lock().next_lsn - 1
+    }
+
+    /// 写入完整快照并删除已被覆盖的日志段，返回快照的 `upto_lsn`。
+    ///
+    /// `state` 必须恰好包含 `lsn` 不超过 `upto_lsn` 的全部记录；
+    /// 已有的快照不早于 `upto_lsn` 时不再写入。
+    pub async fn checkpoint(
+        &self,
+        state: &mf_state::State,
+        upto_lsn: u64,
+    ) -> anyhow::Result<u64> {
+        if let Some(lsn) = self.snapshot_lsn().filter(|lsn| *lsn >= upto_lsn) {
+            return Ok(lsn);
+        }
+        let mut ser = state.serialize().await?;
+        let data = SnapshotData {
+            node_pool: std::mem::take(&mut ser.node_pool),
+            state_fields: std::mem::take(&mut ser.state_fields),
+        };
+        let mut inner = self.inner.lock();
+        if upto_lsn >= inner.next_lsn {
+            anyhow::bail!(
+                "快照位置 {upto_lsn} 超出最后一条记录 {}",
+                inner.next_lsn - 1
+            );
+        }
+        if let Some(lsn) = inner.snapshot_lsn.filter(|lsn| *lsn >= upto_lsn) {
+            return Ok(lsn);
+        }
+        let blob = zstd::encode_all(
+            &serde_json::to_vec(&WalSnapshot { upto_lsn, data })?[..],
+            1,
+        )?;
+        let tmp = self.options.dir.join(SNAPSHOT_TMP_FILE);
+        let mut file = File::create(&tmp)?;
+        file.write_all(&encode_frame(&blob))?;
+        file.sync_all()?;
+        fs::rename(&tmp, self.options.dir.join(SNAPSHOT_FILE))?;
+        inner.snapshot_lsn = Some(upto_lsn);
+
+        if inner.segment.bytes > 0 {
+            self.rotate(&mut inner)?;
+        }
+        self.compact(&inner, upto_lsn)?;
+        Ok(upto_lsn)
+    }
+
+    /// 从最新快照恢复状态，并重放其后的记录；没有快照时从配置创建初始状态。
+    pub async fn recover(
+        &self,
+        configuration: &mf_state::Configuration,
+        step_factory: &StepFactoryRegistry,
+    ) -> anyhow::Result<Arc<mf_state::State>> {
+        let (mut state, upto_lsn) = match self.latest_snapshot()? {
+            Some(snap) => {
+                let ser = mf_state::state::StateSerialize {
+                    node_pool: snap.data.node_pool,
+                    state_fields: snap.data.state_fields,
+                };
+                let state =
+                    mf_state::State::deserialize(&ser, configuration).await?;
+                (Arc::new(state), snap.upto_lsn)
+            },
+            None => (
+                Arc::new(mf_state::State::new(Arc::new(
+                    configuration.clone(),
+                ))?),
+                0,
+            ),
+        };
+        for record in self.records_since(upto_lsn)? {
+            let mut tr = Transaction::new(&state);
+            if let Some(actor) = record.actor {
+                tr.set_actor(actor);
+            }
+            for f in record.frames {
+                tr.step(step_factory.create(&f.type_id, &f.data))?;
+            }
+            // 插件追加的事务已单独记录，不能再次触发追加
+            state = state.apply_inner_generic(&tr).await?;
+        }
+        Ok(state)
+    }
+
+    fn rotate(
+        &self,
+        inner: &mut WalInner,
+    ) -> anyhow::Result<()> {
+        inner.segment.file.sync_all()?;
+        let start_lsn = inner.next_lsn;
+        let file = open_segment(&self.options.dir, start_lsn)?;
+        inner.segment = ActiveSegment { file, start_lsn, bytes: 0 };
+        Ok(())
+    }
+
+    /// 删除记录全部不超过 `upto_lsn` 的日志段，当前段保留
+    fn compact(
+        &self,
+        inner: &WalInner,
+        upto_lsn: u64,
+    ) -> anyhow::Result<()> {
+        let segments = list_segments(&self.options.dir)?;
+        for (i, (start, path)) in segments.iter().enumerate() {
+            if *start == inner.segment.start_lsn {
+                continue;
+            }
+            let covered = segments
+                .get(i + 1)
+                .is_some_and(|(next, _)| *next <= upto_lsn + 1);
+            if covered {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 将事务写入预写日志的中间件，每 `snapshot_every_n_events` 条记录写一次快照。
+///
+/// 首次写入时若没有快照，会先以写入后的状态建立快照。
+#[derive(Clone)]
+pub struct WalRecorder {
+    wal: Arc<Wal>,
+    snapshot_every_n_events: u32,
+    events_since: Arc<AtomicU32>,
+}
+
+impl WalRecorder {
+    pub fn new(
+        wal: Arc<Wal>,
+        snapshot_every_n_events: u32,
+    ) -> Self {
+        Self { wal, snapshot_every_n_events, events_since: Arc::default() }
+    }
+
+    async fn record(
+        &self,
+        state: &mf_state::State,
+        records: Vec<(u64, Option<&str>, Vec<TypeWrapper>)>,
+    ) -> ForgeResult<()> {
+        let mut appended = 0;
+        let mut last_lsn = None;
+        for (tr_id, actor, frames) in records {
+            let lsn =
+                self.wal.append_frames(tr_id, actor, frames).map_err(|e| {
+                    middleware_error(format!("写入预写日志失败: {e}"))
+                })?;
+            if lsn.is_some() {
+                appended += 1;
+                last_lsn = lsn;
+            }
+        }
+        let Some(last_lsn) = last_lsn else {
+            return Ok(());
+        };
+        let since =
+            self.events_since.fetch_add(appended, Ordering::SeqCst) + appended;
+        if self.wal.snapshot_lsn().is_none()
+            || (self.snapshot_every_n_events > 0
+                && since >= self.snapshot_every_n_events)
+        {
+            self.wal
+                .checkpoint(state, last_lsn)
+                .await
+                .map_err(|e| middleware_error(format!("写入快照失败: {e}")))?;
+            self.events_since.store(0, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MiddlewareGeneric<NodePool, Schema> for WalRecorder {
+    fn name(&self) -> String {
+        "wal".to_string()
+    }
+
+    async fn after_dispatch(
+        &self,
+        state: Option<Arc<mf_state::State>>,
+        transactions: &[Arc<Transaction>],
+    ) -> ForgeResult<Option<Transaction>> {
+        let Some(state) = state else {
+            return Ok(None);
+        };
+        let records = transactions
+            .iter()
+            .map(|tr| (tr.id, tr.actor(), frame_steps(tr)))
+            .collect();
+        self.record(&state, records).await?;
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl EventHandler<Event> for WalRecorder {
+    async fn handle(
+        &self,
+        event: &Event,
+    ) -> ForgeResult<()> {
+        match event {
+            Event::Undo { new_state, transactions, .. } => {
+                // 按与提交相反的顺序撤销
+                let records = transactions
+                    .iter()
+                    .rev()
+                    .map(|tr| (get_tr_id(), tr.actor(), frame_invert_steps(tr)))
+                    .collect();
+                self.record(new_state, records).await
+            },
+            Event::Redo { new_state, transactions, .. } => {
+                let records = transactions
+                    .iter()
+                    .map(|tr| (get_tr_id(), tr.actor(), frame_steps(tr)))
+                    .collect();
+                self.record(new_state, records).await
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Debug for WalRecorder {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str("WalRecorder")
+    }
+}
+
+fn encode_frame(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(&checksum32(payload).to_le_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+/// 解析下一帧，返回负载与帧长度；截断或校验失败时返回 `None`
+fn decode_frame(bytes: &[u8]) -> Option<(&[u8], usize)> {
+    let header = bytes.get(..RECORD_HEADER_LEN)?;
+    let len = u32::from_le_bytes(header[..4].try_into().ok()?) as usize;
+    let checksum = u32::from_le_bytes(header[4..].try_into().ok()?);
+    let payload = bytes.get(RECORD_HEADER_LEN..RECORD_HEADER_LEN + len)?;
+    (checksum32(payload) == checksum)
+        .then_some((payload, RECORD_HEADER_LEN + len))
+}
+
+/// 解析日志段，返回完整的记录与其占用的字节数
+fn decode_records(bytes: &[u8]) -> (Vec<WalRecord>, usize) {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while let Some((payload, len)) = decode_frame(&bytes[offset..]) {
+        let Ok(record) = serde_json::from_slice::<WalRecord>(payload) else {
+            break;
+        };
+        records.push(record);
+        offset += len;
+    }
+    (records, offset)
+}
+
+fn read_snapshot(dir: &Path) -> anyhow::Result<Option<WalSnapshot>> {
+    let path = dir.join(SNAPSHOT_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = fs::read(&path)?;
+    let (blob, _) = decode_frame(&bytes)
+        .ok_or_else(|| anyhow::anyhow!("快照文件 {} 已损坏", path.display()))?;
+    let json = zstd::decode_all(blob)?;
+    Ok(Some(serde_json::from_slice(&json)?))
+}
+
+fn open_segment(
+    dir: &Path,
+    start_lsn: u64,
+) -> anyhow::Result<File> {
+    let path = dir.join(format!("{start_lsn:020}.{SEGMENT_EXT}"));
+    Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+/// 按起始 `lsn` 排序的日志段
+fn list_segments(dir: &Path) -> anyhow::Result<Vec<(u64, PathBuf)>> {
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(SEGMENT_EXT) {
+            continue;
+        }
+        if let Some(start) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse::<u64>().ok())
+        {
+            segments.push((start, path));
+        }
+    }
+    segments.sort_by_key(|(start, _)| *start);
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use mf_model::{
+        attrs::Attrs,
+        node::Node,
+        node_definition::{NodeSpec, NodeTree},
+        rpds::HashTrieMapSync,
+        schema::{AttributeSpec, SchemaSpec},
+        tree::Tree,
+    };
+    use mf_core::{
+        config::ForgeConfig,
+        extension::Extension,
+        extension_manager::ExtensionManager,
+        runtime::runtime::ForgeRuntime,
+        types::{Extensions, RuntimeOptions},
+    };
+    use mf_state::{
+        error::StateResult,
+        plugin::{
+            Plugin, PluginMetadata, PluginSpec, PluginTrait, PluginTraitGeneric,
+        },
+        State,
+    };
+    use mf_transform::node_step::ReorderChildrenStep;
+    use serde_json::Value;
+
+    use super::*;
+
+    async fn configuration() -> mf_state::Configuration {
+        let title = AttributeSpec::new(Some(Value::from("")));
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            "doc".to_string(),
+            NodeSpec {
+                content: Some("item*".to_string()),
+                attrs: Some(HashMap::from([("title".to_string(), title)])),
+                ..Default::default()
+            },
+        );
+        nodes.insert("item".to_string(), NodeSpec::default());
+        let spec = SchemaSpec {
+            nodes,
+            marks: HashMap::new(),
+            top_node: Some("doc".to_string()),
+        };
+        let schema = Arc::new(Schema::compile(spec).unwrap());
+        let root = Node::new(
+            "root",
+            "doc".to_string(),
+            Attrs::default(),
+            vec![],
+            vec![],
+        );
+        let doc = NodePool::new(Arc::new(Tree::new(root)));
+        mf_state::Configuration::new(schema, None, Some(doc), None)
+            .await
+            .unwrap()
+    }
+
+    fn options(dir: &Path) -> WalOptions {
+        WalOptions {
+            dir: dir.to_path_buf(),
+            segment_max_bytes: 256,
+            commit_mode: CommitMode::SyncDurable,
+        }
+    }
+
+    fn title(state: &mf_state::State) -> Value {
+        state.doc().get_node(&"root".into()).unwrap().attrs["title"].clone()
+    }
+
+    /// 依次设置标题 v0..v{n}，每个事务写入日志
+    async fn write_titles(
+        wal: &Wal,
+        mut state: Arc<mf_state::State>,
+        n: usize,
+    ) -> Arc<mf_state::State> {
+        for i in 0..n {
+            let mut tr = state.tr();
+            tr.set_node_attribute(
+                "root".into(),
+                HashTrieMapSync::new_sync()
+                    .insert("title".to_string(), Value::from(format!("v{i}"))),
+            )
+            .unwrap();
+            wal.append(&tr).unwrap();
+            state = state.apply(tr).await.unwrap().state;
+        }
+        state
+    }
+
+    #[tokio::test]
+    async fn test_recover_truncated_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = configuration().await;
+        let wal = Wal::open(options(dir.path())).unwrap();
+        let state =
+            Arc::new(mf_state::State::new(Arc::new(config.clone())).unwrap());
+        wal.checkpoint(&state, wal.last_lsn()).await.unwrap();
+        write_titles(&wal, state, 6).await;
+        drop(wal);
+
+        let segments = list_segments(dir.path()).unwrap();
+        assert!(segments.len() > 1, "日志段应已切换");
+        // 模拟写入最后一条记录时崩溃
+        let (_, last) = segments.last().unwrap();
+        let len = fs::metadata(last).unwrap().len();
+        let file = OpenOptions::new().write(true).open(last).unwrap();
+        file.set_len(len - 5).unwrap();
+        drop(file);
+
+        let wal = Wal::open(options(dir.path())).unwrap();
+        let factory = StepFactoryRegistry::new();
+        let recovered = wal.recover(&config, &factory).await.unwrap();
+        assert_eq!(title(&recovered), Value::from("v4"));
+        assert_eq!(wal.records_since(0).unwrap().len(), 5);
+
+        // 截断后的日志可以继续追加
+        write_titles(&wal, recovered, 1).await;
+        let records = wal.records_since(0).unwrap();
+        assert_eq!(records.last().unwrap().lsn, 6);
+        let recovered = wal.recover(&config, &factory).await.unwrap();
+        assert_eq!(title(&recovered), Value::from("v0"));
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_compacts_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = configuration().await;
+        let wal = Wal::open(options(dir.path())).unwrap();
+        let state =
+            Arc::new(mf_state::State::new(Arc::new(config.clone())).unwrap());
+        let state = write_titles(&wal, state, 6).await;
+        assert!(list_segments(dir.path()).unwrap().len() > 1);
+
+        assert_eq!(wal.checkpoint(&state, wal.last_lsn()).await.unwrap(), 6);
+        assert_eq!(list_segments(dir.path()).unwrap().len(), 1);
+        assert!(wal.records_since(0).unwrap().is_empty());
+
+        let state = write_titles(&wal, state, 2).await;
+        drop(wal);
+        let wal = Wal::open(options(dir.path())).unwrap();
+        let records = wal.records_since(0).unwrap();
+        assert_eq!(records.iter().map(|r| r.lsn).collect::<Vec<_>>(), [7, 8]);
+        let recovered =
+            wal.recover(&config, &StepFactoryRegistry::new()).await.unwrap();
+        assert_eq!(title(&recovered), title(&state));
+    }
+
+    fn children(state: &mf_state::State) -> Vec<String> {
+        state
+            .doc()
+            .children(&"root".into())
+            .unwrap()
+            .iter()
+            .map(|id| id.to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_recover_reorder_children() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = configuration().await;
+        let wal = Wal::open(options(dir.path())).unwrap();
+        let mut state =
+            Arc::new(mf_state::State::new(Arc::new(config.clone())).unwrap());
+
+        let mut tr = state.tr();
+        let items = ["a", "b", "c"].map(|id| {
+            let node = Node::new(
+                id,
+                "item".to_string(),
+                Attrs::default(),
+                vec![],
+                vec![],
+            );
+            NodeTree(node, vec![])
+        });
+        tr.add_node("root".into(), items.to_vec()).unwrap();
+        wal.append(&tr).unwrap();
+        state = state.apply(tr).await.unwrap().state;
+
+        let mut tr = state.tr();
+        tr.step(Arc::new(ReorderChildrenStep::new(
+            "root".into(),
+            vec!["c".into(), "a".into(), "b".into()],
+        )))
+        .unwrap();
+        wal.append(&tr).unwrap();
+        state = state.apply(tr).await.unwrap().state;
+        assert_eq!(children(&state), ["c", "a", "b"]);
+        drop(wal);
+
+        let wal = Wal::open(options(dir.path())).unwrap();
+        let recovered =
+            wal.recover(&config, &StepFactoryRegistry::new()).await.unwrap();
+        assert_eq!(children(&recovered), ["c", "a", "b"]);
+    }
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<schema top_node="doc">
+  <nodes>
+    <node name="doc" content="item*">
+      <attrs>
+        <attr name="title" default=""/>
+      </attrs>
+    </node>
+    <node name="item"/>
+  </nodes>
+</schema>"#;
+
+    /// 标题改为 `v1` 且还没有日志节点时，追加一个固定 ID 的日志节点
+    #[derive(Debug)]
+    struct LogNodePlugin;
+
+    #[async_trait]
+    impl PluginTraitGeneric<NodePool, Schema> for LogNodePlugin {
+        fn metadata(&self) -> PluginMetadata {
+            PluginMetadata {
+                name: "log_node".to_string(),
+                version: "1.0.0".to_string(),
+                description: String::new(),
+                author: String::new(),
+                dependencies: vec![],
+                conflicts: vec![],
+                state_fields: vec![],
+                tags: vec![],
+            }
+        }
+
+        async fn append_transaction(
+            &self,
+            _: &[Arc<Transaction>],
+            _: &Arc<State>,
+            new_state: &Arc<State>,
+        ) -> StateResult<Option<Transaction>> {
+            let doc = new_state.doc();
+            let root = doc.root_id().clone();
+            if doc.contains_node(&"log".into())
+                || doc.get_node(&root).unwrap().attrs["title"] != "v1"
+            {
+                return Ok(None);
+            }
+            let mut tr = new_state.tr();
+            let log = Node::new(
+                "log",
+                "item".to_string(),
+                Attrs::default(),
+                vec![],
+                vec![],
+            );
+            tr.add_node(root, vec![NodeTree(log, vec![])])?;
+            Ok(Some(tr))
+        }
+    }
+
+    impl PluginTrait for LogNodePlugin {}
+
+    /// 位于 [`WalRecorder`] 之后，记录运行到这里时日志中最后一条记录的 `lsn`
+    #[derive(Clone)]
+    struct WrittenProbe {
+        wal: Arc<Wal>,
+        written: Arc<Mutex<Vec<u64>>>,
+    }
+
+    #[async_trait]
+    impl MiddlewareGeneric<NodePool, Schema> for WrittenProbe {
+        fn name(&self) -> String {
+            "written_probe".to_string()
+        }
+
+        async fn after_dispatch(
+            &self,
+            _: Option<Arc<State>>,
+            _: &[Arc<Transaction>],
+        ) -> ForgeResult<Option<Transaction>> {
+            self.written.lock().push(self.wal.last_lsn());
+            Ok(None)
+        }
+    }
+
+    impl std::fmt::Debug for WrittenProbe {
+        fn fmt(
+            &self,
+            f: &mut std::fmt::Formatter<'_>,
+        ) -> std::fmt::Result {
+            f.write_str("WrittenProbe")
+        }
+    }
+
+    async fn runtime(
+        wal: Arc<Wal>,
+        probe: WrittenProbe,
+    ) -> ForgeRuntime {
+        let recorder = WalRecorder::new(wal, 0);
+        let mut extension = Extension::new();
+        extension.add_plugin(Arc::new(Plugin::new(PluginSpec {
+            state_field: None,
+            tr: Arc::new(LogNodePlugin),
+        })));
+        let options = RuntimeOptions::from_extension_manager(
+            ExtensionManager::from_xml_string(XML).unwrap(),
+        )
+        .add_extension(Extensions::E(extension));
+        let mut stack = options.get_middleware_stack();
+        stack.add(recorder.clone());
+        stack.add(probe);
+        let options = options
+            .set_middleware_stack(stack)
+            .set_event_handlers(vec![Arc::new(recorder)]);
+        ForgeRuntime::create_with_config(options, ForgeConfig::default())
+            .await
+            .unwrap()
+    }
+
+    async fn set_title(
+        runtime: &mut ForgeRuntime,
+        value: &str,
+    ) -> ForgeResult<()> {
+        let mut tr = runtime.get_tr();
+        let root = tr.doc().root_id().clone();
+        tr.set_node_attribute(
+            root,
+            HashTrieMapSync::new_sync()
+                .insert("title".to_string(), Value::from(value)),
+        )?;
+        runtime.dispatch(tr).await
+    }
+
+    /// 撤销与重做的记录由事件处理器异步写入
+    async fn wait_for_lsn(
+        wal: &Wal,
+        lsn: u64,
+    ) {
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while wal.last_lsn() < lsn {
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("日志记录未写入");
+    }
+
+    /// 标题与根节点的子节点
+    fn doc_summary(state: &State) -> (Value, Vec<String>) {
+        let doc = state.doc();
+        let root = doc.root().unwrap();
+        let children = doc
+            .children(&root.id)
+            .unwrap()
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        (root.attrs["title"].clone(), children)
+    }
+
+    #[tokio::test]
+    async fn test_recorder_replays_runtime_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let wal = Arc::new(Wal::open(options(dir.path())).unwrap());
+        let probe = WrittenProbe { wal: wal.clone(), written: Arc::default() };
+        let mut runtime = runtime(wal.clone(), probe.clone()).await;
+        let config = runtime.get_state().config.as_ref().clone();
+        let factory = StepFactoryRegistry::new();
+
+        set_title(&mut runtime, "v0").await.unwrap();
+        assert_eq!(wal.snapshot_lsn(), Some(1));
+        // 插件追加的日志节点单独记录，重放时不能再次追加
+        set_title(&mut runtime, "v1").await.unwrap();
+        assert_eq!(wal.last_lsn(), 3);
+        // 状态提交前记录已写入
+        assert_eq!(*probe.written.lock(), [1, 3]);
+        let recovered = wal.recover(&config, &factory).await.unwrap();
+        assert_eq!(
+            doc_summary(&recovered),
+            (Value::from("v1"), vec!["log".to_string()])
+        );
+
+        runtime.undo();
+        wait_for_lsn(&wal, 5).await;
+        let recovered = wal.recover(&config, &factory).await.unwrap();
+        assert_eq!(doc_summary(&recovered), doc_summary(runtime.get_state()));
+        assert_eq!(doc_summary(&recovered), (Value::from("v0"), vec![]));
+
+        runtime.redo();
+        wait_for_lsn(&wal, 7).await;
+        let recovered = wal.recover(&config, &factory).await.unwrap();
+        assert_eq!(doc_summary(&recovered), doc_summary(runtime.get_state()));
+        assert_eq!(
+            doc_summary(&recovered),
+            (Value::from("v1"), vec!["log".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recorder_failure_fails_dispatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let wal_dir = dir.path().join("wal");
+        let wal = Arc::new(
+            Wal::open(WalOptions { segment_max_bytes: 1, ..options(&wal_dir) })
+                .unwrap(),
+        );
+        let probe = WrittenProbe { wal: wal.clone(), written: Arc::default() };
+        let mut runtime = runtime(wal.clone(), probe.clone()).await;
+        set_title(&mut runtime, "v0").await.unwrap();
+        set_title(&mut runtime, "v2").await.unwrap();
+
+        // 目录被删除后切换日志段失败
+        fs::remove_dir_all(&wal_dir).unwrap();
+        assert!(set_title(&mut runtime, "v3").await.is_err());
+        assert_eq!(doc_summary(runtime.get_state()).0, Value::from("v2"));
+        assert_eq!(wal.last_lsn(), 2);
+        assert_eq!(*probe.written.lock(), [1, 2]);
+    }
+}
@@ -23,6 +23,7 @@ fn bench_search_model(c: &mut Criterion) {
                 range_field: None,
                 range_min: None,
                 range_max: None,
+                expr: None,
             };
             criterion::black_box(query)
         })
@@ -1,4 +1,6 @@
+use crate::live_query::LiveQueries;
 use crate::model::IndexDoc;
+use crate::query::QueryExpr;
 use anyhow::Result;
 use rbatis::{executor::Executor, RBatis};
 use rbdc_sqlite::Driver;
@@ -103,6 +105,58 @@ pub struct SearchQuery {
     pub range_field: Option<String>,
     pub range_min: Option<i64>,
     pub range_max: Option<i64>,
+    /// 布尔表达式条件，与上述扁平条件按 AND 组合
+    pub expr: Option<QueryExpr>,
+}
+
+impl SearchQuery {
+    /// 将扁平条件（不含全文 `text`）与 `expr` 合并降级为单个表达式
+    ///
+    /// 没有任何条件时返回 `None`
+    pub fn to_expr(&self) -> Option<QueryExpr> {
+        let mut items = Vec::new();
+
+        if let Some(node_type) = &self.node_type {
+            items.push(QueryExpr::term("type", node_type.clone()));
+        }
+        if let Some(parent_id) = &self.parent_id {
+            items.push(QueryExpr::term("parent", parent_id.clone()));
+        }
+        if let Some(path_prefix) = &self.path_prefix {
+            items.push(QueryExpr::term("path", path_prefix.clone()));
+        }
+        for mark in &self.marks {
+            items.push(QueryExpr::term("mark", mark.clone()));
+        }
+        for (mark_type, attr_key, attr_value) in &self.mark_attrs {
+            items.push(QueryExpr::term(
+                format!("mark.{}.{}", mark_type, attr_key),
+                attr_value.clone(),
+            ));
+        }
+        for (key, value) in &self.attrs {
+            items
+                .push(QueryExpr::term(format!("attrs.{}", key), value.clone()));
+        }
+        if let Some(field) = &self.range_field
+            && (self.range_min.is_some() || self.range_max.is_some())
+        {
+            items.push(QueryExpr::range(
+                field.clone(),
+                self.range_min,
+                self.range_max,
+            ));
+        }
+        if let Some(expr) = &self.expr {
+            items.push(expr.clone());
+        }
+
+        match items.len() {
+            0 => None,
+            1 => items.pop(),
+            _ => Some(QueryExpr::And(items)),
+        }
+    }
 }
 
 /// SQLite 后端实现
@@ -110,6 +164,7 @@ pub struct SqliteBackend {
     pool: Arc<RBatis>,
     index_dir: PathBuf,
     _temp_dir: Option<tempfile::TempDir>,
+    live_queries: Arc<LiveQueries>,
 }
 
 impl SqliteBackend {
@@ -150,6 +205,7 @@ impl SqliteBackend {
                 .map(Path::to_path_buf)
                 .unwrap_or_else(|| PathBuf::from(".")),
             _temp_dir: temp_dir,
+            live_queries: Arc::new(LiveQueries::default()),
         })
     }
 
@@ -158,6 +214,11 @@ impl SqliteBackend {
         &self.index_dir
     }
 
+    /// 已注册的实时查询
+    pub fn live_queries(&self) -> &Arc<LiveQueries> {
+        &self.live_queries
+    }
+
     /// 应用增量变更
     pub async fn apply(
         &self,
@@ -167,6 +228,8 @@ impl SqliteBackend {
             return Ok(());
         }
 
+        let _gate = self.live_queries.lock().await;
+        let changed = self.live_queries.changed_docs(&mutations);
         let tx = self.pool.acquire_begin().await?;
         for mutation in mutations {
             match mutation {
@@ -192,6 +255,9 @@ impl SqliteBackend {
             }
         }
         tx.commit().await?;
+        if let Some(changed) = changed {
+            self.live_queries.notify(self, &changed).await?;
+        }
         Ok(())
     }
 
@@ -241,12 +307,14 @@ impl SqliteBackend {
         &self,
         docs: Vec<IndexDoc>,
     ) -> Result<()> {
+        let _gate = self.live_queries.lock().await;
         let tx = self.pool.acquire_begin().await?;
         tx.exec("DELETE FROM nodes", vec![]).await?;
         for doc in &docs {
             self.upsert_doc(&tx, doc).await?;
         }
         tx.commit().await?;
+        self.live_queries.refresh(self).await?;
         Ok(())
     }
 
@@ -312,6 +380,14 @@ impl SqliteBackend {
         Ok(ordered)
     }
 
+    /// 获取索引中的全部节点 ID
+    pub async fn all_ids(&self) -> Result<Vec<String>> {
+        let conn = self.pool.acquire().await?;
+        let rows: Vec<IdRow> =
+            conn.query_decode("SELECT id FROM nodes", vec![]).await?;
+        Ok(rows.into_iter().map(|r| r.id).collect())
+    }
+
     async fn search_tree(
         &self,
         query: &SearchQuery,
@@ -336,6 +412,11 @@ impl SqliteBackend {
             );
             params.push(to_value(node_type.clone()));
         }
+        if let Some(expr) = &query.expr {
+            sql.push_str(" AND id IN (SELECT nodes.id FROM nodes WHERE ");
+            push_expr_sql(expr, &mut sql, &mut params);
+            sql.push(')');
+        }
 
         if let Some(sort_by) = &query.sort_by {
             let direction = if query.sort_asc { "ASC" } else { "DESC" };
@@ -392,6 +473,11 @@ impl SqliteBackend {
             params.push(to_value(attr_value.clone()));
         }
 
+        if let Some(expr) = &query.expr {
+            sql.push_str(" AND ");
+            push_expr_sql(expr, &mut sql, &mut params);
+        }
+
         if let Some(sort_by) = &query.sort_by {
             let direction = if query.sort_asc { "ASC" } else { "DESC" };
             sql.push_str(&format!(" ORDER BY nodes.{} {}", sort_by, direction));
@@ -414,51 +500,9 @@ impl SqliteBackend {
         let mut sql = String::from("SELECT id FROM nodes WHERE 1=1");
         let mut params: Vec<Value> = Vec::new();
 
-        if let Some(node_type) = &query.node_type {
-            sql.push_str(" AND node_type = ?");
-            params.push(to_value(node_type.clone()));
-        }
-        if let Some(parent_id) = &query.parent_id {
-            sql.push_str(" AND parent_id = ?");
-            params.push(to_value(parent_id.clone()));
-        }
-        if let Some(path_prefix) = &query.path_prefix {
-            sql.push_str(" AND path LIKE ?");
-            params.push(to_value(format!("{}%", path_prefix)));
-        }
-
-        for mark in &query.marks {
-            sql.push_str(" AND marks LIKE ?");
-            params.push(to_value(format!("%\"{}\"%%", mark)));
-        }
-
-        for (mark_type, attr_key, attr_value) in &query.mark_attrs {
-            sql.push_str(&format!(
-                " AND EXISTS (
-                    SELECT 1 FROM json_each(marks_json)
-                    WHERE json_extract(value, '$.type') = ?
-                    AND json_extract(value, '$.attrs.{}') = ?
-                )",
-                attr_key
-            ));
-            params.push(to_value(mark_type.clone()));
-            params.push(to_value(attr_value.clone()));
-        }
-
-        for (key, value) in &query.attrs {
-            sql.push_str(&format!(" AND json_extract(attrs, '$.{}') = ?", key));
-            params.push(to_value(value.clone()));
-        }
-
-        if let Some(field) = &query.range_field {
-            if let Some(min) = query.range_min {
-                sql.push_str(&format!(" AND {} >= ?", field));
-                params.push(to_value(min));
-            }
-            if let Some(max) = query.range_max {
-                sql.push_str(&format!(" AND {} <= ?", field));
-                params.push(to_value(max));
-            }
+        if let Some(expr) = query.to_expr() {
+            sql.push_str(" AND ");
+            push_expr_sql(&expr, &mut sql, &mut params);
         }
 
         if let Some(sort_by) = &query.sort_by {
@@ -475,6 +519,143 @@ impl SqliteBackend {
     }
 }
 
+/// 将表达式翻译为参数化 SQL 条件（列统一以 `nodes.` 限定）
+///
+/// 字段名与值均通过参数绑定，不会拼接进 SQL 文本
+fn push_expr_sql(
+    expr: &QueryExpr,
+    sql: &mut String,
+    params: &mut Vec<Value>,
+) {
+    match expr {
+        QueryExpr::And(items) | QueryExpr::Or(items) if items.is_empty() => {
+            // 空 AND 恒真，空 OR 恒假
+            let is_and = matches!(expr, QueryExpr::And(_));
+            sql.push_str(if is_and { "1=1" } else { "0=1" });
+        },
+        QueryExpr::And(items) | QueryExpr::Or(items) => {
+            let joiner = if matches!(expr, QueryExpr::And(_)) {
+                " AND "
+            } else {
+                " OR "
+            };
+            sql.push('(');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    sql.push_str(joiner);
+                }
+                push_expr_sql(item, sql, params);
+            }
+            sql.push(')');
+        },
+        QueryExpr::Not(inner) => {
+            sql.push_str("NOT (");
+            push_expr_sql(inner, sql, params);
+            sql.push(')');
+        },
+        QueryExpr::Term { field, value } => {
+            push_term_sql(field, value, sql, params);
+        },
+        QueryExpr::Range { field, min, max } => {
+            // 列表达式本身可能带参数，需先于边界值绑定
+            match (min, max) {
+                (None, None) => sql.push_str("1=1"),
+                (Some(min), None) => {
+                    let column = range_column(field, params);
+                    sql.push_str(&format!("{} >= ?", column));
+                    params.push(to_value(*min));
+                },
+                (None, Some(max)) => {
+                    let column = range_column(field, params);
+                    sql.push_str(&format!("{} <= ?", column));
+                    params.push(to_value(*max));
+                },
+                (Some(min), Some(max)) => {
+                    let column = range_column(field, params);
+                    sql.push_str(&format!("{} BETWEEN ? AND ?", column));
+                    params.push(to_value(*min));
+                    params.push(to_value(*max));
+                },
+            }
+        },
+    }
+}
+
+fn push_term_sql(
+    field: &str,
+    value: &str,
+    sql: &mut String,
+    params: &mut Vec<Value>,
+) {
+    match field {
+        "id" => {
+            sql.push_str("nodes.id = ?");
+            params.push(to_value(value));
+        },
+        "type" | "node_type" => {
+            sql.push_str("nodes.node_type = ?");
+            params.push(to_value(value));
+        },
+        "parent" | "parent_id" => {
+            sql.push_str("nodes.parent_id = ?");
+            params.push(to_value(value));
+        },
+        "path" => {
+            sql.push_str("nodes.path LIKE ?");
+            params.push(to_value(format!("{}%", value)));
+        },
+        "mark" | "marks" => {
+            sql.push_str("nodes.marks LIKE ?");
+            params.push(to_value(format!("%\"{}\"%", value)));
+        },
+        "text" => {
+            sql.push_str(
+                "nodes.rowid IN (SELECT rowid FROM nodes_fts WHERE nodes_fts.text MATCH ?)",
+            );
+            params.push(to_value(value));
+        },
+        _ => {
+            if let Some((mark_type, attr_key)) = field
+                .strip_prefix("mark.")
+                .and_then(|rest| rest.split_once('.'))
+            {
+                sql.push_str(
+                    "EXISTS (
+                    SELECT 1 FROM json_each(nodes.marks_json)
+                    WHERE json_extract(value, '$.type') = ?
+                    AND json_extract(value, ?) = ?
+                )",
+                );
+                params.push(to_value(mark_type));
+                params.push(to_value(format!("$.attrs.{}", attr_key)));
+                params.push(to_value(value));
+            } else {
+                let key = field.strip_prefix("attrs.").unwrap_or(field);
+                sql.push_str("json_extract(nodes.attrs, ?) = ?");
+                params.push(to_value(format!("$.{}", key)));
+                params.push(to_value(value));
+            }
+        },
+    }
+}
+
+/// 范围字段对应的列表达式：内置数值列直接使用，其余按属性取整数
+fn range_column(
+    field: &str,
+    params: &mut Vec<Value>,
+) -> &'static str {
+    match field {
+        "order" | "order_i64" => "nodes.order_i64",
+        "created_at" | "created_at_i64" => "nodes.created_at_i64",
+        "updated_at" | "updated_at_i64" => "nodes.updated_at_i64",
+        _ => {
+            let key = field.strip_prefix("attrs.").unwrap_or(field);
+            params.push(to_value(format!("$.{}", key)));
+            "CAST(json_extract(nodes.attrs, ?) AS INTEGER)"
+        },
+    }
+}
+
 fn to_value<T: Serialize>(value: T) -> Value {
     rbs::value_def(value)
 }
@@ -784,4 +965,89 @@ mod tests {
         let empty = backend.get_docs_by_ids(&[]).await.unwrap();
         assert_eq!(empty.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_query_expr() {
+        let backend = SqliteBackend::new_in_system_temp().await.unwrap();
+
+        let make =
+            |id: &str, node_type: &str, name: &str, order: i64| IndexDoc {
+                node_id: id.to_string(),
+                node_type: node_type.to_string(),
+                parent_id: Some("root".to_string()),
+                path: vec!["root".to_string(), id.to_string()],
+                marks: vec![],
+                marks_json: "[]".to_string(),
+                attrs_flat: vec![("name".to_string(), name.to_string())],
+                attrs_json: format!(r#"{{"name":"{}"}}"#, name),
+                text: None,
+                order_i64: Some(order),
+                created_at_i64: None,
+                updated_at_i64: None,
+            };
+        backend
+            .rebuild_all(vec![
+                make("a", "DW", "foo", 1),
+                make("b", "DW", "bar", 2),
+                make("c", "GC", "foo", 3),
+            ])
+            .await
+            .unwrap();
+
+        let search = |q: &str| {
+            let expr = QueryExpr::parse(q).unwrap();
+            let backend = &backend;
+            async move {
+                let mut ids = backend
+                    .search_ids(SearchQuery {
+                        expr: Some(expr),
+                        limit: 10,
+                        ..Default::default()
+                    })
+                    .await
+                    .unwrap();
+                ids.sort();
+                ids
+            }
+        };
+
+        assert_eq!(search("type:DW AND name:foo").await, vec!["a"]);
+        assert_eq!(search("type:GC OR name:bar").await, vec!["b", "c"]);
+        assert_eq!(search("NOT type:DW").await, vec!["c"]);
+        assert_eq!(search("order:[2 TO *]").await, vec!["b", "c"]);
+        // 参数化：值中的引号不会破坏 SQL
+        assert!(search(r#"name:"x' OR 1=1 --""#).await.is_empty());
+
+        // 扁平条件与表达式按 AND 组合
+        let ids = backend
+            .search_ids(SearchQuery {
+                node_type: Some("DW".to_string()),
+                expr: Some(QueryExpr::parse("name:bar").unwrap()),
+                limit: 10,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids, vec!["b"]);
+    }
+
+    #[test]
+    fn test_flat_query_lowering() {
+        let query = SearchQuery {
+            node_type: Some("DW".to_string()),
+            attrs: vec![("name".to_string(), "foo".to_string())],
+            range_field: Some("order_i64".to_string()),
+            range_max: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(
+            query.to_expr(),
+            Some(QueryExpr::And(vec![
+                QueryExpr::term("type", "DW"),
+                QueryExpr::term("attrs.name", "foo"),
+                QueryExpr::range("order_i64", None, Some(5)),
+            ]))
+        );
+        assert_eq!(SearchQuery::default().to_expr(), None);
+    }
 }
@@ -1,11 +1,11 @@
 use crate::backend::IndexMutation;
 use crate::model::IndexDoc;
-use crate::step_registry::{global_registry, StepIndexContext};
+use crate::step_registry::{bulk_attr_upserts, global_registry, StepIndexContext};
 use mf_model::NodeId;
 use mf_model::{node_pool::NodePool, node_definition::NodeTree, schema::Schema};
 use mf_transform::step::StepGeneric;
 use mf_transform::{
-    attr_step::AttrStep,
+    attr_step::{AttrStep, BulkAttrStep},
     mark_step::{AddMarkStep, RemoveMarkStep},
     node_step::{AddNodeStep, MoveNodeStep, RemoveNodeStep},
 };
@@ -38,6 +38,10 @@ pub fn mutations_from_step(
         return vec![];
     }
 
+    if let Some(s) = step.downcast_ref::<BulkAttrStep>() {
+        return bulk_attr_upserts(pool_after, &s.updates);
+    }
+
     if let Some(s) = step.downcast_ref::<AddMarkStep>() {
         if let Some(node) = pool_after.get_node(&s.id) {
             return vec![IndexMutation::Upsert(IndexDoc::from_node(
@@ -1,15 +1,19 @@
 pub mod backend;
 pub mod backend_sqlite;
 pub mod indexer;
+pub mod live_query;
 pub mod model;
+pub mod query;
 pub mod service;
 pub mod state_plugin;
 pub mod step_registry;
 
 // 导出类型
 pub use backend::{Backend, IndexMutation, SearchQuery, SqliteBackend};
+pub use live_query::{LiveQueryDelta, LiveQueryHandle};
+pub use query::QueryExpr;
 pub use service::{
-    IndexService, SearchService, IndexEvent, RebuildScope,
+    IndexService, SearchService, IndexEvent, RebuildScope, ConsistencyReport,
     event_from_transaction,
 };
 pub use state_plugin::{
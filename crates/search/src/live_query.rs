@@ -0,0 +1,634 @@
+use crate::backend::{IndexMutation, SearchQuery, SqliteBackend};
+use crate::query::QueryExpr;
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use tokio::sync::mpsc;
+
+/// 按 id 限定查询时每批的 id 数（受 SQLite 参数个数限制）
+const ID_BATCH: usize = 500;
+
+/// 实时查询结果集的增量
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiveQueryDelta {
+    /// 新进入结果集的节点
+    pub added: Vec<String>,
+    /// 离开结果集的节点
+    pub removed: Vec<String>,
+    /// 仍在结果集中、但索引文档已更新的节点
+    pub updated: Vec<String>,
+}
+
+impl LiveQueryDelta {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.updated.is_empty()
+    }
+
+    fn sort(&mut self) {
+        self.added.sort();
+        self.removed.sort();
+        self.updated.sort();
+    }
+}
+
+/// 一批索引变更涉及的节点：id -> 变更后的节点类型，`None` 表示已删除
+pub(crate) type ChangedDocs = HashMap<String, Option<String>>;
+
+struct LiveEntry {
+    query: SearchQuery,
+    /// 查询要求的节点类型，用于跳过类型不符的变更
+    node_type: Option<String>,
+    members: HashSet<String>,
+    sender: mpsc::UnboundedSender<LiveQueryDelta>,
+}
+
+/// 已注册的实时查询，由 [`SqliteBackend`] 在索引写入后驱动
+#[derive(Default)]
+pub struct LiveQueries {
+    next_id: AtomicU64,
+    entries: Mutex<HashMap<u64, LiveEntry>>,
+    /// 串行化索引写入与查询注册，保证初始结果与后续增量之间不漏掉变更
+    gate: tokio::sync::Mutex<()>,
+}
+
+impl LiveQueries {
+    /// 当前注册的实时查询数
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) async fn lock(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.gate.lock().await
+    }
+
+    /// 注册查询并计算初始结果集
+    ///
+    /// 实时查询维护完整的结果集，忽略 `limit`、`offset` 与排序
+    pub(crate) async fn register(
+        self: &Arc<Self>,
+        backend: &SqliteBackend,
+        query: SearchQuery,
+    ) -> Result<LiveQueryHandle> {
+        let _gate = self.lock().await;
+        let query = unbounded(&query);
+        let initial = backend.search_ids(query.clone()).await?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.entries.lock().insert(
+            id,
+            LiveEntry {
+                node_type: required_type(&query),
+                members: initial.iter().cloned().collect(),
+                query,
+                sender,
+            },
+        );
+        Ok(LiveQueryHandle {
+            id,
+            initial,
+            receiver,
+            registry: Arc::downgrade(self),
+        })
+    }
+
+    /// 汇总变更涉及的节点，没有注册查询时返回 `None`
+    pub(crate) fn changed_docs(
+        &self,
+        mutations: &[IndexMutation],
+    ) -> Option<ChangedDocs> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut changed = ChangedDocs::new();
+        for mutation in mutations {
+            match mutation {
+                IndexMutation::Add(doc) | IndexMutation::Upsert(doc) => {
+                    changed.insert(
+                        doc.node_id.clone(),
+                        Some(doc.node_type.clone()),
+                    );
+                },
+                IndexMutation::DeleteById(id) => {
+                    changed.insert(id.clone(), None);
+                },
+                IndexMutation::DeleteManyById(ids) => {
+                    for id in ids {
+                        changed.insert(id.clone(), None);
+                    }
+                },
+            }
+        }
+        Some(changed)
+    }
+
+    /// 索引写入后推送增量，只对可能影响结果集的节点重新求值
+    pub(crate) async fn notify(
+        &self,
+        backend: &SqliteBackend,
+        changed: &ChangedDocs,
+    ) -> Result<()> {
+        let plans: Vec<(u64, SearchQuery, Vec<String>, Vec<String>)> = {
+            let mut entries = self.entries.lock();
+            entries.retain(|_, entry| !entry.sender.is_closed());
+            entries
+                .iter()
+                .filter_map(|(id, entry)| {
+                    let mut candidates = Vec::new();
+                    let mut removed = Vec::new();
+                    for (node_id, node_type) in changed {
+                        let matches_type = match (node_type, &entry.node_type) {
+                            (None, _) => false,
+                            (Some(actual), Some(required)) => {
+                                actual == required
+                            },
+                            (Some(_), None) => true,
+                        };
+                        if matches_type {
+                            candidates.push(node_id.clone());
+                        } else if entry.members.contains(node_id) {
+                            removed.push(node_id.clone());
+                        }
+                    }
+                    (!candidates.is_empty() || !removed.is_empty()).then(|| {
+                        (*id, entry.query.clone(), candidates, removed)
+                    })
+                })
+                .collect()
+        };
+
+        for (id, query, candidates, removed) in plans {
+            let matched = filter_ids(backend, &query, &candidates).await?;
+            let mut entries = self.entries.lock();
+            let Some(entry) = entries.get_mut(&id) else {
+                continue;
+            };
+            let mut delta = LiveQueryDelta { removed, ..Default::default() };
+            for node_id in candidates {
+                match (
+                    matched.contains(&node_id),
+                    entry.members.contains(&node_id),
+                ) {
+                    (true, true) => delta.updated.push(node_id),
+                    (true, false) => delta.added.push(node_id),
+                    (false, true) => delta.removed.push(node_id),
+                    (false, false) => {},
+                }
+            }
+            entry.send(delta);
+        }
+        Ok(())
+    }
+
+    /// 全量重建后重新计算所有查询（不报告 `updated`）
+    pub(crate) async fn refresh(
+        &self,
+        backend: &SqliteBackend,
+    ) -> Result<()> {
+        let plans: Vec<(u64, SearchQuery)> = {
+            let mut entries = self.entries.lock();
+            entries.retain(|_, entry| !entry.sender.is_closed());
+            entries
+                .iter()
+                .map(|(id, entry)| (*id, entry.query.clone()))
+                .collect()
+        };
+        for (id, query) in plans {
+            let current: HashSet<String> =
+                backend.search_ids(query).await?.into_iter().collect();
+            let mut entries = self.entries.lock();
+            let Some(entry) = entries.get_mut(&id) else {
+                continue;
+            };
+            let delta = LiveQueryDelta {
+                added: current.difference(&entry.members).cloned().collect(),
+                removed: entry.members.difference(&current).cloned().collect(),
+                updated: Vec::new(),
+            };
+            entry.send(delta);
+        }
+        Ok(())
+    }
+}
+
+impl LiveEntry {
+    fn send(
+        &mut self,
+        mut delta: LiveQueryDelta,
+    ) {
+        if delta.is_empty() {
+            return;
+        }
+        for id in &delta.removed {
+            self.members.remove(id);
+        }
+        self.members.extend(delta.added.iter().cloned());
+        delta.sort();
+        let _ = self.sender.send(delta);
+    }
+}
+
+/// 实时查询句柄，drop 时注销查询
+pub struct LiveQueryHandle {
+    id: u64,
+    initial: Vec<String>,
+    receiver: mpsc::UnboundedReceiver<LiveQueryDelta>,
+    registry: Weak<LiveQueries>,
+}
+
+impl LiveQueryHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// 注册时的结果集
+    pub fn initial(&self) -> &[String] {
+        &self.initial
+    }
+
+    /// 等待下一个增量，查询被注销后返回 `None`
+    pub async fn recv(&mut self) -> Option<LiveQueryDelta> {
+        self.receiver.recv().await
+    }
+
+    /// 取出一个已到达的增量，不等待
+    pub fn try_recv(&mut self) -> Option<LiveQueryDelta> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for LiveQueryHandle {
+    fn drop(&mut self) {
+        if let Some(registry) = self.registry.upgrade() {
+            registry.entries.lock().remove(&self.id);
+        }
+    }
+}
+
+/// 去掉分页与排序，得到完整结果集的查询
+fn unbounded(query: &SearchQuery) -> SearchQuery {
+    SearchQuery {
+        limit: i64::MAX as usize,
+        offset: 0,
+        sort_by: None,
+        ..query.clone()
+    }
+}
+
+/// 查询要求的节点类型：`node_type` 或顶层 AND 中的 `type:` 条件
+fn required_type(query: &SearchQuery) -> Option<String> {
+    fn expr_type(expr: &QueryExpr) -> Option<String> {
+        match expr {
+            QueryExpr::Term { field, value }
+                if field == "type" || field == "node_type" =>
+            {
+                Some(value.clone())
+            },
+            QueryExpr::And(items) => items.iter().find_map(expr_type),
+            _ => None,
+        }
+    }
+    query.node_type.clone().or_else(|| query.expr.as_ref().and_then(expr_type))
+}
+
+/// 在给定节点中筛选满足查询的节点
+async fn filter_ids(
+    backend: &SqliteBackend,
+    query: &SearchQuery,
+    ids: &[String],
+) -> Result<HashSet<String>> {
+    let mut matched = HashSet::new();
+    for chunk in ids.chunks(ID_BATCH) {
+        let only = QueryExpr::Or(
+            chunk.iter().map(|id| QueryExpr::term("id", id)).collect(),
+        );
+        let mut restricted = query.clone();
+        restricted.expr = Some(match restricted.expr.take() {
+            Some(expr) => QueryExpr::And(vec![expr, only]),
+            None => only,
+        });
+        matched.extend(backend.search_ids(restricted).await?);
+    }
+    Ok(matched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::IndexDoc;
+    use crate::service::{IndexEvent, IndexService, RebuildScope, SearchService};
+    use mf_model::{
+        attrs::Attrs,
+        node::Node,
+        node_definition::{NodeSpec, NodeTree},
+        node_pool::NodePool,
+        rpds::ht_map_sync,
+        schema::{AttributeSpec, Schema, SchemaSpec},
+        tree::Tree,
+    };
+    use mf_transform::{
+        attr_step::AttrStep,
+        node_step::{AddNodeStep, RemoveNodeStep},
+        Transform,
+    };
+    use serde_json::json;
+
+    const STATUSES: [&str; 2] = ["open", "done"];
+
+    fn create_schema() -> Arc<Schema> {
+        let status = HashMap::from([(
+            "status".to_string(),
+            AttributeSpec::new(Some(json!("open"))),
+        )]);
+        let leaf = NodeSpec {
+            content: Some("(dw | qd)*".to_string()),
+            attrs: Some(status),
+            ..Default::default()
+        };
+        let nodes = HashMap::from([
+            (
+                "doc".to_string(),
+                NodeSpec {
+                    content: Some("(dw | qd)*".to_string()),
+                    ..Default::default()
+                },
+            ),
+            ("dw".to_string(), leaf.clone()),
+            ("qd".to_string(), leaf),
+        ]);
+        Arc::new(
+            Schema::compile(SchemaSpec {
+                nodes,
+                marks: HashMap::new(),
+                top_node: Some("doc".to_string()),
+            })
+            .unwrap(),
+        )
+    }
+
+    /// 线性同余随机数，保证测试可复现
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(
+            &mut self,
+            bound: usize,
+        ) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((self.0 >> 33) as usize) % bound
+        }
+    }
+
+    fn all_ids(pool: &NodePool) -> Vec<String> {
+        let mut ids: Vec<String> = pool
+            .get_inner()
+            .nodes
+            .iter()
+            .flat_map(|shard| shard.keys().map(|id| id.to_string()))
+            .filter(|id| id != "doc")
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// 随机生成一个事务：新增、删除子树或修改状态
+    fn random_transaction(
+        rng: &mut Lcg,
+        pool: &Arc<NodePool>,
+        schema: &Arc<Schema>,
+        next_id: &mut usize,
+    ) -> Transform {
+        let mut tr = Transform::new(pool.clone(), schema.clone());
+        for _ in 0..rng.next(3) + 1 {
+            let ids = all_ids(&tr.doc());
+            match rng.next(6) {
+                0 | 1 if !ids.is_empty() => {
+                    let id = &ids[rng.next(ids.len())];
+                    let status = STATUSES[rng.next(2)];
+                    tr.step(Arc::new(AttrStep::new(
+                        id.as_str().into(),
+                        ht_map_sync! { "status".to_string() => json!(status) },
+                    )))
+                    .unwrap();
+                },
+                2 if !ids.is_empty() => {
+                    let doc = tr.doc();
+                    let id = ids[rng.next(ids.len())].as_str().into();
+                    let parent = doc.parent_id(&id).unwrap().clone();
+                    tr.step(Arc::new(RemoveNodeStep::new(parent, vec![id])))
+                        .unwrap();
+                },
+                _ => {
+                    let parent = if ids.is_empty() || rng.next(2) == 0 {
+                        "doc".to_string()
+                    } else {
+                        ids[rng.next(ids.len())].clone()
+                    };
+                    *next_id += 1;
+                    let node = Node::new(
+                        &format!("n{next_id}"),
+                        ["dw", "qd"][rng.next(2)].to_string(),
+                        Attrs::from(ht_map_sync! {
+                            "status".to_string() => json!(STATUSES[rng.next(2)])
+                        }),
+                        vec![],
+                        vec![],
+                    );
+                    tr.step(Arc::new(AddNodeStep::new(
+                        parent.into(),
+                        vec![NodeTree(node, vec![])],
+                    )))
+                    .unwrap();
+                },
+            }
+        }
+        tr
+    }
+
+    fn apply_deltas(
+        handle: &mut LiveQueryHandle,
+        members: &mut HashSet<String>,
+        ids: &HashSet<String>,
+    ) {
+        while let Some(delta) = handle.try_recv() {
+            for id in &delta.added {
+                assert!(members.insert(id.clone()), "重复新增 {id}");
+            }
+            for id in &delta.removed {
+                assert!(members.remove(id), "删除不在结果集中的 {id}");
+            }
+            for id in &delta.updated {
+                assert!(members.contains(id) && ids.contains(id));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_live_query_matches_fresh_query() {
+        let schema = create_schema();
+        let root = Node::new(
+            "doc",
+            "doc".to_string(),
+            Attrs::default(),
+            vec![],
+            vec![],
+        );
+        let mut pool = NodePool::new(Arc::new(Tree::new(root)));
+
+        let backend =
+            Arc::new(SqliteBackend::new_in_system_temp().await.unwrap());
+        let index = IndexService::new(backend.clone());
+        let search = SearchService::new(backend.clone());
+        index
+            .handle(IndexEvent::Rebuild {
+                pool: pool.clone(),
+                scope: RebuildScope::Full,
+            })
+            .await
+            .unwrap();
+
+        let queries = [
+            SearchQuery {
+                node_type: Some("dw".to_string()),
+                attrs: vec![("status".to_string(), "open".to_string())],
+                ..Default::default()
+            },
+            SearchQuery {
+                expr: Some(QueryExpr::parse("attrs.status:done").unwrap()),
+                ..Default::default()
+            },
+        ];
+        let mut live = Vec::new();
+        for query in &queries {
+            let handle =
+                search.register_live_query(query.clone()).await.unwrap();
+            let members: HashSet<String> =
+                handle.initial().iter().cloned().collect();
+            live.push((query, handle, members));
+        }
+        assert_eq!(backend.live_queries().len(), 2);
+
+        let mut rng = Lcg(7);
+        let mut next_id = 0;
+        for _ in 0..60 {
+            let tr = random_transaction(&mut rng, &pool, &schema, &mut next_id);
+            let pool_after = tr.doc();
+            index
+                .handle(IndexEvent::TransactionCommitted {
+                    pool_before: Some(pool.clone()),
+                    pool_after: pool_after.clone(),
+                    steps: tr.steps.iter().cloned().collect(),
+                })
+                .await
+                .unwrap();
+            pool = pool_after;
+
+            let ids: HashSet<String> = all_ids(&pool).into_iter().collect();
+            for (query, handle, members) in &mut live {
+                apply_deltas(handle, members, &ids);
+                let fresh: HashSet<String> = search
+                    .search(unbounded(query))
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .collect();
+                assert_eq!(*members, fresh);
+            }
+        }
+
+        // 重建后结果集保持一致
+        index
+            .handle(IndexEvent::Rebuild {
+                pool: pool.clone(),
+                scope: RebuildScope::Full,
+            })
+            .await
+            .unwrap();
+        let ids: HashSet<String> = all_ids(&pool).into_iter().collect();
+        for (query, handle, members) in &mut live {
+            apply_deltas(handle, members, &ids);
+            let fresh: HashSet<String> = search
+                .search(unbounded(query))
+                .await
+                .unwrap()
+                .into_iter()
+                .collect();
+            assert_eq!(*members, fresh);
+        }
+
+        drop(live);
+        assert!(backend.live_queries().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_type_mismatch_skips_query() {
+        let backend =
+            Arc::new(SqliteBackend::new_in_system_temp().await.unwrap());
+        let search = SearchService::new(backend.clone());
+        let mut handle = search
+            .register_live_query(SearchQuery {
+                node_type: Some("dw".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(handle.initial().is_empty());
+
+        let pool = NodePool::new(Arc::new(Tree::new(Node::new(
+            "doc",
+            "doc".to_string(),
+            Attrs::default(),
+            vec![],
+            vec![],
+        ))));
+        let doc = |id: &str, node_type: &str| {
+            let node = Node::new(
+                id,
+                node_type.to_string(),
+                Attrs::default(),
+                vec![],
+                vec![],
+            );
+            IndexDoc::from_node(&pool, &node)
+        };
+        let changed = backend
+            .live_queries()
+            .changed_docs(&[IndexMutation::Add(doc("q1", "qd"))])
+            .unwrap();
+        // 类型不符的变更不会触发按 id 的求值
+        backend.live_queries().notify(&backend, &changed).await.unwrap();
+        assert!(handle.try_recv().is_none());
+
+        backend
+            .apply(vec![
+                IndexMutation::Add(doc("q1", "qd")),
+                IndexMutation::Add(doc("d1", "dw")),
+            ])
+            .await
+            .unwrap();
+        let delta = handle.try_recv().unwrap();
+        assert_eq!(delta.added, vec!["d1".to_string()]);
+
+        backend
+            .apply(vec![IndexMutation::Upsert(doc("d1", "dw"))])
+            .await
+            .unwrap();
+        assert_eq!(handle.try_recv().unwrap().updated, vec!["d1".to_string()]);
+
+        backend
+            .apply(vec![IndexMutation::DeleteById("d1".to_string())])
+            .await
+            .unwrap();
+        assert_eq!(handle.try_recv().unwrap().removed, vec!["d1".to_string()]);
+    }
+}
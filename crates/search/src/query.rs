@@ -0,0 +1,374 @@
+use anyhow::{anyhow, bail, Result};
+use std::str::FromStr;
+
+/// 布尔查询表达式
+///
+/// 字段约定（由后端负责翻译）：
+/// - `id` / `type`(`node_type`) / `parent`(`parent_id`)：精确匹配
+/// - `path`：路径前缀匹配
+/// - `mark`(`marks`)：包含某类型 mark
+/// - `mark.<type>.<attr>`：带属性的 mark 匹配
+/// - `text`：全文匹配（FTS5）
+/// - `attrs.<key>` 或其它任意字段：顶层属性匹配
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryExpr {
+    And(Vec<QueryExpr>),
+    Or(Vec<QueryExpr>),
+    Not(Box<QueryExpr>),
+    Term {
+        field: String,
+        value: String,
+    },
+    /// 闭区间范围匹配，`None` 表示该侧不限
+    Range {
+        field: String,
+        min: Option<i64>,
+        max: Option<i64>,
+    },
+}
+
+impl QueryExpr {
+    pub fn term(
+        field: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        QueryExpr::Term { field: field.into(), value: value.into() }
+    }
+
+    pub fn range(
+        field: impl Into<String>,
+        min: Option<i64>,
+        max: Option<i64>,
+    ) -> Self {
+        QueryExpr::Range { field: field.into(), min, max }
+    }
+
+    /// 解析查询字符串
+    ///
+    /// 语法：
+    /// - `field:value`，值含空格时使用 `field:"a b"`
+    /// - `field:[min TO max]`，`*` 表示不限
+    /// - `AND` / `OR` / `NOT` 与括号，优先级 `NOT > AND > OR`
+    /// - 相邻的条件之间省略运算符时视为 `AND`
+    ///
+    /// 例如：`type:DW AND (name:foo OR NOT mark:bold)`
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0, depth: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            bail!("查询解析失败: 意外的符号 {:?}", token);
+        }
+        Ok(expr)
+    }
+}
+
+impl FromStr for QueryExpr {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        QueryExpr::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(String, String),
+    Range(String, Option<i64>, Option<i64>),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+            continue;
+        }
+        if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+            continue;
+        }
+
+        // 读取字段名或关键字
+        let start = i;
+        while i < chars.len()
+            && !chars[i].is_whitespace()
+            && !matches!(chars[i], '(' | ')' | ':')
+        {
+            i += 1;
+        }
+        let word: String = chars[start..i].iter().collect();
+
+        if i >= chars.len() || chars[i] != ':' {
+            match word.as_str() {
+                "AND" => tokens.push(Token::And),
+                "OR" => tokens.push(Token::Or),
+                "NOT" => tokens.push(Token::Not),
+                _ => bail!(
+                    "查询解析失败: `{}` 缺少字段名（应为 field:value）",
+                    word
+                ),
+            }
+            continue;
+        }
+        if word.is_empty() {
+            bail!("查询解析失败: 位置 {} 处缺少字段名", i);
+        }
+
+        // 跳过 ':'
+        i += 1;
+        match chars.get(i) {
+            Some('"') => {
+                i += 1;
+                let mut value = String::new();
+                loop {
+                    match chars.get(i) {
+                        Some('"') => {
+                            i += 1;
+                            break;
+                        },
+                        Some('\\') if i + 1 < chars.len() => {
+                            value.push(chars[i + 1]);
+                            i += 2;
+                        },
+                        Some(ch) => {
+                            value.push(*ch);
+                            i += 1;
+                        },
+                        None => {
+                            bail!("查询解析失败: 字段 `{}` 的引号未闭合", word)
+                        },
+                    }
+                }
+                tokens.push(Token::Term(word, value));
+            },
+            Some('[') => {
+                i += 1;
+                let body_start = i;
+                while i < chars.len() && chars[i] != ']' {
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    bail!("查询解析失败: 字段 `{}` 的范围未闭合", word);
+                }
+                let body: String = chars[body_start..i].iter().collect();
+                i += 1;
+                let (min, max) = parse_range_body(&word, &body)?;
+                tokens.push(Token::Range(word, min, max));
+            },
+            _ => {
+                let value_start = i;
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !matches!(chars[i], '(' | ')')
+                {
+                    i += 1;
+                }
+                let value: String = chars[value_start..i].iter().collect();
+                if value.is_empty() {
+                    bail!("查询解析失败: 字段 `{}` 缺少值", word);
+                }
+                tokens.push(Token::Term(word, value));
+            },
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_range_body(
+    field: &str,
+    body: &str,
+) -> Result<(Option<i64>, Option<i64>)> {
+    let parts: Vec<&str> = body.split_whitespace().collect();
+    if parts.len() != 3 || parts[1] != "TO" {
+        bail!("查询解析失败: 字段 `{}` 的范围应为 [min TO max]", field);
+    }
+    let bound = |raw: &str| -> Result<Option<i64>> {
+        if raw == "*" {
+            return Ok(None);
+        }
+        raw.parse::<i64>().map(Some).map_err(|_| {
+            anyhow!(
+                "查询解析失败: 字段 `{}` 的范围值 `{}` 不是整数",
+                field,
+                raw
+            )
+        })
+    };
+    Ok((bound(parts[0])?, bound(parts[2])?))
+}
+
+/// 括号与 `NOT` 的最大嵌套层数，避免恶意输入导致栈溢出
+pub const MAX_QUERY_DEPTH: usize = 64;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// 当前嵌套层数
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<QueryExpr> {
+        let mut items = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            items.push(self.parse_and()?);
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            QueryExpr::Or(items)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<QueryExpr> {
+        let mut items = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.pos += 1;
+                    items.push(self.parse_unary()?);
+                },
+                // 省略运算符的相邻条件按 AND 处理
+                Some(Token::Not | Token::LParen | Token::Term(..))
+                | Some(Token::Range(..)) => {
+                    items.push(self.parse_unary()?);
+                },
+                _ => break,
+            }
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            QueryExpr::And(items)
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<QueryExpr> {
+        if self.depth >= MAX_QUERY_DEPTH {
+            bail!("查询解析失败: 嵌套超过 {} 层", MAX_QUERY_DEPTH);
+        }
+        self.depth += 1;
+        let expr = self.parse_primary();
+        self.depth -= 1;
+        expr
+    }
+
+    fn parse_primary(&mut self) -> Result<QueryExpr> {
+        match self.next() {
+            Some(Token::Not) => {
+                Ok(QueryExpr::Not(Box::new(self.parse_unary()?)))
+            },
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => bail!("查询解析失败: 缺少右括号"),
+                }
+            },
+            Some(Token::Term(field, value)) => {
+                Ok(QueryExpr::Term { field, value })
+            },
+            Some(Token::Range(field, min, max)) => {
+                Ok(QueryExpr::Range { field, min, max })
+            },
+            Some(token) => bail!("查询解析失败: 意外的符号 {:?}", token),
+            None => bail!("查询解析失败: 表达式不完整"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and() {
+        let expr = QueryExpr::parse("type:DW AND name:foo").unwrap();
+        assert_eq!(
+            expr,
+            QueryExpr::And(vec![
+                QueryExpr::term("type", "DW"),
+                QueryExpr::term("name", "foo"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_precedence_and_grouping() {
+        let expr =
+            QueryExpr::parse("type:DW OR type:GC AND NOT (mark:bold)").unwrap();
+        assert_eq!(
+            expr,
+            QueryExpr::Or(vec![
+                QueryExpr::term("type", "DW"),
+                QueryExpr::And(vec![
+                    QueryExpr::term("type", "GC"),
+                    QueryExpr::Not(Box::new(QueryExpr::term("mark", "bold"))),
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_quoted_range_and_implicit_and() {
+        let expr =
+            QueryExpr::parse(r#"name:"foo bar" order:[1 TO *]"#).unwrap();
+        assert_eq!(
+            expr,
+            QueryExpr::And(vec![
+                QueryExpr::term("name", "foo bar"),
+                QueryExpr::range("order", Some(1), None),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(QueryExpr::parse("type:DW AND").is_err());
+        assert!(QueryExpr::parse("(type:DW").is_err());
+        assert!(QueryExpr::parse("foo").is_err());
+        assert!(QueryExpr::parse(r#"name:"foo"#).is_err());
+        assert!(QueryExpr::parse("order:[a TO 2]").is_err());
+    }
+
+    #[test]
+    fn test_parse_depth_limit() {
+        let nested = |depth: usize| {
+            format!("{}type:DW{}", "(".repeat(depth), ")".repeat(depth))
+        };
+        assert!(QueryExpr::parse(&nested(MAX_QUERY_DEPTH - 1)).is_ok());
+        let err = QueryExpr::parse(&nested(MAX_QUERY_DEPTH)).unwrap_err();
+        assert!(err.to_string().contains("嵌套"), "{err}");
+        // 远超限制的输入返回错误而不是栈溢出
+        assert!(QueryExpr::parse(&nested(100_000)).is_err());
+        assert!(QueryExpr::parse(&"NOT ".repeat(100_000)).is_err());
+    }
+}
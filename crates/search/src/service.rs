@@ -1,10 +1,12 @@
-use crate::backend::SqliteBackend;
+use crate::backend::{IndexMutation, SqliteBackend};
 use crate::indexer::mutations_from_step;
+use crate::live_query::LiveQueryHandle;
 use crate::model::IndexDoc;
 use anyhow::Result;
 use mf_model::node_pool::NodePool;
 use mf_model::schema::Schema;
 use mf_transform::step::StepGeneric;
+use std::collections::HashSet;
 use std::sync::Arc;
 use mf_state::transaction::Transaction;
 
@@ -32,6 +34,40 @@ pub enum RebuildScope {
     Full,
 }
 
+/// 索引与节点池的一致性检查结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsistencyReport {
+    /// 已索引但节点池中不存在的文档 ID
+    pub missing_in_pool: Vec<String>,
+    /// 节点池中存在但未被索引的节点 ID
+    pub missing_in_index: Vec<String>,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.missing_in_pool.is_empty() && self.missing_in_index.is_empty()
+    }
+
+    /// 生成使索引与节点池一致所需的最小增量变更
+    pub fn to_mutations(
+        &self,
+        pool: &NodePool,
+    ) -> Vec<IndexMutation> {
+        let mut muts = Vec::new();
+        if !self.missing_in_pool.is_empty() {
+            muts.push(IndexMutation::DeleteManyById(
+                self.missing_in_pool.clone(),
+            ));
+        }
+        for id in &self.missing_in_index {
+            if let Some(node) = pool.get_node(&id.as_str().into()) {
+                muts.push(IndexMutation::Add(IndexDoc::from_node(pool, node)));
+            }
+        }
+        muts
+    }
+}
+
 /// 索引服务：桥接 `Transaction/Step` 与后端
 pub struct IndexService {
     backend: Arc<SqliteBackend>,
@@ -78,6 +114,43 @@ impl IndexService {
             },
         }
     }
+
+    /// 比对索引与节点池的节点 ID 集合（不重建索引）
+    ///
+    /// 只检查节点是否存在，不比较文档内容
+    pub async fn verify(
+        &self,
+        pool: &NodePool,
+    ) -> Result<ConsistencyReport> {
+        let indexed: HashSet<String> =
+            self.backend.all_ids().await?.into_iter().collect();
+        let mut in_pool: HashSet<String> = HashSet::new();
+        for shard in &pool.get_inner().nodes {
+            for id in shard.keys() {
+                in_pool.insert(id.to_string());
+            }
+        }
+
+        let mut missing_in_pool: Vec<String> =
+            indexed.difference(&in_pool).cloned().collect();
+        let mut missing_in_index: Vec<String> =
+            in_pool.difference(&indexed).cloned().collect();
+        missing_in_pool.sort();
+        missing_in_index.sort();
+        Ok(ConsistencyReport { missing_in_pool, missing_in_index })
+    }
+
+    /// 检查并修复索引，返回修复前的检查结果
+    pub async fn repair(
+        &self,
+        pool: &NodePool,
+    ) -> Result<ConsistencyReport> {
+        let report = self.verify(pool).await?;
+        if !report.is_consistent() {
+            self.backend.apply(report.to_mutations(pool)).await?;
+        }
+        Ok(report)
+    }
 }
 
 /// 搜索服务：提供高层查询接口
@@ -152,6 +225,17 @@ impl SearchService {
             .await
     }
 
+    /// 注册实时查询：返回初始结果集，之后每次索引变更改变结果集时
+    /// 通过句柄推送增量，句柄 drop 后自动注销
+    ///
+    /// 实时查询维护完整结果集，忽略 `limit`、`offset` 与排序
+    pub async fn register_live_query(
+        &self,
+        query: crate::backend::SearchQuery,
+    ) -> Result<LiveQueryHandle> {
+        self.backend.live_queries().register(&self.backend, query).await
+    }
+
     /// 按类型查询
     pub async fn query_by_type(
         &self,
@@ -176,3 +260,80 @@ pub fn event_from_transaction(
     let steps: Vec<Arc<dyn StepGeneric<NodePool, Schema>>> = tr.steps.iter().cloned().collect();
     IndexEvent::TransactionCommitted { pool_before: None, pool_after, steps }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mf_model::{attrs::Attrs, node::Node, tree::Tree};
+
+    fn node(id: &str) -> Node {
+        Node::new(id, "paragraph".to_string(), Attrs::default(), vec![], vec![])
+    }
+
+    #[tokio::test]
+    async fn test_verify_and_repair() {
+        let mut tree = Tree::new(node("root"));
+        tree.add_node(&"root".into(), &vec![node("a"), node("b")]).unwrap();
+        let pool = NodePool::new(Arc::new(tree));
+
+        let backend =
+            Arc::new(SqliteBackend::new_in_system_temp().await.unwrap());
+        let service = IndexService::new(backend.clone());
+        service
+            .handle(IndexEvent::Rebuild {
+                pool: pool.clone(),
+                scope: RebuildScope::Full,
+            })
+            .await
+            .unwrap();
+        assert!(service.verify(&pool).await.unwrap().is_consistent());
+
+        // 模拟漂移：索引中多出一个过期文档、缺少节点 b
+        let stale = IndexDoc {
+            node_id: "stale".to_string(),
+            ..IndexDoc::from_node(&pool, pool.get_node(&"a".into()).unwrap())
+        };
+        backend
+            .apply(vec![
+                IndexMutation::Add(stale),
+                IndexMutation::DeleteById("b".to_string()),
+            ])
+            .await
+            .unwrap();
+
+        let report = service.verify(&pool).await.unwrap();
+        assert_eq!(report.missing_in_pool, vec!["stale".to_string()]);
+        assert_eq!(report.missing_in_index, vec!["b".to_string()]);
+
+        let repaired = service.repair(&pool).await.unwrap();
+        assert_eq!(repaired, report);
+        assert!(service.verify(&pool).await.unwrap().is_consistent());
+    }
+
+    #[test]
+    fn test_bulk_attr_step_mutations() {
+        use mf_model::rpds::ht_map_sync;
+        use mf_transform::attr_step::BulkAttrStep;
+
+        let mut tree = Tree::new(node("root"));
+        tree.add_node(&"root".into(), &vec![node("a"), node("b")]).unwrap();
+        let pool = NodePool::new(Arc::new(tree));
+
+        let change = ht_map_sync! { "rate".to_string() => 2.into() };
+        let step: Arc<dyn StepGeneric<NodePool, Schema>> =
+            Arc::new(BulkAttrStep::new(vec![
+                ("a".into(), change.clone()),
+                ("b".into(), change.clone()),
+                ("a".into(), change),
+            ]));
+        let muts = mutations_from_step(&pool, &pool, &step);
+        let ids: Vec<String> = muts
+            .iter()
+            .map(|m| match m {
+                IndexMutation::Upsert(doc) => doc.node_id.clone(),
+                _ => panic!("批量属性变更应只产生 Upsert"),
+            })
+            .collect();
+        assert_eq!(ids, vec!["a".to_string(), "b".to_string()]);
+    }
+}
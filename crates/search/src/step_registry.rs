@@ -119,7 +119,10 @@ where
 }
 
 // ---------------- 内置步骤的默认转换器 ----------------
-use mf_transform::attr_step::AttrStep;
+use mf_transform::attr_step::{AttrStep, BulkAttrStep};
+use mf_transform::derived::DerivedAttrStep;
+use mf_model::rpds::HashTrieMapSync;
+use serde_json::Value;
 use mf_transform::mark_step::{AddMarkStep, RemoveMarkStep};
 use mf_transform::node_step::{AddNodeStep, RemoveNodeStep, MoveNodeStep};
 use mf_model::node_definition::NodeTree;
@@ -144,6 +147,44 @@ impl TypedStepIndexer<AttrStep> for AttrIndexer {
     }
 }
 
+#[derive(Default)]
+struct BulkAttrIndexer;
+impl TypedStepIndexer<BulkAttrStep> for BulkAttrIndexer {
+    fn index_step(
+        &self,
+        step: &BulkAttrStep,
+        ctx: &StepIndexContext,
+    ) -> Vec<IndexMutation> {
+        bulk_attr_upserts(ctx.pool_after, &step.updates)
+    }
+}
+
+#[derive(Default)]
+struct DerivedAttrIndexer;
+impl TypedStepIndexer<DerivedAttrStep> for DerivedAttrIndexer {
+    fn index_step(
+        &self,
+        step: &DerivedAttrStep,
+        ctx: &StepIndexContext,
+    ) -> Vec<IndexMutation> {
+        bulk_attr_upserts(ctx.pool_after, &step.updates)
+    }
+}
+
+/// 批量属性变更：每个节点只 Upsert 一次
+pub(crate) fn bulk_attr_upserts(
+    pool: &NodePool,
+    updates: &[(NodeId, HashTrieMapSync<String, Value>)],
+) -> Vec<IndexMutation> {
+    let mut seen = std::collections::HashSet::new();
+    updates
+        .iter()
+        .filter(|(id, _)| seen.insert(id.clone()))
+        .filter_map(|(id, _)| pool.get_node(id))
+        .map(|node| IndexMutation::Upsert(IndexDoc::from_node(pool, node)))
+        .collect()
+}
+
 #[derive(Default)]
 struct AddMarkIndexer;
 impl TypedStepIndexer<AddMarkStep> for AddMarkIndexer {
@@ -300,6 +341,8 @@ static DEFAULTS: _OnceLock<()> = _OnceLock::new();
 pub fn ensure_default_step_indexers() {
     DEFAULTS.get_or_init(|| {
         register_step_indexer::<AttrStep, AttrIndexer>();
+        register_step_indexer::<BulkAttrStep, BulkAttrIndexer>();
+        register_step_indexer::<DerivedAttrStep, DerivedAttrIndexer>();
         register_step_indexer::<AddMarkStep, AddMarkIndexer>();
         register_step_indexer::<RemoveMarkStep, RemoveMarkIndexer>();
         register_step_indexer::<AddNodeStep, AddNodeIndexer>();
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use criterion::{criterion_group, criterion_main, Criterion};
+use mf_model::node_definition::NodeSpec;
+use mf_model::node_pool::NodePool;
+use mf_model::schema::{Schema, SchemaSpec};
+use mf_state::plugin::{
+    Plugin, PluginMetadata, PluginSpec, PluginTraitGeneric, StateFieldGeneric,
+};
+use mf_state::resource::Resource;
+use mf_state::state::{State, StateConfig, StateGeneric, StateConfigGeneric};
+use mf_state::transaction::TransactionGeneric;
+
+/// 模拟每个插件 apply 的耗时
+const WORK: Duration = Duration::from_millis(5);
+
+#[derive(Debug)]
+struct Counter(u64);
+impl Resource for Counter {}
+
+#[derive(Debug)]
+struct SlowField;
+
+#[async_trait]
+impl StateFieldGeneric<NodePool, Schema> for SlowField {
+    type Value = Counter;
+
+    async fn init(
+        &self,
+        _config: &StateConfigGeneric<NodePool, Schema>,
+        _instance: &StateGeneric<NodePool, Schema>,
+    ) -> Arc<Self::Value> {
+        Arc::new(Counter(0))
+    }
+
+    async fn apply(
+        &self,
+        _tr: &TransactionGeneric<NodePool, Schema>,
+        value: Arc<Self::Value>,
+        _old_state: &StateGeneric<NodePool, Schema>,
+        _new_state: &StateGeneric<NodePool, Schema>,
+    ) -> Arc<Self::Value> {
+        tokio::time::sleep(WORK).await;
+        Arc::new(Counter(value.0 + 1))
+    }
+}
+
+#[derive(Debug)]
+struct SlowPlugin(&'static str, &'static [&'static str]);
+
+impl PluginTraitGeneric<NodePool, Schema> for SlowPlugin {
+    fn metadata(&self) -> PluginMetadata {
+        PluginMetadata {
+            name: self.0.to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            author: String::new(),
+            dependencies: vec![],
+            conflicts: vec![],
+            state_fields: vec![],
+            tags: vec![],
+        }
+    }
+
+    fn state_dependencies(&self) -> Vec<String> {
+        self.1.iter().map(|dep| dep.to_string()).collect()
+    }
+}
+
+fn plugin(
+    name: &'static str,
+    state_dependencies: &'static [&'static str],
+) -> Arc<Plugin> {
+    Arc::new(Plugin::new(PluginSpec {
+        state_field: Some(Arc::new(SlowField)),
+        tr: Arc::new(SlowPlugin(name, state_dependencies)),
+    }))
+}
+
+/// 四个插件：a、b、c 相互独立，d 依赖 a，关键路径为两次 apply
+async fn create_state(sequential_apply: bool) -> Arc<State> {
+    let mut nodes = HashMap::new();
+    nodes.insert("doc".to_string(), NodeSpec::default());
+    let schema = Schema::compile(SchemaSpec {
+        nodes,
+        marks: HashMap::new(),
+        top_node: Some("doc".to_string()),
+    })
+    .unwrap();
+    let state = State::create(StateConfig {
+        schema: Some(Arc::new(schema)),
+        doc: None,
+        stored_marks: None,
+        plugins: Some(vec![
+            plugin("a", &[]),
+            plugin("b", &[]),
+            plugin("c", &[]),
+            plugin("d", &["a"]),
+        ]),
+        resource_manager: None,
+    })
+    .await
+    .unwrap();
+    Arc::new(state.with_sequential_apply(sequential_apply))
+}
+
+/// 插件状态 apply 基准测试：并发执行时耗时应接近关键路径（约 10ms），
+/// 顺序执行时为所有插件耗时之和（约 20ms）
+fn bench_plugin_apply(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("插件状态应用");
+    group.sample_size(20);
+
+    for (name, sequential_apply) in [("并发", false), ("顺序", true)] {
+        let state = rt.block_on(create_state(sequential_apply));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                rt.block_on(async {
+                    let result = state.apply(state.tr()).await.unwrap();
+                    criterion::black_box(result)
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_plugin_apply);
+criterion_main!(benches);
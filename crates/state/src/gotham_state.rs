@@ -1,13 +1,26 @@
 use std::any::Any;
 use std::any::TypeId;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use dashmap::DashMap;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::error::{error, StateResult};
+
+/// 将类型擦除的值序列化为 JSON
+type SerializeFn = fn(&(dyn Any + Send + Sync)) -> Option<Value>;
+
+/// 将 JSON 反序列化并以给定名称存入状态容器
+type DeserializeFn = fn(&GothamState, &'static str, Value) -> StateResult<()>;
 
 #[derive(Default, Debug)]
 pub struct GothamState {
     /// 使用BTreeMap存储不同类型的数据，以TypeId为键
     data: DashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+    /// 可序列化类型的 (注册名, 序列化函数)，以TypeId为键
+    serializers: DashMap<TypeId, (&'static str, SerializeFn)>,
 }
 
 impl GothamState {
@@ -34,6 +47,16 @@ impl GothamState {
         let type_id = TypeId::of::<T>();
         self.data.contains_key(&type_id)
     }
+
+    /// 已存储的数据条数
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub fn get<T: Clone + Send + Sync + 'static>(&self) -> Option<Arc<T>> {
         self.try_get::<T>()
     }
@@ -50,6 +73,7 @@ impl GothamState {
 
     pub fn try_take<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
         let type_id = TypeId::of::<T>();
+        self.serializers.remove(&type_id);
         match self.data.remove(&type_id) {
             Some((_, v)) => Arc::downcast(v).ok(),
             None => None,
@@ -65,4 +89,195 @@ impl GothamState {
     pub fn take<T: Send + Sync + 'static>(&mut self) -> Option<Arc<T>> {
         self.try_take::<T>()
     }
+
+    /// 存入可序列化的数据，可通过 `export_serializable` 导出
+    ///
+    /// `name` 是导出时使用的键，需与 `TypeRegistry::register` 中的名称一致，
+    /// 且在各类型间唯一；它不随类型路径或编译器版本变化，可以跨版本传递
+    pub fn put_serializable<T>(
+        &self,
+        name: &'static str,
+        t: T,
+    ) where
+        T: Clone + Serialize + Send + Sync + 'static,
+    {
+        let type_id = TypeId::of::<T>();
+        self.serializers.insert(type_id, (name, serialize_value::<T>));
+        self.put(t);
+    }
+
+    /// 导出所有可序列化的数据，键为存入时的名称
+    ///
+    /// 用于跨请求传递状态（如写入请求头或会话存储），序列化失败的数据会被跳过
+    pub fn export_serializable(&self) -> HashMap<String, Value> {
+        let mut map = HashMap::new();
+        for entry in self.serializers.iter() {
+            let (name, serialize) = *entry.value();
+            let Some(value) = self.data.get(entry.key()) else {
+                continue;
+            };
+            if let Some(json) = serialize(value.value().as_ref()) {
+                map.insert(name.to_string(), json);
+            }
+        }
+        map
+    }
+
+    /// 根据 `export_serializable` 导出的数据重建状态容器
+    ///
+    /// 名称必须在 `registry` 中注册，否则返回错误
+    pub fn import_serializable(
+        map: HashMap<String, Value>,
+        registry: &TypeRegistry,
+    ) -> StateResult<GothamState> {
+        let state = GothamState::default();
+        for (name, value) in map {
+            let (name, deserialize) = registry
+                .deserializers
+                .get_key_value(name.as_str())
+                .ok_or_else(|| {
+                    error::deserialize_error(format!("未注册的类型: {name}"))
+                })?;
+            deserialize(&state, name, value)?;
+        }
+        Ok(state)
+    }
+}
+
+/// 类型注册表
+///
+/// 记录注册名到反序列化函数的映射，供 `GothamState::import_serializable` 使用
+#[derive(Default, Debug, Clone)]
+pub struct TypeRegistry {
+    deserializers: HashMap<&'static str, DeserializeFn>,
+}
+
+impl TypeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 以稳定的名称注册可反序列化的类型，名称即导出数据中的键
+    pub fn register<T>(
+        &mut self,
+        name: &'static str,
+    ) -> &mut Self
+    where
+        T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        self.deserializers.insert(name, deserialize_value::<T>);
+        self
+    }
+
+    /// 检查名称是否已注册
+    pub fn contains(
+        &self,
+        name: &str,
+    ) -> bool {
+        self.deserializers.contains_key(name)
+    }
+}
+
+fn serialize_value<T: Serialize + 'static>(
+    value: &(dyn Any + Send + Sync)
+) -> Option<Value> {
+    value.downcast_ref::<T>().and_then(|v| serde_json::to_value(v).ok())
+}
+
+fn deserialize_value<T>(
+    state: &GothamState,
+    name: &'static str,
+    value: Value,
+) -> StateResult<()>
+where
+    T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    let t: T = serde_json::from_value(value)
+        .map_err(|e| error::deserialize_error(format!("{name}: {e}")))?;
+    state.put_serializable(name, t);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Session {
+        user: String,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Counter(u32);
+
+    #[derive(Debug, Clone)]
+    struct Plain;
+
+    const SESSION: &str = "session";
+    const COUNTER: &str = "counter";
+
+    fn registry() -> TypeRegistry {
+        let mut registry = TypeRegistry::new();
+        registry.register::<Session>(SESSION).register::<Counter>(COUNTER);
+        registry
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let state = GothamState::default();
+        state.put_serializable(SESSION, Session { user: "alice".to_string() });
+        state.put_serializable(COUNTER, Counter(3));
+        state.put(Plain);
+
+        // 只导出以 put_serializable 存入的数据
+        let exported = state.export_serializable();
+        assert_eq!(exported.len(), 2);
+        assert_eq!(exported[SESSION], json!({ "user": "alice" }));
+        assert_eq!(exported[COUNTER], json!(3));
+        assert!(registry().contains(SESSION));
+
+        let imported =
+            GothamState::import_serializable(exported.clone(), &registry())
+                .unwrap();
+        assert_eq!(
+            imported.get::<Session>().as_deref(),
+            Some(&Session { user: "alice".to_string() })
+        );
+        assert_eq!(imported.get::<Counter>().as_deref(), Some(&Counter(3)));
+        assert!(!imported.has::<Plain>());
+        assert_eq!(imported.export_serializable(), exported);
+    }
+
+    #[test]
+    fn test_taken_values_are_not_exported() {
+        let state = GothamState::default();
+        state.put_serializable(COUNTER, Counter(1));
+        assert!(state.try_take::<Counter>().is_some());
+        assert!(state.export_serializable().is_empty());
+    }
+
+    #[test]
+    fn test_import_rejects_unknown_or_invalid() {
+        let unknown = HashMap::from([("Unknown".to_string(), json!(1))]);
+        assert!(
+            GothamState::import_serializable(unknown, &registry()).is_err()
+        );
+
+        // 类型路径不是注册名
+        let by_type = HashMap::from([(
+            std::any::type_name::<Counter>().to_string(),
+            json!(1),
+        )]);
+        assert!(
+            GothamState::import_serializable(by_type, &registry()).is_err()
+        );
+
+        let invalid =
+            HashMap::from([(COUNTER.to_string(), json!("not a number"))]);
+        assert!(
+            GothamState::import_serializable(invalid, &registry()).is_err()
+        );
+    }
 }
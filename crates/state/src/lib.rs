@@ -32,6 +32,8 @@ pub mod resource;
 pub mod resource_table;
 pub mod state;
 pub mod transaction;
-pub use state::{State, StateConfig, Configuration};
-pub use transaction::Transaction;
+pub use state::{State, StateConfig, StateView, Configuration, DivergenceToken};
+pub use transaction::{
+    CommandContext, CommandProgress, Transaction, TransactionOrigin,
+};
 pub use tracing::{info, debug, warn, error};
@@ -1,4 +1,5 @@
 use std::ops::{Deref, DerefMut};
+use std::time::Duration;
 
 use crate::{gotham_state::GothamState, resource_table::ResourceTable};
 
@@ -43,3 +44,23 @@ impl DerefMut for GlobalResourceManager {
         &mut self.gotham_state
     }
 }
+
+/// 操作函数的执行错误
+///
+/// 仅对声明了超时的操作生效，携带操作名称以便定位
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum OpError {
+    #[error("操作 {op} 执行超时 ({timeout:?})")]
+    Timeout { op: String, timeout: Duration },
+    #[error("操作 {op} 发生 panic: {message}")]
+    Panicked { op: String, message: String },
+}
+
+impl OpError {
+    /// 出错的操作名称
+    pub fn op(&self) -> &str {
+        match self {
+            OpError::Timeout { op, .. } | OpError::Panicked { op, .. } => op,
+        }
+    }
+}
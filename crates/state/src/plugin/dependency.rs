@@ -220,6 +220,36 @@ impl DependencyManager {
         Ok(result)
     }
 
+    /// 按依赖深度分层
+    ///
+    /// 第 0 层不依赖任何插件，第 n 层只依赖前 n 层中的插件，
+    /// 同一层的插件之间没有依赖关系；每层内部按名称排序
+    pub fn get_levels(&self) -> Result<Vec<Vec<String>>> {
+        let order = self.get_topological_order()?;
+        let mut depth: HashMap<String, usize> = HashMap::new();
+        // 拓扑序中依赖方在前，逆序遍历保证依赖先被计算
+        for name in order.iter().rev() {
+            let idx = self.node_indices[name];
+            let level = self
+                .dependency_graph
+                .neighbors(idx)
+                .map(|dep| depth[&self.dependency_graph[dep]] + 1)
+                .max()
+                .unwrap_or(0);
+            depth.insert(name.clone(), level);
+        }
+
+        let mut levels: Vec<Vec<String>> =
+            vec![Vec::new(); depth.values().max().map_or(0, |d| d + 1)];
+        for (name, level) in depth {
+            levels[level].push(name);
+        }
+        for level in &mut levels {
+            level.sort();
+        }
+        Ok(levels)
+    }
+
     /// 获取插件的直接依赖
     pub fn get_direct_dependencies(
         &self,
@@ -9,6 +9,9 @@ use mf_model::traits::{DataContainer, SchemaDefinition};
 use mf_model::node_pool::NodePool;
 use mf_model::schema::Schema;
 
+/// 按状态依赖分层的插件列表
+pub type PluginLevels<C, S> = Vec<Vec<Arc<PluginGeneric<C, S>>>>;
+
 /// 插件管理器 - 初始化后不可变 (泛型版本)
 ///
 /// # 设计理念
@@ -51,6 +54,8 @@ where
     plugins: Arc<HashMap<String, Arc<PluginGeneric<C, S>>>>,
     /// 排序后的插件列表（初始化后不可变，按依赖顺序）
     sorted_plugins: Arc<Vec<Arc<PluginGeneric<C, S>>>>,
+    /// 按状态依赖分层的带状态插件，同一层内的插件可以并发执行 apply
+    state_levels: Arc<PluginLevels<C, S>>,
     /// 初始化状态标记（使用原子操作，无锁）
     initialized: Arc<AtomicBool>,
 }
@@ -182,6 +187,9 @@ where
             .filter_map(|name| self.plugins.get(name).cloned())
             .collect();
 
+        // 6. 按状态依赖分层
+        let state_levels = Self::build_state_levels(&self.plugins)?;
+
         tracing::info!(
             "插件管理器构建完成，共注册 {} 个插件",
             self.plugins.len()
@@ -190,9 +198,54 @@ where
         Ok(PluginManagerGeneric {
             plugins: Arc::new(self.plugins),
             sorted_plugins: Arc::new(sorted_plugins),
+            state_levels: Arc::new(state_levels),
             initialized: Arc::new(AtomicBool::new(true)),
         })
     }
+
+    /// 根据 `state_dependencies` 计算带状态插件的执行层级
+    fn build_state_levels(
+        plugins: &HashMap<String, Arc<PluginGeneric<C, S>>>
+    ) -> Result<PluginLevels<C, S>> {
+        let mut dependency_manager = DependencyManager::new();
+        let mut missing = Vec::new();
+        for (name, plugin) in plugins {
+            dependency_manager.add_plugin(name);
+            for dep in &plugin.state_dependencies() {
+                if !plugins.contains_key(dep) {
+                    missing.push(format!("{name} -> {dep}"));
+                }
+                dependency_manager.add_dependency(name, dep)?;
+            }
+        }
+        if !missing.is_empty() {
+            missing.sort();
+            return Err(anyhow::anyhow!(
+                "检测到缺失的插件状态依赖: {}",
+                missing.join(", ")
+            ));
+        }
+
+        if dependency_manager.has_circular_dependencies() {
+            let report = dependency_manager.get_circular_dependency_report();
+            return Err(anyhow::anyhow!("检测到插件状态循环依赖: {report}"));
+        }
+
+        let levels = dependency_manager
+            .get_levels()?
+            .into_iter()
+            .map(|level| {
+                level
+                    .iter()
+                    .filter_map(|name| plugins.get(name))
+                    .filter(|plugin| plugin.spec.state_field.is_some())
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .filter(|level| !level.is_empty())
+            .collect();
+        Ok(levels)
+    }
 }
 
 impl<C, S> Default for PluginManagerBuilderGeneric<C, S>
@@ -215,6 +268,7 @@ where
         Self {
             plugins: Arc::new(HashMap::new()),
             sorted_plugins: Arc::new(Vec::new()),
+            state_levels: Arc::new(Vec::new()),
             initialized: Arc::new(AtomicBool::new(true)),
         }
     }
@@ -252,6 +306,14 @@ where
         self.sorted_plugins.as_ref()
     }
 
+    /// 获取按状态依赖分层的带状态插件
+    ///
+    /// 每一层只依赖之前各层的插件状态，同一层内的插件相互独立。
+    #[inline]
+    pub fn get_state_levels(&self) -> &[Vec<Arc<PluginGeneric<C, S>>>] {
+        self.state_levels.as_ref()
+    }
+
     /// 检查初始化状态（异步接口，兼容现有代码）
     ///
     /// 使用原子操作，无锁开销 (~5ns)。
@@ -4,6 +4,7 @@ pub mod dependency;
 pub mod manager;
 #[allow(clippy::module_inception)]
 pub mod plugin;
+pub mod timing;
 
 pub use plugin::*;
 pub use dependency::DependencyManager;
@@ -30,3 +31,32 @@ pub struct PluginConfig {
     pub priority: i32, //插件优先级
     pub settings: std::collections::HashMap<String, serde_json::Value>, //插件配置
 }
+
+/// 插件能力
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PluginCapability {
+    FilterTransaction, //过滤事务
+    AppendTransaction, //追加事务
+    ManagesState,      //管理插件状态
+    ObservesHistory,   //观察历史记录
+}
+
+/// 插件描述
+/// 机器可读的插件能力描述，供工具在创建状态前校验插件与结构定义是否兼容
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PluginDescriptor {
+    pub name: String,                        //插件名称
+    pub version: String,                     //插件版本
+    pub capabilities: Vec<PluginCapability>, //插件能力
+    pub required_schema_nodes: Vec<String>,  //插件依赖的节点类型
+}
+
+impl PluginDescriptor {
+    /// 是否具备指定能力
+    pub fn has_capability(
+        &self,
+        capability: PluginCapability,
+    ) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
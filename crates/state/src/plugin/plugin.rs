@@ -2,7 +2,8 @@ use async_trait::async_trait;
 use std::sync::Arc;
 
 use crate::error::StateResult;
-use crate::plugin::{PluginConfig, PluginMetadata};
+use crate::plugin::timing::{self, PluginTimer};
+use crate::plugin::{PluginConfig, PluginDescriptor, PluginMetadata};
 use crate::resource::Resource;
 
 use crate::state::{StateGeneric, StateConfigGeneric};
@@ -31,6 +32,18 @@ where
         }
     }
 
+    /// 获取插件能力描述 - 默认返回空描述
+    fn describe(&self) -> PluginDescriptor {
+        PluginDescriptor::default()
+    }
+
+    /// 获取插件状态依赖的插件名称 - 默认无依赖
+    /// 这些插件的新状态计算完成后才会计算本插件的状态，
+    /// 没有依赖关系的插件会并发执行 apply
+    fn state_dependencies(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     /// 追加事务处理
     /// 允许插件在事务执行前修改或扩展事务内容
     async fn append_transaction(
@@ -51,6 +64,23 @@ where
     ) -> bool {
         true
     }
+
+    /// 状态创建完成（所有插件状态已初始化）后调用
+    /// 返回错误时 `State::create` 失败
+    async fn on_state_created(
+        &self,
+        _: &StateConfigGeneric<C, S>,
+        _: Option<&StateGeneric<C, S>>,
+    ) -> StateResult<()> {
+        Ok(())
+    }
+
+    /// 状态销毁时调用，见 [`StateGeneric::destroy`]
+    async fn on_state_destroyed(
+        &self,
+        _: &StateGeneric<C, S>,
+    ) {
+    }
 }
 
 /// 向后兼容的类型别名
@@ -257,6 +287,7 @@ where
 {
     pub spec: PluginSpecGeneric<C, S>,
     pub key: String,
+    timer: Arc<PluginTimer>,
 }
 
 impl<C, S> PluginGeneric<C, S>
@@ -267,7 +298,13 @@ where
     /// 创建新的插件实例
     pub fn new(spec: PluginSpecGeneric<C, S>) -> Self {
         let key = spec.tr.metadata().name.clone();
-        PluginGeneric { spec, key }
+        let timer = timing::timer(&key);
+        PluginGeneric { spec, key, timer }
+    }
+
+    /// 获取插件的耗时记录器
+    pub fn timer(&self) -> &PluginTimer {
+        &self.timer
     }
 
     /// 获取插件名称
@@ -285,6 +322,16 @@ where
         self.spec.tr.config()
     }
 
+    /// 获取插件能力描述
+    pub fn describe(&self) -> PluginDescriptor {
+        self.spec.tr.describe()
+    }
+
+    /// 获取插件状态依赖的插件名称
+    pub fn state_dependencies(&self) -> Vec<String> {
+        self.spec.tr.state_dependencies()
+    }
+
     /// 从全局状态中获取插件状态
     pub fn get_state(
         &self,
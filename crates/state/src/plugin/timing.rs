@@ -0,0 +1,255 @@
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, OnceLock};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use metrics::{counter, histogram, Counter, Histogram};
+
+/// 插件执行耗时（秒），标签 `plugin_key`、`phase`
+pub const PLUGIN_EXECUTION_DURATION_SECONDS: &str =
+    "state.plugin.execution.duration.seconds";
+/// 插件执行次数，标签 `plugin_key`、`phase`
+pub const PLUGIN_EXECUTIONS_TOTAL: &str = "state.plugin.executions.total";
+
+/// 插件在事务处理中的执行阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluginPhase {
+    /// `filter_transaction`
+    FilterTransaction,
+    /// `append_transaction`
+    AppendTransaction,
+    /// 状态字段 `apply`
+    StateApply,
+}
+
+impl PluginPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PluginPhase::FilterTransaction => "filter_transaction",
+            PluginPhase::AppendTransaction => "append_transaction",
+            PluginPhase::StateApply => "state_apply",
+        }
+    }
+}
+
+/// 单个阶段的累计耗时与调用次数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PluginTiming {
+    pub calls: u64,
+    pub total: Duration,
+}
+
+/// 单个插件各阶段的累计耗时
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PluginTimings {
+    pub filter_transaction: PluginTiming,
+    pub append_transaction: PluginTiming,
+    pub state_apply: PluginTiming,
+}
+
+impl PluginTimings {
+    pub fn get(
+        &self,
+        phase: PluginPhase,
+    ) -> PluginTiming {
+        match phase {
+            PluginPhase::FilterTransaction => self.filter_transaction,
+            PluginPhase::AppendTransaction => self.append_transaction,
+            PluginPhase::StateApply => self.state_apply,
+        }
+    }
+
+    /// 所有阶段合计
+    pub fn total(&self) -> PluginTiming {
+        let mut total = PluginTiming::default();
+        for timing in
+            [self.filter_transaction, self.append_transaction, self.state_apply]
+        {
+            total.calls += timing.calls;
+            total.total += timing.total;
+        }
+        total
+    }
+}
+
+/// 单个阶段的累计值及指标句柄
+#[derive(Default)]
+struct PhaseTimer {
+    calls: AtomicU64,
+    nanos: AtomicU64,
+    handles: OnceLock<(Counter, Histogram)>,
+}
+
+impl PhaseTimer {
+    fn timing(&self) -> PluginTiming {
+        PluginTiming {
+            calls: self.calls.load(Ordering::Relaxed),
+            total: Duration::from_nanos(self.nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// 插件的耗时记录器
+///
+/// 创建插件时按 key 注册一次，之后的记录只更新原子计数，
+/// 指标句柄在首次记录时注册并缓存。
+pub struct PluginTimer {
+    key: String,
+    phases: [PhaseTimer; 3],
+}
+
+impl std::fmt::Debug for PluginTimer {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.debug_struct("PluginTimer")
+            .field("key", &self.key)
+            .field("timings", &self.timings())
+            .finish()
+    }
+}
+
+impl PluginTimer {
+    fn new(key: &str) -> Self {
+        PluginTimer { key: key.to_string(), phases: Default::default() }
+    }
+
+    /// 记录一次执行，同时上报到 `metrics`
+    pub fn record(
+        &self,
+        phase: PluginPhase,
+        elapsed: Duration,
+    ) {
+        let timer = &self.phases[phase as usize];
+        timer.calls.fetch_add(1, Ordering::Relaxed);
+        timer.nanos.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        let (calls, duration) = timer.handles.get_or_init(|| {
+            let labels = [
+                ("plugin_key", self.key.clone()),
+                ("phase", phase.as_str().to_string()),
+            ];
+            (
+                counter!(PLUGIN_EXECUTIONS_TOTAL, &labels),
+                histogram!(PLUGIN_EXECUTION_DURATION_SECONDS, &labels),
+            )
+        });
+        calls.increment(1);
+        duration.record(elapsed.as_secs_f64());
+    }
+
+    /// 执行 future 并记录耗时
+    ///
+    /// 只累计 future 自身被 poll 的时间，与其他 future 一起
+    /// `join_all` 时不会把等待其他插件的时间算进来。
+    pub async fn time<F: Future>(
+        &self,
+        phase: PluginPhase,
+        future: F,
+    ) -> F::Output {
+        let mut future = pin!(future);
+        let mut elapsed = Duration::ZERO;
+        let output = poll_fn(|cx| {
+            let start = Instant::now();
+            let poll = future.as_mut().poll(cx);
+            elapsed += start.elapsed();
+            poll
+        })
+        .await;
+        self.record(phase, elapsed);
+        output
+    }
+
+    /// 当前累计耗时
+    pub fn timings(&self) -> PluginTimings {
+        let [filter_transaction, append_transaction, state_apply] =
+            &self.phases;
+        PluginTimings {
+            filter_transaction: filter_transaction.timing(),
+            append_transaction: append_transaction.timing(),
+            state_apply: state_apply.timing(),
+        }
+    }
+
+    fn reset(&self) {
+        for timer in &self.phases {
+            timer.calls.store(0, Ordering::Relaxed);
+            timer.nanos.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 进程级注册表，同 key 的插件共用一个记录器
+static TIMERS: LazyLock<DashMap<String, Arc<PluginTimer>>> =
+    LazyLock::new(DashMap::new);
+
+/// 获取（必要时注册）插件的耗时记录器
+pub fn timer(plugin_key: &str) -> Arc<PluginTimer> {
+    if let Some(timer) = TIMERS.get(plugin_key) {
+        return timer.clone();
+    }
+    TIMERS
+        .entry(plugin_key.to_string())
+        .or_insert_with(|| Arc::new(PluginTimer::new(plugin_key)))
+        .clone()
+}
+
+/// 指定插件的累计耗时，未执行过时返回 `None`
+pub fn plugin_timing(plugin_key: &str) -> Option<PluginTimings> {
+    TIMERS
+        .get(plugin_key)
+        .map(|timer| timer.timings())
+        .filter(|timings| timings.total().calls > 0)
+}
+
+/// 所有插件的累计耗时（按 key 排序）
+pub fn plugin_timings() -> Vec<(String, PluginTimings)> {
+    let mut timings: Vec<_> = TIMERS
+        .iter()
+        .map(|entry| (entry.key().clone(), entry.value().timings()))
+        .filter(|(_, timings)| timings.total().calls > 0)
+        .collect();
+    timings.sort_by(|a, b| a.0.cmp(&b.0));
+    timings
+}
+
+/// 清空累计值，已注册的记录器保持有效
+pub fn reset_plugin_timings() {
+    for entry in TIMERS.iter() {
+        entry.value().reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 先让出一次再阻塞，模拟同一层中互相交错的插件
+    async fn busy(duration: Duration) {
+        tokio::task::yield_now().await;
+        std::thread::sleep(duration);
+    }
+
+    #[tokio::test]
+    async fn test_join_all_measures_each_future() {
+        let slow = timer("timing_test_slow");
+        let other = timer("timing_test_other");
+        let phase = PluginPhase::StateApply;
+        futures::future::join_all([
+            slow.time(phase, busy(Duration::from_millis(30))),
+            other.time(phase, busy(Duration::from_millis(30))),
+        ])
+        .await;
+
+        // 按墙钟计时时后完成的一方会把另一方的 30ms 也算进来
+        for timer in [&slow, &other] {
+            let timing = timer.timings().get(phase);
+            assert_eq!(timing.calls, 1);
+            assert!(timing.total >= Duration::from_millis(30));
+            assert!(timing.total < Duration::from_millis(60), "{timing:?}");
+        }
+        assert!(Arc::ptr_eq(&slow, &timer("timing_test_slow")));
+    }
+}
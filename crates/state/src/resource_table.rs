@@ -1,3 +1,4 @@
+use std::any::{Any, TypeId};
 use std::sync::Arc;
 use std::fmt::{self, Debug};
 
@@ -13,6 +14,8 @@ pub type ResourceId = String;
 pub struct ResourceTable {
     // 使用BTreeMap存储资源ID到资源的映射
     index: DashMap<ResourceId, Arc<dyn Resource>>,
+    // 按资源类型分组的资源ID，用于按类型遍历
+    type_index: DashMap<TypeId, Vec<ResourceId>>,
 }
 impl Debug for ResourceTable {
     fn fmt(
@@ -58,7 +61,11 @@ impl ResourceTable {
         rid: ResourceId,
         resource: Arc<dyn Resource>,
     ) {
-        self.index.insert(rid, resource);
+        let type_id = Any::type_id(resource.as_ref());
+        if let Some(old) = self.index.insert(rid.clone(), resource) {
+            self.unindex(Any::type_id(old.as_ref()), &rid);
+        }
+        self.type_index.entry(type_id).or_default().push(rid);
     }
 
     // 检查指定ID的资源是否存在
@@ -93,7 +100,7 @@ impl ResourceTable {
         &self,
         rid: ResourceId,
     ) -> Option<Arc<T>> {
-        let (_, resource) = self.index.remove(&rid)?;
+        let resource = self.take_any(rid)?;
         resource.downcast_arc::<T>().cloned()
     }
 
@@ -102,7 +109,39 @@ impl ResourceTable {
         &self,
         rid: ResourceId,
     ) -> Option<Arc<dyn Resource>> {
-        self.index.remove(&rid).map(|rc| rc.1)
+        let (rid, resource) = self.index.remove(&rid)?;
+        self.unindex(Any::type_id(resource.as_ref()), &rid);
+        Some(resource)
+    }
+
+    // 遍历指定类型的全部资源，只访问该类型的条目
+    pub fn iter_typed<T: Resource>(
+        &self
+    ) -> impl Iterator<Item = (ResourceId, Arc<T>)> {
+        let rids = self
+            .type_index
+            .get(&TypeId::of::<T>())
+            .map(|rids| rids.value().clone())
+            .unwrap_or_default();
+        rids.into_iter().filter_map(|rid| {
+            let resource = self.get::<T>(rid.clone())?;
+            Some((rid, resource))
+        })
+    }
+
+    // 从类型索引中移除资源ID
+    fn unindex(
+        &self,
+        type_id: TypeId,
+        rid: &ResourceId,
+    ) {
+        if let Some(mut rids) = self.type_index.get_mut(&type_id) {
+            rids.retain(|id| id != rid);
+            if rids.is_empty() {
+                drop(rids);
+                self.type_index.remove_if(&type_id, |_, rids| rids.is_empty());
+            }
+        }
     }
 }
 
@@ -118,3 +157,61 @@ pub enum ResourceError {
     #[error("{0}")]
     Other(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Counter(u32);
+    impl Resource for Counter {}
+
+    #[derive(Debug)]
+    struct Label;
+    impl Resource for Label {}
+
+    fn typed_ids<T: Resource>(table: &ResourceTable) -> Vec<ResourceId> {
+        let mut ids: Vec<_> =
+            table.iter_typed::<T>().map(|(id, _)| id).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_iter_typed() {
+        let table = ResourceTable::default();
+        table.add("a".to_string(), Counter(1));
+        table.add("b".to_string(), Counter(2));
+        table.add("label".to_string(), Label);
+
+        let mut counters: Vec<_> =
+            table.iter_typed::<Counter>().map(|(id, c)| (id, c.0)).collect();
+        counters.sort();
+        assert_eq!(counters, [("a".to_string(), 1), ("b".to_string(), 2)]);
+        assert_eq!(typed_ids::<Label>(&table), ["label"]);
+    }
+
+    #[test]
+    fn test_type_index_follows_take_and_replace() {
+        let table = ResourceTable::default();
+        table.add("a".to_string(), Counter(1));
+        table.add("b".to_string(), Counter(2));
+
+        assert_eq!(
+            table.take::<Counter>("a".to_string()),
+            Some(Arc::new(Counter(1)))
+        );
+        assert_eq!(typed_ids::<Counter>(&table), ["b"]);
+
+        // 同一 ID 换成其他类型的资源后，只出现在新类型的索引中
+        table.add("b".to_string(), Label);
+        assert!(typed_ids::<Counter>(&table).is_empty());
+        assert_eq!(typed_ids::<Label>(&table), ["b"]);
+        assert!(!table.type_index.contains_key(&TypeId::of::<Counter>()));
+
+        assert!(table.take_any("b".to_string()).is_some());
+        assert!(typed_ids::<Label>(&table).is_empty());
+        assert!(table.type_index.is_empty());
+        assert!(table.is_empty());
+    }
+}
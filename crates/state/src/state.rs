@@ -1,13 +1,19 @@
 use mf_model::{
+    attrs::Attrs,
+    content::ContentMatch,
     id_generator::IdGenerator,
     mark::Mark,
+    node::Node,
+    node_definition::NodeTree,
     node_pool::NodePool,
-    schema::Schema,
+    schema::{Attribute, Schema},
     traits::{DataContainer, SchemaDefinition},
 };
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fmt::{self, Debug};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc,
@@ -20,20 +26,27 @@ use crate::{ops::GlobalResourceManager, resource::Resource};
 
 use super::{
     error::{error, StateResult},
-    plugin::{PluginGeneric},
-    transaction::{Transaction, TransactionGeneric},
+    plugin::{timing::PluginPhase, PluginGeneric},
+    transaction::{
+        Transaction, TransactionGeneric, TransactionOrigin, DERIVED_META,
+        ORIGIN_META,
+    },
 };
+use mf_transform::derived;
 
 static VERSION: AtomicU64 = AtomicU64::new(1);
 pub fn get_state_version() -> u64 {
     //生成 全局自增的版本号，用于兼容性
     VERSION.fetch_add(1, Ordering::SeqCst)
 }
+
+static FORK_ID: AtomicU64 = AtomicU64::new(1);
 /// State 结构体代表编辑器的整体状态 (泛型版本)
 /// - 配置信息: 存储编辑器的配置信息
 /// - 字段实例: 存储插件的状态数据
 /// - 节点池: 文档的节点池
 /// - 版本号: 状态版本号，用于追踪变更
+/// - 分叉谱系: 状态所在的分叉（由 `clone_with_diverge` 产生），由后续状态继承
 #[derive(Clone)]
 pub struct StateGeneric<C, S>
 where
@@ -44,6 +57,7 @@ where
     pub fields_instances: Arc<HashTrieMapSync<String, Arc<dyn Resource>>>,
     pub node_pool: Arc<C>,
     pub version: u64,
+    lineage: Arc<[u64]>,
 }
 
 impl<C, S> Debug for StateGeneric<C, S>
@@ -80,6 +94,7 @@ where
             config,
             node_pool: doc,
             version: get_state_version(),
+            lineage: Arc::from([]),
         })
     }
 
@@ -97,6 +112,24 @@ where
         Arc::clone(&self.config.schema)
     }
 
+    /// 是否按顺序逐个执行插件状态的 apply
+    pub fn sequential_apply(&self) -> bool {
+        self.config.sequential_apply
+    }
+
+    /// 返回切换插件 apply 执行方式的副本
+    ///
+    /// 为 true 时按顺序逐个执行（调试用），默认并发执行相互独立的插件；
+    /// 之后由该状态派生的状态沿用此设置
+    pub fn with_sequential_apply(
+        &self,
+        sequential: bool,
+    ) -> Self {
+        let mut config = self.config.as_ref().clone();
+        config.sequential_apply = sequential;
+        Self { config: Arc::new(config), ..self.clone() }
+    }
+
     /// 获取插件列表
     pub async fn plugins(&self) -> Vec<Arc<PluginGeneric<C, S>>> {
         self.config.plugin_manager.get_sorted_plugins().await
@@ -109,6 +142,13 @@ where
         self.config.plugin_manager.get_sorted_plugins().await
     }
 
+    /// 销毁状态：按插件执行顺序的逆序调用 `on_state_destroyed`
+    pub async fn destroy(&self) {
+        for plugin in self.sorted_plugins().await.iter().rev() {
+            plugin.spec.tr.on_state_destroyed(self).await;
+        }
+    }
+
     /// 获取字段值
     pub fn get_field(
         &self,
@@ -128,6 +168,16 @@ where
             .and_then(|state| state.downcast_arc::<T>().cloned())
     }
 
+    /// 按插件 key 读取插件状态的引用，插件不存在、没有状态字段或类型不匹配时返回 None
+    ///
+    /// 与 [`Self::get`] 相同，但不克隆 `Arc`
+    pub fn plugin_state<T: Resource>(
+        &self,
+        key: &str,
+    ) -> Option<&T> {
+        self.fields_instances.get(key).and_then(|state| state.downcast::<T>())
+    }
+
     /// 检查字段是否存在
     pub fn has_field(
         &self,
@@ -136,6 +186,38 @@ where
         self.fields_instances.contains_key(name)
     }
 
+    /// 创建一个用于预览/试算的分叉副本
+    ///
+    /// 副本与当前状态共享不可变的文档节点与插件状态（仅克隆 `Arc`），
+    /// 之后可以在副本上任意应用事务而不影响当前状态。
+    /// 返回的 `DivergenceToken` 记录分叉点，用于合并回父状态或放弃。
+    pub fn clone_with_diverge(&self) -> (Self, DivergenceToken) {
+        let fork_id = FORK_ID.fetch_add(1, Ordering::Relaxed);
+        let mut fork = self.clone();
+        fork.lineage = self.lineage.iter().copied().chain([fork_id]).collect();
+        let token = DivergenceToken {
+            base_version: self.version,
+            fork_id,
+            base_lineage: self.lineage.clone(),
+        };
+        (fork, token)
+    }
+
+    /// 捕获当前状态的只读快照，供报表等长时间读取使用
+    ///
+    /// 快照只克隆文档与插件状态的 `Arc`，内部的持久化结构（rpds）与活动状态共享，
+    /// 之后的编辑会产生新的结构而不会影响快照。
+    /// 快照存活期间，被后续编辑替换掉的节点与插件状态无法释放，
+    /// 长期持有快照会随编辑量增加内存占用，用完应及时丢弃。
+    pub fn read_snapshot(&self) -> StateViewGeneric<C, S> {
+        StateViewGeneric {
+            schema: self.schema(),
+            fields_instances: Arc::clone(&self.fields_instances),
+            node_pool: Arc::clone(&self.node_pool),
+            version: self.version,
+        }
+    }
+
     /// 创建新的事务 (泛型版本)
     #[must_use]
     pub fn tr_generic(&self) -> TransactionGeneric<C, S> {
@@ -153,15 +235,17 @@ where
         state_config: StateConfigGeneric<C, S>,
     ) -> StateResult<Arc<StateGeneric<C, S>>> {
         tracing::info!("正在重新配置状态");
-        let config = ConfigurationGeneric::new(
+        let mut config = ConfigurationGeneric::new(
             self.schema(),
             state_config.plugins.clone(),
             state_config.doc.clone(),
             state_config.resource_manager.clone(),
         )
         .await?;
+        config.sequential_apply = self.config.sequential_apply;
         let mut instance =
             Self::new_generic(Arc::new(config), self.node_pool.clone())?;
+        instance.lineage = self.lineage.clone();
         let mut field_values = Vec::new();
         let mut fields_instances = HashTrieMapSync::new_sync();
         for plugin in instance.config.plugin_manager.get_sorted_plugins().await
@@ -227,9 +311,17 @@ where
         let sorted_plugins = self.sorted_plugins().await;
 
         for (i, plugin) in sorted_plugins.iter().enumerate() {
-            if Some(i) != ignore
-                && !plugin.apply_filter_transaction(tr, self).await
-            {
+            if Some(i) == ignore {
+                continue;
+            }
+            let allowed = plugin
+                .timer()
+                .time(
+                    PluginPhase::FilterTransaction,
+                    plugin.apply_filter_transaction(tr, self),
+                )
+                .await;
+            if !allowed {
                 return Ok(false);
             }
         }
@@ -259,6 +351,9 @@ where
         let mut trs = Vec::new();
         let mut new_state: Arc<StateGeneric<C, S>> =
             self.apply_inner_generic(&root_tr).await?;
+        // 追加事务的触发事务：沿调用链指向最初的事务
+        let trigger = root_tr.caused_by().unwrap_or(root_tr.id);
+        let actor = root_tr.actor().map(str::to_string);
         trs.push(root_tr.clone());
         let mut seen: Option<Vec<SeenStateGeneric<C, S>>> = None;
 
@@ -269,11 +364,26 @@ where
             let mut have_new = false;
             for (i, plugin) in sorted_plugins.iter().enumerate() {
                 let n: usize = seen.as_ref().map(|s| s[i].n).unwrap_or(0);
-                if let Some(appended) = plugin
-                    .append_transaction(self, &new_state, &trs[n..], n)
-                    .await
-                {
+                let appended = plugin
+                    .timer()
+                    .time(
+                        PluginPhase::AppendTransaction,
+                        plugin.append_transaction(
+                            self,
+                            &new_state,
+                            &trs[n..],
+                            n,
+                        ),
+                    )
+                    .await;
+                if let Some(mut appended) = appended {
                     have_new = true;
+                    Self::tag_appended(
+                        &mut appended,
+                        &plugin.key,
+                        trigger,
+                        actor.as_deref(),
+                    );
                     if let Some(ref mut s) = seen {
                         s[i].n = trs.len();
                         s[i].state = new_state.clone();
@@ -317,6 +427,31 @@ where
         }
     }
 
+    /// 为插件追加的事务标记来源与 actor，插件已自行设置的来源、触发事务与 actor 保持不变
+    fn tag_appended(
+        tr: &mut Arc<TransactionGeneric<C, S>>,
+        plugin_key: &str,
+        trigger: u64,
+        actor: Option<&str>,
+    ) {
+        let has_origin = tr.meta.contains_key(ORIGIN_META);
+        let has_trigger = tr.caused_by().is_some();
+        let has_actor = actor.is_none() || tr.actor().is_some();
+        if has_origin && has_trigger && has_actor {
+            return;
+        }
+        let tr = Arc::make_mut(tr);
+        if !has_origin {
+            tr.set_origin(TransactionOrigin::Plugin(plugin_key.to_string()));
+        }
+        if !has_trigger {
+            tr.set_caused_by(trigger);
+        }
+        if !has_actor && let Some(actor) = actor {
+            tr.set_actor(actor);
+        }
+    }
+
     /// 异步应用内部事务 (泛型版本)
     #[cfg_attr(feature = "dev-tracing", tracing::instrument(skip(self, tr), fields(
         crate_name = "state",
@@ -332,18 +467,45 @@ where
         let new_doc = tr.doc();
         config.doc = Some(new_doc.clone());
         let mut new_instance = Self::new_generic(Arc::new(config), new_doc)?;
+        new_instance.lineage = self.lineage.clone();
         let mut fields_instances = HashTrieMapSync::new_sync();
-        // 获取已排序的插件列表
-        let sorted_plugins = self.sorted_plugins().await;
+        let sequential = self.config.sequential_apply;
 
-        for plugin in sorted_plugins.iter() {
-            if let Some(field) = &plugin.spec.state_field {
-                if let Some(old_plugin_state) = self.get_field(&plugin.key) {
-                    let value = field
-                        .apply_erased(tr, old_plugin_state, self, &new_instance)
+        // 按状态依赖分层执行，同一层的插件看到相同的新状态快照
+        // （只包含之前各层的结果），因此结果与调度顺序无关
+        for level in self.config.plugin_manager.get_state_levels() {
+            new_instance.fields_instances = Arc::new(fields_instances.clone());
+            let snapshot = &new_instance;
+            let tasks = level.iter().filter_map(|plugin| {
+                let field = plugin.spec.state_field.as_ref()?;
+                let old_plugin_state = self.get_field(&plugin.key)?;
+                Some(async move {
+                    let value = plugin
+                        .timer()
+                        .time(
+                            PluginPhase::StateApply,
+                            field.apply_erased(
+                                tr,
+                                old_plugin_state,
+                                self,
+                                snapshot,
+                            ),
+                        )
                         .await;
-                    fields_instances.insert_mut(plugin.key.clone(), value);
+                    (plugin.key.clone(), value)
+                })
+            });
+            let values = if sequential {
+                let mut values = Vec::new();
+                for task in tasks {
+                    values.push(task.await);
                 }
+                values
+            } else {
+                futures::future::join_all(tasks).await
+            };
+            for (key, value) in values {
+                fields_instances.insert_mut(key, value);
             }
         }
         new_instance.fields_instances = Arc::new(fields_instances);
@@ -423,6 +585,177 @@ where
     }
 }
 
+/// 分叉令牌，由 `clone_with_diverge` 返回
+///
+/// 令牌记录分叉点与分叉标识，不持有分叉出的状态：在副本上应用事务会产生新的状态实例，
+/// 合并时需要把最终的分叉状态传入，不是从该分叉派生的状态会被拒绝。
+#[derive(Debug)]
+#[must_use = "分叉状态需要调用 merge_into 合并或 discard 放弃"]
+pub struct DivergenceToken {
+    base_version: u64,
+    fork_id: u64,
+    /// 父状态的分叉谱系，合并后恢复
+    base_lineage: Arc<[u64]>,
+}
+
+impl DivergenceToken {
+    /// 分叉时父状态的版本号
+    pub fn base_version(&self) -> u64 {
+        self.base_version
+    }
+
+    /// 父状态自分叉后是否未发生变化（可以快进合并）
+    ///
+    /// 每个新状态都会分配新的版本号，版本号一致即说明父状态未被替换
+    pub fn can_fast_forward<C, S>(
+        &self,
+        parent: &StateGeneric<C, S>,
+    ) -> bool
+    where
+        C: DataContainer + 'static,
+        S: SchemaDefinition<Container = C> + 'static,
+    {
+        parent.version == self.base_version
+    }
+
+    /// 分叉状态是否由该令牌的副本派生（包括副本本身）
+    pub fn is_descendant<C, S>(
+        &self,
+        state: &StateGeneric<C, S>,
+    ) -> bool
+    where
+        C: DataContainer + 'static,
+        S: SchemaDefinition<Container = C> + 'static,
+    {
+        state.lineage.contains(&self.fork_id)
+    }
+
+    /// 将分叉状态合并回父状态
+    ///
+    /// 仅支持快进合并：父状态在分叉后没有被替换为其它版本时，
+    /// 直接以分叉状态（文档与插件状态）取代父状态；否则返回错误且父状态保持不变，
+    /// 调用方可改为在最新状态上重新应用事务。
+    /// `diverged` 必须由该令牌的副本派生，否则同样返回错误。
+    pub fn merge_into<C, S>(
+        self,
+        parent: &mut StateGeneric<C, S>,
+        diverged: &StateGeneric<C, S>,
+    ) -> StateResult<()>
+    where
+        C: DataContainer + 'static,
+        S: SchemaDefinition<Container = C> + 'static,
+    {
+        if !self.is_descendant(diverged) {
+            return Err(error::divergence_error(format!(
+                "状态 {} 不是由分叉 {} 派生",
+                diverged.version, self.fork_id
+            )));
+        }
+        if !self.can_fast_forward(parent) {
+            return Err(error::divergence_error(format!(
+                "父状态已从版本 {} 变更为 {}",
+                self.base_version, parent.version
+            )));
+        }
+        *parent = diverged.clone();
+        parent.lineage = self.base_lineage;
+        Ok(())
+    }
+
+    /// 放弃分叉状态，父状态保持不变
+    pub fn discard(self) {}
+}
+
+/// 状态的只读快照，由 [`StateGeneric::read_snapshot`] 返回
+///
+/// 克隆开销与克隆几个 `Arc` 相当，可以在线程间传递；
+/// 内容固定为捕获时的文档与插件状态，不会看到之后的编辑。
+/// 快照与活动状态共享结构，持有期间会延长旧节点的生命周期。
+#[derive(Clone)]
+pub struct StateViewGeneric<C, S>
+where
+    C: DataContainer + 'static,
+    S: SchemaDefinition<Container = C> + 'static,
+{
+    schema: Arc<S>,
+    fields_instances: Arc<HashTrieMapSync<String, Arc<dyn Resource>>>,
+    node_pool: Arc<C>,
+    version: u64,
+}
+
+impl<C, S> Debug for StateViewGeneric<C, S>
+where
+    C: DataContainer + 'static,
+    S: SchemaDefinition<Container = C> + 'static,
+{
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "StateView {{ 版本: {}, 字段数量: {} }}",
+            self.version,
+            self.fields_instances.keys().len()
+        )
+    }
+}
+
+impl<C, S> StateViewGeneric<C, S>
+where
+    C: DataContainer + 'static,
+    S: SchemaDefinition<Container = C> + 'static,
+{
+    pub fn doc(&self) -> Arc<C> {
+        Arc::clone(&self.node_pool)
+    }
+
+    /// 获取结构定义
+    pub fn schema(&self) -> Arc<S> {
+        Arc::clone(&self.schema)
+    }
+
+    /// 捕获时的状态版本号
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// 获取字段值
+    pub fn get_field(
+        &self,
+        key: &str,
+    ) -> Option<Arc<dyn Resource>> {
+        self.fields_instances.get(key).cloned()
+    }
+
+    /// 获取强类型字段值
+    pub fn get<T: Resource>(
+        &self,
+        name: &str,
+    ) -> Option<Arc<T>> {
+        self.fields_instances
+            .get(name)
+            .cloned()
+            .and_then(|state| state.downcast_arc::<T>().cloned())
+    }
+
+    /// 按插件 key 读取插件状态的引用，不克隆 `Arc`
+    pub fn plugin_state<T: Resource>(
+        &self,
+        key: &str,
+    ) -> Option<&T> {
+        self.fields_instances.get(key).and_then(|state| state.downcast::<T>())
+    }
+
+    /// 检查字段是否存在
+    pub fn has_field(
+        &self,
+        name: &str,
+    ) -> bool {
+        self.fields_instances.contains_key(name)
+    }
+}
+
 // ========================================
 // NodePool 特化实现
 // ========================================
@@ -430,6 +763,9 @@ where
 /// 默认的 State 实现（NodePool + Schema）
 pub type State = StateGeneric<NodePool, Schema>;
 
+/// 默认的只读快照（NodePool + Schema）
+pub type StateView = StateViewGeneric<NodePool, Schema>;
+
 impl State {
     /// 创建新的编辑器状态
     /// - 初始化基础配置
@@ -442,6 +778,7 @@ impl State {
     )))]
     pub async fn create(state_config: StateConfig) -> StateResult<State> {
         tracing::info!("正在创建新的state");
+        state_config.validate()?;
         let schema: Arc<Schema> = match &state_config.schema {
             Some(schema) => schema.clone(),
             None => state_config.schema.clone().ok_or_else(|| {
@@ -470,6 +807,14 @@ impl State {
             fields_instances.insert_mut(name, value);
         }
         instance.fields_instances = Arc::new(fields_instances);
+        for plugin in instance.config.plugin_manager.get_sorted_plugins().await
+        {
+            plugin
+                .spec
+                .tr
+                .on_state_created(&state_config, Some(&instance))
+                .await?;
+        }
         tracing::info!("state创建成功");
         Ok(instance)
     }
@@ -497,6 +842,7 @@ impl State {
             config,
             node_pool: doc,
             version: get_state_version(),
+            lineage: Arc::from([]),
         })
     }
 
@@ -506,12 +852,50 @@ impl State {
     }
 
     /// 异步应用事务到当前状态（便捷方法）
-    /// 委托给 apply_generic 实现
+    /// 委托给 apply_generic 实现，之后重算受影响的派生属性
     pub async fn apply(
         self: &Arc<Self>,
         transaction: Transaction,
     ) -> StateResult<TransactionResult> {
-        self.apply_generic(transaction).await
+        let result = self.apply_generic(transaction).await?;
+        if Arc::ptr_eq(&result.state, self) {
+            return Ok(result);
+        }
+        Self::apply_derived(result).await
+    }
+
+    /// 按 schema 声明重算派生属性
+    ///
+    /// 需要更新时追加一个标记了 [`DERIVED_META`] 的事务，
+    /// 该事务只更新插件状态，不经过插件的过滤与追加
+    async fn apply_derived(
+        result: TransactionResult
+    ) -> StateResult<TransactionResult> {
+        if !result.state.schema().has_derived_attrs() {
+            return Ok(result);
+        }
+        let changed = derived::changed_nodes(
+            result.transactions.iter().flat_map(|tr| tr.steps.iter()),
+        );
+        if changed.as_ref().is_some_and(|changed| changed.is_empty()) {
+            return Ok(result);
+        }
+        let mut tr = result.state.tr();
+        tr.set_meta(DERIVED_META, true);
+        tr.set_origin(TransactionOrigin::System("derived".to_string()));
+        if let Some(root) = result.transactions.first() {
+            tr.set_caused_by(root.caused_by().unwrap_or(root.id));
+            if let Some(actor) = root.actor() {
+                tr.set_actor(actor);
+            }
+        }
+        if tr.recompute_derived(changed.as_ref())? == 0 {
+            return Ok(result);
+        }
+        let state = result.state.apply_inner_generic(&tr).await?;
+        let mut transactions = result.transactions;
+        transactions.push(Arc::new(tr));
+        Ok(TransactionResult { state, transactions })
     }
 
     #[cfg_attr(feature = "dev-tracing", tracing::instrument(skip(self, state_config), fields(
@@ -557,6 +941,240 @@ impl State {
         };
         Self::deserialize_generic(&generic_s, configuration).await
     }
+
+    /// 导出为可读的文档 JSON 快照
+    ///
+    /// 包含完整的节点树（id、类型、属性、标记与有序子节点），
+    /// 不包含插件状态，适合存入数据库或与其他系统交换
+    pub fn to_document_json(&self) -> Value {
+        document_json(&self.node_pool)
+    }
+
+    /// 从 [`State::to_document_json`] 导出的快照重建状态
+    ///
+    /// 按 `config` 中的 schema 校验节点类型、属性、标记与内容规则，
+    /// 校验通过后以该文档创建状态，`config.doc` 会被忽略
+    pub async fn from_document_json(
+        mut config: StateConfig,
+        value: &Value,
+    ) -> StateResult<State> {
+        let schema = config.schema.clone().ok_or_else(|| {
+            error::schema_error("必须提供结构定义".to_string())
+        })?;
+        let document = DocumentJson::deserialize(value).map_err(|e| {
+            error::deserialize_error(format!("文档 JSON 格式无效: {e}"))
+        })?;
+        if document.version != DOCUMENT_JSON_VERSION {
+            return Err(error::deserialize_error(format!(
+                "不支持的文档 JSON 版本: {}，当前版本: {DOCUMENT_JSON_VERSION}",
+                document.version
+            )));
+        }
+        if let Some(top) = schema.top_node()
+            && top.name != document.doc.r#type
+        {
+            return Err(error::schema_error(format!(
+                "根节点类型应为 {}，实际为 {}",
+                top.name, document.doc.r#type
+            )));
+        }
+        let tree = document.doc.into_tree(&schema, &mut HashSet::new())?;
+        config.doc = Some(NodePool::from(tree));
+        State::create(config).await
+    }
+}
+
+impl StateView {
+    /// 导出快照中的文档，格式与 [`State::to_document_json`] 相同
+    pub fn to_document_json(&self) -> Value {
+        document_json(&self.node_pool)
+    }
+}
+
+fn document_json(doc: &NodePool) -> Value {
+    let doc = doc
+        .get_inner()
+        .all_children(doc.root_id(), None)
+        .map(DocumentJsonNode::from_tree);
+    serde_json::json!({
+        "version": DOCUMENT_JSON_VERSION,
+        "doc": doc,
+    })
+}
+
+/// 文档 JSON 快照的格式版本
+pub const DOCUMENT_JSON_VERSION: u64 = 1;
+
+/// 文档 JSON 快照中的节点，子节点按顺序内嵌
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DocumentJsonNode {
+    id: String,
+    r#type: String,
+    #[serde(default)]
+    attrs: BTreeMap<String, Value>,
+    #[serde(default)]
+    marks: Vec<DocumentJsonMark>,
+    #[serde(default)]
+    children: Vec<DocumentJsonNode>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DocumentJsonMark {
+    r#type: String,
+    #[serde(default)]
+    attrs: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DocumentJson {
+    version: u64,
+    doc: DocumentJsonNode,
+}
+
+impl DocumentJsonNode {
+    fn from_tree(tree: NodeTree) -> Self {
+        let (node, children) = tree.into_parts();
+        Self {
+            id: node.id.to_string(),
+            r#type: node.r#type,
+            attrs: to_btree(&node.attrs),
+            marks: node
+                .marks
+                .iter()
+                .map(|mark| DocumentJsonMark {
+                    r#type: mark.r#type.clone(),
+                    attrs: to_btree(&mark.attrs),
+                })
+                .collect(),
+            children: children.into_iter().map(Self::from_tree).collect(),
+        }
+    }
+
+    /// 按 schema 校验并转换为节点树，缺省的属性使用默认值补齐
+    fn into_tree(
+        self,
+        schema: &Schema,
+        ids: &mut HashSet<String>,
+    ) -> StateResult<NodeTree> {
+        let path = format!("{}({})", self.r#type, self.id);
+        if !ids.insert(self.id.clone()) {
+            return Err(error::deserialize_error(format!(
+                "文档 JSON 中节点 id 重复: {path}"
+            )));
+        }
+        let node_type = schema
+            .node_types()
+            .find(|t| t.name == self.r#type)
+            .ok_or_else(|| {
+                error::schema_error(format!(
+                    "文档 JSON 中存在未定义的节点类型: {path}"
+                ))
+            })?;
+        let attrs = check_attrs(&path, &node_type.attrs, self.attrs)?;
+
+        let mut marks = Vec::with_capacity(self.marks.len());
+        for mark in self.marks {
+            let allowed = match &node_type.mark_set {
+                Some(set) => set.iter().find(|m| m.name == mark.r#type),
+                None => schema.mark_types().find(|m| m.name == mark.r#type),
+            };
+            let mark_type = allowed.ok_or_else(|| {
+                error::schema_error(format!(
+                    "节点 {path} 不允许标记类型 {}",
+                    mark.r#type
+                ))
+            })?;
+            let mark_path = format!("{path} 的标记 {}", mark.r#type);
+            marks.push(Mark {
+                r#type: mark.r#type,
+                attrs: check_attrs(&mark_path, &mark_type.attrs, mark.attrs)?,
+            });
+        }
+
+        let children = self
+            .children
+            .into_iter()
+            .map(|child| child.into_tree(schema, ids))
+            .collect::<StateResult<Vec<_>>>()?;
+        let child_nodes: Vec<Node> =
+            children.iter().map(|child| child.0.clone()).collect();
+        let mut allowed = HashSet::new();
+        if let Some(content) = &node_type.content_match {
+            content_types(content, &mut allowed);
+        }
+        if let Some(child) =
+            child_nodes.iter().find(|child| !allowed.contains(&child.r#type))
+        {
+            return Err(error::schema_error(format!(
+                "节点 {path} 不允许包含 {} 类型的子节点",
+                child.r#type
+            )));
+        }
+        if !node_type.check_content(&child_nodes, schema) {
+            return Err(error::schema_error(format!(
+                "节点 {path} 的子节点不符合内容规则"
+            )));
+        }
+
+        let node = Node::new(
+            &self.id,
+            self.r#type,
+            attrs,
+            child_nodes.iter().map(|child| child.id.clone()).collect(),
+            marks,
+        );
+        // 标记互斥等节点级规则统一由 Schema 校验
+        schema
+            .validate_node(&node)
+            .map_err(|e| error::schema_error(format!("节点 {path}: {e}")))?;
+        Ok(NodeTree(node, children))
+    }
+}
+
+/// 收集内容规则中出现的所有节点类型
+fn content_types(
+    content: &ContentMatch,
+    types: &mut HashSet<String>,
+) {
+    for edge in &content.next {
+        types.insert(edge.node_type.name.clone());
+        content_types(&edge.next, types);
+    }
+}
+
+fn to_btree(attrs: &Attrs) -> BTreeMap<String, Value> {
+    attrs.attrs.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+}
+
+fn check_attrs(
+    path: &str,
+    spec: &HashMap<String, Attribute>,
+    values: BTreeMap<String, Value>,
+) -> StateResult<Attrs> {
+    if let Some(key) = values.keys().find(|key| !spec.contains_key(*key)) {
+        return Err(error::schema_error(format!(
+            "{path} 的属性 {key} 没有定义"
+        )));
+    }
+    let mut attrs = HashTrieMapSync::new_sync();
+    for (name, attr) in spec {
+        let value = match values.get(name) {
+            Some(value) => value.clone(),
+            None if attr.has_default => {
+                attr.default.clone().unwrap_or(Value::Null)
+            },
+            None => {
+                return Err(error::schema_error(format!(
+                    "{path} 的属性 {name} 没有值，这个属性必填"
+                )));
+            },
+        };
+        attrs.insert_mut(name.clone(), value);
+    }
+    Ok(Attrs::from(attrs))
 }
 
 /// 泛型的序列化结构
@@ -592,6 +1210,37 @@ where
     pub resource_manager: Option<Arc<GlobalResourceManager>>,
 }
 
+impl<C, S> StateConfigGeneric<C, S>
+where
+    C: DataContainer + 'static,
+    S: SchemaDefinition<Container = C> + 'static,
+{
+    /// 校验插件与结构定义的兼容性
+    /// 检查每个插件描述中声明的节点类型是否都存在于结构定义中
+    pub fn validate(&self) -> StateResult<()> {
+        let (Some(schema), Some(plugins)) = (&self.schema, &self.plugins)
+        else {
+            return Ok(());
+        };
+        let mut missing = Vec::new();
+        for plugin in plugins {
+            let descriptor = plugin.describe();
+            for node in &descriptor.required_schema_nodes {
+                if schema.get_definition(node).is_none() {
+                    missing.push(format!("{}({})", node, plugin.key));
+                }
+            }
+        }
+        if !missing.is_empty() {
+            return Err(error::schema_error(format!(
+                "插件依赖的节点类型不存在: {}",
+                missing.join(", ")
+            )));
+        }
+        Ok(())
+    }
+}
+
 pub struct SeenStateGeneric<C, S>
 where
     C: DataContainer + 'static,
@@ -635,6 +1284,8 @@ where
     pub doc: Option<Arc<C>>,
     pub schema: Arc<S>,
     pub resource_manager: Arc<GlobalResourceManager>,
+    /// 按顺序逐个执行插件状态的 apply
+    sequential_apply: bool,
 }
 
 impl<C, S> ConfigurationGeneric<C, S>
@@ -672,6 +1323,7 @@ where
             schema,
             resource_manager: resource_manager
                 .unwrap_or_else(|| Arc::new(GlobalResourceManager::default())),
+            sequential_apply: false,
         })
     }
 }
@@ -682,3 +1334,447 @@ where
 
 /// 默认的 Configuration 实现（NodePool + Schema）
 pub type Configuration = ConfigurationGeneric<NodePool, Schema>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::{
+        Plugin, PluginDescriptor, PluginMetadata, PluginSpec,
+        PluginTraitGeneric, StateFieldGeneric,
+    };
+    use mf_model::{
+        mark_definition::MarkSpec,
+        node_definition::NodeSpec,
+        schema::{AttributeSpec, SchemaSpec},
+    };
+    use serde_json::json;
+
+    fn create_test_schema() -> Arc<Schema> {
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            "doc".to_string(),
+            NodeSpec {
+                content: Some("paragraph*".to_string()),
+                attrs: Some(HashMap::from([(
+                    "title".to_string(),
+                    AttributeSpec::new(Some(Value::from(""))),
+                )])),
+                ..Default::default()
+            },
+        );
+        nodes.insert(
+            "paragraph".to_string(),
+            NodeSpec {
+                attrs: Some(HashMap::from([(
+                    "level".to_string(),
+                    AttributeSpec::new(None),
+                )])),
+                ..Default::default()
+            },
+        );
+        let weight = MarkSpec {
+            group: Some("weight".to_string()),
+            excludes: Some("weight".to_string()),
+            ..Default::default()
+        };
+        let marks = HashMap::from([
+            ("bold".to_string(), weight.clone()),
+            ("light".to_string(), weight),
+            ("italic".to_string(), MarkSpec::default()),
+        ]);
+        let spec =
+            SchemaSpec { nodes, marks, top_node: Some("doc".to_string()) };
+        Arc::new(Schema::compile(spec).expect("测试 Schema 编译失败"))
+    }
+
+    fn config() -> StateConfig {
+        StateConfig {
+            schema: Some(create_test_schema()),
+            doc: None,
+            stored_marks: None,
+            plugins: None,
+            resource_manager: None,
+        }
+    }
+
+    fn document() -> Value {
+        json!({
+            "version": DOCUMENT_JSON_VERSION,
+            "doc": {
+                "id": "root",
+                "type": "doc",
+                "children": [
+                    {
+                        "id": "p1",
+                        "type": "paragraph",
+                        "attrs": { "level": 1 },
+                        "marks": [{ "type": "bold" }, { "type": "italic" }],
+                    },
+                    { "id": "p2", "type": "paragraph", "attrs": { "level": 2 } },
+                ],
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_document_json_round_trip() {
+        let state =
+            State::from_document_json(config(), &document()).await.unwrap();
+        let exported = state.to_document_json();
+
+        // 缺省的属性以默认值补齐，子节点顺序与标记保持不变
+        assert_eq!(exported["doc"]["attrs"], json!({ "title": "" }));
+        let children = exported["doc"]["children"].as_array().unwrap();
+        let ids: Vec<&str> =
+            children.iter().map(|c| c["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["p1", "p2"]);
+        assert_eq!(
+            children[0]["marks"],
+            json!([
+                { "type": "bold", "attrs": {} },
+                { "type": "italic", "attrs": {} },
+            ])
+        );
+
+        let reimported =
+            State::from_document_json(config(), &exported).await.unwrap();
+        assert_eq!(reimported.to_document_json(), exported);
+    }
+
+    #[tokio::test]
+    async fn test_document_json_rejects_invalid_input() {
+        type Mutate = Box<dyn Fn(&mut Value)>;
+        let cases: Vec<(&str, Mutate)> = vec![
+            ("版本", Box::new(|v| v["version"] = json!(99))),
+            ("根节点类型", Box::new(|v| v["doc"]["type"] = json!("paragraph"))),
+            ("未知字段", Box::new(|v| v["doc"]["extra"] = json!(true))),
+            (
+                "未定义的节点类型",
+                Box::new(|v| v["doc"]["children"][1]["type"] = json!("image")),
+            ),
+            (
+                "重复 id",
+                Box::new(|v| v["doc"]["children"][1]["id"] = json!("p1")),
+            ),
+            (
+                "未定义的属性",
+                Box::new(|v| v["doc"]["attrs"] = json!({ "color": "red" })),
+            ),
+            (
+                "缺少必填属性",
+                Box::new(|v| v["doc"]["children"][1]["attrs"] = json!({})),
+            ),
+            (
+                "未定义的标记",
+                Box::new(|v| {
+                    v["doc"]["children"][0]["marks"] =
+                        json!([{ "type": "strike" }])
+                }),
+            ),
+            (
+                "互斥的标记",
+                Box::new(|v| {
+                    v["doc"]["children"][0]["marks"] =
+                        json!([{ "type": "bold" }, { "type": "light" }])
+                }),
+            ),
+            (
+                "内容规则",
+                Box::new(|v| {
+                    v["doc"]["children"][0]["children"] = json!([
+                        { "id": "p3", "type": "paragraph", "attrs": { "level": 3 } }
+                    ])
+                }),
+            ),
+        ];
+        for (name, mutate) in cases {
+            let mut value = document();
+            mutate(&mut value);
+            assert!(
+                State::from_document_json(config(), &value).await.is_err(),
+                "{name} 应校验失败"
+            );
+        }
+        assert!(State::from_document_json(config(), &json!([])).await.is_err());
+    }
+
+    async fn set_level(
+        state: &State,
+        level: i64,
+    ) -> State {
+        let mut tr = state.tr();
+        tr.set_node_attribute(
+            "p1".into(),
+            HashTrieMapSync::new_sync()
+                .insert("level".to_string(), Value::from(level)),
+        )
+        .unwrap();
+        Arc::new(state.clone()).apply(tr).await.unwrap().state.as_ref().clone()
+    }
+
+    fn level(state: &State) -> Value {
+        state.to_document_json()["doc"]["children"][0]["attrs"]["level"].clone()
+    }
+
+    #[tokio::test]
+    async fn test_diverge_fast_forward_merge() {
+        let mut parent =
+            State::from_document_json(config(), &document()).await.unwrap();
+        let (fork, token) = parent.clone_with_diverge();
+        assert_eq!(token.base_version(), parent.version);
+
+        let fork = set_level(&fork, 5).await;
+        assert_eq!(level(&parent), json!(1));
+        assert!(token.can_fast_forward(&parent));
+        token.merge_into(&mut parent, &fork).unwrap();
+        assert_eq!(level(&parent), json!(5));
+        assert_eq!(parent.version, fork.version);
+    }
+
+    #[tokio::test]
+    async fn test_diverge_merge_rejects_unrelated_state() {
+        let mut parent =
+            State::from_document_json(config(), &document()).await.unwrap();
+        let version = parent.version;
+        let (fork, token) = parent.clone_with_diverge();
+        let (other, other_token) = parent.clone_with_diverge();
+        let other = set_level(&other, 9).await;
+        let unrelated = set_level(&parent, 3).await;
+
+        // 父状态自身的后继和另一个分叉都不是该令牌的派生状态
+        assert!(!token.is_descendant(&unrelated));
+        assert!(!token.is_descendant(&other));
+        assert!(token.is_descendant(&set_level(&fork, 5).await));
+        for state in [&unrelated, &other] {
+            let (_, token) = parent.clone_with_diverge();
+            assert!(token.merge_into(&mut parent, state).is_err());
+            assert_eq!(level(&parent), json!(1));
+            assert_eq!(parent.version, version);
+        }
+
+        // 合并后父状态回到原有谱系，可以再次分叉合并
+        other_token.merge_into(&mut parent, &other).unwrap();
+        assert_eq!(level(&parent), json!(9));
+        assert!(!token.is_descendant(&parent));
+        token.discard();
+        let (fork, token) = parent.clone_with_diverge();
+        let fork = set_level(&fork, 4).await;
+        token.merge_into(&mut parent, &fork).unwrap();
+        assert_eq!(level(&parent), json!(4));
+    }
+
+    #[tokio::test]
+    async fn test_diverge_merge_rejected_after_parent_changed() {
+        let parent =
+            State::from_document_json(config(), &document()).await.unwrap();
+        let (fork, token) = parent.clone_with_diverge();
+        let fork = set_level(&fork, 5).await;
+
+        let mut parent = set_level(&parent, 7).await;
+        let version = parent.version;
+        assert!(!token.can_fast_forward(&parent));
+        assert!(token.merge_into(&mut parent, &fork).is_err());
+        // 合并失败时父状态保持不变
+        assert_eq!(level(&parent), json!(7));
+        assert_eq!(parent.version, version);
+    }
+
+    #[derive(Debug)]
+    struct RequiresNode(&'static str);
+
+    #[async_trait::async_trait]
+    impl PluginTraitGeneric<NodePool, Schema> for RequiresNode {
+        fn metadata(&self) -> PluginMetadata {
+            PluginMetadata {
+                name: format!("requires_{}", self.0),
+                version: "1.0.0".to_string(),
+                description: String::new(),
+                author: String::new(),
+                dependencies: vec![],
+                conflicts: vec![],
+                state_fields: vec![],
+                tags: vec![],
+            }
+        }
+
+        fn describe(&self) -> PluginDescriptor {
+            PluginDescriptor {
+                name: self.metadata().name,
+                required_schema_nodes: vec![self.0.to_string()],
+                ..Default::default()
+            }
+        }
+    }
+
+    fn requires(node: &'static str) -> Arc<Plugin> {
+        Arc::new(Plugin::new(PluginSpec {
+            state_field: None,
+            tr: Arc::new(RequiresNode(node)),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_plugin_required_nodes_are_validated() {
+        let mut ok = config();
+        ok.plugins = Some(vec![requires("paragraph")]);
+        assert!(ok.validate().is_ok());
+        assert!(State::create(ok).await.is_ok());
+
+        let mut missing = config();
+        missing.plugins = Some(vec![requires("paragraph"), requires("table")]);
+        let err = missing.validate().unwrap_err().to_string();
+        assert!(err.contains("table(requires_table)"), "{err}");
+        assert!(!err.contains("paragraph"), "{err}");
+        assert!(State::create(missing).await.is_err());
+    }
+
+    #[derive(Debug)]
+    struct Count(u64);
+    impl Resource for Count {}
+
+    #[derive(Debug)]
+    struct Counter;
+    impl Resource for Counter {}
+
+    /// 每次 apply 加一，再加上依赖插件在新状态中的计数
+    #[derive(Debug)]
+    struct CountField(&'static [&'static str]);
+
+    #[async_trait::async_trait]
+    impl StateFieldGeneric<NodePool, Schema> for CountField {
+        type Value = Count;
+
+        async fn init(
+            &self,
+            _config: &StateConfig,
+            _instance: &State,
+        ) -> Arc<Count> {
+            Arc::new(Count(0))
+        }
+
+        async fn apply(
+            &self,
+            _tr: &Transaction,
+            value: Arc<Count>,
+            _old_state: &State,
+            new_state: &State,
+        ) -> Arc<Count> {
+            let deps: u64 = self
+                .0
+                .iter()
+                .filter_map(|dep| new_state.get::<Count>(dep))
+                .map(|count| count.0)
+                .sum();
+            Arc::new(Count(value.0 + 1 + deps))
+        }
+    }
+
+    #[derive(Debug)]
+    struct CountPlugin(&'static str, &'static [&'static str]);
+
+    #[async_trait::async_trait]
+    impl PluginTraitGeneric<NodePool, Schema> for CountPlugin {
+        fn metadata(&self) -> PluginMetadata {
+            PluginMetadata {
+                name: self.0.to_string(),
+                version: "1.0.0".to_string(),
+                description: String::new(),
+                author: String::new(),
+                dependencies: vec![],
+                conflicts: vec![],
+                state_fields: vec![],
+                tags: vec![],
+            }
+        }
+
+        fn state_dependencies(&self) -> Vec<String> {
+            self.1.iter().map(|dep| dep.to_string()).collect()
+        }
+    }
+
+    fn counter(
+        name: &'static str,
+        deps: &'static [&'static str],
+    ) -> Arc<Plugin> {
+        Arc::new(Plugin::new(PluginSpec {
+            state_field: Some(Arc::new(CountField(deps))),
+            tr: Arc::new(CountPlugin(name, deps)),
+        }))
+    }
+
+    fn counts(state: &State) -> Vec<u64> {
+        ["a", "b", "d"]
+            .iter()
+            .map(|key| state.get::<Count>(key).unwrap().0)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_state_levels_follow_dependencies() {
+        let mut config = config();
+        config.plugins = Some(vec![
+            counter("a", &[]),
+            counter("b", &[]),
+            counter("d", &["a"]),
+        ]);
+        let state = State::create(config).await.unwrap();
+        let levels: Vec<Vec<&str>> = state
+            .config
+            .plugin_manager
+            .get_state_levels()
+            .iter()
+            .map(|level| {
+                let mut names: Vec<&str> =
+                    level.iter().map(|p| p.get_name()).collect();
+                names.sort();
+                names
+            })
+            .collect();
+        assert_eq!(levels, [vec!["a", "b"], vec!["d"]]);
+        assert_eq!(
+            state
+                .config
+                .plugin_manager
+                .get_plugin("d")
+                .unwrap()
+                .state_dependencies(),
+            ["a"]
+        );
+
+        // d 在 a 之后执行，看到 a 的新计数；顺序执行结果相同
+        let state = Arc::new(state);
+        assert!(!state.sequential_apply());
+        let concurrent = state.apply(state.tr()).await.unwrap().state;
+        let sequential = Arc::new(state.with_sequential_apply(true));
+        assert!(sequential.sequential_apply());
+        let sequential = sequential.apply(sequential.tr()).await.unwrap().state;
+        assert_eq!(counts(&concurrent), [1, 1, 2]);
+        assert_eq!(counts(&sequential), counts(&concurrent));
+        assert!(sequential.sequential_apply());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_state_dependencies_are_rejected() {
+        let mut missing = config();
+        missing.plugins = Some(vec![counter("a", &["x"])]);
+        assert!(State::create(missing).await.is_err());
+
+        let mut cycle = config();
+        cycle.plugins = Some(vec![counter("a", &["b"]), counter("b", &["a"])]);
+        assert!(State::create(cycle).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_plugin_state() {
+        let mut config = config();
+        config.plugins = Some(vec![counter("a", &[]), requires("paragraph")]);
+        let state = Arc::new(State::create(config).await.unwrap());
+        let state = state.apply(state.tr()).await.unwrap().state;
+
+        assert_eq!(state.plugin_state::<Count>("a").map(|c| c.0), Some(1));
+        // 插件不存在、没有状态字段或类型不匹配
+        assert!(state.plugin_state::<Count>("missing").is_none());
+        assert!(state.plugin_state::<Count>("requires_paragraph").is_none());
+        assert!(state.plugin_state::<Counter>("a").is_none());
+    }
+}
@@ -1,4 +1,5 @@
 use std::any::Any;
+use std::collections::HashSet;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
@@ -8,12 +9,15 @@ use mf_model::node_definition::NodeTree;
 use mf_model::types::NodeId;
 use mf_model::traits::{DataContainer, SchemaDefinition};
 use mf_transform::TransformResult;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tokio_util::sync::CancellationToken;
 
 use super::state::State;
 use mf_model::node_pool::NodePool;
 use mf_model::schema::Schema;
-use mf_transform::attr_step::AttrStep;
+use mf_transform::attr_step::{AttrStep, BulkAttrStep};
+use mf_transform::conflict::{self, ConflictReport, MergeConflict, RebaseError};
 use mf_transform::node_step::{AddNodeStep, RemoveNodeStep};
 use mf_transform::mark_step::{AddMarkStep, RemoveMarkStep};
 use mf_transform::transform::{Transform, TransformGeneric};
@@ -33,9 +37,113 @@ where
         &self,
         tr: &mut TransactionGeneric<C, S>,
     ) -> TransformResult<()>;
+
+    /// 支持取消与进度上报的执行入口
+    ///
+    /// 运行时总是调用此方法，默认实现直接调用 `execute`；
+    /// 长耗时的命令可以覆盖它，通过 `ctx` 观察取消并上报进度
+    async fn execute_with(
+        &self,
+        ctx: &CommandContext,
+        tr: &mut TransactionGeneric<C, S>,
+    ) -> TransformResult<()> {
+        let _ = ctx;
+        self.execute(tr).await
+    }
+
     fn name(&self) -> String;
 }
 
+/// 命令执行进度
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandProgress {
+    /// 已完成的工作量
+    pub done: u64,
+    /// 总工作量
+    pub total: u64,
+    pub message: String,
+}
+
+type ProgressListener = Arc<dyn Fn(&CommandProgress) + Send + Sync>;
+
+/// 命令执行上下文：取消令牌与进度上报
+///
+/// 调用方保留 `cancellation_token()` 的克隆即可在命令执行期间取消；
+/// 被取消的命令不会进入事务应用流程。
+#[derive(Clone, Default)]
+pub struct CommandContext {
+    token: CancellationToken,
+    listeners: Vec<ProgressListener>,
+}
+
+impl Debug for CommandContext {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.debug_struct("CommandContext")
+            .field("cancelled", &self.is_cancelled())
+            .field("listeners", &self.listeners.len())
+            .finish()
+    }
+}
+
+impl CommandContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用外部的取消令牌创建上下文
+    pub fn with_token(token: CancellationToken) -> Self {
+        Self { token, listeners: Vec::new() }
+    }
+
+    /// 追加进度监听器
+    pub fn on_progress<F>(
+        mut self,
+        listener: F,
+    ) -> Self
+    where
+        F: Fn(&CommandProgress) + Send + Sync + 'static,
+    {
+        self.listeners.push(Arc::new(listener));
+        self
+    }
+
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// 已取消时返回错误，便于在命令中用 `?` 提前退出
+    pub fn check_cancelled(&self) -> TransformResult<()> {
+        if self.is_cancelled() {
+            return Err(anyhow::anyhow!("命令已取消"));
+        }
+        Ok(())
+    }
+
+    /// 上报进度
+    pub fn report(
+        &self,
+        done: u64,
+        total: u64,
+        message: impl Into<String>,
+    ) {
+        let progress = CommandProgress { done, total, message: message.into() };
+        for listener in &self.listeners {
+            listener(&progress);
+        }
+    }
+}
+
 static VERSION: AtomicU64 = AtomicU64::new(1);
 pub fn get_tr_id() -> u64 {
     //生成 全局自增的版本号，用于兼容性
@@ -52,6 +160,8 @@ where
     /// 存储元数据的哈希表，支持任意类型数据
     pub meta: HashTrieMapSync<String, Arc<dyn Any + Send + Sync>>,
     pub id: u64,
+    /// 发起修改的用户或客户端 id，用于审计与协作归属
+    actor: Option<String>,
     transform: TransformGeneric<C, S>,
 }
 
@@ -64,7 +174,12 @@ where
         &self,
         f: &mut std::fmt::Formatter<'_>,
     ) -> std::fmt::Result {
-        write!(f, "Transaction {{ id: {}}}", self.id)
+        match &self.actor {
+            Some(actor) => {
+                write!(f, "Transaction {{ id: {}, actor: {} }}", self.id, actor)
+            },
+            None => write!(f, "Transaction {{ id: {}}}", self.id),
+        }
     }
 }
 
@@ -103,6 +218,7 @@ where
         TransactionGeneric {
             meta: HashTrieMapSync::new_sync(),
             id: get_tr_id(),
+            actor: None,
             transform: TransformGeneric::new(node, schema),
         }
     }
@@ -139,11 +255,101 @@ where
 
         value.downcast_ref::<T>().cloned()
     }
+
+    /// 指定发起修改的用户或客户端 id
+    ///
+    /// 插件追加与派生属性事务沿用本次提交根事务的 actor，
+    /// 并随事件、历史帧与持久化的事件记录一同保存
+    #[must_use]
+    pub fn with_actor(
+        mut self,
+        actor_id: String,
+    ) -> Self {
+        self.actor = Some(actor_id);
+        self
+    }
+
+    pub fn set_actor(
+        &mut self,
+        actor_id: impl Into<String>,
+    ) -> &mut Self {
+        self.actor = Some(actor_id.into());
+        self
+    }
+
+    /// 发起修改的用户或客户端 id，未设置时为 `None`
+    pub fn actor(&self) -> Option<&str> {
+        self.actor.as_deref()
+    }
+
+    /// 事务来源，未设置时视为用户操作，见 [`ORIGIN_META`]
+    pub fn origin(&self) -> TransactionOrigin {
+        self.get_meta::<TransactionOrigin>(ORIGIN_META).unwrap_or_default()
+    }
+
+    pub fn set_origin(
+        &mut self,
+        origin: TransactionOrigin,
+    ) -> &mut Self {
+        self.set_meta(ORIGIN_META, origin)
+    }
+
+    /// 触发本事务的事务 id，见 [`CAUSED_BY_META`]
+    pub fn caused_by(&self) -> Option<u64> {
+        self.get_meta::<u64>(CAUSED_BY_META)
+    }
+
+    pub fn set_caused_by(
+        &mut self,
+        tr_id: u64,
+    ) -> &mut Self {
+        self.set_meta(CAUSED_BY_META, tr_id)
+    }
+}
+
+/// 事务来源
+///
+/// 历史记录、"文档已修改"提示与协作归属等面向用户的功能据此区分用户操作
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum TransactionOrigin {
+    /// 用户操作（命令或直接提交的事务）
+    #[default]
+    User,
+    /// 插件追加的事务，值为插件 key
+    Plugin(String),
+    /// 框架内部生成的事务，如派生属性重算
+    System(String),
+    /// 协作同步的远程编辑，值为远程客户端标识
+    Collaboration(String),
+}
+
+impl TransactionOrigin {
+    pub fn is_user(&self) -> bool {
+        matches!(self, TransactionOrigin::User)
+    }
 }
 
 /// 默认的 Transaction 实现（NodePool + Schema）
 pub type Transaction = TransactionGeneric<NodePool, Schema>;
 
+/// 事务元数据键：设为 `false` 时关闭按 schema `sort_by` 自动排序子节点，
+/// 适用于批量导入，导入后调用 `normalize_order` 统一整理
+pub const AUTO_SORT_META: &str = "auto_sort";
+
+/// 事务元数据键：派生属性重算生成的事务设为 `true`，
+/// 历史记录等面向用户的功能可据此区分用户操作
+pub const DERIVED_META: &str = "derived_attrs";
+
+/// 事务元数据键：[`TransactionOrigin`]，插件追加与派生属性事务由状态自动设置
+pub const ORIGIN_META: &str = "origin";
+
+/// 事务元数据键：触发本事务的事务 id（`u64`），
+/// 插件追加与派生属性事务自动指向本次提交的根事务
+pub const CAUSED_BY_META: &str = "caused_by";
+
 impl Transaction {
     /// 创建新的事务实例
     /// state: 当前状态对象
@@ -159,6 +365,7 @@ impl Transaction {
         let tr = Transaction {
             meta: HashTrieMapSync::new_sync(),
             id: get_tr_id(), // ✅ 使用 UUID v4 生成唯一标识
+            actor: None,
             transform: Transform::new(node, schema),
         };
         #[cfg(feature = "dev-tracing")]
@@ -188,6 +395,62 @@ impl Transaction {
         }
     }
 
+    /// 分析与另一个基于同一状态构建的事务之间的冲突
+    pub fn conflicts_with(
+        &self,
+        other: &Self,
+    ) -> ConflictReport {
+        conflict::conflicts_with(&self.transform, &other.transform)
+    }
+
+    /// 将当前事务变基到 `over` 之后
+    ///
+    /// 返回的事务以 `over` 的结果文档为基础，保留原事务的 id 与元数据；
+    /// 存在冲突时返回 `RebaseError::Conflicts`，由调用方决定拒绝或人工合并。
+    pub fn rebase(
+        &self,
+        over: &Self,
+    ) -> Result<Self, RebaseError> {
+        let transform = conflict::rebase(&self.transform, &over.transform)?;
+        Ok(Transaction {
+            meta: self.meta.clone(),
+            id: self.id,
+            actor: self.actor.clone(),
+            transform,
+        })
+    }
+
+    /// 三方合并两个基于 `base` 并发构建的事务
+    ///
+    /// 用于协作场景：本地事务尚未提交时收到远程事务。无冲突的步骤合并为一个
+    /// 以 `base` 为基础的新事务（本地步骤在前，远程步骤变基到其后），
+    /// 并沿用本地事务的元数据；存在冲突时在 `MergeConflict::conflicts` 中列出。
+    /// 结果只由输入决定，交换 `local` 与 `remote` 得到的文档一致。
+    pub fn three_way_merge(
+        base: &State,
+        local: &Self,
+        remote: &Self,
+    ) -> Result<Self, MergeConflict> {
+        let transform =
+            conflict::merge(&base.doc(), &local.transform, &remote.transform)?;
+        Ok(Transaction {
+            meta: local.meta.clone(),
+            id: get_tr_id(),
+            actor: local.actor.clone(),
+            transform,
+        })
+    }
+
+    /// 是否为派生属性重算生成的事务，见 [`DERIVED_META`]
+    pub fn is_derived(&self) -> bool {
+        self.get_meta::<bool>(DERIVED_META).unwrap_or(false)
+    }
+
+    /// 是否按 schema 声明的排序规则自动排序子节点，见 [`AUTO_SORT_META`]
+    pub fn auto_sort(&self) -> bool {
+        self.get_meta::<bool>(AUTO_SORT_META).unwrap_or(true)
+    }
+
     /// 设置节点属性
     /// id: 节点ID
     /// values: 属性键值对
@@ -202,7 +465,37 @@ impl Transaction {
         id: NodeId,
         values: HashTrieMapSync<String, Value>,
     ) -> TransformResult<()> {
-        self.step(Arc::new(AttrStep::new(id, values)))?;
+        self.step(Arc::new(AttrStep::new(id.clone(), values)))?;
+        if self.auto_sort() {
+            self.restore_order(&id)?;
+        }
+        Ok(())
+    }
+    /// 批量设置多个节点的属性，只生成一个 [`BulkAttrStep`]
+    /// changes: (节点ID, 属性键值对) 列表
+    #[cfg_attr(feature = "dev-tracing", tracing::instrument(skip(self, changes), fields(
+        crate_name = "state",
+        tr_id = %self.id,
+        node_count = changes.len()
+    )))]
+    pub fn set_attrs_bulk(
+        &mut self,
+        changes: Vec<(NodeId, HashTrieMapSync<String, Value>)>,
+    ) -> TransformResult<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<NodeId> =
+            changes.iter().map(|(id, _)| id.clone()).collect();
+        self.step(Arc::new(BulkAttrStep::new(changes)))?;
+        if self.auto_sort() {
+            let mut seen = HashSet::new();
+            for id in ids {
+                if seen.insert(id.clone()) {
+                    self.restore_order(&id)?;
+                }
+            }
+        }
         Ok(())
     }
     /// 添加新节点
@@ -219,7 +512,12 @@ impl Transaction {
         parent_id: NodeId,
         nodes: Vec<NodeTree>,
     ) -> TransformResult<()> {
-        self.step(Arc::new(AddNodeStep::new(parent_id, nodes)))?;
+        let step = if self.auto_sort() {
+            AddNodeStep::new(parent_id, nodes)
+        } else {
+            AddNodeStep::unsorted(parent_id, nodes)
+        };
+        self.step(Arc::new(step))?;
         Ok(())
     }
     /// 删除节点
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use mf_model::attrs::Attrs;
+use mf_model::node::Node;
+use mf_model::node_pool::NodePool;
+use mf_model::node_definition::NodeSpec;
+use mf_model::rpds::{ht_map_sync, HashTrieMapSync};
+use mf_model::schema::{AttributeSpec, Schema, SchemaSpec};
+use mf_model::tree::Tree;
+use mf_model::types::NodeId;
+use mf_transform::attr_step::{AttrStep, BulkAttrStep};
+use mf_transform::step::StepGeneric;
+use mf_transform::Transform;
+use serde_json::{json, Value};
+
+/// 模拟一次全局费率调整涉及的节点数
+const NODES: usize = 10_000;
+
+fn create_schema() -> Arc<Schema> {
+    let mut attrs = HashMap::new();
+    attrs.insert("rate".to_string(), AttributeSpec::new(Some(json!(1.0))));
+    let mut nodes = HashMap::new();
+    nodes.insert("doc".to_string(), NodeSpec::default());
+    nodes.insert(
+        "item".to_string(),
+        NodeSpec { attrs: Some(attrs), ..Default::default() },
+    );
+    Arc::new(
+        Schema::compile(SchemaSpec {
+            nodes,
+            marks: HashMap::new(),
+            top_node: Some("doc".to_string()),
+        })
+        .unwrap(),
+    )
+}
+
+fn create_tree() -> (Tree, Vec<NodeId>) {
+    let root =
+        Node::new("doc", "doc".to_string(), Attrs::default(), vec![], vec![]);
+    let mut tree = Tree::new(root);
+    let items: Vec<Node> = (0..NODES)
+        .map(|i| {
+            let attrs = Attrs::from(ht_map_sync! {
+                "rate".to_string() => json!(1.0)
+            });
+            Node::new(
+                &format!("item-{i}"),
+                "item".to_string(),
+                attrs,
+                vec![],
+                vec![],
+            )
+        })
+        .collect();
+    let ids = items.iter().map(|node| node.id.clone()).collect();
+    tree.add_node(&"doc".into(), &items).unwrap();
+    (tree, ids)
+}
+
+fn rate_change() -> HashTrieMapSync<String, Value> {
+    ht_map_sync! { "rate".to_string() => json!(1.05) }
+}
+
+/// 10k 个 AttrStep 与一个 BulkAttrStep 的应用耗时对比
+fn bench_bulk_attr(c: &mut Criterion) {
+    let schema = create_schema();
+    let (tree, ids) = create_tree();
+
+    let single_steps: Vec<AttrStep> =
+        ids.iter().map(|id| AttrStep::new(id.clone(), rate_change())).collect();
+    let bulk_step = BulkAttrStep::new(
+        ids.iter().map(|id| (id.clone(), rate_change())).collect(),
+    );
+
+    let single_size: usize = single_steps
+        .iter()
+        .map(|step| step.serialize().map_or(0, |bytes| bytes.len()))
+        .sum();
+    let bulk_size = bulk_step.serialize().map_or(0, |bytes| bytes.len());
+    println!(
+        "序列化大小：{NODES} 个 AttrStep = {single_size} 字节，BulkAttrStep = {bulk_size} 字节"
+    );
+
+    let mut group = c.benchmark_group("批量属性更新");
+    group.sample_size(10);
+
+    group.bench_function("10k 个 AttrStep", |b| {
+        b.iter(|| {
+            let mut draft = tree.clone();
+            for step in &single_steps {
+                step.apply(&mut draft, schema.clone()).unwrap();
+            }
+            criterion::black_box(draft)
+        })
+    });
+
+    group.bench_function("单个 BulkAttrStep", |b| {
+        b.iter(|| {
+            let mut draft = tree.clone();
+            bulk_step.apply(&mut draft, schema.clone()).unwrap();
+            criterion::black_box(draft)
+        })
+    });
+
+    // 经事务提交：每个步骤都会生成反向步骤并记录到历史
+    let pool = NodePool::new(Arc::new(tree.clone()));
+    group.bench_function("事务 10k 个 AttrStep", |b| {
+        b.iter(|| {
+            let mut tr = Transform::new(pool.clone(), schema.clone());
+            for step in &single_steps {
+                tr.step(Arc::new(step.clone())).unwrap();
+            }
+            criterion::black_box(tr.doc())
+        })
+    });
+
+    group.bench_function("事务 单个 BulkAttrStep", |b| {
+        b.iter(|| {
+            let mut tr = Transform::new(pool.clone(), schema.clone());
+            tr.step(Arc::new(bulk_step.clone())).unwrap();
+            criterion::black_box(tr.doc())
+        })
+    });
+
+    group.bench_function("BulkAttrStep 反转", |b| {
+        let before = Arc::new(tree.clone());
+        b.iter(|| criterion::black_box(bulk_step.invert(&before)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_bulk_attr);
+criterion_main!(benches);
@@ -0,0 +1,141 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use mf_model::attrs::Attrs;
+use mf_model::node::Node;
+use mf_model::node_definition::NodeSpec;
+use mf_model::rpds::ht_map_sync;
+use mf_model::schema::{AttributeSpec, Schema, SchemaSpec};
+use mf_model::tree::Tree;
+use mf_model::types::NodeId;
+use mf_transform::derived::{
+    derived_updates, derived_updates_counted, DerivedAttrStep,
+};
+use mf_transform::step::StepGeneric;
+use serde_json::json;
+
+/// 树的深度（不含根节点）与每层分支数：4^8 = 65536 个叶子
+const DEPTH: usize = 8;
+const BRANCHES: usize = 4;
+
+fn create_schema() -> Arc<Schema> {
+    let section = |computed: &str| {
+        let mut attrs = HashMap::new();
+        attrs.insert("value".to_string(), AttributeSpec::new(Some(json!(1))));
+        attrs.insert(
+            "total".to_string(),
+            AttributeSpec {
+                computed: Some(computed.to_string()),
+                ..Default::default()
+            },
+        );
+        NodeSpec {
+            content: Some("section*".to_string()),
+            attrs: Some(attrs),
+            ..Default::default()
+        }
+    };
+    let mut nodes = HashMap::new();
+    nodes.insert("doc".to_string(), section("sum(children.total)"));
+    nodes.insert("section".to_string(), section("value + sum(children.total)"));
+    Arc::new(
+        Schema::compile(SchemaSpec {
+            nodes,
+            marks: HashMap::new(),
+            top_node: Some("doc".to_string()),
+        })
+        .unwrap(),
+    )
+}
+
+/// 构建满 `BRANCHES` 叉树，返回树与最左侧的叶子
+fn create_tree(schema: &Arc<Schema>) -> (Tree, NodeId) {
+    let root =
+        Node::new("doc", "doc".to_string(), Attrs::default(), vec![], vec![]);
+    let mut tree = Tree::new(root);
+    let mut level = vec![tree.root_id.clone()];
+    for depth in 0..DEPTH {
+        let mut next = Vec::with_capacity(level.len() * BRANCHES);
+        for parent in &level {
+            let children: Vec<Node> = (0..BRANCHES)
+                .map(|i| {
+                    Node::new(
+                        &format!("{parent}-{i}"),
+                        "section".to_string(),
+                        Attrs::from(ht_map_sync! {
+                            "value".to_string() => json!(depth)
+                        }),
+                        vec![],
+                        vec![],
+                    )
+                })
+                .collect();
+            next.extend(children.iter().map(|node| node.id.clone()));
+            tree.add_node(parent, &children).unwrap();
+        }
+        level = next;
+    }
+    // 先整体计算一次，得到派生属性都已就绪的文档
+    let updates = derived_updates(&tree, schema, None).unwrap();
+    DerivedAttrStep::new(updates).apply(&mut tree, schema.clone()).unwrap();
+    (tree, level.swap_remove(0))
+}
+
+/// 修改一个叶子后的增量重算与整棵树重算的耗时对比
+fn bench_derived_attrs(c: &mut Criterion) {
+    let schema = create_schema();
+    let (tree, leaf) = create_tree(&schema);
+    let nodes = tree.nodes.iter().map(|shard| shard.size()).sum::<usize>();
+
+    let mut changed_tree = tree.clone();
+    changed_tree
+        .update_attr(&leaf, ht_map_sync! { "value".to_string() => json!(100) })
+        .unwrap();
+    let changed: HashSet<NodeId> = [leaf.clone()].into();
+
+    // 增量重算只对叶子到根的路径求值，与文档大小无关
+    let start = Instant::now();
+    let (updates, incremental_evals) =
+        derived_updates_counted(&changed_tree, &schema, Some(&changed))
+            .unwrap();
+    let incremental = start.elapsed();
+    assert_eq!(updates.len(), DEPTH + 1);
+    let start = Instant::now();
+    let (full, full_evals) =
+        derived_updates_counted(&changed_tree, &schema, None).unwrap();
+    let full_elapsed = start.elapsed();
+    assert_eq!(full.len(), DEPTH + 1);
+    assert_eq!(incremental_evals, DEPTH + 1);
+    assert!(full_evals >= nodes);
+    assert!(incremental_evals < full_evals);
+    println!(
+        "{nodes} 个节点：增量重算求值 {incremental_evals} 次耗时 {incremental:?}，整树重算求值 {full_evals} 次耗时 {full_elapsed:?}"
+    );
+
+    let mut group = c.benchmark_group("派生属性重算");
+    group.sample_size(10);
+
+    group.bench_function("叶子变化 增量重算", |b| {
+        b.iter(|| {
+            criterion::black_box(
+                derived_updates(&changed_tree, &schema, Some(&changed))
+                    .unwrap(),
+            )
+        })
+    });
+
+    group.bench_function("叶子变化 整树重算", |b| {
+        b.iter(|| {
+            criterion::black_box(
+                derived_updates(&changed_tree, &schema, None).unwrap(),
+            )
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_derived_attrs);
+criterion_main!(benches);
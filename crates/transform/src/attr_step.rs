@@ -1,6 +1,7 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::{transform_error, TransformResult};
+use crate::{derived::check_not_derived, transform_error, TransformResult};
 
 use super::{
     step::{StepGeneric, StepResult},
@@ -50,6 +51,7 @@ impl StepGeneric<NodePool, Schema> for AttrStep {
                         )));
                     },
                 };
+                check_not_derived(&schema, &node.r#type, &self.values)?;
                 let attr = &node_type.attrs;
                 // 删除 self.values 中 attr中没有定义的属性
                 let mut new_values = self.values.clone();
@@ -101,3 +103,273 @@ impl StepGeneric<NodePool, Schema> for AttrStep {
         }
     }
 }
+
+/// 批量节点属性变更步骤
+///
+/// 在一个步骤内更新多个节点的属性：先校验全部节点，任一失败则不做任何修改。
+/// 序列化时相邻且变更相同的节点合并为一组，全局调整时只保存一份属性值。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(from = "BulkAttrRuns", into = "BulkAttrRuns")]
+pub struct BulkAttrStep {
+    pub updates: Vec<(NodeId, HashTrieMapSync<String, Value>)>,
+}
+
+/// [`BulkAttrStep`] 的序列化形式：(节点 id 列表, 属性变更) 分组，保持原有顺序
+#[derive(Serialize, Deserialize)]
+struct BulkAttrRuns {
+    runs: Vec<(Vec<NodeId>, HashTrieMapSync<String, Value>)>,
+}
+
+impl From<BulkAttrStep> for BulkAttrRuns {
+    fn from(step: BulkAttrStep) -> Self {
+        let mut runs: Vec<(Vec<NodeId>, HashTrieMapSync<String, Value>)> =
+            Vec::new();
+        for (id, values) in step.updates {
+            match runs.last_mut() {
+                Some((ids, last)) if *last == values => ids.push(id),
+                _ => runs.push((vec![id], values)),
+            }
+        }
+        BulkAttrRuns { runs }
+    }
+}
+
+impl From<BulkAttrRuns> for BulkAttrStep {
+    fn from(wire: BulkAttrRuns) -> Self {
+        let updates = wire
+            .runs
+            .into_iter()
+            .flat_map(|(ids, values)| {
+                ids.into_iter().map(move |id| (id, values.clone()))
+            })
+            .collect();
+        BulkAttrStep { updates }
+    }
+}
+
+impl BulkAttrStep {
+    pub fn new(updates: Vec<(NodeId, HashTrieMapSync<String, Value>)>) -> Self {
+        BulkAttrStep { updates }
+    }
+
+    /// 校验所有更新，返回过滤掉未定义属性后的值
+    fn validate(
+        &self,
+        dart: &Tree,
+        schema: &Schema,
+    ) -> TransformResult<Vec<(NodeId, HashTrieMapSync<String, Value>)>> {
+        let factory = schema.factory();
+        let mut checked = Vec::with_capacity(self.updates.len());
+        for (id, values) in &self.updates {
+            let node = dart
+                .get_node(id)
+                .ok_or_else(|| transform_error(format!("节点不存在: {id}")))?;
+            let node_type =
+                factory.node_definition(&node.r#type).ok_or_else(|| {
+                    transform_error(format!("未知的节点类型: {}", node.r#type))
+                })?;
+            check_not_derived(schema, &node.r#type, values)?;
+            let specs = node_type.spec.attrs.as_ref();
+            let mut new_values = values.clone();
+            for (key, value) in values.iter() {
+                if !node_type.attrs.contains_key(key) {
+                    new_values.remove_mut(key);
+                    continue;
+                }
+                let constraint = specs
+                    .and_then(|specs| specs.get(key))
+                    .and_then(|spec| spec.constraint.as_ref());
+                if constraint.is_some_and(|c| !c.allows(value)) {
+                    return Err(transform_error(format!(
+                        "节点 {id} 的属性 {key} 不满足约束: {value}"
+                    )));
+                }
+            }
+            checked.push((id.clone(), new_values));
+        }
+        Ok(checked)
+    }
+}
+
+impl StepGeneric<NodePool, Schema> for BulkAttrStep {
+    fn name(&self) -> String {
+        "bulk_attr_step".to_string()
+    }
+
+    fn apply(
+        &self,
+        dart: &mut Tree,
+        schema: Arc<Schema>,
+    ) -> TransformResult<StepResult> {
+        // 先校验全部节点，保证要么全部成功要么不修改
+        let mut checked = self.validate(dart, &schema)?;
+        checked.retain(|(_, values)| !values.is_empty());
+        dart.update_attrs(checked)
+            .map_err(|e| transform_error(e.to_string()))?;
+        Ok(StepResult::ok())
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        serde_json::to_vec(self).ok()
+    }
+
+    fn invert(
+        &self,
+        dart: &Arc<Tree>,
+    ) -> Option<Arc<dyn StepGeneric<NodePool, Schema>>> {
+        let reverts = invert_updates(&self.updates, dart)?;
+        Some(Arc::new(BulkAttrStep::new(reverts)))
+    }
+}
+
+/// 批量属性变更的反向值，没有可恢复的值时返回 `None`
+///
+/// 同一节点出现多次时，只记录其首次修改前的值
+pub(crate) fn invert_updates(
+    updates: &[(NodeId, HashTrieMapSync<String, Value>)],
+    dart: &Tree,
+) -> Option<Vec<(NodeId, HashTrieMapSync<String, Value>)>> {
+    let mut reverts: Vec<(NodeId, HashTrieMapSync<String, Value>)> = Vec::new();
+    let mut positions: HashMap<&NodeId, usize> = HashMap::new();
+    for (id, values) in updates {
+        let node = dart.get_node(id)?;
+        let index = *positions.entry(id).or_insert_with(|| {
+            reverts.push((id.clone(), HashTrieMapSync::new_sync()));
+            reverts.len() - 1
+        });
+        let revert_values = &mut reverts[index].1;
+        for (changed_key, _) in values.iter() {
+            if let Some(old_val) = node.attrs.get_safe(changed_key)
+                && !revert_values.contains_key(changed_key)
+            {
+                revert_values.insert_mut(changed_key.clone(), old_val.clone());
+            }
+        }
+    }
+    reverts.retain(|(_, values)| !values.is_empty());
+    if reverts.is_empty() { None } else { Some(reverts) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node_step::AddNodeStep;
+    use mf_model::{
+        attrs::Attrs,
+        node::Node,
+        node_definition::{NodeSpec, NodeTree},
+        schema::{AttributeConstraint, AttributeSpec, SchemaSpec},
+    };
+    use mf_model::rpds::ht_map_sync;
+    use serde_json::json;
+
+    fn create_schema() -> Arc<Schema> {
+        let mut attrs = HashMap::new();
+        attrs.insert(
+            "level".to_string(),
+            AttributeSpec {
+                default: Some(json!(1)),
+                constraint: Some(AttributeConstraint {
+                    base: Some("integer".to_string()),
+                    max_inclusive: Some("9".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+        attrs.insert("name".to_string(), AttributeSpec::new(Some(json!(""))));
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            "doc".to_string(),
+            NodeSpec { attrs: Some(attrs), ..Default::default() },
+        );
+        let spec = SchemaSpec {
+            nodes,
+            marks: HashMap::new(),
+            top_node: Some("doc".to_string()),
+        };
+        Arc::new(Schema::compile(spec).expect("测试 Schema 编译失败"))
+    }
+
+    fn create_tree(schema: &Arc<Schema>) -> Tree {
+        let node = |id: &str| {
+            let attrs = Attrs::from(ht_map_sync! {
+                "level".to_string() => json!(1),
+                "name".to_string() => json!("")
+            });
+            Node::new(id, "doc".to_string(), attrs, vec![], vec![])
+        };
+        let mut tree = Tree::new(node("doc"));
+        AddNodeStep::new(
+            "doc".into(),
+            vec![NodeTree(node("a"), vec![]), NodeTree(node("b"), vec![])],
+        )
+        .apply(&mut tree, schema.clone())
+        .unwrap();
+        tree
+    }
+
+    #[test]
+    fn bulk_attr_step_apply_and_invert() {
+        let schema = create_schema();
+        let mut tree = create_tree(&schema);
+        let step = BulkAttrStep::new(vec![
+            ("a".into(), ht_map_sync! { "name".to_string() => json!("x") }),
+            ("b".into(), ht_map_sync! { "level".to_string() => json!(3) }),
+            ("a".into(), ht_map_sync! { "name".to_string() => json!("y") }),
+        ]);
+        let before = Arc::new(tree.clone());
+        let inverse = step.invert(&before).unwrap();
+        step.apply(&mut tree, schema.clone()).unwrap();
+
+        let a = tree.get_node(&"a".into()).unwrap();
+        let b = tree.get_node(&"b".into()).unwrap();
+        assert_eq!(a.attrs.get_safe("name"), Some(&json!("y")));
+        assert_eq!(b.attrs.get_safe("level"), Some(&json!(3)));
+
+        inverse.apply(&mut tree, schema).unwrap();
+        let a = tree.get_node(&"a".into()).unwrap();
+        let b = tree.get_node(&"b".into()).unwrap();
+        assert_eq!(a.attrs.get_safe("name"), Some(&json!("")));
+        assert_eq!(b.attrs.get_safe("level"), Some(&json!(1)));
+    }
+
+    #[test]
+    fn bulk_attr_step_serializes_runs() {
+        let rate = ht_map_sync! { "level".to_string() => json!(2) };
+        let step = BulkAttrStep::new(vec![
+            ("a".into(), rate.clone()),
+            ("b".into(), rate.clone()),
+            ("a".into(), ht_map_sync! { "name".to_string() => json!("x") }),
+            ("b".into(), rate),
+        ]);
+        let bytes = StepGeneric::serialize(&step).unwrap();
+        let wire: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(wire["runs"].as_array().unwrap().len(), 3);
+        assert_eq!(wire["runs"][0][0], json!(["a", "b"]));
+
+        let restored: BulkAttrStep = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(restored.updates, step.updates);
+    }
+
+    #[test]
+    fn bulk_attr_step_is_all_or_nothing() {
+        let schema = create_schema();
+        let mut tree = create_tree(&schema);
+        let step = BulkAttrStep::new(vec![
+            ("a".into(), ht_map_sync! { "level".to_string() => json!(2) }),
+            ("b".into(), ht_map_sync! { "level".to_string() => json!(99) }),
+        ]);
+        assert!(step.apply(&mut tree, schema.clone()).is_err());
+        let a = tree.get_node(&"a".into()).unwrap();
+        assert_eq!(a.attrs.get_safe("level"), Some(&json!(1)));
+
+        let missing = BulkAttrStep::new(vec![
+            ("a".into(), ht_map_sync! { "level".to_string() => json!(2) }),
+            ("zz".into(), ht_map_sync! { "level".to_string() => json!(2) }),
+        ]);
+        assert!(missing.apply(&mut tree, schema).is_err());
+        let a = tree.get_node(&"a".into()).unwrap();
+        assert_eq!(a.attrs.get_safe("level"), Some(&json!(1)));
+    }
+}
@@ -6,17 +6,64 @@ use crate::{transform_error, TransformResult};
 
 use super::step::{StepGeneric, StepResult};
 
+/// 默认允许的批量步骤最大嵌套深度
+pub const DEFAULT_MAX_BATCH_DEPTH: usize = 8;
+
 /// 批量步骤：将多个 Step 作为一个原子单元执行
 /// - 成功：全部子步骤成功应用
 /// - 失败：自动回滚已应用的子步骤，保证草稿一致性
+/// - 子步骤可以是 BatchStep，形成分层分组，每组作为一个整体回滚
 #[derive(Debug, Clone)]
 pub struct BatchStep {
     pub steps: Vec<Arc<dyn StepGeneric<NodePool, Schema>>>,
+    /// 最大嵌套深度，只包含普通步骤的批量步骤深度为 1
+    pub max_batch_depth: usize,
 }
 
 impl BatchStep {
     pub fn new(steps: Vec<Arc<dyn StepGeneric<NodePool, Schema>>>) -> Self {
-        Self { steps }
+        Self { steps, max_batch_depth: DEFAULT_MAX_BATCH_DEPTH }
+    }
+
+    pub fn with_max_batch_depth(
+        mut self,
+        max_batch_depth: usize,
+    ) -> Self {
+        self.max_batch_depth = max_batch_depth;
+        self
+    }
+
+    /// 计算嵌套深度，超过 `limit` 时提前返回 `limit + 1`
+    ///
+    /// 使用显式栈遍历，避免深度嵌套时递归导致栈溢出
+    pub fn depth(
+        &self,
+        limit: usize,
+    ) -> usize {
+        let mut max = 0;
+        let mut stack = vec![(self, 1)];
+        while let Some((batch, depth)) = stack.pop() {
+            max = max.max(depth);
+            if max > limit {
+                return limit + 1;
+            }
+            for step in &batch.steps {
+                if let Some(inner) = step.downcast_ref::<BatchStep>() {
+                    stack.push((inner, depth + 1));
+                }
+            }
+        }
+        max
+    }
+
+    pub(crate) fn check_depth(&self) -> TransformResult<()> {
+        if self.depth(self.max_batch_depth) > self.max_batch_depth {
+            return Err(transform_error(format!(
+                "批量步骤嵌套深度超过上限 {}",
+                self.max_batch_depth
+            )));
+        }
+        Ok(())
     }
 }
 
@@ -30,6 +77,7 @@ impl StepGeneric<NodePool, Schema> for BatchStep {
         dart: &mut Tree,
         schema: Arc<Schema>,
     ) -> TransformResult<StepResult> {
+        self.check_depth()?;
         // 预先为每个子步骤生成回滚步骤（基于应用前的快照）
         // 注意：为保证回滚正确性，这里在应用每个子步骤前都记录一次基线
         let mut inverses: Vec<Arc<dyn StepGeneric<NodePool, Schema>>> =
@@ -83,6 +131,10 @@ impl StepGeneric<NodePool, Schema> for BatchStep {
     ) -> Option<Arc<dyn StepGeneric<NodePool, Schema>>> {
         // 简化策略：对每个子步骤都基于同一基线计算反向，并逆序封装
         // 注意：这与 Transform::apply_steps_batch 的预处理策略保持一致
+        // 嵌套的批量步骤递归取反，撤销时最内层的分组先整体回滚
+        if self.check_depth().is_err() {
+            return None;
+        }
         let mut invs: Vec<Arc<dyn StepGeneric<NodePool, Schema>>> = Vec::new();
         for step in &self.steps {
             if let Some(inv) = step.invert(dart) {
@@ -93,7 +145,9 @@ impl StepGeneric<NodePool, Schema> for BatchStep {
             None
         } else {
             invs.reverse();
-            Some(Arc::new(BatchStep::new(invs)))
+            Some(Arc::new(
+                BatchStep::new(invs).with_max_batch_depth(self.max_batch_depth),
+            ))
         }
     }
 }
@@ -123,6 +177,7 @@ mod tests {
                 group: None,
                 desc: None,
                 attrs: None,
+                sort_by: None,
             },
         );
         let spec = SchemaSpec {
@@ -171,4 +226,51 @@ mod tests {
         let r = inv.apply(&mut tree, schema);
         assert!(r.is_ok());
     }
+
+    #[test]
+    fn nested_batch_step_and_depth_limit() {
+        let schema = create_schema();
+        let root = Node::new(
+            "doc",
+            "doc".to_string(),
+            Attrs::default(),
+            vec![],
+            vec![],
+        );
+        let mut tree = Tree::new(root);
+        let add = |id: &str| -> Arc<dyn StepGeneric<NodePool, Schema>> {
+            let node = Node::new(
+                id,
+                "doc".to_string(),
+                Attrs::default(),
+                vec![],
+                vec![],
+            );
+            Arc::new(AddNodeStep::new(
+                "doc".into(),
+                vec![NodeTree(node, vec![])],
+            ))
+        };
+
+        let inner = Arc::new(BatchStep::new(vec![add("n1"), add("n2")]));
+        let outer = BatchStep::new(vec![inner, add("n3")]);
+        assert_eq!(outer.depth(DEFAULT_MAX_BATCH_DEPTH), 2);
+        let inv = outer.invert(&Arc::new(tree.clone())).unwrap();
+        assert!(outer.apply(&mut tree, schema.clone()).is_ok());
+        assert!(tree.get_node(&"n1".into()).is_some());
+        assert!(tree.get_node(&"n3".into()).is_some());
+        assert!(inv.apply(&mut tree, schema.clone()).is_ok());
+        assert!(tree.get_node(&"n1".into()).is_none());
+        assert!(tree.get_node(&"n3".into()).is_none());
+
+        // 超过嵌套上限的批量步骤被拒绝
+        let mut deep = BatchStep::new(vec![add("n4")]).with_max_batch_depth(3);
+        for _ in 0..3 {
+            deep = BatchStep::new(vec![Arc::new(deep)]).with_max_batch_depth(3);
+        }
+        assert_eq!(deep.depth(3), 4);
+        assert!(deep.apply(&mut tree, schema).is_err());
+        assert!(deep.invert(&Arc::new(tree.clone())).is_none());
+        assert!(tree.get_node(&"n4".into()).is_none());
+    }
 }
@@ -0,0 +1,940 @@
+//! 事务冲突检测与变基
+//!
+//! 针对基于同一基础文档并发构建的两个事务：
+//! - `conflicts_with`：分析两组步骤的作用目标，给出结构化的冲突列表
+//! - `rebase`：在无冲突的情况下，将一个事务的步骤重放到另一个事务之后
+//! - `merge`：三方合并，将本地事务与变基后的远程事务合并为一个事务
+//!
+//! 插入位置使用"锚点"描述：插入到锚点节点之前，锚点为 `None` 表示追加到末尾。
+//! 变基时按锚点在新文档中的位置重新计算下标，从而处理兄弟节点的下标偏移。
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use mf_error_codes::{ErrorCode, ErrorWire, ToWire};
+use mf_model::{node_pool::NodePool, schema::Schema, types::NodeId};
+use serde_json::Value;
+
+use crate::{
+    attr_step::AttrStep,
+    batch_step::BatchStep,
+    mark_step::{AddMarkStep, RemoveMarkStep},
+    node_step::{AddNodeStep, MoveNodeStep, RemoveNodeStep},
+    step::StepGeneric,
+    transform::Transform,
+};
+
+type DynStep = Arc<dyn StepGeneric<NodePool, Schema>>;
+
+/// 冲突类型
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConflictKind {
+    /// 双方将同一节点的同一属性设置为不同的值
+    AttrOverlap { node_id: NodeId, key: String },
+    /// 双方修改同一节点上同一类型的 mark
+    MarkOverlap { node_id: NodeId, mark_type: String },
+    /// 一方删除了另一方修改、移动或插入目标所在的节点（含子树）
+    RemovedTarget { node_id: NodeId },
+    /// 双方删除了同一节点
+    BothRemoved { node_id: NodeId },
+    /// 双方移动了同一节点
+    BothMoved { node_id: NodeId },
+    /// 双方在同一父节点的同一位置插入，结果顺序依赖应用次序
+    ConcurrentInsert { parent_id: NodeId, anchor: Option<NodeId> },
+    /// 一方插入所依赖的锚点节点被另一方移动或删除
+    AnchorChanged { parent_id: NodeId, anchor: NodeId },
+}
+
+/// 一对冲突的步骤（下标为各自事务 `steps` 中的顶层下标）
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct StepConflict {
+    pub a_step: usize,
+    pub b_step: usize,
+    pub kind: ConflictKind,
+}
+
+/// 冲突分析报告
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConflictReport {
+    /// 冲突的步骤对
+    pub conflicts: Vec<StepConflict>,
+    /// a 中无法分析的步骤（未知的 Step 类型）
+    pub unknown_a: Vec<usize>,
+    /// b 中无法分析的步骤（未知的 Step 类型）
+    pub unknown_b: Vec<usize>,
+}
+
+impl ConflictReport {
+    /// 两个事务是否可交换（无冲突且所有步骤都可分析）
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+            && self.unknown_a.is_empty()
+            && self.unknown_b.is_empty()
+    }
+
+    /// 交换 a/b 视角
+    pub fn swapped(&self) -> Self {
+        let mut conflicts: Vec<StepConflict> = self
+            .conflicts
+            .iter()
+            .map(|c| StepConflict {
+                a_step: c.b_step,
+                b_step: c.a_step,
+                kind: c.kind.clone(),
+            })
+            .collect();
+        conflicts.sort();
+        ConflictReport {
+            conflicts,
+            unknown_a: self.unknown_b.clone(),
+            unknown_b: self.unknown_a.clone(),
+        }
+    }
+}
+
+/// 变基错误
+#[derive(Debug, Clone)]
+pub enum RebaseError {
+    /// 两个事务存在冲突，需要调用方处理
+    Conflicts(ConflictReport),
+    /// 变基后的步骤应用失败
+    Apply { step: usize, message: String },
+}
+
+impl fmt::Display for RebaseError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            RebaseError::Conflicts(report) => write!(
+                f,
+                "事务变基失败: 存在 {} 处冲突，{} 个无法分析的步骤",
+                report.conflicts.len(),
+                report.unknown_a.len() + report.unknown_b.len()
+            ),
+            RebaseError::Apply { step, message } => {
+                write!(
+                    f,
+                    "事务变基失败: 第 {} 个步骤应用失败: {}",
+                    step, message
+                )
+            },
+        }
+    }
+}
+
+impl std::error::Error for RebaseError {}
+
+impl ToWire for RebaseError {
+    fn to_wire(&self) -> ErrorWire {
+        match self {
+            RebaseError::Conflicts(report) => {
+                let conflicts: Vec<Value> = report
+                    .conflicts
+                    .iter()
+                    .map(|c| {
+                        serde_json::json!({
+                            "a_step": c.a_step,
+                            "b_step": c.b_step,
+                            "kind": format!("{:?}", c.kind),
+                        })
+                    })
+                    .collect();
+                ErrorWire::new(
+                    ErrorCode::TransformRebaseConflict,
+                    self.to_string(),
+                )
+                .with_detail("conflicts", conflicts)
+                .with_detail("unknown_a", report.unknown_a.clone())
+                .with_detail("unknown_b", report.unknown_b.clone())
+            },
+            RebaseError::Apply { step, .. } => ErrorWire::new(
+                ErrorCode::TransformRebaseApply,
+                self.to_string(),
+            )
+            .with_detail("step", *step),
+        }
+    }
+}
+
+/// 三方合并失败原因
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// 冲突的步骤对（`a_step` 为本地事务下标，`b_step` 为远程事务下标）
+    pub conflicts: Vec<StepConflict>,
+    /// 本地事务中无法分析的步骤
+    pub unknown_local: Vec<usize>,
+    /// 远程事务中无法分析的步骤
+    pub unknown_remote: Vec<usize>,
+    /// 冲突以外的失败原因，如基础文档不一致、合并后的步骤应用失败
+    pub error: Option<String>,
+}
+
+impl MergeConflict {
+    fn from_error(message: impl Into<String>) -> Self {
+        MergeConflict { error: Some(message.into()), ..Default::default() }
+    }
+}
+
+impl From<ConflictReport> for MergeConflict {
+    fn from(report: ConflictReport) -> Self {
+        MergeConflict {
+            conflicts: report.conflicts,
+            unknown_local: report.unknown_a,
+            unknown_remote: report.unknown_b,
+            error: None,
+        }
+    }
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match &self.error {
+            Some(message) => write!(f, "事务合并失败: {}", message),
+            None => write!(
+                f,
+                "事务合并失败: 存在 {} 处冲突，{} 个无法分析的步骤",
+                self.conflicts.len(),
+                self.unknown_local.len() + self.unknown_remote.len()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MergeConflict {}
+
+/// 分析两个基于同一基础文档的事务是否冲突
+///
+/// 两个事务应当拥有相同的 `base_doc`，子树与锚点信息以各自的步骤执行过程为准。
+pub fn conflicts_with(
+    a: &Transform,
+    b: &Transform,
+) -> ConflictReport {
+    let (fa, unknown_a) = collect_footprints(a);
+    let (fb, unknown_b) = collect_footprints(b);
+
+    let mut conflicts = Vec::new();
+    for (i, x) in &fa {
+        for (j, y) in &fb {
+            for kind in compare(x, y) {
+                conflicts.push(StepConflict { a_step: *i, b_step: *j, kind });
+            }
+        }
+    }
+    // 排序去重，保证结果确定且与 a/b 的视角无关
+    conflicts.sort();
+    conflicts.dedup();
+
+    ConflictReport { conflicts, unknown_a, unknown_b }
+}
+
+/// 收集事务修改到的全部节点
+///
+/// 包括属性/mark 的目标、删除的子树、移动的节点以及子节点发生变化的父节点。
+/// 事务包含无法分析的步骤时返回 `None`，调用方应视为可能修改任意节点。
+pub fn touched_nodes(tr: &Transform) -> Option<HashSet<NodeId>> {
+    let (footprints, unknown) = collect_footprints(tr);
+    if !unknown.is_empty() {
+        return None;
+    }
+    let mut touched = HashSet::new();
+    for (_, footprint) in footprints {
+        touched.extend(footprint.attrs.into_iter().map(|(id, _, _)| id));
+        touched.extend(footprint.marks.into_iter().map(|(id, _)| id));
+        touched.extend(footprint.removed_subtree);
+        touched.extend(footprint.moved);
+        touched.extend(footprint.inserts.into_iter().map(|(parent, _)| parent));
+        touched.extend(footprint.requires);
+    }
+    Some(touched)
+}
+
+/// 将 `tr` 的步骤变基到 `over` 之后
+///
+/// 返回的 Transform 以 `over.doc()` 为基础文档。仅处理可交换的情况：
+/// 目标不相交、兄弟节点插入的下标偏移；存在冲突时返回 `RebaseError::Conflicts`。
+pub fn rebase(
+    tr: &Transform,
+    over: &Transform,
+) -> Result<Transform, RebaseError> {
+    let report = conflicts_with(tr, over);
+    if !report.is_clean() {
+        return Err(RebaseError::Conflicts(report));
+    }
+
+    let mut replay = Transform::new(tr.base_doc.clone(), tr.schema.clone());
+    let mut out = Transform::new(over.doc(), tr.schema.clone());
+    for (index, step) in tr.steps.iter().enumerate() {
+        let rebased = rebase_step(step, &replay, &out)
+            .map_err(|message| RebaseError::Apply { step: index, message })?;
+        replay.step(step.clone()).map_err(|e| RebaseError::Apply {
+            step: index,
+            message: e.to_string(),
+        })?;
+        out.step(rebased).map_err(|e| RebaseError::Apply {
+            step: index,
+            message: e.to_string(),
+        })?;
+    }
+    Ok(out)
+}
+
+/// 三方合并：`local` 与 `remote` 都基于 `base` 构建
+///
+/// 结果以 `base` 为基础文档，先包含本地步骤，再包含变基到本地之后的远程步骤。
+/// 只有两组步骤可交换时才会合并，因此交换 `local` 与 `remote` 得到的文档一致，
+/// 各客户端按任意顺序合并都能收敛；存在冲突时全部列在 `MergeConflict` 中。
+pub fn merge(
+    base: &Arc<NodePool>,
+    local: &Transform,
+    remote: &Transform,
+) -> Result<Transform, MergeConflict> {
+    let same_base =
+        |doc: &Arc<NodePool>| Arc::ptr_eq(doc, base) || **doc == **base;
+    if !same_base(&local.base_doc) || !same_base(&remote.base_doc) {
+        return Err(MergeConflict::from_error(
+            "事务不是基于同一基础文档构建的",
+        ));
+    }
+
+    let report = conflicts_with(local, remote);
+    if !report.is_clean() {
+        return Err(report.into());
+    }
+    let rebased = rebase(remote, local).map_err(|e| match e {
+        RebaseError::Conflicts(report) => MergeConflict::from(report.swapped()),
+        RebaseError::Apply { .. } => MergeConflict::from_error(e.to_string()),
+    })?;
+
+    let mut merged = local.clone();
+    for (index, step) in rebased.steps.iter().enumerate() {
+        merged.step(step.clone()).map_err(|e| {
+            MergeConflict::from_error(format!(
+                "远程第 {} 个步骤应用失败: {}",
+                index, e
+            ))
+        })?;
+    }
+    Ok(merged)
+}
+
+/// 按原文档（`replay`）与新文档（`out`）重新计算步骤的插入位置
+fn rebase_step(
+    step: &DynStep,
+    replay: &Transform,
+    out: &Transform,
+) -> Result<DynStep, String> {
+    if let Some(s) = step.downcast_ref::<MoveNodeStep>() {
+        let anchor = move_anchor(&replay.doc(), s);
+        let position = match anchor {
+            None => None,
+            Some(anchor) => {
+                let siblings = siblings_without(
+                    &out.doc(),
+                    &s.target_parent_id,
+                    &s.node_id,
+                );
+                let index =
+                    siblings.iter().position(|id| id == &anchor).ok_or_else(
+                        || format!("锚点节点 {} 已不在目标父节点中", anchor),
+                    )?;
+                Some(index)
+            },
+        };
+        return Ok(Arc::new(MoveNodeStep::new(
+            s.source_parent_id.clone(),
+            s.target_parent_id.clone(),
+            s.node_id.clone(),
+            position,
+        )));
+    }
+    if let Some(batch) = step.downcast_ref::<BatchStep>() {
+        // 沿用调用方设置的嵌套深度上限，超出时不再递归
+        batch.check_depth().map_err(|e| e.to_string())?;
+        let mut replay = replay.clone();
+        let mut out = out.clone();
+        let mut steps = Vec::with_capacity(batch.steps.len());
+        for inner in &batch.steps {
+            let rebased = rebase_step(inner, &replay, &out)?;
+            replay.step(inner.clone()).map_err(|e| e.to_string())?;
+            out.step(rebased.clone()).map_err(|e| e.to_string())?;
+            steps.push(rebased);
+        }
+        return Ok(Arc::new(
+            BatchStep::new(steps).with_max_batch_depth(batch.max_batch_depth),
+        ));
+    }
+    Ok(step.clone())
+}
+
+/// 单个步骤的作用目标
+#[derive(Debug, Default)]
+struct Footprint {
+    /// 设置的属性 (节点, 键, 值)
+    attrs: Vec<(NodeId, String, Value)>,
+    /// 修改的 mark (节点, mark 类型)
+    marks: Vec<(NodeId, String)>,
+    /// 显式删除的节点
+    removed: Vec<NodeId>,
+    /// 被删除的全部节点（含子树）
+    removed_subtree: HashSet<NodeId>,
+    /// 移动的节点
+    moved: Vec<NodeId>,
+    /// 插入 (父节点, 锚点)
+    inserts: Vec<(NodeId, Option<NodeId>)>,
+    /// 需要存在的节点（修改、移动目标、插入父节点等）
+    requires: Vec<NodeId>,
+}
+
+/// 逐步重放事务，收集每个顶层步骤的作用目标
+fn collect_footprints(tr: &Transform) -> (Vec<(usize, Footprint)>, Vec<usize>) {
+    let mut replay = Transform::new(tr.base_doc.clone(), tr.schema.clone());
+    let mut footprints = Vec::with_capacity(tr.steps.len());
+    let mut unknown = Vec::new();
+
+    for (index, step) in tr.steps.iter().enumerate() {
+        let mut footprint = Footprint::default();
+        if !fill_footprint(step, &replay, &mut footprint) {
+            unknown.push(index);
+        }
+        footprints.push((index, footprint));
+        // 重放失败说明事务本身不完整，后续步骤的锚点以最近的成功状态为准
+        let _ = replay.step(step.clone());
+    }
+    (footprints, unknown)
+}
+
+/// 填充步骤的作用目标，未知的步骤类型返回 false
+fn fill_footprint(
+    step: &DynStep,
+    replay: &Transform,
+    footprint: &mut Footprint,
+) -> bool {
+    if let Some(s) = step.downcast_ref::<AttrStep>() {
+        for (key, value) in s.values.iter() {
+            footprint.attrs.push((s.id.clone(), key.clone(), value.clone()));
+        }
+        footprint.requires.push(s.id.clone());
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<AddMarkStep>() {
+        for mark in &s.marks {
+            footprint.marks.push((s.id.clone(), mark.r#type.clone()));
+        }
+        footprint.requires.push(s.id.clone());
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<RemoveMarkStep>() {
+        for mark_type in &s.mark_types {
+            footprint.marks.push((s.id.clone(), mark_type.clone()));
+        }
+        footprint.requires.push(s.id.clone());
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<AddNodeStep>() {
+        footprint.inserts.push((s.parent_id.clone(), None));
+        footprint.requires.push(s.parent_id.clone());
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<RemoveNodeStep>() {
+        let doc = replay.doc();
+        for id in &s.node_ids {
+            footprint.removed.push(id.clone());
+            footprint.removed_subtree.insert(id.clone());
+            for node in doc.descendants(id) {
+                footprint.removed_subtree.insert(node.id.clone());
+            }
+        }
+        footprint.requires.push(s.parent_id.clone());
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<MoveNodeStep>() {
+        let anchor = move_anchor(&replay.doc(), s);
+        footprint.moved.push(s.node_id.clone());
+        footprint.inserts.push((s.target_parent_id.clone(), anchor));
+        footprint.requires.push(s.node_id.clone());
+        footprint.requires.push(s.source_parent_id.clone());
+        footprint.requires.push(s.target_parent_id.clone());
+        return true;
+    }
+    if let Some(batch) = step.downcast_ref::<BatchStep>() {
+        if batch.check_depth().is_err() {
+            return false;
+        }
+        let mut replay = replay.clone();
+        let mut known = true;
+        for inner in &batch.steps {
+            known &= fill_footprint(inner, &replay, footprint);
+            let _ = replay.step(inner.clone());
+        }
+        return known;
+    }
+    false
+}
+
+/// 比较两个步骤的作用目标
+fn compare(
+    x: &Footprint,
+    y: &Footprint,
+) -> Vec<ConflictKind> {
+    let mut kinds = Vec::new();
+
+    for (node_id, key, value) in &x.attrs {
+        let clash = y
+            .attrs
+            .iter()
+            .any(|(n, k, v)| n == node_id && k == key && v != value);
+        if clash {
+            kinds.push(ConflictKind::AttrOverlap {
+                node_id: node_id.clone(),
+                key: key.clone(),
+            });
+        }
+    }
+
+    for (node_id, mark_type) in &x.marks {
+        if y.marks.iter().any(|(n, t)| n == node_id && t == mark_type) {
+            kinds.push(ConflictKind::MarkOverlap {
+                node_id: node_id.clone(),
+                mark_type: mark_type.clone(),
+            });
+        }
+    }
+
+    for id in &x.removed {
+        if y.removed.contains(id) {
+            kinds.push(ConflictKind::BothRemoved { node_id: id.clone() });
+        }
+    }
+    for (own, other) in [(x, y), (y, x)] {
+        for id in &own.requires {
+            if other.removed_subtree.contains(id) {
+                kinds.push(ConflictKind::RemovedTarget { node_id: id.clone() });
+            }
+        }
+        // 删除的子树中包含对方显式删除的节点之外的节点
+        for id in &own.removed {
+            if !other.removed.contains(id) && other.removed_subtree.contains(id)
+            {
+                kinds.push(ConflictKind::RemovedTarget { node_id: id.clone() });
+            }
+        }
+        for (parent_id, anchor) in &own.inserts {
+            if let Some(anchor) = anchor
+                && (other.moved.contains(anchor)
+                    || other.removed_subtree.contains(anchor))
+            {
+                kinds.push(ConflictKind::AnchorChanged {
+                    parent_id: parent_id.clone(),
+                    anchor: anchor.clone(),
+                });
+            }
+        }
+    }
+
+    for id in &x.moved {
+        if y.moved.contains(id) {
+            kinds.push(ConflictKind::BothMoved { node_id: id.clone() });
+        }
+    }
+
+    for insert in &x.inserts {
+        if y.inserts.contains(insert) {
+            kinds.push(ConflictKind::ConcurrentInsert {
+                parent_id: insert.0.clone(),
+                anchor: insert.1.clone(),
+            });
+        }
+    }
+
+    kinds
+}
+
+/// 移动步骤的锚点：目标父节点（移除被移动节点后）在 position 处的子节点
+fn move_anchor(
+    doc: &NodePool,
+    step: &MoveNodeStep,
+) -> Option<NodeId> {
+    let position = step.position?;
+    siblings_without(doc, &step.target_parent_id, &step.node_id)
+        .get(position)
+        .cloned()
+}
+
+fn siblings_without(
+    doc: &NodePool,
+    parent_id: &NodeId,
+    node_id: &NodeId,
+) -> Vec<NodeId> {
+    doc.children(parent_id)
+        .map(|children| {
+            children.iter().filter(|id| *id != node_id).cloned().collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch_step::DEFAULT_MAX_BATCH_DEPTH;
+    use mf_model::{
+        attrs::Attrs,
+        node::Node,
+        node_definition::{NodeSpec, NodeTree},
+        rpds::HashTrieMapSync,
+        schema::{AttributeSpec, SchemaSpec},
+        tree::Tree,
+    };
+    use std::collections::HashMap;
+
+    const ITEMS: usize = 5;
+
+    fn create_test_schema() -> Arc<Schema> {
+        let mut attrs = HashMap::new();
+        attrs.insert("v".to_string(), AttributeSpec::new(Some(Value::from(0))));
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            "doc".to_string(),
+            NodeSpec {
+                content: Some("item*".to_string()),
+                marks: None,
+                group: None,
+                desc: None,
+                attrs: None,
+                sort_by: None,
+            },
+        );
+        nodes.insert(
+            "item".to_string(),
+            NodeSpec {
+                content: None,
+                marks: None,
+                group: None,
+                desc: None,
+                attrs: Some(attrs),
+                sort_by: None,
+            },
+        );
+        let spec = SchemaSpec {
+            nodes,
+            marks: HashMap::new(),
+            top_node: Some("doc".to_string()),
+        };
+        Arc::new(Schema::compile(spec).expect("测试 Schema 编译失败"))
+    }
+
+    fn item(id: &str) -> Node {
+        Node::new(id, "item".to_string(), Attrs::default(), vec![], vec![])
+    }
+
+    fn create_test_doc() -> Arc<NodePool> {
+        let root = Node::new(
+            "root",
+            "doc".to_string(),
+            Attrs::default(),
+            vec![],
+            vec![],
+        );
+        let mut tree = Tree::new(root);
+        for i in 0..ITEMS {
+            tree.add_node(&"root".into(), &vec![item(&format!("n{i}"))])
+                .expect("测试中添加节点应该成功");
+        }
+        NodePool::new(Arc::new(tree))
+    }
+
+    fn attr_step(
+        id: &str,
+        value: i64,
+    ) -> DynStep {
+        let mut values = HashTrieMapSync::new_sync();
+        values.insert_mut("v".to_string(), Value::from(value));
+        Arc::new(AttrStep::new(id.into(), values))
+    }
+
+    fn move_step(
+        id: &str,
+        position: Option<usize>,
+    ) -> DynStep {
+        Arc::new(MoveNodeStep::new(
+            "root".into(),
+            "root".into(),
+            id.into(),
+            position,
+        ))
+    }
+
+    fn remove_step(id: &str) -> DynStep {
+        Arc::new(RemoveNodeStep::new("root".into(), vec![id.into()]))
+    }
+
+    fn add_step(id: &str) -> DynStep {
+        Arc::new(AddNodeStep::new(
+            "root".into(),
+            vec![NodeTree(item(id), vec![])],
+        ))
+    }
+
+    fn build(
+        doc: &Arc<NodePool>,
+        schema: &Arc<Schema>,
+        steps: Vec<DynStep>,
+    ) -> Transform {
+        let mut tr = Transform::new(doc.clone(), schema.clone());
+        for step in steps {
+            tr.step(step).expect("测试步骤应用失败");
+        }
+        tr
+    }
+
+    /// 文档快照：根节点子节点顺序 + 每个节点的属性 v
+    fn snapshot(doc: &NodePool) -> Vec<(String, Option<Value>)> {
+        doc.children(&"root".into())
+            .unwrap()
+            .iter()
+            .map(|id| {
+                let v =
+                    doc.get_node(id).and_then(|n| n.attrs.get("v").cloned());
+                (id.to_string(), v)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_disjoint_attrs_commute() {
+        let doc = create_test_doc();
+        let schema = create_test_schema();
+        let a = build(&doc, &schema, vec![attr_step("n0", 1)]);
+        let b = build(&doc, &schema, vec![attr_step("n1", 2)]);
+
+        let report = conflicts_with(&a, &b);
+        assert!(report.is_clean());
+
+        let rebased = rebase(&a, &b).unwrap();
+        let final_doc = rebased.doc();
+        assert_eq!(
+            final_doc.get_node(&"n0".into()).unwrap().attrs.get("v"),
+            Some(&Value::from(1))
+        );
+        assert_eq!(
+            final_doc.get_node(&"n1".into()).unwrap().attrs.get("v"),
+            Some(&Value::from(2))
+        );
+    }
+
+    #[test]
+    fn test_conflict_kinds() {
+        let doc = create_test_doc();
+        let schema = create_test_schema();
+
+        let a = build(&doc, &schema, vec![attr_step("n0", 1)]);
+        let b = build(&doc, &schema, vec![attr_step("n0", 2)]);
+        assert_eq!(
+            conflicts_with(&a, &b).conflicts[0].kind,
+            ConflictKind::AttrOverlap {
+                node_id: "n0".into(),
+                key: "v".to_string()
+            }
+        );
+
+        // 相同的值可以交换
+        let b = build(&doc, &schema, vec![attr_step("n0", 1)]);
+        assert!(conflicts_with(&a, &b).is_clean());
+
+        let b = build(&doc, &schema, vec![remove_step("n0")]);
+        assert_eq!(
+            conflicts_with(&a, &b).conflicts[0].kind,
+            ConflictKind::RemovedTarget { node_id: "n0".into() }
+        );
+
+        let a = build(&doc, &schema, vec![add_step("x")]);
+        let b = build(&doc, &schema, vec![add_step("y")]);
+        assert_eq!(
+            conflicts_with(&a, &b).conflicts[0].kind,
+            ConflictKind::ConcurrentInsert {
+                parent_id: "root".into(),
+                anchor: None
+            }
+        );
+
+        // a 插入到 n2 之前，b 删除了 n2
+        let a = build(&doc, &schema, vec![move_step("n4", Some(2))]);
+        let b = build(&doc, &schema, vec![remove_step("n2")]);
+        let report = conflicts_with(&a, &b);
+        assert_eq!(
+            report.conflicts[0].kind,
+            ConflictKind::AnchorChanged {
+                parent_id: "root".into(),
+                anchor: "n2".into()
+            }
+        );
+        assert!(matches!(rebase(&a, &b), Err(RebaseError::Conflicts(_))));
+        assert_eq!(conflicts_with(&b, &a), report.swapped());
+    }
+
+    #[test]
+    fn test_rebase_shifts_sibling_index() {
+        let doc = create_test_doc();
+        let schema = create_test_schema();
+        // a: 将 n4 移到 n3 之前；b: 删除 n0，n3 的下标左移
+        let a = build(&doc, &schema, vec![move_step("n4", Some(3))]);
+        let b = build(&doc, &schema, vec![remove_step("n0")]);
+
+        let rebased = rebase(&a, &b).unwrap();
+        let ids: Vec<String> =
+            snapshot(&rebased.doc()).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec!["n1", "n2", "n4", "n3"]);
+    }
+
+    #[test]
+    fn test_rebase_keeps_batch_depth_limit() {
+        let doc = create_test_doc();
+        let schema = create_test_schema();
+        // 嵌套深度超过默认上限，但在调用方设置的上限之内
+        let depth = DEFAULT_MAX_BATCH_DEPTH + 2;
+        let mut batch = BatchStep::new(vec![attr_step("n0", 1)])
+            .with_max_batch_depth(depth);
+        for _ in 1..depth {
+            batch = BatchStep::new(vec![Arc::new(batch)])
+                .with_max_batch_depth(depth);
+        }
+        let a = build(&doc, &schema, vec![Arc::new(batch)]);
+        let b = build(&doc, &schema, vec![attr_step("n1", 2)]);
+
+        let rebased = rebase(&a, &b).unwrap();
+        let outer = rebased.steps[0].downcast_ref::<BatchStep>().unwrap();
+        assert_eq!(outer.max_batch_depth, depth);
+        assert_eq!(outer.depth(depth), depth);
+        assert_eq!(
+            rebased.doc().get_node(&"n0".into()).unwrap().attrs.get("v"),
+            Some(&Value::from(1))
+        );
+    }
+
+    #[test]
+    fn test_merge_combines_and_reports() {
+        let doc = create_test_doc();
+        let schema = create_test_schema();
+        let local = build(&doc, &schema, vec![attr_step("n0", 1)]);
+        let remote =
+            build(&doc, &schema, vec![remove_step("n1"), attr_step("n2", 2)]);
+
+        let merged = merge(&doc, &local, &remote).unwrap();
+        assert_eq!(merged.steps.len(), 3);
+        assert!(Arc::ptr_eq(&merged.base_doc, &doc));
+        // 交换本地与远程，结果文档一致
+        let swapped = merge(&doc, &remote, &local).unwrap();
+        assert_eq!(snapshot(&merged.doc()), snapshot(&swapped.doc()));
+        assert_eq!(
+            snapshot(&merged.doc()),
+            vec![
+                ("n0".to_string(), Some(Value::from(1))),
+                ("n2".to_string(), Some(Value::from(2))),
+                ("n3".to_string(), None),
+                ("n4".to_string(), None),
+            ]
+        );
+
+        let remote = build(&doc, &schema, vec![attr_step("n0", 2)]);
+        let err = merge(&doc, &local, &remote).unwrap_err();
+        assert_eq!(err.conflicts, conflicts_with(&local, &remote).conflicts);
+        assert!(err.error.is_none());
+
+        let other = build(&local.doc(), &schema, vec![attr_step("n1", 3)]);
+        assert!(merge(&doc, &local, &other).unwrap_err().error.is_some());
+    }
+
+    /// 简单的线性同余随机数，保证测试可复现
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(
+            &mut self,
+            bound: usize,
+        ) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((self.0 >> 33) as usize) % bound
+        }
+    }
+
+    fn random_steps(
+        rng: &mut Lcg,
+        doc: &Arc<NodePool>,
+        schema: &Arc<Schema>,
+        side: &str,
+    ) -> Transform {
+        let mut tr = Transform::new(doc.clone(), schema.clone());
+        let count = 1 + rng.next(3);
+        for n in 0..count {
+            let children: Vec<NodeId> = tr
+                .doc()
+                .children(&"root".into())
+                .unwrap()
+                .iter()
+                .cloned()
+                .collect();
+            if children.is_empty() {
+                break;
+            }
+            let target = children[rng.next(children.len())].to_string();
+            let step = match rng.next(4) {
+                0 => attr_step(&target, rng.next(3) as i64),
+                1 => {
+                    let position = rng.next(children.len() + 1);
+                    move_step(
+                        &target,
+                        (position < children.len()).then_some(position),
+                    )
+                },
+                2 => remove_step(&target),
+                _ => add_step(&format!("{side}{n}")),
+            };
+            let _ = tr.step(step);
+        }
+        tr
+    }
+
+    #[test]
+    fn test_random_interleavings_converge() {
+        let doc = create_test_doc();
+        let schema = create_test_schema();
+        let mut rng = Lcg(42);
+        let mut clean = 0;
+
+        for _ in 0..500 {
+            let a = random_steps(&mut rng, &doc, &schema, "a");
+            let b = random_steps(&mut rng, &doc, &schema, "b");
+
+            let report = conflicts_with(&a, &b);
+            // 报告与视角无关，且结果确定
+            assert_eq!(conflicts_with(&b, &a), report.swapped());
+            assert_eq!(conflicts_with(&a, &b), report);
+            if !report.is_clean() {
+                continue;
+            }
+            clean += 1;
+
+            // a 然后 b'，与 b 然后 a' 的结果必须一致
+            let b_over_a = rebase(&b, &a).expect("无冲突时变基应成功");
+            let a_over_b = rebase(&a, &b).expect("无冲突时变基应成功");
+            assert_eq!(
+                snapshot(&b_over_a.doc()),
+                snapshot(&a_over_b.doc()),
+                "a: {:?}\nb: {:?}",
+                a.steps,
+                b.steps
+            );
+            let merged = merge(&doc, &a, &b).expect("无冲突时合并应成功");
+            assert_eq!(snapshot(&merged.doc()), snapshot(&a_over_b.doc()));
+        }
+        assert!(clean > 50, "随机模型中无冲突的样本过少: {clean}");
+    }
+}
@@ -0,0 +1,540 @@
+//! 派生属性重算
+//!
+//! 节点类型在 schema 中声明派生属性（见 [`mf_model::derived`]）后，
+//! 事务提交时由 [`Transform::recompute_derived`] 生成一个 [`DerivedAttrStep`]：
+//! - 只从本次修改到的节点出发，沿表达式引用的 `parent.*` / `children.*` 传播
+//! - 深的节点先计算，子节点聚合自下而上完成
+//! - 值没有变化的节点不再继续传播
+//!
+//! 普通的 `AttrStep` / `BulkAttrStep` 不能修改派生属性。
+
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
+
+use mf_model::{
+    node_pool::NodePool, rpds::HashTrieMapSync, schema::Schema, tree::Tree,
+    types::NodeId,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    attr_step::{invert_updates, AttrStep, BulkAttrStep},
+    batch_step::BatchStep,
+    mark_step::{AddMarkStep, RemoveMarkStep},
+    node_step::{
+        AddNodeStep, MoveNodeStep, RemoveNodeStep, ReorderChildrenStep,
+    },
+    step::{StepGeneric, StepResult},
+    transform::Transform,
+    transform_error, TransformResult,
+};
+
+type DynStep = Arc<dyn StepGeneric<NodePool, Schema>>;
+
+/// 单个节点在一次重算中最多被计算的次数，超过视为父子之间存在循环依赖
+const MAX_EVALUATIONS: usize = 32;
+
+/// 写入派生属性的步骤，只由重算流程生成
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DerivedAttrStep {
+    pub updates: Vec<(NodeId, HashTrieMapSync<String, Value>)>,
+}
+
+impl DerivedAttrStep {
+    pub fn new(updates: Vec<(NodeId, HashTrieMapSync<String, Value>)>) -> Self {
+        DerivedAttrStep { updates }
+    }
+}
+
+impl StepGeneric<NodePool, Schema> for DerivedAttrStep {
+    fn name(&self) -> String {
+        "derived_attr_step".to_string()
+    }
+
+    fn apply(
+        &self,
+        dart: &mut Tree,
+        schema: Arc<Schema>,
+    ) -> TransformResult<StepResult> {
+        let _ = schema;
+
+        dart.update_attrs(self.updates.clone())
+            .map_err(|e| transform_error(e.to_string()))?;
+        Ok(StepResult::ok())
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        serde_json::to_vec(self).ok()
+    }
+
+    fn invert(
+        &self,
+        dart: &Arc<Tree>,
+    ) -> Option<Arc<dyn StepGeneric<NodePool, Schema>>> {
+        let reverts = invert_updates(&self.updates, dart)?;
+        Some(Arc::new(DerivedAttrStep::new(reverts)))
+    }
+}
+
+/// 检查属性变更是否写入了派生属性
+pub(crate) fn check_not_derived(
+    schema: &Schema,
+    node_type: &str,
+    values: &HashTrieMapSync<String, Value>,
+) -> TransformResult<()> {
+    let Some(derived) = schema.derived_attrs(node_type) else {
+        return Ok(());
+    };
+    match values.keys().find(|key| derived.contains(key)) {
+        Some(key) => Err(transform_error(format!(
+            "属性 {key} 是节点类型 {node_type} 的派生属性，不能直接修改"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// 收集步骤修改到的节点（属性变化的节点、新增的节点、子节点变化的父节点）
+///
+/// 存在无法分析的步骤时返回 `None`，调用方应重算整棵树
+pub fn changed_nodes<'a>(
+    steps: impl IntoIterator<Item = &'a DynStep>
+) -> Option<HashSet<NodeId>> {
+    let mut changed = HashSet::new();
+    for step in steps {
+        if !collect_changed(step, &mut changed) {
+            return None;
+        }
+    }
+    Some(changed)
+}
+
+fn collect_changed(
+    step: &DynStep,
+    changed: &mut HashSet<NodeId>,
+) -> bool {
+    if let Some(s) = step.downcast_ref::<AttrStep>() {
+        changed.insert(s.id.clone());
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<BulkAttrStep>() {
+        changed.extend(s.updates.iter().map(|(id, _)| id.clone()));
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<AddNodeStep>() {
+        changed.insert(s.parent_id.clone());
+        for node in &s.nodes {
+            changed.extend(AddNodeStep::collect_node_ids(node));
+        }
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<RemoveNodeStep>() {
+        changed.insert(s.parent_id.clone());
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<MoveNodeStep>() {
+        changed.insert(s.source_parent_id.clone());
+        changed.insert(s.target_parent_id.clone());
+        changed.insert(s.node_id.clone());
+        return true;
+    }
+    if let Some(batch) = step.downcast_ref::<BatchStep>() {
+        return batch.steps.iter().all(|inner| collect_changed(inner, changed));
+    }
+    // 以下步骤不影响派生属性的取值：聚合与子节点顺序无关
+    step.downcast_ref::<ReorderChildrenStep>().is_some()
+        || step.downcast_ref::<AddMarkStep>().is_some()
+        || step.downcast_ref::<RemoveMarkStep>().is_some()
+        || step.downcast_ref::<DerivedAttrStep>().is_some()
+}
+
+/// 派生属性的更新，见 [`derived_updates_counted`]
+pub type DerivedUpdates = Vec<(NodeId, HashTrieMapSync<String, Value>)>;
+
+/// 计算派生属性的更新
+///
+/// `changed` 为 `None` 时重算整棵树。返回的更新按首次变化的顺序排列，
+/// 每个节点只出现一次。
+pub fn derived_updates(
+    tree: &Tree,
+    schema: &Schema,
+    changed: Option<&HashSet<NodeId>>,
+) -> TransformResult<DerivedUpdates> {
+    derived_updates_counted(tree, schema, changed).map(|(updates, _)| updates)
+}
+
+/// 同 [`derived_updates`]，并返回对节点求值的总次数
+pub fn derived_updates_counted(
+    tree: &Tree,
+    schema: &Schema,
+    changed: Option<&HashSet<NodeId>>,
+) -> TransformResult<(DerivedUpdates, usize)> {
+    if !schema.has_derived_attrs() {
+        return Ok((Vec::new(), 0));
+    }
+    let mut work = tree.clone();
+    let mut queue = DirtyQueue::default();
+    match changed {
+        Some(changed) => {
+            for id in changed {
+                queue.push_affected(&work, schema, id, None);
+            }
+        },
+        None => {
+            let mut stack = vec![tree.root_id.clone()];
+            while let Some(id) = stack.pop() {
+                if let Some(children) = tree.children(&id) {
+                    stack.extend(children.iter().cloned());
+                }
+                queue.push(&work, id);
+            }
+        },
+    }
+
+    let mut updates: DerivedUpdates = Vec::new();
+    let mut positions: HashMap<NodeId, usize> = HashMap::new();
+    let mut evaluations: HashMap<NodeId, usize> = HashMap::new();
+    let mut total = 0;
+    while let Some(id) = queue.pop() {
+        let Some(node) = work.get_node(&id) else {
+            continue;
+        };
+        let Some(derived) = schema.derived_attrs(&node.r#type) else {
+            continue;
+        };
+        let count = evaluations.entry(id.clone()).or_default();
+        *count += 1;
+        total += 1;
+        if *count > MAX_EVALUATIONS {
+            return Err(transform_error(format!(
+                "节点 {id} 的派生属性无法收敛，请检查父子节点之间的循环依赖"
+            )));
+        }
+        let changes = {
+            let children = work.children_node(&id).unwrap_or_default();
+            let children: Vec<_> = children.iter().copied().collect();
+            derived.evaluate(node, work.get_parent_node(&id), &children)
+        };
+        if changes.is_empty() {
+            continue;
+        }
+
+        let names: HashSet<String> = changes.keys().cloned().collect();
+        work.update_attr(&id, changes.clone())
+            .map_err(|e| transform_error(e.to_string()))?;
+        match positions.get(&id) {
+            Some(&index) => {
+                for (key, value) in changes.iter() {
+                    updates[index].1.insert_mut(key.clone(), value.clone());
+                }
+            },
+            None => {
+                positions.insert(id.clone(), updates.len());
+                updates.push((id.clone(), changes));
+            },
+        }
+        queue.push_affected(&work, schema, &id, Some(&names));
+    }
+    Ok((updates, total))
+}
+
+/// 待重算的节点，深度大的先出队
+#[derive(Default)]
+struct DirtyQueue {
+    heap: BinaryHeap<(usize, NodeId)>,
+    queued: HashSet<NodeId>,
+    depths: HashMap<NodeId, usize>,
+}
+
+impl DirtyQueue {
+    fn depth(
+        &mut self,
+        tree: &Tree,
+        id: &NodeId,
+    ) -> usize {
+        if let Some(&depth) = self.depths.get(id) {
+            return depth;
+        }
+        let depth = match tree.get_parent_node(id) {
+            Some(parent) => {
+                let parent_id = parent.id.clone();
+                self.depth(tree, &parent_id) + 1
+            },
+            None => 0,
+        };
+        self.depths.insert(id.clone(), depth);
+        depth
+    }
+
+    fn push(
+        &mut self,
+        tree: &Tree,
+        id: NodeId,
+    ) {
+        if self.queued.insert(id.clone()) {
+            let depth = self.depth(tree, &id);
+            self.heap.push((depth, id));
+        }
+    }
+
+    fn pop(&mut self) -> Option<NodeId> {
+        let (_, id) = self.heap.pop()?;
+        self.queued.remove(&id);
+        Some(id)
+    }
+
+    /// 节点属性变化后需要重算的节点：自身、引用父节点属性的子节点、
+    /// 聚合子节点属性的父节点。`names` 为 `None` 表示任意属性都可能变化
+    fn push_affected(
+        &mut self,
+        tree: &Tree,
+        schema: &Schema,
+        id: &NodeId,
+        names: Option<&HashSet<String>>,
+    ) {
+        let Some(node) = tree.get_node(id) else {
+            return;
+        };
+        let uses = |deps: &std::collections::BTreeSet<String>| match names {
+            Some(names) => deps.iter().any(|dep| names.contains(dep)),
+            None => !deps.is_empty(),
+        };
+        if names.is_none() {
+            self.push(tree, id.clone());
+        }
+        for child in tree.children_node(id).unwrap_or_default().iter() {
+            if schema
+                .derived_attrs(&child.r#type)
+                .is_some_and(|derived| uses(derived.parent_deps()))
+            {
+                self.push(tree, child.id.clone());
+            }
+        }
+        if let Some(parent) = tree.get_parent_node(&node.id)
+            && schema
+                .derived_attrs(&parent.r#type)
+                .is_some_and(|derived| uses(derived.children_deps()))
+        {
+            self.push(tree, parent.id.clone());
+        }
+    }
+}
+
+impl Transform {
+    /// 重算派生属性，需要更新时追加一个 [`DerivedAttrStep`]
+    ///
+    /// `changed` 为其他事务修改到的节点（见 [`changed_nodes`]），
+    /// `None` 表示重算整棵树。返回更新的节点数
+    pub fn recompute_derived(
+        &mut self,
+        changed: Option<&HashSet<NodeId>>,
+    ) -> TransformResult<usize> {
+        let doc = self.doc();
+        let updates = derived_updates(doc.get_inner(), &self.schema, changed)?;
+        let count = updates.len();
+        if count > 0 {
+            self.step(Arc::new(DerivedAttrStep::new(updates)))?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mf_model::{
+        attrs::Attrs,
+        node::Node,
+        node_definition::{NodeSpec, NodeTree},
+        rpds::ht_map_sync,
+        schema::{AttributeSpec, SchemaSpec},
+    };
+    use serde_json::json;
+
+    fn attr(computed: Option<&str>) -> AttributeSpec {
+        AttributeSpec {
+            computed: computed.map(str::to_string),
+            ..AttributeSpec::new(Some(json!(0)))
+        }
+    }
+
+    /// doc(total) -> group(subtotal) -> item(amount = qty * price * parent.rate)
+    fn create_schema() -> Arc<Schema> {
+        let node = |content: &str, attrs: Vec<(&str, Option<&str>)>| NodeSpec {
+            content: Some(content.to_string()),
+            attrs: Some(
+                attrs
+                    .into_iter()
+                    .map(|(name, computed)| (name.to_string(), attr(computed)))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            "doc".to_string(),
+            node("group*", vec![("total", Some("sum(children.subtotal)"))]),
+        );
+        nodes.insert(
+            "group".to_string(),
+            node(
+                "item*",
+                vec![
+                    ("rate", None),
+                    ("subtotal", Some("sum(children.amount)")),
+                ],
+            ),
+        );
+        nodes.insert(
+            "item".to_string(),
+            node(
+                "",
+                vec![
+                    ("qty", None),
+                    ("price", None),
+                    ("amount", Some("qty * price * parent.rate")),
+                ],
+            ),
+        );
+        Arc::new(
+            Schema::compile(SchemaSpec {
+                nodes,
+                marks: HashMap::new(),
+                top_node: Some("doc".to_string()),
+            })
+            .unwrap(),
+        )
+    }
+
+    fn node(
+        id: &str,
+        r#type: &str,
+        attrs: HashTrieMapSync<String, Value>,
+    ) -> Node {
+        Node::new(id, r#type.to_string(), Attrs::from(attrs), vec![], vec![])
+    }
+
+    fn item(
+        id: &str,
+        qty: i64,
+    ) -> NodeTree {
+        NodeTree(
+            node(
+                id,
+                "item",
+                ht_map_sync! {
+                    "qty".to_string() => json!(qty),
+                    "price".to_string() => json!(10)
+                },
+            ),
+            vec![],
+        )
+    }
+
+    fn create_transform(schema: &Arc<Schema>) -> Transform {
+        let root = node("doc", "doc", HashTrieMapSync::new_sync());
+        let doc = NodePool::new(Arc::new(Tree::new(root)));
+        let mut tr = Transform::new(doc, schema.clone());
+        for (group, rate) in [("g1", 1), ("g2", 2)] {
+            let attrs = ht_map_sync! { "rate".to_string() => json!(rate) };
+            let items = (1..=2).map(|i| item(&format!("{group}-{i}"), i));
+            tr.step(Arc::new(AddNodeStep::new(
+                "doc".into(),
+                vec![NodeTree(node(group, "group", attrs), items.collect())],
+            )))
+            .unwrap();
+        }
+        tr
+    }
+
+    fn attr_of(
+        tr: &Transform,
+        id: &str,
+        name: &str,
+    ) -> Value {
+        tr.doc().get_node(&id.into()).unwrap().attrs[name].clone()
+    }
+
+    #[test]
+    fn recompute_bottom_up_after_changes() {
+        let schema = create_schema();
+        let mut tr = create_transform(&schema);
+        let changed = changed_nodes(tr.steps.iter()).unwrap();
+        assert_eq!(tr.recompute_derived(Some(&changed)).unwrap(), 7);
+        assert_eq!(attr_of(&tr, "g1-2", "amount"), json!(20));
+        assert_eq!(attr_of(&tr, "g1", "subtotal"), json!(30));
+        assert_eq!(attr_of(&tr, "g2", "subtotal"), json!(60));
+        assert_eq!(attr_of(&tr, "doc", "total"), json!(90));
+
+        // 只重算修改节点到根的路径
+        let step: DynStep = Arc::new(AttrStep::new(
+            "g1-1".into(),
+            ht_map_sync! { "qty".to_string() => json!(5) },
+        ));
+        tr.step(step.clone()).unwrap();
+        let changed = changed_nodes([&step]).unwrap();
+        let updates =
+            derived_updates(tr.doc().get_inner(), &schema, Some(&changed))
+                .unwrap();
+        let ids: Vec<&str> =
+            updates.iter().map(|(id, _)| id.as_ref()).collect();
+        assert_eq!(ids, vec!["g1-1", "g1", "doc"]);
+        tr.recompute_derived(Some(&changed)).unwrap();
+        assert_eq!(attr_of(&tr, "doc", "total"), json!(130));
+
+        // 父节点属性变化向下传播到子节点，再聚合回父节点
+        let step: DynStep = Arc::new(AttrStep::new(
+            "g2".into(),
+            ht_map_sync! { "rate".to_string() => json!(3) },
+        ));
+        tr.step(step.clone()).unwrap();
+        let changed = changed_nodes([&step]).unwrap();
+        tr.recompute_derived(Some(&changed)).unwrap();
+        assert_eq!(attr_of(&tr, "g2-2", "amount"), json!(60));
+        assert_eq!(attr_of(&tr, "g2", "subtotal"), json!(90));
+        assert_eq!(attr_of(&tr, "doc", "total"), json!(160));
+
+        // 删除节点后父节点重新聚合
+        let step: DynStep =
+            Arc::new(RemoveNodeStep::new("doc".into(), vec!["g2".into()]));
+        tr.step(step.clone()).unwrap();
+        let changed = changed_nodes([&step]).unwrap();
+        tr.recompute_derived(Some(&changed)).unwrap();
+        assert_eq!(attr_of(&tr, "doc", "total"), json!(70));
+
+        // 整棵树重算结果一致
+        assert!(
+            derived_updates(tr.doc().get_inner(), &schema, None)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn derived_attrs_are_read_only() {
+        let schema = create_schema();
+        let mut tr = create_transform(&schema);
+        let values = ht_map_sync! { "amount".to_string() => json!(1) };
+        let err = tr
+            .step(Arc::new(AttrStep::new("g1-1".into(), values.clone())))
+            .unwrap_err();
+        assert!(err.to_string().contains("派生属性"), "{err}");
+        assert!(
+            tr.step(Arc::new(BulkAttrStep::new(vec![(
+                "g1-1".into(),
+                values.clone()
+            )])))
+            .is_err()
+        );
+
+        // 派生属性步骤可以写入，反向步骤恢复原值
+        tr.recompute_derived(None).unwrap();
+        let before = Arc::new(tr.doc().get_inner().clone());
+        let step = DerivedAttrStep::new(vec![("g1-1".into(), values)]);
+        tr.step(Arc::new(step.clone())).unwrap();
+        assert_eq!(attr_of(&tr, "g1-1", "amount"), json!(1));
+        let inverted = step.invert(&before).unwrap();
+        tr.step(inverted).unwrap();
+        assert_eq!(attr_of(&tr, "g1-1", "amount"), json!(10));
+    }
+}
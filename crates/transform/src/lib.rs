@@ -9,10 +9,14 @@
 //! - 增量更新和内存优化
 //!
 //! 主要组件：
-//! - `attr_step`: 属性步骤，处理属性更新操作
+//! - `attr_step`: 属性步骤，处理单节点与批量属性更新操作
+//! - `conflict`: 冲突检测与变基，用于并发构建的事务
+//! - `derived`: 派生属性重算，维护 schema 声明的计算属性
 //! - `draft`: 草稿系统，管理文档的临时状态
+//! - `mark_guard`: 级联标记守卫，判断与拒绝对受保护子树的修改
 //! - `mark_step`: 标记步骤，处理标记的添加和删除
 //! - `node_step`: 节点步骤，处理节点的各种操作
+//! - `order`: 子节点自动排序，维护 schema 声明的 sort_by 顺序
 //! - `patch`: 补丁系统，用于增量更新
 //! - `step`: 步骤定义，定义转换操作的基本接口
 //! - `transform`: 转换系统，协调各种转换操作
@@ -29,8 +33,12 @@
 
 pub mod attr_step;
 pub mod batch_step;
+pub mod conflict;
+pub mod derived;
+pub mod mark_guard;
 pub mod mark_step;
 pub mod node_step;
+pub mod order;
 pub mod step;
 pub mod transform;
 use anyhow::Result;
@@ -38,12 +46,19 @@ use anyhow::Result;
 pub type TransformResult<T> = Result<T>;
 
 pub fn transform_error(msg: impl Into<String>) -> anyhow::Error {
-    anyhow::anyhow!("事务应用失败: {}", msg.into())
+    mf_error_codes::coded(
+        mf_error_codes::ErrorCode::TransformApply,
+        format!("事务应用失败: {}", msg.into()),
+    )
 }
 
 // 导出泛型类型
 pub use step::{StepGeneric, StepResult};
 pub use transform::{TransformGeneric, Transform};
+pub use conflict::{
+    conflicts_with, merge, rebase, touched_nodes, ConflictKind, ConflictReport,
+    MergeConflict, RebaseError, StepConflict,
+};
 
 // 导出具体 NodePool Step 实现
 pub use node_step::{
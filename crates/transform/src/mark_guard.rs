@@ -0,0 +1,265 @@
+//! 级联标记守卫
+//!
+//! 以级联标记（如"锁定"）保护子树：命令可用 [`is_guarded`] 在构建步骤前判断节点
+//! 是否受保护，插件可在 `filter_transaction` 中用 [`find_violation`] /
+//! [`check_guard`] 否决修改受保护子树的事务。
+//!
+//! 判断以事务的基础文档为准，即修改前的保护状态。只增删守卫标记本身的步骤
+//! 不视为修改，以便加锁与解锁；同一事务中先解锁再修改仍会被拒绝。
+
+use std::fmt;
+use std::sync::Arc;
+
+use mf_model::{node_pool::NodePool, schema::Schema, types::NodeId};
+
+use crate::{
+    attr_step::{AttrStep, BulkAttrStep},
+    batch_step::BatchStep,
+    derived::DerivedAttrStep,
+    mark_step::{AddMarkStep, RemoveMarkStep},
+    node_step::{
+        AddNodeStep, MoveNodeStep, RemoveNodeStep, ReorderChildrenStep,
+    },
+    step::StepGeneric,
+    transform::Transform,
+    transform_error, TransformResult,
+};
+
+type DynStep = Arc<dyn StepGeneric<NodePool, Schema>>;
+
+/// 节点自身或经祖先级联是否带有守卫标记
+pub fn is_guarded(
+    doc: &NodePool,
+    schema: &Schema,
+    node_id: &NodeId,
+    mark_type: &str,
+) -> bool {
+    doc.has_effective_mark(node_id, schema, mark_type)
+}
+
+/// 事务违反守卫的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardViolation {
+    /// 第 `step` 个顶层步骤修改了受保护的节点
+    Node { step: usize, node_id: NodeId },
+    /// 第 `step` 个顶层步骤无法分析，且文档中存在守卫标记
+    UnknownStep { step: usize },
+}
+
+impl fmt::Display for GuardViolation {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            GuardViolation::Node { step, node_id } => {
+                write!(f, "第 {} 个步骤修改了受保护的节点 {}", step, node_id)
+            },
+            GuardViolation::UnknownStep { step } => {
+                write!(f, "第 {} 个步骤无法分析，文档中存在受保护的节点", step)
+            },
+        }
+    }
+}
+
+/// 查找事务中第一个修改受保护节点的步骤
+///
+/// 属性、标记步骤检查目标节点；插入、删除、移动与重排检查父节点，
+/// 删除与移动还检查被删除或移动的节点本身。派生属性由系统重算，不受守卫限制。
+pub fn find_violation(
+    tr: &Transform,
+    mark_type: &str,
+) -> Option<GuardViolation> {
+    let doc = &tr.base_doc;
+    let schema = &tr.schema;
+    for (index, step) in tr.steps.iter().enumerate() {
+        let mut targets = Vec::new();
+        if !collect_targets(step, mark_type, &mut targets) {
+            let marked = doc.find_node(|node| {
+                node.marks.iter().any(|m| m.r#type == mark_type)
+            });
+            if marked.is_some() {
+                return Some(GuardViolation::UnknownStep { step: index });
+            }
+            continue;
+        }
+        if let Some(node_id) = targets
+            .into_iter()
+            .find(|id| is_guarded(doc, schema, id, mark_type))
+        {
+            return Some(GuardViolation::Node { step: index, node_id });
+        }
+    }
+    None
+}
+
+/// 事务修改了受保护的节点时返回错误
+pub fn check_guard(
+    tr: &Transform,
+    mark_type: &str,
+) -> TransformResult<()> {
+    match find_violation(tr, mark_type) {
+        Some(violation) => Err(transform_error(format!(
+            "{violation}（守卫标记: {mark_type}）"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// 收集步骤的目标节点，未知的步骤类型返回 false
+fn collect_targets(
+    step: &DynStep,
+    mark_type: &str,
+    targets: &mut Vec<NodeId>,
+) -> bool {
+    if let Some(s) = step.downcast_ref::<AttrStep>() {
+        targets.push(s.id.clone());
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<BulkAttrStep>() {
+        targets.extend(s.updates.iter().map(|(id, _)| id.clone()));
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<AddMarkStep>() {
+        if s.marks.iter().any(|m| m.r#type != mark_type) {
+            targets.push(s.id.clone());
+        }
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<RemoveMarkStep>() {
+        if s.mark_types.iter().any(|t| t != mark_type) {
+            targets.push(s.id.clone());
+        }
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<AddNodeStep>() {
+        targets.push(s.parent_id.clone());
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<RemoveNodeStep>() {
+        targets.push(s.parent_id.clone());
+        targets.extend(s.node_ids.iter().cloned());
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<MoveNodeStep>() {
+        targets.push(s.source_parent_id.clone());
+        targets.push(s.target_parent_id.clone());
+        targets.push(s.node_id.clone());
+        return true;
+    }
+    if let Some(s) = step.downcast_ref::<ReorderChildrenStep>() {
+        targets.push(s.parent_id.clone());
+        return true;
+    }
+    if let Some(batch) = step.downcast_ref::<BatchStep>() {
+        return batch
+            .steps
+            .iter()
+            .all(|inner| collect_targets(inner, mark_type, targets));
+    }
+    step.downcast_ref::<DerivedAttrStep>().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use mf_model::{
+        attrs::Attrs, mark::Mark, mark_definition::MarkSpec, node::Node,
+        node_definition::NodeSpec, rpds::HashTrieMapSync, schema::SchemaSpec,
+        tree::Tree,
+    };
+    use serde_json::Value;
+
+    use super::*;
+
+    fn schema() -> Arc<Schema> {
+        let mut marks = HashMap::new();
+        marks.insert(
+            "locked".to_string(),
+            MarkSpec { cascade: Some(true), ..Default::default() },
+        );
+        marks.insert("bold".to_string(), MarkSpec::default());
+        let mut nodes = HashMap::new();
+        nodes.insert("doc".to_string(), NodeSpec::default());
+        let spec =
+            SchemaSpec { nodes, marks, top_node: Some("doc".to_string()) };
+        Arc::new(Schema::compile(spec).expect("测试 Schema 编译失败"))
+    }
+
+    fn node(id: &str) -> Node {
+        Node::new(id, "doc".to_string(), Attrs::default(), vec![], vec![])
+    }
+
+    fn mark(r#type: &str) -> Mark {
+        Mark { r#type: r#type.to_string(), attrs: Attrs::default() }
+    }
+
+    /// root -> { project -> item, other }，project 已锁定
+    fn doc() -> Arc<NodePool> {
+        let mut tree = Tree::new(node("root"));
+        tree.add_node(&"root".into(), &vec![node("project"), node("other")])
+            .unwrap();
+        tree.add_node(&"project".into(), &vec![node("item")]).unwrap();
+        tree.add_mark(&"project".into(), &[mark("locked")]).unwrap();
+        NodePool::new(Arc::new(tree))
+    }
+
+    fn set_attr(id: &str) -> DynStep {
+        Arc::new(AttrStep::new(
+            id.into(),
+            HashTrieMapSync::new_sync()
+                .insert("title".to_string(), Value::from("标题")),
+        ))
+    }
+
+    #[test]
+    fn test_guard_blocks_locked_subtree() {
+        let schema = schema();
+        let doc = doc();
+        assert!(is_guarded(&doc, &schema, &"item".into(), "locked"));
+        assert!(!is_guarded(&doc, &schema, &"other".into(), "locked"));
+
+        let mut tr = Transform::new(doc.clone(), schema.clone());
+        tr.step(set_attr("other")).unwrap();
+        assert_eq!(find_violation(&tr, "locked"), None);
+
+        tr.step(set_attr("item")).unwrap();
+        assert_eq!(
+            find_violation(&tr, "locked"),
+            Some(GuardViolation::Node { step: 1, node_id: "item".into() })
+        );
+        assert!(check_guard(&tr, "locked").is_err());
+
+        let mut tr = Transform::new(doc.clone(), schema.clone());
+        tr.step(Arc::new(MoveNodeStep::new(
+            "project".into(),
+            "root".into(),
+            "item".into(),
+            None,
+        )))
+        .unwrap();
+        assert!(find_violation(&tr, "locked").is_some());
+    }
+
+    #[test]
+    fn test_guard_allows_toggling_the_guard_mark() {
+        let schema = schema();
+        let mut tr = Transform::new(doc(), schema.clone());
+        tr.step(Arc::new(RemoveMarkStep::new(
+            "project".into(),
+            vec!["locked".to_string()],
+        )))
+        .unwrap();
+        assert_eq!(find_violation(&tr, "locked"), None);
+
+        // 判断以修改前的文档为准，同一事务中解锁后修改仍被拒绝
+        tr.step(set_attr("item")).unwrap();
+        assert!(find_violation(&tr, "locked").is_some());
+
+        let mut tr = Transform::new(doc(), schema);
+        tr.step(Arc::new(AddMarkStep::new("item".into(), vec![mark("bold")])))
+            .unwrap();
+        assert!(find_violation(&tr, "locked").is_some());
+    }
+}
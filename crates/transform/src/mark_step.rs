@@ -5,6 +5,7 @@ use mf_model::{mark::Mark, schema::Schema, tree::Tree, types::NodeId, node_pool:
 use crate::{transform_error, TransformResult};
 
 use super::{
+    batch_step::BatchStep,
     step::{StepGeneric, StepResult},
 };
 use serde::{Deserialize, Serialize};
@@ -30,28 +31,52 @@ impl StepGeneric<NodePool, Schema> for AddMarkStep {
         dart: &mut Tree,
         schema: Arc<Schema>,
     ) -> TransformResult<StepResult> {
-        let _ = schema;
-        let result = dart.mark(&self.id) + self.marks.clone();
-        match result {
-            Ok(_) => Ok(StepResult::ok()),
-            Err(e) => Err(transform_error(e.to_string())),
-        }
+        let node = dart.get_node(&self.id).ok_or_else(|| {
+            transform_error(format!("节点 {} 不存在", self.id))
+        })?;
+        // 先移除与新标记互斥的已有标记，同类型标记由添加时覆盖
+        let excluded: Vec<String> = node
+            .marks
+            .iter()
+            .filter(|m| {
+                self.marks.iter().all(|n| n.r#type != m.r#type)
+                    && self.marks.iter().any(|n| {
+                        schema.marks_exclusive(&n.r#type, &m.r#type)
+                    })
+            })
+            .map(|m| m.r#type.clone())
+            .collect();
+        let node = node.remove_mark(&excluded).add_marks(&self.marks);
+        // 新标记之间也可能互斥，或不在节点允许的标记集合内；校验通过后才写入
+        schema
+            .validate_node(&node)
+            .map_err(|e| transform_error(e.to_string()))?;
+        dart.update_node(node).map_err(|e| transform_error(e.to_string()))?;
+        Ok(StepResult::ok())
     }
     fn serialize(&self) -> Option<Vec<u8>> {
         serde_json::to_vec(self).ok()
     }
 
+    /// 移除新增的标记，并还原被覆盖或因互斥被移除的原有标记
     fn invert(
         &self,
         dart: &Arc<Tree>,
     ) -> Option<Arc<dyn StepGeneric<NodePool, Schema>>> {
-        match dart.get_node(&self.id) {
-            Some(_) => Some(Arc::new(RemoveMarkStep::new(
+        let node = dart.get_node(&self.id)?;
+        let remove: Arc<dyn StepGeneric<NodePool, Schema>> =
+            Arc::new(RemoveMarkStep::new(
                 self.id.clone(),
-                self.marks.clone().iter().map(|m| m.r#type.clone()).collect(),
-            ))),
-            None => None,
+                self.marks.iter().map(|m| m.r#type.clone()).collect(),
+            ));
+        if node.marks.is_empty() {
+            return Some(remove);
         }
+        let restore = AddMarkStep::new(
+            self.id.clone(),
+            node.marks.iter().cloned().collect(),
+        );
+        Some(Arc::new(BatchStep::new(vec![remove, Arc::new(restore)])))
     }
 }
 
@@ -115,3 +140,119 @@ impl StepGeneric<NodePool, Schema> for RemoveMarkStep {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mf_model::{
+        attrs::Attrs, mark_definition::MarkSpec, node::Node,
+        node_definition::NodeSpec, schema::SchemaSpec,
+    };
+    use std::collections::HashMap;
+
+    fn create_test_schema() -> Arc<Schema> {
+        let weight = |excludes: Option<&str>| MarkSpec {
+            group: Some("weight".to_string()),
+            excludes: excludes.map(str::to_string),
+            ..Default::default()
+        };
+        let mut marks = HashMap::new();
+        marks.insert("bold".to_string(), weight(Some("weight")));
+        marks.insert("light".to_string(), weight(Some("weight")));
+        marks.insert("italic".to_string(), MarkSpec::default());
+        let mut nodes = HashMap::new();
+        nodes.insert("doc".to_string(), NodeSpec::default());
+        let spec =
+            SchemaSpec { nodes, marks, top_node: Some("doc".to_string()) };
+        Arc::new(Schema::compile(spec).expect("测试 Schema 编译失败"))
+    }
+
+    fn mark(r#type: &str) -> Mark {
+        Mark { r#type: r#type.to_string(), attrs: Attrs::default() }
+    }
+
+    fn mark_types(tree: &Tree) -> Vec<String> {
+        let node = tree.get_node(&"doc".into()).unwrap();
+        let mut types: Vec<String> =
+            node.marks.iter().map(|m| m.r#type.clone()).collect();
+        types.sort();
+        types
+    }
+
+    #[test]
+    fn test_exclusive_marks() {
+        let schema = create_test_schema();
+        let bold = schema.mark_type("bold").unwrap();
+        let light = schema.mark_type("light").unwrap();
+        let italic = schema.mark_type("italic").unwrap();
+        assert!(bold.is_exclusive_with(light));
+        assert!(bold.is_exclusive_with(bold));
+        assert!(!bold.is_exclusive_with(italic));
+
+        let mut tree = Tree::new(Node::new(
+            "doc",
+            "doc".to_string(),
+            Attrs::default(),
+            vec![],
+            vec![mark("bold"), mark("italic")],
+        ));
+        assert!(
+            schema.validate_node(tree.get_node(&"doc".into()).unwrap()).is_ok()
+        );
+
+        let step = AddMarkStep::new("doc".into(), vec![mark("light")]);
+        let before = Arc::new(tree.clone());
+        step.apply(&mut tree, schema.clone()).unwrap();
+        assert_eq!(mark_types(&tree), vec!["italic", "light"]);
+
+        // 反向步骤恢复被互斥移除的标记
+        step.invert(&before).unwrap().apply(&mut tree, schema.clone()).unwrap();
+        assert_eq!(mark_types(&tree), vec!["bold", "italic"]);
+
+        let invalid = Node::new(
+            "doc",
+            "doc".to_string(),
+            Attrs::default(),
+            vec![],
+            vec![mark("bold"), mark("light")],
+        );
+        assert!(schema.validate_node(&invalid).is_err());
+
+        // 同时添加互斥的标记会被拒绝
+        let step =
+            AddMarkStep::new("doc".into(), vec![mark("bold"), mark("light")]);
+        assert!(step.apply(&mut tree, schema).is_err());
+    }
+
+    #[test]
+    fn test_rejected_add_mark_leaves_transform_unchanged() {
+        let schema = create_test_schema();
+        let doc = NodePool::new(Arc::new(Tree::new(Node::new(
+            "doc",
+            "doc".to_string(),
+            Attrs::default(),
+            vec![],
+            vec![mark("bold"), mark("italic")],
+        ))));
+        let mut tr = crate::transform::Transform::new(doc, schema);
+
+        let rejected =
+            AddMarkStep::new("doc".into(), vec![mark("bold"), mark("light")]);
+        assert!(tr.step(Arc::new(rejected)).is_err());
+        assert!(tr.steps.is_empty());
+        tr.commit().unwrap();
+        assert_eq!(mark_types(tr.doc().get_inner()), vec!["bold", "italic"]);
+
+        // 失败的步骤没有写入草稿，之后的步骤不受影响
+        tr.step(Arc::new(RemoveMarkStep::new(
+            "doc".into(),
+            vec!["italic".to_string()],
+        )))
+        .unwrap();
+        tr.step(Arc::new(AddMarkStep::new("doc".into(), vec![mark("italic")])))
+            .unwrap();
+        tr.commit().unwrap();
+        assert_eq!(tr.steps.len(), 2);
+        assert_eq!(mark_types(tr.doc().get_inner()), vec!["bold", "italic"]);
+    }
+}
@@ -5,7 +5,7 @@ use mf_model::{
     node_pool::NodePool,
 };
 
-use crate::transform_error;
+use crate::{order, transform_error};
 
 use super::{
     step::{StepGeneric, StepResult},
@@ -18,10 +18,15 @@ use serde::{Deserialize, Serialize};
 // ========================================
 
 /// 添加节点的步骤
+///
+/// 父节点类型声明了 `sort_by` 时，新节点会直接插入到排序位置
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AddNodeStep {
     pub parent_id: NodeId,
     pub nodes: Vec<NodeTree>,
+    /// 为 true 时忽略排序规则，追加到末尾
+    #[serde(default)]
+    pub unsorted: bool,
 }
 
 impl AddNodeStep {
@@ -29,7 +34,15 @@ impl AddNodeStep {
         parent_id: NodeId,
         nodes: Vec<NodeTree>,
     ) -> Self {
-        AddNodeStep { parent_id, nodes }
+        AddNodeStep { parent_id, nodes, unsorted: false }
+    }
+
+    /// 忽略排序规则追加节点，用于批量导入后统一整理
+    pub fn unsorted(
+        parent_id: NodeId,
+        nodes: Vec<NodeTree>,
+    ) -> Self {
+        AddNodeStep { parent_id, nodes, unsorted: true }
     }
 
     // 递归收集单个节点枚举的所有子节点 id
@@ -52,12 +65,30 @@ impl StepGeneric<NodePool, Schema> for AddNodeStep {
         dart: &mut Tree,
         schema: Arc<Schema>,
     ) -> TransformResult<StepResult> {
-        let _ = schema;
-        let result = dart.add(&self.parent_id, self.nodes.clone());
-        match result {
-            Ok(_) => Ok(StepResult::ok()),
-            Err(e) => Err(transform_error(e.to_string())),
+        for node in &self.nodes {
+            validate_tree(&schema, node)?;
         }
+        if let Err(e) = dart.add(&self.parent_id, self.nodes.clone()) {
+            return Err(transform_error(e.to_string()));
+        }
+        if !self.unsorted {
+            for node in &self.nodes {
+                let id = &node.0.id;
+                let Some(index) =
+                    order::sorted_index(dart, &schema, &self.parent_id, id)
+                else {
+                    break;
+                };
+                dart.move_node(
+                    &self.parent_id,
+                    &self.parent_id,
+                    id,
+                    Some(index),
+                )
+                .map_err(|e| transform_error(e.to_string()))?;
+            }
+        }
+        Ok(StepResult::ok())
     }
 
     fn serialize(&self) -> Option<Vec<u8>> {
@@ -83,6 +114,17 @@ impl StepGeneric<NodePool, Schema> for AddNodeStep {
     }
 }
 
+/// 按 Schema 校验待添加的节点及其子树
+fn validate_tree(
+    schema: &Schema,
+    tree: &NodeTree,
+) -> TransformResult<()> {
+    schema
+        .validate_node(&tree.0)
+        .map_err(|e| transform_error(e.to_string()))?;
+    tree.1.iter().try_for_each(|child| validate_tree(schema, child))
+}
+
 /// 删除节点的步骤
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RemoveNodeStep {
@@ -147,10 +189,10 @@ impl StepGeneric<NodePool, Schema> for RemoveNodeStep {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MoveNodeStep {
-    source_parent_id: NodeId,
-    target_parent_id: NodeId,
-    node_id: NodeId,
-    position: Option<usize>, // 目标位置，None 表示追加到末尾
+    pub(crate) source_parent_id: NodeId,
+    pub(crate) target_parent_id: NodeId,
+    pub(crate) node_id: NodeId,
+    pub(crate) position: Option<usize>, // 目标位置，None 表示追加到末尾
 }
 
 impl MoveNodeStep {
@@ -215,6 +257,57 @@ impl StepGeneric<NodePool, Schema> for MoveNodeStep {
     }
 }
 
+/// 重排父节点直接子节点的步骤，子节点 ID 不变
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReorderChildrenStep {
+    pub(crate) parent_id: NodeId,
+    pub(crate) new_order: Vec<NodeId>,
+}
+
+impl ReorderChildrenStep {
+    pub fn new(
+        parent_id: NodeId,
+        new_order: Vec<NodeId>,
+    ) -> Self {
+        ReorderChildrenStep { parent_id, new_order }
+    }
+}
+
+impl StepGeneric<NodePool, Schema> for ReorderChildrenStep {
+    fn name(&self) -> String {
+        "reorder_children_step".to_string()
+    }
+
+    fn apply(
+        &self,
+        dart: &mut Tree,
+        schema: Arc<Schema>,
+    ) -> TransformResult<StepResult> {
+        let _ = schema;
+
+        match dart.reorder_children(&self.parent_id, &self.new_order) {
+            Ok(()) => Ok(StepResult::ok()),
+            Err(err) => Err(transform_error(err.to_string())),
+        }
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        serde_json::to_vec(self).ok()
+    }
+
+    fn invert(
+        &self,
+        dart: &Arc<Tree>,
+    ) -> Option<Arc<dyn StepGeneric<NodePool, Schema>>> {
+        dart.children(&self.parent_id).map(|children| {
+            Arc::new(ReorderChildrenStep::new(
+                self.parent_id.clone(),
+                children.iter().cloned().collect(),
+            )) as Arc<dyn StepGeneric<NodePool, Schema>>
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,6 +335,7 @@ mod tests {
                 group: None,
                 desc: Some("Test node".to_string()),
                 attrs: None,
+                sort_by: None,
             },
         );
 
@@ -293,6 +387,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_add_node_step_validates_schema() {
+        let mut tree = create_test_tree();
+        let schema = create_test_schema();
+
+        // 子树中未定义的节点类型也会被拒绝
+        let unknown = Node::new(
+            "unknown",
+            "missing".to_string(),
+            Attrs::default(),
+            vec![],
+            vec![],
+        );
+        let node_enum = NodeTree(
+            create_test_node("child"),
+            vec![NodeTree(unknown, vec![])],
+        );
+        let step = AddNodeStep::new("root".into(), vec![node_enum]);
+        assert!(step.apply(&mut tree, schema).is_err());
+        assert!(tree.get_node(&"child".into()).is_none());
+    }
+
     #[test]
     fn test_remove_node_step() {
         let mut tree = create_test_tree();
@@ -362,4 +478,34 @@ mod tests {
         let inverted = step.invert(&Arc::new(tree.clone()));
         assert!(inverted.is_some());
     }
+
+    #[test]
+    fn test_reorder_children_step() {
+        let mut tree = create_test_tree();
+        let schema = create_test_schema();
+        let children: Vec<Node> =
+            ["a", "b", "c"].iter().map(|id| create_test_node(id)).collect();
+        tree.add_node(&"root".into(), &children)
+            .expect("测试中添加子节点应该成功");
+        let before = Arc::new(tree.clone());
+
+        let step = ReorderChildrenStep::new(
+            "root".into(),
+            vec!["b".into(), "c".into(), "a".into()],
+        );
+        step.apply(&mut tree, schema.clone()).expect("重排应该成功");
+        let order: Vec<NodeId> =
+            tree.children(&"root".into()).unwrap().iter().cloned().collect();
+        assert_eq!(order, step.new_order);
+
+        // 反向步骤恢复原顺序
+        let inverted = step.invert(&before).expect("应该生成反向步骤");
+        inverted.apply(&mut tree, schema.clone()).expect("恢复应该成功");
+        let order: Vec<NodeId> =
+            tree.children(&"root".into()).unwrap().iter().cloned().collect();
+        assert_eq!(order, vec!["a".into(), "b".into(), "c".into()]);
+
+        let invalid = ReorderChildrenStep::new("root".into(), vec!["a".into()]);
+        assert!(invalid.apply(&mut tree, schema).is_err());
+    }
 }
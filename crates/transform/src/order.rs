@@ -0,0 +1,346 @@
+//! 子节点自动排序
+//!
+//! 节点类型通过 `NodeSpec::sort_by` 声明子节点按属性排序后：
+//! - `AddNodeStep` 直接把新节点插入到排序位置
+//! - 排序键变更后由 [`Transform::restore_order`] 生成最少的 `MoveNodeStep`
+//! - 批量导入时可关闭自动排序，之后用 [`Transform::normalize_order`] 统一整理
+//!
+//! 排序键相同的子节点保持插入顺序。
+
+use std::sync::Arc;
+
+use mf_model::{
+    node::Node, node_definition::SortSpec, schema::Schema, tree::Tree,
+    types::NodeId,
+};
+
+use crate::{node_step::MoveNodeStep, Transform, TransformResult};
+
+/// 父节点类型声明的排序规则
+pub fn sort_spec_of(
+    tree: &Tree,
+    schema: &Schema,
+    parent_id: &NodeId,
+) -> Option<SortSpec> {
+    let parent = tree.get_node(parent_id)?;
+    schema.factory().node_definition(&parent.r#type)?.sort_spec()
+}
+
+/// 节点在父节点中应处的位置（基于移除该节点后的子节点列表）
+///
+/// 父节点类型未声明排序规则时返回 None
+pub fn sorted_index(
+    tree: &Tree,
+    schema: &Schema,
+    parent_id: &NodeId,
+    node_id: &NodeId,
+) -> Option<usize> {
+    let spec = sort_spec_of(tree, schema, parent_id)?;
+    let node = tree.get_node(node_id)?;
+    let siblings = siblings_without(tree, parent_id, node_id);
+    Some(insert_position(&spec, node, &siblings))
+}
+
+fn siblings_without<'a>(
+    tree: &'a Tree,
+    parent_id: &NodeId,
+    node_id: &NodeId,
+) -> Vec<&'a Node> {
+    tree.children_node(parent_id)
+        .map(|children| {
+            children.iter().filter(|n| &n.id != node_id).copied().collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 排在所有不大于该节点的兄弟之后，相同键保持插入顺序
+fn insert_position(
+    spec: &SortSpec,
+    node: &Node,
+    siblings: &[&Node],
+) -> usize {
+    siblings
+        .iter()
+        .position(|sibling| spec.compare(node, sibling).is_lt())
+        .unwrap_or(siblings.len())
+}
+
+/// 计算节点恢复顺序所需的移动，已处于合法位置时返回 None
+pub fn order_step(
+    tree: &Tree,
+    schema: &Schema,
+    node_id: &NodeId,
+) -> Option<MoveNodeStep> {
+    let parent = tree.get_parent_node(node_id)?;
+    let spec = sort_spec_of(tree, schema, &parent.id)?;
+    let node = tree.get_node(node_id)?;
+    let children = tree.children_node(&parent.id)?;
+    let index = children.iter().position(|n| &n.id == node_id)?;
+
+    let after_prev =
+        index == 0 || !spec.compare(children[index - 1], node).is_gt();
+    let before_next = index + 1 >= children.len()
+        || !spec.compare(node, children[index + 1]).is_gt();
+    if after_prev && before_next {
+        return None;
+    }
+
+    let siblings = siblings_without(tree, &parent.id, node_id);
+    let position = insert_position(&spec, node, &siblings);
+    Some(MoveNodeStep::new(
+        parent.id.clone(),
+        parent.id.clone(),
+        node_id.clone(),
+        Some(position),
+    ))
+}
+
+/// 整理整棵树中声明了排序规则的子节点，返回需要的移动步骤
+///
+/// 移动按顺序应用后，每个父节点的子节点都按排序键稳定排序
+pub fn normalize_steps(
+    tree: &Tree,
+    schema: &Schema,
+) -> Vec<MoveNodeStep> {
+    let mut steps = Vec::new();
+    let mut stack = vec![tree.root_id.clone()];
+    while let Some(parent_id) = stack.pop() {
+        let Some(children) = tree.children_node(&parent_id) else {
+            continue;
+        };
+        stack.extend(children.iter().map(|n| n.id.clone()));
+        let Some(spec) = sort_spec_of(tree, schema, &parent_id) else {
+            continue;
+        };
+
+        let mut current: Vec<&Node> = children.iter().copied().collect();
+        let mut target = current.clone();
+        target.sort_by(|a, b| spec.compare(a, b));
+        for (index, node) in target.iter().enumerate() {
+            if current[index].id == node.id {
+                continue;
+            }
+            let from = current.iter().position(|n| n.id == node.id).unwrap();
+            let moved = current.remove(from);
+            current.insert(index, moved);
+            steps.push(MoveNodeStep::new(
+                parent_id.clone(),
+                parent_id.clone(),
+                node.id.clone(),
+                Some(index),
+            ));
+        }
+    }
+    steps
+}
+
+impl Transform {
+    /// 节点排序键变更后恢复其在父节点中的顺序
+    ///
+    /// 需要移动时追加一个 `MoveNodeStep`，返回是否发生了移动
+    pub fn restore_order(
+        &mut self,
+        node_id: &NodeId,
+    ) -> TransformResult<bool> {
+        let doc = self.doc();
+        match order_step(doc.get_inner(), &self.schema, node_id) {
+            Some(step) => {
+                self.step(Arc::new(step))?;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// 按 schema 声明的排序规则整理整棵树，返回追加的移动步骤数
+    pub fn normalize_order(&mut self) -> TransformResult<usize> {
+        let doc = self.doc();
+        let steps = normalize_steps(doc.get_inner(), &self.schema);
+        let count = steps.len();
+        for step in steps {
+            self.step(Arc::new(step))?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{attr_step::AttrStep, node_step::AddNodeStep, StepGeneric};
+    use mf_model::{
+        attrs::Attrs,
+        node_definition::{NodeSpec, NodeTree},
+        node_pool::NodePool,
+        rpds::HashTrieMapSync,
+        schema::{AttributeSpec, SchemaSpec},
+    };
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    fn create_schema() -> Arc<Schema> {
+        let mut attrs = HashMap::new();
+        attrs.insert("seq".to_string(), AttributeSpec::new(Some(Value::Null)));
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            "doc".to_string(),
+            NodeSpec {
+                content: Some("item*".to_string()),
+                sort_by: Some("attrs.seq".to_string()),
+                ..Default::default()
+            },
+        );
+        nodes.insert(
+            "item".to_string(),
+            NodeSpec { attrs: Some(attrs), ..Default::default() },
+        );
+        Arc::new(
+            Schema::compile(SchemaSpec {
+                nodes,
+                marks: HashMap::new(),
+                top_node: Some("doc".to_string()),
+            })
+            .unwrap(),
+        )
+    }
+
+    fn item(
+        id: &str,
+        seq: Value,
+    ) -> NodeTree {
+        let attrs = HashTrieMapSync::new_sync().insert("seq".to_string(), seq);
+        NodeTree(
+            Node::new(
+                id,
+                "item".to_string(),
+                Attrs::from(attrs),
+                vec![],
+                vec![],
+            ),
+            vec![],
+        )
+    }
+
+    fn create_doc(
+        schema: &Arc<Schema>,
+        items: Vec<NodeTree>,
+    ) -> Arc<NodePool> {
+        let root = Node::new(
+            "doc",
+            "doc".to_string(),
+            Attrs::default(),
+            vec![],
+            vec![],
+        );
+        let mut transform = Transform::new(
+            NodePool::new(Arc::new(Tree::new(root))),
+            schema.clone(),
+        );
+        transform
+            .step(Arc::new(AddNodeStep::new("doc".into(), items)))
+            .unwrap();
+        transform.commit().unwrap();
+        transform.doc()
+    }
+
+    fn order(doc: &NodePool) -> Vec<String> {
+        doc.get_inner()
+            .children(&"doc".into())
+            .unwrap()
+            .iter()
+            .map(|id| id.to_string())
+            .collect()
+    }
+
+    fn set_seq(
+        transform: &mut Transform,
+        id: &str,
+        seq: Value,
+    ) {
+        let values = HashTrieMapSync::new_sync().insert("seq".to_string(), seq);
+        transform.step(Arc::new(AttrStep::new(id.into(), values))).unwrap();
+        transform.restore_order(&id.into()).unwrap();
+    }
+
+    #[test]
+    fn test_add_inserts_sorted_with_duplicates() {
+        let schema = create_schema();
+        let doc = create_doc(
+            &schema,
+            vec![
+                item("c", json!(2)),
+                item("a", json!(1)),
+                item("d", json!(2)),
+                item("e", json!(3)),
+            ],
+        );
+        // 相同键保持插入顺序
+        assert_eq!(order(&doc), ["a", "c", "d", "e"]);
+
+        let mut transform = Transform::new(doc, schema);
+        transform
+            .step(Arc::new(AddNodeStep::new(
+                "doc".into(),
+                vec![item("b", json!(2))],
+            )))
+            .unwrap();
+        assert_eq!(order(&transform.doc()), ["a", "c", "d", "b", "e"]);
+    }
+
+    #[test]
+    fn test_attr_change_moves_minimally_and_undoes() {
+        let schema = create_schema();
+        let doc = create_doc(
+            &schema,
+            vec![item("a", json!(1)), item("b", json!(2)), item("c", json!(3))],
+        );
+
+        let mut transform = Transform::new(doc.clone(), schema.clone());
+        // 位置仍然合法时不产生移动
+        set_seq(&mut transform, "b", json!(2.5));
+        assert_eq!(transform.steps.len(), 1);
+
+        set_seq(&mut transform, "c", json!(0));
+        assert_eq!(order(&transform.doc()), ["c", "a", "b"]);
+        assert_eq!(transform.steps.len(), 3);
+
+        // 移除排序键后排在最后，多个无键节点之间保持原顺序
+        set_seq(&mut transform, "a", Value::Null);
+        assert_eq!(order(&transform.doc()), ["c", "b", "a"]);
+        set_seq(&mut transform, "c", Value::Null);
+        assert_eq!(order(&transform.doc()), ["b", "a", "c"]);
+
+        // 逆序应用反向步骤，位置与属性都恢复
+        let mut tree = transform.doc().get_inner().as_ref().clone();
+        for step in transform.invert_steps.iter().rev() {
+            step.apply(&mut tree, schema.clone()).unwrap();
+        }
+        let undone = NodePool::new(Arc::new(tree));
+        assert_eq!(order(&undone), ["a", "b", "c"]);
+        let c = undone.get_node(&"c".into()).unwrap();
+        assert_eq!(c.attrs.get_safe("seq"), Some(&json!(3)));
+    }
+
+    #[test]
+    fn test_unsorted_add_then_normalize() {
+        let schema = create_schema();
+        let doc = create_doc(&schema, vec![]);
+        let mut transform = Transform::new(doc, schema);
+        let step: Arc<dyn StepGeneric<NodePool, Schema>> =
+            Arc::new(AddNodeStep::unsorted(
+                "doc".into(),
+                vec![
+                    item("c", json!(3)),
+                    item("x", Value::Null),
+                    item("a", json!(1)),
+                    item("b", json!(2)),
+                ],
+            ));
+        transform.step(step).unwrap();
+        assert_eq!(order(&transform.doc()), ["c", "x", "a", "b"]);
+
+        assert_eq!(transform.normalize_order().unwrap(), 2);
+        assert_eq!(order(&transform.doc()), ["a", "b", "c", "x"]);
+        assert_eq!(transform.normalize_order().unwrap(), 0);
+    }
+}
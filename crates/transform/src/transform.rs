@@ -285,4 +285,72 @@ impl Transform {
         }
         Ok(())
     }
+
+    /// 在文档副本上试运行步骤，返回应用后的文档，原文档保持不变
+    ///
+    /// 副本与原文档结构共享，未修改的节点不会被复制，
+    /// 可用于预览待应用的编辑而无需提交
+    pub fn apply_dry_run(
+        pool: &NodePool,
+        schema: Arc<Schema>,
+        steps: &[Arc<dyn StepGeneric<NodePool, Schema>>],
+    ) -> TransformResult<Arc<NodePool>> {
+        let mut tree = pool.get_inner().as_ref().clone();
+        for step in steps {
+            let result = step.apply(&mut tree, schema.clone())?;
+            if let Some(message) = result.failed {
+                return Err(anyhow::anyhow!(message));
+            }
+        }
+        Ok(NodePool::new(Arc::new(tree)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node_step::AddNodeStep;
+    use mf_model::{
+        attrs::Attrs,
+        node::Node,
+        node_definition::{NodeSpec, NodeTree},
+        schema::SchemaSpec,
+        tree::Tree,
+    };
+    use std::collections::HashMap;
+
+    fn node(id: &str) -> Node {
+        Node::new(id, "test".to_string(), Attrs::default(), vec![], vec![])
+    }
+
+    #[test]
+    fn test_apply_dry_run_keeps_original() {
+        let mut nodes = HashMap::new();
+        nodes.insert("test".to_string(), NodeSpec::default());
+        let schema = Arc::new(
+            Schema::compile(SchemaSpec {
+                nodes,
+                marks: HashMap::new(),
+                top_node: Some("test".to_string()),
+            })
+            .unwrap(),
+        );
+        let pool = NodePool::new(Arc::new(Tree::from(NodeTree(
+            node("root"),
+            vec![NodeTree(node("a"), vec![]), NodeTree(node("b"), vec![])],
+        ))));
+
+        let step: Arc<dyn StepGeneric<NodePool, Schema>> = Arc::new(
+            AddNodeStep::new("root".into(), vec![NodeTree(node("c"), vec![])]),
+        );
+        let preview = Transform::apply_dry_run(&pool, schema, &[step]).unwrap();
+
+        assert!(preview.get_node(&"c".into()).is_some());
+        assert!(pool.get_node(&"c".into()).is_none());
+        // 未修改的节点与原文档共享
+        assert!(std::ptr::eq(
+            pool.get_node(&"a".into()).unwrap(),
+            preview.get_node(&"a".into()).unwrap()
+        ));
+    }
 }
@@ -0,0 +1,176 @@
+//! 命令桥接
+//!
+//! 应用命令按名称注册到 [`CommandRegistry`]，参数为可反序列化的结构体。
+//! 前端通过唯一的 Tauri 命令 [`dispatch_command`] 调用：
+//! `invoke("dispatch_command", { editorName, commandName, params })`，
+//! 新增编辑功能只需实现命令并注册，不再需要单独的 Tauri 命令与控制器。
+//!
+//! 错误以 `ErrorWire` 返回，命令不存在、编辑器不存在与参数无效分别对应
+//! `COMMAND_NOT_FOUND`、`COMMAND_EDITOR_NOT_FOUND` 与 `COMMAND_INVALID_PARAMS`。
+
+use std::{collections::HashMap, sync::Arc};
+
+use mf_error_codes::{coded, ErrorCode, ErrorWire, ToWire};
+use mf_state::{transaction::Command, State};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::AppError,
+    plugins::inc::{Operations, INC_DATA_KEY},
+    res,
+    response::Res,
+    ContextHelper, ResponseResult,
+};
+
+/// 由请求参数构造命令及其历史记录元数据
+type BuildFn = Arc<
+    dyn Fn(Value, &State) -> serde_json::Result<(Arc<dyn Command>, Value)>
+        + Send
+        + Sync,
+>;
+
+struct RegisteredCommand {
+    build: BuildFn,
+    /// 历史记录描述模板，使用元数据渲染
+    description: String,
+}
+
+/// 命令注册表
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: HashMap<String, RegisteredCommand>,
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册命令，历史记录元数据为请求参数本身
+    pub fn register<P, F>(
+        &mut self,
+        name: &str,
+        description: &str,
+        build: F,
+    ) -> &mut Self
+    where
+        P: DeserializeOwned,
+        F: Fn(P) -> Arc<dyn Command> + Send + Sync + 'static,
+    {
+        let build: BuildFn = Arc::new(move |params: Value, _: &State| {
+            let meta = params.clone();
+            Ok((build(serde_json::from_value(params)?), meta))
+        });
+        self.insert(name, description, build)
+    }
+
+    /// 注册命令，并由参数与执行前的状态生成历史记录元数据
+    pub fn register_with_meta<P, F, M>(
+        &mut self,
+        name: &str,
+        description: &str,
+        build: F,
+        meta: M,
+    ) -> &mut Self
+    where
+        P: DeserializeOwned,
+        F: Fn(P) -> Arc<dyn Command> + Send + Sync + 'static,
+        M: Fn(&P, &State) -> Value + Send + Sync + 'static,
+    {
+        let build: BuildFn = Arc::new(move |params: Value, state: &State| {
+            let params = serde_json::from_value(params)?;
+            let meta = meta(&params, state);
+            Ok((build(params), meta))
+        });
+        self.insert(name, description, build)
+    }
+
+    fn insert(
+        &mut self,
+        name: &str,
+        description: &str,
+        build: BuildFn,
+    ) -> &mut Self {
+        self.commands.insert(
+            name.to_string(),
+            RegisteredCommand { build, description: description.to_string() },
+        );
+        self
+    }
+
+    /// 所有已注册的命令名称（按名称排序）
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> =
+            self.commands.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+}
+
+/// 命令执行结果
+#[derive(Debug, Serialize)]
+pub struct CommandOutcome {
+    /// 执行后的状态版本
+    pub state_version: u64,
+    /// 本次命令产生的增量数据，取出后不会再由 `get_inc_data` 返回
+    pub patches: Option<Arc<Operations>>,
+}
+
+/// 查找编辑器、构造并执行命令
+pub async fn dispatch(
+    editor_name: &str,
+    command_name: &str,
+    params: Value,
+) -> ResponseResult<CommandOutcome> {
+    let registry = ContextHelper::get::<CommandRegistry>();
+    let Some(command) = registry.commands.get(command_name) else {
+        return Err(AppError(coded(
+            ErrorCode::CommandNotFound,
+            format!(
+                "命令 {command_name} 不存在，可用的命令: {}",
+                registry.names().join(", ")
+            ),
+        )));
+    };
+    let Some(mut editor) = ContextHelper::get_editor(editor_name) else {
+        return Err(AppError(coded(
+            ErrorCode::CommandEditorNotFound,
+            format!("工程项目 {editor_name} 不存在"),
+        )));
+    };
+    let state = editor.get_state().await;
+    let (cmd, meta) = (command.build)(params, &state).map_err(|e| {
+        AppError(coded(
+            ErrorCode::CommandInvalidParams,
+            format!("命令 {command_name} 的参数无效: {e}"),
+        ))
+    })?;
+    editor.command_with_meta(cmd, command.description.clone(), meta).await?;
+
+    let state = editor.get_state().await;
+    let patches = state
+        .resource_manager()
+        .resource_table
+        .take::<Operations>(INC_DATA_KEY.to_string());
+    res!(CommandOutcome { state_version: state.version, patches })
+}
+
+/// 通用命令入口
+#[tauri::command]
+pub async fn dispatch_command(
+    editor_name: String,
+    command_name: String,
+    params: Value,
+) -> Result<Res<CommandOutcome>, ErrorWire> {
+    dispatch(&editor_name, &command_name, params)
+        .await
+        .map_err(|e| e.0.to_wire())
+}
+
+/// 注册示例中的所有命令
+pub fn default_registry() -> CommandRegistry {
+    let mut registry = CommandRegistry::new();
+    crate::commands::gcxm::register(&mut registry);
+    registry
+}
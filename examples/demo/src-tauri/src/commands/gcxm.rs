@@ -1,16 +1,50 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
 use mf_model::types::NodeId;
-use mf_model::{node_pool::NodePool, schema::Schema};
+use mf_model::{id_generator::IdGenerator, node_pool::NodePool, schema::Schema};
 use mf_state::{transaction::CommandGeneric, Transaction};
 use mf_transform::TransformResult;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    bridge::CommandRegistry,
     commands::{AddMarkRequest, AddRequest, DeleteNodeRequest, ShareCommand},
     marks::FOOTNOTE_STR,
 };
+
+/// 注册工程项目相关的命令
+pub fn register(registry: &mut CommandRegistry) {
+    registry
+        //插入子节点 单项、单位
+        .register(
+            "insert_gcxm_child",
+            "插入 {{attrs.name}} 子节点",
+            |mut data: AddRequest| {
+                data.id = Some(IdGenerator::get_id());
+                Arc::new(InsertChildCammand { data })
+            },
+        )
+        //添加脚注
+        .register(
+            "add_gcxm_footnote",
+            "添加id：{{id}}脚注",
+            |command: AddFootNoteCammand| Arc::new(command),
+        )
+        //删除工程项目节点，历史记录使用删除前的节点
+        .register_with_meta(
+            "delete_gcxm",
+            "删除  {{a.name}}",
+            |data: DeleteNodeRequest| Arc::new(DeleteGcxmCammand { data }),
+            |data, state| {
+                state
+                    .doc()
+                    .get_node(&data.id)
+                    .and_then(|node| serde_json::to_value(&*node).ok())
+                    .unwrap_or_default()
+            },
+        );
+}
 #[derive(Debug, Clone)]
 pub struct InsertChildCammand {
     pub data: AddRequest,
@@ -78,6 +112,9 @@ impl CommandGeneric<NodePool, Schema> for DeleteGcxmCammand {
         &self,
         tr: &mut Transaction,
     ) -> TransformResult<()> {
+        if self.data.id.to_string() == self.data.editor_name {
+            return Err(anyhow::anyhow!("不能删除工程项目".to_string()));
+        }
         self.delete_node(tr, &self.data).await
     }
     fn name(&self) -> String {
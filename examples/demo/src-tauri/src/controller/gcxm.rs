@@ -15,10 +15,6 @@ use serde::Deserialize;
 use serde_json::Value;
 
 use crate::{
-    commands::{
-        gcxm::{AddFootNoteCammand, DeleteGcxmCammand, InsertChildCammand},
-        AddRequest, DeleteNodeRequest,
-    },
     controller::{get_data_tree, get_history, get_inc_data, GcxmTreeItem},
     error::AppError,
     initialize::editor::{
@@ -129,23 +125,6 @@ pub async fn new_project(
         Err(AppError(anyhow::anyhow!("无法构建工程树,未找到根节点")))
     }
 }
-///插入子节点
-pub async fn insert_child(
-    Json(mut param): Json<AddRequest>
-) -> ResponseResult<()> {
-    let mut editor = ContextHelper::get_editor(&param.editor_name).unwrap();
-    param.id = Some(IdGenerator::get_id());
-    let meta = serde_json::to_value(param.clone())?;
-    editor
-        .command_with_meta(
-            Arc::new(InsertChildCammand { data: param.clone() }),
-            "插入 {{attrs.name}} 子节点".to_string(),
-            meta,
-        )
-        .await?;
-    res!(())
-}
-
 ///获取工程项目树节点
 
 pub async fn get_gcxm_tree(
@@ -173,64 +152,13 @@ pub async fn get_gcxm_tree(
     }
 }
 
-///删除工程项目节点
-pub async fn delete_gcxm(
-    Json(param): Json<DeleteNodeRequest>
-) -> ResponseResult<String> {
-    if param.id.to_string() == param.editor_name {
-        return Err(AppError(anyhow::anyhow!("不能删除工程项目".to_string())));
-    }
-    let editor = ContextHelper::get_editor(&param.editor_name);
-    if editor.is_none() {
-        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
-    }
-    let mut editor = editor.unwrap();
-    let node = editor.doc().await.get_node(&param.id).unwrap();
-    let meta = serde_json::to_value(node)?;
-    editor
-        .command_with_meta(
-            Arc::new(DeleteGcxmCammand { data: param.clone() }),
-            "删除  {{a.name}}".to_string(),
-            meta,
-        )
-        .await?;
-
-    res!("删除成功".to_string())
-}
-
-///添加脚注
-pub async fn add_footnote(
-    Json(param): Json<AddFootNoteCammand>
-) -> ResponseResult<()> {
-    let editor = ContextHelper::get_editor(&param.editor_name);
-    if editor.is_none() {
-        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
-    }
-    let mut editor = editor.unwrap();
-    let meta = serde_json::to_value(param.clone())?;
-    editor
-        .command_with_meta(
-            Arc::new(param.clone()),
-            "添加id：{{id}}脚注".to_string(),
-            meta,
-        )
-        .await?;
-
-    res!(())
-}
-
 pub fn build_app() -> Router {
     Router::new()
         //创建新工程项目
         .route("/", post(new_project))
-        //插入子节点 单项、单位
-        .route("/insert_child", post(insert_child))
+        //插入子节点、添加脚注、删除节点通过 dispatch_command 调用
         //获取工程项目树
         .route("/get_gcxm_tree/{editor_name}", get(get_gcxm_tree))
-        //添加脚注
-        .route("/add_footnote", post(add_footnote))
-        //删除工程项目
-        .route("/delete_gcxm", post(delete_gcxm))
         // 历史记录
         .route("/get_history", post(get_history))
         //获取数据树
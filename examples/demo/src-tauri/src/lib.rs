@@ -25,6 +25,8 @@ pub mod response;
 
 pub mod commands;
 
+pub mod bridge;
+
 pub mod middleware;
 pub mod router;
 
@@ -196,7 +196,8 @@ async fn main() -> anyhow::Result<()> {
             show_main_window,
             quit_app,
             show_tray_menu,
-            hide_tray_menu
+            hide_tray_menu,
+            app_lib::bridge::dispatch_command
         ])
         .run(tauri::generate_context!())
         .map_err(|e| {
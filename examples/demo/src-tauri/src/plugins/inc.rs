@@ -35,7 +35,7 @@ impl Resource for Operations {}
 #[derive(Debug)]
 pub struct IncStateField;
 
-const INC_DATA_KEY: &str = "inc_data";
+pub const INC_DATA_KEY: &str = "inc_data";
 
 impl IncStateField {
     ///收集增量的数据更新
@@ -1,4 +1,5 @@
 import { get, post } from "@/utils/request";
+import { dispatchCommand } from "@/utils/command";
 
 //ipc 根据工程项目id获取工程项目树
 export const getGcxmTree = async (id: string) => {
@@ -8,7 +9,7 @@ export const getGcxmTree = async (id: string) => {
 
 //ipc 新增 树节点
 export const addGcxmTree = async (data: any) => {
-  const result = await post("/gcxm/insert_child", data);
+  const result = await dispatchCommand(data.editor_name, "insert_gcxm_child", data);
   return result;
 };
 
@@ -20,13 +21,13 @@ export const addRootTree = async (data: any) => {
 
 //ipc 添加脚注
 export const addFootNote = async (data: any) => {
-  const result = await post("/gcxm/add_footnote", data);
+  const result = await dispatchCommand(data.editor_name, "add_gcxm_footnote", data);
   return result;
 };
 
 //ipc 删除 树节点
 
 export const deleteGcxmTree = async (data: any) => {
-  const result = await post("/gcxm/delete_gcxm", data);
+  const result = await dispatchCommand(data.editor_name, "delete_gcxm", data);
   return result;
 };
@@ -0,0 +1,35 @@
+import { invoke } from '@tauri-apps/api/core';
+import { ElMessage } from 'element-plus';
+
+// 后端 ErrorWire 错误格式
+export interface ErrorWire {
+  code: string;
+  message: string;
+  details: Record<string, any>;
+}
+
+// 命令执行结果
+export interface CommandOutcome {
+  state_version: number;
+  patches: any[] | null;
+}
+
+// 通过 dispatch_command 调用已注册的编辑器命令
+export async function dispatchCommand(
+  editorName: string,
+  commandName: string,
+  params: any
+): Promise<CommandOutcome> {
+  try {
+    const res: any = await invoke('dispatch_command', { editorName, commandName, params });
+    return res.data;
+  } catch (error) {
+    const wire = error as ErrorWire;
+    ElMessage({
+      message: wire.message || '命令执行失败',
+      type: 'error',
+      duration: 5 * 1000,
+    });
+    return Promise.reject(wire);
+  }
+}
@@ -2,13 +2,22 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use mf_error_codes::{ErrorKind, ToWire};
 
 pub struct AppError(pub anyhow::Error);
 
 impl IntoResponse for AppError {
+    /// 以 `ErrorWire` JSON 返回错误，HTTP 状态码由错误类别决定
     fn into_response(self) -> Response {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("{}", self.0))
-            .into_response()
+        let wire = self.0.to_wire();
+        let status = match wire.error_code().map(|code| code.kind()) {
+            Some(ErrorKind::Validation) => StatusCode::BAD_REQUEST,
+            Some(ErrorKind::NotFound) => StatusCode::NOT_FOUND,
+            Some(ErrorKind::Conflict) => StatusCode::CONFLICT,
+            Some(ErrorKind::Timeout) => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, axum::Json(wire)).into_response()
     }
 }
 
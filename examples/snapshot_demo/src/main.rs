@@ -1,77 +1,122 @@
 //! 快照启动演示
-//! 
-//! 演示如何使用快照实现毫秒级启动
+//!
+//! 只使用公开 API：创建快照、从快照启动、按目录选择最新快照启动以及后台定时快照
 
-use std::time::Instant;
-use mf_core::{ForgeRuntime, types::RuntimeOptions};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use mf_core::{
+    list_snapshots, Event, EventHandler, ForgeConfig, ForgeResult,
+    ForgeRuntime, SnapshotConfig, types::RuntimeOptions,
+};
+
+const SNAPSHOT_DIR: &str = "target/snapshots";
+
+/// 打印快照事件
+#[derive(Debug)]
+struct SnapshotLogger;
+
+#[async_trait::async_trait]
+impl EventHandler<Event> for SnapshotLogger {
+    async fn handle(
+        &self,
+        event: &Event,
+    ) -> ForgeResult<()> {
+        match event {
+            Event::SnapshotStarted { path } => {
+                println!("   📝 开始写入快照: {}", path.display());
+            },
+            Event::SnapshotSucceeded { path, duration, size } => {
+                println!(
+                    "   ✅ 快照写入完成: {} ({} 字节，耗时 {:?})",
+                    path.display(),
+                    size,
+                    duration
+                );
+            },
+            Event::SnapshotFailed { path, error, .. } => {
+                println!("   ❌ 快照写入失败: {}: {}", path.display(), error);
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== ModuForge 快照启动演示 ===\n");
 
-    // 1. 传统启动方式测试
+    // 1. 传统启动方式（加载 schema/main.xml）
     println!("📊 测试传统启动方式...");
     let traditional_start = Instant::now();
-    let mut traditional_time = None;
-    
-    match ForgeRuntime::create(RuntimeOptions::default()).await {
-        Ok(_traditional_runtime) => {
-            let time = traditional_start.elapsed();
-            traditional_time = Some(time);
-            println!("✅ 传统启动完成，耗时: {:?}\n", time);
-        },
-        Err(e) => {
-            println!("⚠️ 传统启动失败: {}", e);
-            println!("这可能是因为缺少默认 schema 配置\n");
-        }
-    }
+    let runtime = ForgeRuntime::create(RuntimeOptions::default()).await?;
+    let traditional_time = traditional_start.elapsed();
+    println!("✅ 传统启动完成，耗时: {:?}\n", traditional_time);
+
+    // 2. 创建快照
+    println!("💾 创建快照...");
+    runtime.get_event_bus().add_event_handler(Arc::new(SnapshotLogger))?;
+    let snapshot_path = format!("{SNAPSHOT_DIR}/demo_snapshot.mfsnap");
+    let info = runtime.create_snapshot(&snapshot_path).await?;
+    println!(
+        "✅ 快照已写入 {}，大小 {} 字节，耗时 {:?}\n",
+        info.path.display(),
+        info.size,
+        info.duration
+    );
 
-    // 2. 快照启动方式测试
+    // 3. 从快照启动
     println!("🚀 测试快照启动方式...");
     let snapshot_start = Instant::now();
-    
-    // 尝试从快照启动，如果失败则回退到传统方式
-    let snapshot_path = "target/snapshots/demo_snapshot.bin";
-    let _snapshot_runtime = match ForgeRuntime::from_snapshot(snapshot_path, None).await {
-        Ok(runtime) => {
-            let snapshot_time = snapshot_start.elapsed();
-            println!("✅ 快照启动完成，耗时: {:?}", snapshot_time);
-            if let Some(traditional_time) = traditional_time {
-                println!("🎯 性能提升: {:.2}x 倍", 
-                    traditional_time.as_secs_f64() / snapshot_time.as_secs_f64());
-            }
-            runtime
+    let restored =
+        ForgeRuntime::from_snapshot(&snapshot_path, None, None).await?;
+    let snapshot_time = snapshot_start.elapsed();
+    println!("✅ 快照启动完成，耗时: {:?}", snapshot_time);
+    println!(
+        "🎯 性能对比: {:.2}x",
+        traditional_time.as_secs_f64() / snapshot_time.as_secs_f64()
+    );
+    println!("   文档节点数: {}\n", restored.doc().size());
+
+    // 4. 后台定时快照：每个事务后、空闲 200ms 时写入
+    println!("⏱️  测试后台定时快照...");
+    let config = ForgeConfig {
+        snapshot: SnapshotConfig {
+            enabled: true,
+            directory: SNAPSHOT_DIR.into(),
+            interval: None,
+            every_transactions: Some(1),
+            idle_after: Duration::from_millis(200),
+            include_plugin_states: true,
+            keep: 3,
         },
-        Err(e) => {
-            println!("⚠️  快照加载失败: {}", e);
-            println!("🔄 回退到传统启动方式...");
-            let fallback_runtime = ForgeRuntime::create(RuntimeOptions::default()).await?;
-            let fallback_time = snapshot_start.elapsed();
-            println!("✅ 回退启动完成，耗时: {:?}", fallback_time);
-            fallback_runtime
-        }
+        ..Default::default()
     };
+    let mut scheduled =
+        ForgeRuntime::create_with_config(RuntimeOptions::default(), config)
+            .await?;
+    scheduled.get_event_bus().add_event_handler(Arc::new(SnapshotLogger))?;
+    let tr = scheduled.get_tr();
+    scheduled.dispatch(tr).await?;
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    for (path, header) in list_snapshots(SNAPSHOT_DIR.as_ref()) {
+        println!(
+            "   {} (状态版本 {}，创建于 {})",
+            path.display(),
+            header.state_version,
+            header.created_at
+        );
+    }
 
-    // 3. 快照 + 回退方式测试
+    // 5. 按目录启动：选择最新的有效快照，没有时回退到传统方式
     println!("\n🛡️  测试智能回退启动...");
     let smart_start = Instant::now();
-    
-    match ForgeRuntime::from_snapshot_or_fallback(
-        snapshot_path, 
-        RuntimeOptions::default()
-    ).await {
-        Ok(_smart_runtime) => {
-            let smart_time = smart_start.elapsed();
-            println!("✅ 智能启动完成，耗时: {:?}", smart_time);
-        },
-        Err(e) => {
-            println!("⚠️ 智能启动失败: {}", e);
-        }
-    }
+    let _smart_runtime =
+        ForgeRuntime::from_snapshot_or_fallback(SNAPSHOT_DIR, None, None)
+            .await?;
+    println!("✅ 智能启动完成，耗时: {:?}", smart_start.elapsed());
 
-    // 4. 总结
-    println!("\n📈 快照机制演示完成!");
-    
     println!("\n🎉 演示完成！");
     Ok(())
-}
\ No newline at end of file
+}
@@ -6,6 +6,7 @@ use std::{
     sync::Arc,
 };
 
+use mf_error_codes::{ErrorCode, ErrorWire, ToWire};
 use mf_file::{document::DocumentReader, error::FileError as MffError, REC_HDR};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
@@ -57,6 +58,37 @@ impl std::fmt::Display for InspectError {
     }
 }
 
+/// 前端收到的错误为 [`ErrorWire`]，可按 `code` 区分处理
+impl ToWire for InspectError {
+    fn to_wire(&self) -> ErrorWire {
+        let mut wire = match self {
+            InspectError::Io(e) if e.kind() == io::ErrorKind::NotFound => {
+                ErrorWire::new(ErrorCode::FileNotFound, self.to_string())
+            },
+            InspectError::Io(_) => {
+                ErrorWire::new(ErrorCode::FileIo, self.to_string())
+            },
+            InspectError::File(e) => e.to_wire(),
+            InspectError::Zip(_) | InspectError::Unsupported(_) => {
+                ErrorWire::new(
+                    ErrorCode::FileUnsupportedFormat,
+                    self.to_string(),
+                )
+            },
+        };
+        wire.message = self.to_string();
+        wire
+    }
+}
+
+fn not_found() -> ErrorWire {
+    ErrorWire::new(ErrorCode::FileNotFound, "文件不存在")
+}
+
+fn to_wire(err: impl Into<InspectError>) -> ErrorWire {
+    err.into().to_wire()
+}
+
 #[derive(Debug, Clone, Copy)]
 enum FileKind {
     Mff,
@@ -123,28 +155,28 @@ struct ZipSummary {
 }
 
 #[tauri::command]
-fn inspect_file(path: &str) -> Result<FileDescriptor, String> {
+fn inspect_file(path: &str) -> Result<FileDescriptor, ErrorWire> {
     let path = PathBuf::from(path);
     if !path.exists() {
-        return Err("文件不存在".to_string());
+        return Err(not_found());
     }
 
-    let kind = detect_kind(&path).map_err(|e| e.to_string())?;
+    let kind = detect_kind(&path).map_err(to_wire)?;
 
     let descriptor = match kind {
-        FileKind::Mff => inspect_mff(&path)
-            .map(FileDescriptor::Mff)
-            .map_err(|e| e.to_string()),
-        FileKind::Zip => inspect_zip(&path)
-            .map(FileDescriptor::Zip)
-            .map_err(|e| e.to_string()),
+        FileKind::Mff => {
+            inspect_mff(&path).map(FileDescriptor::Mff).map_err(to_wire)
+        },
+        FileKind::Zip => {
+            inspect_zip(&path).map(FileDescriptor::Zip).map_err(to_wire)
+        },
     };
 
     if descriptor.is_ok() && matches!(kind, FileKind::Mff) {
         let mut cache = DOCUMENT_CACHE.lock();
         cache.insert(
             path_to_string(&path),
-            Arc::new(DocumentReader::open(&path).map_err(|e| e.to_string())?),
+            Arc::new(DocumentReader::open(&path).map_err(to_wire)?),
         );
     }
 
@@ -155,14 +187,14 @@ fn inspect_file(path: &str) -> Result<FileDescriptor, String> {
 fn load_mff_segment(
     path: &str,
     index: usize,
-) -> Result<MffSegment, String> {
+) -> Result<MffSegment, ErrorWire> {
     let path = PathBuf::from(path);
     if !path.exists() {
-        return Err("文件不存在".to_string());
+        return Err(not_found());
     }
     let key = path_to_string(&path);
-    let reader = get_or_open_reader(&path, &key).map_err(|e| e.to_string())?;
-    read_mff_segment_from_reader(&reader, index).map_err(|e| e.to_string())
+    let reader = get_or_open_reader(&path, &key).map_err(to_wire)?;
+    read_mff_segment_from_reader(&reader, index).map_err(to_wire)
 }
 
 fn get_or_open_reader(